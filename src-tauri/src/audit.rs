@@ -0,0 +1,394 @@
+//! Append-only audit log of destructive operations
+//!
+//! Records worktree deletions, local/remote branch deletions, discarded
+//! working-tree changes and files restored over local edits, so it's possible
+//! to answer "what deleted branch X". The backend never force-pushes and has
+//! no trash to purge; those need an operation here if they're ever added.
+//! Entries are JSON lines in `~/.shellflow/audit.log`, rotated to
+//! `audit.log.1`, `audit.log.2`, ... once the file exceeds `MAX_LOG_BYTES`.
+//!
+//! `record` never blocks the caller: events go through a bounded channel to a
+//! writer thread. When the channel is full the event is dropped and counted.
+
+use crate::path_utils;
+use crate::worktree::chrono_lite_now;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+/// Rotate the log once it grows past this size
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Number of rotated files kept besides the active one
+const MAX_ROTATED_FILES: usize = 3;
+/// Events buffered for the writer thread before new ones are dropped
+const CHANNEL_CAPACITY: usize = 256;
+
+lazy_static::lazy_static! {
+    static ref RECORDER: AuditRecorder = AuditRecorder::start(AuditLog::new(get_audit_log_path()));
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditOperation {
+    DeleteWorktree,
+    DeleteLocalBranch,
+    DeleteRemoteBranch,
+    DiscardChanges,
+    RestoreFile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+}
+
+/// A single audit log entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub operation: AuditOperation,
+    /// Project (repository) path
+    pub project: String,
+    pub worktree: Option<String>,
+    pub branch: Option<String>,
+    /// Options the operation ran with (e.g. merge workflow flags)
+    #[serde(default)]
+    pub options: serde_json::Value,
+    pub outcome: AuditOutcome,
+    pub error: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(operation: AuditOperation, project: impl AsRef<Path>) -> Self {
+        Self {
+            timestamp: chrono_lite_now(),
+            operation,
+            project: path_utils::normalize_path_string(project.as_ref()),
+            worktree: None,
+            branch: None,
+            options: serde_json::Value::Null,
+            outcome: AuditOutcome::Success,
+            error: None,
+        }
+    }
+
+    pub fn with_worktree(mut self, worktree: impl Into<String>) -> Self {
+        self.worktree = Some(worktree.into());
+        self
+    }
+
+    pub fn with_branch(mut self, branch: impl Into<String>) -> Self {
+        self.branch = Some(branch.into());
+        self
+    }
+
+    pub fn with_options(mut self, options: serde_json::Value) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the outcome from the operation's result
+    pub fn with_result<T, E: std::fmt::Display>(mut self, result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => {
+                self.outcome = AuditOutcome::Success;
+                self.error = None;
+            }
+            Err(e) => {
+                self.outcome = AuditOutcome::Error;
+                self.error = Some(e.to_string());
+            }
+        }
+        self
+    }
+}
+
+/// Filter for querying the audit log. Unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub project: Option<String>,
+    pub operation: Option<AuditOperation>,
+    pub branch: Option<String>,
+}
+
+impl AuditFilter {
+    fn matches(&self, event: &AuditEvent) -> bool {
+        if let Some(project) = &self.project {
+            if path_utils::normalize_path_string(Path::new(project)) != event.project {
+                return false;
+            }
+        }
+        if let Some(operation) = self.operation {
+            if operation != event.operation {
+                return false;
+            }
+        }
+        if let Some(branch) = &self.branch {
+            if event.branch.as_deref() != Some(branch.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Log query result
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogResult {
+    /// Matching entries, newest first
+    pub entries: Vec<AuditEvent>,
+    /// Events dropped since startup because the writer fell behind
+    pub dropped: u64,
+}
+
+pub fn get_audit_log_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".shellflow")
+        .join("audit.log")
+}
+
+/// Size-rotated JSON lines file
+struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_rotated: usize,
+}
+
+impl AuditLog {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_bytes: MAX_LOG_BYTES,
+            max_rotated: MAX_ROTATED_FILES,
+        }
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn append(&self, event: &AuditEvent) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            self.rotate()?;
+        }
+
+        let line = serde_json::to_string(event)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)
+    }
+
+    /// Shift audit.log -> audit.log.1 -> audit.log.2 ..., dropping the oldest
+    fn rotate(&self) -> std::io::Result<()> {
+        let oldest = self.rotated_path(self.max_rotated);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.max_rotated).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.max_rotated > 0 {
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// Read matching entries across all files, newest first
+    fn read(&self, filter: &AuditFilter, limit: usize) -> Vec<AuditEvent> {
+        let mut files: Vec<PathBuf> = (1..=self.max_rotated).rev().map(|i| self.rotated_path(i)).collect();
+        files.push(self.path.clone());
+
+        let mut entries = Vec::new();
+        for path in files {
+            let Ok(file) = std::fs::File::open(&path) else {
+                continue;
+            };
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                if let Ok(event) = serde_json::from_str::<AuditEvent>(&line) {
+                    if filter.matches(&event) {
+                        entries.push(event);
+                    }
+                }
+            }
+        }
+
+        entries.reverse();
+        entries.truncate(limit);
+        entries
+    }
+}
+
+/// Non-blocking front end to the writer thread
+struct AuditRecorder {
+    sender: SyncSender<AuditEvent>,
+    dropped: AtomicU64,
+}
+
+impl AuditRecorder {
+    fn with_capacity(capacity: usize) -> (Self, Receiver<AuditEvent>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let recorder = Self {
+            sender,
+            dropped: AtomicU64::new(0),
+        };
+        (recorder, receiver)
+    }
+
+    fn start(log: AuditLog) -> Self {
+        let (recorder, receiver) = Self::with_capacity(CHANNEL_CAPACITY);
+        std::thread::spawn(move || {
+            for event in receiver {
+                if let Err(e) = log.append(&event) {
+                    warn!("[Audit] Failed to write audit entry: {}", e);
+                }
+            }
+        });
+        recorder
+    }
+
+    fn record(&self, event: AuditEvent) {
+        match self.sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Record a destructive operation. Never blocks; drops the event if the writer is behind.
+pub fn record(event: AuditEvent) {
+    RECORDER.record(event);
+}
+
+/// Record the outcome of an operation and pass its result through
+pub fn record_result<T, E: std::fmt::Display>(event: AuditEvent, result: Result<T, E>) -> Result<T, E> {
+    record(event.with_result(&result));
+    result
+}
+
+/// Query the audit log
+pub fn get_audit_log(filter: &AuditFilter, limit: usize) -> AuditLogResult {
+    AuditLogResult {
+        entries: AuditLog::new(get_audit_log_path()).read(filter, limit),
+        dropped: RECORDER.dropped(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_log(max_bytes: u64, max_rotated: usize) -> (AuditLog, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("shellflow-audit-{}", uuid::Uuid::new_v4()));
+        let log = AuditLog {
+            path: dir.join("audit.log"),
+            max_bytes,
+            max_rotated,
+        };
+        (log, dir)
+    }
+
+    fn make_event(project: &str, branch: &str) -> AuditEvent {
+        AuditEvent::new(AuditOperation::DeleteLocalBranch, project).with_branch(branch)
+    }
+
+    #[test]
+    fn rotates_when_file_exceeds_limit() {
+        let (log, dir) = make_log(1, 2);
+        for i in 0..4 {
+            log.append(&make_event("/repo", &format!("branch-{}", i))).unwrap();
+        }
+
+        // Every append after the first rotates; only the newest three files survive
+        assert!(log.path.exists());
+        assert!(log.rotated_path(1).exists());
+        assert!(log.rotated_path(2).exists());
+        assert!(!log.rotated_path(3).exists());
+
+        let entries = log.read(&AuditFilter::default(), 10);
+        let branches: Vec<_> = entries.iter().map(|e| e.branch.clone().unwrap()).collect();
+        assert_eq!(branches, vec!["branch-3", "branch-2", "branch-1"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filters_by_project_and_limits() {
+        let (log, dir) = make_log(MAX_LOG_BYTES, MAX_ROTATED_FILES);
+        log.append(&make_event("/repo-a", "one")).unwrap();
+        log.append(&make_event("/repo-b", "two")).unwrap();
+        log.append(&make_event("/repo-a", "three")).unwrap();
+
+        let filter = AuditFilter {
+            project: Some("/repo-a".to_string()),
+            ..Default::default()
+        };
+        let entries = log.read(&filter, 10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].branch.as_deref(), Some("three"));
+        assert_eq!(entries[1].branch.as_deref(), Some("one"));
+
+        assert_eq!(log.read(&filter, 1).len(), 1);
+
+        let filter = AuditFilter {
+            operation: Some(AuditOperation::DeleteWorktree),
+            ..Default::default()
+        };
+        assert!(log.read(&filter, 10).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn drops_events_when_channel_is_full() {
+        let (recorder, receiver) = AuditRecorder::with_capacity(1);
+        recorder.record(make_event("/repo", "kept"));
+        recorder.record(make_event("/repo", "dropped-1"));
+        recorder.record(make_event("/repo", "dropped-2"));
+
+        assert_eq!(recorder.dropped(), 2);
+        assert_eq!(receiver.try_recv().unwrap().branch.as_deref(), Some("kept"));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn records_error_outcome() {
+        let result: Result<(), String> = Err("branch is checked out".to_string());
+        let event = make_event("/repo", "feature").with_result(&result);
+        assert_eq!(event.outcome, AuditOutcome::Error);
+        assert_eq!(event.error.as_deref(), Some("branch is checked out"));
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["operation"], "delete-local-branch");
+        assert_eq!(json["outcome"], "error");
+        assert_eq!(serde_json::to_value(AuditOperation::DiscardChanges).unwrap(), "discard-changes");
+        assert_eq!(serde_json::to_value(AuditOperation::RestoreFile).unwrap(), "restore-file");
+    }
+}
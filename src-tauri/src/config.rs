@@ -1,3 +1,4 @@
+use crate::template::{TemplateProblem, TemplateScope, TemplateValidator};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -9,6 +10,66 @@ pub const DEFAULT_CONFIG: &str = include_str!("default_config.jsonc");
 pub struct ConfigError {
     pub file: String,
     pub message: String,
+    pub severity: ConfigErrorSeverity,
+    /// Where a parse error occurred in the original file, if known
+    pub location: Option<ConfigErrorLocation>,
+}
+
+/// Position of a parse error in the original (comment-preserving) file text
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigErrorLocation {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// The error line with one line of context on each side and a caret under the column
+    pub excerpt: String,
+}
+
+/// A JSONC parse failure
+#[derive(Debug, Clone)]
+pub struct JsoncError {
+    pub message: String,
+    pub location: Option<ConfigErrorLocation>,
+}
+
+impl std::fmt::Display for JsoncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Whether a ConfigError is a real error or an informational note (e.g. a disabled layer)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigErrorSeverity {
+    Error,
+    /// Loaded fine but likely wrong, e.g. a template that doesn't render
+    Warning,
+    Info,
+}
+
+/// Project config layers that can be disabled per project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    /// {project}/.shellflow/config.jsonc
+    Repo,
+    /// {project}/.shellflow/config.local.jsonc
+    Local,
+}
+
+/// Options for loading config
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoadOptions {
+    /// Layers to skip while merging (the files are still watched)
+    pub disabled_layers: Vec<ConfigLayer>,
+}
+
+impl ConfigLoadOptions {
+    pub fn is_disabled(&self, layer: ConfigLayer) -> bool {
+        self.disabled_layers.contains(&layer)
+    }
 }
 
 /// Result of loading configuration, includes both config and any errors
@@ -22,6 +83,9 @@ pub struct ConfigResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct RawConfig {
+    /// Config format version; files without one predate versioning
+    #[serde(rename = "schemaVersion", skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
     pub main: MainConfig,
     pub drawer: RawDrawerConfig,
     pub apps: AppsConfig,
@@ -29,13 +93,24 @@ pub struct RawConfig {
     /// Override the default shell for all terminals (e.g., "C:\\Program Files\\PowerShell\\7\\pwsh.exe")
     pub shell: Option<String>,
     pub worktree: WorktreeConfig,
+    pub changes: ChangesConfig,
     pub navigation: NavigationConfig,
     pub indicators: IndicatorsConfig,
     pub tasks: Vec<TaskConfig>,
+    /// Explicit order of task groups in the switcher
+    #[serde(rename = "tasksGroups")]
+    pub tasks_groups: Vec<String>,
+    #[serde(rename = "taskDefaults")]
+    pub task_defaults: TaskDefaultsConfig,
     pub actions: ActionsConfig,
     pub scratch: ScratchConfig,
     pub mappings: MappingsConfig,
     pub panes: PanesConfig,
+    pub terminal: TerminalConfig,
+    pub confirmations: ConfirmationsConfig,
+    pub network: NetworkConfig,
+    pub wake: WakeConfig,
+    pub perf: PerfConfig,
     /// Theme configuration. Can be a single theme name (string) or an object with light/dark themes.
     pub theme: Option<ThemeConfig>,
     /// How to handle borders when adapting themes.
@@ -46,19 +121,28 @@ pub struct RawConfig {
 impl Default for RawConfig {
     fn default() -> Self {
         Self {
+            schema_version: None,
             main: MainConfig::default(),
             drawer: RawDrawerConfig::default(),
             apps: AppsConfig::default(),
             commit: CommitConfig::default(),
             shell: None,
             worktree: WorktreeConfig::default(),
+            changes: ChangesConfig::default(),
             navigation: NavigationConfig::default(),
             indicators: IndicatorsConfig::default(),
             tasks: Vec::new(),
+            tasks_groups: Vec::new(),
+            task_defaults: TaskDefaultsConfig::default(),
             actions: ActionsConfig::default(),
             scratch: ScratchConfig::default(),
             mappings: MappingsConfig::default(),
             panes: PanesConfig::default(),
+            terminal: TerminalConfig::default(),
+            confirmations: ConfirmationsConfig::default(),
+            network: NetworkConfig::default(),
+            wake: WakeConfig::default(),
+            perf: PerfConfig::default(),
             theme: None, // Uses default Catppuccin themes when None
             theme_border_style: ThemeBorderStyle::default(),
         }
@@ -68,6 +152,8 @@ impl Default for RawConfig {
 /// Resolved config with all values populated
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(rename = "schemaVersion", skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
     pub main: MainConfig,
     pub drawer: DrawerConfig,
     pub apps: AppsConfig,
@@ -75,13 +161,24 @@ pub struct Config {
     /// Override the default shell for all terminals (e.g., "C:\\Program Files\\PowerShell\\7\\pwsh.exe")
     pub shell: Option<String>,
     pub worktree: WorktreeConfig,
+    pub changes: ChangesConfig,
     pub navigation: NavigationConfig,
     pub indicators: IndicatorsConfig,
     pub tasks: Vec<TaskConfig>,
+    /// Explicit order of task groups in the switcher
+    #[serde(rename = "tasksGroups")]
+    pub tasks_groups: Vec<String>,
+    #[serde(rename = "taskDefaults")]
+    pub task_defaults: TaskDefaultsConfig,
     pub actions: ActionsConfig,
     pub scratch: ScratchConfig,
     pub mappings: MappingsConfig,
     pub panes: PanesConfig,
+    pub terminal: TerminalConfig,
+    pub confirmations: ConfirmationsConfig,
+    pub network: NetworkConfig,
+    pub wake: WakeConfig,
+    pub perf: PerfConfig,
     /// Theme configuration. Can be a single theme name or an object with light/dark themes.
     pub theme: Option<ThemeConfig>,
     /// How to handle borders when adapting themes.
@@ -90,22 +187,70 @@ pub struct Config {
 }
 
 impl Config {
+    /// Every minijinja-templated setting as (config path, template, scope),
+    /// in a stable order
+    pub fn templated_fields(&self) -> Vec<(String, &str, TemplateScope)> {
+        let mut fields = Vec::new();
+        if let Some(directory) = &self.worktree.directory {
+            fields.push(("worktree.directory".to_string(), directory.as_str(), TemplateScope::WorktreeDirectory));
+        }
+        if let Some(prefix) = &self.worktree.name_generator.prefix {
+            fields.push(("worktree.nameGenerator.prefix".to_string(), prefix.as_str(), TemplateScope::NamePrefix));
+        }
+        if let Some(command) = &self.main.command {
+            fields.push(("main.command".to_string(), command.as_str(), TemplateScope::Task));
+        }
+        for task in &self.tasks {
+            fields.push((format!("tasks[{}].command", task.name), task.command.as_str(), TemplateScope::Task));
+            let mut env: Vec<_> = task.env.iter().collect();
+            env.sort();
+            for (key, value) in env {
+                fields.push((format!("tasks[{}].env.{}", task.name, key), value.as_str(), TemplateScope::Task));
+            }
+            let mut urls: Vec<_> = task.urls.iter().collect();
+            urls.sort();
+            for (label, url) in urls {
+                fields.push((format!("tasks[{}].urls.{}", task.name, label), url.as_str(), TemplateScope::TaskUrl));
+            }
+        }
+        fields.push((
+            "actions.mergeWorktreeWithConflicts".to_string(),
+            self.actions.merge_worktree_with_conflicts.as_str(),
+            TemplateScope::Action,
+        ));
+        fields.push((
+            "actions.rebaseWorktreeWithConflicts".to_string(),
+            self.actions.rebase_worktree_with_conflicts.as_str(),
+            TemplateScope::Action,
+        ));
+        fields
+    }
+
     /// Resolve a RawConfig into a Config by inheriting drawer values from main
     pub fn from_raw(raw: RawConfig) -> Self {
         Self {
+            schema_version: raw.schema_version,
             drawer: DrawerConfig::from_raw(&raw.drawer, &raw.main, raw.panes.unfocused_opacity),
             main: raw.main,
             apps: raw.apps,
             commit: raw.commit,
             shell: raw.shell,
             worktree: raw.worktree,
+            changes: raw.changes,
             navigation: raw.navigation,
             indicators: raw.indicators,
             tasks: raw.tasks,
+            tasks_groups: raw.tasks_groups,
+            task_defaults: raw.task_defaults,
             actions: raw.actions,
             scratch: raw.scratch,
             mappings: raw.mappings,
             panes: raw.panes,
+            terminal: raw.terminal,
+            confirmations: raw.confirmations,
+            network: raw.network,
+            wake: raw.wake,
+            perf: raw.perf,
             theme: raw.theme,
             theme_border_style: raw.theme_border_style,
         }
@@ -124,6 +269,8 @@ pub enum MergeStrategy {
     #[default]
     Merge,
     Rebase,
+    /// All of the branch's commits as one commit on the target
+    Squash,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
@@ -213,6 +360,60 @@ pub struct TaskConfig {
     /// Example: { "Dev": "http://localhost:{{ branch | hash_port }}" }
     #[serde(default)]
     pub urls: UrlMap,
+    /// Stop a daemon task after this many idle minutes; overrides
+    /// `taskDefaults.idleShutdownMinutes` (0 = never)
+    #[serde(default, rename = "idleShutdownMinutes", skip_serializing_if = "Option::is_none")]
+    pub idle_shutdown_minutes: Option<u32>,
+    /// Machine-readable output protocol. With "jsonlines", output lines that are
+    /// JSON objects with a `shellflow` key are also emitted as structured events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    /// Task switcher group; groups are ordered by `tasksGroups`, then first appearance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Position within the group; tasks without one follow in config order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+    /// Left out of the task switcher but still runnable by name
+    #[serde(default)]
+    pub hidden: bool,
+    /// Daemon tasks only: start when the worktree (or project) is opened
+    #[serde(default, rename = "autoStart")]
+    pub auto_start: bool,
+}
+
+/// `TaskConfig::protocol` value for JSON-lines progress events
+pub const JSON_LINES_PROTOCOL: &str = "jsonlines";
+
+impl TaskConfig {
+    /// Whether this task's output should be scanned for structured events
+    pub fn uses_json_lines(&self) -> bool {
+        self.protocol.as_deref() == Some(JSON_LINES_PROTOCOL)
+    }
+
+    /// Idle threshold for this task, if it is a daemon with idle shutdown enabled
+    pub fn effective_idle_shutdown_minutes(&self, defaults: &TaskDefaultsConfig) -> Option<u32> {
+        if self.kind != TaskKind::Daemon {
+            return None;
+        }
+        self.idle_shutdown_minutes
+            .or(defaults.idle_shutdown_minutes)
+            .filter(|minutes| *minutes > 0)
+    }
+}
+
+/// Settings applied to every task unless the task overrides them
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TaskDefaultsConfig {
+    /// Stop daemon tasks after this many minutes without terminal activity in
+    /// the task or its worktree (None or 0 = never)
+    #[serde(rename = "idleShutdownMinutes")]
+    pub idle_shutdown_minutes: Option<u32>,
+    /// Auto-start stops starting tasks once this many task sessions are
+    /// running across all worktrees (None or 0 = no limit)
+    #[serde(rename = "autoStartLimit")]
+    pub auto_start_limit: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -229,6 +430,13 @@ pub struct MergeConfig {
     /// Delete the remote branch after successful merge (default: false)
     #[serde(rename = "deleteRemoteBranch")]
     pub delete_remote_branch: bool,
+    /// Push the target branch after successful merge (default: false)
+    #[serde(rename = "pushAfterMerge")]
+    pub push_after_merge: bool,
+    /// Fetch the target from origin and rebase onto `origin/<target>` instead
+    /// of the local branch (default: false)
+    #[serde(rename = "rebaseOntoRemote")]
+    pub rebase_onto_remote: bool,
 }
 
 impl Default for MergeConfig {
@@ -238,6 +446,8 @@ impl Default for MergeConfig {
             delete_worktree: true,
             delete_local_branch: false,
             delete_remote_branch: false,
+            push_after_merge: false,
+            rebase_onto_remote: false,
         }
     }
 }
@@ -375,6 +585,154 @@ impl Default for PanesConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Maximum number of live PTY sessions across the app (0 = unlimited)
+    #[serde(rename = "maxSessions")]
+    pub max_sessions: usize,
+    /// Maximum number of live PTY sessions for a single worktree/project (0 = unlimited)
+    #[serde(rename = "maxSessionsPerWorktree")]
+    pub max_sessions_per_worktree: usize,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 64,
+            max_sessions_per_worktree: 16,
+        }
+    }
+}
+
+/// Destructive operations the backend refuses unless the caller passes `confirmed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmationsConfig {
+    /// Require confirmation to delete a worktree, including after a merge (default: true)
+    #[serde(rename = "deleteWorktree")]
+    pub delete_worktree: bool,
+    /// Require confirmation to delete a remote branch (default: true)
+    #[serde(rename = "deleteRemoteBranch")]
+    pub delete_remote_branch: bool,
+    /// Require confirmation to throw away changes to tracked files (default: true)
+    #[serde(rename = "forceReset")]
+    pub force_reset: bool,
+    /// Require confirmation to delete untracked files (default: true)
+    #[serde(rename = "cleanUntracked")]
+    pub clean_untracked: bool,
+}
+
+impl Default for ConfirmationsConfig {
+    fn default() -> Self {
+        Self {
+            delete_worktree: true,
+            delete_remote_branch: true,
+            force_reset: true,
+            clean_untracked: true,
+        }
+    }
+}
+
+/// Retries for git commands that talk to a remote (push, remote branch deletion)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Retries after a transient failure such as a dropped connection (default: 2, at most 10)
+    pub retries: u32,
+    /// Wait before the first retry in milliseconds; doubles for each one after (default: 1000)
+    #[serde(rename = "retryDelayMs")]
+    pub retry_delay_ms: u64,
+    /// Kill a fetch or remote prune still running after this many seconds (default: 120)
+    #[serde(rename = "fetchTimeoutSeconds")]
+    pub fetch_timeout_seconds: u64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            retry_delay_ms: 1000,
+            fetch_timeout_seconds: 120,
+        }
+    }
+}
+
+/// Recovery of watchers and sessions after the system sleeps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WakeConfig {
+    /// Clock drift in seconds that counts as having slept; 0 disables detection (default: 60)
+    #[serde(rename = "sleepThresholdSeconds")]
+    pub sleep_threshold_seconds: u64,
+    /// Delay between restarting one worktree watcher and the next, in milliseconds (default: 200)
+    #[serde(rename = "watcherStaggerMs")]
+    pub watcher_stagger_ms: u64,
+}
+
+impl Default for WakeConfig {
+    fn default() -> Self {
+        Self {
+            sleep_threshold_seconds: 60,
+            watcher_stagger_ms: 200,
+        }
+    }
+}
+
+/// Timing of backend commands for performance debugging
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerfConfig {
+    /// Record how long each command takes, for `get_perf_stats` (default: false)
+    pub enabled: bool,
+}
+
+/// An operation gated by `ConfirmationsConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmableOperation {
+    DeleteWorktree,
+    DeleteRemoteBranch,
+    ForceReset,
+    CleanUntracked,
+}
+
+impl ConfirmableOperation {
+    /// Key of the operation in the `confirmations` config section
+    pub fn config_key(self) -> &'static str {
+        match self {
+            Self::DeleteWorktree => "deleteWorktree",
+            Self::DeleteRemoteBranch => "deleteRemoteBranch",
+            Self::ForceReset => "forceReset",
+            Self::CleanUntracked => "cleanUntracked",
+        }
+    }
+}
+
+impl ConfirmationsConfig {
+    pub fn requires(&self, operation: ConfirmableOperation) -> bool {
+        match operation {
+            ConfirmableOperation::DeleteWorktree => self.delete_worktree,
+            ConfirmableOperation::DeleteRemoteBranch => self.delete_remote_branch,
+            ConfirmableOperation::ForceReset => self.force_reset,
+            ConfirmableOperation::CleanUntracked => self.clean_untracked,
+        }
+    }
+
+    /// Refuse an operation that needs confirmation when the caller didn't confirm it
+    pub fn ensure_confirmed(&self, operation: ConfirmableOperation, confirmed: bool) -> Result<(), ConfirmationRequired> {
+        if confirmed || !self.requires(operation) {
+            return Ok(());
+        }
+        Err(ConfirmationRequired(operation.config_key()))
+    }
+}
+
+/// An operation refused by `ConfirmationsConfig::ensure_confirmed`, with the
+/// config key that requires confirming it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Confirmation required (confirmations.{0} is enabled)")]
+pub struct ConfirmationRequired(pub &'static str);
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum WebglMode {
@@ -486,7 +844,8 @@ impl Default for MainConfig {
 pub struct WorktreeConfig {
     /// Directory where worktrees are created.
     /// Supports placeholder: {{ repo_directory }} (the repository directory)
-    /// Final path: {directory}/{workspace_name}
+    /// Final path: {directory}/{workspace_name}, or {directory} itself when the
+    /// template already ends with the worktree name
     /// Default: {{ repo_directory }}/.worktrees
     pub directory: Option<String>,
 
@@ -500,6 +859,15 @@ pub struct WorktreeConfig {
     /// Configuration for copying files to new worktrees
     pub copy: CopyConfig,
 
+    /// Maximum number of background setup jobs (gitignored file copies) that run at once.
+    /// Additional jobs are queued until a slot frees up.
+    #[serde(rename = "maxConcurrentSetups")]
+    pub max_concurrent_setups: usize,
+
+    /// Configuration for generated worktree names
+    #[serde(rename = "nameGenerator")]
+    pub name_generator: NameGeneratorConfig,
+
     /// Focus the branch name input when creating a new worktree
     #[serde(rename = "focusNewBranchNames")]
     pub focus_new_branch_names: bool,
@@ -509,6 +877,10 @@ pub struct WorktreeConfig {
 
     /// Configuration for delete operations
     pub delete: DeleteConfig,
+
+    /// When to nudge about worktrees falling behind their base branch
+    #[serde(rename = "driftWarning")]
+    pub drift_warning: DriftWarningConfig,
 }
 
 impl Default for WorktreeConfig {
@@ -517,13 +889,73 @@ impl Default for WorktreeConfig {
             directory: None,
             base_branch: BaseBranch::default(),
             copy: CopyConfig::default(),
+            max_concurrent_setups: 2,
+            name_generator: NameGeneratorConfig::default(),
             focus_new_branch_names: false,
             merge: MergeConfig::default(),
             delete: DeleteConfig::default(),
+            drift_warning: DriftWarningConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DriftWarningConfig {
+    /// Warn once a worktree is this many commits behind its base branch (0 = never)
+    #[serde(rename = "warnBehind")]
+    pub warn_behind: u32,
+    /// Also warn about a worktree that is behind at all and has seen no
+    /// commits or focus for this many days (0 = never)
+    #[serde(rename = "staleDays")]
+    pub stale_days: u32,
+}
+
+impl Default for DriftWarningConfig {
+    fn default() -> Self {
+        Self {
+            warn_behind: 20,
+            stale_days: 7,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NameGeneratorConfig {
+    /// Glob patterns generated names must not match (e.g. "release-*")
+    pub blocklist: Vec<String>,
+
+    /// Put in front of every generated name, e.g. "wt/{{ user }}/". Supports
+    /// {{ user }} (the OS user name) and {{ repo_directory }}. A prefix not
+    /// ending in `/`, `-` or `_` is joined with `-`. Slashes stay in the
+    /// branch name; the worktree name and directory use `-` instead.
+    pub prefix: Option<String>,
+
+    /// How the rest of the name is made
+    pub strategy: NameStrategy,
+
+    /// Word-list file replacing the built-in adjectives (one word per line).
+    /// Relative paths are resolved from the project directory; `~/` expands to home.
+    pub adjectives: Option<String>,
+
+    /// Word-list file replacing the built-in nouns (one word per line)
+    pub nouns: Option<String>,
+}
+
+/// How `nameGenerator` makes names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameStrategy {
+    /// adjective-noun, e.g. "quick-fox"
+    #[default]
+    Petname,
+    /// UTC creation time, e.g. "20240131-154502"
+    Timestamp,
+    /// The next free number after the prefix, e.g. "fix-3"
+    Incremental,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct CopyConfig {
@@ -533,6 +965,11 @@ pub struct CopyConfig {
 
     /// Glob patterns to exclude from copying
     pub except: Vec<String>,
+
+    /// Store each copied file once and hardlink it into every worktree, so
+    /// identical files across worktrees share disk space. A linked file edited
+    /// in place changes in every worktree linking it.
+    pub dedupe: bool,
 }
 
 impl Default for CopyConfig {
@@ -540,10 +977,20 @@ impl Default for CopyConfig {
         Self {
             gitignored: false,
             except: vec![".claude".to_string(), ".worktrees".to_string()],
+            dedupe: false,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangesConfig {
+    /// Glob patterns for changed files to collapse in the changed-files panel
+    /// (e.g. lockfiles). Matching files are flagged, not dropped.
+    #[serde(rename = "hidePatterns")]
+    pub hide_patterns: Vec<String>,
+}
+
 /// Raw drawer config as stored in JSON (fields optional, inherit from main)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RawDrawerConfig {
@@ -628,7 +1075,7 @@ impl Default for DrawerConfig {
 }
 
 /// Target for opening apps - where the app should open.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum AppTarget {
     /// Open in external application (default) - runs the command directly
@@ -661,7 +1108,6 @@ pub enum AppConfig {
     },
 }
 
-#[allow(dead_code)] // Used only in tests
 impl AppConfig {
     /// Get the command, if specified
     pub fn command(&self) -> Option<&str> {
@@ -713,9 +1159,23 @@ impl Default for CommitConfig {
     }
 }
 
+/// API shape of the `commit.ai` endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiProvider {
+    /// `{baseUrl}/chat/completions` with a bearer token; also OpenAI-compatible servers
+    #[default]
+    Openai,
+    /// `{baseUrl}/v1/messages` with an `x-api-key` header
+    Anthropic,
+    /// `{baseUrl}/api/chat`, no key
+    Ollama,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct CommitAiConfig {
+    pub provider: AiProvider,
     #[serde(rename = "baseUrl")]
     pub base_url: String,
     #[serde(rename = "apiKey")]
@@ -732,6 +1192,7 @@ pub struct CommitAiConfig {
 impl Default for CommitAiConfig {
     fn default() -> Self {
         Self {
+            provider: AiProvider::Openai,
             base_url: "https://api.openai.com/v1".to_string(),
             api_key: "".to_string(),
             model: "gpt-4o-mini".to_string(),
@@ -1105,12 +1566,61 @@ pub fn load_config() -> Config {
     load_config_for_project(None)
 }
 
-fn parse_jsonc_value(content: &str) -> Result<serde_json::Value, String> {
+/// `content` with its comments blanked to spaces, so every byte offset (and
+/// line number) still matches the file
+fn blank_jsonc_comments(content: &str) -> Result<String, JsoncError> {
     let mut json = content.to_string();
-    json_strip_comments::strip(&mut json)
-        .map_err(|e| format!("Failed to strip comments: {}", e))?;
-    serde_json::from_str(&json)
-        .map_err(|e| format!("{}", e))
+    json_strip_comments::strip(&mut json).map_err(|e| JsoncError {
+        message: format!("Failed to strip comments: {}", e),
+        location: None,
+    })?;
+    // Comments are blanked with spaces byte-for-byte, but newlines inside block
+    // comments are blanked too; put them back so line numbers match the file.
+    let mut bytes = json.into_bytes();
+    for (stripped, original) in bytes.iter_mut().zip(content.bytes()) {
+        if original == b'\n' || original == b'\r' {
+            *stripped = original;
+        }
+    }
+    Ok(String::from_utf8(bytes).unwrap_or_else(|_| content.to_string()))
+}
+
+fn parse_jsonc_value(content: &str) -> Result<serde_json::Value, JsoncError> {
+    let json = blank_jsonc_comments(content)?;
+    serde_json::from_str(&json).map_err(|e| JsoncError {
+        message: e.to_string(),
+        location: error_location(content, e.line(), e.column()),
+    })
+}
+
+/// Map serde_json's line and byte column onto the original text, with an excerpt
+fn error_location(content: &str, line: usize, byte_column: usize) -> Option<ConfigErrorLocation> {
+    if line == 0 {
+        return None;
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    let text = *lines.get(line - 1).unwrap_or(&"");
+
+    // serde_json counts bytes; report characters so the caret lines up
+    let mut byte_index = byte_column.saturating_sub(1).min(text.len());
+    while !text.is_char_boundary(byte_index) {
+        byte_index -= 1;
+    }
+    let column = text[..byte_index].chars().count() + 1;
+
+    let first = line.saturating_sub(1).max(1);
+    let last = (line + 1).min(lines.len().max(line));
+    let width = last.to_string().len();
+    let mut excerpt = String::new();
+    for n in first..=last {
+        let source = lines.get(n - 1).copied().unwrap_or("");
+        excerpt.push_str(&format!("{:>width$} | {}\n", n, source, width = width));
+        if n == line {
+            excerpt.push_str(&format!("{:>width$} | {}^\n", "", " ".repeat(column - 1), width = width));
+        }
+    }
+
+    Some(ConfigErrorLocation { line, column, excerpt })
 }
 
 /// Get all config file paths that should be watched for a given project
@@ -1126,6 +1636,53 @@ pub fn get_config_paths(project_path: Option<&str>) -> Vec<PathBuf> {
     paths
 }
 
+/// Config file a layer is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigScope {
+    /// ~/.config/shellflow/config.jsonc
+    Global,
+    /// {project}/.shellflow/config.jsonc
+    Repo,
+    /// {project}/.shellflow/config.local.jsonc
+    Local,
+}
+
+/// A config layer's file and whether it exists, for the "edit which config?" chooser
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigLocation {
+    pub scope: ConfigScope,
+    pub path: String,
+    pub exists: bool,
+    /// The file an edit should go to: the nearest existing layer, or the
+    /// global file (created by the edit) when none exist
+    pub edit_target: bool,
+}
+
+/// Every config layer for a project (just the global one without a project),
+/// in load order
+pub fn locate_configs(project_path: Option<&str>) -> Vec<ConfigLocation> {
+    locate_configs_with(project_path, |path| path.exists())
+}
+
+fn locate_configs_with(project_path: Option<&str>, exists: impl Fn(&Path) -> bool) -> Vec<ConfigLocation> {
+    let scopes = [ConfigScope::Global, ConfigScope::Repo, ConfigScope::Local];
+    let mut locations: Vec<ConfigLocation> = get_config_paths(project_path)
+        .iter()
+        .zip(scopes)
+        .map(|(path, scope)| ConfigLocation {
+            scope,
+            path: path.display().to_string(),
+            exists: exists(path),
+            edit_target: false,
+        })
+        .collect();
+    let target = locations.iter().rposition(|l| l.exists).unwrap_or(0);
+    locations[target].edit_target = true;
+    locations
+}
+
 /// Recursively merge overlay into base. Overlay values take precedence.
 /// Arrays are merged by "name" field if items are objects with that field.
 fn deep_merge(base: &mut serde_json::Value, overlay: &serde_json::Value) {
@@ -1147,6 +1704,10 @@ fn deep_merge(base: &mut serde_json::Value, overlay: &serde_json::Value) {
     }
 }
 
+/// Keys a same-named array item keeps from the layer below unless it sets them,
+/// so overriding a task's command doesn't drop its switcher placement
+const INHERITED_ITEM_KEYS: &[&str] = &["group", "order", "hidden", "autoStart"];
+
 /// Merge two arrays. If items are objects with a "name" field, merge by name.
 /// Items with matching names are overridden; unique items accumulate.
 fn merge_arrays(base: &mut serde_json::Value, overlay: &serde_json::Value) {
@@ -1171,7 +1732,15 @@ fn merge_arrays(base: &mut serde_json::Value, overlay: &serde_json::Value) {
                     .iter_mut()
                     .find(|v| v.get("name").and_then(|n| n.as_str()) == Some(overlay_name))
                 {
-                    *base_item = overlay_item.clone();
+                    let mut merged_item = overlay_item.clone();
+                    if let (Some(merged_obj), Some(base_obj)) = (merged_item.as_object_mut(), base_item.as_object()) {
+                        for key in INHERITED_ITEM_KEYS {
+                            if let (false, Some(value)) = (merged_obj.contains_key(*key), base_obj.get(*key)) {
+                                merged_obj.insert(key.to_string(), value.clone());
+                            }
+                        }
+                    }
+                    *base_item = merged_item;
                 } else {
                     base_arr.push(overlay_item.clone());
                 }
@@ -1204,8 +1773,10 @@ pub fn update_config(patch: serde_json::Value) -> Result<(), String> {
     let mut base: Value = if path.exists() {
         let content = std::fs::read_to_string(&path)
             .map_err(|e| format!("Failed to read config file: {}", e))?;
-        parse_jsonc_value(&content)
-            .map_err(|e| format!("Failed to parse config file: {}", e))?
+        parse_jsonc_value(&content).map_err(|e| match &e.location {
+            Some(location) => format!("Failed to parse config file: {}\n{}", e, location.excerpt),
+            None => format!("Failed to parse config file: {}", e),
+        })?
     } else {
         parse_jsonc_value(DEFAULT_CONFIG)
             .unwrap_or_else(|_| Value::Object(serde_json::Map::new()))
@@ -1221,32 +1792,609 @@ pub fn update_config(patch: serde_json::Value) -> Result<(), String> {
     Ok(())
 }
 
+/// Set `autoStart` on the task named `task_name` in the most specific config
+/// file that defines it: the project's local file, its repo file, then the
+/// global config. Returns the file written.
+pub fn set_task_autostart(project_path: &Path, task_name: &str, enabled: bool) -> Result<PathBuf, String> {
+    let shellflow_dir = project_path.join(".shellflow");
+    let candidates = [
+        shellflow_dir.join("config.local.jsonc"),
+        shellflow_dir.join("config.jsonc"),
+        get_config_path(),
+    ];
+    for path in candidates {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let updated = task_autostart_text(&content, task_name, enabled)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let Some(updated) = updated else {
+            continue;
+        };
+        std::fs::write(&path, updated)
+            .map_err(|e| format!("Failed to write config file: {}", e))?;
+        return Ok(path);
+    }
+    Err(format!("Task not found: {}", task_name))
+}
+
+/// Current config format version, written by `migrate_config`
+pub const CONFIG_SCHEMA_VERSION: u32 = 2;
+
+// Deprecated config forms. `migrate_config` rewrites them and
+// `config_warnings` reports them; add new deprecations here.
+
+/// Keys that moved, as dotted (old, new) paths. The old spelling is otherwise ignored.
+/// Mappings were keyed by plain names until actions got namespaced IDs.
+pub const RENAMED_KEYS: &[(&str, &str)] = &[
+    ("mappings.quit", "mappings.app::quit"),
+    ("mappings.addProject", "mappings.app::addProject"),
+    ("mappings.projectSwitcher", "mappings.palette::projectSwitcher"),
+    ("mappings.newWorkspace", "mappings.worktree::new"),
+    ("mappings.newScratchTerminal", "mappings.scratch::new"),
+    ("mappings.newTab", "mappings.session::newTab"),
+    ("mappings.closeTab", "mappings.session::closeTab"),
+    ("mappings.toggleDrawer", "mappings.drawer::toggle"),
+    ("mappings.toggleRightPanel", "mappings.rightPanel::toggle"),
+    ("mappings.expandDrawer", "mappings.drawer::expand"),
+    ("mappings.commandPalette", "mappings.palette::toggle"),
+    ("mappings.zoomIn", "mappings.view::zoomIn"),
+    ("mappings.zoomOut", "mappings.view::zoomOut"),
+    ("mappings.zoomReset", "mappings.view::zoomReset"),
+    ("mappings.navigatePrev", "mappings.navigate::prev"),
+    ("mappings.navigateNext", "mappings.navigate::next"),
+    ("mappings.navigateBack", "mappings.navigate::back"),
+    ("mappings.navigateForward", "mappings.navigate::forward"),
+    ("mappings.switchFocus", "mappings.focus::switch"),
+    ("mappings.nextChangedFile", "mappings.diff::nextFile"),
+    ("mappings.prevChangedFile", "mappings.diff::prevFile"),
+    ("mappings.session1", "mappings.navigate::toEntity1"),
+    ("mappings.session2", "mappings.navigate::toEntity2"),
+    ("mappings.session3", "mappings.navigate::toEntity3"),
+    ("mappings.session4", "mappings.navigate::toEntity4"),
+    ("mappings.session5", "mappings.navigate::toEntity5"),
+    ("mappings.session6", "mappings.navigate::toEntity6"),
+    ("mappings.session7", "mappings.navigate::toEntity7"),
+    ("mappings.session8", "mappings.navigate::toEntity8"),
+    ("mappings.session9", "mappings.navigate::toEntity9"),
+    ("mappings.renameBranch", "mappings.worktree::renameBranch"),
+    ("mappings.runTask", "mappings.task::run"),
+    ("mappings.taskSwitcher", "mappings.task::switcher"),
+];
+
+/// Sections whose `webgl` used to be a boolean (now "off" | "auto" | "on")
+const WEBGL_SECTIONS: &[&str] = &["main", "drawer"];
+
+fn get_dotted<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(value, |v, k| v.get(k))
+}
+
+/// One change made by `migrate_config`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigration {
+    /// Dotted key path the change applies to (the new path for renames)
+    pub key: String,
+    pub description: String,
+}
+
+/// What `migrate_config` did to a config file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigrationReport {
+    pub path: String,
+    /// Where the original was copied before rewriting; None if nothing changed
+    pub backup_path: Option<String>,
+    /// Version before migrating (None for unversioned files)
+    pub from_version: Option<u32>,
+    pub to_version: u32,
+    pub migrations: Vec<ConfigMigration>,
+}
+
+fn take_dotted(value: &mut serde_json::Value, path: &str) -> Option<serde_json::Value> {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => (parent.split('.').try_fold(&mut *value, |v, k| v.get_mut(k))?, key),
+        None => (value, path),
+    };
+    parent.as_object_mut()?.remove(key)
+}
+
+fn insert_dotted(value: &mut serde_json::Value, path: &str, new: serde_json::Value) {
+    let mut current = value;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(object) = current.as_object_mut() else { return };
+        if keys.peek().is_none() {
+            object.insert(key.to_string(), new);
+            return;
+        }
+        current = object
+            .entry(key)
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    }
+}
+
+/// Upgrade deprecated forms in a parsed config file to `CONFIG_SCHEMA_VERSION`,
+/// returning what changed. Renamed keys don't overwrite a new key that is already set.
+fn migrate_value(value: &mut serde_json::Value, renames: &[(&str, &str)]) -> Vec<ConfigMigration> {
+    let mut migrations = Vec::new();
+
+    for section in WEBGL_SECTIONS {
+        let Some(webgl) = value.get_mut(*section).and_then(|s| s.get_mut("webgl")) else { continue };
+        if let Some(enabled) = webgl.as_bool() {
+            let mode = if enabled { "auto" } else { "off" };
+            *webgl = serde_json::Value::String(mode.to_string());
+            migrations.push(ConfigMigration {
+                key: format!("{}.webgl", section),
+                description: format!("Replaced boolean {} with \"{}\"", enabled, mode),
+            });
+        }
+    }
+
+    for (old, new) in renames {
+        let Some(old_value) = take_dotted(value, old) else { continue };
+        let already_set = get_dotted(value, new).is_some();
+        let description = if already_set {
+            format!("Removed {}; {} is already set", old, new)
+        } else {
+            insert_dotted(value, new, old_value);
+            format!("Renamed {} to {}", old, new)
+        };
+        migrations.push(ConfigMigration { key: new.to_string(), description });
+    }
+
+    let version = value.get("schemaVersion").and_then(|v| v.as_u64());
+    if version != Some(u64::from(CONFIG_SCHEMA_VERSION)) {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("schemaVersion".to_string(), CONFIG_SCHEMA_VERSION.into());
+            migrations.push(ConfigMigration {
+                key: "schemaVersion".to_string(),
+                description: format!("Set schemaVersion to {}", CONFIG_SCHEMA_VERSION),
+            });
+        }
+    }
+
+    migrations
+}
+
+/// End of the JSON value starting at `start` in comment-free `json`
+fn json_value_end(json: &[u8], start: usize) -> usize {
+    let mut i = start;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    while i < json.len() {
+        let b = json[i];
+        if in_string {
+            match b {
+                b'\\' => i += 1,
+                b'"' => {
+                    in_string = false;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                _ => {}
+            }
+        } else {
+            match b {
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth == 0 => return i,
+                b'}' | b']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                b',' if depth == 0 => return i,
+                b if b.is_ascii_whitespace() && depth == 0 => return i,
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    i
+}
+
+fn skip_json_whitespace(json: &[u8], mut i: usize) -> usize {
+    while json.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// Byte spans of a member in comment-free `json`
+struct JsonMember {
+    key: std::ops::Range<usize>,
+    value: std::ops::Range<usize>,
+}
+
+/// Members of the object whose `{` is at `open`
+fn json_object_members(json: &str, open: usize) -> Vec<(String, JsonMember)> {
+    let bytes = json.as_bytes();
+    let mut members = Vec::new();
+    let mut i = skip_json_whitespace(bytes, open + 1);
+    while bytes.get(i) == Some(&b'"') {
+        let key_end = json_value_end(bytes, i);
+        let Ok(name) = serde_json::from_str::<String>(&json[i..key_end]) else { break };
+        let value_start = skip_json_whitespace(bytes, skip_json_whitespace(bytes, key_end) + 1);
+        let value_end = json_value_end(bytes, value_start);
+        members.push((name, JsonMember { key: i..key_end, value: value_start..value_end }));
+        i = skip_json_whitespace(bytes, value_end);
+        if bytes.get(i) == Some(&b',') {
+            i = skip_json_whitespace(bytes, i + 1);
+        }
+    }
+    members
+}
+
+/// Spans of the items of the array whose `[` is at `open`
+fn json_array_items(json: &str, open: usize) -> Vec<std::ops::Range<usize>> {
+    let bytes = json.as_bytes();
+    let mut items = Vec::new();
+    let mut i = skip_json_whitespace(bytes, open + 1);
+    while i < bytes.len() && bytes[i] != b']' {
+        let end = json_value_end(bytes, i);
+        items.push(i..end);
+        i = skip_json_whitespace(bytes, end);
+        if bytes.get(i) != Some(&b',') {
+            break;
+        }
+        i = skip_json_whitespace(bytes, i + 1);
+    }
+    items
+}
+
+/// The member at dotted `path` in comment-free `json`
+fn locate_json_member(json: &str, path: &str) -> Option<JsonMember> {
+    let mut object = skip_json_whitespace(json.as_bytes(), 0);
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if json.as_bytes().get(object) != Some(&b'{') {
+            return None;
+        }
+        let (_, member) = json_object_members(json, object).into_iter().find(|(name, _)| name == segment)?;
+        if segments.peek().is_none() {
+            return Some(member);
+        }
+        object = member.value.start;
+    }
+    None
+}
+
+/// Make `migrate_value`'s changes by editing the file's text, so its comments
+/// and layout survive. A rename into another object, or onto a key that is
+/// already set, can't be made in place and is refused.
+fn migrate_text(content: &str, renames: &[(&str, &str)]) -> Result<(String, Vec<ConfigMigration>), String> {
+    let parse_error = |e: JsoncError| format!("Failed to parse config file: {}", e);
+    let original = parse_jsonc_value(content).map_err(parse_error)?;
+    let mut migrated = original.clone();
+    let migrations = migrate_value(&mut migrated, renames);
+    if migrations.is_empty() {
+        return Ok((content.to_string(), migrations));
+    }
+
+    let json = blank_jsonc_comments(content).map_err(parse_error)?;
+    let mut edits: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+    for section in WEBGL_SECTIONS {
+        let key = format!("{}.webgl", section);
+        if get_dotted(&original, &key).is_some_and(|v| v.is_boolean()) {
+            let member = locate_json_member(&json, &key).ok_or_else(|| format!("Couldn't find {} in the file", key))?;
+            edits.push((member.value, get_dotted(&migrated, &key).map(|v| v.to_string()).unwrap_or_default()));
+        }
+    }
+    for (old, new) in renames {
+        if get_dotted(&original, old).is_none() {
+            continue;
+        }
+        let (old_parent, _) = old.rsplit_once('.').unwrap_or(("", old));
+        let (new_parent, new_key) = new.rsplit_once('.').unwrap_or(("", new));
+        if old_parent != new_parent || get_dotted(&original, new).is_some() {
+            return Err(format!("Can't move {} to {} without losing the file's comments; edit it by hand", old, new));
+        }
+        let member = locate_json_member(&json, old).ok_or_else(|| format!("Couldn't find {} in the file", old))?;
+        edits.push((member.key, serde_json::Value::String(new_key.to_string()).to_string()));
+    }
+    if original.get("schemaVersion") != migrated.get("schemaVersion") {
+        let version = CONFIG_SCHEMA_VERSION.to_string();
+        match locate_json_member(&json, "schemaVersion") {
+            Some(member) => edits.push((member.value, version)),
+            None => {
+                let open = skip_json_whitespace(json.as_bytes(), 0);
+                let first = skip_json_whitespace(json.as_bytes(), open + 1);
+                let member = format!("\"schemaVersion\": {}", version);
+                let insert = if json.as_bytes().get(first) == Some(&b'}') {
+                    member
+                } else {
+                    // Indent like the line the first member is on
+                    let line_start = content[..first].rfind('\n').map_or(0, |i| i + 1);
+                    let indent: String = content[line_start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+                    format!("{}{}{},", newline, indent, member)
+                };
+                edits.push((open + 1..open + 1, insert));
+            }
+        }
+    }
+
+    let mut text = content.to_string();
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, replacement) in edits {
+        text.replace_range(range, &replacement);
+    }
+    if parse_jsonc_value(&text).ok().as_ref() != Some(&migrated) {
+        return Err("Couldn't migrate the file in place; edit it by hand".to_string());
+    }
+    Ok((text, migrations))
+}
+
+/// `content` with `autoStart` set on the task named `task_name`, edited in
+/// place like `migrate_text` so comments and layout survive. None when the
+/// file doesn't define the task.
+fn task_autostart_text(content: &str, task_name: &str, enabled: bool) -> Result<Option<String>, String> {
+    let parse_error = |e: JsoncError| format!("Failed to parse config file: {}", e);
+    let mut expected = parse_jsonc_value(content).map_err(parse_error)?;
+    let task = expected
+        .get_mut("tasks")
+        .and_then(|tasks| tasks.as_array_mut())
+        .and_then(|tasks| tasks.iter_mut().find(|t| t.get("name").and_then(|n| n.as_str()) == Some(task_name)))
+        .and_then(|task| task.as_object_mut());
+    let Some(task) = task else {
+        return Ok(None);
+    };
+    task.insert("autoStart".to_string(), serde_json::Value::Bool(enabled));
+
+    let json = blank_jsonc_comments(content).map_err(parse_error)?;
+    let not_found = || format!("Couldn't find task {} in the file", task_name);
+    let tasks = locate_json_member(&json, "tasks").ok_or_else(not_found)?;
+    let members = json_array_items(&json, tasks.value.start)
+        .into_iter()
+        .filter(|item| json.as_bytes()[item.start] == b'{')
+        .map(|item| json_object_members(&json, item.start))
+        .find(|members| {
+            members.iter().any(|(key, member)| {
+                key == "name" && serde_json::from_str::<String>(&json[member.value.clone()]).ok().as_deref() == Some(task_name)
+            })
+        })
+        .ok_or_else(not_found)?;
+
+    let mut text = content.to_string();
+    match members.iter().find(|(key, _)| key == "autoStart") {
+        Some((_, member)) => text.replace_range(member.value.clone(), &enabled.to_string()),
+        None => {
+            let (first, last) = (&members[0].1, &members[members.len() - 1].1);
+            let member = format!("\"autoStart\": {}", enabled);
+            let line_start = content[..first.key.start].rfind('\n').map_or(0, |i| i + 1);
+            let object_start = json[..first.key.start].rfind('{').unwrap_or(0);
+            if line_start <= object_start {
+                // Members share the object's line
+                text.insert_str(last.value.end, &format!(", {}", member));
+            } else {
+                // On its own line, indented like the first member; a comment
+                // after the last member stays on that member's line
+                let indent: String = content[line_start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+                let line_end = content[last.value.end..].find(['\r', '\n']).map_or(content.len(), |i| last.value.end + i);
+                let line = format!("{}{}{}", newline, indent, member);
+                if json[last.value.end..line_end].trim().is_empty() {
+                    text.insert_str(line_end, &line);
+                    text.insert(last.value.end, ',');
+                } else {
+                    text.insert_str(last.value.end, &format!(",{}", line));
+                }
+            }
+        }
+    }
+    if parse_jsonc_value(&text).ok().as_ref() != Some(&expected) {
+        return Err("Couldn't set autoStart in place; edit the file by hand".to_string());
+    }
+    Ok(Some(text))
+}
+
+/// A deprecated key or value found in a config file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigWarning {
+    pub source: ConfigSource,
+    pub file: String,
+    /// Dotted path of the deprecated key
+    pub key: String,
+    /// What to write instead, as a key path or a value
+    pub replacement: String,
+    pub message: String,
+}
+
+/// Deprecated forms in one parsed config file, as (key, replacement, message)
+fn find_deprecations(value: &serde_json::Value, renames: &[(&str, &str)]) -> Vec<(String, String, String)> {
+    let mut found = Vec::new();
+    for section in WEBGL_SECTIONS {
+        let key = format!("{}.webgl", section);
+        if let Some(enabled) = get_dotted(value, &key).and_then(|v| v.as_bool()) {
+            let mode = if enabled { "auto" } else { "off" };
+            let message = format!("Boolean {} is deprecated; use \"{}\" (\"off\" | \"auto\" | \"on\")", key, mode);
+            found.push((key, format!("\"{}\"", mode), message));
+        }
+    }
+    for (old, new) in renames {
+        if get_dotted(value, old).is_some() {
+            found.push((old.to_string(), new.to_string(), format!("{} has been renamed to {}", old, new)));
+        }
+    }
+    found
+}
+
+/// Deprecated keys in each config layer, with their replacements. Unknown keys
+/// aren't reported here.
+pub fn config_warnings(project_path: Option<&str>, options: &ConfigLoadOptions) -> Vec<ConfigWarning> {
+    let paths = get_config_paths(project_path);
+    config_layers(project_path, options)
+        .into_iter()
+        .flat_map(|(source, value)| {
+            let file = match source {
+                ConfigSource::Repo => paths.get(1),
+                ConfigSource::Local => paths.get(2),
+                _ => paths.first(),
+            }
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+            find_deprecations(&value, RENAMED_KEYS)
+                .into_iter()
+                .map(move |(key, replacement, message)| ConfigWarning {
+                    source,
+                    file: file.clone(),
+                    key,
+                    replacement,
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// Path of a config layer's file. Repo and local layers need a project.
+pub fn config_path_for_scope(scope: ConfigScope, project_path: Option<&str>) -> Result<PathBuf, String> {
+    let index = match scope {
+        ConfigScope::Global => 0,
+        ConfigScope::Repo => 1,
+        ConfigScope::Local => 2,
+    };
+    get_config_paths(project_path)
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| "Repo and local configs need a project path".to_string())
+}
+
+/// Rewrite one config file in the current format, copying the original to
+/// `<file>.bak` first. Only the migrated keys and values change; comments and
+/// formatting are kept. Files already current are left untouched.
+pub fn migrate_config(scope: ConfigScope, project_path: Option<&str>) -> Result<ConfigMigrationReport, String> {
+    let path = config_path_for_scope(scope, project_path)?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    let value = parse_jsonc_value(&content).map_err(|e| format!("Failed to parse config file: {}", e))?;
+    if !value.is_object() {
+        return Err("Config file must contain an object".to_string());
+    }
+
+    let from_version = value.get("schemaVersion").and_then(|v| v.as_u64()).map(|v| v as u32);
+    if from_version.is_some_and(|v| v > CONFIG_SCHEMA_VERSION) {
+        return Err(format!(
+            "Config schemaVersion {} is newer than this version of Shellflow supports ({})",
+            from_version.unwrap_or_default(),
+            CONFIG_SCHEMA_VERSION
+        ));
+    }
+
+    let (migrated, migrations) = migrate_text(&content, RENAMED_KEYS)?;
+    let mut backup_path = None;
+    if !migrations.is_empty() {
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        std::fs::write(&backup, &content).map_err(|e| format!("Failed to back up config file: {}", e))?;
+        std::fs::write(&path, migrated).map_err(|e| format!("Failed to write config file: {}", e))?;
+        backup_path = Some(backup.display().to_string());
+    }
+
+    Ok(ConfigMigrationReport {
+        path: path.display().to_string(),
+        backup_path,
+        from_version,
+        to_version: CONFIG_SCHEMA_VERSION,
+        migrations,
+    })
+}
+
 /// Load config with optional project-specific overrides.
 /// Config files are merged in order: global <- repo <- local
 /// - Global: ~/.config/shellflow/config.jsonc
 /// - Repo: {project_path}/.shellflow/config.jsonc (tracked in git)
 /// - Local: {project_path}/.shellflow/config.local.jsonc (gitignored)
 pub fn load_config_for_project(project_path: Option<&str>) -> Config {
-    load_config_with_errors(project_path).config
+    load_config_with_errors(project_path, &ConfigLoadOptions::default()).config
+}
+
+/// Load config for a project, honoring per-project load options
+pub fn load_config_for_project_with(project_path: Option<&str>, options: &ConfigLoadOptions) -> Config {
+    load_config_with_errors(project_path, options).config
+}
+
+/// Merge the repo ({project}/.shellflow/config.jsonc) and local
+/// ({project}/.shellflow/config.local.jsonc) layers into `merged`, returning
+/// the ones merged. Disabled layers are skipped with an informational entry in `errors`.
+fn merge_project_layers(
+    merged: &mut serde_json::Value,
+    project_dir: &Path,
+    options: &ConfigLoadOptions,
+    errors: &mut Vec<ConfigError>,
+) -> Vec<(PathBuf, serde_json::Value)> {
+    let mut merged_layers = Vec::new();
+    let layers = [
+        (ConfigLayer::Repo, "Repo", project_dir.join(".shellflow").join("config.jsonc")),
+        (ConfigLayer::Local, "Local", project_dir.join(".shellflow").join("config.local.jsonc")),
+    ];
+
+    for (layer, label, path) in layers {
+        if options.is_disabled(layer) {
+            errors.push(ConfigError {
+                file: path.display().to_string(),
+                message: format!("{} config disabled for this project", label),
+                severity: ConfigErrorSeverity::Info,
+                location: None,
+            });
+            continue;
+        }
+
+        if !path.exists() {
+            continue;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match parse_jsonc_value(&content) {
+                Ok(value) => {
+                    deep_merge(merged, &value);
+                    merged_layers.push((path, value));
+                }
+                Err(e) => errors.push(ConfigError {
+                    file: path.display().to_string(),
+                    message: e.message,
+                    severity: ConfigErrorSeverity::Error,
+                    location: e.location,
+                }),
+            },
+            Err(e) => errors.push(ConfigError {
+                file: path.display().to_string(),
+                message: format!("Failed to read file: {}", e),
+                severity: ConfigErrorSeverity::Error,
+                location: None,
+            }),
+        }
+    }
+    merged_layers
 }
 
 /// Load config with error reporting.
 /// Returns both the config (with defaults for invalid parts) and any parse errors.
-pub fn load_config_with_errors(project_path: Option<&str>) -> ConfigResult {
+pub fn load_config_with_errors(project_path: Option<&str>, options: &ConfigLoadOptions) -> ConfigResult {
     use serde_json::Value;
 
     let mut errors = Vec::new();
+    let mut layers = LayerFiles::default();
 
     // Start with global config (or empty object if missing)
     let global_path = get_config_path();
     let mut merged: Value = if global_path.exists() {
         match std::fs::read_to_string(&global_path) {
             Ok(content) => match parse_jsonc_value(&content) {
-                Ok(value) => value,
+                Ok(value) => {
+                    layers.0.push((global_path.clone(), value.clone()));
+                    value
+                }
                 Err(e) => {
                     errors.push(ConfigError {
                         file: global_path.display().to_string(),
-                        message: e,
+                        message: e.message,
+                        severity: ConfigErrorSeverity::Error,
+                        location: e.location,
                     });
                     Value::Object(serde_json::Map::new())
                 }
@@ -1255,6 +2403,8 @@ pub fn load_config_with_errors(project_path: Option<&str>) -> ConfigResult {
                 errors.push(ConfigError {
                     file: global_path.display().to_string(),
                     message: format!("Failed to read file: {}", e),
+                    severity: ConfigErrorSeverity::Error,
+                    location: None,
                 });
                 Value::Object(serde_json::Map::new())
             }
@@ -1270,52 +2420,239 @@ pub fn load_config_with_errors(project_path: Option<&str>) -> ConfigResult {
 
     // Merge repo and local configs if project path is provided
     if let Some(project_path) = project_path {
-        let project_dir = Path::new(project_path);
-
-        // Repo config: {project_path}/.shellflow/config.jsonc
-        let repo_config_path = project_dir.join(".shellflow").join("config.jsonc");
-        if repo_config_path.exists() {
-            match std::fs::read_to_string(&repo_config_path) {
-                Ok(content) => match parse_jsonc_value(&content) {
-                    Ok(repo_value) => deep_merge(&mut merged, &repo_value),
-                    Err(e) => errors.push(ConfigError {
-                        file: repo_config_path.display().to_string(),
-                        message: e,
-                    }),
-                },
-                Err(e) => errors.push(ConfigError {
-                    file: repo_config_path.display().to_string(),
-                    message: format!("Failed to read file: {}", e),
-                }),
-            }
-        }
-
-        // Local config: {project_path}/.shellflow/config.local.jsonc
-        let local_config_path = project_dir.join(".shellflow").join("config.local.jsonc");
-        if local_config_path.exists() {
-            match std::fs::read_to_string(&local_config_path) {
-                Ok(content) => match parse_jsonc_value(&content) {
-                    Ok(local_value) => deep_merge(&mut merged, &local_value),
-                    Err(e) => errors.push(ConfigError {
-                        file: local_config_path.display().to_string(),
-                        message: e,
-                    }),
-                },
-                Err(e) => errors.push(ConfigError {
-                    file: local_config_path.display().to_string(),
-                    message: format!("Failed to read file: {}", e),
-                }),
-            }
-        }
+        layers
+            .0
+            .extend(merge_project_layers(&mut merged, Path::new(project_path), options, &mut errors));
     }
 
     // Deserialize merged config as RawConfig, then resolve to Config
     let raw_config: RawConfig = serde_json::from_value(merged).unwrap_or_default();
     let config = Config::from_raw(raw_config);
 
+    validate_name_generator(&config.worktree.name_generator, project_path.map(Path::new), &layers, &mut errors);
+    validate_hide_patterns(&config.changes, &layers, &mut errors);
+    for problem in validate_templates(&config) {
+        errors.push(ConfigError {
+            file: layers.file_setting(&problem.config_path),
+            message: format!("Template {} doesn't render: {}", problem.config_path, problem.error),
+            severity: ConfigErrorSeverity::Warning,
+            location: None,
+        });
+    }
+
     ConfigResult { config, errors }
 }
 
+/// Templated settings that fail to render against placeholder values,
+/// including ones using a variable their scope doesn't provide
+pub fn validate_templates(config: &Config) -> Vec<TemplateProblem> {
+    let validator = TemplateValidator::default();
+    config
+        .templated_fields()
+        .into_iter()
+        .filter_map(|(path, template, scope)| validator.check(&path, template, scope))
+        .collect()
+}
+
+/// Config layer that supplied an effective setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// ~/.config/shellflow/config.jsonc
+    Global,
+    /// {project}/.shellflow/config.jsonc
+    Repo,
+    /// {project}/.shellflow/config.local.jsonc
+    Local,
+}
+
+/// A keyboard mapping after merging all config layers
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveMapping {
+    /// Action ID, e.g. "session::newTab"
+    pub action: String,
+    pub shortcut: Shortcut,
+    /// The shortcut for the current platform in menu accelerator format
+    pub accelerator: String,
+    pub source: ConfigSource,
+}
+
+/// Read a config layer for mapping resolution. Unreadable or invalid files are
+/// skipped; `load_config_with_errors` is what reports them.
+fn read_config_layer(path: &Path) -> Option<serde_json::Value> {
+    let content = std::fs::read_to_string(path).ok()?;
+    parse_jsonc_value(&content).ok()
+}
+
+/// The project's repo and local layers in merge order, honoring disabled layers
+fn project_config_layers(project_dir: &Path, options: &ConfigLoadOptions) -> Vec<(ConfigSource, serde_json::Value)> {
+    [
+        (ConfigLayer::Repo, ConfigSource::Repo, project_dir.join(".shellflow").join("config.jsonc")),
+        (ConfigLayer::Local, ConfigSource::Local, project_dir.join(".shellflow").join("config.local.jsonc")),
+    ]
+    .into_iter()
+    .filter(|(layer, _, _)| !options.is_disabled(*layer))
+    .filter_map(|(_, source, path)| read_config_layer(&path).map(|value| (source, value)))
+    .collect()
+}
+
+/// Merge config layers (in order, later wins) and report, for every mapping,
+/// the effective shortcut and the last layer that set it
+pub fn resolve_effective_mappings(layers: &[(ConfigSource, serde_json::Value)]) -> Vec<EffectiveMapping> {
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for (_, value) in layers {
+        deep_merge(&mut merged, value);
+    }
+    let raw: RawConfig = serde_json::from_value(merged).unwrap_or_default();
+    let serde_json::Value::Object(effective) = serde_json::to_value(&raw.mappings).unwrap_or_default() else {
+        return Vec::new();
+    };
+
+    effective
+        .into_iter()
+        .filter_map(|(action, value)| {
+            let shortcut: Shortcut = serde_json::from_value(value).ok()?;
+            let source = layers
+                .iter()
+                .rev()
+                .find(|(_, layer)| layer.get("mappings").and_then(|m| m.get(&action)).is_some())
+                .map(|(source, _)| *source)
+                .unwrap_or(ConfigSource::Default);
+            Some(EffectiveMapping {
+                accelerator: shortcut.to_accelerator(),
+                action,
+                shortcut,
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Effective mappings for a project (global <- repo <- local), with their sources
+pub fn load_effective_mappings(project_path: Option<&str>, options: &ConfigLoadOptions) -> Vec<EffectiveMapping> {
+    resolve_effective_mappings(&config_layers(project_path, options))
+}
+
+/// The global layer followed by the project's enabled layers, skipping missing files
+fn config_layers(project_path: Option<&str>, options: &ConfigLoadOptions) -> Vec<(ConfigSource, serde_json::Value)> {
+    let mut layers = Vec::new();
+    if let Some(global) = read_config_layer(&get_config_path()) {
+        layers.push((ConfigSource::Global, global));
+    }
+    if let Some(project_path) = project_path {
+        layers.extend(project_config_layers(Path::new(project_path), options));
+    }
+    layers
+}
+
+/// Parsed config files in merge order, to name the file a setting came from
+#[derive(Default)]
+struct LayerFiles(Vec<(PathBuf, serde_json::Value)>);
+
+impl LayerFiles {
+    /// The last file whose `key` array lists `item`; the global config if none does
+    fn file_listing(&self, key: &str, item: &str) -> String {
+        self.0
+            .iter()
+            .rev()
+            .find(|(_, value)| {
+                get_dotted(value, key)
+                    .and_then(|v| v.as_array())
+                    .is_some_and(|items| items.iter().any(|v| v.as_str() == Some(item)))
+            })
+            .map_or_else(get_config_path, |(path, _)| path.clone())
+            .display()
+            .to_string()
+    }
+
+    /// The last file that sets `key`, a dotted path where `tasks[name]` picks
+    /// the array item with that name. A key no file sets is put on the file
+    /// setting its nearest parent, then on the global config.
+    fn file_setting(&self, key: &str) -> String {
+        let mut segments: Vec<(&str, Option<&str>)> = Vec::new();
+        let mut rest = key;
+        while !rest.is_empty() {
+            let end = rest.find('.').unwrap_or(rest.len());
+            let segment = &rest[..end];
+            segments.push(match segment.split_once('[') {
+                Some((field, item)) => (field, Some(item.trim_end_matches(']'))),
+                None => (segment, None),
+            });
+            rest = rest.get(end + 1..).unwrap_or("");
+        }
+
+        let sets = |value: &serde_json::Value, segments: &[(&str, Option<&str>)]| {
+            segments
+                .iter()
+                .try_fold(value, |v, (field, item)| {
+                    let v = v.get(*field)?;
+                    match item {
+                        Some(name) => v
+                            .as_array()?
+                            .iter()
+                            .find(|i| i.get("name").and_then(|n| n.as_str()) == Some(*name)),
+                        None => Some(v),
+                    }
+                })
+                .is_some()
+        };
+        (1..=segments.len())
+            .rev()
+            .find_map(|len| self.0.iter().rev().find(|(_, value)| sets(value, &segments[..len])))
+            .map_or_else(get_config_path, |(path, _)| path.clone())
+            .display()
+            .to_string()
+    }
+}
+
+/// Report `changes.hidePatterns` entries that aren't valid globs
+fn validate_hide_patterns(changes: &ChangesConfig, layers: &LayerFiles, errors: &mut Vec<ConfigError>) {
+    for pattern in &changes.hide_patterns {
+        if let Err(e) = glob::Pattern::new(pattern.trim_end_matches('/')) {
+            errors.push(ConfigError {
+                file: layers.file_listing("changes.hidePatterns", pattern),
+                message: format!("Invalid changes.hidePatterns pattern '{}': {}", pattern, e),
+                severity: ConfigErrorSeverity::Error,
+                location: None,
+            });
+        }
+    }
+}
+
+/// Report unparseable blocklist patterns and unusable word-list files
+fn validate_name_generator(
+    name_generator: &NameGeneratorConfig,
+    project_path: Option<&Path>,
+    layers: &LayerFiles,
+    errors: &mut Vec<ConfigError>,
+) {
+    for pattern in &name_generator.blocklist {
+        if let Err(e) = glob::Pattern::new(pattern) {
+            errors.push(ConfigError {
+                file: layers.file_listing("worktree.nameGenerator.blocklist", pattern),
+                message: format!("Invalid worktree.nameGenerator.blocklist pattern '{}': {}", pattern, e),
+                severity: ConfigErrorSeverity::Error,
+                location: None,
+            });
+        }
+    }
+
+    for list in [&name_generator.adjectives, &name_generator.nouns].into_iter().flatten() {
+        let path = crate::worktree::resolve_word_list_path(list, project_path);
+        if let Err(message) = crate::worktree::load_word_list(&path) {
+            errors.push(ConfigError {
+                file: path.display().to_string(),
+                message,
+                severity: ConfigErrorSeverity::Error,
+                location: None,
+            });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1562,6 +2899,247 @@ mod tests {
         }
     }
 
+    mod migrate_config {
+        use super::*;
+        use serde_json::json;
+
+        #[test]
+        fn boolean_webgl_becomes_a_mode_and_version_is_set() {
+            let mut value = json!({ "main": { "webgl": true }, "drawer": { "webgl": false, "fontSize": 12 } });
+            let migrations = migrate_value(&mut value, &[]);
+            assert_eq!(value["main"]["webgl"], "auto");
+            assert_eq!(value["drawer"]["webgl"], "off");
+            assert_eq!(value["drawer"]["fontSize"], 12);
+            assert_eq!(value["schemaVersion"], CONFIG_SCHEMA_VERSION);
+            let keys: Vec<_> = migrations.iter().map(|m| m.key.as_str()).collect();
+            assert_eq!(keys, vec!["main.webgl", "drawer.webgl", "schemaVersion"]);
+
+            // Already current: nothing to do
+            assert!(migrate_value(&mut value, &[]).is_empty());
+        }
+
+        #[test]
+        fn renamed_keys_move_without_clobbering() {
+            let renames = [("main.oldSize", "main.fontSize"), ("oldTop", "panes.newTop")];
+            let mut value = json!({ "main": { "oldSize": 14, "fontSize": 12 }, "oldTop": 1 });
+            let migrations = migrate_value(&mut value, &renames);
+            assert_eq!(value["main"], json!({ "fontSize": 12 }));
+            assert_eq!(value["panes"]["newTop"], 1);
+            assert!(value.get("oldTop").is_none());
+            assert!(migrations[0].description.contains("already set"));
+            assert_eq!(migrations[1].description, "Renamed oldTop to panes.newTop");
+        }
+
+        #[test]
+        fn text_migration_keeps_comments_and_refuses_moves_it_cannot_make() {
+            let content = "{\n\t\"schemaVersion\": 1, /* old */\n\t\"mappings\": {\n\t\t// mine\n\t\t\"toggleDrawer\": \"cmd+j\"\n\t}\n}";
+            let (text, migrations) = migrate_text(content, RENAMED_KEYS).unwrap();
+            assert_eq!(
+                text,
+                "{\n\t\"schemaVersion\": 2, /* old */\n\t\"mappings\": {\n\t\t// mine\n\t\t\"drawer::toggle\": \"cmd+j\"\n\t}\n}"
+            );
+            assert_eq!(migrations.len(), 2);
+            let value = parse_jsonc_value(&text).unwrap();
+            assert_eq!(value["mappings"]["drawer::toggle"], "cmd+j");
+
+            // Nothing to do leaves the text as is, and an empty object just gains the version
+            assert_eq!(migrate_text(&text, RENAMED_KEYS).unwrap(), (text.clone(), vec![]));
+            assert_eq!(migrate_text("{}", &[]).unwrap().0, "{\"schemaVersion\": 2}");
+
+            let moved = migrate_text("{ // c\n \"oldTop\": 1 }", &[("oldTop", "panes.newTop")]);
+            assert!(moved.unwrap_err().contains("oldTop"));
+            let clobbering = r#"{ "mappings": { "toggleDrawer": "a", "drawer::toggle": "b" } }"#;
+            assert!(migrate_text(clobbering, RENAMED_KEYS).is_err());
+        }
+
+        #[test]
+        fn deprecations_name_the_replacement() {
+            let value = json!({ "main": { "webgl": false, "old": 1 }, "drawer": { "webgl": "on" } });
+            let found = find_deprecations(&value, &[("main.old", "main.new"), ("gone", "here")]);
+            assert_eq!(found.len(), 2);
+            assert_eq!(found[0].0, "main.webgl");
+            assert_eq!(found[0].1, "\"off\"");
+            assert_eq!(found[1], ("main.old".to_string(), "main.new".to_string(), "main.old has been renamed to main.new".to_string()));
+
+            // Migrated files have nothing left to report
+            let mut migrated = value.clone();
+            migrate_value(&mut migrated, &[("main.old", "main.new")]);
+            assert!(find_deprecations(&migrated, &[("main.old", "main.new")]).is_empty());
+        }
+
+        #[test]
+        fn project_layers_report_their_own_file() {
+            let project = std::env::temp_dir().join(format!("shellflow-warnings-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(project.join(".shellflow")).unwrap();
+            std::fs::write(project.join(".shellflow/config.local.jsonc"), r#"{ "drawer": { "webgl": true } }"#).unwrap();
+            let project_path = project.to_string_lossy().to_string();
+
+            let warnings: Vec<_> = config_warnings(Some(&project_path), &ConfigLoadOptions::default())
+                .into_iter()
+                .filter(|w| w.source != ConfigSource::Global)
+                .collect();
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].source, ConfigSource::Local);
+            assert_eq!(warnings[0].key, "drawer.webgl");
+            assert!(warnings[0].file.ends_with("config.local.jsonc"));
+
+            let disabled = ConfigLoadOptions { disabled_layers: vec![ConfigLayer::Local] };
+            assert!(config_warnings(Some(&project_path), &disabled).iter().all(|w| w.source == ConfigSource::Global));
+            let _ = std::fs::remove_dir_all(&project);
+        }
+
+        #[test]
+        fn template_problems_name_the_config_key() {
+            let mut config = Config::from_raw(RawConfig::default());
+            assert!(validate_templates(&config).is_empty(), "{:?}", validate_templates(&config));
+
+            config.worktree.directory = Some("{{ repo_directory }}/../{{ worktree_name }}".to_string());
+            config.tasks = vec![serde_json::from_value(serde_json::json!({
+                "name": "Dev",
+                "command": "npm run dev -- --port {{ branch | hash_port }}",
+                "env": { "PORT": "{{ brnch | hash_port }}", "NODE_ENV": "development" },
+                "urls": { "App": "http://localhost:{{ branch | hash_port", "Docs": "http://{{ branch }}.docs" },
+            }))
+            .unwrap()];
+            let problems = validate_templates(&config);
+            let paths: Vec<&str> = problems.iter().map(|p| p.config_path.as_str()).collect();
+            assert_eq!(paths, ["tasks[Dev].env.PORT", "tasks[Dev].urls.App"]);
+            assert_eq!(problems[0].template, "{{ brnch | hash_port }}");
+        }
+
+        #[test]
+        fn task_autostart_is_set_where_the_task_is_defined() {
+            let project = std::env::temp_dir().join(format!("shellflow-autostart-{}", uuid::Uuid::new_v4()));
+            let dir = project.join(".shellflow");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("config.jsonc"),
+                r#"{ "tasks": [{ "name": "Dev", "command": "npm run dev", "kind": "daemon" }] }"#,
+            )
+            .unwrap();
+            std::fs::write(dir.join("config.local.jsonc"), r#"{ "main": { "fontSize": 14 } }"#).unwrap();
+            let read = |name: &str| parse_jsonc_value(&std::fs::read_to_string(dir.join(name)).unwrap()).unwrap();
+
+            let written = set_task_autostart(&project, "Dev", true).unwrap();
+            assert_eq!(written, dir.join("config.jsonc"));
+            assert_eq!(read("config.jsonc")["tasks"][0]["autoStart"], true);
+            assert_eq!(read("config.jsonc")["tasks"][0]["command"], "npm run dev");
+
+            // A local override of the task is the more specific place
+            std::fs::write(
+                dir.join("config.local.jsonc"),
+                r#"{ "tasks": [{ "name": "Dev", "command": "pnpm dev" }] }"#,
+            )
+            .unwrap();
+            assert_eq!(set_task_autostart(&project, "Dev", false).unwrap(), dir.join("config.local.jsonc"));
+            assert_eq!(read("config.local.jsonc")["tasks"][0]["autoStart"], false);
+            assert_eq!(read("config.jsonc")["tasks"][0]["autoStart"], true);
+
+            assert!(set_task_autostart(&project, "shellflow-no-such-task", true).is_err());
+            let _ = std::fs::remove_dir_all(&project);
+        }
+
+        #[test]
+        fn task_autostart_keeps_the_files_comments() {
+            let content = "{\n  // my tasks\n  \"tasks\": [\n    {\n      \"name\": \"Dev\", // main one\n      \"command\": \"npm run dev\" /* fast */\n    }\n  ]\n}";
+            let enabled = task_autostart_text(content, "Dev", true).unwrap().unwrap();
+            assert_eq!(
+                enabled,
+                "{\n  // my tasks\n  \"tasks\": [\n    {\n      \"name\": \"Dev\", // main one\n      \"command\": \"npm run dev\", /* fast */\n      \"autoStart\": true\n    }\n  ]\n}"
+            );
+            assert_eq!(task_autostart_text(&enabled, "Dev", false).unwrap().unwrap(), enabled.replace("true", "false"));
+
+            let inline = r#"{ "tasks": [{ "name": "Web" }, { "name": "Dev", "command": "x" }] } // end"#;
+            assert_eq!(
+                task_autostart_text(inline, "Dev", true).unwrap().unwrap(),
+                r#"{ "tasks": [{ "name": "Web" }, { "name": "Dev", "command": "x", "autoStart": true }] } // end"#
+            );
+            assert_eq!(task_autostart_text(inline, "Api", true).unwrap(), None);
+
+            let project = std::env::temp_dir().join(format!("shellflow-autostart-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(project.join(".shellflow")).unwrap();
+            let file = project.join(".shellflow/config.jsonc");
+            std::fs::write(&file, content).unwrap();
+            set_task_autostart(&project, "Dev", true).unwrap();
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), enabled);
+            let _ = std::fs::remove_dir_all(&project);
+        }
+
+        #[test]
+        fn rewrites_file_and_keeps_a_backup() {
+            let project = std::env::temp_dir().join(format!("shellflow-migrate-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(project.join(".shellflow")).unwrap();
+            let file = project.join(".shellflow/config.jsonc");
+            let original = "{\n  // renderer\n  \"main\": { \"webgl\": false }\n}\n";
+            std::fs::write(&file, original).unwrap();
+            let project_path = project.to_string_lossy().to_string();
+
+            let report = migrate_config(ConfigScope::Repo, Some(&project_path)).unwrap();
+            assert_eq!(report.from_version, None);
+            assert_eq!(report.migrations.len(), 2);
+            let backup = report.backup_path.unwrap();
+            assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+            assert_eq!(
+                std::fs::read_to_string(&file).unwrap(),
+                "{\n  \"schemaVersion\": 2,\n  // renderer\n  \"main\": { \"webgl\": \"off\" }\n}\n"
+            );
+
+            let again = migrate_config(ConfigScope::Repo, Some(&project_path)).unwrap();
+            assert!(again.migrations.is_empty() && again.backup_path.is_none());
+            assert!(migrate_config(ConfigScope::Local, None).is_err());
+            let _ = std::fs::remove_dir_all(&project);
+        }
+    }
+
+    mod locate_configs {
+        use super::*;
+
+        #[test]
+        fn nearest_existing_layer_is_the_edit_target() {
+            let project = "/work/app";
+            let only = |name: &'static str| move |path: &Path| path.ends_with(name);
+
+            let locations = locate_configs_with(Some(project), only(".shellflow/config.jsonc"));
+            let scopes: Vec<_> = locations.iter().map(|l| l.scope).collect();
+            assert_eq!(scopes, vec![ConfigScope::Global, ConfigScope::Repo, ConfigScope::Local]);
+            assert_eq!(locations.iter().map(|l| l.exists).collect::<Vec<_>>(), vec![false, true, false]);
+            assert!(locations[1].edit_target);
+            assert!(locations[2].path.ends_with("config.local.jsonc"));
+
+            // Nothing exists yet: an edit creates the global file
+            let locations = locate_configs_with(Some(project), |_| false);
+            assert!(locations[0].edit_target && !locations[0].exists);
+            assert_eq!(locations.iter().filter(|l| l.edit_target).count(), 1);
+
+            let locations = locate_configs_with(None, |_| true);
+            assert_eq!(locations.len(), 1);
+            assert!(locations[0].edit_target);
+        }
+    }
+
+    mod task_config {
+        use super::*;
+
+        #[test]
+        fn idle_shutdown_applies_to_daemons_and_falls_back_to_defaults() {
+            let tasks: Vec<TaskConfig> = serde_json::from_str(
+                r#"[
+                    { "name": "dev", "command": "npm run dev", "kind": "daemon" },
+                    { "name": "api", "command": "cargo run", "kind": "daemon", "idleShutdownMinutes": 0 },
+                    { "name": "db", "command": "pg", "kind": "daemon", "idleShutdownMinutes": 15 },
+                    { "name": "test", "command": "npm test", "idleShutdownMinutes": 15 }
+                ]"#,
+            )
+            .unwrap();
+            let defaults = TaskDefaultsConfig { idle_shutdown_minutes: Some(60), ..Default::default() };
+            let minutes: Vec<_> = tasks.iter().map(|t| t.effective_idle_shutdown_minutes(&defaults)).collect();
+            assert_eq!(minutes, vec![Some(60), None, Some(15), None]);
+
+            let none = TaskDefaultsConfig::default();
+            assert_eq!(tasks[0].effective_idle_shutdown_minutes(&none), None);
+        }
+    }
+
     mod apps_config {
         use super::*;
 
@@ -1627,4 +3205,330 @@ mod tests {
             assert_eq!(apps.editor_target(), AppTarget::Tab);
         }
     }
+
+    mod config_layers {
+        use super::*;
+
+        fn make_project(repo: &str, local: &str) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!("shellflow-config-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(dir.join(".shellflow")).unwrap();
+            std::fs::write(dir.join(".shellflow").join("config.jsonc"), repo).unwrap();
+            std::fs::write(dir.join(".shellflow").join("config.local.jsonc"), local).unwrap();
+            dir
+        }
+
+        #[test]
+        fn merges_repo_and_local_layers() {
+            let dir = make_project(
+                r#"{ "main": { "command": "repo-cmd", "fontSize": 14 } }"#,
+                r#"{ "main": { "fontSize": 16 } }"#,
+            );
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors);
+
+            assert!(errors.is_empty());
+            assert_eq!(merged["main"]["command"], "repo-cmd");
+            assert_eq!(merged["main"]["fontSize"], 16);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn task_overrides_keep_switcher_placement() {
+            let dir = make_project(r#"{ "tasks": [{ "name": "Build", "command": "make release" }] }"#, "{}");
+            let mut merged = serde_json::json!({
+                "tasks": [{ "name": "Build", "command": "make", "group": "build", "order": 2, "hidden": true, "env": { "A": "1" } }]
+            });
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors);
+
+            let task = &merged["tasks"][0];
+            assert_eq!(task["command"], "make release");
+            assert_eq!((&task["group"], &task["order"], &task["hidden"]), (&serde_json::json!("build"), &serde_json::json!(2), &serde_json::json!(true)));
+            // Everything else is still replaced wholesale
+            assert!(task.get("env").is_none());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn skips_disabled_repo_layer() {
+            let dir = make_project(
+                r#"{ "main": { "command": "crashy-cmd" } }"#,
+                r#"{ "main": { "fontSize": 16 } }"#,
+            );
+            let options = ConfigLoadOptions {
+                disabled_layers: vec![ConfigLayer::Repo],
+            };
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &options, &mut errors);
+
+            assert!(merged["main"].get("command").is_none());
+            assert_eq!(merged["main"]["fontSize"], 16);
+
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].severity, ConfigErrorSeverity::Info);
+            assert!(errors[0].file.ends_with("config.jsonc"));
+            assert_eq!(errors[0].message, "Repo config disabled for this project");
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn disabled_layer_hides_its_parse_errors() {
+            let dir = make_project("{ not valid json", "{}");
+            let options = ConfigLoadOptions {
+                disabled_layers: vec![ConfigLayer::Repo],
+            };
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &options, &mut errors);
+
+            assert!(errors.iter().all(|e| e.severity == ConfigErrorSeverity::Info));
+
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].severity, ConfigErrorSeverity::Error);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn pattern_errors_name_the_file_that_lists_the_pattern() {
+            let dir = make_project(
+                r#"{ "changes": { "hidePatterns": ["*.lock", "[oops"] } }"#,
+                r#"{ "changes": { "hidePatterns": ["dist/"] } }"#,
+            );
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            let layers = LayerFiles(merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors));
+            assert_eq!(layers.0.len(), 2);
+
+            let config = Config::from_raw(serde_json::from_value(merged).unwrap());
+            validate_hide_patterns(&config.changes, &layers, &mut errors);
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0].message.contains("[oops"));
+            assert_eq!(errors[0].file, dir.join(".shellflow").join("config.jsonc").display().to_string());
+
+            let blocklist = LayerFiles(vec![(
+                dir.join("local.jsonc"),
+                serde_json::json!({ "worktree": { "nameGenerator": { "blocklist": ["[bad"] } } }),
+            )]);
+            let name_generator = NameGeneratorConfig { blocklist: vec!["[bad".to_string()], ..Default::default() };
+            errors.clear();
+            validate_name_generator(&name_generator, Some(&dir), &blocklist, &mut errors);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].file, dir.join("local.jsonc").display().to_string());
+            // Patterns no layer lists fall back to the global config
+            assert_eq!(layers.file_listing("changes.hidePatterns", "missing"), get_config_path().display().to_string());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn template_warnings_name_the_file_that_sets_the_template() {
+            let dir = make_project(
+                r#"{ "tasks": [{ "name": "dev", "command": "run {{ brnch }}", "env": { "PORT": "{{ branch | hash_port }}" } }] }"#,
+                r#"{ "tasks": [{ "name": "dev", "env": { "PORT": "{{ prt }}" } }], "main": { "command": "sh" } }"#,
+            );
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            let layers = LayerFiles(merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors));
+            let repo = dir.join(".shellflow").join("config.jsonc").display().to_string();
+            let local = dir.join(".shellflow").join("config.local.jsonc").display().to_string();
+
+            assert_eq!(layers.file_setting("tasks[dev].command"), repo);
+            assert_eq!(layers.file_setting("tasks[dev].env.PORT"), local);
+            assert_eq!(layers.file_setting("main.command"), local);
+            // Unset keys go to the nearest parent some file sets, then the global config
+            assert_eq!(layers.file_setting("tasks[dev].urls.app"), local);
+            assert_eq!(layers.file_setting("tasks[other].command"), local);
+            assert_eq!(layers.file_setting("actions.mergeWorktreeWithConflicts"), get_config_path().display().to_string());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn config_layer_serializes_lowercase() {
+            assert_eq!(serde_json::to_string(&ConfigLayer::Repo).unwrap(), "\"repo\"");
+            let layer: ConfigLayer = serde_json::from_str("\"local\"").unwrap();
+            assert_eq!(layer, ConfigLayer::Local);
+        }
+    }
+
+    mod effective_mappings {
+        use super::*;
+
+        fn mapping_for<'a>(mappings: &'a [EffectiveMapping], action: &str) -> &'a EffectiveMapping {
+            mappings.iter().find(|m| m.action == action).unwrap()
+        }
+
+        fn make_project(repo: Option<&str>, local: Option<&str>) -> PathBuf {
+            let dir = std::env::temp_dir().join(format!("shellflow-mappings-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(dir.join(".shellflow")).unwrap();
+            if let Some(repo) = repo {
+                std::fs::write(dir.join(".shellflow").join("config.jsonc"), repo).unwrap();
+            }
+            if let Some(local) = local {
+                std::fs::write(dir.join(".shellflow").join("config.local.jsonc"), local).unwrap();
+            }
+            dir
+        }
+
+        #[test]
+        fn later_layers_win_and_report_their_source() {
+            let layers = vec![
+                (
+                    ConfigSource::Global,
+                    serde_json::json!({ "mappings": { "session::newTab": "ctrl+t", "drawer::toggle": "ctrl+d" } }),
+                ),
+                (ConfigSource::Repo, serde_json::json!({ "mappings": { "session::newTab": "ctrl+y" } })),
+                (ConfigSource::Local, serde_json::json!({ "mappings": { "drawer::toggle": "F3" } })),
+            ];
+            let mappings = resolve_effective_mappings(&layers);
+
+            let new_tab = mapping_for(&mappings, "session::newTab");
+            assert_eq!(new_tab.accelerator, "Ctrl+Y");
+            assert_eq!(new_tab.source, ConfigSource::Repo);
+
+            let drawer = mapping_for(&mappings, "drawer::toggle");
+            assert_eq!(drawer.accelerator, "F3");
+            assert_eq!(drawer.source, ConfigSource::Local);
+
+            let quit = mapping_for(&mappings, "app::quit");
+            assert_eq!(quit.source, ConfigSource::Default);
+            assert_eq!(quit.accelerator, MappingsConfig::default().quit.to_accelerator());
+        }
+
+        #[test]
+        fn switching_projects_changes_overridden_accelerator() {
+            let overriding = make_project(Some(r#"{ "mappings": { "session::newTab": "F7" } }"#), None);
+            let plain = make_project(None, None);
+            let global = (ConfigSource::Global, serde_json::json!({ "mappings": { "session::newTab": "F6" } }));
+            let options = ConfigLoadOptions::default();
+
+            let resolve = |project: &Path| {
+                let mut layers = vec![global.clone()];
+                layers.extend(project_config_layers(project, &options));
+                resolve_effective_mappings(&layers)
+            };
+
+            let first = resolve(&overriding);
+            assert_eq!(mapping_for(&first, "session::newTab").accelerator, "F7");
+            assert_eq!(mapping_for(&first, "session::newTab").source, ConfigSource::Repo);
+
+            let second = resolve(&plain);
+            assert_eq!(mapping_for(&second, "session::newTab").accelerator, "F6");
+            assert_eq!(mapping_for(&second, "session::newTab").source, ConfigSource::Global);
+
+            let _ = std::fs::remove_dir_all(&overriding);
+            let _ = std::fs::remove_dir_all(&plain);
+        }
+
+        #[test]
+        fn disabled_layer_does_not_supply_mappings() {
+            let dir = make_project(
+                Some(r#"{ "mappings": { "session::newTab": "F7" } }"#),
+                Some(r#"{ "mappings": { "drawer::toggle": "F8" } }"#),
+            );
+            let options = ConfigLoadOptions {
+                disabled_layers: vec![ConfigLayer::Repo],
+            };
+            let mappings = resolve_effective_mappings(&project_config_layers(&dir, &options));
+
+            assert_eq!(mapping_for(&mappings, "session::newTab").source, ConfigSource::Default);
+            assert_eq!(mapping_for(&mappings, "drawer::toggle").source, ConfigSource::Local);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    mod confirmations {
+        use super::*;
+
+        #[test]
+        fn defaults_require_confirmation() {
+            let confirmations = ConfirmationsConfig::default();
+            assert_eq!(
+                confirmations.ensure_confirmed(ConfirmableOperation::DeleteWorktree, false),
+                Err(ConfirmationRequired("deleteWorktree"))
+            );
+            assert_eq!(
+                confirmations.ensure_confirmed(ConfirmableOperation::ForceReset, false),
+                Err(ConfirmationRequired("forceReset"))
+            );
+            assert_eq!(
+                confirmations.ensure_confirmed(ConfirmableOperation::CleanUntracked, false),
+                Err(ConfirmationRequired("cleanUntracked"))
+            );
+            assert!(confirmations
+                .ensure_confirmed(ConfirmableOperation::DeleteRemoteBranch, true)
+                .is_ok());
+        }
+
+        #[test]
+        fn disabled_confirmation_allows_unconfirmed() {
+            let confirmations: ConfirmationsConfig =
+                serde_json::from_str(r#"{"deleteRemoteBranch": false}"#).unwrap();
+            assert!(confirmations
+                .ensure_confirmed(ConfirmableOperation::DeleteRemoteBranch, false)
+                .is_ok());
+            assert!(confirmations
+                .ensure_confirmed(ConfirmableOperation::DeleteWorktree, false)
+                .is_err());
+        }
+    }
+
+    mod jsonc_errors {
+        use super::*;
+
+        const MALFORMED: &str = "{\n  // a line comment\n  /* a block\n     comment\n     spanning lines */\n  \"main\": {\n    \"fontSize\": 14\n    \"padding\": 2\n  }\n}\n";
+
+        #[test]
+        fn reports_line_in_original_text() {
+            let err = parse_jsonc_value(MALFORMED).unwrap_err();
+            let location = err.location.unwrap();
+            // The missing comma is detected at `"padding"` on line 8 of the file
+            assert_eq!(location.line, 8);
+            assert_eq!(location.column, 5);
+            assert_eq!(MALFORMED.lines().nth(location.line - 1).unwrap().trim(), "\"padding\": 2");
+        }
+
+        #[test]
+        fn excerpt_has_context_and_caret() {
+            let location = parse_jsonc_value(MALFORMED).unwrap_err().location.unwrap();
+            assert_eq!(
+                location.excerpt,
+                "7 |     \"fontSize\": 14\n8 |     \"padding\": 2\n  |     ^\n9 |   }\n"
+            );
+        }
+
+        #[test]
+        fn columns_count_characters_after_multibyte_comments() {
+            let content = "{ /* café ☕ */ \"a\": 1 \"b\": 2 }";
+            let location = parse_jsonc_value(content).unwrap_err().location.unwrap();
+            assert_eq!(location.line, 1);
+            assert_eq!(content.chars().nth(location.column - 1), Some('"'));
+            assert_eq!(location.column, 23);
+        }
+
+        #[test]
+        fn layer_errors_carry_location() {
+            let dir = std::env::temp_dir().join(format!("shellflow-config-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(dir.join(".shellflow")).unwrap();
+            std::fs::write(dir.join(".shellflow").join("config.local.jsonc"), MALFORMED).unwrap();
+
+            let mut merged = serde_json::json!({});
+            let mut errors = Vec::new();
+            merge_project_layers(&mut merged, &dir, &ConfigLoadOptions::default(), &mut errors);
+
+            assert_eq!(errors.len(), 1);
+            assert!(errors[0].file.ends_with("config.local.jsonc"));
+            assert_eq!(errors[0].location.as_ref().map(|l| l.line), Some(8));
+            assert!(errors[0].message.contains("line 8"), "{}", errors[0].message);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn valid_jsonc_still_parses() {
+            let value = parse_jsonc_value("{\n  /* x\n */ \"a\": [1, 2,],\n}").unwrap();
+            assert_eq!(value["a"], serde_json::json!([1, 2]));
+        }
+    }
 }
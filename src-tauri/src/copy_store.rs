@@ -0,0 +1,460 @@
+//! Deduplicated copies of gitignored files.
+//!
+//! With `worktree.copy.dedupe`, every file copied into a worktree is kept
+//! once in a content-addressed store under `~/.shellflow/copy-store` and
+//! hardlinked into each worktree, so six worktrees of the same project share
+//! one copy of its build assets. Objects are named by a 64-bit FNV-1a hash and
+//! the file size; a name match is confirmed byte by byte, and a collision gets
+//! its own object. Whether the worktree can take hardlinks from the store is
+//! tried once per worktree; where it can't (another device, or a filesystem
+//! without them) files are copied as before and nothing is stored.
+//!
+//! Linked files are one file on disk: editing one in place (rather than
+//! replacing it) changes it in every worktree that links it, and in the store.
+//!
+//! `index.json` records which worktrees use each object. It is rewritten via a
+//! temp file and rename, so a crash leaves either the old or the new index.
+//! Objects no worktree uses any more are removed when a worktree is deleted or
+//! by `gc_copy_store`. Removing an object only drops the store's link, so the
+//! files in worktrees are never affected.
+
+use crate::path_utils;
+use log::info;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+const READ_CHUNK: usize = 64 * 1024;
+
+lazy_static::lazy_static! {
+    /// Held shared while copying and exclusively while collecting garbage, so
+    /// an object isn't removed between being stored and being recorded
+    static ref STORE_LOCK: RwLock<()> = RwLock::new(());
+    /// Serializes read-modify-write of the index
+    static ref INDEX_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoreIndex {
+    objects: BTreeMap<String, StoredObject>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredObject {
+    size: u64,
+    /// Paths of the worktrees linked to this object
+    worktrees: BTreeSet<String>,
+}
+
+/// Result of a garbage collection
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyStoreGc {
+    pub removed_objects: usize,
+    pub freed_bytes: u64,
+}
+
+pub struct CopyStore {
+    root: PathBuf,
+    /// Replaced in tests to simulate a cross-device destination
+    link: fn(&Path, &Path) -> io::Result<()>,
+}
+
+impl CopyStore {
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            link: |original, link| std::fs::hard_link(original, link),
+        }
+    }
+
+    /// The store in the app data directory
+    pub fn open_default() -> Self {
+        Self::at(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".shellflow")
+                .join("copy-store"),
+        )
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join("index.json")
+    }
+
+    fn load_index(&self) -> io::Result<StoreIndex> {
+        match std::fs::read_to_string(self.index_path()) {
+            Ok(content) => Ok(serde_json::from_str(&content).unwrap_or_else(|e| {
+                // Unreadable references only cost the dedupe, not any worktree files
+                info!("[copy_store] Ignoring unreadable index: {}", e);
+                StoreIndex::default()
+            })),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(StoreIndex::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the index atomically (write to temp, then rename)
+    fn save_index(&self, index: &StoreIndex) -> io::Result<()> {
+        std::fs::create_dir_all(&self.root)?;
+        let path = self.index_path();
+        let temp_path = path.with_extension("json.tmp");
+        let content = serde_json::to_string(index).map_err(io::Error::other)?;
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, &path)
+    }
+
+    /// Start copying files into one worktree
+    pub fn begin(&self) -> DedupedCopy<'_> {
+        DedupedCopy {
+            store: self,
+            _shared: STORE_LOCK.read(),
+            linked: BTreeMap::new(),
+            can_link: None,
+        }
+    }
+
+    /// Whether hardlinks from the store into `dir` work, tried with a scratch file
+    fn links_into(&self, dir: &Path) -> bool {
+        let probe_id = uuid::Uuid::new_v4();
+        let objects = self.objects_dir();
+        let probe = objects.join(format!("link-probe.{}.tmp", probe_id));
+        let target = dir.join(format!(".shellflow-link-probe-{}", probe_id));
+        let linked = std::fs::create_dir_all(&objects)
+            .and_then(|()| std::fs::write(&probe, b""))
+            .and_then(|()| (self.link)(&probe, &target));
+        let _ = std::fs::remove_file(&target);
+        let _ = std::fs::remove_file(&probe);
+        if let Err(e) = &linked {
+            info!("[copy_store] Copying into {:?} without the store: {}", dir, e);
+        }
+        linked.is_ok()
+    }
+
+    /// Name of the object holding `src`'s contents, storing it first if needed
+    fn intern(&self, src: &Path) -> io::Result<(String, u64)> {
+        let (hash, size) = hash_file(src)?;
+        let objects = self.objects_dir();
+        std::fs::create_dir_all(&objects)?;
+
+        for attempt in 0u32.. {
+            let key = match attempt {
+                0 => format!("{:016x}-{:x}", hash, size),
+                n => format!("{:016x}-{:x}-{}", hash, size, n),
+            };
+            let object = objects.join(&key);
+            match files_equal(&object, src) {
+                Ok(true) => return Ok((key, size)),
+                // A hash collision; try the next name
+                Ok(false) => continue,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    let temp = objects.join(format!("{}.{}.tmp", key, uuid::Uuid::new_v4()));
+                    std::fs::copy(src, &temp)?;
+                    std::fs::rename(&temp, &object)?;
+                    return Ok((key, size));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    /// Drop `worktree_path`'s references and remove the objects nothing else uses.
+    /// Doesn't wait for copies into other worktrees.
+    pub fn release_worktree(&self, worktree_path: &Path) -> io::Result<CopyStoreGc> {
+        let worktree = path_utils::normalize_path_string(worktree_path);
+        let _index_lock = INDEX_LOCK.lock();
+        self.collect(|path| path != worktree, false)
+    }
+
+    /// Drop references of worktrees not in `live_worktrees` and remove objects
+    /// nothing uses, along with files the index doesn't know about (left by
+    /// an interrupted copy). Waits for running copies to finish.
+    pub fn collect_garbage(&self, live_worktrees: &HashSet<String>) -> io::Result<CopyStoreGc> {
+        let _exclusive = STORE_LOCK.write();
+        let _index_lock = INDEX_LOCK.lock();
+        self.collect(|path| live_worktrees.contains(path), true)
+    }
+
+    /// Callers hold `INDEX_LOCK`, and `STORE_LOCK` exclusively to remove unindexed files
+    fn collect(&self, keep: impl Fn(&str) -> bool, remove_unindexed: bool) -> io::Result<CopyStoreGc> {
+        if !self.root.exists() {
+            return Ok(CopyStoreGc::default());
+        }
+
+        let mut index = self.load_index()?;
+        let mut unused = Vec::new();
+        index.objects.retain(|key, object| {
+            object.worktrees.retain(|path| keep(path));
+            if object.worktrees.is_empty() {
+                unused.push(key.clone());
+            }
+            !object.worktrees.is_empty()
+        });
+
+        if remove_unindexed {
+            let stored: Vec<String> = match std::fs::read_dir(self.objects_dir()) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e),
+            };
+            for name in stored {
+                if !index.objects.contains_key(&name) && !unused.contains(&name) {
+                    unused.push(name);
+                }
+            }
+        }
+
+        let mut gc = CopyStoreGc::default();
+        for key in &unused {
+            let path = self.objects_dir().join(key);
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    gc.removed_objects += 1;
+                    gc.freed_bytes += size;
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => info!("[copy_store] Failed to remove {:?}: {}", path, e),
+            }
+        }
+
+        if !unused.is_empty() || remove_unindexed {
+            self.save_index(&index)?;
+        }
+        Ok(gc)
+    }
+}
+
+/// Files copied into one worktree through the store
+pub struct DedupedCopy<'a> {
+    store: &'a CopyStore,
+    _shared: RwLockReadGuard<'a, ()>,
+    /// Objects linked into the worktree, with their size
+    linked: BTreeMap<String, u64>,
+    /// Whether the worktree can take hardlinks from the store; probed on the first file
+    can_link: Option<bool>,
+}
+
+impl DedupedCopy<'_> {
+    /// Put `src`'s contents at `dst`, as a link to the stored object where possible
+    pub fn copy_file(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        let store = self.store;
+        let can_link = *self
+            .can_link
+            .get_or_insert_with(|| store.links_into(dst.parent().unwrap_or(Path::new("."))));
+        if !can_link {
+            // Storing would only leave an object nothing links to
+            std::fs::copy(src, dst)?;
+            return Ok(());
+        }
+
+        let (key, size) = self.store.intern(src)?;
+        match std::fs::remove_file(dst) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        match (self.store.link)(&self.store.objects_dir().join(&key), dst) {
+            Ok(()) => {
+                self.linked.insert(key, size);
+            }
+            Err(e) => {
+                info!("[copy_store] Copying {:?} instead of linking: {}", dst, e);
+                std::fs::copy(src, dst)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the worktree's references in the index
+    pub fn finish(self, worktree_path: &Path) -> io::Result<()> {
+        if self.linked.is_empty() {
+            return Ok(());
+        }
+        let worktree = path_utils::normalize_path_string(worktree_path);
+        let _index_lock = INDEX_LOCK.lock();
+        let mut index = self.store.load_index()?;
+        for (key, size) in self.linked {
+            let object = index.objects.entry(key).or_default();
+            object.size = size;
+            object.worktrees.insert(worktree.clone());
+        }
+        self.store.save_index(&index)
+    }
+}
+
+/// 64-bit FNV-1a of the file's contents, and its size
+fn hash_file(path: &Path) -> io::Result<(u64, u64)> {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; READ_CHUNK];
+    let mut hash = OFFSET_BASIS;
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            return Ok((hash, size));
+        }
+        for byte in &buf[..read] {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+        size += read as u64;
+    }
+}
+
+/// Whether two files have the same contents; NotFound if `a` doesn't exist
+fn files_equal(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = File::open(a)?;
+    let mut b = File::open(b)?;
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    let mut buf_a = vec![0u8; READ_CHUNK];
+    let mut buf_b = vec![0u8; READ_CHUNK];
+    loop {
+        let read = a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn object_count(store: &CopyStore) -> usize {
+        std::fs::read_dir(store.objects_dir()).map(|d| d.count()).unwrap_or(0)
+    }
+
+    #[cfg(unix)]
+    fn same_inode(a: &Path, b: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata(a).unwrap().ino() == std::fs::metadata(b).unwrap().ino()
+    }
+
+    #[test]
+    fn identical_files_share_one_object_across_worktrees() {
+        let base = temp_dir("copy-store");
+        let store = CopyStore::at(base.join("store"));
+        std::fs::write(base.join("asset.bin"), vec![7u8; 200_000]).unwrap();
+        std::fs::write(base.join("other.bin"), b"other").unwrap();
+
+        for worktree in ["wt1", "wt2"] {
+            let dir = base.join(worktree);
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut copy = store.begin();
+            copy.copy_file(&base.join("asset.bin"), &dir.join("asset.bin")).unwrap();
+            copy.copy_file(&base.join("other.bin"), &dir.join("other.bin")).unwrap();
+            copy.finish(&dir).unwrap();
+        }
+
+        assert_eq!(object_count(&store), 2);
+        assert_eq!(std::fs::read(base.join("wt2").join("other.bin")).unwrap(), b"other");
+        #[cfg(unix)]
+        assert!(same_inode(&base.join("wt1").join("asset.bin"), &base.join("wt2").join("asset.bin")));
+        let index = store.load_index().unwrap();
+        assert!(index.objects.values().all(|o| o.worktrees.len() == 2));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn hash_collisions_are_told_apart_by_content() {
+        let base = temp_dir("copy-store-collision");
+        let store = CopyStore::at(base.join("store"));
+        std::fs::write(base.join("real.txt"), b"real").unwrap();
+        // Plant a different file under the name real.txt would get
+        let (hash, size) = hash_file(&base.join("real.txt")).unwrap();
+        std::fs::create_dir_all(store.objects_dir()).unwrap();
+        std::fs::write(store.objects_dir().join(format!("{:016x}-{:x}", hash, size)), b"fake").unwrap();
+
+        let (key, _) = store.intern(&base.join("real.txt")).unwrap();
+        assert!(key.ends_with("-1"), "{}", key);
+        assert_eq!(std::fs::read(store.objects_dir().join(key)).unwrap(), b"real");
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn falls_back_to_copying_when_links_fail() {
+        let base = temp_dir("copy-store-xdev");
+        let mut store = CopyStore::at(base.join("store"));
+        store.link = |_, _| Err(io::Error::new(io::ErrorKind::CrossesDevices, "cross-device link"));
+        std::fs::write(base.join("asset.bin"), b"asset").unwrap();
+        let dir = base.join("wt1");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut copy = store.begin();
+        copy.copy_file(&base.join("asset.bin"), &dir.join("asset.bin")).unwrap();
+        copy.finish(&dir).unwrap();
+
+        assert_eq!(std::fs::read(dir.join("asset.bin")).unwrap(), b"asset");
+        #[cfg(unix)]
+        assert!(!same_inode(&base.join("asset.bin"), &dir.join("asset.bin")));
+        // Linking was tried once, so nothing went into the store
+        assert!(store.load_index().unwrap().objects.is_empty());
+        assert_eq!(object_count(&store), 0);
+        assert!(std::fs::read_dir(&dir).unwrap().all(|e| e.unwrap().file_name() == "asset.bin"));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn deleting_worktrees_frees_objects_only_they_used() {
+        let base = temp_dir("copy-store-gc");
+        let store = CopyStore::at(base.join("store"));
+        std::fs::write(base.join("shared.bin"), b"shared").unwrap();
+        std::fs::write(base.join("only-wt1.bin"), b"only wt1").unwrap();
+        let (wt1, wt2) = (base.join("wt1"), base.join("wt2"));
+
+        for (dir, files) in [(&wt1, &["shared.bin", "only-wt1.bin"][..]), (&wt2, &["shared.bin"][..])] {
+            std::fs::create_dir_all(dir).unwrap();
+            let mut copy = store.begin();
+            for file in files {
+                copy.copy_file(&base.join(file), &dir.join(file)).unwrap();
+            }
+            copy.finish(dir).unwrap();
+        }
+
+        std::fs::remove_dir_all(&wt1).unwrap();
+        let gc = store.release_worktree(&wt1).unwrap();
+        assert_eq!(gc, CopyStoreGc { removed_objects: 1, freed_bytes: 8 });
+        assert_eq!(object_count(&store), 1);
+        assert_eq!(std::fs::read(wt2.join("shared.bin")).unwrap(), b"shared");
+
+        // An orphan left by an interrupted copy, and a worktree that's gone
+        std::fs::write(store.objects_dir().join("orphan"), b"x").unwrap();
+        let gc = store.collect_garbage(&HashSet::new()).unwrap();
+        assert_eq!(gc.removed_objects, 2);
+        assert_eq!(object_count(&store), 0);
+        assert_eq!(std::fs::read(wt2.join("shared.bin")).unwrap(), b"shared");
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,114 @@
+//! Base-branch drift.
+//!
+//! Reports how far each worktree of a project has fallen behind its base
+//! branch and how long it has sat untouched, so the UI can nudge about
+//! updating before a merge gets painful. Warnings are limited to one per
+//! worktree per day.
+
+use crate::config::DriftWarningConfig;
+use serde::Serialize;
+
+/// Minimum time between two drift warnings for the same worktree
+pub const DRIFT_WARNING_INTERVAL_MS: u64 = 24 * 60 * 60 * 1000;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// One worktree's position relative to its base branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeDrift {
+    pub worktree_id: String,
+    pub name: String,
+    pub branch: String,
+    pub base_branch: String,
+    pub commits_behind: usize,
+    pub commits_ahead: usize,
+    /// Whole days since the last commit on HEAD or the last focus, whichever is later
+    pub days_since_activity: u64,
+    /// Past one of the `worktree.driftWarning` thresholds
+    pub exceeds_threshold: bool,
+}
+
+/// Drift for a project's worktrees, most behind first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub warn_behind: u32,
+    pub stale_days: u32,
+    pub worktrees: Vec<WorktreeDrift>,
+}
+
+/// Whether a worktree this far behind and idle this long should be flagged:
+/// at least `warn_behind` commits behind, or behind at all and untouched for
+/// `stale_days`. A zero threshold disables that check.
+pub fn exceeds_threshold(behind: usize, days_idle: u64, config: &DriftWarningConfig) -> bool {
+    let too_far = config.warn_behind > 0 && behind >= config.warn_behind as usize;
+    let stale = config.stale_days > 0 && behind > 0 && days_idle >= u64::from(config.stale_days);
+    too_far || stale
+}
+
+/// Whether a warning may be emitted now, given when the last one went out
+pub fn warning_due(last_warned_ms: Option<u64>, now_ms: u64) -> bool {
+    last_warned_ms.is_none_or(|at| now_ms.saturating_sub(at) >= DRIFT_WARNING_INTERVAL_MS)
+}
+
+/// Whole days between the latest activity and now
+pub fn days_since(last_activity_ms: u64, now_ms: u64) -> u64 {
+    now_ms.saturating_sub(last_activity_ms) / DAY_MS
+}
+
+/// Sort most-behind first; ties keep their order
+pub fn sort_by_drift(worktrees: &mut [WorktreeDrift]) {
+    worktrees.sort_by_key(|w| std::cmp::Reverse(w.commits_behind));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(warn_behind: u32, stale_days: u32) -> DriftWarningConfig {
+        DriftWarningConfig { warn_behind, stale_days }
+    }
+
+    #[test]
+    fn thresholds_flag_far_behind_or_stale_worktrees() {
+        let defaults = DriftWarningConfig::default();
+        assert!(exceeds_threshold(20, 0, &defaults));
+        assert!(!exceeds_threshold(19, 6, &defaults));
+        assert!(exceeds_threshold(1, 7, &defaults));
+        // Idle but up to date is nothing to rebase
+        assert!(!exceeds_threshold(0, 30, &defaults));
+
+        assert!(!exceeds_threshold(500, 0, &config(0, 7)));
+        assert!(!exceeds_threshold(5, 500, &config(20, 0)));
+    }
+
+    #[test]
+    fn warnings_are_suppressed_for_a_day() {
+        let now = 10 * DAY_MS;
+        assert!(warning_due(None, now));
+        assert!(!warning_due(Some(now - DAY_MS + 1), now));
+        assert!(warning_due(Some(now - DAY_MS), now));
+        // A warning recorded "in the future" (clock change) doesn't underflow
+        assert!(!warning_due(Some(now + 5), now));
+    }
+
+    #[test]
+    fn report_sorts_most_behind_first() {
+        let drift = |id: &str, behind| WorktreeDrift {
+            worktree_id: id.to_string(),
+            name: id.to_string(),
+            branch: id.to_string(),
+            base_branch: "main".to_string(),
+            commits_behind: behind,
+            commits_ahead: 0,
+            days_since_activity: days_since(0, 3 * DAY_MS - 1),
+            exceeds_threshold: false,
+        };
+        let mut worktrees = vec![drift("a", 2), drift("b", 40), drift("c", 2)];
+        sort_by_drift(&mut worktrees);
+        let ids: Vec<_> = worktrees.iter().map(|w| w.worktree_id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a", "c"]);
+        assert_eq!(worktrees[0].days_since_activity, 2);
+    }
+}
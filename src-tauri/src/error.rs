@@ -0,0 +1,258 @@
+use crate::config::ConfirmationRequired;
+use crate::git::GitError;
+use crate::pty::PtyError;
+use crate::quick_commit::QuickCommitError;
+use crate::status::WorkflowKind;
+use crate::worktree::WorktreeError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use thiserror::Error;
+
+/// Error returned by Tauri commands.
+/// Serialized as `{ code, message, details }` so the frontend can branch on a
+/// stable `code` (e.g. "NOT_GIT_REPO") instead of matching message text.
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error(transparent)]
+    Git(#[from] GitError),
+    #[error(transparent)]
+    Pty(#[from] PtyError),
+    #[error(transparent)]
+    Worktree(#[from] WorktreeError),
+    /// A step of a quick commit; the code comes from the underlying git error
+    #[error(transparent)]
+    QuickCommit(#[from] QuickCommitError),
+    /// A destructive command called without `confirmed` while the config requires it
+    #[error(transparent)]
+    ConfirmationRequired(#[from] ConfirmationRequired),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A project, worktree, or other entity the command refers to doesn't exist
+    #[error("{0}")]
+    NotFound(String),
+    /// The command's arguments were rejected
+    #[error("{0}")]
+    InvalidInput(String),
+    /// A merge, cleanup or delete workflow already has the worktree
+    #[error("A {} workflow is already running for worktree {worktree_id}", .workflow.as_str())]
+    WorkflowInProgress { worktree_id: String, workflow: WorkflowKind },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Other(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Other(message.to_string())
+    }
+}
+
+impl From<git2::Error> for CommandError {
+    fn from(err: git2::Error) -> Self {
+        CommandError::Git(GitError::Git(err))
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(err: tauri::Error) -> Self {
+        CommandError::Other(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(err: serde_json::Error) -> Self {
+        CommandError::Other(err.to_string())
+    }
+}
+
+fn git2_code(err: &git2::Error) -> &'static str {
+    match (err.code(), err.class()) {
+        (git2::ErrorCode::NotFound, git2::ErrorClass::Repository) => "NOT_GIT_REPO",
+        (git2::ErrorCode::NotFound, _) => "GIT_NOT_FOUND",
+        (git2::ErrorCode::Auth, _) => "AUTH_FAILED",
+        (git2::ErrorCode::Conflict | git2::ErrorCode::MergeConflict, _) => "GIT_CONFLICT",
+        (git2::ErrorCode::Locked, _) => "GIT_LOCKED",
+        _ => "GIT_ERROR",
+    }
+}
+
+fn git_code(err: &GitError) -> &'static str {
+    match err {
+        GitError::Git(e) => git2_code(e),
+        GitError::Io(_) => "IO_ERROR",
+        GitError::MergeConflict(_) => "MERGE_CONFLICT",
+        GitError::BranchNotFound(_) => "BRANCH_NOT_FOUND",
+        GitError::UncommittedChanges => "UNCOMMITTED_CHANGES",
+        GitError::NoRebaseInProgress => "NO_REBASE_IN_PROGRESS",
+        GitError::BranchAlreadyExists(_) => "BRANCH_ALREADY_EXISTS",
+        GitError::BranchInUse { .. } => "BRANCH_IN_USE",
+        GitError::BranchCaseConflict { .. } => "BRANCH_CASE_CONFLICT",
+        GitError::NoChanges => "NO_CHANGES",
+        GitError::HunkOutdated(_) => "HUNK_OUTDATED",
+        GitError::InvalidRebasePlan(_) => "INVALID_REBASE_PLAN",
+        GitError::RefNotReachable(_) => "REF_NOT_REACHABLE",
+        GitError::PathNotInCommit { .. } => "PATH_NOT_IN_COMMIT",
+        GitError::RemoteNotFound(_) => "REMOTE_NOT_FOUND",
+        GitError::SnapshotNotFound(_) => "SNAPSHOT_NOT_FOUND",
+        GitError::NotFastForward { .. } => "NOT_FAST_FORWARD",
+        GitError::Network { .. } => "NETWORK_FAILED",
+    }
+}
+
+impl CommandError {
+    /// Stable identifier for the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::Git(e) => git_code(e),
+            CommandError::Pty(e) => match e {
+                PtyError::Pty(_) => "PTY_ERROR",
+                PtyError::Io(_) => "IO_ERROR",
+                PtyError::SessionNotFound(_) => "SESSION_NOT_FOUND",
+                PtyError::InvalidPath(_) => "INVALID_PATH",
+                PtyError::SessionLimitReached { .. } => "SESSION_LIMIT_REACHED",
+                PtyError::WorktreeSessionLimitReached { .. } => "WORKTREE_SESSION_LIMIT_REACHED",
+            },
+            CommandError::Worktree(e) => match e {
+                WorktreeError::Git(e) => git_code(e),
+                WorktreeError::Io(_) => "IO_ERROR",
+                WorktreeError::NotARepository => "NOT_GIT_REPO",
+                WorktreeError::WorktreeNotFound(_) => "WORKTREE_NOT_FOUND",
+                WorktreeError::NameGenerationFailed { .. } => "NAME_GENERATION_FAILED",
+                WorktreeError::NameGenerator(_) => "NAME_GENERATOR_ERROR",
+                WorktreeError::Template(_) => "TEMPLATE_ERROR",
+                WorktreeError::PathTooLong { .. } => "PATH_TOO_LONG",
+                WorktreeError::ReservedName { .. } => "RESERVED_NAME",
+                WorktreeError::RepositoryMismatch(_) => "REPOSITORY_MISMATCH",
+            },
+            CommandError::QuickCommit(e) => git_code(&e.source),
+            CommandError::ConfirmationRequired(_) => "CONFIRMATION_REQUIRED",
+            CommandError::Io(_) => "IO_ERROR",
+            CommandError::NotFound(_) => "NOT_FOUND",
+            CommandError::InvalidInput(_) => "INVALID_INPUT",
+            CommandError::WorkflowInProgress { .. } => "WORKFLOW_IN_PROGRESS",
+            CommandError::Other(_) => "UNKNOWN",
+        }
+    }
+
+    /// Structured data for errors the UI can act on, if any
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            CommandError::Git(GitError::BranchNotFound(branch))
+            | CommandError::Worktree(WorktreeError::Git(GitError::BranchNotFound(branch))) => {
+                Some(json!({ "branch": branch }))
+            }
+            CommandError::Git(GitError::BranchAlreadyExists(branch))
+            | CommandError::Worktree(WorktreeError::Git(GitError::BranchAlreadyExists(branch))) => {
+                Some(json!({ "branch": branch }))
+            }
+            CommandError::Git(GitError::BranchInUse { branch, worktree_path })
+            | CommandError::Worktree(WorktreeError::Git(GitError::BranchInUse { branch, worktree_path })) => {
+                Some(json!({ "branch": branch, "worktreePath": worktree_path }))
+            }
+            CommandError::Git(GitError::BranchCaseConflict { requested, existing })
+            | CommandError::Worktree(WorktreeError::Git(GitError::BranchCaseConflict { requested, existing })) => {
+                Some(json!({ "branch": requested, "existing": existing }))
+            }
+            CommandError::Git(GitError::HunkOutdated(path)) => Some(json!({ "path": path })),
+            CommandError::Git(GitError::RefNotReachable(sha)) => Some(json!({ "sha": sha })),
+            CommandError::Git(GitError::PathNotInCommit { path, sha, renamed_from }) => {
+                Some(json!({ "path": path, "sha": sha, "renamedFrom": renamed_from }))
+            }
+            CommandError::Git(GitError::Network { reason, attempts, .. }) => Some(json!({
+                "reason": reason,
+                "transient": reason.is_transient(),
+                "attempts": attempts,
+            })),
+            CommandError::QuickCommit(e) => Some(json!({ "step": e.step, "sha": e.sha })),
+            CommandError::Pty(PtyError::SessionNotFound(pty_id)) => Some(json!({ "ptyId": pty_id })),
+            CommandError::Pty(PtyError::SessionLimitReached { limit, current }) => {
+                Some(json!({ "limit": limit, "current": current }))
+            }
+            CommandError::Pty(PtyError::WorktreeSessionLimitReached {
+                worktree_id,
+                limit,
+                current,
+            }) => Some(json!({ "worktreeId": worktree_id, "limit": limit, "current": current })),
+            CommandError::Worktree(WorktreeError::WorktreeNotFound(name)) => Some(json!({ "worktree": name })),
+            CommandError::Worktree(WorktreeError::NameGenerationFailed {
+                attempts,
+                blocked,
+                collided,
+            }) => Some(json!({ "attempts": attempts, "blocked": blocked, "collided": collided })),
+            CommandError::Worktree(WorktreeError::PathTooLong { length, limit }) => {
+                Some(json!({ "length": length, "limit": limit }))
+            }
+            CommandError::Worktree(WorktreeError::ReservedName { name, suggestion }) => {
+                Some(json!({ "name": name, "suggestion": suggestion }))
+            }
+            CommandError::Worktree(WorktreeError::RepositoryMismatch(path)) => Some(json!({ "path": path })),
+            CommandError::ConfirmationRequired(ConfirmationRequired(key)) => {
+                Some(json!({ "setting": format!("confirmations.{}", key) }))
+            }
+            CommandError::WorkflowInProgress { worktree_id, workflow } => {
+                Some(json!({ "worktreeId": worktree_id, "workflow": workflow }))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CommandError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        let err = CommandError::from(PtyError::SessionLimitReached { limit: 4, current: 4 });
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "SESSION_LIMIT_REACHED");
+        assert_eq!(value["message"], "Session limit reached: 4 of 4 terminals are running");
+        assert_eq!(value["details"], json!({ "limit": 4, "current": 4 }));
+    }
+
+    #[test]
+    fn wrapped_git_errors_keep_their_code() {
+        let err = CommandError::from(WorktreeError::Git(GitError::UncommittedChanges));
+        assert_eq!(err.code(), "UNCOMMITTED_CHANGES");
+        assert!(err.to_string().ends_with("Repository has uncommitted changes"));
+
+        let not_a_repo = std::env::temp_dir().join(format!("shellflow-error-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&not_a_repo).unwrap();
+        let err = CommandError::from(git2::Repository::open(&not_a_repo).err().unwrap());
+        assert_eq!(err.code(), "NOT_GIT_REPO");
+        let _ = std::fs::remove_dir_all(&not_a_repo);
+    }
+
+    #[test]
+    fn workflow_in_progress_names_the_workflow() {
+        let err = CommandError::WorkflowInProgress { worktree_id: "wt1".to_string(), workflow: WorkflowKind::Delete };
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "WORKFLOW_IN_PROGRESS");
+        assert_eq!(value["message"], "A delete workflow is already running for worktree wt1");
+        assert_eq!(value["details"], json!({ "worktreeId": "wt1", "workflow": "delete" }));
+    }
+
+    #[test]
+    fn plain_messages_have_no_details() {
+        let value = serde_json::to_value(CommandError::from("boom")).unwrap();
+        assert_eq!(value["code"], "UNKNOWN");
+        assert_eq!(value["message"], "boom");
+        assert!(value["details"].is_null());
+    }
+}
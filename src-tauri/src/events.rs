@@ -0,0 +1,718 @@
+//! Events sent to the frontend.
+//!
+//! Every event name is a constant here and is described in `CATALOG`, which
+//! `get_event_catalog` returns to the frontend and to external automation.
+//! Payloads that exist only to be emitted live in this module; ones that are
+//! also command results (`StatusSummary`, `RetryNotice`) stay with their
+//! module and are only described here. `SCHEMA_VERSION` goes up whenever an
+//! event is renamed or a payload field changes name or type.
+//!
+//! Payloads use camelCase fields, except a few older ones whose snake_case
+//! fields listeners already rely on. Those are kept as-is and marked
+//! deprecated in the catalog.
+
+use crate::drift::WorktreeDrift;
+use crate::git::CommitInfo;
+use crate::state::FileChange;
+use crate::MergeTargetResult;
+use serde::{Deserialize, Serialize};
+
+/// Version of the event names and payload shapes described by `CATALOG`
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const APP_READY: &str = "app-ready";
+pub const PTY_OUTPUT: &str = "pty-output";
+pub const PTY_READY: &str = "pty-ready";
+pub const PTY_EXIT: &str = "pty-exit";
+pub const TASK_STRUCTURED_EVENT: &str = "task-structured-event";
+pub const TASK_IDLE_SHUTDOWN: &str = "task-idle-shutdown";
+pub const SHUTDOWN_PROGRESS: &str = "shutdown-progress";
+pub const FILES_CHANGED: &str = "files-changed";
+pub const WORKTREE_REMOVED: &str = "worktree-removed";
+pub const BULK_OPERATION: &str = "bulk-operation";
+pub const BULK_OPERATION_FINISHED: &str = "bulk-operation-finished";
+pub const MERGE_COMPLETE: &str = "merge-complete";
+pub const REBASE_COMPLETE: &str = "rebase-complete";
+pub const CONFIG_CHANGED: &str = "config-changed";
+pub const MAPPINGS_CHANGED: &str = "mappings-changed";
+pub const MENU_ACTION: &str = "menu-action";
+pub const CLOSE_REQUESTED: &str = "close-requested";
+pub const STATUS_SUMMARY: &str = "status-summary";
+pub const DRIFT_WARNING: &str = "drift-warning";
+pub const GIT_NETWORK_RETRY: &str = "git-network-retry";
+pub const WORKTREE_COPY_QUEUED: &str = "worktree-copy-queued";
+pub const WORKTREE_COPY_STARTED: &str = "worktree-copy-started";
+pub const WORKTREE_COPY_COMPLETED: &str = "worktree-copy-completed";
+pub const DELETE_WORKTREE_PROGRESS: &str = "delete-worktree-progress";
+pub const DELETE_WORKTREE_COMPLETED: &str = "delete-worktree-completed";
+pub const MERGE_PROGRESS: &str = "merge-progress";
+pub const MERGE_COMPLETED: &str = "merge-completed";
+pub const MERGE_MULTIPLE_COMPLETED: &str = "merge-multiple-completed";
+pub const PROJECT_PATH_MISSING: &str = "project-path-missing";
+pub const SYSTEM_RESUMED: &str = "system-resumed";
+pub const TASK_AUTO_STARTED: &str = "task-auto-started";
+pub const FETCH_PROGRESS: &str = "fetch-progress";
+pub const FETCH_COMPLETED: &str = "fetch-completed";
+pub const COMMIT_ADDED: &str = "commit-added";
+pub const COMMIT_MESSAGE_CHUNK: &str = "commit-message-chunk";
+pub const COMMIT_MESSAGE_COMPLETED: &str = "commit-message-completed";
+
+/// Payload of `app-ready`, sent once setup has finished
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppReady {
+    pub schema_version: u32,
+    /// Application version
+    pub version: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PtyOutput {
+    pub pty_id: String,
+    pub data: String,
+    /// Buffered output re-sent by `attach_pty`, not new output
+    pub replay: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyReady {
+    pub pty_id: String,
+    pub worktree_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyExit {
+    pub pty_id: String,
+    pub worktree_id: String,
+    pub command: String,
+    pub exit_code: Option<u32>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStructuredEvent {
+    pub pty_id: String,
+    pub worktree_id: String,
+    pub task_name: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleShutdownEvent {
+    pub pty_id: String,
+    pub worktree_id: String,
+    pub task_name: String,
+    pub idle_minutes: u32,
+}
+
+/// Shutdown progress event payload
+#[derive(Clone, serde::Serialize)]
+pub struct ShutdownProgress {
+    pub phase: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signal: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FilesChanged {
+    pub worktree_path: String,
+    pub files: Vec<FileChange>,
+    /// How many of `files` match `changes.hidePatterns`
+    pub hidden_count: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WorktreeRemoved {
+    pub worktree_path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct BulkOperation {
+    pub worktree_path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct BulkOperationFinished {
+    pub worktree_path: String,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeComplete {
+    pub worktree_id: String,
+    pub worktree_path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseComplete {
+    pub worktree_id: String,
+    pub worktree_path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChanged {
+    pub project_path: Option<String>,
+}
+
+/// Payload of the `drift-warning` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftWarning {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub drift: WorktreeDrift,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeCopyCompleted {
+    pub worktree_id: String,
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteWorktreeProgress {
+    pub phase: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteWorktreeCompleted {
+    pub worktree_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeProgress {
+    pub phase: String,
+    pub message: String,
+}
+
+/// Progress event for an automatic stash, listing what is about to be stashed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoStashProgress {
+    pub phase: String,
+    pub message: String,
+    pub files: Vec<String>,
+    pub file_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeCompleted {
+    pub worktree_id: String,
+    pub success: bool,
+    pub branch_name: String,
+    pub deleted_worktree: bool,
+    pub error: Option<String>,
+    /// The merge succeeded but pushing the target branch (pushAfterMerge) failed
+    pub push_failed: bool,
+    /// Why the push failed
+    pub push_error: Option<String>,
+}
+
+/// Payload of `merge-multiple-completed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeMultipleCompleted {
+    pub worktree_id: String,
+    pub results: Vec<MergeTargetResult>,
+}
+
+/// Payload of the `project-path-missing` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPathMissing {
+    pub project_id: String,
+    pub name: String,
+    pub path: String,
+}
+
+/// Payload of `system-resumed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemResumed {
+    pub slept_seconds: u64,
+    /// Sessions whose process ended unnoticed, now removed
+    pub closed_sessions: Vec<String>,
+    /// Worktree watchers being restarted, a few at a time
+    pub restarting_watchers: usize,
+}
+
+/// Payload of `task-auto-started`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskAutoStarted {
+    pub worktree_id: String,
+    pub task_name: String,
+    pub pty_id: String,
+}
+
+/// Payload of `fetch-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchProgress {
+    pub repo_path: String,
+    pub remote: String,
+    pub message: String,
+}
+
+/// Payload of `fetch-completed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchCompleted {
+    pub repo_path: String,
+    pub remote: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Payload of `commit-added`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitAdded {
+    pub worktree_id: String,
+    pub worktree_path: String,
+    pub commit: CommitInfo,
+}
+
+/// Payload of `commit-message-chunk`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitMessageChunk {
+    pub repo_path: String,
+    /// Text to append to what has arrived so far
+    pub delta: String,
+}
+
+/// Payload of `commit-message-completed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitMessageCompleted {
+    pub repo_path: String,
+    /// The whole message, trimmed; None when generation failed
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+/// What an event carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PayloadKind {
+    Object,
+    /// A bare string, e.g. a worktree id
+    String,
+    /// No payload
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventField {
+    pub name: &'static str,
+    /// TypeScript-style type; `?` marks a field that may be left out
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub payload: PayloadKind,
+    /// Fields of an object payload
+    pub fields: &'static [EventField],
+    /// Set for shapes kept for compatibility that a later schema version will change
+    pub deprecated: Option<&'static str>,
+}
+
+/// Result of `get_event_catalog`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventCatalog {
+    pub schema_version: u32,
+    pub events: &'static [EventSpec],
+}
+
+const fn field(name: &'static str, ty: &'static str) -> EventField {
+    EventField { name, ty }
+}
+
+const fn object(name: &'static str, description: &'static str, fields: &'static [EventField]) -> EventSpec {
+    EventSpec { name, description, payload: PayloadKind::Object, fields, deprecated: None }
+}
+
+const fn bare(name: &'static str, description: &'static str, payload: PayloadKind) -> EventSpec {
+    EventSpec { name, description, payload, fields: &[], deprecated: None }
+}
+
+const fn snake_case(spec: EventSpec) -> EventSpec {
+    EventSpec {
+        deprecated: Some("Fields are snake_case unlike other events; kept for existing listeners and will become camelCase in a later schema version"),
+        ..spec
+    }
+}
+
+const PROGRESS_FIELDS: &[EventField] = &[field("phase", "string"), field("message", "string")];
+const WORKTREE_PATH_FIELDS: &[EventField] = &[field("worktree_path", "string")];
+const WORKTREE_FILES_FIELDS: &[EventField] = &[field("worktree_path", "string"), field("files", "FileChange[]")];
+const GIT_STATE_FIELDS: &[EventField] = &[field("worktreeId", "string"), field("worktreePath", "string")];
+
+/// Every event the backend emits
+pub const CATALOG: &[EventSpec] = &[
+    object(APP_READY, "Setup finished", &[field("schemaVersion", "number"), field("version", "string")]),
+    snake_case(object(
+        PTY_OUTPUT,
+        "Terminal output, or replayed buffer on attach",
+        &[field("pty_id", "string"), field("data", "string"), field("replay", "boolean")],
+    )),
+    object(PTY_READY, "A terminal produced its first output", &[field("ptyId", "string"), field("worktreeId", "string")]),
+    object(
+        PTY_EXIT,
+        "A terminal process exited",
+        &[
+            field("ptyId", "string"),
+            field("worktreeId", "string"),
+            field("command", "string"),
+            field("exitCode", "number | null"),
+        ],
+    ),
+    object(
+        TASK_STRUCTURED_EVENT,
+        "A JSON line printed by a task with structured output",
+        &[
+            field("ptyId", "string"),
+            field("worktreeId", "string"),
+            field("taskName", "string"),
+            field("payload", "unknown"),
+        ],
+    ),
+    object(
+        TASK_IDLE_SHUTDOWN,
+        "A daemon task was stopped after being idle",
+        &[
+            field("ptyId", "string"),
+            field("worktreeId", "string"),
+            field("taskName", "string"),
+            field("idleMinutes", "number"),
+        ],
+    ),
+    snake_case(object(
+        SHUTDOWN_PROGRESS,
+        "Progress of stopping terminals on quit",
+        &[
+            field("phase", "string"),
+            field("message", "string"),
+            field("process_name", "string?"),
+            field("pid", "number?"),
+            field("signal", "string?"),
+        ],
+    )),
+    snake_case(object(
+        FILES_CHANGED,
+        "Changed files of a worktree after a file system change",
+        &[field("worktree_path", "string"), field("files", "FileChange[]"), field("hidden_count", "number")],
+    )),
+    snake_case(object(WORKTREE_REMOVED, "A worktree folder was deleted outside the app", WORKTREE_PATH_FIELDS)),
+    snake_case(object(BULK_OPERATION, "A checkout or merge started rewriting the tree; refreshes pause", WORKTREE_PATH_FIELDS)),
+    snake_case(object(BULK_OPERATION_FINISHED, "The bulk operation finished", WORKTREE_FILES_FIELDS)),
+    object(MERGE_COMPLETE, "A merge with conflicts was committed (not the merge workflow, see merge-completed)", GIT_STATE_FIELDS),
+    object(REBASE_COMPLETE, "A rebase finished", GIT_STATE_FIELDS),
+    object(CONFIG_CHANGED, "A config file changed", &[field("projectPath", "string | null")]),
+    bare(MAPPINGS_CHANGED, "The key mappings file changed", PayloadKind::Null),
+    bare(MENU_ACTION, "A menu item was chosen; the payload is its id", PayloadKind::String),
+    bare(CLOSE_REQUESTED, "The window is asked to close", PayloadKind::Null),
+    object(
+        STATUS_SUMMARY,
+        "Status bar summary, sent when it changes",
+        &[
+            field("ptySessions", "number"),
+            field("workflows", "WorkflowStatus[]"),
+            field("watchers", "WatcherStatus"),
+            field("setupJobs", "number"),
+            field("recentError", "RecentError | null"),
+        ],
+    ),
+    object(
+        DRIFT_WARNING,
+        "A worktree fell behind its base branch past the configured thresholds",
+        &[
+            field("projectId", "string"),
+            field("worktreeId", "string"),
+            field("name", "string"),
+            field("branch", "string"),
+            field("baseBranch", "string"),
+            field("commitsBehind", "number"),
+            field("commitsAhead", "number"),
+            field("daysSinceActivity", "number"),
+            field("exceedsThreshold", "boolean"),
+        ],
+    ),
+    object(
+        GIT_NETWORK_RETRY,
+        "A push or remote branch deletion failed and will be retried",
+        &[
+            field("operation", "string"),
+            field("attempt", "number"),
+            field("maxAttempts", "number"),
+            field("delayMs", "number"),
+            field("reason", "NetworkFailureKind"),
+            field("message", "string"),
+        ],
+    ),
+    bare(WORKTREE_COPY_QUEUED, "Copying files into a new worktree waits for a free slot; the payload is the worktree id", PayloadKind::String),
+    bare(WORKTREE_COPY_STARTED, "Copying files into a new worktree started; the payload is the worktree id", PayloadKind::String),
+    object(
+        WORKTREE_COPY_COMPLETED,
+        "Copying files into a new worktree finished",
+        &[field("worktreeId", "string"), field("success", "boolean"), field("durationMs", "number")],
+    ),
+    object(DELETE_WORKTREE_PROGRESS, "A step of the delete workflow", PROGRESS_FIELDS),
+    object(
+        DELETE_WORKTREE_COMPLETED,
+        "The delete workflow finished",
+        &[field("worktreeId", "string"), field("success", "boolean"), field("error", "string | null")],
+    ),
+    object(
+        MERGE_PROGRESS,
+        "A step of the merge or cleanup workflow; the auto-stash phase also lists the stashed files",
+        &[
+            field("phase", "string"),
+            field("message", "string"),
+            field("files", "string[]?"),
+            field("fileCount", "number?"),
+        ],
+    ),
+    object(
+        MERGE_COMPLETED,
+        "The merge workflow finished",
+        &[
+            field("worktreeId", "string"),
+            field("success", "boolean"),
+            field("branchName", "string"),
+            field("deletedWorktree", "boolean"),
+            field("error", "string | null"),
+            field("pushFailed", "boolean"),
+            field("pushError", "string | null"),
+        ],
+    ),
+    object(
+        MERGE_MULTIPLE_COMPLETED,
+        "A merge into several target branches finished",
+        &[field("worktreeId", "string"), field("results", "MergeTargetResult[]")],
+    ),
+    object(
+        PROJECT_PATH_MISSING,
+        "A project's directory no longer exists",
+        &[field("projectId", "string"), field("name", "string"), field("path", "string")],
+    ),
+    object(
+        SYSTEM_RESUMED,
+        "The system woke from sleep; watchers are being restarted",
+        &[
+            field("sleptSeconds", "number"),
+            field("closedSessions", "string[]"),
+            field("restartingWatchers", "number"),
+        ],
+    ),
+    object(
+        TASK_AUTO_STARTED,
+        "An autoStart task was started because its worktree was opened",
+        &[field("worktreeId", "string"), field("taskName", "string"), field("ptyId", "string")],
+    ),
+    object(
+        FETCH_PROGRESS,
+        "A fetch started by fetch_remote is running",
+        &[field("repoPath", "string"), field("remote", "string"), field("message", "string")],
+    ),
+    object(
+        FETCH_COMPLETED,
+        "A fetch started by fetch_remote finished, failed or timed out",
+        &[
+            field("repoPath", "string"),
+            field("remote", "string"),
+            field("success", "boolean"),
+            field("error", "string | null"),
+        ],
+    ),
+    object(
+        COMMIT_ADDED,
+        "HEAD of a worktree watched with watch_commits advanced to a new commit",
+        &[
+            field("worktreeId", "string"),
+            field("worktreePath", "string"),
+            field("commit", "CommitInfo"),
+        ],
+    ),
+    object(
+        COMMIT_MESSAGE_CHUNK,
+        "More text of a message being generated by generate_commit_message",
+        &[field("repoPath", "string"), field("delta", "string")],
+    ),
+    object(
+        COMMIT_MESSAGE_COMPLETED,
+        "A message started by generate_commit_message finished or failed",
+        &[
+            field("repoPath", "string"),
+            field("message", "string | null"),
+            field("error", "string | null"),
+        ],
+    ),
+];
+
+pub fn catalog() -> EventCatalog {
+    EventCatalog { schema_version: SCHEMA_VERSION, events: CATALOG }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    fn spec(name: &str) -> &'static EventSpec {
+        CATALOG.iter().find(|e| e.name == name).unwrap_or_else(|| panic!("{} is not in the catalog", name))
+    }
+
+    /// Name constants declared above, by identifier
+    fn declared_constants() -> HashMap<String, String> {
+        include_str!("events.rs")
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const ")?.split_once(": &str = \""))
+            .map(|(ident, rest)| (ident.to_string(), rest.trim_end_matches("\";").to_string()))
+            .collect()
+    }
+
+    /// Event argument of every `.emit(` call outside test code
+    fn emitted_event_arguments() -> Vec<(String, String)> {
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(&src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path).unwrap();
+            let code = text.split("#[cfg(test)]\nmod tests").next().unwrap();
+            for (_, rest) in code.match_indices(".emit(").map(|(i, _)| code.split_at(i + ".emit(".len())) {
+                let argument = rest.trim_start().split([',', ')']).next().unwrap().trim().to_string();
+                found.push((path.file_name().unwrap().to_string_lossy().to_string(), argument));
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn every_emitted_event_is_in_the_catalog() {
+        let constants = declared_constants();
+        let emitted = emitted_event_arguments();
+        assert!(emitted.len() > 20, "expected emit sites, found {}", emitted.len());
+
+        for (file, argument) in &emitted {
+            let ident = argument.strip_prefix("events::").unwrap_or(argument);
+            let name = constants
+                .get(ident)
+                .unwrap_or_else(|| panic!("{} emits {}; use a constant from events.rs", file, argument));
+            spec(name);
+        }
+
+        let catalogued: HashSet<&str> = CATALOG.iter().map(|e| e.name).collect();
+        assert_eq!(catalogued.len(), CATALOG.len(), "duplicate catalog entries");
+        let declared: HashSet<&str> = constants.values().map(String::as_str).collect();
+        assert_eq!(catalogued, declared);
+    }
+
+    fn assert_fields(name: &str, payload: impl Serialize) {
+        let value = serde_json::to_value(payload).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut listed: Vec<&str> = spec(name).fields.iter().map(|f| f.name).collect();
+        keys.sort();
+        listed.sort();
+        assert_eq!(keys, listed, "{} payload doesn't match its catalog entry", name);
+    }
+
+    #[test]
+    fn payload_fields_match_the_catalog() {
+        let s = String::new;
+        assert_fields(APP_READY, AppReady { schema_version: SCHEMA_VERSION, version: s() });
+        assert_fields(PTY_OUTPUT, PtyOutput { pty_id: s(), data: s(), replay: false });
+        assert_fields(PTY_READY, PtyReady { pty_id: s(), worktree_id: s() });
+        assert_fields(PTY_EXIT, PtyExit { pty_id: s(), worktree_id: s(), command: s(), exit_code: None });
+        assert_fields(
+            SHUTDOWN_PROGRESS,
+            ShutdownProgress { phase: s(), message: s(), process_name: Some(s()), pid: Some(1), signal: Some(s()) },
+        );
+        assert_fields(FILES_CHANGED, FilesChanged { worktree_path: s(), files: vec![], hidden_count: 0 });
+        assert_fields(MERGE_COMPLETE, MergeComplete { worktree_id: s(), worktree_path: s() });
+        assert_fields(CONFIG_CHANGED, ConfigChanged { project_path: None });
+        assert_fields(SYSTEM_RESUMED, SystemResumed { slept_seconds: 0, closed_sessions: vec![], restarting_watchers: 0 });
+        assert_fields(TASK_AUTO_STARTED, TaskAutoStarted { worktree_id: s(), task_name: s(), pty_id: s() });
+        assert_fields(FETCH_PROGRESS, FetchProgress { repo_path: s(), remote: s(), message: s() });
+        assert_fields(FETCH_COMPLETED, FetchCompleted { repo_path: s(), remote: s(), success: true, error: None });
+        let commit = CommitInfo {
+            hash: s(),
+            short_hash: s(),
+            author: s(),
+            email: s(),
+            timestamp: 0,
+            subject: s(),
+            body: None,
+        };
+        assert_fields(COMMIT_ADDED, CommitAdded { worktree_id: s(), worktree_path: s(), commit });
+        assert_fields(COMMIT_MESSAGE_CHUNK, CommitMessageChunk { repo_path: s(), delta: s() });
+        assert_fields(COMMIT_MESSAGE_COMPLETED, CommitMessageCompleted { repo_path: s(), message: None, error: None });
+        assert_fields(WORKTREE_COPY_COMPLETED, WorktreeCopyCompleted { worktree_id: s(), success: true, duration_ms: 0 });
+        assert_fields(
+            MERGE_PROGRESS,
+            AutoStashProgress { phase: s(), message: s(), files: vec![], file_count: 0 },
+        );
+        assert_fields(
+            MERGE_COMPLETED,
+            MergeCompleted {
+                worktree_id: s(),
+                success: true,
+                branch_name: s(),
+                deleted_worktree: false,
+                error: None,
+                push_failed: false,
+                push_error: None,
+            },
+        );
+        assert_fields(
+            DRIFT_WARNING,
+            DriftWarning {
+                project_id: s(),
+                drift: WorktreeDrift {
+                    worktree_id: s(),
+                    name: s(),
+                    branch: s(),
+                    base_branch: s(),
+                    commits_behind: 0,
+                    commits_ahead: 0,
+                    days_since_activity: 0,
+                    exceeds_threshold: false,
+                },
+            },
+        );
+        assert_fields(STATUS_SUMMARY, crate::status::StatusSummary::default());
+        assert!(spec(FILES_CHANGED).deprecated.is_some());
+        assert!(spec(MERGE_COMPLETE).deprecated.is_none());
+    }
+}
@@ -1,8 +1,12 @@
 use crate::config::{BaseBranch, BaseBranchMode, MergeStrategy};
+use crate::network_retry::{NetworkFailureKind, NetworkRetry};
 use crate::state::{FileChange, FileStatus};
 use git2::{BranchType, Repository, Status, StatusOptions};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::process::{Command, Stdio};
 use std::io::Write;
 use thiserror::Error;
@@ -14,7 +18,7 @@ use std::os::windows::process::CommandExt;
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 fn git_command() -> Command {
-    let mut cmd = Command::new("git");
+    let mut cmd = Command::new(GIT_PROGRAM.as_str());
     #[cfg(windows)]
     {
         cmd.creation_flags(CREATE_NO_WINDOW);
@@ -22,6 +26,32 @@ fn git_command() -> Command {
     cmd
 }
 
+lazy_static::lazy_static! {
+    // git as found in the user's PATH, so an app started outside a shell still
+    // finds e.g. a Homebrew git; plain "git" if it isn't there
+    static ref GIT_PROGRAM: String = crate::which::find_in_user_path(&["git"]).unwrap_or_else(|| "git".to_string());
+    // One lock per repository, used to serialize git operations that mutate it
+    static ref REPO_LOCKS: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+    // Whether each repository's ref storage is case-insensitive, keyed by common git dir
+    static ref CASE_INSENSITIVE_REFS: Mutex<HashMap<PathBuf, bool>> = Mutex::new(HashMap::new());
+    // Language breakdown per repository, valid while HEAD doesn't move
+    static ref LANGUAGE_STATS: Mutex<HashMap<PathBuf, CachedLanguageStats>> = Mutex::new(HashMap::new());
+    // Ahead/behind counts per (HEAD, base) commit pair
+    static ref AHEAD_BEHIND: Mutex<HashMap<(git2::Oid, git2::Oid), (usize, usize)>> = Mutex::new(HashMap::new());
+    // Derived author identities; they depend only on name and email
+    static ref AUTHOR_IDENTITIES: Mutex<AuthorCache> = Mutex::new(HashMap::new());
+}
+
+/// Get the lock that serializes mutating git operations (worktree add/remove, etc.) for a repository.
+pub fn repo_lock(repo_path: &Path) -> Arc<Mutex<()>> {
+    let key = crate::path_utils::canonicalize_for_storage(repo_path);
+    REPO_LOCKS
+        .lock()
+        .entry(key)
+        .or_insert_with(|| Arc::new(Mutex::new(())))
+        .clone()
+}
+
 #[derive(Error, Debug)]
 pub enum GitError {
     #[error("Git error: {0}")]
@@ -34,6 +64,47 @@ pub enum GitError {
     BranchNotFound(String),
     #[error("Repository has uncommitted changes")]
     UncommittedChanges,
+    #[error("No rebase in progress")]
+    NoRebaseInProgress,
+    #[error("A branch named '{0}' already exists")]
+    BranchAlreadyExists(String),
+    #[error("Branch '{branch}' is already checked out at {worktree_path}")]
+    BranchInUse { branch: String, worktree_path: String },
+    #[error("Branch '{requested}' differs only by case from existing branch '{existing}'")]
+    BranchCaseConflict { requested: String, existing: String },
+    #[error("Nothing to commit")]
+    NoChanges,
+    #[error("'{0}' changed since the diff was loaded; reload the diff and retry")]
+    HunkOutdated(String),
+    #[error("Invalid rebase plan: {0}")]
+    InvalidRebasePlan(String),
+    #[error("Commit '{0}' is not on the current branch")]
+    RefNotReachable(String),
+    #[error("'{path}' does not exist at commit {sha}{}", renamed_hint(.renamed_from))]
+    PathNotInCommit { path: String, sha: String, renamed_from: Option<String> },
+    #[error("No remote named '{0}'")]
+    RemoteNotFound(String),
+    #[error("Snapshot '{0}' not found")]
+    SnapshotNotFound(String),
+    #[error("Can't fast-forward '{target}' to '{branch}'; the branches have diverged, rebase first")]
+    NotFastForward { branch: String, target: String },
+    #[error("{message}{}", attempts_hint(*.attempts))]
+    Network { reason: NetworkFailureKind, attempts: u32, message: String },
+}
+
+fn attempts_hint(attempts: u32) -> String {
+    if attempts > 1 {
+        format!(" (after {} attempts)", attempts)
+    } else {
+        String::new()
+    }
+}
+
+fn renamed_hint(renamed_from: &Option<String>) -> String {
+    renamed_from
+        .as_ref()
+        .map(|old| format!(" (it was named '{}' then)", old))
+        .unwrap_or_default()
 }
 
 pub fn stage_all(repo_path: &Path) -> Result<(), GitError> {
@@ -53,6 +124,298 @@ pub fn stage_all(repo_path: &Path) -> Result<(), GitError> {
     Ok(())
 }
 
+fn run_index_command(repo_path: &Path, args: &[&str], paths: &[String]) -> Result<(), GitError> {
+    // Paths are literal, so names with `*` or a leading `:` aren't pathspec magic
+    let output = git_command()
+        .arg("--literal-pathspecs")
+        .args(args)
+        .arg("--")
+        .args(paths)
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args[0],
+            stderr.trim()
+        ))));
+    }
+    Ok(())
+}
+
+/// Stage `paths` as they are in the working tree; a deleted file stages its removal
+pub fn stage_files(repo_path: &Path, paths: &[String]) -> Result<(), GitError> {
+    // An empty pathspec would stage everything
+    if paths.is_empty() {
+        return Ok(());
+    }
+    run_index_command(repo_path, &["add", "-A"], paths)
+}
+
+/// Put `paths` in the index back to HEAD, leaving the working tree alone.
+/// Before the first commit they are removed from the index instead.
+pub fn unstage_files(repo_path: &Path, paths: &[String]) -> Result<(), GitError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    if Repository::open(repo_path)?.head().is_ok() {
+        run_index_command(repo_path, &["reset", "-q", "HEAD"], paths)
+    } else {
+        run_index_command(repo_path, &["rm", "--cached", "-r", "-q", "--ignore-unmatch"], paths)
+    }
+}
+
+/// Outcome of discarding one path, from `discard_changes`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardResult {
+    pub path: String,
+    pub discarded: bool,
+    pub error: Option<String>,
+}
+
+/// A relative path that stays inside the worktree
+fn is_inside_worktree(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir))
+}
+
+/// `path` joined onto the worktree, refused when it leaves the worktree either
+/// lexically or through a symlinked directory. The last component isn't
+/// resolved, so a symlink there is removed as a link.
+fn resolve_in_worktree(repo_path: &Path, path: &str) -> Result<PathBuf, String> {
+    let outside = || "Path is outside the worktree".to_string();
+    if !is_inside_worktree(path) {
+        return Err(outside());
+    }
+    let root = repo_path.canonicalize().map_err(|e| e.to_string())?;
+    let target = root.join(path);
+    // Deleted files have no parent on disk; check the nearest one that exists
+    let mut parent = target.parent();
+    while let Some(dir) = parent {
+        if dir.symlink_metadata().is_ok() {
+            break;
+        }
+        parent = dir.parent();
+    }
+    let resolved = parent.unwrap_or(&root).canonicalize().map_err(|_| outside())?;
+    if !resolved.starts_with(&root) {
+        return Err(outside());
+    }
+    Ok(target)
+}
+
+fn discard_one(repo: &Repository, repo_path: &Path, path: &str, include_untracked: bool) -> Result<(), String> {
+    let target = resolve_in_worktree(repo_path, path)?;
+    let status = repo.status_file(Path::new(path)).map_err(|e| e.message().to_string())?;
+    if status.contains(Status::WT_NEW) {
+        if !include_untracked {
+            return Err("Untracked; left in place".to_string());
+        }
+        let is_dir = target.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false);
+        let removed = if is_dir {
+            std::fs::remove_dir_all(&target)
+        } else {
+            std::fs::remove_file(&target)
+        };
+        return removed.map_err(|e| e.to_string());
+    }
+
+    let output = git_command()
+        .args(["--literal-pathspecs", "checkout", "--", path])
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Throw away working-tree changes to `paths`, restoring tracked files from
+/// the index (`git checkout -- <path>`), so staged changes are kept. Untracked
+/// files are deleted only with `include_untracked`. Paths leaving the
+/// worktree are refused. Each path gets its own result, so one failure
+/// doesn't hide the rest.
+pub fn discard_changes(repo_path: &Path, paths: &[String], include_untracked: bool) -> Result<Vec<DiscardResult>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let outcome = discard_one(&repo, repo_path, path, include_untracked);
+            DiscardResult {
+                path: path.clone(),
+                discarded: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect())
+}
+
+/// `prefix` + `path` as git writes it in a patch header: C-quoted when it has
+/// quotes, backslashes, control or non-ASCII bytes, otherwise as is
+fn patch_header_path(prefix: &str, path: &str) -> String {
+    let name = format!("{}{}", prefix, path);
+    if !name.bytes().any(|b| b == b'"' || b == b'\\' || !(0x20..0x7f).contains(&b)) {
+        return name;
+    }
+    let mut quoted = String::from("\"");
+    for b in name.bytes() {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            0x20..=0x7e => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Single-hunk patch for `file_path`, from a hunk header (`@@ -a,b +c,d @@`)
+/// and its lines as shown in a diff (each starting with ' ', '+', '-' or '\\').
+/// `new_file_mode` (e.g. "100644") makes it a patch creating the file, for a
+/// hunk of an untracked or newly added file.
+pub fn build_hunk_patch(
+    file_path: &str,
+    hunk_header: &str,
+    hunk_lines: &[String],
+    new_file_mode: Option<&str>,
+) -> Result<String, GitError> {
+    let invalid = |message: String| GitError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, message));
+    if !hunk_header.starts_with("@@ -") {
+        return Err(invalid(format!("Not a hunk header: {}", hunk_header)));
+    }
+    if let Some(line) = hunk_lines
+        .iter()
+        .find(|l| !matches!(l.chars().next(), Some(' ' | '+' | '-' | '\\')))
+    {
+        return Err(invalid(format!("Not a diff line: {:?}", line)));
+    }
+    if !hunk_lines.iter().any(|l| l.starts_with('+') || l.starts_with('-')) {
+        return Err(invalid("Hunk has no changes".to_string()));
+    }
+
+    let (old, new) = (patch_header_path("a/", file_path), patch_header_path("b/", file_path));
+    // git ends a ---/+++ name containing a space with a tab, so it isn't
+    // mistaken for the start of a timestamp
+    let tab = if !new.starts_with('"') && file_path.contains(' ') { "\t" } else { "" };
+    let mut patch = format!("diff --git {} {}\n", old, new);
+    match new_file_mode {
+        Some(mode) => patch.push_str(&format!("new file mode {}\n--- /dev/null\n", mode)),
+        None => patch.push_str(&format!("--- {}{}\n", old, tab)),
+    }
+    patch.push_str(&format!("+++ {}{}\n{}\n", new, tab, hunk_header.trim_end()));
+    for line in hunk_lines {
+        patch.push_str(line.trim_end_matches(['\r', '\n']));
+        patch.push('\n');
+    }
+    Ok(patch)
+}
+
+/// Mode to create `file_path` with when its hunk adds the whole file: staging
+/// a file the index doesn't have yet, or unstaging one HEAD doesn't have.
+/// None for a file the patch only changes.
+fn new_file_mode(worktree_path: &Path, file_path: &str, reverse: bool) -> Result<Option<String>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let index = repo.index()?;
+    let indexed = index.get_path(Path::new(file_path), 0);
+    if reverse {
+        let in_head = repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .is_ok_and(|tree| tree.get_path(Path::new(file_path)).is_ok());
+        return Ok((!in_head).then(|| format!("{:o}", indexed.map_or(0o100644, |e| e.mode))));
+    }
+    if indexed.is_some() {
+        return Ok(None);
+    }
+    let metadata = std::fs::symlink_metadata(worktree_path.join(file_path))?;
+    let mode = if metadata.file_type().is_symlink() {
+        "120000"
+    } else if is_executable(&metadata) {
+        "100755"
+    } else {
+        "100644"
+    };
+    Ok(Some(mode.to_string()))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Apply one hunk to the index. `reverse` unstages a hunk taken from
+/// `git diff --cached`; otherwise the hunk comes from `git diff`. A hunk of a
+/// file the other side doesn't have is applied as adding (or, reversed,
+/// removing) the whole file. Context that no longer matches the index gives
+/// `HunkOutdated`.
+fn apply_hunk_to_index(
+    worktree_path: &Path,
+    file_path: &str,
+    hunk_header: &str,
+    hunk_lines: &[String],
+    reverse: bool,
+) -> Result<(), GitError> {
+    let mode = new_file_mode(worktree_path, file_path, reverse)?;
+    let patch = build_hunk_patch(file_path, hunk_header, hunk_lines, mode.as_deref())?;
+
+    let mut cmd = git_command();
+    // Only the hunk's own counts are sent, so let git recount them
+    cmd.args(["apply", "--cached", "--recount"]);
+    if reverse {
+        cmd.arg("--reverse");
+    }
+    cmd.arg("-")
+        .current_dir(worktree_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd.spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(patch.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("patch does not apply") || stderr.contains("while searching for") {
+            return Err(GitError::HunkOutdated(file_path.to_string()));
+        }
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git apply failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Stage a single hunk of `git diff` output (`git add -p` style)
+pub fn stage_hunk(worktree_path: &Path, file_path: &str, hunk_header: &str, hunk_lines: &[String]) -> Result<(), GitError> {
+    apply_hunk_to_index(worktree_path, file_path, hunk_header, hunk_lines, false)
+}
+
+/// Unstage a single hunk of `git diff --cached` output
+pub fn unstage_hunk(worktree_path: &Path, file_path: &str, hunk_header: &str, hunk_lines: &[String]) -> Result<(), GitError> {
+    apply_hunk_to_index(worktree_path, file_path, hunk_header, hunk_lines, true)
+}
+
 pub fn diff_cached(repo_path: &Path) -> Result<String, GitError> {
     let output = git_command()
         .args(["diff", "--cached"])
@@ -70,6 +433,25 @@ pub fn diff_cached(repo_path: &Path) -> Result<String, GitError> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
+/// Staged diff of one file (`git diff --cached -- <file>`). Newly added files
+/// diff against nothing; a file with no staged changes gives an empty string.
+pub fn staged_file_diff(worktree_path: &Path, file_path: &str) -> Result<String, GitError> {
+    let output = git_command()
+        .args(["diff", "--cached", "--", file_path])
+        .current_dir(worktree_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git diff --cached failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 pub fn diff_cached_files(repo_path: &Path) -> Result<Vec<String>, GitError> {
     let output = git_command()
         .args(["diff", "--cached", "--name-only"])
@@ -124,6 +506,49 @@ pub fn commit_staged(repo_path: &Path, message: &str) -> Result<(), GitError> {
     Ok(())
 }
 
+/// Fold the staged changes into the last commit. `message` replaces the
+/// commit message; `None` keeps it. Refuses when there is no commit yet or
+/// HEAD is detached, where the amended commit would be on no branch.
+pub fn amend_last_commit(repo_path: &Path, message: Option<&str>) -> Result<(), GitError> {
+    let invalid = |message: &str| GitError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string()));
+    let repo = Repository::open(repo_path)?;
+    if repo.head().is_err() {
+        return Err(invalid("Nothing to amend: there are no commits yet"));
+    }
+    if repo.head_detached()? {
+        return Err(invalid("Cannot amend on a detached HEAD; check out a branch first"));
+    }
+    if message.is_some_and(|m| m.trim().is_empty()) {
+        return Err(invalid("Commit message cannot be empty"));
+    }
+
+    let mut cmd = git_command();
+    match message {
+        Some(_) => cmd.args(["commit", "--amend", "-F", "-"]),
+        None => cmd.args(["commit", "--amend", "--no-edit"]),
+    };
+    cmd.current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd.spawn()?;
+    if let (Some(message), Some(stdin)) = (message, child.stdin.as_mut()) {
+        stdin.write_all(message.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git commit --amend failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(())
+}
+
 pub fn create_branch(repo_path: &Path, branch_name: &str) -> Result<(), GitError> {
     let output = git_command()
         .args(["checkout", "-b", branch_name])
@@ -141,7 +566,7 @@ pub fn create_branch(repo_path: &Path, branch_name: &str) -> Result<(), GitError
     Ok(())
 }
 
-pub fn push_current_branch(repo_path: &Path) -> Result<(), GitError> {
+pub fn push_current_branch(repo_path: &Path, retry: &NetworkRetry) -> Result<(), GitError> {
     let repo = Repository::open(repo_path)?;
     let branch = get_current_branch(&repo)?;
 
@@ -150,47 +575,389 @@ pub fn push_current_branch(repo_path: &Path) -> Result<(), GitError> {
         .current_dir(repo_path)
         .output()?;
 
-    let output = if upstream_check.status.success() {
-        git_command()
-            .args(["push"])
-            .current_dir(repo_path)
-            .output()?
+    let args: &[&str] = if upstream_check.status.success() {
+        &["push"]
     } else {
-        git_command()
-            .args(["push", "-u", "origin", &branch])
-            .current_dir(repo_path)
-            .output()?
+        &["push", "-u", "origin", &branch]
     };
+    retry.run("push", || run_remote_command(repo_path, args, "git push failed"))
+}
 
+/// Run a git command that talks to a remote, for `NetworkRetry::run`: the
+/// error is `context` followed by git's stderr
+fn run_remote_command(repo_path: &Path, args: &[&str], context: &str) -> Result<(), String> {
+    let output = git_command()
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| format!("{}: {}", context, e))?;
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(GitError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("git push failed: {}", stderr),
-        )));
+        return Err(format!("{}: {}", context, String::from_utf8_lossy(&output.stderr).trim()));
     }
-
     Ok(())
 }
 
-pub fn push_default_branch(repo_path: &Path) -> Result<(), GitError> {
+/// Remote a branch is pushed to, using git's precedence: branch.<name>.pushRemote,
+/// remote.pushDefault, branch.<name>.remote, then "origin"
+pub fn push_remote_for_branch(repo: &Repository, branch: &str) -> String {
+    let Ok(config) = repo.config() else {
+        return "origin".to_string();
+    };
+    [
+        format!("branch.{}.pushRemote", branch),
+        "remote.pushDefault".to_string(),
+        format!("branch.{}.remote", branch),
+    ]
+    .iter()
+    .find_map(|key| config.get_string(key).ok().filter(|remote| !remote.is_empty() && remote != "."))
+    .unwrap_or_else(|| "origin".to_string())
+}
+
+/// Editor fallback when nothing is configured; must run inside a terminal
+#[cfg(not(windows))]
+const FALLBACK_EDITOR: &str = "vi";
+/// Git for Windows bundles vim and runs the editor through its own shell, so
+/// vim is there without being on PATH; notepad would open outside the terminal
+#[cfg(windows)]
+const FALLBACK_EDITOR: &str = "vim";
+
+/// Editor for commands git runs in a terminal, using git's precedence:
+/// GIT_EDITOR, core.editor, VISUAL, EDITOR, then a terminal fallback
+pub fn choose_editor(core_editor: Option<String>, env: impl Fn(&str) -> Option<String>) -> String {
+    let set = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    set(env("GIT_EDITOR"))
+        .or_else(|| set(core_editor))
+        .or_else(|| set(env("VISUAL")))
+        .or_else(|| set(env("EDITOR")))
+        .unwrap_or_else(|| FALLBACK_EDITOR.to_string())
+}
+
+/// `GIT_EDITOR` value for interactive git sessions in `repo_path`
+pub fn interactive_editor(repo_path: &Path) -> String {
+    let core_editor = Repository::open(repo_path)
+        .ok()
+        .and_then(|repo| repo.config().ok())
+        .and_then(|config| config.get_string("core.editor").ok());
+    choose_editor(core_editor, |key| std::env::var(key).ok())
+}
+
+/// Config file a setting is read from. With `extensions.worktreeConfig`, each
+/// worktree also has its own `config.worktree` that overrides the shared
+/// repository config. Readers that open a worktree's path with libgit2 get
+/// that layering already, as does git run in the worktree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitConfigScope {
+    /// This worktree's `config.worktree`
+    Worktree,
+    /// The repository config shared by all worktrees
+    #[default]
+    Local,
+    /// The user's global config
+    Global,
+}
+
+impl GitConfigScope {
+    fn flag(self) -> &'static str {
+        match self {
+            GitConfigScope::Worktree => "--worktree",
+            GitConfigScope::Local => "--local",
+            GitConfigScope::Global => "--global",
+        }
+    }
+}
+
+/// Whether the repository keeps per-worktree settings in `config.worktree`
+pub fn worktree_config_enabled(repo_path: &Path) -> bool {
+    Repository::open(repo_path)
+        .and_then(|repo| repo.config())
+        .and_then(|config| config.get_bool("extensions.worktreeConfig"))
+        .unwrap_or(false)
+}
+
+/// Read `key` from one scope, or with `None` from every scope with git's own
+/// precedence. Without the worktreeConfig extension there is no worktree scope,
+/// and git would silently read the shared config instead, so that reads as unset.
+pub fn get_config(repo_path: &Path, key: &str, scope: Option<GitConfigScope>) -> Result<Option<String>, GitError> {
+    if scope == Some(GitConfigScope::Worktree) && !worktree_config_enabled(repo_path) {
+        return Ok(None);
+    }
+
+    let mut cmd = git_command();
+    cmd.arg("config");
+    if let Some(scope) = scope {
+        cmd.arg(scope.flag());
+    }
+    let output = cmd.args(["--get", key]).current_dir(repo_path).output()?;
+
+    match output.status.code() {
+        Some(0) => Ok(Some(String::from_utf8_lossy(&output.stdout).trim_end_matches('\n').to_string())),
+        // Exit code 1 means the key isn't set
+        Some(1) => Ok(None),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(GitError::Io(std::io::Error::other(format!(
+                "git config --get failed: {}",
+                stderr.trim()
+            ))))
+        }
+    }
+}
+
+/// Push the branch checked out in `repo_path` (e.g. a merge target) to its push remote
+pub fn push_checked_out_branch(repo_path: &Path, retry: &NetworkRetry) -> Result<(), GitError> {
     let repo = Repository::open(repo_path)?;
-    let branch = get_default_branch(&repo)?;
+    let branch = get_current_branch(&repo)?;
+    let remote = push_remote_for_branch(&repo, &branch);
+
+    let context = format!("git push {} {} failed", remote, branch);
+    retry.run("push", || run_remote_command(repo_path, &["push", &remote, &branch], &context))
+}
+
+/// Fetch `base_branch` from origin so a rebase can start from its remote tip.
+/// Returns false without running git when there is no origin remote.
+pub fn fetch_base_branch(repo_path: &Path, base_branch: &str, retry: &NetworkRetry) -> Result<bool, GitError> {
+    if Repository::open(repo_path)?.find_remote("origin").is_err() {
+        return Ok(false);
+    }
+    let context = format!("git fetch origin {} failed", base_branch);
+    retry.run("fetch", || run_remote_command(repo_path, &["fetch", "origin", base_branch], &context))?;
+    Ok(true)
+}
+
+/// The upstream of the branch checked out in `worktree_path`, e.g.
+/// "origin/feature", or None when it has none or HEAD is detached
+pub fn get_upstream(worktree_path: &Path) -> Result<Option<String>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let Ok(head) = repo.head() else {
+        return Ok(None);
+    };
+    let Some(name) = head.shorthand().filter(|_| head.is_branch()) else {
+        return Ok(None);
+    };
+    let Ok(upstream) = repo.find_branch(name, BranchType::Local)?.upstream() else {
+        return Ok(None);
+    };
+    Ok(upstream.name()?.map(str::to_string))
+}
+
+/// Make `<remote>/<remote_branch>` the upstream of the branch checked out in
+/// `worktree_path`. The remote must exist and the branch must have been fetched.
+pub fn set_upstream(worktree_path: &Path, remote: &str, remote_branch: &str) -> Result<(), GitError> {
+    let repo = Repository::open(worktree_path)?;
+    if repo.find_remote(remote).is_err() {
+        return Err(GitError::RemoteNotFound(remote.to_string()));
+    }
+    let upstream = format!("{}/{}", remote, remote_branch);
+    if repo.find_branch(&upstream, BranchType::Remote).is_err() {
+        return Err(GitError::BranchNotFound(upstream));
+    }
+    if repo.head_detached().unwrap_or(true) {
+        return Err(GitError::BranchNotFound("HEAD".to_string()));
+    }
+    drop(repo);
 
     let output = git_command()
-        .args(["push", "origin", &branch])
-        .current_dir(repo_path)
+        .args(["branch", &format!("--set-upstream-to={}", upstream)])
+        .current_dir(worktree_path)
         .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git branch --set-upstream-to failed: {}",
+            stderr.trim()
+        ))));
+    }
+    Ok(())
+}
+
+/// Run a git command that talks to a remote without letting it prompt, and
+/// return its stdout. It runs in its own process group (a job tree on
+/// Windows) and the whole group is killed after `timeout`, so a hung `ssh` or
+/// `git-remote-https` can't outlive it or hold its pipes open. The error
+/// message starts with `context` (e.g. "git fetch origin") and includes
+/// git's stderr, for `NetworkRetry` to classify.
+fn run_remote_with_timeout(
+    repo_path: &Path,
+    args: &[&str],
+    timeout: std::time::Duration,
+    context: &str,
+) -> Result<String, String> {
+    use std::io::Read;
+
+    let mut cmd = git_command();
+    cmd.args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .current_dir(repo_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // BatchMode makes ssh fail instead of asking for a passphrase or host key
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() && std::env::var_os("GIT_SSH").is_none() {
+        cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd.spawn().map_err(|e| format!("{} failed: {}", context, e))?;
+
+    // Drained on the side so a chatty command can't fill a pipe and stall
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut text);
+            }
+            text
+        })
+    };
+    let stdout_reader = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr_reader = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if std::time::Instant::now() < deadline => {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            _ => {
+                kill_process_tree(&mut child);
+                break None;
+            }
+        }
+    };
+    // With the whole group gone the pipes are closed and the readers finish
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    match status {
+        None => Err(format!("{} timed out after {}s", context, timeout.as_secs())),
+        Some(status) if !status.success() => Err(format!("{} failed: {}", context, stderr.trim())),
+        Some(_) => Ok(stdout),
+    }
+}
+
+/// Kill `child` and everything it started
+fn kill_process_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    unsafe {
+        // The child leads its own process group, see `run_remote_with_timeout`
+        libc::kill(-(child.id() as i32), libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Fetch `remote` with the git CLI, so credential helpers and SSH config apply.
+/// Git and ssh never prompt, and an attempt still running after `timeout` is
+/// killed along with its helpers, so a host waiting on a passphrase can't keep
+/// it alive. Transient failures, timeouts included, are retried per `retry`.
+pub fn fetch_remote(
+    repo_path: &Path,
+    remote: &str,
+    timeout: std::time::Duration,
+    retry: &NetworkRetry,
+) -> Result<(), GitError> {
+    if Repository::open(repo_path)?.find_remote(remote).is_err() {
+        return Err(GitError::RemoteNotFound(remote.to_string()));
+    }
+
+    let context = format!("git fetch {}", remote);
+    retry.run("fetch", || run_remote_with_timeout(repo_path, &["fetch", remote], timeout, &context).map(drop))
+}
+
+/// Delete remote-tracking refs of `remote` whose branch no longer exists there.
+/// Returns the pruned refs, e.g. "origin/feature". Like `fetch_remote`, each
+/// attempt contacts the remote and is killed after `timeout`.
+pub fn prune_remote_refs(
+    repo_path: &Path,
+    remote: &str,
+    timeout: std::time::Duration,
+    retry: &NetworkRetry,
+) -> Result<Vec<String>, GitError> {
+    if Repository::open(repo_path)?.find_remote(remote).is_err() {
+        return Err(GitError::RemoteNotFound(remote.to_string()));
+    }
+
+    let context = format!("git remote prune {}", remote);
+    let stdout = retry.run("prune", || run_remote_with_timeout(repo_path, &["remote", "prune", remote], timeout, &context))?;
+    Ok(parse_pruned_refs(&stdout))
+}
 
+/// Refs from ` * [pruned] origin/feature` lines of `git remote prune`
+fn parse_pruned_refs(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* [pruned] "))
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+/// A local branch whose upstream was deleted on the remote
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoneBranch {
+    pub name: String,
+    /// The upstream it still names, e.g. "origin/feature"
+    pub upstream: String,
+    /// Worktree it is checked out in; such a branch can't be deleted yet
+    pub worktree_path: Option<String>,
+}
+
+/// Local branches whose upstream is gone, the `[gone]` entries of
+/// `git branch -vv`. Upstreams only disappear once the remote-tracking ref is
+/// pruned, so run this after `prune_remote_refs` or a pruning fetch.
+pub fn list_gone_branches(repo_path: &Path) -> Result<Vec<GoneBranch>, GitError> {
+    let output = git_command()
+        .args([
+            "for-each-ref",
+            "--format=%(refname:short)%00%(upstream:short)%00%(upstream:track)",
+            "refs/heads",
+        ])
+        .current_dir(repo_path)
+        .output()?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(GitError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("git push origin {} failed: {}", branch, stderr),
-        )));
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git for-each-ref failed: {}",
+            stderr.trim()
+        ))));
     }
 
-    Ok(())
+    let occupied: HashMap<String, String> = list_registered_worktrees(repo_path)?
+        .into_iter()
+        .filter_map(|w| Some((w.branch?, w.path)))
+        .collect();
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let (name, upstream, track) = (fields.next()?, fields.next()?, fields.next()?);
+            (!upstream.is_empty() && track == "[gone]").then(|| GoneBranch {
+                name: name.to_string(),
+                upstream: upstream.to_string(),
+                worktree_path: occupied.get(name).cloned(),
+            })
+        })
+        .collect())
+}
+
+pub fn push_default_branch(repo_path: &Path, retry: &NetworkRetry) -> Result<(), GitError> {
+    let repo = Repository::open(repo_path)?;
+    let branch = get_default_branch(&repo)?;
+
+    let context = format!("git push origin {} failed", branch);
+    retry.run("push", || run_remote_command(repo_path, &["push", "origin", &branch], &context))
 }
 
 /// Result of checking merge feasibility
@@ -215,6 +982,121 @@ pub struct MergeFeasibility {
     pub target_branch: String,
     /// Error message if any
     pub error: Option<String>,
+    /// Every reason the merge can't proceed; empty exactly when `can_merge`
+    pub blockers: Vec<MergeBlocker>,
+    /// Problems that only matter when pushing after the merge (`BehindRemote`);
+    /// they don't clear `can_merge`
+    pub warnings: Vec<MergeBlocker>,
+}
+
+/// A reason `check_merge_feasibility` refuses a merge, with what the UI
+/// needs to explain it. Serialized as `{ "kind": "uncommittedChanges", ... }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum MergeBlocker {
+    /// The worktree isn't on a branch
+    DetachedHead,
+    /// The worktree is on the branch it would merge into
+    OnTargetBranch { branch: String },
+    TargetBranchMissing { branch: String },
+    UncommittedChanges { file_count: usize },
+    /// The branch has no commits the target lacks
+    NothingToMerge { target_branch: String },
+    /// The local target lags its upstream, so a push after merging would be rejected
+    BehindRemote { branch: String, upstream: String, count: usize },
+    /// Git stopped in the middle of a merge, rebase, cherry-pick, etc.
+    OperationInProgress { operation: String },
+    /// A merge/cleanup/delete workflow is already running for the worktree
+    WorkflowInProgress { workflow: String },
+}
+
+/// What `check_merge_feasibility` found out about a worktree, before it is
+/// turned into blockers
+#[derive(Debug, Clone, Default)]
+pub struct MergeFacts {
+    /// None on a detached HEAD
+    pub current_branch: Option<String>,
+    pub target_branch: String,
+    pub target_exists: bool,
+    pub dirty_files: usize,
+    pub commits_ahead: usize,
+    pub commits_behind: usize,
+    pub can_fast_forward: bool,
+    /// The target's upstream and how many of its commits the local target lacks
+    pub target_behind_upstream: Option<(String, usize)>,
+    pub operation_in_progress: Option<String>,
+    /// Name of the shellflow workflow running for the worktree ("merge", ...)
+    pub workflow_in_progress: Option<String>,
+    /// Why a rebase would use the local target instead of `origin/<target>`
+    pub rebase_warning: Option<String>,
+}
+
+/// Everything in `facts` that stops the merge, in the order the UI should
+/// mention them
+pub fn merge_blockers(facts: &MergeFacts) -> Vec<MergeBlocker> {
+    let mut blockers = Vec::new();
+    if let Some(workflow) = &facts.workflow_in_progress {
+        blockers.push(MergeBlocker::WorkflowInProgress { workflow: workflow.clone() });
+    }
+    if let Some(operation) = &facts.operation_in_progress {
+        blockers.push(MergeBlocker::OperationInProgress { operation: operation.clone() });
+    }
+    let on_target = facts.current_branch.as_deref() == Some(facts.target_branch.as_str());
+    match &facts.current_branch {
+        None => blockers.push(MergeBlocker::DetachedHead),
+        Some(branch) if on_target => blockers.push(MergeBlocker::OnTargetBranch { branch: branch.clone() }),
+        Some(_) => {}
+    }
+    if !facts.target_exists {
+        blockers.push(MergeBlocker::TargetBranchMissing { branch: facts.target_branch.clone() });
+    }
+    if facts.dirty_files > 0 {
+        blockers.push(MergeBlocker::UncommittedChanges { file_count: facts.dirty_files });
+    }
+    // Ahead/behind are only meaningful against an existing, different branch
+    if facts.target_exists && !on_target && facts.commits_ahead == 0 {
+        blockers.push(MergeBlocker::NothingToMerge { target_branch: facts.target_branch.clone() });
+    }
+    blockers
+}
+
+/// What in `facts` would only fail a push after the merge. A local-only merge
+/// goes ahead regardless.
+pub fn merge_warnings(facts: &MergeFacts) -> Vec<MergeBlocker> {
+    match &facts.target_behind_upstream {
+        Some((upstream, count)) if *count > 0 => vec![MergeBlocker::BehindRemote {
+            branch: facts.target_branch.clone(),
+            upstream: upstream.clone(),
+            count: *count,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+impl MergeFeasibility {
+    pub fn from_facts(facts: MergeFacts) -> Self {
+        let blockers = merge_blockers(&facts);
+        let warnings = merge_warnings(&facts);
+        let error = blockers.iter().find_map(|blocker| match blocker {
+            MergeBlocker::OnTargetBranch { branch } => Some(format!("Already on the target branch {}", branch)),
+            MergeBlocker::TargetBranchMissing { branch } => Some(format!("Branch not found: {}", branch)),
+            _ => None,
+        })
+        .or(facts.rebase_warning);
+        MergeFeasibility {
+            can_merge: blockers.is_empty(),
+            has_uncommitted_changes: facts.dirty_files > 0,
+            is_up_to_date: facts.commits_ahead == 0,
+            can_fast_forward: facts.can_fast_forward,
+            commits_ahead: facts.commits_ahead,
+            commits_behind: facts.commits_behind,
+            current_branch: facts.current_branch.unwrap_or_else(|| "HEAD".to_string()),
+            target_branch: facts.target_branch,
+            error,
+            blockers,
+            warnings,
+        }
+    }
 }
 
 /// Status information for worktree deletion
@@ -296,10 +1178,114 @@ pub fn list_registered_worktrees(repo_path: &Path) -> Result<Vec<DiscoveredWorkt
     Ok(parse_worktree_list_porcelain(&stdout))
 }
 
+/// A branch for the branch picker, from `list_branches`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchEntry {
+    /// e.g. "feature/x", or "origin/feature/x" for a remote-tracking branch
+    pub name: String,
+    pub is_remote: bool,
+    /// Checked out in `repo_path` itself
+    pub is_current: bool,
+    /// Checked out in some worktree (the main checkout included), so a new
+    /// worktree can't use it
+    pub is_checked_out_in_worktree: bool,
+    pub worktree_path: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits ahead of / behind `upstream`; 0 without one
+    pub ahead: usize,
+    pub behind: usize,
+    /// Unix seconds of the branch's latest commit
+    pub last_commit_time: i64,
+}
+
+/// Local branches, plus remote-tracking ones with `include_remote`, most
+/// recently committed first. Occupancy comes from `git worktree list`.
+pub fn list_branches(repo_path: &Path, include_remote: bool) -> Result<Vec<BranchEntry>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let occupied: HashMap<String, String> = list_registered_worktrees(repo_path)?
+        .into_iter()
+        .filter_map(|w| Some((w.branch?, w.path)))
+        .collect();
+    let current = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+
+    let filter = if include_remote { None } else { Some(BranchType::Local) };
+    let mut entries = Vec::new();
+    for branch in repo.branches(filter)? {
+        let (branch, kind) = branch?;
+        let Some(name) = branch.name()?.map(str::to_string) else {
+            continue;
+        };
+        let is_remote = kind == BranchType::Remote;
+        // `origin/HEAD` only points at another remote branch
+        if is_remote && branch.get().symbolic_target().is_some() {
+            continue;
+        }
+        let Ok(tip) = branch.get().peel_to_commit() else {
+            continue;
+        };
+
+        let (upstream, ahead, behind) = match branch.upstream().ok().filter(|_| !is_remote) {
+            Some(upstream) => {
+                let (ahead, behind) = match upstream.get().target() {
+                    Some(target) => repo.graph_ahead_behind(tip.id(), target)?,
+                    None => (0, 0),
+                };
+                (upstream.name()?.map(str::to_string), ahead, behind)
+            }
+            None => (None, 0, 0),
+        };
+        let worktree_path = if is_remote { None } else { occupied.get(&name).cloned() };
+
+        entries.push(BranchEntry {
+            is_current: !is_remote && current.as_ref() == Some(&name),
+            is_checked_out_in_worktree: worktree_path.is_some(),
+            worktree_path,
+            upstream,
+            ahead,
+            behind,
+            last_commit_time: tip.time().seconds(),
+            is_remote,
+            name,
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        a.is_remote
+            .cmp(&b.is_remote)
+            .then(b.last_commit_time.cmp(&a.last_commit_time))
+            .then(a.name.cmp(&b.name))
+    });
+    Ok(entries)
+}
+
 pub fn is_git_repo(path: &Path) -> bool {
     Repository::open(path).is_ok()
 }
 
+/// Smallest root commit reachable from HEAD, identifying a repository across
+/// moves. None for a repository without commits.
+pub fn root_commit(repo_path: &Path) -> Result<Option<String>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    if repo.head().is_err() {
+        return Ok(None);
+    }
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    let mut root: Option<git2::Oid> = None;
+    for oid in walk {
+        let oid = oid?;
+        if repo.find_commit(oid)?.parent_count() == 0 && root.is_none_or(|r| oid < r) {
+            root = Some(oid);
+        }
+    }
+    Ok(root.map(|oid| oid.to_string()))
+}
+
 pub fn get_repo_name(path: &Path) -> String {
     path.file_name()
         .and_then(|n| n.to_str())
@@ -339,6 +1325,23 @@ pub fn get_current_branch(repo: &Repository) -> Result<String, GitError> {
         })
 }
 
+/// `name` if it is a local branch, BranchNotFound otherwise
+fn existing_local_branch(repo: &Repository, name: &str) -> Result<String, GitError> {
+    if repo.find_branch(name, BranchType::Local).is_err() {
+        return Err(GitError::BranchNotFound(name.to_string()));
+    }
+    Ok(name.to_string())
+}
+
+/// Branch the merge workflow lands in: `target_branch` when given (and it
+/// exists), otherwise the default branch
+pub fn merge_target_branch(repo: &Repository, target_branch: Option<&str>) -> Result<String, GitError> {
+    match target_branch {
+        Some(name) => existing_local_branch(repo, name),
+        None => get_default_branch(repo),
+    }
+}
+
 /// Resolve a BaseBranch config to an actual branch name
 pub fn resolve_target_branch(repo: &Repository, base_branch: &BaseBranch) -> Result<String, GitError> {
     match base_branch {
@@ -354,6 +1357,122 @@ pub fn resolve_target_branch(repo: &Repository, base_branch: &BaseBranch) -> Res
     }
 }
 
+/// Ref a rebase onto `branch` starts from. With `onto_remote` that is
+/// `origin/<branch>`; when there is no origin remote, or origin has no such
+/// branch, it falls back to the local branch along with a warning saying so.
+pub fn rebase_base(repo: &Repository, branch: &str, onto_remote: bool) -> (String, Option<String>) {
+    if !onto_remote {
+        return (branch.to_string(), None);
+    }
+    if repo.find_remote("origin").is_err() {
+        return (branch.to_string(), Some(format!("No origin remote; rebasing onto local {}", branch)));
+    }
+    let remote = format!("origin/{}", branch);
+    if repo.find_branch(&remote, BranchType::Remote).is_err() {
+        return (branch.to_string(), Some(format!("{} not found; rebasing onto local {}", remote, branch)));
+    }
+    (remote, None)
+}
+
+/// Whether branch refs in this repository collide when names differ only by case
+/// (the usual macOS/Windows filesystems). Probed once per repository by creating
+/// a file in the git dir and looking it up with a different case.
+pub fn refs_case_insensitive(repo_path: &Path) -> bool {
+    let Ok(repo) = Repository::open(repo_path) else {
+        return false;
+    };
+    // Linked worktrees keep branch refs in the main repository's git dir
+    let git_dir = repo.path().to_path_buf();
+    let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(dir) => git_dir.join(dir.trim()),
+        Err(_) => git_dir,
+    };
+    if let Some(&cached) = CASE_INSENSITIVE_REFS.lock().get(&common_dir) {
+        return cached;
+    }
+
+    let probe = common_dir.join(format!("shellflow-case-probe-{}", uuid::Uuid::new_v4().simple()));
+    let insensitive = match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let upper = common_dir.join(probe.file_name().unwrap().to_string_lossy().to_uppercase());
+            let found = upper.exists();
+            let _ = std::fs::remove_file(&probe);
+            found
+        }
+        // Can't probe (read-only git dir); keep git's own error handling
+        Err(_) => false,
+    };
+    CASE_INSENSITIVE_REFS.lock().insert(common_dir, insensitive);
+    insensitive
+}
+
+/// Names of all local branches
+pub fn local_branch_names(repo_path: &Path) -> Result<Vec<String>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let mut names = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        if let Some(name) = branch?.0.name()? {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// A local branch whose name equals `branch_name` ignoring case but isn't
+/// identical to it
+pub fn find_case_variant_branch(repo_path: &Path, branch_name: &str) -> Result<Option<String>, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let wanted = branch_name.to_lowercase();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            if name != branch_name && name.to_lowercase() == wanted {
+                return Ok(Some(name.to_string()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Check that a new branch can be named `branch_name`. `renaming_from` is the
+/// branch being renamed, which may differ from the new name only by case.
+///
+/// Fails with `BranchAlreadyExists` for an exact duplicate and, when refs are
+/// case-insensitive, `BranchCaseConflict` for a branch differing only by case.
+pub fn ensure_branch_name_available(
+    repo_path: &Path,
+    branch_name: &str,
+    renaming_from: Option<&str>,
+) -> Result<(), GitError> {
+    let case_insensitive = refs_case_insensitive(repo_path);
+    ensure_branch_name_available_with(repo_path, branch_name, renaming_from, case_insensitive)
+}
+
+fn ensure_branch_name_available_with(
+    repo_path: &Path,
+    branch_name: &str,
+    renaming_from: Option<&str>,
+    case_insensitive: bool,
+) -> Result<(), GitError> {
+    let is_case_only_rename = renaming_from
+        .is_some_and(|old| old != branch_name && old.to_lowercase() == branch_name.to_lowercase());
+    // On case-insensitive storage the lookup for a case-only rename finds the old branch itself
+    if !(is_case_only_rename && case_insensitive) && branch_exists(repo_path, branch_name)? {
+        return Err(GitError::BranchAlreadyExists(branch_name.to_string()));
+    }
+    if case_insensitive {
+        if let Some(existing) = find_case_variant_branch(repo_path, branch_name)? {
+            if Some(existing.as_str()) != renaming_from {
+                return Err(GitError::BranchCaseConflict {
+                    requested: branch_name.to_string(),
+                    existing,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Check if a branch with the given name exists
 pub fn branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool, GitError> {
     let repo = Repository::open(repo_path)?;
@@ -361,15 +1480,30 @@ pub fn branch_exists(repo_path: &Path, branch_name: &str) -> Result<bool, GitErr
     Ok(exists)
 }
 
+/// Create a worktree on a new branch `branch_name` cut from `base_branch`.
+///
+/// If `branch_name` already exists, this fails with `BranchAlreadyExists` unless
+/// `adopt_existing_branch` is set, in which case the worktree is created on that
+/// branch instead (`BranchInUse` if another worktree has it checked out).
 pub fn create_worktree(
     repo_path: &Path,
     worktree_path: &Path,
     branch_name: &str,
     base_branch: &BaseBranch,
+    adopt_existing_branch: bool,
 ) -> Result<(), GitError> {
 
     log::info!("[git::create_worktree] Creating worktree at {:?}", worktree_path);
 
+    if branch_exists(repo_path, branch_name)? {
+        if !adopt_existing_branch {
+            return Err(GitError::BranchAlreadyExists(branch_name.to_string()));
+        }
+        return add_worktree_for_existing_branch(repo_path, worktree_path, branch_name);
+    }
+    // A branch differing only by case would collide with the new ref on disk
+    ensure_branch_name_available(repo_path, branch_name, None)?;
+
     // Check for modified/staged changes before proceeding using libgit2
     // (read-only operation, no lock issues)
     {
@@ -420,30 +1554,391 @@ pub fn create_worktree(
         )));
     }
 
-    log::info!("[git::create_worktree] Worktree created successfully");
-    Ok(())
+    log::info!("[git::create_worktree] Worktree created successfully");
+    Ok(())
+}
+
+/// Create a worktree on the existing branch `branch_name`, without creating a
+/// branch. A branch only on a remote is checked out as a new local branch
+/// tracking it. Fails with `BranchNotFound` if there is neither, or
+/// `BranchInUse` if another worktree has the branch checked out.
+pub fn create_worktree_for_branch(repo_path: &Path, worktree_path: &Path, branch_name: &str) -> Result<(), GitError> {
+    log::info!("[git::create_worktree_for_branch] Creating worktree at {:?} on {}", worktree_path, branch_name);
+
+    if branch_exists(repo_path, branch_name)? {
+        return add_worktree_for_existing_branch(repo_path, worktree_path, branch_name);
+    }
+
+    let remote_branch = {
+        let repo = Repository::open(repo_path)?;
+        let found = repo
+            .branches(Some(BranchType::Remote))?
+            .filter_map(|b| b.ok())
+            .filter_map(|(branch, _)| branch.name().ok().flatten().map(str::to_string))
+            .find(|name| name.split_once('/').is_some_and(|(_, name)| name == branch_name));
+        found.ok_or_else(|| GitError::BranchNotFound(branch_name.to_string()))?
+    };
+
+    log::info!("[git::create_worktree_for_branch] Tracking remote branch: {}", remote_branch);
+    let output = git_command()
+        .args(["worktree", "add", "--track", "-b", branch_name, &worktree_path.to_string_lossy(), &remote_branch])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git worktree add failed: {}",
+            stderr
+        ))));
+    }
+    Ok(())
+}
+
+fn add_worktree_for_existing_branch(
+    repo_path: &Path,
+    worktree_path: &Path,
+    branch_name: &str,
+) -> Result<(), GitError> {
+    if let Some(existing) = list_registered_worktrees(repo_path)?
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch_name))
+    {
+        return Err(GitError::BranchInUse {
+            branch: branch_name.to_string(),
+            worktree_path: existing.path,
+        });
+    }
+
+    log::info!("[git::create_worktree] Adopting existing branch: {}", branch_name);
+    let output = git_command()
+        .args(["worktree", "add", &worktree_path.to_string_lossy(), branch_name])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git worktree add failed: {}",
+            stderr
+        ))));
+    }
+
+    log::info!("[git::create_worktree] Worktree created successfully");
+    Ok(())
+}
+
+pub fn delete_worktree(repo_path: &Path, worktree_name: &str) -> Result<(), GitError> {
+    let repo = Repository::open(repo_path)?;
+
+    // Find and prune the worktree
+    if let Ok(worktree) = repo.find_worktree(worktree_name) {
+        // Remove the worktree directory first
+        if let Ok(wt_path) = worktree.path().canonicalize() {
+            let _ = std::fs::remove_dir_all(&wt_path);
+        }
+
+        // Prune the worktree reference
+        worktree.prune(Some(
+            git2::WorktreePruneOptions::new()
+                .working_tree(true)
+                .valid(true)
+                .locked(false),
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Run `git worktree prune` to drop registrations whose directories are gone
+pub fn prune_worktrees(repo_path: &Path) -> Result<(), GitError> {
+    let output = git_command()
+        .args(["worktree", "prune"])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git worktree prune failed: {}",
+            stderr
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Run `git worktree repair` for `worktree_paths`, reconnecting worktrees and
+/// the repository after either side moved on disk
+pub fn repair_worktrees(repo_path: &Path, worktree_paths: &[PathBuf]) -> Result<(), GitError> {
+    let output = git_command()
+        .args(["worktree", "repair"])
+        .args(worktree_paths)
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git worktree repair failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Totals over a worktree's uncommitted changes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl ChangeSummary {
+    pub fn from_files(files: &[FileChange]) -> Self {
+        files.iter().fold(Self::default(), |acc, f| Self {
+            files: acc.files + 1,
+            insertions: acc.insertions + f.insertions.unwrap_or(0),
+            deletions: acc.deletions + f.deletions.unwrap_or(0),
+        })
+    }
+}
+
+/// Author identities keyed by (name, email)
+type AuthorCache = HashMap<(String, String), AuthorIdentity>;
+
+/// Number of avatar colors the frontend palette provides
+pub const AUTHOR_COLOR_COUNT: u8 = 12;
+
+/// How a commit author is shown in the UI, derived without exposing their email
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorIdentity {
+    /// Stable hash of the author's email (or name, if there's no email)
+    pub author_id: String,
+    pub display_name: String,
+    pub initials: String,
+    /// Index into the frontend's avatar palette, below `AUTHOR_COLOR_COUNT`
+    pub color_index: u8,
+}
+
+/// FNV-1a, chosen because its output is stable across Rust versions and platforms
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Stable id for an author: the same email always maps to the same id, whatever
+/// name or case it was committed with
+pub fn author_id(name: &str, email: &str) -> String {
+    let key = if email.trim().is_empty() { name } else { email };
+    format!("{:016x}", fnv1a(key.trim().to_lowercase().as_bytes()))
+}
+
+/// Name to show for an author, falling back to the email's local part
+pub fn author_display_name(name: &str, email: &str) -> String {
+    let name = name.trim();
+    if !name.is_empty() {
+        return name.to_string();
+    }
+    match email.trim().split('@').next() {
+        Some(local) if !local.is_empty() => local.to_string(),
+        _ => "Unknown".to_string(),
+    }
+}
+
+/// Up to two initials: first and last word of the display name, or just the
+/// first character for single-word names
+pub fn author_initials(display_name: &str) -> String {
+    let words: Vec<&str> = display_name
+        .split(|c: char| c.is_whitespace() || matches!(c, '.' | '-' | '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let first_char = |word: &str| word.chars().next().map(|c| c.to_uppercase().collect::<String>());
+    match words.as_slice() {
+        [] => "?".to_string(),
+        [only] => first_char(only).unwrap_or_default(),
+        [first, .., last] => [first_char(first), first_char(last)].into_iter().flatten().collect(),
+    }
+}
+
+/// Palette index derived from an author id so each author keeps their color
+pub fn author_color_index(author_id: &str) -> u8 {
+    (fnv1a(author_id.as_bytes()) % AUTHOR_COLOR_COUNT as u64) as u8
+}
+
+fn derive_author_identity(name: &str, email: &str) -> AuthorIdentity {
+    let author_id = author_id(name, email);
+    let display_name = author_display_name(name, email);
+    AuthorIdentity {
+        initials: author_initials(&display_name),
+        color_index: author_color_index(&author_id),
+        author_id,
+        display_name,
+    }
+}
+
+/// Author identity for a commit, cached so long commit lists derive each
+/// author once
+pub fn author_identity(name: &str, email: &str) -> AuthorIdentity {
+    let key = (name.to_string(), email.to_string());
+    let mut cache = AUTHOR_IDENTITIES.lock();
+    if let Some(identity) = cache.get(&key) {
+        return identity.clone();
+    }
+    if cache.len() > 1024 {
+        cache.clear();
+    }
+    let identity = derive_author_identity(name, email);
+    cache.insert(key, identity.clone());
+    identity
 }
 
-pub fn delete_worktree(repo_path: &Path, worktree_name: &str) -> Result<(), GitError> {
+/// A commit as listed in the UI
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSummary {
+    pub sha: String,
+    pub summary: String,
+    /// Commit time as a Unix timestamp in seconds
+    pub time: i64,
+    pub author: AuthorIdentity,
+    /// Only included when explicitly requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+}
+
+/// The most recent commits reachable from HEAD, newest first
+pub fn recent_commits(repo_path: &Path, limit: usize, include_email: bool) -> Result<Vec<CommitSummary>, GitError> {
     let repo = Repository::open(repo_path)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        let commit = repo.find_commit(oid?)?;
+        let author = commit.author();
+        let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+        let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+        commits.push(CommitSummary {
+            sha: commit.id().to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            time: commit.time().seconds(),
+            author: author_identity(&name, &email),
+            author_email: include_email.then_some(email),
+        });
+    }
+    Ok(commits)
+}
 
-    // Find and prune the worktree
-    if let Ok(worktree) = repo.find_worktree(worktree_name) {
-        // Remove the worktree directory first
-        if let Ok(wt_path) = worktree.path().canonicalize() {
-            let _ = std::fs::remove_dir_all(&wt_path);
-        }
+/// A commit in a worktree's history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    /// Commit time as a Unix timestamp in seconds
+    pub timestamp: i64,
+    pub subject: String,
+    /// Message after the subject line, if any
+    pub body: Option<String>,
+}
 
-        // Prune the worktree reference
-        worktree.prune(Some(
-            git2::WorktreePruneOptions::new()
-                .working_tree(true)
-                .valid(true)
-                .locked(false),
-        ))?;
+/// Commits reachable from HEAD, newest first, skipping the first `skip`.
+/// A repository without commits has an empty log.
+pub fn get_commit_log(worktree_path: &Path, limit: usize, skip: usize) -> Result<Vec<CommitInfo>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    if matches!(repo.head(), Err(e) if e.code() == git2::ErrorCode::UnbornBranch) {
+        return Ok(Vec::new());
     }
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    // Topological first, so commits made within the same second keep their order
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    revwalk
+        .skip(skip)
+        .take(limit)
+        .map(|oid| commit_info(&repo.find_commit(oid?)?))
+        .collect()
+}
 
-    Ok(())
+/// Commits on the worktree's branch that the base branch doesn't have, newest
+/// first. Like `get_branch_info`, a worktree on the base branch has none.
+pub fn get_branch_commit_log(
+    worktree_path: &Path,
+    base_branch: &BaseBranch,
+    limit: usize,
+) -> Result<Vec<CommitInfo>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    if matches!(repo.head(), Err(e) if e.code() == git2::ErrorCode::UnbornBranch) {
+        return Ok(Vec::new());
+    }
+    let base = resolve_target_branch(&repo, base_branch)?;
+    if get_current_branch(&repo)? == base {
+        return Ok(Vec::new());
+    }
+    let base_commit = repo.revparse_single(&base)?.peel_to_commit()?;
+
+    // Same as `git log <base>..HEAD`
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.hide(base_commit.id())?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk
+        .take(limit)
+        .map(|oid| commit_info(&repo.find_commit(oid?)?))
+        .collect()
+}
+
+/// The commit HEAD points at, if there is one yet
+pub fn head_commit_id(worktree_path: &Path) -> Option<String> {
+    let repo = Repository::open(worktree_path).ok()?;
+    let id = repo.head().ok()?.peel_to_commit().ok()?.id();
+    Some(id.to_string())
+}
+
+/// Compare HEAD with `last_seen` and remember it. Returns the new HEAD commit
+/// when HEAD advanced to a descendant (a commit, fast-forward or pull); a
+/// reset, amend or checkout elsewhere is recorded but not returned.
+pub fn head_advanced(worktree_path: &Path, last_seen: &mut Option<String>) -> Result<Option<CommitInfo>, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let Ok(head) = repo.head().and_then(|h| h.peel_to_commit()) else {
+        *last_seen = None;
+        return Ok(None);
+    };
+    let current = head.id().to_string();
+    let previous = last_seen.replace(current.clone());
+    let advanced = match previous {
+        None => true,
+        Some(previous) if previous == current => false,
+        Some(previous) => git2::Oid::from_str(&previous)
+            .ok()
+            .is_some_and(|old| repo.graph_descendant_of(head.id(), old).unwrap_or(false)),
+    };
+    if !advanced {
+        return Ok(None);
+    }
+    commit_info(&head).map(Some)
+}
+
+fn commit_info(commit: &git2::Commit) -> Result<CommitInfo, GitError> {
+    let author = commit.author();
+    let short_hash = commit.as_object().short_id()?.as_str().unwrap_or_default().to_string();
+    Ok(CommitInfo {
+        hash: commit.id().to_string(),
+        short_hash,
+        author: String::from_utf8_lossy(author.name_bytes()).to_string(),
+        email: String::from_utf8_lossy(author.email_bytes()).to_string(),
+        timestamp: commit.time().seconds(),
+        subject: commit.summary().unwrap_or("").to_string(),
+        body: commit.body().map(|b| b.trim_end().to_string()).filter(|b| !b.is_empty()),
+    })
 }
 
 pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitError> {
@@ -535,6 +2030,7 @@ pub fn get_changed_files(worktree_path: &Path) -> Result<Vec<FileChange>, GitErr
                 status: file_status,
                 insertions: if insertions > 0 || deletions > 0 { Some(insertions) } else { None },
                 deletions: if insertions > 0 || deletions > 0 { Some(deletions) } else { None },
+                hidden: false,
             });
         }
     }
@@ -581,6 +2077,17 @@ pub fn get_branch_info(worktree_path: &Path, base_branch: &BaseBranch) -> Result
     })
 }
 
+/// Flag changed files matching the project's `changes.hidePatterns`. Nothing is
+/// removed, so counts and merge checks still see every change.
+pub fn mark_hidden(files: &mut [FileChange], hide: &crate::path_utils::PathMatcher) {
+    if hide.is_empty() {
+        return;
+    }
+    for file in files {
+        file.hidden = hide.matches(&file.path);
+    }
+}
+
 /// Get files changed between the working tree and the base branch
 /// This includes committed changes (not in base), uncommitted changes, AND untracked files
 pub fn get_branch_changed_files(
@@ -675,6 +2182,7 @@ pub fn get_branch_changed_files(
                 status,
                 insertions: if insertions > 0 || deletions > 0 { Some(insertions) } else { None },
                 deletions: if insertions > 0 || deletions > 0 { Some(deletions) } else { None },
+                hidden: false,
             }
         })
         .collect();
@@ -685,6 +2193,33 @@ pub fn get_branch_changed_files(
     Ok(changes)
 }
 
+/// Totals of a worktree's changes against its base branch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub files_changed: usize,
+    pub total_insertions: usize,
+    pub total_deletions: usize,
+}
+
+/// Totals of what the changes panel lists for a worktree: the files from
+/// `get_branch_changed_files` that `hide` doesn't match. Untracked files count
+/// as changed files but, as in the panel, without line counts.
+pub fn get_diff_stats(
+    worktree_path: &Path,
+    base_branch: &BaseBranch,
+    hide: &crate::path_utils::PathMatcher,
+) -> Result<DiffStats, GitError> {
+    let mut files = get_branch_changed_files(worktree_path, base_branch)?;
+    mark_hidden(&mut files, hide);
+    Ok(files.iter().filter(|f| !f.hidden).fold(DiffStats::default(), |mut stats, f| {
+        stats.files_changed += 1;
+        stats.total_insertions += f.insertions.unwrap_or(0);
+        stats.total_deletions += f.deletions.unwrap_or(0);
+        stats
+    }))
+}
+
 /// Get file content at a specific git ref (branch, commit, HEAD)
 pub fn get_file_at_ref(
     repo_path: &Path,
@@ -714,6 +2249,53 @@ pub fn get_working_file(repo_path: &Path, file_path: &str) -> Result<String, Git
     std::fs::read_to_string(&full_path).map_err(GitError::Io)
 }
 
+/// Both sides of a file's diff for the diff viewer, from `get_file_sides`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSides {
+    /// Empty when the file was added since `base_ref`
+    pub old_content: String,
+    /// Empty when the file was deleted from the working tree
+    pub new_content: String,
+    pub language: String,
+    /// A NUL byte on either side; both contents are left empty
+    pub binary: bool,
+}
+
+/// Like git, a NUL byte within the first 8000 bytes means binary
+fn looks_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|&b| b == 0)
+}
+
+/// `file_path` at `base_ref` and in the working tree. A side where the file
+/// doesn't exist is empty; an unknown `base_ref` is an error.
+pub fn get_file_sides(worktree_path: &Path, file_path: &str, base_ref: &str) -> Result<FileSides, GitError> {
+    let commit = Repository::open(worktree_path)?
+        .revparse_single(base_ref)?
+        .peel_to_commit()?
+        .id()
+        .to_string();
+    let old_content = get_file_at_ref(worktree_path, file_path, &commit).unwrap_or_default();
+    let new_content = match std::fs::read(worktree_path.join(file_path)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(GitError::Io(e)),
+    };
+
+    let binary = looks_binary(old_content.as_bytes()) || looks_binary(&new_content);
+    let (old_content, new_content) = if binary {
+        (String::new(), String::new())
+    } else {
+        (old_content, String::from_utf8_lossy(&new_content).into_owned())
+    };
+    Ok(FileSides {
+        old_content,
+        new_content,
+        language: detect_language(file_path),
+        binary,
+    })
+}
+
 /// Detect programming language from file extension
 pub fn detect_language(file_path: &str) -> String {
     let ext = std::path::Path::new(file_path)
@@ -750,6 +2332,335 @@ pub fn detect_language(file_path: &str) -> String {
     .to_string()
 }
 
+/// Files (and optionally bytes) of one language among a repository's tracked files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStat {
+    pub language: String,
+    pub files: usize,
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStats {
+    /// Commit the stats were computed at; None for a repository without commits
+    pub head: Option<String>,
+    pub total_files: usize,
+    pub total_bytes: Option<u64>,
+    /// Most files first
+    pub languages: Vec<LanguageStat>,
+}
+
+struct CachedLanguageStats {
+    head: String,
+    include_bytes: bool,
+    stats: LanguageStats,
+}
+
+/// Group tracked files by `detect_language`. `sizes`, when given, lines up with `paths`.
+pub fn tally_languages(paths: &[String], sizes: Option<&[u64]>) -> Vec<LanguageStat> {
+    let mut by_language: HashMap<String, (usize, u64)> = HashMap::new();
+    for (i, path) in paths.iter().enumerate() {
+        let entry = by_language.entry(detect_language(path)).or_default();
+        entry.0 += 1;
+        entry.1 += sizes.and_then(|s| s.get(i)).copied().unwrap_or(0);
+    }
+    let mut languages: Vec<LanguageStat> = by_language
+        .into_iter()
+        .map(|(language, (files, bytes))| LanguageStat {
+            language,
+            files,
+            bytes: sizes.map(|_| bytes),
+        })
+        .collect();
+    languages.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.language.cmp(&b.language)));
+    languages
+}
+
+fn tracked_files(repo_path: &Path) -> Result<Vec<String>, GitError> {
+    let output = git_command()
+        .args(["ls-files", "-z"])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git ls-files failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// Language breakdown of the files tracked in `repo_path`, cached until HEAD
+/// moves. Byte sizes come from the working tree and are only read when asked for.
+pub fn language_stats(repo_path: &Path, include_bytes: bool) -> Result<LanguageStats, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|h| h.target())
+        .map(|oid| oid.to_string());
+    let key = repo_path.to_path_buf();
+
+    if let Some(head) = &head {
+        if let Some(cached) = LANGUAGE_STATS.lock().get(&key) {
+            if &cached.head == head && cached.include_bytes == include_bytes {
+                return Ok(cached.stats.clone());
+            }
+        }
+    }
+
+    let paths = tracked_files(repo_path)?;
+    let sizes: Option<Vec<u64>> = include_bytes.then(|| {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(repo_path.join(path)).map(|m| m.len()).unwrap_or(0))
+            .collect()
+    });
+    let stats = LanguageStats {
+        head: head.clone(),
+        total_files: paths.len(),
+        total_bytes: sizes.as_ref().map(|s| s.iter().sum()),
+        languages: tally_languages(&paths, sizes.as_deref()),
+    };
+
+    if let Some(head) = head {
+        LANGUAGE_STATS.lock().insert(
+            key,
+            CachedLanguageStats {
+                head,
+                include_bytes,
+                stats: stats.clone(),
+            },
+        );
+    }
+    Ok(stats)
+}
+
+/// How far a worktree's HEAD has moved from its base branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseDrift {
+    pub base_branch: String,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Unix seconds of the HEAD commit
+    pub head_time: i64,
+}
+
+/// Commits ahead of and behind the base branch. Counts are cached per commit
+/// pair, so repeated calls only walk history once HEAD or the base moves.
+pub fn base_drift(worktree_path: &Path, base_branch: &BaseBranch) -> Result<BaseDrift, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let base = resolve_target_branch(&repo, base_branch)?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let base_commit = repo
+        .find_branch(&base, BranchType::Local)
+        .map_err(|_| GitError::BranchNotFound(base.clone()))?
+        .get()
+        .peel_to_commit()?;
+
+    let key = (head_commit.id(), base_commit.id());
+    let cached = AHEAD_BEHIND.lock().get(&key).copied();
+    let (ahead, behind) = match cached {
+        Some(counts) => counts,
+        None => {
+            let counts = repo.graph_ahead_behind(key.0, key.1)?;
+            let mut cache = AHEAD_BEHIND.lock();
+            if cache.len() > 1024 {
+                cache.clear();
+            }
+            cache.insert(key, counts);
+            counts
+        }
+    };
+
+    Ok(BaseDrift {
+        base_branch: base,
+        ahead,
+        behind,
+        head_time: head_commit.time().seconds(),
+    })
+}
+
+/// Branch headers of `git status --porcelain=v2 --branch`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusBranch {
+    /// HEAD commit; None before the first commit
+    pub oid: Option<String>,
+    /// Checked-out branch; None when detached
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    /// Only known when an upstream is set and exists
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusEntryKind {
+    Ordinary,
+    Renamed,
+    Copied,
+    Unmerged,
+    Untracked,
+    Ignored,
+}
+
+/// Submodule state from the `<sub>` field (`S<c><m><u>`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleState {
+    pub commit_changed: bool,
+    pub tracked_changes: bool,
+    pub untracked_changes: bool,
+}
+
+/// One path from porcelain v2 status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusEntry {
+    pub kind: StatusEntryKind,
+    /// Two-letter XY code (`.` = unchanged); "??" or "!!" for untracked or ignored
+    pub xy: String,
+    pub path: String,
+    /// Source path of a rename or copy
+    pub orig_path: Option<String>,
+    /// Rename/copy similarity percentage
+    pub score: Option<u8>,
+    pub submodule: Option<SubmoduleState>,
+    /// Octal file modes in HEAD, the index, and the worktree
+    pub modes: Vec<String>,
+    /// Object names in HEAD and the index (stages 1-3 for unmerged entries)
+    pub oids: Vec<String>,
+}
+
+/// Parsed `git status --porcelain=v2 --branch --show-stash`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusV2 {
+    pub branch: StatusBranch,
+    pub stash_count: usize,
+    pub entries: Vec<StatusEntry>,
+}
+
+fn parse_submodule(field: &str) -> Option<SubmoduleState> {
+    let flags = field.strip_prefix('S')?.as_bytes();
+    (flags.len() == 3).then(|| SubmoduleState {
+        commit_changed: flags[0] == b'C',
+        tracked_changes: flags[1] == b'M',
+        untracked_changes: flags[2] == b'U',
+    })
+}
+
+/// Split `line` into `n` space-separated fields, the last keeping any spaces (the path)
+fn status_fields(line: &str, n: usize) -> Option<Vec<&str>> {
+    let fields: Vec<&str> = line.splitn(n, ' ').collect();
+    (fields.len() == n).then_some(fields)
+}
+
+/// Parse NUL-separated (`-z`) porcelain v2 output. Unrecognized records are skipped.
+pub fn parse_status_v2(output: &str) -> StatusV2 {
+    let mut status = StatusV2::default();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" => status.branch.oid = (value != "(initial)").then(|| value.to_string()),
+                "branch.head" => status.branch.head = (value != "(detached)").then(|| value.to_string()),
+                "branch.upstream" => status.branch.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    let mut counts = value.split(' ');
+                    status.branch.ahead = counts.next().and_then(|a| a.trim_start_matches('+').parse().ok());
+                    status.branch.behind = counts.next().and_then(|b| b.trim_start_matches('-').parse().ok());
+                }
+                "stash" => status.stash_count = value.parse().unwrap_or(0),
+                _ => {}
+            }
+            continue;
+        }
+
+        let entry = match record.split_at(record.len().min(2)) {
+            ("1 ", rest) => status_fields(rest, 8).map(|f| StatusEntry {
+                kind: StatusEntryKind::Ordinary,
+                xy: f[0].to_string(),
+                path: f[7].to_string(),
+                orig_path: None,
+                score: None,
+                submodule: parse_submodule(f[1]),
+                modes: f[2..5].iter().map(|m| m.to_string()).collect(),
+                oids: f[5..7].iter().map(|o| o.to_string()).collect(),
+            }),
+            ("2 ", rest) => status_fields(rest, 9).map(|f| {
+                let kind = if f[7].starts_with('C') { StatusEntryKind::Copied } else { StatusEntryKind::Renamed };
+                StatusEntry {
+                    kind,
+                    xy: f[0].to_string(),
+                    path: f[8].to_string(),
+                    // With -z the source path is the next record
+                    orig_path: records.next().map(|p| p.to_string()),
+                    score: f[7][1..].parse().ok(),
+                    submodule: parse_submodule(f[1]),
+                    modes: f[2..5].iter().map(|m| m.to_string()).collect(),
+                    oids: f[5..7].iter().map(|o| o.to_string()).collect(),
+                }
+            }),
+            ("u ", rest) => status_fields(rest, 10).map(|f| StatusEntry {
+                kind: StatusEntryKind::Unmerged,
+                xy: f[0].to_string(),
+                path: f[9].to_string(),
+                orig_path: None,
+                score: None,
+                submodule: parse_submodule(f[1]),
+                modes: f[2..6].iter().map(|m| m.to_string()).collect(),
+                oids: f[6..9].iter().map(|o| o.to_string()).collect(),
+            }),
+            (prefix @ ("? " | "! "), path) => Some(StatusEntry {
+                kind: if prefix == "? " { StatusEntryKind::Untracked } else { StatusEntryKind::Ignored },
+                xy: prefix[..1].repeat(2),
+                path: path.to_string(),
+                orig_path: None,
+                score: None,
+                submodule: None,
+                modes: Vec::new(),
+                oids: Vec::new(),
+            }),
+            _ => None,
+        };
+        status.entries.extend(entry);
+    }
+    status
+}
+
+/// Full-fidelity status (rename sources and scores, submodule state, stash
+/// count, upstream tracking) for clients that need more than `get_changed_files`
+pub fn status_v2(worktree_path: &Path) -> Result<StatusV2, GitError> {
+    let output = git_command()
+        .args(["status", "--porcelain=v2", "--branch", "--show-stash", "-z"])
+        .current_dir(worktree_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git status failed: {}",
+            stderr.trim()
+        ))));
+    }
+
+    Ok(parse_status_v2(&String::from_utf8_lossy(&output.stdout)))
+}
+
 /// Get list of files with merge conflicts in the worktree.
 pub fn get_conflicted_files(worktree_path: &Path) -> Result<Vec<String>, GitError> {
 
@@ -773,6 +2684,161 @@ pub fn get_conflicted_files(worktree_path: &Path) -> Result<Vec<String>, GitErro
     Ok(conflicts)
 }
 
+/// Maximum bytes read from a conflicted file when counting conflict markers
+const CONFLICT_SCAN_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// What kind of conflict a file has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictKind {
+    /// Text conflict with markers in the working file
+    Content,
+    /// Binary file; git leaves no markers
+    Binary,
+    /// One side modified the file, the other deleted it
+    ModifyDelete,
+}
+
+/// Per-file conflict details for prioritizing resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictSummary {
+    pub path: String,
+    pub kind: ConflictKind,
+    /// Number of `<<<<<<<` conflict hunks found in the working file
+    pub hunks: usize,
+    /// The file was larger than the scan limit, so `hunks` may be an undercount
+    pub truncated: bool,
+    /// Whether the conflict can be resolved by editing markers (false for binary/modify-delete)
+    pub auto_resolvable: bool,
+}
+
+/// Count conflict hunks in file content, returning (hunks, looks_binary)
+fn count_conflict_hunks(content: &[u8]) -> (usize, bool) {
+    // Same heuristic git uses: a NUL byte in the first 8000 bytes means binary
+    if content.iter().take(8000).any(|&b| b == 0) {
+        return (0, true);
+    }
+    let hunks = content
+        .split(|&b| b == b'\n')
+        .filter(|line| line.starts_with(b"<<<<<<<"))
+        .count();
+    (hunks, false)
+}
+
+/// Get the conflicted files in a worktree with their hunk counts and conflict kind
+pub fn get_conflict_summary(worktree_path: &Path) -> Result<Vec<ConflictSummary>, GitError> {
+    use std::io::Read;
+
+    // Unmerged index entries: "<mode> <sha> <stage>\t<path>". A path missing
+    // stage 2 (ours) or stage 3 (theirs) was deleted on that side.
+    let output = git_command()
+        .args(["ls-files", "-u"])
+        .current_dir(worktree_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let mut stages: Vec<(String, Vec<u8>)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((meta, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(stage) = meta.split_whitespace().nth(2).and_then(|s| s.parse::<u8>().ok()) else {
+            continue;
+        };
+        match stages.iter_mut().find(|(p, _)| p == path) {
+            Some((_, entry)) => entry.push(stage),
+            None => stages.push((path.to_string(), vec![stage])),
+        }
+    }
+
+    let mut summaries = Vec::with_capacity(stages.len());
+    for (path, entry) in stages {
+        let modify_delete = !(entry.contains(&2) && entry.contains(&3));
+
+        let (mut hunks, mut binary, mut truncated) = (0, false, false);
+        if let Ok(file) = std::fs::File::open(worktree_path.join(&path)) {
+            truncated = file.metadata().map(|m| m.len() > CONFLICT_SCAN_LIMIT).unwrap_or(false);
+            let mut content = Vec::new();
+            file.take(CONFLICT_SCAN_LIMIT).read_to_end(&mut content)?;
+            (hunks, binary) = count_conflict_hunks(&content);
+        }
+
+        let kind = if modify_delete {
+            ConflictKind::ModifyDelete
+        } else if binary {
+            ConflictKind::Binary
+        } else {
+            ConflictKind::Content
+        };
+        summaries.push(ConflictSummary {
+            path,
+            kind,
+            hunks,
+            truncated,
+            auto_resolvable: kind == ConflictKind::Content,
+        });
+    }
+
+    summaries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(summaries)
+}
+
+/// 1-based line numbers of conflict markers (`<<<<<<<`, `|||||||`, `=======`, `>>>>>>>`)
+fn find_conflict_markers(content: &str) -> Vec<usize> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.starts_with("<<<<<<<")
+                || line.starts_with("|||||||")
+                || line == &"======="
+                || line.starts_with(">>>>>>>")
+        })
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Stage a conflicted file as resolved after checking no conflict markers remain.
+/// Returns true when the worktree has no conflicted files left.
+pub fn mark_resolved(worktree_path: &Path, file_path: &str) -> Result<bool, GitError> {
+    let full_path = worktree_path.join(file_path);
+
+    // Missing files (resolved modify/delete by deleting) and binary files have no markers
+    if let Ok(bytes) = std::fs::read(&full_path) {
+        if !bytes.iter().take(8000).any(|&b| b == 0) {
+            let markers = find_conflict_markers(&String::from_utf8_lossy(&bytes));
+            if !markers.is_empty() {
+                let lines: Vec<String> = markers.iter().map(|n| n.to_string()).collect();
+                return Err(GitError::MergeConflict(format!(
+                    "{} still has conflict markers on line(s) {}",
+                    file_path,
+                    lines.join(", ")
+                )));
+            }
+        }
+    }
+
+    // -A also stages a deletion when the file was removed to resolve the conflict
+    let output = git_command()
+        .args(["add", "-A", "--", file_path])
+        .current_dir(worktree_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git add failed: {}",
+            stderr
+        ))));
+    }
+
+    Ok(get_conflicted_files(worktree_path)?.is_empty())
+}
+
 /// Get a list of gitignored files and directories in the repository.
 /// Uses `git status --ignored --porcelain` to get ignored entries.
 /// Directories are returned with a trailing slash.
@@ -803,70 +2869,192 @@ pub fn get_ignored_files(repo_path: &Path) -> Result<Vec<String>, GitError> {
     Ok(ignored_files)
 }
 
-/// Check if a merge or rebase is feasible for a worktree branch
-pub fn check_merge_feasibility(worktree_path: &Path, base_branch: &BaseBranch) -> Result<MergeFeasibility, GitError> {
-    let repo = Repository::open(worktree_path)?;
+/// A gitignored file or directory, from `list_ignored_entries`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoredEntry {
+    /// Relative to the worktree; directories end with a slash
+    pub path: String,
+    pub is_dir: bool,
+    /// Bytes on disk, directories summed recursively; only when asked for
+    pub size: Option<u64>,
+}
 
-    // Get current branch name
-    let head = repo.head()?;
-    let current_branch = head
-        .shorthand()
-        .ok_or_else(|| GitError::BranchNotFound("HEAD".to_string()))?
-        .to_string();
+/// Bytes under `path`, without following symlinks
+fn disk_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_size(&e.path())).sum())
+        .unwrap_or(0)
+}
 
-    // Get target branch from config
-    let target_branch = resolve_target_branch(&repo, base_branch)?;
+/// `get_ignored_files` with a directory flag and, if `with_sizes`, sizes
+pub fn list_ignored_entries(worktree_path: &Path, with_sizes: bool) -> Result<Vec<IgnoredEntry>, GitError> {
+    Ok(get_ignored_files(worktree_path)?
+        .into_iter()
+        .map(|path| IgnoredEntry {
+            is_dir: path.ends_with('/'),
+            size: with_sizes.then(|| disk_size(&worktree_path.join(&path))),
+            path,
+        })
+        .collect())
+}
 
-    // If we're on the default branch, nothing to merge
-    if current_branch == target_branch {
-        return Ok(MergeFeasibility {
-            can_merge: false,
-            has_uncommitted_changes: false,
-            is_up_to_date: true,
-            can_fast_forward: false,
-            commits_ahead: 0,
-            commits_behind: 0,
-            current_branch,
-            target_branch,
-            error: Some("Already on the default branch".to_string()),
-        });
+/// Whether a path is ignored and by which rule, from `check_ignore`.
+/// Serialized as `{ "kind": "ignored", "source": ".gitignore", ... }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum IgnoreCheck {
+    /// `pattern` on line `line` of `source` ignores the path
+    Ignored { source: String, line: u32, pattern: String },
+    /// The last matching rule is a `!` negation, so the path isn't ignored
+    Unignored { source: String, line: u32, pattern: String },
+    /// No rule matches, or the path is already tracked
+    NotIgnored,
+}
+
+/// Which gitignore rule, if any, applies to `file_path` (`git check-ignore -v`)
+pub fn check_ignore(worktree_path: &Path, file_path: &str) -> Result<IgnoreCheck, GitError> {
+    // -z needs the path on stdin; it keeps colons in the source or pattern unambiguous
+    let mut child = git_command()
+        .args(["check-ignore", "-v", "-z", "--stdin"])
+        .current_dir(worktree_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(file_path.as_bytes())?;
+        stdin.write_all(b"\0")?;
+    }
+    let output = child.wait_with_output()?;
+    // Exit 1 means nothing matched
+    if output.status.code() == Some(1) {
+        return Ok(IgnoreCheck::NotIgnored);
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git check-ignore failed: {}",
+            stderr.trim()
+        ))));
     }
 
-    // Check for uncommitted changes
-    let has_uncommitted_changes = has_uncommitted_changes(&repo)?;
+    // Source, line number, pattern and path, each NUL-terminated
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split('\0');
+    let (Some(source), Some(line), Some(pattern)) = (fields.next(), fields.next(), fields.next()) else {
+        return Ok(IgnoreCheck::NotIgnored);
+    };
+    let (source, line) = (source.to_string(), line.parse().unwrap_or(0));
+    Ok(match pattern.strip_prefix('!') {
+        Some(_) => IgnoreCheck::Unignored { source, line, pattern: pattern.to_string() },
+        None => IgnoreCheck::Ignored { source, line, pattern: pattern.to_string() },
+    })
+}
 
-    // Get the commits for both branches
-    let current_commit = head.peel_to_commit()?;
-    let target_branch_ref = repo.find_branch(&target_branch, BranchType::Local)?;
-    let target_commit = target_branch_ref.get().peel_to_commit()?;
+/// Check if a merge or rebase is feasible for a worktree branch. `target_branch`
+/// replaces the configured base, e.g. for a backport to a release branch.
+/// Workflows aren't tracked by git, so the caller passes the one running, if any.
+/// With `rebase_onto_remote`, a rebase that can't use `origin/<target>` is
+/// reported in `error` as a warning; it doesn't block the merge.
+pub fn check_merge_feasibility(
+    worktree_path: &Path,
+    base_branch: &BaseBranch,
+    target_branch: Option<&str>,
+    rebase_onto_remote: bool,
+    workflow_in_progress: Option<&str>,
+) -> Result<MergeFeasibility, GitError> {
+    let mut facts = gather_merge_facts(worktree_path, base_branch, target_branch)?;
+    if facts.target_exists {
+        let repo = Repository::open(worktree_path)?;
+        facts.rebase_warning = rebase_base(&repo, &facts.target_branch, rebase_onto_remote).1;
+    }
+    facts.workflow_in_progress = workflow_in_progress.map(str::to_string);
+    Ok(MergeFeasibility::from_facts(facts))
+}
+
+/// Collect the facts `merge_blockers` works from
+fn gather_merge_facts(
+    worktree_path: &Path,
+    base_branch: &BaseBranch,
+    target_branch: Option<&str>,
+) -> Result<MergeFacts, GitError> {
+    let repo = Repository::open(worktree_path)?;
 
-    // Find merge base
-    let merge_base = repo.merge_base(current_commit.id(), target_commit.id())?;
+    let head = repo.head()?;
+    let current_branch = if head.is_branch() {
+        Some(
+            head.shorthand()
+                .ok_or_else(|| GitError::BranchNotFound("HEAD".to_string()))?
+                .to_string(),
+        )
+    } else {
+        None
+    };
 
-    // Calculate ahead/behind
-    let (commits_ahead, commits_behind) =
-        repo.graph_ahead_behind(current_commit.id(), target_commit.id())?;
+    // Get target branch from the override or config
+    let target_branch = match target_branch {
+        Some(name) => name.to_string(),
+        None => match resolve_target_branch(&repo, base_branch) {
+            Ok(name) => name,
+            Err(GitError::BranchNotFound(name)) => name,
+            Err(e) => return Err(e),
+        },
+    };
 
-    // Check if up-to-date (current branch has no commits ahead of target)
-    let is_up_to_date = commits_ahead == 0;
+    let mut facts = MergeFacts {
+        current_branch,
+        dirty_files: dirty_file_count(&repo)?,
+        operation_in_progress: operation_in_progress(repo.state()),
+        ..Default::default()
+    };
 
-    // Check if fast-forward is possible (target hasn't diverged)
-    let can_fast_forward = merge_base == target_commit.id();
+    let Ok(target_ref) = repo.find_branch(&target_branch, BranchType::Local) else {
+        facts.target_branch = target_branch;
+        return Ok(facts);
+    };
+    facts.target_exists = true;
+    let target_commit = target_ref.get().peel_to_commit()?;
+
+    if let Ok(upstream) = target_ref.upstream() {
+        let upstream_commit = upstream.get().peel_to_commit()?;
+        let (_, behind) = repo.graph_ahead_behind(target_commit.id(), upstream_commit.id())?;
+        let name = upstream.name()?.unwrap_or_default().to_string();
+        facts.target_behind_upstream = Some((name, behind));
+    }
 
-    // Can merge if there are commits to merge and no uncommitted changes
-    let can_merge = commits_ahead > 0 && !has_uncommitted_changes;
+    if facts.current_branch.as_deref() != Some(target_branch.as_str()) {
+        let current_commit = head.peel_to_commit()?;
+        let merge_base = repo.merge_base(current_commit.id(), target_commit.id())?;
+        let (ahead, behind) = repo.graph_ahead_behind(current_commit.id(), target_commit.id())?;
+        facts.commits_ahead = ahead;
+        facts.commits_behind = behind;
+        // The target hasn't diverged
+        facts.can_fast_forward = merge_base == target_commit.id();
+    }
+    facts.target_branch = target_branch;
+    Ok(facts)
+}
 
-    Ok(MergeFeasibility {
-        can_merge,
-        has_uncommitted_changes,
-        is_up_to_date,
-        can_fast_forward,
-        commits_ahead,
-        commits_behind,
-        current_branch,
-        target_branch,
-        error: None,
-    })
+/// Name of the operation git is in the middle of, if any
+fn operation_in_progress(state: git2::RepositoryState) -> Option<String> {
+    use git2::RepositoryState::*;
+    let operation = match state {
+        Clean => return None,
+        Merge => "merge",
+        Revert | RevertSequence => "revert",
+        CherryPick | CherryPickSequence => "cherry-pick",
+        Bisect => "bisect",
+        Rebase | RebaseInteractive | RebaseMerge => "rebase",
+        ApplyMailbox | ApplyMailboxOrRebase => "am",
+    };
+    Some(operation.to_string())
 }
 
 /// Check if repository has uncommitted changes
@@ -876,18 +3064,31 @@ pub fn has_uncommitted_changes_at_path(repo_path: &Path) -> Result<bool, GitErro
 }
 
 fn has_uncommitted_changes(repo: &Repository) -> Result<bool, GitError> {
+    Ok(dirty_file_count(repo)? > 0)
+}
+
+/// Changed and untracked files; an untracked directory counts once
+fn dirty_file_count(repo: &Repository) -> Result<usize, GitError> {
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .include_ignored(false)
         .recurse_untracked_dirs(false);
 
     let statuses = repo.statuses(Some(&mut opts))?;
-    Ok(!statuses.is_empty())
+    Ok(statuses.len())
 }
 
 /// Check if repository has modified or staged changes (excludes untracked files)
 /// This is used for worktree creation where untracked files don't matter
-fn has_modified_or_staged_changes(repo: &Repository) -> Result<bool, GitError> {
+/// Whether a merge/rebase/checkout would need an auto-stash first: true for
+/// modified or staged changes. Untracked files are ignored since git usually
+/// tolerates them.
+pub fn needs_stash(worktree_path: &Path) -> Result<bool, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    has_modified_or_staged_changes(&repo)
+}
+
+pub fn has_modified_or_staged_changes(repo: &Repository) -> Result<bool, GitError> {
     let mut opts = StatusOptions::new();
     opts.include_untracked(false)
         .include_ignored(false);
@@ -956,6 +3157,18 @@ fn count_commits_ahead_of_base(repo: &Repository, branch_name: &str, target_bran
     Ok(ahead)
 }
 
+/// Message prefix of stashes created by shellflow workflows
+pub const AUTO_STASH_PREFIX: &str = "shellflow-auto-stash-";
+
+/// A shellflow auto-stash present in a repository's stash list
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoStash {
+    /// Current position in the stash list, e.g. "stash@{0}"
+    pub stash_ref: String,
+    /// The stash ID (message) returned by `stash_changes`
+    pub stash_id: String,
+}
+
 /// Stash uncommitted changes in a repository using git CLI.
 /// Returns a unique stash ID that can be used with `stash_pop` to restore the correct stash.
 pub fn stash_changes(repo_path: &Path) -> Result<String, GitError> {
@@ -966,7 +3179,7 @@ pub fn stash_changes(repo_path: &Path) -> Result<String, GitError> {
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis();
-    let stash_id = format!("shellflow-auto-stash-{}", timestamp);
+    let stash_id = format!("{}{}", AUTO_STASH_PREFIX, timestamp);
 
     log::info!("[stash_changes] Stashing changes in {:?} with id {}", repo_path, stash_id);
 
@@ -987,15 +3200,199 @@ pub fn stash_changes(repo_path: &Path) -> Result<String, GitError> {
     Ok(stash_id)
 }
 
+/// Refs that keep working snapshots reachable, so gc doesn't prune them
+const SNAPSHOT_REF_PREFIX: &str = "refs/shellflow/snapshots/";
+
+/// Record the worktree's tracked changes, staged and unstaged, as a commit
+/// without touching the working tree or the stash list (`git stash create`).
+/// A clean worktree snapshots HEAD. Untracked files aren't captured.
+pub fn create_working_snapshot(worktree_path: &Path) -> Result<String, GitError> {
+    let output = git_command()
+        .args(["stash", "create", "shellflow snapshot"])
+        .current_dir(worktree_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git stash create failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    let hash = match String::from_utf8_lossy(&output.stdout).trim() {
+        "" => Repository::open(worktree_path)?.head()?.peel_to_commit()?.id().to_string(),
+        hash => hash.to_string(),
+    };
+
+    let snapshot_ref = format!("{}{}", SNAPSHOT_REF_PREFIX, hash);
+    let output = git_command()
+        .args(["update-ref", &snapshot_ref, &hash])
+        .current_dir(worktree_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git update-ref failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(hash)
+}
+
+/// Drop the refs keeping `hashes`' snapshots reachable, so gc can prune them.
+/// Refs already gone are skipped.
+pub fn delete_working_snapshots(repo_path: &Path, hashes: &[String]) -> Result<(), GitError> {
+    for hash in hashes {
+        let hash = git2::Oid::from_str(hash)
+            .map_err(|_| GitError::SnapshotNotFound(hash.to_string()))?
+            .to_string();
+        let output = git_command()
+            .args(["update-ref", "-d", &format!("{}{}", SNAPSHOT_REF_PREFIX, hash)])
+            .current_dir(repo_path)
+            .output()?;
+        if !output.status.success() {
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git update-ref -d failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+    }
+    Ok(())
+}
+
+/// Unified diff from a snapshot to the current working tree (tracked files)
+pub fn diff_against_snapshot(worktree_path: &Path, snapshot_hash: &str) -> Result<String, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let snapshot = git2::Oid::from_str(snapshot_hash)
+        .and_then(|oid| repo.find_commit(oid))
+        .map_err(|_| GitError::SnapshotNotFound(snapshot_hash.to_string()))?
+        .id()
+        .to_string();
+
+    let output = git_command()
+        .args(["diff", &snapshot, "--"])
+        .current_dir(worktree_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// What `get_file_diff` compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileDiffMode {
+    /// Working tree against HEAD, staged or not
+    WorkingVsHead,
+    /// Working tree against the base branch
+    WorkingVsBase,
+    /// Index against HEAD
+    Staged,
+}
+
+/// Unified diff of one file, from `get_file_diff`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub path: String,
+    /// Path on the old side when the file was renamed
+    pub old_path: Option<String>,
+    /// Empty for binary files
+    pub diff: String,
+    pub binary: bool,
+    /// Not tracked yet; diffed against an empty file
+    pub untracked: bool,
+}
+
+fn run_diff(worktree_path: &Path, args: &[&str]) -> Result<String, GitError> {
+    let output = git_command().args(args).current_dir(worktree_path).output()?;
+    // `--no-index` exits 1 when the files differ
+    let no_index_differs = args.contains(&"--no-index") && output.status.code() == Some(1);
+    if !output.status.success() && !no_index_differs {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Unified diff of `file_path` for the changed-files panel. Renames are
+/// followed to their old path, untracked files are shown as added in the
+/// working-tree modes, and binary files come back flagged with no diff text.
+pub fn get_file_diff(
+    worktree_path: &Path,
+    file_path: &str,
+    mode: FileDiffMode,
+    base_branch: &BaseBranch,
+) -> Result<FileDiff, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let untracked = repo
+        .status_file(Path::new(file_path))
+        .map(|status| status.contains(Status::WT_NEW))
+        .unwrap_or(false);
+    let from: Vec<String> = match mode {
+        FileDiffMode::WorkingVsHead => vec!["HEAD".to_string()],
+        FileDiffMode::WorkingVsBase => vec![resolve_target_branch(&repo, base_branch)?],
+        FileDiffMode::Staged => vec!["--cached".to_string()],
+    };
+    drop(repo);
+
+    let (old_path, diff) = if untracked {
+        let diff = if mode == FileDiffMode::Staged {
+            String::new()
+        } else {
+            run_diff(worktree_path, &["diff", "--no-index", "--", "/dev/null", file_path])?
+        };
+        (None, diff)
+    } else {
+        let mut args: Vec<&str> = vec!["diff", "-M", "--name-status", "-z"];
+        args.extend(from.iter().map(String::as_str));
+        args.push("--");
+        let name_status = run_diff(worktree_path, &args)?;
+        let fields: Vec<&str> = name_status.split('\0').collect();
+        let old_path = fields
+            .windows(3)
+            .find(|w| w[0].starts_with('R') && w[2] == file_path)
+            .map(|w| w[1].to_string());
+
+        let mut args: Vec<&str> = vec!["diff", "-M"];
+        args.extend(from.iter().map(String::as_str));
+        args.push("--");
+        if let Some(old) = &old_path {
+            args.push(old);
+        }
+        args.push(file_path);
+        let diff = run_diff(worktree_path, &args)?;
+        (old_path, diff)
+    };
+
+    let binary = diff.lines().any(|line| line.starts_with("Binary files ")) && !diff.contains("\n@@ ");
+    Ok(FileDiff {
+        path: file_path.to_string(),
+        old_path,
+        diff: if binary { String::new() } else { diff },
+        binary,
+        untracked,
+    })
+}
+
 /// Pop a specific stash by its ID (message).
 /// Finds the stash with the matching message and pops it.
 pub fn stash_pop(repo_path: &Path, stash_id: &str) -> Result<(), GitError> {
 
     log::info!("[stash_pop] Looking for stash with id {} in {:?}", stash_id, repo_path);
 
-    // List stashes to find the one with our ID
+    let Some(stash_ref) = find_stash_ref(repo_path, stash_id)? else {
+        log::warn!("[stash_pop] Stash with id {} not found, nothing to pop", stash_id);
+        return Ok(());
+    };
+
+    log::info!("[stash_pop] Found stash at {}, popping", stash_ref);
+
     let output = git_command()
-        .args(["stash", "list"])
+        .args(["stash", "pop", &stash_ref])
         .current_dir(repo_path)
         .output()?;
 
@@ -1003,49 +3400,92 @@ pub fn stash_pop(repo_path: &Path, stash_id: &str) -> Result<(), GitError> {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(GitError::Io(std::io::Error::new(
             std::io::ErrorKind::Other,
-            format!("git stash list failed: {}", stderr),
+            format!("git stash pop failed: {}", stderr),
         )));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    log::info!("[stash_pop] Stash pop successful");
+    Ok(())
+}
+
+/// Run `git stash list` and return its stdout
+fn stash_list(repo_path: &Path) -> Result<String, GitError> {
+    let output = git_command()
+        .args(["stash", "list"])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git stash list failed: {}",
+            stderr
+        ))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
 
-    // Find the stash index that matches our ID
+/// Find the stash ref (e.g. "stash@{2}") whose message contains `stash_id`
+pub fn find_stash_ref(repo_path: &Path, stash_id: &str) -> Result<Option<String>, GitError> {
     // Format: "stash@{0}: On branch: message"
-    let stash_ref = stdout
+    Ok(stash_list(repo_path)?
         .lines()
         .find(|line| line.contains(stash_id))
         .and_then(|line| line.split(':').next())
-        .map(|s| s.trim().to_string());
+        .map(|s| s.trim().to_string()))
+}
 
-    let Some(stash_ref) = stash_ref else {
-        log::warn!("[stash_pop] Stash with id {} not found, nothing to pop", stash_id);
-        return Ok(());
+/// List the stashes created by shellflow workflows, newest first
+pub fn list_auto_stashes(repo_path: &Path) -> Result<Vec<AutoStash>, GitError> {
+    Ok(stash_list(repo_path)?
+        .lines()
+        .filter_map(|line| {
+            let stash_ref = line.split(':').next()?.trim().to_string();
+            let start = line.find(AUTO_STASH_PREFIX)?;
+            let stash_id = line[start..].split_whitespace().next()?.to_string();
+            Some(AutoStash { stash_ref, stash_id })
+        })
+        .collect())
+}
+
+/// Drop a specific stash by its ID (message)
+pub fn drop_stash(repo_path: &Path, stash_id: &str) -> Result<(), GitError> {
+    let Some(stash_ref) = find_stash_ref(repo_path, stash_id)? else {
+        return Err(GitError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Stash {} not found", stash_id),
+        )));
     };
 
-    log::info!("[stash_pop] Found stash at {}, popping", stash_ref);
+    log::info!("[drop_stash] Dropping {} ({}) in {:?}", stash_ref, stash_id, repo_path);
 
     let output = git_command()
-        .args(["stash", "pop", &stash_ref])
+        .args(["stash", "drop", &stash_ref])
         .current_dir(repo_path)
         .output()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(GitError::Io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("git stash pop failed: {}", stderr),
-        )));
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git stash drop failed: {}",
+            stderr
+        ))));
     }
 
-    log::info!("[stash_pop] Stash pop successful");
     Ok(())
 }
 
-/// Merge the current branch into the target branch
+/// Merge the current branch into the target branch (the default branch unless
+/// `target_branch` is given). With `fast_forward` only a fast-forward is
+/// allowed: a diverged target fails with NotFastForward before anything is
+/// checked out.
 /// This performs: checkout target, merge current, checkout current
 pub fn merge_branch_to_target(
     worktree_path: &Path,
     repo_path: &Path,
+    target_branch: Option<&str>,
+    fast_forward: bool,
 ) -> Result<(), GitError> {
 
     // Use git CLI for merge operations as libgit2 merge is complex
@@ -1057,7 +3497,10 @@ pub fn merge_branch_to_target(
             .shorthand()
             .ok_or_else(|| GitError::BranchNotFound("HEAD".to_string()))?
             .to_string();
-        let target = get_default_branch(&repo)?;
+        let target = merge_target_branch(&repo, target_branch)?;
+        if fast_forward && !can_fast_forward(&repo, &current, &target)? {
+            return Err(GitError::NotFastForward { branch: current, target });
+        }
         (current, target)
     };
 
@@ -1079,11 +3522,25 @@ pub fn merge_branch_to_target(
     }
 
     // Merge the worktree branch
+    let mode = if fast_forward { "--ff-only" } else { "--no-edit" };
     let output = git_command()
-        .args(["merge", "--no-edit", &current_branch])
+        .args(["merge", mode, &current_branch])
         .current_dir(repo_path)
         .output()?;
 
+    if !output.status.success() && fast_forward {
+        // --ff-only leaves nothing to clean up. Only a target that moved since
+        // the check is NotFastForward; anything else (e.g. local changes the
+        // merge would overwrite) is git's own error.
+        let repo = Repository::open(repo_path)?;
+        if !can_fast_forward(&repo, &current_branch, &target_branch)? {
+            return Err(GitError::NotFastForward { branch: current_branch, target: target_branch });
+        }
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git merge --ff-only failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
     if !output.status.success() {
         // Don't abort here - leave conflicts for resolution (AI or manual)
         // Caller should call abort_merge if user cancels without resolving
@@ -1093,66 +3550,421 @@ pub fn merge_branch_to_target(
         )));
     }
 
-    Ok(())
+    Ok(())
+}
+
+/// Whether local `target` is an ancestor of local `branch`
+fn can_fast_forward(repo: &Repository, branch: &str, target: &str) -> Result<bool, GitError> {
+    let tip = |name: &str| -> Result<git2::Oid, GitError> {
+        let branch = repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|_| GitError::BranchNotFound(name.to_string()))?;
+        Ok(branch.get().peel_to_commit()?.id())
+    };
+    let (branch_tip, target_tip) = (tip(branch)?, tip(target)?);
+    Ok(branch_tip == target_tip || repo.graph_descendant_of(branch_tip, target_tip)?)
+}
+
+/// Squash the current branch into the target branch (the default branch
+/// unless `target_branch` is given) as one commit whose message comes from
+/// `message`, called with the changes staged in `repo_path`.
+/// This performs: checkout target, merge --squash current, commit
+pub fn squash_branch_to_target(
+    worktree_path: &Path,
+    repo_path: &Path,
+    target_branch: Option<&str>,
+    message: impl FnOnce(&str) -> String,
+) -> Result<(), GitError> {
+    let (current_branch, target_branch) = {
+        let repo = Repository::open(worktree_path)?;
+        let head = repo.head()?;
+        let current = head
+            .shorthand()
+            .ok_or_else(|| GitError::BranchNotFound("HEAD".to_string()))?
+            .to_string();
+        let target = merge_target_branch(&repo, target_branch)?;
+        (current, target)
+    };
+
+    let output = git_command()
+        .args(["checkout", &target_branch])
+        .current_dir(repo_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "Failed to checkout {}: {}",
+            target_branch,
+            String::from_utf8_lossy(&output.stderr)
+        ))));
+    }
+
+    let output = git_command()
+        .args(["merge", "--squash", &current_branch])
+        .current_dir(repo_path)
+        .output()?;
+    if !output.status.success() {
+        // Left for resolution like a merge; abort_merge cleans a squash up too
+        return Err(GitError::MergeConflict(format!(
+            "Squash failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    // The branch's changes may already be on the target
+    if diff_cached_files(repo_path)?.is_empty() {
+        abort_squash(repo_path)?;
+        return Err(GitError::NoChanges);
+    }
+    commit_staged(repo_path, &message(&current_branch))
+}
+
+/// Whether `repo_path` holds an uncommitted `merge --squash`: git writes
+/// SQUASH_MSG but no MERGE_HEAD, so it doesn't count as a merge
+fn squash_in_progress(repo_path: &Path) -> bool {
+    Repository::open(repo_path)
+        .map(|repo| repo.path().join("SQUASH_MSG").exists() && !repo.path().join("MERGE_HEAD").exists())
+        .unwrap_or(false)
+}
+
+/// Undo an uncommitted `merge --squash`, keeping unrelated local changes
+fn abort_squash(repo_path: &Path) -> Result<(), GitError> {
+    let output = git_command()
+        .args(["reset", "--merge"])
+        .current_dir(repo_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "Failed to abort squash: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ))));
+    }
+    let repo = Repository::open(repo_path)?;
+    let _ = std::fs::remove_file(repo.path().join("SQUASH_MSG"));
+    Ok(())
+}
+
+/// Abort an in-progress merge operation
+pub fn abort_merge(repo_path: &Path) -> Result<(), GitError> {
+    if squash_in_progress(repo_path) {
+        return abort_squash(repo_path);
+    }
+
+    let output = git_command()
+        .args(["merge", "--abort"])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        // It's okay if abort fails (e.g., no merge in progress)
+        // Just log and continue
+    }
+
+    Ok(())
+}
+
+/// Abort an in-progress rebase operation
+pub fn abort_rebase(repo_path: &Path) -> Result<(), GitError> {
+
+    let output = git_command()
+        .args(["rebase", "--abort"])
+        .current_dir(repo_path)
+        .output()?;
+
+    if !output.status.success() {
+        // It's okay if abort fails (e.g., no rebase in progress)
+        // Just log and continue
+    }
+
+    Ok(())
+}
+
+/// Rebase the current branch onto the target branch (the default branch unless
+/// `target_branch` is given), or onto its origin counterpart with `onto_remote`
+pub fn rebase_branch_onto_target(
+    worktree_path: &Path,
+    target_branch: Option<&str>,
+    onto_remote: bool,
+) -> Result<(), GitError> {
+
+    let repo = Repository::open(worktree_path)?;
+    let target_branch = merge_target_branch(&repo, target_branch)?;
+    let (onto, warning) = rebase_base(&repo, &target_branch, onto_remote);
+    drop(repo);
+    if let Some(warning) = warning {
+        log::info!("[rebase_branch_onto_target] {}", warning);
+    }
+
+    // Rebase onto target branch
+    let output = git_command()
+        .args(["rebase", &onto])
+        .current_dir(worktree_path)
+        .output()?;
+
+    if !output.status.success() {
+        // Don't abort here - leave conflicts for resolution (AI or manual)
+        // Caller should call abort_rebase if user cancels without resolving
+        return Err(GitError::MergeConflict(format!(
+            "Rebase failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Outcome of continuing (or skipping a commit in) a rebase, merge or cherry-pick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinueResult {
+    /// The operation finished; nothing is left in progress
+    pub completed: bool,
+    /// Files conflicting at the point the operation stopped (empty when completed)
+    pub conflicted_files: Vec<String>,
+}
+
+/// Run `git <operation> --continue`, reporting whether it finished or stopped on
+/// the next conflict. Unresolved conflicts are reported without running git.
+fn continue_operation(worktree_path: &Path, operation: &str) -> Result<ContinueResult, GitError> {
+    let conflicted_files = get_conflicted_files(worktree_path)?;
+    if !conflicted_files.is_empty() {
+        return Ok(ContinueResult { completed: false, conflicted_files });
+    }
+
+    run_sequencer_command(worktree_path, &[operation, "--continue"])
+}
+
+/// Run a rebase/merge/cherry-pick step (`--continue`, `--skip`) and report
+/// whether the operation finished or stopped on a conflict
+fn run_sequencer_command(worktree_path: &Path, args: &[&str]) -> Result<ContinueResult, GitError> {
+    log::info!("[run_sequencer_command] git {} in {:?}", args.join(" "), worktree_path);
+
+    // Accept the prepared commit message instead of opening an editor
+    let output = git_command()
+        .args(args)
+        .env("GIT_EDITOR", "true")
+        .current_dir(worktree_path)
+        .output()?;
+
+    if output.status.success() {
+        return Ok(ContinueResult { completed: true, conflicted_files: Vec::new() });
+    }
+
+    let conflicted_files = get_conflicted_files(worktree_path)?;
+    if !conflicted_files.is_empty() {
+        return Ok(ContinueResult { completed: false, conflicted_files });
+    }
+
+    Err(GitError::Io(std::io::Error::other(format!(
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))))
+}
+
+/// Where `restore_file_from_commit` puts the old content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreMode {
+    /// Working tree only, left as an unstaged change
+    Working,
+    /// Working tree and index
+    Staged,
 }
 
-/// Abort an in-progress merge operation
-pub fn abort_merge(repo_path: &Path) -> Result<(), GitError> {
+/// Result of `restore_file_from_commit`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredFile {
+    pub path: String,
+    /// Path the content was read from at that commit; differs from `path` after a rename
+    pub source_path: String,
+    /// Full id of the commit restored from
+    pub sha: String,
+}
 
-    let output = git_command()
-        .args(["merge", "--abort"])
-        .current_dir(repo_path)
-        .output()?;
+/// Put one file back the way it was at `sha`, leaving every other file alone.
+/// `sha` must be reachable from HEAD. If the file was renamed since, the
+/// content comes from its old path; a path that didn't exist at all fails
+/// with `PathNotInCommit`.
+pub fn restore_file_from_commit(
+    worktree_path: &Path,
+    file_path: &str,
+    sha: &str,
+    mode: RestoreMode,
+) -> Result<RestoredFile, GitError> {
+    let repo = Repository::open(worktree_path)?;
+    let not_reachable = || GitError::RefNotReachable(sha.to_string());
+    let commit = repo
+        .revparse_single(sha)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| not_reachable())?;
+    let head = repo.head()?.peel_to_commit()?;
+    if commit.id() != head.id() && !repo.graph_descendant_of(head.id(), commit.id())? {
+        return Err(not_reachable());
+    }
+    let full_sha = commit.id().to_string();
 
-    if !output.status.success() {
-        // It's okay if abort fails (e.g., no merge in progress)
-        // Just log and continue
+    let tree = commit.tree()?;
+    let source_path = if tree.get_path(Path::new(file_path)).is_ok() {
+        file_path.to_string()
+    } else {
+        let renamed_from = renamed_source(&repo, &tree, &head.tree()?, file_path)?;
+        match renamed_from {
+            Some(old) if tree.get_path(Path::new(&old)).is_ok() => old,
+            renamed_from => {
+                return Err(GitError::PathNotInCommit {
+                    path: file_path.to_string(),
+                    sha: full_sha[..7].to_string(),
+                    renamed_from,
+                })
+            }
+        }
+    };
+
+    if source_path == file_path {
+        let mut cmd = git_command();
+        cmd.args(["restore", &format!("--source={}", full_sha), "--worktree"]);
+        if mode == RestoreMode::Staged {
+            cmd.arg("--staged");
+        }
+        let output = cmd.args(["--", file_path]).current_dir(worktree_path).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git restore failed: {}",
+                stderr.trim()
+            ))));
+        }
+    } else {
+        // `git restore` writes to the source path, so copy the content across,
+        // with the same checkout filters (line endings, LFS) git would apply
+        let output = git_command()
+            .args(["cat-file", "--filters", &format!("{}:{}", full_sha, source_path)])
+            .current_dir(worktree_path)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git cat-file failed: {}",
+                stderr.trim()
+            ))));
+        }
+        let target = worktree_path.join(file_path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, &output.stdout)?;
+        if mode == RestoreMode::Staged {
+            let output = git_command()
+                .args(["add", "--", file_path])
+                .current_dir(worktree_path)
+                .output()?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(GitError::Io(std::io::Error::other(format!(
+                    "git add failed: {}",
+                    stderr.trim()
+                ))));
+            }
+        }
     }
 
-    Ok(())
+    Ok(RestoredFile {
+        path: file_path.to_string(),
+        source_path,
+        sha: full_sha,
+    })
 }
 
-/// Abort an in-progress rebase operation
-pub fn abort_rebase(repo_path: &Path) -> Result<(), GitError> {
+/// Old path of `file_path` if rename detection between `old_tree` and
+/// `new_tree` finds it was renamed from something
+fn renamed_source(
+    repo: &Repository,
+    old_tree: &git2::Tree,
+    new_tree: &git2::Tree,
+    file_path: &str,
+) -> Result<Option<String>, GitError> {
+    let mut diff = repo.diff_tree_to_tree(Some(old_tree), Some(new_tree), None)?;
+    diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .find(|delta| delta.new_file().path() == Some(Path::new(file_path)))
+        .and_then(|delta| delta.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/"))))
+}
 
+/// Commits `git rebase -i <base>` would replay, oldest first, as full ids.
+/// Merge commits are left out, as the rebase flattens them.
+pub fn commits_to_rebase(worktree_path: &Path, base: &str) -> Result<Vec<String>, GitError> {
     let output = git_command()
-        .args(["rebase", "--abort"])
-        .current_dir(repo_path)
+        .args(["rev-list", "--reverse", "--no-merges", &format!("{}..HEAD", base), "--"])
+        .current_dir(worktree_path)
         .output()?;
 
     if !output.status.success() {
-        // It's okay if abort fails (e.g., no rebase in progress)
-        // Just log and continue
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Io(std::io::Error::other(format!(
+            "git rev-list failed: {}",
+            stderr.trim()
+        ))));
     }
 
-    Ok(())
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
 }
 
-/// Rebase the current branch onto the target branch
-pub fn rebase_branch_onto_target(
-    worktree_path: &Path,
-) -> Result<(), GitError> {
+/// Whether a rebase is in progress (checks the worktree's own git dir)
+pub fn is_rebase_in_progress(worktree_path: &Path) -> Result<bool, GitError> {
+    for dir in ["rebase-merge", "rebase-apply"] {
+        let output = git_command()
+            .args(["rev-parse", "--path-format=absolute", "--git-path", dir])
+            .current_dir(worktree_path)
+            .output()?;
+        if !output.status.success() {
+            return Err(GitError::Io(std::io::Error::other(format!(
+                "git rev-parse failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+        if Path::new(String::from_utf8_lossy(&output.stdout).trim()).exists() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
 
-    let repo = Repository::open(worktree_path)?;
-    let target_branch = get_default_branch(&repo)?;
-    drop(repo);
+/// Skip the commit the rebase stopped on (e.g. one left empty by conflict resolution)
+pub fn skip_rebase_commit(worktree_path: &Path) -> Result<ContinueResult, GitError> {
+    if !is_rebase_in_progress(worktree_path)? {
+        return Err(GitError::NoRebaseInProgress);
+    }
+    run_sequencer_command(worktree_path, &["rebase", "--skip"])
+}
 
-    // Rebase onto target branch
-    let output = git_command()
-        .args(["rebase", &target_branch])
-        .current_dir(worktree_path)
-        .output()?;
+/// Continue an in-progress rebase after conflicts are resolved
+pub fn continue_rebase(worktree_path: &Path) -> Result<ContinueResult, GitError> {
+    continue_operation(worktree_path, "rebase")
+}
 
-    if !output.status.success() {
-        // Don't abort here - leave conflicts for resolution (AI or manual)
-        // Caller should call abort_rebase if user cancels without resolving
-        return Err(GitError::MergeConflict(format!(
-            "Rebase failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        )));
+/// Conclude an in-progress merge after conflicts are resolved
+pub fn continue_merge(worktree_path: &Path) -> Result<ContinueResult, GitError> {
+    if squash_in_progress(worktree_path) {
+        // A squash has no merge to continue; committing uses the prepared SQUASH_MSG
+        let conflicted_files = get_conflicted_files(worktree_path)?;
+        if !conflicted_files.is_empty() {
+            return Ok(ContinueResult { completed: false, conflicted_files });
+        }
+        return run_sequencer_command(worktree_path, &["commit", "--no-edit"]);
     }
+    continue_operation(worktree_path, "merge")
+}
 
-    Ok(())
+/// Continue an in-progress cherry-pick after conflicts are resolved
+pub fn continue_cherry_pick(worktree_path: &Path) -> Result<ContinueResult, GitError> {
+    continue_operation(worktree_path, "cherry-pick")
 }
 
 /// Delete a local branch
@@ -1166,25 +3978,14 @@ pub fn delete_local_branch(repo_path: &Path, branch_name: &str) -> Result<(), Gi
 }
 
 /// Delete a remote branch by pushing a delete refspec
-pub fn delete_remote_branch(repo_path: &Path, branch_name: &str) -> Result<(), GitError> {
-
-    let output = git_command()
-        .args(["push", "origin", "--delete", branch_name])
-        .current_dir(repo_path)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        // Don't fail if branch doesn't exist on remote
-        if !stderr.contains("remote ref does not exist") {
-            return Err(GitError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to delete remote branch: {}", stderr),
-            )));
+pub fn delete_remote_branch(repo_path: &Path, branch_name: &str, retry: &NetworkRetry) -> Result<(), GitError> {
+    retry.run("delete remote branch", || {
+        match run_remote_command(repo_path, &["push", "origin", "--delete", branch_name], "Failed to delete remote branch") {
+            // Don't fail if branch doesn't exist on remote
+            Err(message) if message.contains("remote ref does not exist") => Ok(()),
+            result => result,
         }
-    }
-
-    Ok(())
+    })
 }
 
 /// Validate a git branch name according to git's rules
@@ -1242,9 +4043,39 @@ pub fn validate_branch_name(name: &str) -> Option<String> {
     None
 }
 
-/// Rename a git branch using `git branch -m`
+/// Rename a git branch using `git branch -m`.
+///
+/// A case-only rename (`Feature-X` to `feature-x`) on case-insensitive ref
+/// storage goes through a temporary name, since git sees the target as taken.
 pub fn rename_branch(repo_path: &Path, old_name: &str, new_name: &str) -> Result<(), GitError> {
+    let case_only = old_name != new_name && old_name.to_lowercase() == new_name.to_lowercase();
+    rename_branch_with(repo_path, old_name, new_name, case_only && refs_case_insensitive(repo_path))
+}
+
+fn rename_branch_with(
+    repo_path: &Path,
+    old_name: &str,
+    new_name: &str,
+    via_intermediate: bool,
+) -> Result<(), GitError> {
+    if via_intermediate {
+        let intermediate = format!("{}-tmp-{}", old_name, &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        run_branch_move(repo_path, old_name, &intermediate)?;
+        if let Err(e) = run_branch_move(repo_path, &intermediate, new_name) {
+            // Put the original name back so the branch isn't left under the temporary one
+            let _ = run_branch_move(repo_path, &intermediate, old_name);
+            return Err(e);
+        }
+        log::info!("[git::rename_branch] Branch renamed successfully via '{}'", intermediate);
+        return Ok(());
+    }
+
+    run_branch_move(repo_path, old_name, new_name)?;
+    log::info!("[git::rename_branch] Branch renamed successfully");
+    Ok(())
+}
 
+fn run_branch_move(repo_path: &Path, old_name: &str, new_name: &str) -> Result<(), GitError> {
     log::info!(
         "[git::rename_branch] Renaming branch '{}' to '{}' in {:?}",
         old_name,
@@ -1265,15 +4096,20 @@ pub fn rename_branch(repo_path: &Path, old_name: &str, new_name: &str) -> Result
         )));
     }
 
-    log::info!("[git::rename_branch] Branch renamed successfully");
     Ok(())
 }
 
-/// Execute the full merge workflow
+/// Execute the full merge workflow into `target_branch`, or the default branch.
+/// `squash_message` names the squash commit and is only called for `Squash`.
+/// `fast_forward` makes `Merge` refuse anything but a fast-forward.
 pub fn execute_merge_workflow(
     worktree_path: &Path,
     repo_path: &Path,
     strategy: MergeStrategy,
+    target_branch: Option<&str>,
+    rebase_onto_remote: bool,
+    fast_forward: bool,
+    squash_message: impl FnOnce(&str) -> String,
 ) -> Result<String, GitError> {
     // Get branch name before any operations
     let branch_name = {
@@ -1286,12 +4122,15 @@ pub fn execute_merge_workflow(
 
     match strategy {
         MergeStrategy::Merge => {
-            merge_branch_to_target(worktree_path, repo_path)?;
+            merge_branch_to_target(worktree_path, repo_path, target_branch, fast_forward)?;
         }
         MergeStrategy::Rebase => {
-            rebase_branch_onto_target(worktree_path)?;
+            rebase_branch_onto_target(worktree_path, target_branch, rebase_onto_remote)?;
             // After rebase, merge into target (fast-forward)
-            merge_branch_to_target(worktree_path, repo_path)?;
+            merge_branch_to_target(worktree_path, repo_path, target_branch, fast_forward)?;
+        }
+        MergeStrategy::Squash => {
+            squash_branch_to_target(worktree_path, repo_path, target_branch, squash_message)?;
         }
     }
 
@@ -1301,6 +4140,710 @@ pub fn execute_merge_workflow(
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn repo_lock_is_shared_per_repository() {
+        let a = repo_lock(Path::new("/tmp/shellflow-repo-lock-a"));
+        let a_again = repo_lock(Path::new("/tmp/shellflow-repo-lock-a"));
+        let b = repo_lock(Path::new("/tmp/shellflow-repo-lock-b"));
+
+        assert!(Arc::ptr_eq(&a, &a_again));
+        assert!(!Arc::ptr_eq(&a, &b));
+    }
+
+    fn git_succeeds(repo: &Path, args: &[&str]) -> bool {
+        git_command()
+            .args(["-c", "user.name=shellflow", "-c", "user.email=shellflow@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .unwrap()
+            .status
+            .success()
+    }
+
+    fn run_git(repo: &Path, args: &[&str]) {
+        assert!(git_succeeds(repo, args), "git {:?} failed", args);
+    }
+
+    fn git_stdout(repo: &Path, args: &[&str]) -> String {
+        let output = git_command().args(args).current_dir(repo).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn init_repo_with_commit() -> PathBuf {
+        let repo = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo).unwrap();
+        run_git(&repo, &["init", "-q"]);
+        // Commands under test run without `-c`, so give the repo its own identity
+        run_git(&repo, &["config", "user.name", "shellflow"]);
+        run_git(&repo, &["config", "user.email", "shellflow@example.com"]);
+        std::fs::write(repo.join("a.txt"), "one\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "init"]);
+        repo
+    }
+
+    #[test]
+    fn working_snapshot_leaves_the_tree_alone_and_diffs_later_edits() {
+        let repo = init_repo_with_commit();
+        let head = git_stdout(&repo, &["rev-parse", "HEAD"]);
+        // A clean tree snapshots HEAD
+        assert_eq!(create_working_snapshot(&repo).unwrap(), head);
+
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        let snapshot = create_working_snapshot(&repo).unwrap();
+        assert_ne!(snapshot, head);
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "two\n");
+        assert_eq!(git_stdout(&repo, &["stash", "list"]), "");
+        assert_eq!(diff_against_snapshot(&repo, &snapshot).unwrap(), "");
+
+        std::fs::write(repo.join("a.txt"), "three\n").unwrap();
+        let diff = diff_against_snapshot(&repo, &snapshot).unwrap();
+        assert!(diff.contains("-two") && diff.contains("+three"), "{}", diff);
+
+        // Kept reachable, and unknown hashes are rejected rather than passed to git
+        let pinned = git_stdout(&repo, &["rev-parse", &format!("{}{}", SNAPSHOT_REF_PREFIX, snapshot)]);
+        assert_eq!(pinned, snapshot);
+        assert!(matches!(
+            diff_against_snapshot(&repo, "--output=/tmp/x"),
+            Err(GitError::SnapshotNotFound(_))
+        ));
+
+        delete_working_snapshots(&repo, std::slice::from_ref(&snapshot)).unwrap();
+        assert_eq!(git_stdout(&repo, &["for-each-ref", SNAPSHOT_REF_PREFIX]), "");
+        assert!(matches!(
+            delete_working_snapshots(&repo, &["refs/heads/main".to_string()]),
+            Err(GitError::SnapshotNotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn file_diff_covers_each_mode_and_odd_files() {
+        let repo = init_repo_with_commit();
+        let base = BaseBranch::Named { name: git_stdout(&repo, &["branch", "--show-current"]) };
+        run_git(&repo, &["checkout", "-q", "-b", "feature"]);
+        std::fs::write(repo.join("b.txt"), "committed\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "b"]);
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+
+        let head = get_file_diff(&repo, "a.txt", FileDiffMode::WorkingVsHead, &base).unwrap();
+        assert!(head.diff.contains("-one") && head.diff.contains("+two"), "{}", head.diff);
+        assert!(!head.binary && !head.untracked && head.old_path.is_none());
+        assert_eq!(get_file_diff(&repo, "a.txt", FileDiffMode::Staged, &base).unwrap().diff, "");
+        let vs_base = get_file_diff(&repo, "b.txt", FileDiffMode::WorkingVsBase, &base).unwrap();
+        assert!(vs_base.diff.contains("+committed"), "{}", vs_base.diff);
+
+        // Untracked files diff against /dev/null
+        std::fs::write(repo.join("new.txt"), "fresh\n").unwrap();
+        let new = get_file_diff(&repo, "new.txt", FileDiffMode::WorkingVsHead, &base).unwrap();
+        assert!(new.untracked && new.diff.contains("+fresh"), "{}", new.diff);
+
+        // Renames keep their old path
+        run_git(&repo, &["mv", "b.txt", "c.txt"]);
+        let renamed = get_file_diff(&repo, "c.txt", FileDiffMode::Staged, &base).unwrap();
+        assert_eq!(renamed.old_path.as_deref(), Some("b.txt"));
+        assert!(renamed.diff.contains("rename from b.txt"), "{}", renamed.diff);
+
+        std::fs::write(repo.join("blob.bin"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+        let binary = get_file_diff(&repo, "blob.bin", FileDiffMode::WorkingVsHead, &base).unwrap();
+        assert!(binary.binary && binary.diff.is_empty());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn ignored_entries_flag_directories_and_sum_sizes() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        std::fs::write(repo.join("debug.log"), "12345").unwrap();
+        std::fs::create_dir_all(repo.join("build").join("out")).unwrap();
+        std::fs::write(repo.join("build").join("a.o"), "abc").unwrap();
+        std::fs::write(repo.join("build").join("out").join("b.o"), "defg").unwrap();
+
+        let mut entries = list_ignored_entries(&repo, true).unwrap();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            entries,
+            vec![
+                IgnoredEntry { path: "build/".to_string(), is_dir: true, size: Some(7) },
+                IgnoredEntry { path: "debug.log".to_string(), is_dir: false, size: Some(5) },
+            ]
+        );
+        assert!(list_ignored_entries(&repo, false).unwrap().iter().all(|e| e.size.is_none()));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn file_sides_handle_added_deleted_and_binary_files() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        let modified = get_file_sides(&repo, "a.txt", "HEAD").unwrap();
+        assert_eq!((modified.old_content.as_str(), modified.new_content.as_str()), ("one\n", "two\n"));
+        assert!(!modified.binary);
+
+        std::fs::write(repo.join("new.rs"), "fn main() {}\n").unwrap();
+        let added = get_file_sides(&repo, "new.rs", "HEAD").unwrap();
+        assert_eq!(added.old_content, "");
+        assert_eq!(added.language, "rust");
+
+        std::fs::remove_file(repo.join("a.txt")).unwrap();
+        let deleted = get_file_sides(&repo, "a.txt", "HEAD").unwrap();
+        assert_eq!((deleted.old_content.as_str(), deleted.new_content.as_str()), ("one\n", ""));
+
+        std::fs::write(repo.join("blob.bin"), [0xffu8, 0, 1, 2]).unwrap();
+        let binary = get_file_sides(&repo, "blob.bin", "HEAD").unwrap();
+        assert!(binary.binary && binary.new_content.is_empty());
+
+        assert!(get_file_sides(&repo, "a.txt", "no-such-ref").is_err());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn stages_and_unstages_individual_files() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("b c.txt"), "spaced\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "b"]);
+
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        std::fs::remove_file(repo.join("b c.txt")).unwrap();
+        std::fs::write(repo.join("new*.txt"), "new\n").unwrap();
+        std::fs::write(repo.join("left.txt"), "alone\n").unwrap();
+        let paths = vec!["a.txt".to_string(), "b c.txt".to_string(), "new*.txt".to_string()];
+
+        stage_files(&repo, &paths).unwrap();
+        assert_eq!(git_stdout(&repo, &["diff", "--cached", "--name-status"]), "M\ta.txt\nD\tb c.txt\nA\tnew*.txt");
+        // Nothing selected stages nothing
+        stage_files(&repo, &[]).unwrap();
+        assert!(!git_stdout(&repo, &["diff", "--cached", "--name-only"]).contains("left.txt"));
+
+        unstage_files(&repo, &paths[..2]).unwrap();
+        assert_eq!(git_stdout(&repo, &["diff", "--cached", "--name-status"]), "A\tnew*.txt");
+        assert!(!repo.join("b c.txt").exists());
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "two\n");
+
+        // Before the first commit there is no HEAD to reset to
+        let fresh = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&fresh).unwrap();
+        run_git(&fresh, &["init", "-q"]);
+        std::fs::write(fresh.join("x.txt"), "x\n").unwrap();
+        stage_files(&fresh, &["x.txt".to_string()]).unwrap();
+        unstage_files(&fresh, &["x.txt".to_string()]).unwrap();
+        assert_eq!(git_stdout(&fresh, &["diff", "--cached", "--name-only"]), "");
+        assert!(fresh.join("x.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&fresh);
+    }
+
+    #[test]
+    fn amends_the_last_commit_message_and_contents() {
+        let repo = init_repo_with_commit();
+        let count = || git_stdout(&repo, &["rev-list", "--count", "HEAD"]);
+
+        std::fs::write(repo.join("forgotten.txt"), "x\n").unwrap();
+        run_git(&repo, &["add", "forgotten.txt"]);
+        amend_last_commit(&repo, None).unwrap();
+        assert_eq!(count(), "1");
+        assert_eq!(git_stdout(&repo, &["log", "-1", "--format=%s"]), "init");
+        assert!(git_stdout(&repo, &["show", "--name-only", "--format="]).contains("forgotten.txt"));
+
+        amend_last_commit(&repo, Some("Initial commit\n\nWith a body")).unwrap();
+        assert_eq!(count(), "1");
+        assert_eq!(git_stdout(&repo, &["log", "-1", "--format=%B"]), "Initial commit\n\nWith a body");
+        assert!(amend_last_commit(&repo, Some("  ")).is_err());
+
+        run_git(&repo, &["checkout", "-q", "--detach"]);
+        let err = amend_last_commit(&repo, None).unwrap_err();
+        assert!(err.to_string().contains("detached"), "{}", err);
+
+        let fresh = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&fresh).unwrap();
+        run_git(&fresh, &["init", "-q"]);
+        let err = amend_last_commit(&fresh, None).unwrap_err();
+        assert!(err.to_string().contains("no commits yet"), "{}", err);
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&fresh);
+    }
+
+    #[test]
+    fn discards_tracked_changes_and_only_opted_in_untracked_files() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        std::fs::write(repo.join("scratch.txt"), "tmp\n").unwrap();
+        let paths = |list: &[&str]| list.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+        let results = discard_changes(&repo, &paths(&["a.txt", "scratch.txt", "../a.txt", "/etc/hosts"]), false).unwrap();
+        assert_eq!(results.iter().map(|r| r.discarded).collect::<Vec<_>>(), vec![true, false, false, false]);
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "one\n");
+        assert!(repo.join("scratch.txt").exists());
+        assert_eq!(results[2].error.as_deref(), Some("Path is outside the worktree"));
+
+        std::fs::remove_file(repo.join("a.txt")).unwrap();
+        let results = discard_changes(&repo, &paths(&["a.txt", "scratch.txt"]), true).unwrap();
+        assert!(results.iter().all(|r| r.discarded), "{:?}", results);
+        assert!(repo.join("a.txt").exists());
+        assert!(!repo.join("scratch.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn discard_refuses_paths_through_a_symlinked_directory() {
+        let repo = init_repo_with_commit();
+        let outside = repo.with_extension("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("keep.txt"), "keep\n").unwrap();
+        std::os::unix::fs::symlink(&outside, repo.join("link")).unwrap();
+
+        let results = discard_changes(&repo, &["link/keep.txt".to_string()], true).unwrap();
+        assert!(!results[0].discarded);
+        assert_eq!(results[0].error.as_deref(), Some("Path is outside the worktree"));
+        assert!(outside.join("keep.txt").exists());
+
+        // The link itself is untracked and is removed as a link
+        let results = discard_changes(&repo, &["link".to_string()], true).unwrap();
+        assert!(results[0].discarded, "{:?}", results);
+        assert!(repo.join("link").symlink_metadata().is_err());
+        assert!(outside.join("keep.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&outside);
+    }
+
+    #[test]
+    fn check_ignore_names_the_matching_rule() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join(".gitignore"), "# build output\n*.log\n!keep.log\n").unwrap();
+
+        assert_eq!(
+            check_ignore(&repo, "debug.log").unwrap(),
+            IgnoreCheck::Ignored { source: ".gitignore".to_string(), line: 2, pattern: "*.log".to_string() }
+        );
+        assert_eq!(
+            check_ignore(&repo, "keep.log").unwrap(),
+            IgnoreCheck::Unignored { source: ".gitignore".to_string(), line: 3, pattern: "!keep.log".to_string() }
+        );
+        assert_eq!(check_ignore(&repo, "notes.txt").unwrap(), IgnoreCheck::NotIgnored);
+        assert_eq!(
+            serde_json::to_value(check_ignore(&repo, "debug.log").unwrap()).unwrap()["kind"],
+            "ignored"
+        );
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn head_advanced_reports_new_commits_only() {
+        let repo = init_repo_with_commit();
+        let mut last_seen = head_commit_id(&repo);
+        assert!(head_advanced(&repo, &mut last_seen).unwrap().is_none());
+
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "second"]);
+        let added = head_advanced(&repo, &mut last_seen).unwrap().unwrap();
+        assert_eq!(added.subject, "second");
+        assert_eq!(last_seen.as_deref(), Some(added.hash.as_str()));
+
+        // Moving back isn't a new commit, but the next one after it is
+        run_git(&repo, &["reset", "-q", "--hard", "HEAD~1"]);
+        assert!(head_advanced(&repo, &mut last_seen).unwrap().is_none());
+        run_git(&repo, &["commit", "-q", "--allow-empty", "-m", "third"]);
+        assert_eq!(head_advanced(&repo, &mut last_seen).unwrap().unwrap().subject, "third");
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn list_branches_reports_occupancy_and_tracking() {
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        let remote = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        run_git(&repo, &["clone", "-q", "--bare", repo.to_str().unwrap(), remote.to_str().unwrap()]);
+        run_git(&repo, &["remote", "add", "origin", remote.to_str().unwrap()]);
+        run_git(&repo, &["fetch", "-q", "origin"]);
+        run_git(&repo, &["branch", "-q", "--set-upstream-to", &format!("origin/{}", main)]);
+        run_git(&repo, &["commit", "-q", "--allow-empty", "-m", "local only"]);
+        let wt = repo.join(".worktrees").join("feature");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "feature", wt.to_str().unwrap()]);
+        run_git(&repo, &["branch", "free"]);
+
+        let branches = list_branches(&repo, false).unwrap();
+        let find = |name: &str| branches.iter().find(|b| b.name == name).unwrap();
+        assert_eq!(branches.len(), 3);
+        assert!(branches.iter().all(|b| !b.is_remote));
+
+        let current = find(&main);
+        assert!(current.is_current && current.is_checked_out_in_worktree);
+        assert_eq!(current.upstream.as_deref(), Some(format!("origin/{}", main).as_str()));
+        assert_eq!((current.ahead, current.behind), (1, 0));
+
+        let feature = find("feature");
+        assert!(!feature.is_current && feature.is_checked_out_in_worktree);
+        assert!(feature.worktree_path.as_deref().is_some_and(|p| p.ends_with("feature")));
+        assert!(!find("free").is_checked_out_in_worktree);
+
+        let with_remote = list_branches(&repo, true).unwrap();
+        let remote_entry = with_remote.iter().find(|b| b.is_remote).unwrap();
+        assert_eq!(remote_entry.name, format!("origin/{}", main));
+        assert!(!remote_entry.is_checked_out_in_worktree);
+        // Locals first
+        assert!(with_remote.last().unwrap().is_remote);
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+
+    #[test]
+    fn upstream_can_be_read_and_set() {
+        let repo = init_repo_with_commit();
+        let remote = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        run_git(&repo, &["clone", "-q", "--bare", repo.to_str().unwrap(), remote.to_str().unwrap()]);
+        run_git(&repo, &["remote", "add", "upstream", remote.to_str().unwrap()]);
+        run_git(&repo, &["checkout", "-q", "-b", "renamed"]);
+        run_git(&repo, &["push", "-q", "upstream", "renamed:tracked"]);
+        assert_eq!(get_upstream(&repo).unwrap(), None);
+
+        set_upstream(&repo, "upstream", "tracked").unwrap();
+        assert_eq!(get_upstream(&repo).unwrap().as_deref(), Some("upstream/tracked"));
+
+        assert!(matches!(set_upstream(&repo, "nope", "tracked"), Err(GitError::RemoteNotFound(_))));
+        assert!(matches!(
+            set_upstream(&repo, "upstream", "missing"),
+            Err(GitError::BranchNotFound(ref name)) if name == "upstream/missing"
+        ));
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+
+    #[test]
+    fn prunes_deleted_remote_branches_and_lists_gone_upstreams() {
+        let repo = init_repo_with_commit();
+        let remote = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        run_git(&repo, &["clone", "-q", "--bare", repo.to_str().unwrap(), remote.to_str().unwrap()]);
+        run_git(&repo, &["remote", "add", "origin", remote.to_str().unwrap()]);
+        for branch in ["merged", "checked-out", "alive"] {
+            run_git(&repo, &["branch", branch]);
+            run_git(&repo, &["push", "-q", "-u", "origin", branch]);
+        }
+        run_git(&repo, &["worktree", "add", "-q", ".worktrees/checked-out", "checked-out"]);
+        run_git(&remote, &["branch", "-D", "merged", "checked-out"]);
+
+        assert!(list_gone_branches(&repo).unwrap().is_empty());
+        let prune = |remote: &str| {
+            prune_remote_refs(&repo, remote, std::time::Duration::from_secs(30), &NetworkRetry::without_notices())
+        };
+        let mut pruned = prune("origin").unwrap();
+        pruned.sort();
+        assert_eq!(pruned, ["origin/checked-out", "origin/merged"]);
+
+        let gone = list_gone_branches(&repo).unwrap();
+        let names: Vec<&str> = gone.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["checked-out", "merged"]);
+        assert_eq!(gone[1].upstream, "origin/merged");
+        assert!(gone[0].worktree_path.as_deref().is_some_and(|p| p.ends_with("checked-out")));
+        assert_eq!(gone[1].worktree_path, None);
+
+        // Nothing left to prune the second time
+        assert!(prune("origin").unwrap().is_empty());
+        assert!(matches!(prune("nope"), Err(GitError::RemoteNotFound(_))));
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&remote);
+    }
+
+    #[test]
+    fn diff_stats_sum_changes_against_the_base_branch() {
+        let no_hiding = crate::path_utils::PathMatcher::new(&[]);
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        let base = BaseBranch::Named { name: main.clone() };
+        // On the base branch with nothing changed
+        assert_eq!(get_diff_stats(&repo, &base, &no_hiding).unwrap(), DiffStats::default());
+
+        run_git(&repo, &["checkout", "-q", "-b", "feature"]);
+        std::fs::write(repo.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "add a"]);
+        // Uncommitted changes count too
+        std::fs::write(repo.join("b.txt"), "b\n").unwrap();
+        run_git(&repo, &["add", "b.txt"]);
+        std::fs::write(repo.join("untracked.txt"), "u\n").unwrap();
+
+        // Same files as the changes panel: untracked ones count, without lines
+        let stats = get_diff_stats(&repo, &base, &no_hiding).unwrap();
+        assert_eq!(stats, DiffStats { files_changed: 3, total_insertions: 3, total_deletions: 0 });
+        // and hidden ones don't
+        let hide = crate::path_utils::PathMatcher::new(&["b.txt".to_string()]);
+        let stats = get_diff_stats(&repo, &base, &hide).unwrap();
+        assert_eq!(stats, DiffStats { files_changed: 2, total_insertions: 2, total_deletions: 0 });
+        assert!(matches!(
+            get_diff_stats(&repo, &BaseBranch::Named { name: "missing".to_string() }, &no_hiding),
+            Err(GitError::BranchNotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn auto_stash_stays_listed_after_failed_pop() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), "stashed\n").unwrap();
+        run_git(&repo, &["stash", "push", "-q", "-m", "unrelated"]);
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+
+        let stash_id = stash_changes(&repo).unwrap();
+        let stashes = list_auto_stashes(&repo).unwrap();
+        assert_eq!(stashes, vec![AutoStash { stash_ref: "stash@{0}".to_string(), stash_id: stash_id.clone() }]);
+
+        // Local edits to the same file make the pop fail and leave the stash behind
+        std::fs::write(repo.join("a.txt"), "conflicting\n").unwrap();
+        assert!(stash_pop(&repo, &stash_id).is_err());
+        assert_eq!(list_auto_stashes(&repo).unwrap().len(), 1);
+
+        // Once the working tree is clean the pop succeeds and the orphan is gone
+        run_git(&repo, &["checkout", "--", "a.txt"]);
+        stash_pop(&repo, &stash_id).unwrap();
+        assert!(list_auto_stashes(&repo).unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "two\n");
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn drop_stash_removes_only_the_auto_stash() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("b.txt"), "untracked\n").unwrap();
+        let stash_id = stash_changes(&repo).unwrap();
+
+        drop_stash(&repo, &stash_id).unwrap();
+        assert!(list_auto_stashes(&repo).unwrap().is_empty());
+        assert!(drop_stash(&repo, &stash_id).is_err());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn count_conflict_hunks_counts_markers_and_detects_binary() {
+        let text = b"a\n<<<<<<< HEAD\nx\n=======\ny\n>>>>>>> b\nc\n<<<<<<< HEAD\nz\n=======\n>>>>>>> b\n";
+        assert_eq!(count_conflict_hunks(text), (2, false));
+        assert_eq!(count_conflict_hunks(b"no conflicts\n"), (0, false));
+        assert_eq!(count_conflict_hunks(b"\x00\x01<<<<<<< HEAD\n"), (0, true));
+    }
+
+    #[test]
+    fn conflict_summary_reports_content_and_modify_delete() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("gone.txt"), "keep\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "add gone"]);
+        run_git(&repo, &["checkout", "-q", "-b", "other"]);
+        std::fs::write(repo.join("a.txt"), "theirs\n").unwrap();
+        std::fs::remove_file(repo.join("gone.txt")).unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "other"]);
+        run_git(&repo, &["checkout", "-q", "-"]);
+        std::fs::write(repo.join("a.txt"), "ours\n").unwrap();
+        std::fs::write(repo.join("gone.txt"), "changed\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "ours"]);
+
+        assert!(!git_succeeds(&repo, &["merge", "other"]));
+
+        let summary = get_conflict_summary(&repo).unwrap();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].path, "a.txt");
+        assert_eq!(summary[0].kind, ConflictKind::Content);
+        assert_eq!(summary[0].hunks, 1);
+        assert!(summary[0].auto_resolvable);
+        assert_eq!(summary[1].path, "gone.txt");
+        assert_eq!(summary[1].kind, ConflictKind::ModifyDelete);
+        assert!(!summary[1].auto_resolvable);
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn mark_resolved_rejects_markers_then_stages() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["checkout", "-q", "-b", "other"]);
+        std::fs::write(repo.join("a.txt"), "theirs\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "other"]);
+        run_git(&repo, &["checkout", "-q", "-"]);
+        std::fs::write(repo.join("a.txt"), "ours\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "ours"]);
+        assert!(!git_succeeds(&repo, &["merge", "other"]));
+
+        let err = mark_resolved(&repo, "a.txt").unwrap_err().to_string();
+        assert!(err.contains("line(s) 1, 3, 5"), "{}", err);
+        assert_eq!(get_conflicted_files(&repo).unwrap(), vec!["a.txt".to_string()]);
+
+        std::fs::write(repo.join("a.txt"), "ours and theirs\n").unwrap();
+        assert!(mark_resolved(&repo, "a.txt").unwrap());
+        assert!(get_conflicted_files(&repo).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    /// Create `other` and the current branch with conflicting edits to a.txt.
+    /// Returns the name of the original branch.
+    fn make_conflicting_branches(repo: &Path) -> String {
+        let output = git_command().args(["branch", "--show-current"]).current_dir(repo).output().unwrap();
+        let original = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        run_git(repo, &["checkout", "-q", "-b", "other"]);
+        std::fs::write(repo.join("a.txt"), "theirs\n").unwrap();
+        run_git(repo, &["commit", "-q", "-am", "other"]);
+        run_git(repo, &["checkout", "-q", &original]);
+        std::fs::write(repo.join("a.txt"), "ours\n").unwrap();
+        run_git(repo, &["commit", "-q", "-am", "ours"]);
+        original
+    }
+
+    #[test]
+    fn continue_merge_reports_unresolved_then_completes() {
+        let repo = init_repo_with_commit();
+        make_conflicting_branches(&repo);
+        assert!(!git_succeeds(&repo, &["merge", "other"]));
+
+        let result = continue_merge(&repo).unwrap();
+        assert!(!result.completed);
+        assert_eq!(result.conflicted_files, vec!["a.txt".to_string()]);
+
+        std::fs::write(repo.join("a.txt"), "merged\n").unwrap();
+        mark_resolved(&repo, "a.txt").unwrap();
+        let result = continue_merge(&repo).unwrap();
+        assert!(result.completed);
+        assert!(!repo.join(".git").join("MERGE_HEAD").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn continue_rebase_stops_on_next_conflict() {
+        let repo = init_repo_with_commit();
+        let original = make_conflicting_branches(&repo);
+        // A second commit on `other` that conflicts again after the first is resolved
+        run_git(&repo, &["checkout", "-q", "other"]);
+        std::fs::write(repo.join("a.txt"), "theirs again\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "other 2"]);
+        assert!(!git_succeeds(&repo, &["rebase", &original]));
+
+        std::fs::write(repo.join("a.txt"), "resolved\n").unwrap();
+        mark_resolved(&repo, "a.txt").unwrap();
+        let result = continue_rebase(&repo).unwrap();
+        assert!(!result.completed);
+        assert_eq!(result.conflicted_files, vec!["a.txt".to_string()]);
+
+        std::fs::write(repo.join("a.txt"), "resolved again\n").unwrap();
+        mark_resolved(&repo, "a.txt").unwrap();
+        assert!(continue_rebase(&repo).unwrap().completed);
+        assert!(!repo.join(".git").join("rebase-merge").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn push_remote_follows_git_precedence() {
+        let repo_path = init_repo_with_commit();
+        let repo = Repository::open(&repo_path).unwrap();
+        let branch = get_current_branch(&repo).unwrap();
+        assert_eq!(push_remote_for_branch(&repo, &branch), "origin");
+
+        run_git(&repo_path, &["config", &format!("branch.{}.remote", branch), "upstream"]);
+        assert_eq!(push_remote_for_branch(&repo, &branch), "upstream");
+
+        run_git(&repo_path, &["config", "remote.pushDefault", "fork"]);
+        assert_eq!(push_remote_for_branch(&repo, &branch), "fork");
+
+        run_git(&repo_path, &["config", &format!("branch.{}.pushRemote", branch), "mirror"]);
+        assert_eq!(push_remote_for_branch(&repo, &branch), "mirror");
+        let _ = std::fs::remove_dir_all(&repo_path);
+    }
+
+    #[test]
+    fn push_checked_out_branch_uses_push_remote() {
+        let repo = init_repo_with_commit();
+        // No remote configured: the push fails with git's message
+        let err = push_checked_out_branch(&repo, &NetworkRetry::without_notices()).unwrap_err().to_string();
+        assert!(err.contains("git push origin"), "{}", err);
+
+        let bare = std::env::temp_dir().join(format!("shellflow-git-bare-{}", uuid::Uuid::new_v4()));
+        run_git(&repo, &["init", "-q", "--bare", bare.to_str().unwrap()]);
+        run_git(&repo, &["remote", "add", "backup", bare.to_str().unwrap()]);
+        run_git(&repo, &["config", "remote.pushDefault", "backup"]);
+        push_checked_out_branch(&repo, &NetworkRetry::without_notices()).unwrap();
+
+        let branch = get_current_branch(&Repository::open(&repo).unwrap()).unwrap();
+        let pushed = Repository::open_bare(&bare).unwrap();
+        assert!(pushed.find_branch(&branch, git2::BranchType::Local).is_ok());
+        let _ = std::fs::remove_dir_all(&bare);
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn needs_stash_ignores_untracked_files() {
+        let repo = init_repo_with_commit();
+        assert!(!needs_stash(&repo).unwrap());
+
+        std::fs::write(repo.join("new.txt"), "untracked\n").unwrap();
+        assert!(!needs_stash(&repo).unwrap());
+
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        assert!(needs_stash(&repo).unwrap());
+
+        run_git(&repo, &["checkout", "--", "a.txt"]);
+        run_git(&repo, &["add", "new.txt"]);
+        assert!(needs_stash(&repo).unwrap());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn skip_rebase_commit_finishes_rebase() {
+        let repo = init_repo_with_commit();
+        let original = make_conflicting_branches(&repo);
+        run_git(&repo, &["checkout", "-q", "other"]);
+        assert!(!git_succeeds(&repo, &["rebase", &original]));
+        assert!(is_rebase_in_progress(&repo).unwrap());
+
+        // Dropping the only conflicting commit completes the rebase
+        let result = skip_rebase_commit(&repo).unwrap();
+        assert!(result.completed);
+        assert!(!is_rebase_in_progress(&repo).unwrap());
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "ours\n");
+
+        assert!(matches!(skip_rebase_commit(&repo), Err(GitError::NoRebaseInProgress)));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn continue_cherry_pick_completes_after_resolution() {
+        let repo = init_repo_with_commit();
+        make_conflicting_branches(&repo);
+        assert!(!git_succeeds(&repo, &["cherry-pick", "other"]));
+
+        std::fs::write(repo.join("a.txt"), "picked\n").unwrap();
+        mark_resolved(&repo, "a.txt").unwrap();
+        assert!(continue_cherry_pick(&repo).unwrap().completed);
+        assert!(!repo.join(".git").join("CHERRY_PICK_HEAD").exists());
+        assert!(continue_cherry_pick(&repo).is_err());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn find_conflict_markers_ignores_lookalikes() {
+        let content = "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> other\n========\n  <<<<<<< indented\n";
+        assert_eq!(find_conflict_markers(content), vec![1, 3, 5]);
+    }
 
     #[test]
     fn file_status_serializes_to_lowercase() {
@@ -1325,6 +4868,7 @@ mod tests {
             status: FileStatus::Modified,
             insertions: Some(10),
             deletions: Some(5),
+            hidden: false,
         };
 
         let json = serde_json::to_value(&change).unwrap();
@@ -1341,6 +4885,7 @@ mod tests {
             status: FileStatus::Untracked,
             insertions: None,
             deletions: None,
+            hidden: false,
         };
 
         let json = serde_json::to_value(&change).unwrap();
@@ -1560,4 +5105,909 @@ detached
         assert_eq!(parsed[1].path, "/repo/.worktrees/hotfix");
         assert_eq!(parsed[1].branch, None);
     }
+
+    #[test]
+    fn create_worktree_rejects_existing_branch_unless_adopting() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["branch", "feature-x"]);
+        let wt_path = repo.join(".worktrees").join("feature-x");
+        let base = BaseBranch::default();
+
+        match create_worktree(&repo, &wt_path, "feature-x", &base, false) {
+            Err(GitError::BranchAlreadyExists(branch)) => assert_eq!(branch, "feature-x"),
+            other => panic!("expected BranchAlreadyExists, got {:?}", other),
+        }
+        assert!(!wt_path.exists());
+
+        create_worktree(&repo, &wt_path, "feature-x", &base, true).unwrap();
+        let wt_repo = Repository::open(&wt_path).unwrap();
+        assert_eq!(get_current_branch(&wt_repo).unwrap(), "feature-x");
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn create_worktree_reports_where_adopted_branch_is_checked_out() {
+        let repo = init_repo_with_commit();
+        let main = get_current_branch(&Repository::open(&repo).unwrap()).unwrap();
+        let wt_path = repo.join(".worktrees").join("other");
+
+        match create_worktree(&repo, &wt_path, &main, &BaseBranch::default(), true) {
+            Err(GitError::BranchInUse { branch, worktree_path }) => {
+                assert_eq!(branch, main);
+                assert_eq!(
+                    std::fs::canonicalize(&worktree_path).unwrap(),
+                    std::fs::canonicalize(&repo).unwrap()
+                );
+            }
+            other => panic!("expected BranchInUse, got {:?}", other),
+        }
+        assert!(!wt_path.exists());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn create_worktree_for_branch_checks_out_local_and_remote_branches() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["branch", "feature/login"]);
+        let wt_path = repo.join(".worktrees").join("brave-otter");
+
+        create_worktree_for_branch(&repo, &wt_path, "feature/login").unwrap();
+        assert_eq!(get_current_branch(&Repository::open(&wt_path).unwrap()).unwrap(), "feature/login");
+        match create_worktree_for_branch(&repo, &repo.join(".worktrees").join("again"), "feature/login") {
+            Err(GitError::BranchInUse { branch, .. }) => assert_eq!(branch, "feature/login"),
+            other => panic!("expected BranchInUse, got {:?}", other),
+        }
+
+        // A teammate's branch known only as origin/teammate
+        run_git(&repo, &["branch", "teammate"]);
+        run_git(&repo, &["remote", "add", "origin", &repo.to_string_lossy()]);
+        run_git(&repo, &["fetch", "-q", "origin"]);
+        run_git(&repo, &["branch", "-D", "teammate"]);
+        let remote_wt = repo.join(".worktrees").join("calm-heron");
+        create_worktree_for_branch(&repo, &remote_wt, "teammate").unwrap();
+        assert_eq!(get_current_branch(&Repository::open(&remote_wt).unwrap()).unwrap(), "teammate");
+
+        assert!(matches!(
+            create_worktree_for_branch(&repo, &repo.join(".worktrees").join("none"), "missing"),
+            Err(GitError::BranchNotFound(_))
+        ));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    fn local_branches(repo: &Path) -> Vec<String> {
+        let repo = Repository::open(repo).unwrap();
+        let mut names: Vec<String> = repo
+            .branches(Some(BranchType::Local))
+            .unwrap()
+            .map(|b| b.unwrap().0.name().unwrap().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn case_only_rename_goes_through_intermediate_name() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["branch", "Feature-X"]);
+
+        rename_branch_with(&repo, "Feature-X", "feature-x", true).unwrap();
+
+        let branches = local_branches(&repo);
+        assert!(branches.contains(&"feature-x".to_string()));
+        assert!(!branches.iter().any(|b| b == "Feature-X" || b.contains("-tmp-")));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn branch_name_check_distinguishes_duplicates_from_case_variants() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["branch", "Feature-X"]);
+
+        assert!(matches!(
+            ensure_branch_name_available_with(&repo, "Feature-X", None, true),
+            Err(GitError::BranchAlreadyExists(_))
+        ));
+        match ensure_branch_name_available_with(&repo, "feature-x", None, true) {
+            Err(GitError::BranchCaseConflict { requested, existing }) => {
+                assert_eq!(requested, "feature-x");
+                assert_eq!(existing, "Feature-X");
+            }
+            other => panic!("expected BranchCaseConflict, got {:?}", other),
+        }
+        // Case-sensitive storage keeps both names apart
+        ensure_branch_name_available_with(&repo, "feature-x", None, false).unwrap();
+        // Renaming the variant itself is allowed
+        ensure_branch_name_available_with(&repo, "feature-x", Some("Feature-X"), true).unwrap();
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn refs_case_insensitive_probe_leaves_no_files() {
+        let repo = init_repo_with_commit();
+        assert!(!refs_case_insensitive(&repo));
+        let leftovers = std::fs::read_dir(repo.join(".git"))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("shellflow-case-probe"))
+            .count();
+        assert_eq!(leftovers, 0);
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn author_identity_is_stable_and_keyed_by_email() {
+        let id = author_id("Ada Lovelace", "Ada@Example.com");
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, author_id("ada", " ada@example.com"));
+        assert_ne!(id, author_id("Ada Lovelace", "other@example.com"));
+        // Without an email the name identifies the author
+        assert_eq!(author_id("Ada", ""), author_id("ada", "  "));
+        assert!(author_color_index(&id) < AUTHOR_COLOR_COUNT);
+        assert_eq!(author_color_index(&id), author_color_index(&id.clone()));
+    }
+
+    #[test]
+    fn author_initials_and_display_name() {
+        assert_eq!(author_initials("ada lovelace"), "AL");
+        assert_eq!(author_initials("Jean-Luc de Picard"), "JP");
+        assert_eq!(author_initials("linus"), "L");
+        assert_eq!(author_initials("张三"), "张");
+        assert_eq!(author_initials("Émile Zola"), "ÉZ");
+        assert_eq!(author_initials("  "), "?");
+
+        assert_eq!(author_display_name(" Ada ", "ada@example.com"), "Ada");
+        assert_eq!(author_display_name("", "ada.l@example.com"), "ada.l");
+        assert_eq!(author_display_name("", ""), "Unknown");
+        assert_eq!(derive_author_identity("", "").initials, "U");
+    }
+
+    #[test]
+    fn recent_commits_omit_email_unless_requested() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("b.txt"), "two\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "second"]);
+
+        let commits = recent_commits(&repo, 1, false).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "second");
+        assert_eq!(commits[0].author.display_name, "shellflow");
+        assert_eq!(commits[0].author.initials, "S");
+        let value = serde_json::to_value(&commits[0]).unwrap();
+        assert!(value.get("authorEmail").is_none());
+        assert!(value["author"]["authorId"].is_string());
+
+        let with_email = recent_commits(&repo, 10, true).unwrap();
+        assert_eq!(with_email.len(), 2);
+        assert_eq!(with_email[1].author_email.as_deref(), Some("shellflow@example.com"));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn commit_log_pages_through_history_in_worktrees() {
+        let empty = std::env::temp_dir().join(format!("shellflow-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&empty).unwrap();
+        run_git(&empty, &["init", "-q"]);
+        assert!(get_commit_log(&empty, 10, 0).unwrap().is_empty());
+
+        let repo = init_repo_with_commit();
+        for name in ["second", "third"] {
+            std::fs::write(repo.join(format!("{}.txt", name)), name).unwrap();
+            run_git(&repo, &["add", "."]);
+            run_git(&repo, &["commit", "-q", "-m", name, "-m", "Details\n\nMore"]);
+        }
+        let linked = repo.join(".worktrees").join("linked");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "linked", linked.to_str().unwrap()]);
+
+        for path in [&repo, &linked] {
+            let first_page = get_commit_log(path, 2, 0).unwrap();
+            assert_eq!(first_page.iter().map(|c| c.subject.as_str()).collect::<Vec<_>>(), vec!["third", "second"]);
+            assert_eq!(first_page[0].body.as_deref(), Some("Details\n\nMore"));
+            assert!(first_page[0].hash.starts_with(&first_page[0].short_hash));
+            assert_eq!(first_page[0].email, "shellflow@example.com");
+
+            let rest = get_commit_log(path, 2, 2).unwrap();
+            assert_eq!(rest.len(), 1);
+            assert_eq!(rest[0].subject, "init");
+            assert_eq!(rest[0].body, None);
+        }
+
+        let _ = std::fs::remove_dir_all(&repo);
+        let _ = std::fs::remove_dir_all(&empty);
+    }
+
+    #[test]
+    fn branch_commit_log_leaves_out_the_base() {
+        let repo = init_repo_with_commit();
+        let wt = repo.join(".worktrees").join("feature");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "feature", wt.to_str().unwrap()]);
+        for name in ["one", "two"] {
+            std::fs::write(wt.join(format!("{}.txt", name)), name).unwrap();
+            run_git(&wt, &["add", "."]);
+            run_git(&wt, &["commit", "-q", "-m", name]);
+        }
+        // Base commits made after branching aren't the branch's
+        std::fs::write(repo.join("base.txt"), "base").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "base"]);
+
+        let commits = get_branch_commit_log(&wt, &BaseBranch::default(), 10).unwrap();
+        assert_eq!(commits.iter().map(|c| c.subject.as_str()).collect::<Vec<_>>(), vec!["two", "one"]);
+        assert_eq!(get_branch_commit_log(&wt, &BaseBranch::default(), 1).unwrap().len(), 1);
+        // On the base branch itself there is nothing to list
+        assert!(get_branch_commit_log(&repo, &BaseBranch::default(), 10).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn tally_languages_groups_by_extension() {
+        let paths: Vec<String> = ["src/a.rs", "src/b.rs", "web/app.tsx", "README"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let stats = tally_languages(&paths, Some(&[10, 20, 5, 1]));
+        assert_eq!(
+            stats[0],
+            LanguageStat { language: "rust".to_string(), files: 2, bytes: Some(30) }
+        );
+        // Ties are ordered by name
+        assert_eq!(stats[1].language, "plaintext");
+        assert_eq!(stats[2].language, "typescript");
+
+        assert!(tally_languages(&paths, None).iter().all(|s| s.bytes.is_none()));
+    }
+
+    #[test]
+    fn language_stats_recompute_only_after_a_commit() {
+        let repo = init_repo_with_commit();
+        let first = language_stats(&repo, true).unwrap();
+        assert_eq!(first.total_files, 1);
+        assert_eq!(first.total_bytes, Some(4));
+        assert_eq!(first.languages[0].language, "plaintext");
+
+        // Newly tracked files are picked up once HEAD moves
+        std::fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        assert_eq!(language_stats(&repo, true).unwrap(), first);
+        run_git(&repo, &["commit", "-q", "-m", "add rust"]);
+        let second = language_stats(&repo, false).unwrap();
+        assert_eq!(second.total_files, 2);
+        assert_eq!(second.total_bytes, None);
+        assert_ne!(second.head, first.head);
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    /// Hunks of a one-file diff as (header, lines)
+    fn diff_hunks(repo: &Path, args: &[&str]) -> Vec<(String, Vec<String>)> {
+        let output = Command::new("git").args(args).current_dir(repo).output().unwrap();
+        let mut hunks: Vec<(String, Vec<String>)> = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if line.starts_with("@@") {
+                hunks.push((line.to_string(), Vec::new()));
+            } else if let Some((_, lines)) = hunks.last_mut() {
+                lines.push(line.to_string());
+            }
+        }
+        hunks
+    }
+
+    fn numbered_lines(changed: &[usize]) -> String {
+        (1..=20)
+            .map(|i| if changed.contains(&i) { format!("changed {}\n", i) } else { format!("line {}\n", i) })
+            .collect()
+    }
+
+    #[test]
+    fn stages_and_unstages_a_single_hunk() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), numbered_lines(&[])).unwrap();
+        run_git(&repo, &["commit", "-qam", "twenty lines"]);
+        std::fs::write(repo.join("a.txt"), numbered_lines(&[2, 18])).unwrap();
+
+        let hunks = diff_hunks(&repo, &["diff", "a.txt"]);
+        assert_eq!(hunks.len(), 2);
+        stage_hunk(&repo, "a.txt", &hunks[1].0, &hunks[1].1).unwrap();
+
+        let staged = diff_hunks(&repo, &["diff", "--cached", "a.txt"]);
+        assert_eq!(staged.len(), 1);
+        assert!(staged[0].1.contains(&"+changed 18".to_string()));
+        // The other hunk is still only in the worktree
+        assert_eq!(diff_hunks(&repo, &["diff", "a.txt"]).len(), 1);
+
+        unstage_hunk(&repo, "a.txt", &staged[0].0, &staged[0].1).unwrap();
+        assert!(diff_hunks(&repo, &["diff", "--cached"]).is_empty());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn stages_and_unstages_hunks_of_new_files() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("new.txt"), "one\ntwo\n").unwrap();
+        let hunks = diff_hunks(&repo, &["diff", "--no-index", "/dev/null", "new.txt"]);
+        stage_hunk(&repo, "new.txt", &hunks[0].0, &hunks[0].1).unwrap();
+
+        let staged = diff_hunks(&repo, &["diff", "--cached", "new.txt"]);
+        assert_eq!(staged[0].1, vec!["+one", "+two"]);
+        unstage_hunk(&repo, "new.txt", &staged[0].0, &staged[0].1).unwrap();
+        // Back to untracked, not an empty file in the index
+        assert!(git_stdout(&repo, &["ls-files", "new.txt"]).is_empty());
+        assert!(repo.join("new.txt").exists());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn stages_hunks_of_paths_git_quotes() {
+        let repo = init_repo_with_commit();
+        for name in ["with space.txt", "quo\"te.txt", "caf\u{e9}.txt"] {
+            std::fs::write(repo.join(name), numbered_lines(&[])).unwrap();
+            run_git(&repo, &["add", name]);
+            run_git(&repo, &["commit", "-qm", name]);
+            std::fs::write(repo.join(name), numbered_lines(&[5])).unwrap();
+
+            let hunks = diff_hunks(&repo, &["diff", "--", name]);
+            stage_hunk(&repo, name, &hunks[0].0, &hunks[0].1).unwrap();
+            assert!(diff_hunks(&repo, &["diff", "--", name]).is_empty(), "{}", name);
+        }
+        assert_eq!(patch_header_path("a/", "quo\"te.txt"), "\"a/quo\\\"te.txt\"");
+        assert_eq!(patch_header_path("b/", "caf\u{e9}.txt"), "\"b/caf\\303\\251.txt\"");
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn staged_file_diff_covers_new_files_and_nothing_staged() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), "unstaged\n").unwrap();
+        assert_eq!(staged_file_diff(&repo, "a.txt").unwrap(), "");
+
+        std::fs::write(repo.join("new.txt"), "fresh\n").unwrap();
+        run_git(&repo, &["add", "new.txt"]);
+        let diff = staged_file_diff(&repo, "new.txt").unwrap();
+        assert!(diff.contains("new file mode"));
+        assert!(diff.contains("+fresh"));
+        // Only the requested file is included
+        assert!(!diff.contains("a.txt"));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn stale_hunks_ask_for_a_retry() {
+        let repo = init_repo_with_commit();
+        std::fs::write(repo.join("a.txt"), numbered_lines(&[])).unwrap();
+        run_git(&repo, &["commit", "-qam", "twenty lines"]);
+        std::fs::write(repo.join("a.txt"), numbered_lines(&[10])).unwrap();
+        let hunks = diff_hunks(&repo, &["diff", "a.txt"]);
+
+        // The index moves under the hunk's context before it is applied
+        std::fs::write(repo.join("a.txt"), numbered_lines(&[9, 10, 11])).unwrap();
+        run_git(&repo, &["add", "a.txt"]);
+        let err = stage_hunk(&repo, "a.txt", &hunks[0].0, &hunks[0].1).unwrap_err();
+        assert!(matches!(err, GitError::HunkOutdated(ref path) if path == "a.txt"));
+
+        assert!(build_hunk_patch("a.txt", "not a header", &hunks[0].1, None).is_err());
+        assert!(build_hunk_patch("a.txt", &hunks[0].0, &["oops".to_string()], None).is_err());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn parses_porcelain_v2_records() {
+        let output = [
+            "# branch.oid 1234abcd",
+            "# branch.head feature/x",
+            "# branch.upstream origin/feature/x",
+            "# branch.ab +2 -5",
+            "# stash 3",
+            "1 .M N... 100644 100644 100644 aaa aaa src/with space.rs",
+            "2 R. N... 100644 100644 100644 bbb bbb R87 new/name.rs",
+            "old/name.rs",
+            "1 M. SC.U 160000 160000 160000 ccc ddd vendor/lib",
+            "u UU N... 100644 100644 100644 100644 e1 e2 e3 conflict.txt",
+            "? notes.md",
+            "! target/",
+            "",
+        ]
+        .join("\0");
+
+        let status = parse_status_v2(&output);
+        assert_eq!(status.branch.head.as_deref(), Some("feature/x"));
+        assert_eq!(status.branch.upstream.as_deref(), Some("origin/feature/x"));
+        assert_eq!((status.branch.ahead, status.branch.behind), (Some(2), Some(5)));
+        assert_eq!(status.stash_count, 3);
+
+        let kinds: Vec<_> = status.entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                StatusEntryKind::Ordinary,
+                StatusEntryKind::Renamed,
+                StatusEntryKind::Ordinary,
+                StatusEntryKind::Unmerged,
+                StatusEntryKind::Untracked,
+                StatusEntryKind::Ignored,
+            ]
+        );
+        assert_eq!(status.entries[0].path, "src/with space.rs");
+        assert_eq!(status.entries[1].orig_path.as_deref(), Some("old/name.rs"));
+        assert_eq!(status.entries[1].score, Some(87));
+        let submodule = status.entries[2].submodule.unwrap();
+        assert!(submodule.commit_changed && !submodule.tracked_changes && submodule.untracked_changes);
+        assert_eq!(status.entries[3].oids, vec!["e1", "e2", "e3"]);
+        assert_eq!(status.entries[4].xy, "??");
+    }
+
+    #[test]
+    fn status_v2_in_a_fresh_repo() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["mv", "a.txt", "b.txt"]);
+        std::fs::write(repo.join("new.txt"), "x").unwrap();
+
+        let status = status_v2(&repo).unwrap();
+        assert!(status.branch.oid.is_some());
+        assert_eq!(status.branch.upstream, None);
+        assert_eq!(status.branch.ahead, None);
+        let renamed = status.entries.iter().find(|e| e.kind == StatusEntryKind::Renamed).unwrap();
+        assert_eq!((renamed.path.as_str(), renamed.orig_path.as_deref()), ("b.txt", Some("a.txt")));
+        assert!(status.entries.iter().any(|e| e.kind == StatusEntryKind::Untracked && e.path == "new.txt"));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn base_drift_counts_commits_behind_the_base() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["branch", "-M", "main"]);
+        run_git(&repo, &["checkout", "-q", "-b", "feature"]);
+        run_git(&repo, &["commit", "-q", "--allow-empty", "-m", "feature work"]);
+        run_git(&repo, &["checkout", "-q", "main"]);
+        for n in 0..3 {
+            run_git(&repo, &["commit", "-q", "--allow-empty", "-m", &format!("main {}", n)]);
+        }
+        run_git(&repo, &["checkout", "-q", "feature"]);
+
+        let base = BaseBranch::Named { name: "main".to_string() };
+        let drift = base_drift(&repo, &base).unwrap();
+        assert_eq!((drift.base_branch.as_str(), drift.ahead, drift.behind), ("main", 1, 3));
+        assert!(drift.head_time > 0);
+
+        let missing = BaseBranch::Named { name: "develop".to_string() };
+        assert!(matches!(base_drift(&repo, &missing), Err(GitError::BranchNotFound(_))));
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn editor_follows_git_precedence() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(choose_editor(Some("nano".into()), env(&[("GIT_EDITOR", "vim"), ("EDITOR", "ed")])), "vim");
+        assert_eq!(choose_editor(Some("nano".into()), env(&[("VISUAL", "emacs")])), "nano");
+        assert_eq!(choose_editor(Some(" ".into()), env(&[("GIT_EDITOR", ""), ("EDITOR", "ed")])), "ed");
+        assert_eq!(choose_editor(None, env(&[])), FALLBACK_EDITOR);
+    }
+
+    #[test]
+    fn worktree_scoped_config_stays_in_its_worktree() {
+        let repo = init_repo_with_commit();
+        let wt_a = repo.join(".worktrees").join("a");
+        let wt_b = repo.join(".worktrees").join("b");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "a", wt_a.to_str().unwrap()]);
+        run_git(&repo, &["worktree", "add", "-q", "-b", "b", wt_b.to_str().unwrap()]);
+        assert!(!worktree_config_enabled(&repo));
+
+        run_git(&repo, &["config", "extensions.worktreeConfig", "true"]);
+        run_git(&wt_a, &["config", "--worktree", "commit.gpgsign", "true"]);
+        run_git(&wt_a, &["config", "--worktree", "remote.pushDefault", "fork"]);
+        assert!(worktree_config_enabled(&wt_b));
+        assert_eq!(get_config(&wt_a, "commit.gpgsign", None).unwrap().as_deref(), Some("true"));
+        assert_eq!(get_config(&wt_b, "commit.gpgsign", None).unwrap(), None);
+        assert_eq!(get_config(&repo, "commit.gpgsign", None).unwrap(), None);
+        // libgit2 readers see the same per-worktree values
+        let config = Repository::open(&wt_a).unwrap().config().unwrap();
+        assert!(config.get_bool("commit.gpgsign").unwrap());
+        assert_eq!(push_remote_for_branch(&Repository::open(&wt_a).unwrap(), "a"), "fork");
+        assert_eq!(push_remote_for_branch(&Repository::open(&wt_b).unwrap(), "b"), "origin");
+
+        // The worktree value overrides the shared one only where it is set
+        run_git(&repo, &["config", "commit.gpgsign", "false"]);
+        assert_eq!(get_config(&wt_a, "commit.gpgsign", None).unwrap().as_deref(), Some("true"));
+        assert_eq!(get_config(&wt_b, "commit.gpgsign", None).unwrap().as_deref(), Some("false"));
+        assert_eq!(get_config(&wt_b, "commit.gpgsign", Some(GitConfigScope::Worktree)).unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn worktree_scope_without_the_extension_reads_as_unset() {
+        let repo = init_repo_with_commit();
+        run_git(&repo, &["config", "core.hooksPath", "hooks"]);
+        // Plain `git config --worktree` would fall back to the shared config here
+        assert_eq!(get_config(&repo, "core.hooksPath", Some(GitConfigScope::Worktree)).unwrap(), None);
+        assert_eq!(get_config(&repo, "core.hooksPath", Some(GitConfigScope::Local)).unwrap().as_deref(), Some("hooks"));
+        assert!(!worktree_config_enabled(&repo));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn restore_file_from_commit_touches_only_that_file() {
+        let repo = init_repo_with_commit();
+        let first = git_stdout(&repo, &["rev-parse", "HEAD"]);
+        std::fs::write(repo.join("a.txt"), "two\n").unwrap();
+        std::fs::write(repo.join("b.txt"), "bee\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "second"]);
+        let second = git_stdout(&repo, &["rev-parse", "HEAD"]);
+        std::fs::write(repo.join("a.txt"), "three\n").unwrap();
+        std::fs::write(repo.join("b.txt"), "bee, edited\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "third"]);
+
+        let restored = restore_file_from_commit(&repo, "a.txt", &first[..8], RestoreMode::Working).unwrap();
+        assert_eq!(restored.sha, first);
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "one\n");
+        assert_eq!(std::fs::read_to_string(repo.join("b.txt")).unwrap(), "bee, edited\n");
+        assert_eq!(git_stdout(&repo, &["diff", "--cached", "--name-only"]), "");
+        assert_eq!(git_stdout(&repo, &["diff", "--name-only"]), "a.txt");
+
+        restore_file_from_commit(&repo, "a.txt", &second, RestoreMode::Staged).unwrap();
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "two\n");
+        assert_eq!(git_stdout(&repo, &["diff", "--cached", "--name-only"]), "a.txt");
+        assert_eq!(git_stdout(&repo, &["diff", "--name-only"]), "");
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn restore_file_from_commit_validates_commit_and_path() {
+        let repo = init_repo_with_commit();
+        let first = git_stdout(&repo, &["rev-parse", "HEAD"]);
+        run_git(&repo, &["checkout", "-q", "-b", "side"]);
+        run_git(&repo, &["commit", "-q", "--allow-empty", "-m", "side only"]);
+        let side = git_stdout(&repo, &["rev-parse", "HEAD"]);
+        run_git(&repo, &["checkout", "-q", "-"]);
+        run_git(&repo, &["mv", "a.txt", "renamed.txt"]);
+        std::fs::write(repo.join("new.txt"), "new\n").unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "rename"]);
+
+        assert!(matches!(
+            restore_file_from_commit(&repo, "renamed.txt", &side, RestoreMode::Working),
+            Err(GitError::RefNotReachable(_))
+        ));
+        assert!(matches!(
+            restore_file_from_commit(&repo, "renamed.txt", "no-such-ref", RestoreMode::Working),
+            Err(GitError::RefNotReachable(_))
+        ));
+        assert!(matches!(
+            restore_file_from_commit(&repo, "new.txt", &first, RestoreMode::Working),
+            Err(GitError::PathNotInCommit { renamed_from: None, .. })
+        ));
+
+        // A renamed file gets the content of its old path
+        std::fs::write(repo.join("renamed.txt"), "changed\n").unwrap();
+        let restored = restore_file_from_commit(&repo, "renamed.txt", &first, RestoreMode::Working).unwrap();
+        assert_eq!(restored.source_path, "a.txt");
+        assert_eq!(std::fs::read_to_string(repo.join("renamed.txt")).unwrap(), "one\n");
+        assert!(!repo.join("a.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn merge_blockers_follow_the_facts() {
+        let ready = MergeFacts {
+            current_branch: Some("feature".to_string()),
+            target_branch: "main".to_string(),
+            target_exists: true,
+            commits_ahead: 2,
+            target_behind_upstream: Some(("origin/main".to_string(), 0)),
+            ..Default::default()
+        };
+        assert!(merge_blockers(&ready).is_empty());
+        assert!(MergeFeasibility::from_facts(ready.clone()).can_merge);
+
+        let blocked = |change: fn(&mut MergeFacts)| {
+            let mut facts = ready.clone();
+            change(&mut facts);
+            merge_blockers(&facts)
+        };
+        assert_eq!(blocked(|f| f.current_branch = None), vec![MergeBlocker::DetachedHead]);
+        assert_eq!(
+            blocked(|f| f.current_branch = Some("main".to_string())),
+            vec![MergeBlocker::OnTargetBranch { branch: "main".to_string() }]
+        );
+        assert_eq!(
+            blocked(|f| f.dirty_files = 3),
+            vec![MergeBlocker::UncommittedChanges { file_count: 3 }]
+        );
+        assert_eq!(
+            blocked(|f| f.commits_ahead = 0),
+            vec![MergeBlocker::NothingToMerge { target_branch: "main".to_string() }]
+        );
+        // A stale target only warns; it matters when pushing after the merge
+        let mut stale = ready.clone();
+        stale.target_behind_upstream = Some(("origin/main".to_string(), 4));
+        assert!(merge_blockers(&stale).is_empty());
+        assert_eq!(
+            merge_warnings(&stale),
+            vec![MergeBlocker::BehindRemote {
+                branch: "main".to_string(),
+                upstream: "origin/main".to_string(),
+                count: 4,
+            }]
+        );
+        assert!(MergeFeasibility::from_facts(stale).can_merge);
+        assert!(merge_warnings(&ready).is_empty());
+        assert_eq!(
+            blocked(|f| f.operation_in_progress = Some("rebase".to_string())),
+            vec![MergeBlocker::OperationInProgress { operation: "rebase".to_string() }]
+        );
+        assert_eq!(
+            blocked(|f| f.workflow_in_progress = Some("merge".to_string())),
+            vec![MergeBlocker::WorkflowInProgress { workflow: "merge".to_string() }]
+        );
+        // A missing target has no ahead count to report as nothing to merge
+        assert_eq!(
+            blocked(|f| {
+                f.target_exists = false;
+                f.commits_ahead = 0;
+            }),
+            vec![MergeBlocker::TargetBranchMissing { branch: "main".to_string() }]
+        );
+
+        let all = blocked(|f| {
+            f.dirty_files = 1;
+            f.commits_ahead = 0;
+            f.workflow_in_progress = Some("cleanup".to_string());
+        });
+        assert_eq!(all.len(), 3);
+        assert!(matches!(all[0], MergeBlocker::WorkflowInProgress { .. }));
+
+        assert_eq!(
+            serde_json::to_value(MergeBlocker::UncommittedChanges { file_count: 2 }).unwrap(),
+            serde_json::json!({ "kind": "uncommittedChanges", "fileCount": 2 })
+        );
+        assert_eq!(
+            serde_json::to_value(MergeBlocker::DetachedHead).unwrap(),
+            serde_json::json!({ "kind": "detachedHead" })
+        );
+    }
+
+    #[test]
+    fn feasibility_reports_dirty_files_and_a_stale_target() {
+        let origin = init_repo_with_commit();
+        let main = git_stdout(&origin, &["branch", "--show-current"]);
+        let clone = origin.with_extension("clone");
+        run_git(&origin, &["clone", "-q", origin.to_str().unwrap(), clone.to_str().unwrap()]);
+        run_git(&clone, &["config", "user.name", "shellflow"]);
+        run_git(&clone, &["config", "user.email", "shellflow@example.com"]);
+        run_git(&clone, &["checkout", "-q", "-b", "feature"]);
+        std::fs::write(clone.join("b.txt"), "two\n").unwrap();
+        run_git(&clone, &["add", "."]);
+        run_git(&clone, &["commit", "-q", "-m", "feature"]);
+        std::fs::write(clone.join("dirty.txt"), "x").unwrap();
+        std::fs::write(clone.join("a.txt"), "changed\n").unwrap();
+
+        // origin moves on; the clone's local main now lags origin/main
+        std::fs::write(origin.join("c.txt"), "three\n").unwrap();
+        run_git(&origin, &["add", "."]);
+        run_git(&origin, &["commit", "-q", "-m", "upstream"]);
+        run_git(&clone, &["fetch", "-q"]);
+
+        let feasibility = check_merge_feasibility(&clone, &BaseBranch::default(), Some(&main), false, None).unwrap();
+        assert!(!feasibility.can_merge);
+        assert!(feasibility.has_uncommitted_changes);
+        assert_eq!(feasibility.blockers, vec![MergeBlocker::UncommittedChanges { file_count: 2 }]);
+        assert_eq!(
+            feasibility.warnings,
+            vec![MergeBlocker::BehindRemote {
+                branch: main.clone(),
+                upstream: format!("origin/{}", main),
+                count: 1,
+            }]
+        );
+
+        let _ = std::fs::remove_dir_all(&origin);
+        let _ = std::fs::remove_dir_all(&clone);
+    }
+
+    #[test]
+    fn fast_forward_only_merge_refuses_a_diverged_target() {
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        let add_commit = |name: &str| {
+            let wt = repo.join(".worktrees").join(name);
+            run_git(&repo, &["worktree", "add", "-q", "-b", name, wt.to_str().unwrap()]);
+            std::fs::write(wt.join(format!("{}.txt", name)), "x\n").unwrap();
+            run_git(&wt, &["add", "."]);
+            run_git(&wt, &["commit", "-q", "-m", name]);
+            wt
+        };
+        let ahead = add_commit("ahead");
+        let diverged = add_commit("diverged");
+
+        // A fast-forward git refuses for another reason keeps git's message
+        std::fs::write(repo.join("ahead.txt"), "in the way\n").unwrap();
+        let err = execute_merge_workflow(&ahead, &repo, MergeStrategy::Merge, None, false, true, |_| unreachable!())
+            .unwrap_err();
+        assert!(matches!(&err, GitError::Io(e) if e.to_string().contains("ahead.txt")), "{:?}", err);
+        std::fs::remove_file(repo.join("ahead.txt")).unwrap();
+
+        execute_merge_workflow(&ahead, &repo, MergeStrategy::Merge, None, false, true, |_| unreachable!()).unwrap();
+        // No merge commit: the target is the branch tip
+        assert_eq!(git_stdout(&repo, &["rev-parse", &main]), git_stdout(&repo, &["rev-parse", "ahead"]));
+
+        let before = git_stdout(&repo, &["rev-parse", &main]);
+        let err = execute_merge_workflow(&diverged, &repo, MergeStrategy::Merge, None, false, true, |_| unreachable!())
+            .unwrap_err();
+        assert!(
+            matches!(&err, GitError::NotFastForward { branch, target } if branch == "diverged" && *target == main),
+            "{:?}",
+            err
+        );
+        assert_eq!(git_stdout(&repo, &["rev-parse", &main]), before);
+        assert!(!repo.join(".git").join("MERGE_HEAD").exists());
+
+        // Without the flag the same merge makes a merge commit
+        execute_merge_workflow(&diverged, &repo, MergeStrategy::Merge, None, false, false, |_| unreachable!()).unwrap();
+        assert!(git_succeeds(&repo, &["merge-base", "--is-ancestor", "diverged", &main]));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn merge_workflow_lands_in_the_requested_target() {
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        run_git(&repo, &["branch", "release/2.4"]);
+        let wt = repo.join(".worktrees").join("hotfix");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "hotfix", wt.to_str().unwrap()]);
+        std::fs::write(wt.join("fix.txt"), "fix\n").unwrap();
+        run_git(&wt, &["add", "."]);
+        run_git(&wt, &["commit", "-q", "-m", "fix"]);
+
+        let feasibility = check_merge_feasibility(&wt, &BaseBranch::default(), Some("release/2.4"), false, None).unwrap();
+        assert_eq!(feasibility.target_branch, "release/2.4");
+        assert!(feasibility.can_merge);
+        let missing = check_merge_feasibility(&wt, &BaseBranch::default(), Some("release/9.9"), false, None).unwrap();
+        assert!(!missing.can_merge);
+        assert_eq!(
+            missing.blockers,
+            vec![MergeBlocker::TargetBranchMissing { branch: "release/9.9".to_string() }]
+        );
+
+        execute_merge_workflow(&wt, &repo, MergeStrategy::Merge, Some("release/2.4"), false, false, |_| unreachable!()).unwrap();
+        assert!(git_succeeds(&repo, &["merge-base", "--is-ancestor", "hotfix", "release/2.4"]));
+        assert!(!git_succeeds(&repo, &["merge-base", "--is-ancestor", "hotfix", &main]));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn fetch_remote_reports_missing_remotes_and_timeouts() {
+        let origin = init_repo_with_commit();
+        let clone = origin.with_extension("clone");
+        run_git(&origin, &["clone", "-q", origin.to_str().unwrap(), clone.to_str().unwrap()]);
+        std::fs::write(origin.join("b.txt"), "two\n").unwrap();
+        run_git(&origin, &["add", "."]);
+        run_git(&origin, &["commit", "-q", "-m", "second"]);
+
+        let retry = NetworkRetry::without_notices();
+        fetch_remote(&clone, "origin", std::time::Duration::from_secs(30), &retry).unwrap();
+        assert_eq!(git_stdout(&clone, &["rev-parse", "origin/HEAD"]), git_stdout(&origin, &["rev-parse", "HEAD"]));
+
+        let err = fetch_remote(&origin, "origin", std::time::Duration::from_secs(30), &retry).unwrap_err();
+        assert!(matches!(err, GitError::RemoteNotFound(ref name) if name == "origin"));
+
+        // No time at all: every attempt is killed before it can finish
+        use crate::network_retry::{RetryNotice, RetryPolicy};
+        let ignore = |_: &RetryNotice| {};
+        let once = NetworkRetry::new(
+            RetryPolicy { max_attempts: 1, initial_delay: std::time::Duration::ZERO },
+            &ignore,
+        );
+        match fetch_remote(&clone, "origin", std::time::Duration::ZERO, &once).unwrap_err() {
+            GitError::Network { reason, message, attempts } => {
+                assert_eq!(reason, NetworkFailureKind::TimedOut);
+                assert_eq!(attempts, 1);
+                assert!(message.contains("timed out"));
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        let _ = std::fs::remove_dir_all(&origin);
+        let _ = std::fs::remove_dir_all(&clone);
+    }
+
+    #[test]
+    fn rebase_onto_remote_uses_the_fetched_base() {
+        let origin = init_repo_with_commit();
+        let main = git_stdout(&origin, &["branch", "--show-current"]);
+        let clone = origin.with_extension("clone");
+        run_git(&origin, &["clone", "-q", origin.to_str().unwrap(), clone.to_str().unwrap()]);
+        run_git(&clone, &["config", "user.name", "shellflow"]);
+        run_git(&clone, &["config", "user.email", "shellflow@example.com"]);
+        let wt = clone.join(".worktrees").join("feature");
+        run_git(&clone, &["worktree", "add", "-q", "-b", "feature", wt.to_str().unwrap()]);
+        std::fs::write(wt.join("b.txt"), "two\n").unwrap();
+        run_git(&wt, &["add", "."]);
+        run_git(&wt, &["commit", "-q", "-m", "feature"]);
+
+        // origin moves on after the clone; only a fetch brings that in
+        std::fs::write(origin.join("c.txt"), "three\n").unwrap();
+        run_git(&origin, &["add", "."]);
+        run_git(&origin, &["commit", "-q", "-m", "upstream"]);
+        assert!(fetch_base_branch(&clone, &main, &NetworkRetry::without_notices()).unwrap());
+
+        let feasibility = check_merge_feasibility(&wt, &BaseBranch::default(), Some(&main), true, None).unwrap();
+        assert_eq!(feasibility.error, None);
+        execute_merge_workflow(&wt, &clone, MergeStrategy::Rebase, Some(&main), true, false, |_| unreachable!()).unwrap();
+        assert!(git_succeeds(&clone, &["merge-base", "--is-ancestor", &format!("origin/{}", main), &main]));
+        assert!(clone.join("c.txt").exists());
+
+        // Without origin the local branch is used, with a warning
+        assert!(!fetch_base_branch(&origin, &main, &NetworkRetry::without_notices()).unwrap());
+        let repo = Repository::open(&origin).unwrap();
+        let (base, warning) = rebase_base(&repo, &main, true);
+        assert_eq!(base, main);
+        assert_eq!(warning, Some(format!("No origin remote; rebasing onto local {}", main)));
+        assert_eq!(rebase_base(&repo, &main, false), (main.clone(), None));
+
+        let _ = std::fs::remove_dir_all(&origin);
+        let _ = std::fs::remove_dir_all(&clone);
+    }
+
+    #[test]
+    fn squash_lands_the_branch_as_one_commit() {
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        let wt = repo.join(".worktrees").join("feature");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "feature", wt.to_str().unwrap()]);
+        for name in ["b", "c"] {
+            std::fs::write(wt.join(format!("{}.txt", name)), name).unwrap();
+            run_git(&wt, &["add", "."]);
+            run_git(&wt, &["commit", "-q", "-m", name]);
+        }
+        let feasibility = check_merge_feasibility(&wt, &BaseBranch::default(), Some(&main), false, None).unwrap();
+        assert_eq!(feasibility.commits_ahead, 2);
+
+        let branch = execute_merge_workflow(&wt, &repo, MergeStrategy::Squash, Some(&main), false, false, |branch| {
+            format!("Squash {}", branch)
+        })
+        .unwrap();
+        assert_eq!(branch, "feature");
+        assert_eq!(git_stdout(&repo, &["rev-list", "--count", "HEAD"]), "2");
+        assert_eq!(git_stdout(&repo, &["log", "-1", "--format=%s"]), "Squash feature");
+        assert!(repo.join("b.txt").exists() && repo.join("c.txt").exists());
+        assert!(!squash_in_progress(&repo));
+
+        // Squashing again finds the changes already on the target
+        let again = execute_merge_workflow(&wt, &repo, MergeStrategy::Squash, Some(&main), false, false, |_| "again".to_string());
+        assert!(matches!(again, Err(GitError::NoChanges)));
+        assert!(!squash_in_progress(&repo));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn aborting_a_conflicted_squash_restores_the_target() {
+        let repo = init_repo_with_commit();
+        let main = git_stdout(&repo, &["branch", "--show-current"]);
+        let wt = repo.join(".worktrees").join("feature");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "feature", wt.to_str().unwrap()]);
+        std::fs::write(wt.join("a.txt"), "feature\n").unwrap();
+        run_git(&wt, &["commit", "-q", "-am", "feature edit"]);
+        std::fs::write(repo.join("a.txt"), "main\n").unwrap();
+        run_git(&repo, &["commit", "-q", "-am", "main edit"]);
+
+        let result = execute_merge_workflow(&wt, &repo, MergeStrategy::Squash, Some(&main), false, false, |_| unreachable!());
+        assert!(matches!(result, Err(GitError::MergeConflict(_))));
+        assert!(squash_in_progress(&repo));
+        assert_eq!(get_conflicted_files(&repo).unwrap(), vec!["a.txt"]);
+
+        abort_merge(&repo).unwrap();
+        assert!(!squash_in_progress(&repo));
+        assert_eq!(git_stdout(&repo, &["status", "--porcelain", "-uno"]), "");
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "main\n");
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
 }
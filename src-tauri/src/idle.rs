@@ -0,0 +1,226 @@
+//! Idle shutdown of daemon task sessions.
+//!
+//! Daemon tasks opt in with `idleShutdownMinutes` (or `taskDefaults`). A
+//! background reaper stops such a task once neither its terminal nor its
+//! worktree has seen activity for that long, and emits `task-idle-shutdown` so
+//! the UI can offer a restart. Tasks of the focused entity are never stopped.
+
+use crate::events::{self, IdleShutdownEvent};
+use crate::pty;
+use crate::state::AppState;
+use crate::status::now_unix_ms;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often the reaper looks for idle tasks
+pub const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Time a task gets to exit after SIGTERM before it is killed
+pub const IDLE_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+struct IdleTask {
+    worktree_id: String,
+    task_name: String,
+    threshold_minutes: u32,
+}
+
+lazy_static::lazy_static! {
+    /// Daemon task sessions with idle shutdown enabled, keyed by pty id
+    static ref IDLE_TASKS: Mutex<HashMap<String, IdleTask>> = Mutex::new(HashMap::new());
+    /// Unix ms each worktree or project was last focused
+    static ref ENTITY_FOCUS: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Make a freshly spawned task session eligible for idle shutdown
+pub fn register_task(pty_id: &str, worktree_id: &str, task_name: &str, threshold_minutes: u32) {
+    IDLE_TASKS.lock().insert(
+        pty_id.to_string(),
+        IdleTask {
+            worktree_id: worktree_id.to_string(),
+            task_name: task_name.to_string(),
+            threshold_minutes,
+        },
+    );
+}
+
+/// Count focusing a worktree or project as activity in it
+pub fn record_focus(entity_id: &str) {
+    ENTITY_FOCUS.lock().insert(entity_id.to_string(), now_unix_ms());
+}
+
+/// Unix ms the worktree or project was last focused this run
+pub fn last_focus(entity_id: &str) -> Option<u64> {
+    ENTITY_FOCUS.lock().get(entity_id).copied()
+}
+
+/// A task session with idle shutdown enabled, with its latest activity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdleCandidate {
+    pub pty_id: String,
+    pub worktree_id: String,
+    pub task_name: String,
+    pub threshold_minutes: u32,
+    /// Last output or input of the task's own terminal
+    pub session_activity_ms: u64,
+    /// Last focus of the worktree or activity in its other terminals
+    pub worktree_activity_ms: u64,
+}
+
+/// Candidates whose task and worktree have both been idle past the threshold.
+/// Anything belonging to `focused_entity` is skipped.
+pub fn idle_shutdown_due<'a>(
+    candidates: &'a [IdleCandidate],
+    focused_entity: Option<&str>,
+    now_ms: u64,
+) -> Vec<&'a IdleCandidate> {
+    candidates
+        .iter()
+        .filter(|c| focused_entity != Some(c.worktree_id.as_str()))
+        .filter(|c| {
+            let threshold_ms = u64::from(c.threshold_minutes) * 60_000;
+            let idle = |at: u64| now_ms.saturating_sub(at) >= threshold_ms;
+            c.threshold_minutes > 0 && idle(c.session_activity_ms) && idle(c.worktree_activity_ms)
+        })
+        .collect()
+}
+
+/// Snapshot registered tasks, forgetting ones whose session has ended
+fn collect_candidates(state: &AppState) -> Vec<IdleCandidate> {
+    let sessions: HashMap<String, String> = state
+        .pty_sessions
+        .read()
+        .iter()
+        .map(|(id, s)| (id.clone(), s.worktree_id.clone()))
+        .collect();
+    let activity = pty::session_activity();
+
+    let mut tasks = IDLE_TASKS.lock();
+    tasks.retain(|pty_id, _| sessions.contains_key(pty_id));
+    if tasks.is_empty() {
+        return Vec::new();
+    }
+
+    // Other terminals in a worktree (not idle-tracked tasks) count as worktree activity
+    let focus = ENTITY_FOCUS.lock();
+    let worktree_activity = |worktree_id: &str| {
+        sessions
+            .iter()
+            .filter(|(pty_id, wt)| wt.as_str() == worktree_id && !tasks.contains_key(*pty_id))
+            .filter_map(|(pty_id, _)| activity.get(pty_id).copied())
+            .chain(focus.get(worktree_id).copied())
+            .max()
+            .unwrap_or(0)
+    };
+
+    tasks
+        .iter()
+        .map(|(pty_id, task)| IdleCandidate {
+            pty_id: pty_id.clone(),
+            worktree_id: task.worktree_id.clone(),
+            task_name: task.task_name.clone(),
+            threshold_minutes: task.threshold_minutes,
+            session_activity_ms: activity.get(pty_id).copied().unwrap_or(0),
+            worktree_activity_ms: worktree_activity(&task.worktree_id),
+        })
+        .collect()
+}
+
+/// Periodically stop daemon tasks that have been idle past their threshold
+pub fn start_idle_reaper(app: AppHandle, state: Arc<AppState>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(IDLE_CHECK_INTERVAL);
+        if pty::SHUTDOWN_IN_PROGRESS.load(std::sync::atomic::Ordering::SeqCst) {
+            break;
+        }
+
+        let candidates = collect_candidates(&state);
+        if candidates.is_empty() {
+            continue;
+        }
+        let focused = {
+            let active = state.active_project.read();
+            active.worktree_id.clone().or_else(|| active.project_id.clone())
+        };
+
+        for candidate in idle_shutdown_due(&candidates, focused.as_deref(), now_unix_ms()) {
+            IDLE_TASKS.lock().remove(&candidate.pty_id);
+            eprintln!(
+                "[idle] Stopping task '{}' in {} after {} idle minutes",
+                candidate.task_name, candidate.worktree_id, candidate.threshold_minutes
+            );
+            // Emitted first so the UI can attribute the following pty-exit
+            let _ = app.emit(
+                events::TASK_IDLE_SHUTDOWN,
+                IdleShutdownEvent {
+                    pty_id: candidate.pty_id.clone(),
+                    worktree_id: candidate.worktree_id.clone(),
+                    task_name: candidate.task_name.clone(),
+                    idle_minutes: candidate.threshold_minutes,
+                },
+            );
+
+            let state = Arc::clone(&state);
+            let pty_id = candidate.pty_id.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = pty::terminate_session_gracefully(&state, &pty_id, IDLE_SHUTDOWN_GRACE) {
+                    eprintln!("[idle] Failed to stop session {}: {}", pty_id, e);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    fn candidate(worktree_id: &str, session_idle_min: u64, worktree_idle_min: u64, now: u64) -> IdleCandidate {
+        IdleCandidate {
+            pty_id: format!("pty-{}", worktree_id),
+            worktree_id: worktree_id.to_string(),
+            task_name: "Dev Server".to_string(),
+            threshold_minutes: 30,
+            session_activity_ms: now - session_idle_min * MINUTE,
+            worktree_activity_ms: now - worktree_idle_min * MINUTE,
+        }
+    }
+
+    #[test]
+    fn due_only_when_task_and_worktree_are_both_idle() {
+        let now = 1_000 * MINUTE;
+        let candidates = vec![
+            candidate("both-idle", 45, 31, now),
+            candidate("recent-output", 5, 90, now),
+            candidate("recently-focused", 90, 10, now),
+            candidate("exactly-threshold", 30, 30, now),
+        ];
+        let due: Vec<_> = idle_shutdown_due(&candidates, None, now)
+            .into_iter()
+            .map(|c| c.worktree_id.as_str())
+            .collect();
+        assert_eq!(due, vec!["both-idle", "exactly-threshold"]);
+    }
+
+    #[test]
+    fn focused_entity_and_disabled_thresholds_are_never_due() {
+        let now = 1_000 * MINUTE;
+        let mut disabled = candidate("disabled", 500, 500, now);
+        disabled.threshold_minutes = 0;
+        let candidates = vec![candidate("focused", 500, 500, now), disabled];
+        assert!(idle_shutdown_due(&candidates, Some("focused"), now).is_empty());
+        assert_eq!(idle_shutdown_due(&candidates, None, now).len(), 1);
+    }
+
+    #[test]
+    fn activity_after_now_is_not_idle() {
+        // A clock that reads earlier than the recorded activity must not underflow
+        let now = 10 * MINUTE;
+        let mut c = candidate("skewed", 0, 0, now);
+        c.session_activity_ms = now + MINUTE;
+        assert!(idle_shutdown_due(std::slice::from_ref(&c), None, now).is_empty());
+    }
+}
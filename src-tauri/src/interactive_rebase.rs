@@ -0,0 +1,182 @@
+//! Scripted interactive rebase.
+//!
+//! The UI decides what happens to each commit; git gets a generated sequence
+//! editor that writes that plan into its todo file, so `git rebase -i` runs
+//! without anyone hand-editing it. The rebase itself runs in a terminal, so
+//! reword editors behave as usual, and a rebase stopped on a conflict is
+//! finished through the regular rebase watcher and continue commands.
+
+use crate::git::{self, GitError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Shortest commit prefix accepted in a plan
+const MIN_COMMIT_PREFIX: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RebaseActionKind {
+    Pick,
+    Reword,
+    Squash,
+    Drop,
+}
+
+impl RebaseActionKind {
+    fn todo_command(self) -> &'static str {
+        match self {
+            RebaseActionKind::Pick => "pick",
+            RebaseActionKind::Reword => "reword",
+            RebaseActionKind::Squash => "squash",
+            RebaseActionKind::Drop => "drop",
+        }
+    }
+}
+
+/// One line of the plan: what to do with a commit. Lines run in plan order,
+/// so reordering the plan reorders the commits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseAction {
+    /// Full or abbreviated commit id
+    pub commit: String,
+    pub action: RebaseActionKind,
+}
+
+/// Turn a plan into a rebase todo list. Every commit git would rebase
+/// (`base..HEAD`, merges excluded) must appear exactly once, so nothing is
+/// dropped by leaving it out, and a squash needs a kept commit before it.
+pub fn build_todo(worktree_path: &Path, base: &str, plan: &[RebaseAction]) -> Result<String, GitError> {
+    let invalid = |message: String| GitError::InvalidRebasePlan(message);
+    let commits = git::commits_to_rebase(worktree_path, base)?;
+    if commits.is_empty() {
+        return Err(invalid(format!("No commits to rebase onto {}", base)));
+    }
+
+    let mut todo = String::new();
+    let mut seen: Vec<&str> = Vec::new();
+    for step in plan {
+        let prefix = step.commit.trim();
+        if prefix.len() < MIN_COMMIT_PREFIX {
+            return Err(invalid(format!("Commit id '{}' is too short", prefix)));
+        }
+        let mut matches = commits.iter().filter(|oid| oid.starts_with(prefix));
+        let oid = match (matches.next(), matches.next()) {
+            (Some(oid), None) => oid.as_str(),
+            (Some(_), Some(_)) => return Err(invalid(format!("Commit id '{}' is ambiguous", prefix))),
+            (None, _) => return Err(invalid(format!("Commit {} is not between {} and HEAD", prefix, base))),
+        };
+        if seen.contains(&oid) {
+            return Err(invalid(format!("Commit {} appears more than once", prefix)));
+        }
+        let kept_before = todo.lines().any(|line| !line.starts_with("drop "));
+        if step.action == RebaseActionKind::Squash && !kept_before {
+            return Err(invalid(format!("Commit {} has no earlier commit to squash into", prefix)));
+        }
+        seen.push(oid);
+        todo.push_str(&format!("{} {}\n", step.action.todo_command(), oid));
+    }
+
+    if let Some(missing) = commits.iter().find(|oid| !seen.contains(&oid.as_str())) {
+        return Err(invalid(format!("Commit {} is missing from the plan", &missing[..12.min(missing.len())])));
+    }
+    Ok(todo)
+}
+
+/// Write a one-shot sequence editor that replaces git's todo file with `todo`
+/// and deletes itself. Returns the script path.
+pub fn write_sequence_editor(todo: &str) -> std::io::Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!("shellflow-rebase-{}.sh", uuid::Uuid::new_v4()));
+    let script = format!(
+        "#!/bin/sh\ncat > \"$1\" <<'SHELLFLOW_TODO'\n{}SHELLFLOW_TODO\nrm -f -- \"$0\"\n",
+        todo
+    );
+    std::fs::write(&path, script)?;
+    Ok(path)
+}
+
+/// `GIT_SEQUENCE_EDITOR` value running the script. Git hands editors to a
+/// POSIX shell on every platform, so this is quoted for sh, not the user's shell.
+pub fn sequence_editor_command(script: &Path) -> String {
+    format!(
+        "sh {}",
+        shell_escape::unix::escape(script.to_string_lossy().replace('\\', "/").into())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// A repo with a base commit and three commits on top; returns (repo, [c1, c2, c3])
+    fn repo_with_commits() -> (PathBuf, Vec<String>) {
+        let repo = std::env::temp_dir().join(format!("shellflow-rebase-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.name", "shellflow"]);
+        git(&repo, &["config", "user.email", "shellflow@example.com"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "base"]);
+        git(&repo, &["tag", "base"]);
+        let commits = ["one", "two", "three"]
+            .iter()
+            .map(|name| {
+                std::fs::write(repo.join(format!("{}.txt", name)), name).unwrap();
+                git(&repo, &["add", "."]);
+                git(&repo, &["commit", "-q", "-m", name]);
+                git(&repo, &["rev-parse", "HEAD"])
+            })
+            .collect();
+        (repo, commits)
+    }
+
+    fn step(commit: &str, action: RebaseActionKind) -> RebaseAction {
+        RebaseAction { commit: commit.to_string(), action }
+    }
+
+    #[test]
+    fn plan_must_cover_every_commit_once() {
+        use RebaseActionKind::*;
+        let (repo, c) = repo_with_commits();
+
+        let todo = build_todo(&repo, "base", &[step(&c[2][..8], Pick), step(&c[0], Squash), step(&c[1], Drop)]).unwrap();
+        assert_eq!(todo, format!("pick {}\nsquash {}\ndrop {}\n", c[2], c[0], c[1]));
+
+        let invalid = |plan: &[RebaseAction]| matches!(build_todo(&repo, "base", plan), Err(GitError::InvalidRebasePlan(_)));
+        assert!(invalid(&[step(&c[0], Pick), step(&c[1], Pick)]));
+        assert!(invalid(&[step(&c[0], Pick), step(&c[0], Pick), step(&c[1], Pick), step(&c[2], Pick)]));
+        assert!(invalid(&[step(&c[0], Drop), step(&c[1], Squash), step(&c[2], Pick)]));
+        assert!(invalid(&[step("abc", Pick)]));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn sequence_editor_applies_the_plan() {
+        use RebaseActionKind::*;
+        let (repo, c) = repo_with_commits();
+        let todo = build_todo(&repo, "base", &[step(&c[0], Pick), step(&c[1], Drop), step(&c[2], Squash)]).unwrap();
+        let script = write_sequence_editor(&todo).unwrap();
+
+        let output = Command::new("git")
+            .args(["rebase", "-i", "base"])
+            .env("GIT_SEQUENCE_EDITOR", sequence_editor_command(&script))
+            .env("GIT_EDITOR", "true")
+            .current_dir(&repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        assert_eq!(git(&repo, &["rev-list", "--count", "base..HEAD"]), "1");
+        assert_eq!(git(&repo, &["ls-tree", "--name-only", "HEAD"]), "one.txt\nthree.txt");
+        assert!(!script.exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+}
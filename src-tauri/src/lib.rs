@@ -1,21 +1,47 @@
+mod audit;
 mod cleanup;
 mod config;
+mod copy_store;
+mod drift;
+mod error;
+mod events;
 mod git;
+mod idle;
+mod interactive_rebase;
 mod mappings;
 mod menu;
+mod network_retry;
 mod path_utils;
+mod perf;
+mod project_scan;
 mod pty;
+mod quick_commit;
+mod relocate;
+mod resources;
+mod search;
 mod state;
+mod status;
+mod task_catalog;
 mod template;
 mod theme;
+mod wake;
 mod watcher;
+mod which;
 mod worktree;
 
-use config::MergeStrategy;
+use audit::{AuditEvent, AuditOperation};
+use config::{ConfirmableOperation, MergeStrategy};
+use error::CommandError;
+use events::{
+    AutoStashProgress, DeleteWorktreeCompleted, DeleteWorktreeProgress, MergeCompleted, MergeMultipleCompleted,
+    MergeProgress, WorktreeCopyCompleted,
+};
 use git::{MergeFeasibility, WorktreeDeleteStatus};
 use log::info;
+use network_retry::{NetworkRetry, RetryNotice, RetryPolicy};
 use serde::{Deserialize, Serialize};
-use state::{AppState, FileChange, Project, WindowSize, Worktree};
+use state::{AppState, FileChange, PersistedState, Project, WindowSize, Worktree, MAIN_RESULT_KEY};
+use status::{StatusSummary, WorkflowKind};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -25,19 +51,19 @@ use tauri::{AppHandle, Emitter, Manager, PhysicalSize, Size, State};
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
-type Result<T> = std::result::Result<T, String>;
+type Result<T> = std::result::Result<T, CommandError>;
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-fn map_err<E: std::fmt::Display>(e: E) -> String {
-    e.to_string()
+fn map_err<E: Into<CommandError>>(e: E) -> CommandError {
+    e.into()
 }
 
 #[derive(Default)]
 struct ProjectWorktreeSync {
     added_worktrees: Vec<(String, String)>,
-    removed_worktree_ids: Vec<String>,
+    removed_worktrees: Vec<Worktree>,
     changed: bool,
 }
 
@@ -86,6 +112,30 @@ fn normalize_path_for_compare(path: &Path) -> String {
     }
 }
 
+/// Build config load options (e.g. disabled layers) for the project at `project_path`
+fn config_load_options(state: &AppState, project_path: Option<&str>) -> config::ConfigLoadOptions {
+    let Some(project_path) = project_path else {
+        return config::ConfigLoadOptions::default();
+    };
+
+    let project_key = normalize_path_for_compare(Path::new(project_path));
+    let persisted = state.persisted.read();
+    let disabled_layers = persisted
+        .projects
+        .iter()
+        .find(|p| normalize_path_for_compare(Path::new(&p.path)) == project_key)
+        .map(|p| p.disabled_config_layers.clone())
+        .unwrap_or_default();
+
+    config::ConfigLoadOptions { disabled_layers }
+}
+
+/// Load config for a project, honoring its per-project load options
+fn load_project_config(state: &AppState, project_path: Option<&str>) -> config::Config {
+    let options = config_load_options(state, project_path);
+    config::load_config_for_project_with(project_path, &options)
+}
+
 fn apply_command_process_options(command: &mut std::process::Command) {
     #[cfg(windows)]
     {
@@ -101,14 +151,17 @@ fn fallback_worktree_name(path: &str) -> String {
         .to_string()
 }
 
-fn sync_project_worktrees_from_git(
-    project: &mut Project,
-) -> std::result::Result<ProjectWorktreeSync, git::GitError> {
-    let project_path = Path::new(&project.path);
+/// Linked worktrees git has registered, keyed by normalized canonical path,
+/// with the canonical path and the branch (None for a detached HEAD)
+type RegisteredWorktrees = HashMap<String, (String, Option<String>)>;
+
+/// Run `git worktree list` for a project. The main worktree and registrations
+/// whose directory is missing are skipped.
+fn list_linked_worktrees(project_path: &Path) -> std::result::Result<RegisteredWorktrees, git::GitError> {
     let project_root = canonicalize_or_original(project_path);
     let project_root_key = normalize_path_for_compare(&project_root);
 
-    let mut discovered_by_path: HashMap<String, (String, Option<String>)> = HashMap::new();
+    let mut discovered_by_path: RegisteredWorktrees = HashMap::new();
 
     for discovered in git::list_registered_worktrees(project_path)? {
         let discovered_path = PathBuf::from(&discovered.path);
@@ -129,6 +182,28 @@ fn sync_project_worktrees_from_git(
             .or_insert((canonical_path.to_string_lossy().to_string(), discovered.branch));
     }
 
+    Ok(discovered_by_path)
+}
+
+fn sync_project_worktrees_from_git(
+    project: &mut Project,
+) -> std::result::Result<ProjectWorktreeSync, git::GitError> {
+    let project_path = PathBuf::from(&project.path);
+    let registered = list_linked_worktrees(&project_path)?;
+    let mut sync = sync_project_worktrees(project, &registered);
+    // Projects added before root commits were recorded
+    if project.root_commit.is_none() {
+        if let Ok(Some(root)) = git::root_commit(&project_path) {
+            project.root_commit = Some(root);
+            sync.changed = true;
+        }
+    }
+    Ok(sync)
+}
+
+/// Track the worktrees in `registered` that `project` doesn't, and drop the
+/// ones git no longer has, reporting both
+fn sync_project_worktrees(project: &mut Project, registered: &RegisteredWorktrees) -> ProjectWorktreeSync {
     let mut sync = ProjectWorktreeSync::default();
     let mut seen_paths = HashSet::new();
     let existing_worktrees = std::mem::take(&mut project.worktrees);
@@ -138,7 +213,7 @@ fn sync_project_worktrees_from_git(
         let existing_path = canonicalize_or_original(Path::new(&existing.path));
         let existing_key = normalize_path_for_compare(&existing_path);
 
-        if let Some((canonical_path, discovered_branch)) = discovered_by_path.get(&existing_key) {
+        if let Some((canonical_path, discovered_branch)) = registered.get(&existing_key) {
             seen_paths.insert(existing_key);
 
             let branch = discovered_branch
@@ -160,20 +235,20 @@ fn sync_project_worktrees_from_git(
 
             merged_worktrees.push(existing);
         } else {
-            sync.removed_worktree_ids.push(existing.id);
+            sync.removed_worktrees.push(existing);
             sync.changed = true;
         }
     }
 
     let mut new_worktrees = Vec::new();
-    for (key, (path, branch)) in discovered_by_path {
-        if seen_paths.contains(&key) {
+    for (key, (path, branch)) in registered {
+        if seen_paths.contains(key) {
             continue;
         }
 
-        let branch = branch.unwrap_or_else(|| fallback_worktree_name(&path));
+        let branch = branch.clone().unwrap_or_else(|| fallback_worktree_name(path));
         let branch = if branch.trim().is_empty() {
-            fallback_worktree_name(&path)
+            fallback_worktree_name(path)
         } else {
             branch
         };
@@ -181,10 +256,13 @@ fn sync_project_worktrees_from_git(
         let worktree = Worktree {
             id: uuid::Uuid::new_v4().to_string(),
             name: branch.clone(),
-            path,
+            path: path.clone(),
             branch,
             created_at: worktree::chrono_lite_now(),
             order: 0,
+            last_results: HashMap::new(),
+            pending_cleanup: false,
+            layout: None,
         };
 
         sync.added_worktrees
@@ -205,7 +283,87 @@ fn sync_project_worktrees_from_git(
     }
 
     project.worktrees = merged_worktrees;
-    Ok(sync)
+    sync
+}
+
+/// A worktree git has registered that the project doesn't track
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct UnmanagedWorktree {
+    path: String,
+    /// None for a detached HEAD
+    branch: Option<String>,
+}
+
+/// A tracked worktree git no longer has registered, or whose directory is gone
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct StaleWorktree {
+    id: String,
+    name: String,
+    path: String,
+}
+
+/// Result of `scan_external_worktrees`
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ExternalWorktreeScan {
+    unmanaged: Vec<UnmanagedWorktree>,
+    stale: Vec<StaleWorktree>,
+}
+
+/// What syncing `project` with `registered` would change: worktrees it would
+/// start tracking, and tracked ones it would drop because git no longer has them
+fn scan_registered_worktrees(project: &Project, registered: &RegisteredWorktrees) -> ExternalWorktreeScan {
+    let mut synced = project.clone();
+    let sync = sync_project_worktrees(&mut synced, registered);
+    let unmanaged = sync
+        .added_worktrees
+        .into_iter()
+        .map(|(_, path)| {
+            let branch = registered
+                .get(&normalize_path_for_compare(Path::new(&path)))
+                .and_then(|(_, branch)| branch.clone());
+            UnmanagedWorktree { path, branch }
+        })
+        .collect();
+    let stale = sync
+        .removed_worktrees
+        .into_iter()
+        .map(|w| StaleWorktree {
+            id: w.id,
+            name: w.name,
+            path: w.path,
+        })
+        .collect();
+    ExternalWorktreeScan { unmanaged, stale }
+}
+
+/// Start tracking the registered worktree at `worktree_path`, named the way
+/// the sync names it: after its branch, or its directory when detached
+fn adopt_registered_worktree(
+    project: &mut Project,
+    registered: &RegisteredWorktrees,
+    worktree_path: &str,
+) -> Result<Worktree> {
+    let mut synced = project.clone();
+    let sync = sync_project_worktrees(&mut synced, registered);
+    let key = normalize_path_for_compare(&canonicalize_or_original(Path::new(worktree_path)));
+    let mut worktree = sync
+        .added_worktrees
+        .iter()
+        .find(|(_, path)| normalize_path_for_compare(Path::new(path)) == key)
+        .and_then(|(id, _)| synced.worktrees.into_iter().find(|w| w.id == *id))
+        .ok_or_else(|| {
+            CommandError::InvalidInput(format!(
+                "{} is not an unmanaged worktree of {}",
+                worktree_path, project.name
+            ))
+        })?;
+
+    worktree.order = project.worktrees.len() as i32;
+    project.worktrees.push(worktree.clone());
+    Ok(worktree)
 }
 
 // Logging command - prints to stdout so it appears in the terminal during dev
@@ -244,6 +402,115 @@ fn git_stage_all(repo_path: &str) -> Result<()> {
     git::stage_all(Path::new(repo_path)).map_err(map_err)
 }
 
+/// Staged changes of one file as a unified diff; empty when nothing is staged
+#[tauri::command]
+fn get_staged_file_diff(worktree_path: &str, file_path: &str) -> Result<String> {
+    git::staged_file_diff(Path::new(worktree_path), file_path).map_err(map_err)
+}
+
+/// Read a git setting as the worktree sees it, or from one scope only
+#[tauri::command]
+fn get_git_config(worktree_path: &str, key: &str, scope: Option<git::GitConfigScope>) -> Result<Option<String>> {
+    git::get_config(Path::new(worktree_path), key, scope).map_err(map_err)
+}
+
+fn worktree_path_by_id(state: &AppState, worktree_id: &str) -> Result<String> {
+    state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .find(|w| w.id == worktree_id)
+        .map(|w| w.path.clone())
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))
+}
+
+/// Project path and worktree path of a worktree, and an audit event naming
+/// both, for destructive commands keyed by worktree id
+fn audited_worktree(
+    state: &AppState,
+    worktree_id: &str,
+    operation: AuditOperation,
+) -> Result<(String, String, AuditEvent)> {
+    let persisted = state.persisted.read();
+    persisted
+        .projects
+        .iter()
+        .find_map(|p| {
+            p.worktrees.iter().find(|w| w.id == worktree_id).map(|w| {
+                let event = AuditEvent::new(operation, &p.path).with_worktree(&w.name).with_branch(&w.branch);
+                (p.path.clone(), w.path.clone(), event)
+            })
+        })
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))
+}
+
+/// Stage whole files, then refresh the changed-files list without waiting
+/// for the watcher
+#[tauri::command]
+fn stage_files(app: AppHandle, state: State<'_, Arc<AppState>>, worktree_id: &str, paths: Vec<String>) -> Result<()> {
+    let worktree_path = worktree_path_by_id(&state, worktree_id)?;
+    git::stage_files(Path::new(&worktree_path), &paths).map_err(map_err)?;
+    if let Err(e) = watcher::emit_changed_files(&app, &worktree_path) {
+        info!("[stage_files] Failed to refresh changed files: {}", e);
+    }
+    Ok(())
+}
+
+/// Unstage whole files, keeping their working-tree changes
+#[tauri::command]
+fn unstage_files(app: AppHandle, state: State<'_, Arc<AppState>>, worktree_id: &str, paths: Vec<String>) -> Result<()> {
+    let worktree_path = worktree_path_by_id(&state, worktree_id)?;
+    git::unstage_files(Path::new(&worktree_path), &paths).map_err(map_err)?;
+    if let Err(e) = watcher::emit_changed_files(&app, &worktree_path) {
+        info!("[unstage_files] Failed to refresh changed files: {}", e);
+    }
+    Ok(())
+}
+
+/// Throw away working-tree changes to files, deleting untracked ones only
+/// with `include_untracked`, then refresh the changed-files list. Gated by
+/// `confirmations.forceReset` (and `cleanUntracked` for untracked files), and
+/// recorded in the audit log.
+#[tauri::command]
+fn discard_changes(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    paths: Vec<String>,
+    include_untracked: bool,
+    confirmed: bool,
+) -> Result<Vec<git::DiscardResult>> {
+    let (project_path, worktree_path, event) = audited_worktree(&state, worktree_id, AuditOperation::DiscardChanges)?;
+    ensure_confirmed(&state, &project_path, ConfirmableOperation::ForceReset, confirmed)?;
+    if include_untracked {
+        ensure_confirmed(&state, &project_path, ConfirmableOperation::CleanUntracked, confirmed)?;
+    }
+    let event = event.with_options(serde_json::json!({ "paths": paths, "includeUntracked": include_untracked }));
+    let results = audit::record_result(
+        event,
+        git::discard_changes(Path::new(&worktree_path), &paths, include_untracked),
+    )
+    .map_err(map_err)?;
+    if let Err(e) = watcher::emit_changed_files(&app, &worktree_path) {
+        info!("[discard_changes] Failed to refresh changed files: {}", e);
+    }
+    Ok(results)
+}
+
+/// Stage one hunk of a file's unstaged diff
+#[tauri::command]
+fn stage_hunk(worktree_path: &str, file_path: &str, hunk_header: &str, hunk_lines: Vec<String>) -> Result<()> {
+    git::stage_hunk(Path::new(worktree_path), file_path, hunk_header, &hunk_lines).map_err(map_err)
+}
+
+/// Unstage one hunk of a file's staged diff
+#[tauri::command]
+fn unstage_hunk(worktree_path: &str, file_path: &str, hunk_header: &str, hunk_lines: Vec<String>) -> Result<()> {
+    git::unstage_hunk(Path::new(worktree_path), file_path, hunk_header, &hunk_lines).map_err(map_err)
+}
+
 #[tauri::command]
 fn git_diff_cached(repo_path: &str) -> Result<String> {
     git::diff_cached(Path::new(repo_path)).map_err(map_err)
@@ -259,6 +526,12 @@ fn git_commit(repo_path: &str, message: &str) -> Result<()> {
     git::commit_staged(Path::new(repo_path), message).map_err(map_err)
 }
 
+/// Add the staged changes to the last commit, optionally rewording it
+#[tauri::command]
+fn amend_commit(repo_path: &str, message: Option<String>) -> Result<()> {
+    git::amend_last_commit(Path::new(repo_path), message.as_deref()).map_err(map_err)
+}
+
 #[tauri::command]
 fn git_current_branch(repo_path: &str) -> Result<String> {
     let repo = git2::Repository::open(repo_path).map_err(map_err)?;
@@ -273,27 +546,342 @@ fn git_branch_exists(repo_path: &str, branch: &str) -> Result<bool> {
 #[tauri::command]
 fn git_create_branch(repo_path: &str, branch: &str) -> Result<()> {
     if let Some(error) = git::validate_branch_name(branch) {
-        return Err(error);
+        return Err(CommandError::InvalidInput(error));
     }
     if git::branch_exists(Path::new(repo_path), branch).map_err(map_err)? {
-        return Err(format!("Branch '{}' already exists", branch));
+        return Err(CommandError::InvalidInput(format!("Branch '{}' already exists", branch)));
     }
     git::create_branch(Path::new(repo_path), branch).map_err(map_err)
 }
 
+/// Worktrees added with `git worktree add` outside shellflow, and tracked
+/// worktrees git no longer has, that the next project sync would pick up
+#[tauri::command]
+fn scan_external_worktrees(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<ExternalWorktreeScan> {
+    let project = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .cloned()
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+    let registered = list_linked_worktrees(Path::new(&project.path)).map_err(map_err)?;
+    Ok(scan_registered_worktrees(&project, &registered))
+}
+
+/// Track an unmanaged worktree found by `scan_external_worktrees`
+#[tauri::command]
+fn adopt_worktree(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_id: &str,
+    worktree_path: &str,
+) -> Result<Worktree> {
+    let project_path = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.path.clone())
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+    // List before taking the write lock so git doesn't hold up other commands
+    let registered = list_linked_worktrees(Path::new(&project_path)).map_err(map_err)?;
+    let worktree = {
+        let mut persisted = state.persisted.write();
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+        adopt_registered_worktree(project, &registered, worktree_path)?
+    };
+    state.save().map_err(map_err)?;
+    watcher::watch_worktree(app, worktree.id.clone(), worktree.path.clone());
+    Ok(worktree)
+}
+
+/// A project's worktrees, most behind their base branch first. Emits
+/// `drift-warning` for each one past a `worktree.driftWarning` threshold, at
+/// most once a day per worktree.
+#[tauri::command]
+fn get_drift_report(app: AppHandle, state: State<'_, Arc<AppState>>, project_id: &str) -> Result<drift::DriftReport> {
+    let project = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .cloned()
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+    let cfg = load_project_config(&state, Some(&project.path));
+    let thresholds = &cfg.worktree.drift_warning;
+    let now = status::now_unix_ms();
+
+    let mut worktrees = Vec::new();
+    for wt in &project.worktrees {
+        // Worktrees whose HEAD or base branch can't be resolved are left out
+        let Ok(base) = git::base_drift(Path::new(&wt.path), &cfg.worktree.base_branch) else {
+            continue;
+        };
+        let head_ms = u64::try_from(base.head_time).unwrap_or(0) * 1000;
+        let last_activity = idle::last_focus(&wt.id).map_or(head_ms, |focus| focus.max(head_ms));
+        let days = drift::days_since(last_activity, now);
+        worktrees.push(drift::WorktreeDrift {
+            worktree_id: wt.id.clone(),
+            name: wt.name.clone(),
+            branch: wt.branch.clone(),
+            base_branch: base.base_branch,
+            commits_behind: base.behind,
+            commits_ahead: base.ahead,
+            days_since_activity: days,
+            exceeds_threshold: drift::exceeds_threshold(base.behind, days, thresholds),
+        });
+    }
+    drift::sort_by_drift(&mut worktrees);
+
+    let mut warned = false;
+    for wt in worktrees.iter().filter(|w| w.exceeds_threshold) {
+        if drift::warning_due(state.drift_warned_at(&wt.worktree_id), now) {
+            state.record_drift_warning(&wt.worktree_id, now);
+            warned = true;
+            let _ = app.emit(
+                events::DRIFT_WARNING,
+                events::DriftWarning {
+                    project_id: project_id.to_string(),
+                    drift: wt.clone(),
+                },
+            );
+        }
+    }
+    if warned {
+        if let Err(e) = state.save() {
+            eprintln!("[drift] Failed to save warning times: {}", e);
+        }
+    }
+
+    Ok(drift::DriftReport {
+        warn_behind: thresholds.warn_behind,
+        stale_days: thresholds.stale_days,
+        worktrees,
+    })
+}
+
+/// Stable color for a branch or worktree name
+#[tauri::command]
+fn branch_color(name: &str) -> template::BranchColor {
+    template::branch_color(name)
+}
+
+/// Variable names and descriptions a template in `scope` can use
+#[tauri::command]
+fn get_template_variables(scope: template::TemplateScope) -> &'static [template::TemplateVariable] {
+    template::template_variables(scope)
+}
+
+/// Render a template against a JSON context for a live preview in the config editor
+#[tauri::command]
+fn render_template_preview(template: &str, context: serde_json::Value) -> template::TemplatePreview {
+    template::preview_template(template, &context)
+}
+
+/// Templated settings of a project's config that don't render, for the
+/// settings UI. Stricter than runtime expansion: unknown variables count.
+#[tauri::command]
+fn validate_templates(state: State<'_, Arc<AppState>>, project_path: Option<String>) -> Vec<template::TemplateProblem> {
+    config::validate_templates(&load_project_config(&state, project_path.as_deref()))
+}
+
+/// Files per language among the project's tracked files, for the project overview
+#[tauri::command]
+fn get_language_stats(project_path: &str, include_bytes: Option<bool>) -> Result<git::LanguageStats> {
+    git::language_stats(Path::new(project_path), include_bytes.unwrap_or(false)).map_err(map_err)
+}
+
+/// Commits returned by `git_recent_commits` when no limit is given
+const DEFAULT_RECENT_COMMITS: usize = 30;
+
+/// Recent commits on HEAD; author emails are left out unless `include_email` is set
+#[tauri::command]
+fn git_recent_commits(
+    repo_path: &str,
+    limit: Option<usize>,
+    include_email: Option<bool>,
+) -> Result<Vec<git::CommitSummary>> {
+    git::recent_commits(
+        Path::new(repo_path),
+        limit.unwrap_or(DEFAULT_RECENT_COMMITS),
+        include_email.unwrap_or(false),
+    )
+    .map_err(map_err)
+}
+
+/// Retry policy for remote git commands under `project_path`
+fn network_retry_policy(state: &AppState, project_path: Option<&str>) -> RetryPolicy {
+    RetryPolicy::from_config(&load_project_config(state, project_path).network)
+}
+
+/// Announce a retry of a remote git command
+fn emit_retry_notice(app: &AppHandle, notice: &RetryNotice) {
+    let _ = app.emit(events::GIT_NETWORK_RETRY, notice);
+}
+
+/// Fetch `remote` (origin by default) on a background thread, emitting
+/// `fetch-progress` and then `fetch-completed`. Each attempt is stopped after
+/// `network.fetchTimeoutSeconds` and transient failures are retried.
+#[tauri::command]
+fn fetch_remote(app: AppHandle, state: State<'_, Arc<AppState>>, repo_path: String, remote: Option<String>) {
+    let remote = remote.filter(|r| !r.trim().is_empty()).unwrap_or_else(|| "origin".to_string());
+    let timeout_secs = load_project_config(&state, Some(&repo_path)).network.fetch_timeout_seconds.max(1);
+    let policy = network_retry_policy(&state, Some(&repo_path));
+
+    std::thread::spawn(move || {
+        let _ = app.emit(
+            events::FETCH_PROGRESS,
+            events::FetchProgress {
+                repo_path: repo_path.clone(),
+                remote: remote.clone(),
+                message: format!("Fetching {}...", remote),
+            },
+        );
+        let on_retry = |notice: &RetryNotice| emit_retry_notice(&app, notice);
+        let retry = NetworkRetry::new(policy, &on_retry);
+        let timeout = std::time::Duration::from_secs(timeout_secs);
+        let result = git::fetch_remote(Path::new(&repo_path), &remote, timeout, &retry);
+        if let Err(e) = &result {
+            status::record_error("fetch", e.to_string());
+        }
+        let _ = app.emit(
+            events::FETCH_COMPLETED,
+            events::FetchCompleted {
+                repo_path,
+                remote,
+                success: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            },
+        );
+    });
+}
+
+/// Delete `remote`'s (origin by default) remote-tracking refs for branches
+/// deleted on the remote; returns the pruned refs. This contacts the remote,
+/// so it runs off the main thread and each attempt is stopped after
+/// `network.fetchTimeoutSeconds`.
 #[tauri::command]
-fn git_push_current_branch(repo_path: &str) -> Result<()> {
-    git::push_current_branch(Path::new(repo_path)).map_err(map_err)
+async fn prune_remote_refs(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_path: String,
+    remote: Option<String>,
+) -> Result<Vec<String>> {
+    let remote = remote.filter(|r| !r.trim().is_empty()).unwrap_or_else(|| "origin".to_string());
+    let network = load_project_config(&state, Some(&project_path)).network;
+    let policy = RetryPolicy::from_config(&network);
+    let timeout = std::time::Duration::from_secs(network.fetch_timeout_seconds.max(1));
+    tauri::async_runtime::spawn_blocking(move || {
+        let on_retry = |notice: &RetryNotice| emit_retry_notice(&app, notice);
+        let retry = NetworkRetry::new(policy, &on_retry);
+        git::prune_remote_refs(Path::new(&project_path), &remote, timeout, &retry)
+    })
+    .await?
+    .map_err(map_err)
+}
+
+/// Local branches whose upstream was deleted on the remote
+#[tauri::command]
+fn list_gone_branches(project_path: &str) -> Result<Vec<git::GoneBranch>> {
+    git::list_gone_branches(Path::new(project_path)).map_err(map_err)
+}
+
+#[tauri::command]
+fn git_push_current_branch(app: AppHandle, state: State<'_, Arc<AppState>>, repo_path: &str) -> Result<()> {
+    let on_retry = |notice: &RetryNotice| emit_retry_notice(&app, notice);
+    let retry = NetworkRetry::new(network_retry_policy(&state, Some(repo_path)), &on_retry);
+    git::push_current_branch(Path::new(repo_path), &retry).map_err(map_err)
 }
 
 #[tauri::command]
 fn git_merge_to_main(worktree_path: &str, repo_path: &str) -> Result<()> {
-    git::merge_branch_to_target(Path::new(worktree_path), Path::new(repo_path)).map_err(map_err)
+    git::merge_branch_to_target(Path::new(worktree_path), Path::new(repo_path), None, false).map_err(map_err)
+}
+
+#[tauri::command]
+fn git_push_default_branch(app: AppHandle, state: State<'_, Arc<AppState>>, repo_path: &str) -> Result<()> {
+    let on_retry = |notice: &RetryNotice| emit_retry_notice(&app, notice);
+    let retry = NetworkRetry::new(network_retry_policy(&state, Some(repo_path)), &on_retry);
+    git::push_default_branch(Path::new(repo_path), &retry).map_err(map_err)
+}
+
+/// Generate a message from `commit.ai` for the changes staged in `repo_path`
+/// on a background thread. The text arrives as `commit-message-chunk` events
+/// while it streams in, then `commit-message-completed` carries the result.
+#[tauri::command]
+fn generate_commit_message(app: AppHandle, state: State<'_, Arc<AppState>>, repo_path: String) {
+    let ai = load_project_config(&state, Some(&repo_path)).commit.ai;
+
+    std::thread::spawn(move || {
+        let result = quick_commit::generate_commit_message_streaming(Path::new(&repo_path), &ai, |delta| {
+            let _ = app.emit(
+                events::COMMIT_MESSAGE_CHUNK,
+                events::CommitMessageChunk {
+                    repo_path: repo_path.clone(),
+                    delta: delta.to_string(),
+                },
+            );
+        });
+        if let Err(e) = &result {
+            status::record_error("commit-message", e.clone());
+        }
+        let (message, error) = match result {
+            Ok(message) => (Some(message), None),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app.emit(
+            events::COMMIT_MESSAGE_COMPLETED,
+            events::CommitMessageCompleted { repo_path, message, error },
+        );
+    });
 }
 
+/// Stage, commit and optionally push everything in a worktree in one call.
+/// Without a message, one is generated from `commit.ai` when it is configured.
 #[tauri::command]
-fn git_push_default_branch(repo_path: &str) -> Result<()> {
-    git::push_default_branch(Path::new(repo_path)).map_err(map_err)
+fn quick_commit_push(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    message: Option<String>,
+    push: Option<bool>,
+) -> Result<quick_commit::QuickCommitResult> {
+    let (project_path, worktree_path) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                p.worktrees
+                    .iter()
+                    .find(|w| w.id == worktree_id)
+                    .map(|w| (p.path.clone(), w.path.clone()))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
+    };
+
+    let cfg = load_project_config(&state, Some(&project_path));
+    let on_retry = |notice: &RetryNotice| emit_retry_notice(&app, notice);
+    let retry = NetworkRetry::new(RetryPolicy::from_config(&cfg.network), &on_retry);
+    quick_commit::quick_commit_push(
+        Path::new(&worktree_path),
+        message.as_deref(),
+        push.unwrap_or(false),
+        &cfg.commit.ai,
+        &retry,
+    )
+    .map_err(|e| {
+        status::record_error("quick-commit", e.to_string());
+        map_err(e)
+    })
 }
 
 // Project commands
@@ -321,7 +909,7 @@ fn add_project(app: AppHandle, state: State<'_, Arc<AppState>>, path: &str) -> R
             match sync_project_worktrees_from_git(existing) {
                 Ok(sync) => {
                     worktrees_to_watch.extend(sync.added_worktrees);
-                    worktrees_to_unwatch.extend(sync.removed_worktree_ids);
+                    worktrees_to_unwatch.extend(sync.removed_worktrees.into_iter().map(|w| w.id));
                 }
                 Err(err) => {
                     info!(
@@ -338,7 +926,7 @@ fn add_project(app: AppHandle, state: State<'_, Arc<AppState>>, path: &str) -> R
             match sync_project_worktrees_from_git(&mut project) {
                 Ok(sync) => {
                     worktrees_to_watch.extend(sync.added_worktrees);
-                    worktrees_to_unwatch.extend(sync.removed_worktree_ids);
+                    worktrees_to_unwatch.extend(sync.removed_worktrees.into_iter().map(|w| w.id));
                 }
                 Err(err) => {
                     info!(
@@ -367,6 +955,72 @@ fn add_project(app: AppHandle, state: State<'_, Arc<AppState>>, path: &str) -> R
     Ok(project)
 }
 
+/// Find git repositories under `root_path` that aren't projects yet
+#[tauri::command]
+fn scan_for_projects(
+    state: State<'_, Arc<AppState>>,
+    root_path: &str,
+    max_depth: Option<usize>,
+) -> Result<project_scan::ProjectScan> {
+    let registered: HashSet<PathBuf> = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .map(|p| canonicalize_or_original(Path::new(&p.path)))
+        .collect();
+    project_scan::scan_for_projects(
+        Path::new(root_path),
+        max_depth.unwrap_or(3),
+        &registered,
+        project_scan::MAX_SCAN_DIRS,
+    )
+    .map_err(map_err)
+}
+
+/// Add several repositories as projects with a single save. Each path gets
+/// its own result; one failing doesn't stop the others.
+#[tauri::command]
+fn add_projects_bulk(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    paths: Vec<String>,
+) -> Result<Vec<project_scan::BulkAddResult>> {
+    let mut worktrees_to_watch: Vec<(String, String)> = Vec::new();
+
+    let results = {
+        let mut persisted = state.persisted.write();
+        let mut results = project_scan::register_projects(&mut persisted.projects, &paths);
+
+        for result in &mut results {
+            let Some(added) = &result.project else { continue };
+            let Some(project) = persisted.projects.iter_mut().find(|p| p.id == added.id) else {
+                continue;
+            };
+            match sync_project_worktrees_from_git(project) {
+                Ok(sync) => worktrees_to_watch.extend(sync.added_worktrees),
+                Err(err) => {
+                    info!(
+                        "[add_projects_bulk] Failed to sync worktrees for '{}': {}",
+                        project.path, err
+                    );
+                }
+            }
+            result.project = Some(project.clone());
+        }
+        results
+    };
+
+    for (id, path) in worktrees_to_watch {
+        watcher::watch_worktree(app.clone(), id, path);
+    }
+
+    if results.iter().any(|r| r.project.is_some()) {
+        state.save().map_err(map_err)?;
+    }
+    Ok(results)
+}
+
 #[tauri::command]
 fn list_projects(
     app: AppHandle,
@@ -374,6 +1028,7 @@ fn list_projects(
     sync_from_git: Option<bool>,
 ) -> Result<Vec<Project>> {
     let force_sync = sync_from_git.unwrap_or(false);
+    relocate::report_missing_projects(&app, &state.persisted.read().projects);
     if !should_sync_projects_from_git(state.inner().as_ref(), force_sync) {
         return Ok(state.persisted.read().projects.clone());
     }
@@ -392,7 +1047,7 @@ fn list_projects(
                         should_save = true;
                     }
 
-                    worktrees_to_unwatch.extend(sync.removed_worktree_ids);
+                    worktrees_to_unwatch.extend(sync.removed_worktrees.into_iter().map(|w| w.id));
                     if project.is_active {
                         worktrees_to_watch.extend(sync.added_worktrees);
                     }
@@ -422,10 +1077,65 @@ fn list_projects(
     Ok(state.persisted.read().projects.clone())
 }
 
+/// CPU and memory of live terminals' process trees in `scope` ("all", a
+/// project id, or a worktree id), per session and per worktree. Sampling
+/// takes a short interval, so it runs off the main thread.
 #[tauri::command]
-fn hide_project(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<()> {
-    {
-        let mut persisted = state.persisted.write();
+async fn get_resource_usage(state: State<'_, Arc<AppState>>, scope: String) -> Result<resources::ResourceUsage> {
+    let sessions = resources::scope_sessions(&state, &scope);
+    Ok(tauri::async_runtime::spawn_blocking(move || resources::resource_usage(&sessions)).await?)
+}
+
+/// Point a project at its repository's new location after the directory moved,
+/// rewriting worktree paths under it and restarting their watchers
+#[tauri::command]
+fn update_project_path(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_id: &str,
+    new_path: &str,
+) -> Result<relocate::ProjectRelocation> {
+    let new_root = canonicalize_or_original(Path::new(new_path));
+    let (relocation, previous_ids) = {
+        let mut persisted = state.persisted.write();
+        if persisted
+            .projects
+            .iter()
+            .any(|p| p.id != project_id && canonicalize_or_original(Path::new(&p.path)) == new_root)
+        {
+            return Err(CommandError::InvalidInput(format!(
+                "{} is already added as another project",
+                new_root.display()
+            )));
+        }
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+        let previous_ids: Vec<String> = project.worktrees.iter().map(|w| w.id.clone()).collect();
+        (relocate::relocate_project(project, &new_root).map_err(map_err)?, previous_ids)
+    };
+
+    for worktree_id in &previous_ids {
+        watcher::stop_watching(worktree_id);
+    }
+    if relocation.project.is_active {
+        for wt in &relocation.project.worktrees {
+            if !relocation.stale_worktree_ids.contains(&wt.id) {
+                watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+            }
+        }
+    }
+
+    state.save().map_err(map_err)?;
+    Ok(relocation)
+}
+
+#[tauri::command]
+fn hide_project(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<()> {
+    {
+        let mut persisted = state.persisted.write();
         if let Some(project) = persisted.projects.iter_mut().find(|p| p.id == project_id) {
             // Stop watching individual worktrees
             for wt in &project.worktrees {
@@ -474,18 +1184,126 @@ fn touch_project(
 }
 
 // Worktree commands
+/// Delete a worktree from its project, recording the outcome in the audit log,
+/// and drop its working snapshots
+fn delete_worktree_audited(
+    persisted: &mut PersistedState,
+    worktree_id: &str,
+    options: serde_json::Value,
+) -> std::result::Result<(), worktree::WorktreeError> {
+    let project = persisted
+        .projects
+        .iter_mut()
+        .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
+        .ok_or_else(|| worktree::WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
+    let mut event = AuditEvent::new(AuditOperation::DeleteWorktree, &project.path).with_options(options);
+    let mut worktree_path = String::new();
+    if let Some(wt) = project.worktrees.iter().find(|w| w.id == worktree_id) {
+        event = event.with_worktree(&wt.name).with_branch(&wt.branch);
+        worktree_path = wt.path.clone();
+    }
+    audit::record_result(event, worktree::delete_worktree(project, worktree_id))?;
+    let project_path = project.path.clone();
+    forget_working_snapshots(persisted, &project_path, &worktree_path);
+    Ok(())
+}
+
+/// Forget a removed worktree's snapshots and drop the refs pinning them
+fn forget_working_snapshots(persisted: &mut PersistedState, project_path: &str, worktree_path: &str) {
+    let unused = persisted.forget_working_snapshots(worktree_path, None);
+    if let Err(e) = git::delete_working_snapshots(Path::new(project_path), &unused) {
+        info!("[forget_working_snapshots] Failed to drop snapshot refs of {}: {}", worktree_path, e);
+    }
+}
+
+/// Run background setup for a new worktree (gitignored file copy), limited by
+/// `worktree.maxConcurrentSetups`.
+fn spawn_worktree_setup(app: &AppHandle, cfg: &config::Config, project_path: &Path, wt: &Worktree) {
+    if !cfg.worktree.copy.gitignored {
+        return;
+    }
+
+    let worktree_path = wt.path.clone();
+    let worktree_id = wt.id.clone();
+    let except = cfg.worktree.copy.except.clone();
+    let dedupe = cfg.worktree.copy.dedupe;
+    let max_concurrent = cfg.worktree.max_concurrent_setups;
+    let app_handle = app.clone();
+    let project_path = project_path.to_path_buf();
+
+    std::thread::spawn(move || {
+        // Limit concurrent setup jobs so batch-created worktrees don't saturate I/O
+        let _permit = match worktree::SETUP_LIMITER.try_acquire(max_concurrent) {
+            Some(permit) => permit,
+            None => {
+                info!(
+                    "[spawn_worktree_setup] {} setup jobs running, queueing copy for {}",
+                    worktree::SETUP_LIMITER.running(),
+                    worktree_id
+                );
+                let _ = app_handle.emit(events::WORKTREE_COPY_QUEUED, &worktree_id);
+                worktree::SETUP_LIMITER.acquire(max_concurrent)
+            }
+        };
+
+        // Emit copy started event
+        let _ = app_handle.emit(events::WORKTREE_COPY_STARTED, &worktree_id);
+
+        let start = Instant::now();
+        let store = dedupe.then(copy_store::CopyStore::open_default);
+        let result = worktree::copy_gitignored_files(
+            &project_path,
+            Path::new(&worktree_path),
+            &except,
+            store.as_ref(),
+        );
+
+        match &result {
+            Ok(()) => info!("[spawn_worktree_setup] background copy_gitignored_files took {:?}", start.elapsed()),
+            Err(e) => {
+                info!("[spawn_worktree_setup] background copy_gitignored_files failed: {}", e);
+                status::record_error("setup", format!("Copying files into worktree failed: {}", e));
+            }
+        }
+
+        // Emit copy completed event
+        let _ = app_handle.emit(events::WORKTREE_COPY_COMPLETED, WorktreeCopyCompleted {
+            worktree_id,
+            success: result.is_ok(),
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+    });
+    info!("[spawn_worktree_setup] spawned background thread for copy_gitignored_files");
+}
+
+/// Remove deduplicated copies no existing worktree links to
+#[tauri::command]
+fn gc_copy_store(state: State<'_, Arc<AppState>>) -> Result<copy_store::CopyStoreGc> {
+    let live: HashSet<String> = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .map(|w| path_utils::normalize_path_string(Path::new(&w.path)))
+        .collect();
+    copy_store::CopyStore::open_default().collect_garbage(&live).map_err(map_err)
+}
+
 #[tauri::command]
 fn create_worktree(
     app: AppHandle,
     state: State<'_, Arc<AppState>>,
     project_path: &str,
     name: Option<String>,
+    adopt_existing_branch: Option<bool>,
+    existing_branch: Option<String>,
 ) -> Result<Worktree> {
     let total_start = Instant::now();
     info!("[create_worktree] Starting...");
 
     let start = Instant::now();
-    let cfg = config::load_config_for_project(Some(project_path));
+    let cfg = load_project_config(&state, Some(project_path));
     info!("[create_worktree] load_config took {:?}", start.elapsed());
 
     let start = Instant::now();
@@ -498,53 +1316,28 @@ fn create_worktree(
         .projects
         .iter_mut()
         .find(|p| normalize_path_for_compare(Path::new(&p.path)) == project_path_key)
-        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_path)))?;
 
     let project_path_buf = Path::new(&project.path).to_path_buf();
 
+    let repo_lock = git::repo_lock(&project_path_buf);
+    let _repo_guard = repo_lock.lock();
+
     let start = Instant::now();
     let wt = worktree::create_worktree(
         project,
         name,
         cfg.worktree.directory.as_deref(),
         &cfg.worktree.base_branch,
+        &cfg.worktree.name_generator,
+        adopt_existing_branch.unwrap_or(false),
+        existing_branch.as_deref().filter(|b| !b.trim().is_empty()),
     )
     .map_err(map_err)?;
     info!("[create_worktree] worktree::create_worktree took {:?}", start.elapsed());
 
     // Copy gitignored files if enabled in config (in background thread)
-    if cfg.worktree.copy.gitignored {
-        let worktree_path = wt.path.clone();
-        let worktree_id = wt.id.clone();
-        let except = cfg.worktree.copy.except.clone();
-        let app_handle = app.clone();
-        let project_path_buf_clone = project_path_buf.clone();
-
-        // Emit copy started event
-        let _ = app_handle.emit("worktree-copy-started", &worktree_id);
-
-        std::thread::spawn(move || {
-            let start = Instant::now();
-            let result = worktree::copy_gitignored_files(
-                &project_path_buf_clone,
-                Path::new(&worktree_path),
-                &except,
-            );
-
-            match &result {
-                Ok(()) => info!("[create_worktree] background copy_gitignored_files took {:?}", start.elapsed()),
-                Err(e) => info!("[create_worktree] background copy_gitignored_files failed: {}", e),
-            }
-
-            // Emit copy completed event
-            let _ = app_handle.emit("worktree-copy-completed", serde_json::json!({
-                "worktreeId": worktree_id,
-                "success": result.is_ok(),
-                "durationMs": start.elapsed().as_millis() as u64,
-            }));
-        });
-        info!("[create_worktree] spawned background thread for copy_gitignored_files");
-    }
+    spawn_worktree_setup(&app, &cfg, &project_path_buf, &wt);
 
     // Start file watcher for this worktree
     let start = Instant::now();
@@ -561,6 +1354,195 @@ fn create_worktree(
     Ok(wt)
 }
 
+/// A worktree `create_worktrees_batch` couldn't create, by its requested name
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchCreateError {
+    /// None when the name was to be generated
+    name: Option<String>,
+    error: String,
+}
+
+/// The worktrees a batch created and one error for each name that failed
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchCreateResult {
+    worktrees: Vec<Worktree>,
+    errors: Vec<BatchCreateError>,
+}
+
+/// Create one worktree per name (None generates one). Partial success: a
+/// failed name is reported in `errors` and the rest are still created.
+#[tauri::command]
+fn create_worktrees_batch(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_path: &str,
+    names: Vec<Option<String>>,
+) -> Result<BatchCreateResult> {
+    let total_start = Instant::now();
+    info!("[create_worktrees_batch] Creating {} worktrees...", names.len());
+
+    let cfg = load_project_config(&state, Some(project_path));
+    let mut persisted = state.persisted.write();
+
+    let project_path_key = normalize_path_for_compare(Path::new(project_path));
+
+    let project = persisted
+        .projects
+        .iter_mut()
+        .find(|p| normalize_path_for_compare(Path::new(&p.path)) == project_path_key)
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_path)))?;
+
+    let project_path_buf = Path::new(&project.path).to_path_buf();
+    let mut result = BatchCreateResult::default();
+
+    for name in names {
+        // Serialize git operations per repository; setup work runs in the limited background pool
+        let repo_lock = git::repo_lock(&project_path_buf);
+        let repo_guard = repo_lock.lock();
+        let created = worktree::create_worktree(
+            project,
+            name.clone(),
+            cfg.worktree.directory.as_deref(),
+            &cfg.worktree.base_branch,
+            &cfg.worktree.name_generator,
+            false,
+            None,
+        );
+        drop(repo_guard);
+
+        match created {
+            Ok(wt) => {
+                spawn_worktree_setup(&app, &cfg, &project_path_buf, &wt);
+                watcher::watch_worktree(app.clone(), wt.id.clone(), wt.path.clone());
+                result.worktrees.push(wt);
+            }
+            Err(e) => {
+                info!("[create_worktrees_batch] Failed to create {:?}: {}", name, e);
+                result.errors.push(BatchCreateError {
+                    name,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    drop(persisted);
+
+    if !result.worktrees.is_empty() {
+        state.save().map_err(map_err)?;
+    }
+
+    info!(
+        "[create_worktrees_batch] Created {} worktrees ({} errors) in {:?}",
+        result.worktrees.len(),
+        result.errors.len(),
+        total_start.elapsed()
+    );
+    Ok(result)
+}
+
+/// Worktrees whose change summaries are computed at once by `list_all_worktrees`
+const CHANGE_SUMMARY_CONCURRENCY: usize = 4;
+
+/// Run `f` over `items` on at most `cap` threads, keeping input order
+fn map_capped<T: Sync, R: Send>(items: &[T], cap: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let results: Vec<parking_lot::Mutex<Option<R>>> = items.iter().map(|_| parking_lot::Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..cap.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some(item) = items.get(i) else { break };
+                *results[i].lock() = Some(f(item));
+            });
+        }
+    });
+    results.into_iter().filter_map(|r| r.into_inner()).collect()
+}
+
+/// A worktree together with the project it belongs to, for global switching
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalWorktree {
+    project_id: String,
+    project_name: String,
+    project_path: String,
+    project_active: bool,
+    #[serde(flatten)]
+    worktree: Worktree,
+    /// None if the worktree's status couldn't be read (e.g. pending cleanup)
+    changes: Option<git::ChangeSummary>,
+}
+
+/// Every worktree across all projects, in project then worktree order
+#[tauri::command]
+fn list_all_worktrees(state: State<'_, Arc<AppState>>) -> Vec<GlobalWorktree> {
+    // Copy out what's needed so the git status work runs without the state lock
+    let entries: Vec<(Project, Worktree)> = {
+        let persisted = state.persisted.read();
+        let mut projects: Vec<&Project> = persisted.projects.iter().collect();
+        projects.sort_by_key(|p| p.order);
+        projects
+            .into_iter()
+            .flat_map(|project| {
+                let mut worktrees = project.worktrees.clone();
+                worktrees.sort_by_key(|w| w.order);
+                let project = Project {
+                    worktrees: vec![],
+                    ..project.clone()
+                };
+                worktrees.into_iter().map(move |wt| (project.clone(), wt))
+            })
+            .collect()
+    };
+
+    map_capped(&entries, CHANGE_SUMMARY_CONCURRENCY, |(project, wt)| GlobalWorktree {
+        project_id: project.id.clone(),
+        project_name: project.name.clone(),
+        project_path: project.path.clone(),
+        project_active: project.is_active,
+        changes: if wt.pending_cleanup {
+            None
+        } else {
+            git::get_changed_files(Path::new(&wt.path))
+                .ok()
+                .map(|files| git::ChangeSummary::from_files(&files))
+        },
+        worktree: wt.clone(),
+    })
+}
+
+/// Results returned by `search_entities` when no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Fuzzy-rank projects and worktrees for the switcher palette
+#[tauri::command]
+fn search_entities(
+    state: State<'_, Arc<AppState>>,
+    query: &str,
+    limit: Option<usize>,
+) -> Vec<search::EntityMatch> {
+    let persisted = state.persisted.read();
+    search::search_entities(&persisted.projects, query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+}
+
+/// Preview where `worktree.directory` would place a worktree for `sample_branch`
+/// (a generated name when omitted), for live feedback while editing settings
+#[tauri::command]
+fn preview_worktree_directory(
+    project_path: &str,
+    template: Option<String>,
+    sample_branch: Option<String>,
+) -> Result<worktree::WorktreeDirectoryPreview> {
+    let sample_branch = sample_branch
+        .filter(|b| !b.trim().is_empty())
+        .unwrap_or_else(worktree::generate_worktree_name);
+    worktree::preview_worktree_directory(template.as_deref(), Path::new(project_path), &sample_branch)
+        .map_err(map_err)
+}
+
 #[tauri::command]
 fn list_worktrees(
     app: AppHandle,
@@ -576,7 +1558,7 @@ fn list_worktrees(
             .projects
             .iter()
             .find(|p| normalize_path_for_compare(Path::new(&p.path)) == project_path_key)
-            .ok_or_else(|| format!("Project not found: {}", project_path))?;
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_path)))?;
 
         return Ok(project.worktrees.clone());
     }
@@ -593,7 +1575,7 @@ fn list_worktrees(
             .projects
             .iter_mut()
             .find(|p| normalize_path_for_compare(Path::new(&p.path)) == project_path_key)
-            .ok_or_else(|| format!("Project not found: {}", project_path))?;
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_path)))?;
 
         match sync_project_worktrees_from_git(project) {
             Ok(sync) => {
@@ -601,7 +1583,7 @@ fn list_worktrees(
                     should_save = true;
                 }
 
-                worktrees_to_unwatch.extend(sync.removed_worktree_ids);
+                worktrees_to_unwatch.extend(sync.removed_worktrees.into_iter().map(|w| w.id));
                 if project.is_active {
                     worktrees_to_watch.extend(sync.added_worktrees);
                 }
@@ -632,25 +1614,72 @@ fn list_worktrees(
     Ok(result)
 }
 
-#[tauri::command]
-fn delete_worktree(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
-    let mut persisted = state.persisted.write();
+/// Check the `confirmations` config of a project before a destructive step
+fn ensure_confirmed(
+    state: &AppState,
+    project_path: &str,
+    operation: ConfirmableOperation,
+    confirmed: bool,
+) -> Result<()> {
+    load_project_config(state, Some(project_path))
+        .confirmations
+        .ensure_confirmed(operation, confirmed)?;
+    Ok(())
+}
 
-    // Find the project containing this worktree
-    for project in &mut persisted.projects {
-        if project.worktrees.iter().any(|w| w.id == worktree_id) {
-            worktree::delete_worktree(project, worktree_id).map_err(map_err)?;
-            drop(persisted);
-            state.save().map_err(map_err)?;
-            return Ok(());
-        }
+/// Confirmation check for the optional deletions of a merge or cleanup
+fn ensure_cleanup_confirmed(
+    state: &AppState,
+    project_path: &str,
+    delete_worktree: bool,
+    delete_remote_branch: bool,
+    confirmed: bool,
+) -> Result<()> {
+    if delete_worktree {
+        ensure_confirmed(state, project_path, ConfirmableOperation::DeleteWorktree, confirmed)?;
+    }
+    if delete_remote_branch {
+        ensure_confirmed(state, project_path, ConfirmableOperation::DeleteRemoteBranch, confirmed)?;
     }
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_worktree(state: State<'_, Arc<AppState>>, worktree_id: &str, confirmed: bool) -> Result<()> {
+    let project_path = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
+        .map(|p| p.path.clone())
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?;
+    ensure_confirmed(&state, &project_path, ConfirmableOperation::DeleteWorktree, confirmed)?;
+    let Some(_deletion) = state.begin_deletion(worktree_id) else {
+        return Err(CommandError::WorkflowInProgress {
+            worktree_id: worktree_id.to_string(),
+            workflow: WorkflowKind::Delete,
+        });
+    };
 
-    Err(format!("Worktree not found: {}", worktree_id))
+    // Sessions must be gone before removal so nothing keeps its cwd inside the worktree
+    pty::terminate_worktree_sessions(&state, worktree_id);
+
+    // On failure the worktree stays in state marked as pending cleanup
+    let result = delete_worktree_audited(&mut state.persisted.write(), worktree_id, serde_json::Value::Null);
+    if let Err(worktree::WorktreeError::WorktreeNotFound(_)) = result {
+        return Err(CommandError::NotFound(format!("Worktree not found: {}", worktree_id)));
+    }
+    state.save().map_err(map_err)?;
+    result.map_err(map_err)
 }
 
 #[tauri::command]
-fn check_worktree_delete_status(worktree_path: &str, project_path: Option<String>) -> Result<WorktreeDeleteStatus> {
+fn check_worktree_delete_status(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> Result<WorktreeDeleteStatus> {
     let path = Path::new(worktree_path);
 
     // If the worktree path is already missing or no longer a git repository,
@@ -669,7 +1698,7 @@ fn check_worktree_delete_status(worktree_path: &str, project_path: Option<String
         });
     }
 
-    let cfg = config::load_config_for_project(project_path.as_deref());
+    let cfg = load_project_config(&state, project_path.as_deref());
     git::check_worktree_delete_status(path, &cfg.worktree.base_branch).map_err(map_err)
 }
 
@@ -679,6 +1708,7 @@ fn execute_delete_worktree_workflow(
     state: State<'_, Arc<AppState>>,
     worktree_id: &str,
     options: DeleteWorktreeOptions,
+    confirmed: bool,
 ) {
     // Extract worktree info before spawning thread
     let worktree_info = {
@@ -701,7 +1731,7 @@ fn execute_delete_worktree_workflow(
             Some(data) => data,
             None => {
                 let _ = app.emit(
-                    "delete-worktree-completed",
+                    events::DELETE_WORKTREE_COMPLETED,
                     DeleteWorktreeCompleted {
                         worktree_id: worktree_id.to_string(),
                         success: false,
@@ -717,11 +1747,47 @@ fn execute_delete_worktree_workflow(
     let app_state = Arc::clone(&*state);
     let (worktree_name, worktree_path, branch_name, project_path) = worktree_info;
 
+    if let Err(e) = ensure_confirmed(&state, &project_path, ConfirmableOperation::DeleteWorktree, confirmed) {
+        let _ = app.emit(
+            events::DELETE_WORKTREE_COMPLETED,
+            DeleteWorktreeCompleted {
+                worktree_id,
+                success: false,
+                error: Some(e.to_string()),
+            },
+        );
+        return;
+    }
+
+    // Claimed before spawning so a second request fails here instead of racing this one
+    let Some(deletion) = app_state.begin_deletion(&worktree_id) else {
+        let _ = app.emit(
+            events::DELETE_WORKTREE_COMPLETED,
+            DeleteWorktreeCompleted {
+                worktree_id: worktree_id.clone(),
+                success: false,
+                error: Some(
+                    CommandError::WorkflowInProgress {
+                        worktree_id: worktree_id.clone(),
+                        workflow: WorkflowKind::Delete,
+                    }
+                    .to_string(),
+                ),
+            },
+        );
+        return;
+    };
+
     // Spawn background thread to avoid blocking UI
     std::thread::spawn(move || {
+        // Released on every return, including the pending-cleanup one
+        let _deletion = deletion;
+        let workflow = status::WorkflowGuard::start(WorkflowKind::Delete, &worktree_id, &project_path);
+
         // Step 1: Stop file watcher
+        workflow.set_phase("stop-watcher");
         let _ = app.emit(
-            "delete-worktree-progress",
+            events::DELETE_WORKTREE_PROGRESS,
             DeleteWorktreeProgress {
                 phase: "stop-watcher".to_string(),
                 message: "Stopping file watcher...".to_string(),
@@ -729,9 +1795,21 @@ fn execute_delete_worktree_workflow(
         );
         watcher::stop_watching(&worktree_id);
 
-        // Step 2: Remove git worktree (this also deletes the directory)
+        // Step 2: Terminate sessions running inside the worktree (must happen before removal)
+        workflow.set_phase("stop-sessions");
+        let _ = app.emit(
+            events::DELETE_WORKTREE_PROGRESS,
+            DeleteWorktreeProgress {
+                phase: "stop-sessions".to_string(),
+                message: "Stopping terminals...".to_string(),
+            },
+        );
+        pty::terminate_worktree_sessions(&app_state, &worktree_id);
+
+        // Step 3: Remove worktree directory and git registration
+        workflow.set_phase("remove-worktree");
         let _ = app.emit(
-            "delete-worktree-progress",
+            events::DELETE_WORKTREE_PROGRESS,
             DeleteWorktreeProgress {
                 phase: "remove-worktree".to_string(),
                 message: "Removing worktree...".to_string(),
@@ -739,84 +1817,89 @@ fn execute_delete_worktree_workflow(
         );
         let project_path = Path::new(&project_path);
         let worktree_path_buf = PathBuf::from(&worktree_path);
-        if let Err(e) = git::delete_worktree(project_path, &worktree_name) {
-            // If the path is already missing / invalid repo, treat this as stale and
-            // continue cleanup so UI state can be fixed.
-            let is_stale = !worktree_path_buf.exists() || !git::is_git_repo(&worktree_path_buf);
-            if is_stale {
-                info!(
-                    "[execute_delete_worktree_workflow] Stale worktree '{}', continuing cleanup: {}",
-                    worktree_name,
-                    e
-                );
-            } else {
-                let _ = app.emit(
-                    "delete-worktree-progress",
-                    DeleteWorktreeProgress {
-                        phase: "error".to_string(),
-                        message: e.to_string(),
-                    },
-                );
-                let _ = app.emit(
-                    "delete-worktree-completed",
-                    DeleteWorktreeCompleted {
-                        worktree_id,
-                        success: false,
-                        error: Some(e.to_string()),
-                    },
-                );
-                return;
+        let removal = audit::record_result(
+            AuditEvent::new(AuditOperation::DeleteWorktree, project_path)
+                .with_worktree(&worktree_name)
+                .with_branch(&branch_name)
+                .with_options(serde_json::to_value(&options).unwrap_or_default()),
+            worktree::remove_worktree_files(project_path, &worktree_name, &worktree_path_buf),
+        );
+        if let Err(e) = removal {
+            // Keep the entry so cleanup can be retried instead of leaving a half-deleted worktree
+            info!(
+                "[execute_delete_worktree_workflow] Could not remove '{}', marking pending cleanup: {}",
+                worktree_name,
+                e
+            );
+            app_state.set_worktree_pending_cleanup(&worktree_id, true);
+            if let Err(e) = app_state.save() {
+                info!("Failed to save state after marking pending cleanup: {}", e);
             }
-        }
 
-        // Clean up directory if git didn't remove it
-        if worktree_path_buf.exists() {
-            if let Err(e) = std::fs::remove_dir_all(&worktree_path_buf) {
-                info!("Failed to remove worktree directory: {}", e);
-            }
+            let message = format!("Worktree directory could not be removed, cleanup will be retried: {}", e);
+            status::record_error("delete-worktree", message.clone());
+            workflow.set_phase("pending-cleanup");
+            let _ = app.emit(
+                events::DELETE_WORKTREE_PROGRESS,
+                DeleteWorktreeProgress {
+                    phase: "pending-cleanup".to_string(),
+                    message: message.clone(),
+                },
+            );
+            let _ = app.emit(
+                events::DELETE_WORKTREE_COMPLETED,
+                DeleteWorktreeCompleted {
+                    worktree_id,
+                    success: false,
+                    error: Some(message),
+                },
+            );
+            return;
         }
 
-        // Step 3: Delete local branch if requested
+        // Step 4: Delete local branch if requested
         if options.delete_branch {
+            workflow.set_phase("delete-local-branch");
             let _ = app.emit(
-                "delete-worktree-progress",
+                events::DELETE_WORKTREE_PROGRESS,
                 DeleteWorktreeProgress {
                     phase: "delete-local-branch".to_string(),
                     message: "Deleting local branch...".to_string(),
                 },
             );
 
-            if let Err(e) = git::delete_local_branch(project_path, &branch_name) {
+            let result = audit::record_result(
+                AuditEvent::new(AuditOperation::DeleteLocalBranch, project_path)
+                    .with_worktree(&worktree_name)
+                    .with_branch(&branch_name)
+                    .with_options(serde_json::to_value(&options).unwrap_or_default()),
+                git::delete_local_branch(project_path, &branch_name),
+            );
+            if let Err(e) = result {
                 info!("Failed to delete local branch: {}", e);
                 // Don't fail the whole operation if branch deletion fails
             }
         }
 
-        // Step 4: Save changes
+        // Step 5: Save changes
+        workflow.set_phase("save");
         let _ = app.emit(
-            "delete-worktree-progress",
+            events::DELETE_WORKTREE_PROGRESS,
             DeleteWorktreeProgress {
                 phase: "save".to_string(),
                 message: "Saving...".to_string(),
             },
         );
-        {
-            let mut persisted = app_state.persisted.write();
-            for project in &mut persisted.projects {
-                if let Some(idx) = project.worktrees.iter().position(|w| w.id == worktree_id) {
-                    project.worktrees.remove(idx);
-                    break;
-                }
-            }
-        }
+        app_state.remove_worktree_entry(&worktree_id);
 
         if let Err(e) = app_state.save() {
             info!("Failed to save state after worktree deletion: {}", e);
         }
 
         // Emit completion
+        workflow.set_phase("complete");
         let _ = app.emit(
-            "delete-worktree-progress",
+            events::DELETE_WORKTREE_PROGRESS,
             DeleteWorktreeProgress {
                 phase: "complete".to_string(),
                 message: "Done".to_string(),
@@ -824,7 +1907,7 @@ fn execute_delete_worktree_workflow(
         );
 
         let _ = app.emit(
-            "delete-worktree-completed",
+            events::DELETE_WORKTREE_COMPLETED,
             DeleteWorktreeCompleted {
                 worktree_id,
                 success: true,
@@ -836,50 +1919,109 @@ fn execute_delete_worktree_workflow(
     info!("[execute_delete_worktree_workflow] spawned background thread");
 }
 
-/// Remove a worktree from state by its path (used when worktree folder is deleted externally)
+/// Retry removing a worktree that a previous delete left in pending cleanup
 #[tauri::command]
-fn remove_stale_worktree(state: State<'_, Arc<AppState>>, worktree_path: &str) -> Result<()> {
-    let mut persisted = state.persisted.write();
-    let target_key = normalize_path_for_compare(Path::new(worktree_path));
+fn retry_worktree_cleanup(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
+    let is_pending = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter())
+        .find(|w| w.id == worktree_id)
+        .map(|w| w.pending_cleanup)
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?;
 
-    for project in &mut persisted.projects {
-        if let Some(idx) = project
-            .worktrees
-            .iter()
-            .position(|w| normalize_path_for_compare(Path::new(&w.path)) == target_key)
-        {
-            let worktree = &project.worktrees[idx];
-            info!(
-                "[remove_stale_worktree] Removing '{}' from project '{}'",
-                worktree.name, project.name
-            );
+    if !is_pending {
+        return Err(CommandError::InvalidInput(format!("Worktree is not pending cleanup: {}", worktree_id)));
+    }
 
-            // Stop watching this worktree
-            watcher::stop_watching(&worktree.id);
+    // The deletion being retried was already confirmed
+    delete_worktree(state, worktree_id, true)
+}
 
-            // Remove from state (don't try to delete files - they're already gone)
-            project.worktrees.remove(idx);
-            drop(persisted);
-            state.save().map_err(map_err)?;
-            return Ok(());
+/// Retry cleanup of all worktrees left in pending cleanup (run once at startup)
+fn retry_pending_worktree_cleanups(state: &AppState) {
+    let pending_ids: Vec<String> = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .flat_map(|p| p.worktrees.iter())
+        .filter(|w| w.pending_cleanup)
+        .map(|w| w.id.clone())
+        .collect();
+
+    if pending_ids.is_empty() {
+        return;
+    }
+
+    for worktree_id in &pending_ids {
+        let options = serde_json::json!({ "retry": true });
+        match delete_worktree_audited(&mut state.persisted.write(), worktree_id, options) {
+            Ok(()) => info!("[retry_pending_worktree_cleanups] Cleaned up worktree {}", worktree_id),
+            Err(worktree::WorktreeError::WorktreeNotFound(_)) => {}
+            Err(e) => info!("[retry_pending_worktree_cleanups] Worktree {} still pending: {}", worktree_id, e),
         }
     }
 
-    // Not found is OK - might have already been cleaned up
-    Ok(())
+    if let Err(e) = state.save() {
+        info!("[retry_pending_worktree_cleanups] Failed to save state: {}", e);
+    }
 }
 
-// PTY commands
+/// Remove a worktree from state by its path (used when worktree folder is deleted externally)
 #[tauri::command]
-fn spawn_main(
-    app: AppHandle,
-    state: State<'_, Arc<AppState>>,
-    worktree_id: &str,
-    cols: Option<u16>,
-    rows: Option<u16>,
-) -> Result<String> {
-    // Find worktree info and parent project path
-    let (worktree_path, worktree_name, worktree_branch, project_path) = {
+fn remove_stale_worktree(state: State<'_, Arc<AppState>>, worktree_path: &str) -> Result<()> {
+    if remove_stale_worktree_entry(&state, worktree_path) {
+        state.save().map_err(map_err)?;
+    }
+    Ok(())
+}
+
+/// Forget the worktree at `worktree_path` without touching files (they're
+/// already gone). A no-op if it's unknown or a deletion of it is underway,
+/// which then owns the state change. Returns whether state changed.
+fn remove_stale_worktree_entry(state: &AppState, worktree_path: &str) -> bool {
+    let target_key = normalize_path_for_compare(Path::new(worktree_path));
+    let found = state.persisted.read().projects.iter().find_map(|project| {
+        project
+            .worktrees
+            .iter()
+            .find(|w| normalize_path_for_compare(Path::new(&w.path)) == target_key)
+            .map(|w| (w.id.clone(), w.name.clone(), project.name.clone(), project.path.clone()))
+    });
+
+    // Not found is OK - might have already been cleaned up
+    let Some((worktree_id, worktree_name, project_name, project_path)) = found else {
+        return false;
+    };
+    let Some(_deletion) = state.begin_deletion(&worktree_id) else {
+        info!("[remove_stale_worktree] '{}' is already being deleted, skipping", worktree_name);
+        return false;
+    };
+
+    info!(
+        "[remove_stale_worktree] Removing '{}' from project '{}'",
+        worktree_name, project_name
+    );
+    watcher::stop_watching(&worktree_id);
+    let removed = state.remove_worktree_entry(&worktree_id);
+    forget_working_snapshots(&mut state.persisted.write(), &project_path, worktree_path);
+    removed
+}
+
+// PTY commands
+#[tauri::command]
+fn spawn_main(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String> {
+    // Find worktree info and parent project path
+    let (worktree_path, worktree_name, worktree_branch, project_path) = {
         let persisted = state.persisted.read();
         let mut found = None;
 
@@ -895,25 +2037,18 @@ fn spawn_main(
             }
         }
 
-        found.ok_or_else(|| format!("Worktree not found: {}", worktree_id))?
+        found.ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
     };
 
     // Load config with project-specific overrides
-    let cfg = config::load_config_for_project(Some(&project_path));
+    let cfg = load_project_config(&state, Some(&project_path));
 
     // Expand template variables in command, or use shell if not configured
-    let command = match &cfg.main.command {
-        Some(cmd) => {
-            let ctx = template::TemplateContext::new(&project_path)
-                .with_branch(&worktree_branch)
-                .with_worktree_name(&worktree_name);
-            template::expand_template(cmd, &ctx).map_err(map_err)?
-        }
-        None => "shell".to_string(),
-    };
+    let command = main_command(&cfg, &project_path, Some((&worktree_branch, &worktree_name)))?
+        .unwrap_or_else(|| "shell".to_string());
 
     let shell_override = cfg.shell.as_deref();
-    pty::spawn_pty(&app, &state, worktree_id, &worktree_path, &command, cols, rows, shell_override, None).map_err(map_err)
+    pty::spawn_pty(&app, &state, worktree_id, &worktree_path, &command, cols, rows, shell_override, None, Some(MAIN_RESULT_KEY), None, &cfg.terminal).map_err(map_err)
 }
 
 #[tauri::command]
@@ -943,12 +2078,12 @@ fn spawn_terminal(
                 .iter()
                 .find(|p| p.id == worktree_id)
                 .map(|p| p.path.clone())
-        }).ok_or_else(|| format!("Worktree or project not found: {}", worktree_id))?
+        }).ok_or_else(|| CommandError::NotFound(format!("Worktree or project not found: {}", worktree_id)))?
     };
 
-    let cfg = config::load_config_for_project(Some(&path));
+    let cfg = load_project_config(&state, Some(&path));
     let shell_override = cfg.shell.as_deref();
-    pty::spawn_pty(&app, &state, worktree_id, &path, "shell", cols, rows, shell_override, None).map_err(map_err)
+    pty::spawn_pty(&app, &state, worktree_id, &path, "shell", cols, rows, shell_override, None, None, None, &cfg.terminal).map_err(map_err)
 }
 
 #[tauri::command]
@@ -972,11 +2107,11 @@ fn spawn_action(
                     .find(|w| w.id == worktree_id)
                     .map(|w| (w.path.clone(), p.path.clone()))
             })
-            .ok_or_else(|| format!("Worktree not found: {}", worktree_id))?
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
     };
 
     // Load config to get the action command
-    let config = config::load_config_for_project(Some(&project_path));
+    let config = load_project_config(&state, Some(&project_path));
     let action_command = &config.actions.command;
 
     // Start action command with initial prompt (stays interactive)
@@ -989,7 +2124,155 @@ fn spawn_action(
         .clone()
         .unwrap_or_else(pty::get_default_shell_command);
 
-    pty::spawn_pty(&app, &state, worktree_id, &worktree_path, &command, cols, rows, Some(&shell), None).map_err(map_err)
+    pty::spawn_pty(&app, &state, worktree_id, &worktree_path, &command, cols, rows, Some(&shell), None, None, None, &config.terminal).map_err(map_err)
+}
+
+/// Run `git <args>` in a worktree's terminal, for commands that need a human:
+/// credential prompts, editors, interactive rebase. Shown like an action session.
+#[tauri::command]
+fn spawn_git_interactive(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    args: Vec<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String> {
+    if args.is_empty() {
+        return Err(CommandError::InvalidInput("No git arguments given".to_string()));
+    }
+
+    let (worktree_path, project_path) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                p.worktrees
+                    .iter()
+                    .find(|w| w.id == worktree_id)
+                    .map(|w| (w.path.clone(), p.path.clone()))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
+    };
+
+    let config = load_project_config(&state, Some(&project_path));
+    let command = std::iter::once("git".to_string())
+        .chain(args.iter().map(|arg| shell_escape::escape(arg.as_str().into()).into_owned()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let shell = config
+        .shell
+        .clone()
+        .unwrap_or_else(pty::get_default_shell_command);
+
+    let env_vars = HashMap::from([(
+        "GIT_EDITOR".to_string(),
+        git::interactive_editor(Path::new(&worktree_path)),
+    )]);
+
+    pty::spawn_pty(&app, &state, worktree_id, &worktree_path, &command, cols, rows, Some(&shell), Some(&env_vars), None, None, &config.terminal).map_err(map_err)
+}
+
+/// Run `git rebase -i <base>` in a terminal with the todo list generated from
+/// `plan`. Reword steps open the editor in that terminal; if git stops on a
+/// conflict the rebase is left in progress for the rebase watcher and
+/// `continue_rebase`.
+#[tauri::command]
+fn start_interactive_rebase(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    base: &str,
+    plan: Vec<interactive_rebase::RebaseAction>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<String> {
+    let path = Path::new(worktree_path);
+    if git::is_rebase_in_progress(path).map_err(map_err)? {
+        return Err(CommandError::InvalidInput("A rebase is already in progress".to_string()));
+    }
+
+    // The session belongs to the worktree (or project) checked out at this path
+    let key = path_utils::canonicalize_for_storage(path);
+    let (entity_id, project_path) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                let same = |other: &str| path_utils::canonicalize_for_storage(Path::new(other)) == key;
+                p.worktrees
+                    .iter()
+                    .find(|w| same(&w.path))
+                    .map(|w| (w.id.clone(), p.path.clone()))
+                    .or_else(|| same(&p.path).then(|| (p.id.clone(), p.path.clone())))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_path)))?
+    };
+
+    let todo = interactive_rebase::build_todo(path, base, &plan).map_err(map_err)?;
+    let script = interactive_rebase::write_sequence_editor(&todo).map_err(map_err)?;
+
+    let config = load_project_config(&state, Some(&project_path));
+    let command = format!("git rebase -i {}", shell_escape::escape(base.into()));
+    let shell = config
+        .shell
+        .clone()
+        .unwrap_or_else(pty::get_default_shell_command);
+    let env_vars = HashMap::from([
+        ("GIT_SEQUENCE_EDITOR".to_string(), interactive_rebase::sequence_editor_command(&script)),
+        ("GIT_EDITOR".to_string(), git::interactive_editor(path)),
+    ]);
+
+    // The script deletes itself when git runs it; git may fail before that
+    let pty_id =
+        pty::spawn_pty(&app, &state, &entity_id, worktree_path, &command, cols, rows, Some(&shell), Some(&env_vars), None, None, &config.terminal)
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&script);
+            })
+            .map_err(map_err)?;
+    pty::remove_file_on_exit(&pty_id, script);
+    Ok(pty_id)
+}
+
+/// Save the frontend's tab and split layout for a worktree; null clears it
+#[tauri::command]
+fn save_worktree_layout(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    layout: Option<serde_json::Value>,
+) -> Result<()> {
+    if !state.set_worktree_layout(worktree_id, layout) {
+        return Err(CommandError::NotFound(format!("Worktree not found: {}", worktree_id)));
+    }
+    state.save().map_err(map_err)
+}
+
+/// Clear a worktree's saved UI state, e.g. a layout whose panes no longer make sense
+#[tauri::command]
+fn reset_worktree_layout(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<Worktree> {
+    let worktree = state
+        .reset_worktree_ui_state(worktree_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?;
+    state.save().map_err(map_err)?;
+    Ok(worktree)
+}
+
+/// The layout last saved for a worktree, if any
+#[tauri::command]
+fn get_worktree_layout(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+) -> Result<Option<serde_json::Value>> {
+    let persisted = state.persisted.read();
+    persisted
+        .projects
+        .iter()
+        .flat_map(|p| &p.worktrees)
+        .find(|w| w.id == worktree_id)
+        .map(|w| w.layout.clone())
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))
 }
 
 #[tauri::command]
@@ -1006,7 +2289,7 @@ fn watch_merge_state(
             .iter()
             .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
             .map(|p| p.path.clone())
-            .ok_or_else(|| format!("Worktree not found: {}", worktree_id))?
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
     };
 
     watcher::watch_merge_state(app, worktree_id.to_string(), project_path);
@@ -1018,6 +2301,20 @@ fn stop_merge_watcher(worktree_id: &str) {
     watcher::stop_merge_watcher(worktree_id);
 }
 
+/// Emit `commit-added` whenever the worktree's HEAD advances, until
+/// `stop_watch_commits`
+#[tauri::command]
+fn watch_commits(app: AppHandle, state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
+    let worktree_path = worktree_path_by_id(&state, worktree_id)?;
+    watcher::watch_commits(app, worktree_id.to_string(), worktree_path);
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_watch_commits(worktree_id: &str) {
+    watcher::stop_commit_watcher(worktree_id);
+}
+
 #[tauri::command]
 fn watch_rebase_state(
     app: AppHandle,
@@ -1033,7 +2330,7 @@ fn watch_rebase_state(
             .flat_map(|p| &p.worktrees)
             .find(|w| w.id == worktree_id)
             .map(|w| w.path.clone())
-            .ok_or_else(|| format!("Worktree not found: {}", worktree_id))?
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
     };
 
     watcher::watch_rebase_state(app, worktree_id.to_string(), worktree_path);
@@ -1045,6 +2342,22 @@ fn stop_rebase_watcher(worktree_id: &str) {
     watcher::stop_rebase_watcher(worktree_id);
 }
 
+/// Switcher tasks of a project, grouped and sorted, with whether each is
+/// running in `entity_id` and any shortcut that names it
+#[tauri::command]
+fn get_task_catalog(
+    state: State<'_, Arc<AppState>>,
+    project_path: Option<String>,
+    entity_id: Option<String>,
+) -> Vec<task_catalog::TaskGroup> {
+    let cfg = load_project_config(&state, project_path.as_deref());
+    let running = entity_id
+        .map(|id| pty::running_task_names(&state, &id))
+        .unwrap_or_default();
+    let shortcuts = task_catalog::task_shortcuts(&mappings::load_mappings().mappings);
+    task_catalog::build_catalog(&cfg.tasks, &cfg.tasks_groups, &running, &shortcuts)
+}
+
 #[tauri::command]
 fn spawn_task(
     app: AppHandle,
@@ -1053,6 +2366,19 @@ fn spawn_task(
     task_name: &str,
     cols: Option<u16>,
     rows: Option<u16>,
+) -> Result<String> {
+    start_task(&app, &state, entity_id, task_name, cols, rows)
+}
+
+/// Spawn the configured task `task_name` in an entity (worktree or project),
+/// returning the new PTY id
+fn start_task(
+    app: &AppHandle,
+    state: &AppState,
+    entity_id: &str,
+    task_name: &str,
+    cols: Option<u16>,
+    rows: Option<u16>,
 ) -> Result<String> {
     // Find entity info and project path (entity can be a worktree or a project)
     // Returns: (entity_path, project_path, branch, optional_worktree_name)
@@ -1083,16 +2409,16 @@ fn spawn_task(
             }
         }
 
-        found.ok_or_else(|| format!("Entity not found: {}", entity_id))?
+        found.ok_or_else(|| CommandError::NotFound(format!("Entity not found: {}", entity_id)))?
     };
 
     // Load config and find the task
-    let cfg = config::load_config_for_project(Some(&project_path));
+    let cfg = load_project_config(state, Some(&project_path));
     let task = cfg
         .tasks
         .iter()
         .find(|t| t.name == task_name)
-        .ok_or_else(|| format!("Task not found: {}", task_name))?;
+        .ok_or_else(|| CommandError::NotFound(format!("Task not found: {}", task_name)))?;
 
     // Expand template variables in command
     let mut ctx = template::TemplateContext::new(&project_path).with_branch(&branch);
@@ -1106,14 +2432,67 @@ fn spawn_task(
         .env
         .iter()
         .map(|(key, value)| {
-            let expanded = template::expand_template(value, &ctx).unwrap_or_else(|_| value.clone());
+            let expanded = template::expand_template(value, &ctx).unwrap_or_else(|e| {
+                template::warn_template_failure(&format!("tasks[{}].env.{}", task.name, key), value, &e);
+                value.clone()
+            });
             (key.clone(), expanded)
         })
         .collect();
     let env_vars = if expanded_env.is_empty() { None } else { Some(&expanded_env) };
 
-    pty::spawn_pty(&app, &state, entity_id, &entity_path, &command, cols, rows, task.shell.as_deref(), env_vars)
-        .map_err(map_err)
+    let pty_id = pty::spawn_pty(app, state, entity_id, &entity_path, &command, cols, rows, task.shell.as_deref(), env_vars, Some(task_name), task.uses_json_lines().then_some(task_name), &cfg.terminal)
+        .map_err(map_err)?;
+    if let Some(minutes) = task.effective_idle_shutdown_minutes(&cfg.task_defaults) {
+        idle::register_task(&pty_id, entity_id, task_name, minutes);
+    }
+    Ok(pty_id)
+}
+
+/// Tasks to start when an entity is opened: daemons marked `autoStart` that
+/// aren't running there yet, cut off once `limit` task sessions (counting
+/// `running_total` already live) would be running
+fn auto_start_tasks<'a>(
+    tasks: &'a [config::TaskConfig],
+    running: &std::collections::HashSet<String>,
+    running_total: usize,
+    limit: Option<usize>,
+) -> Vec<&'a config::TaskConfig> {
+    let room = limit.filter(|l| *l > 0).map_or(usize::MAX, |l| l.saturating_sub(running_total));
+    tasks
+        .iter()
+        .filter(|t| t.auto_start && t.kind == config::TaskKind::Daemon && !running.contains(&t.name))
+        .take(room)
+        .collect()
+}
+
+/// Start the entity's auto-start tasks, emitting `task-auto-started` for each
+fn start_auto_start_tasks(app: &AppHandle, state: &AppState, entity_id: &str) {
+    let project_path = state.worktree_project_path(entity_id).or_else(|| {
+        let persisted = state.persisted.read();
+        persisted.projects.iter().find(|p| p.id == entity_id).map(|p| p.path.clone())
+    });
+    let Some(project_path) = project_path else {
+        return;
+    };
+    let cfg = load_project_config(state, Some(&project_path));
+    let running = pty::running_task_names(state, entity_id);
+    let tasks = auto_start_tasks(&cfg.tasks, &running, pty::live_task_session_count(state), cfg.task_defaults.auto_start_limit);
+    for task in tasks {
+        match start_task(app, state, entity_id, &task.name, None, None) {
+            Ok(pty_id) => {
+                let _ = app.emit(
+                    events::TASK_AUTO_STARTED,
+                    events::TaskAutoStarted {
+                        worktree_id: entity_id.to_string(),
+                        task_name: task.name.clone(),
+                        pty_id,
+                    },
+                );
+            }
+            Err(e) => info!("[auto_start] Failed to start '{}' in {}: {}", task.name, entity_id, e),
+        }
+    }
 }
 
 /// A named URL returned from get_task_urls
@@ -1154,16 +2533,16 @@ fn get_task_urls(
             }
         }
 
-        found.ok_or_else(|| format!("Entity not found: {}", entity_id))?
+        found.ok_or_else(|| CommandError::NotFound(format!("Entity not found: {}", entity_id)))?
     };
 
     // Load config and find the task
-    let cfg = config::load_config_for_project(Some(&project_path));
+    let cfg = load_project_config(&state, Some(&project_path));
     let task = cfg
         .tasks
         .iter()
         .find(|t| t.name == task_name)
-        .ok_or_else(|| format!("Task not found: {}", task_name))?;
+        .ok_or_else(|| CommandError::NotFound(format!("Task not found: {}", task_name)))?;
 
     // Build template context
     let ctx = TemplateContext::new(&project_path).with_branch(&branch);
@@ -1172,10 +2551,12 @@ fn get_task_urls(
     let urls: Vec<NamedUrl> = task
         .urls
         .iter()
-        .filter_map(|(name, url_template)| {
-            expand_template(url_template, &ctx)
-                .ok()
-                .map(|url| NamedUrl { name: name.clone(), url })
+        .filter_map(|(name, url_template)| match expand_template(url_template, &ctx) {
+            Ok(url) => Some(NamedUrl { name: name.clone(), url }),
+            Err(e) => {
+                template::warn_template_failure(&format!("tasks[{}].urls.{}", task.name, name), url_template, &e);
+                None
+            }
         })
         .collect();
 
@@ -1198,24 +2579,63 @@ fn spawn_project_shell(
             .iter()
             .find(|p| p.id == project_id)
             .map(|p| p.path.clone())
-            .ok_or_else(|| format!("Project not found: {}", project_id))?
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?
     };
 
     // Load config with project-specific overrides
-    let cfg = config::load_config_for_project(Some(&project_path));
+    let cfg = load_project_config(&state, Some(&project_path));
 
     // Expand template variables in command, or use shell if not configured
-    let command = match &cfg.main.command {
-        Some(cmd) => {
-            let ctx = template::TemplateContext::new(&project_path);
-            template::expand_template(cmd, &ctx).map_err(map_err)?
-        }
-        None => "shell".to_string(),
-    };
+    let command = main_command(&cfg, &project_path, None)?.unwrap_or_else(|| "shell".to_string());
 
     let shell_override = cfg.shell.as_deref();
     // Use project_id as the "worktree_id" for PTY tracking purposes
-    pty::spawn_pty(&app, &state, project_id, &project_path, &command, cols, rows, shell_override, None).map_err(map_err)
+    pty::spawn_pty(&app, &state, project_id, &project_path, &command, cols, rows, shell_override, None, None, None, &cfg.terminal).map_err(map_err)
+}
+
+/// Shown by `preview_main_command` when `main.command` isn't set
+const USER_SHELL_LABEL: &str = "<user shell>";
+
+/// `main.command` with its templates expanded, or None to run the user's shell.
+/// Worktrees get their branch and name; a project only its directory.
+fn main_command(cfg: &config::Config, project_path: &str, worktree: Option<(&str, &str)>) -> Result<Option<String>> {
+    let Some(cmd) = &cfg.main.command else {
+        return Ok(None);
+    };
+    let mut ctx = template::TemplateContext::new(project_path);
+    if let Some((branch, name)) = worktree {
+        ctx = ctx.with_branch(branch).with_worktree_name(name);
+    }
+    template::expand_template(cmd, &ctx).map(Some).map_err(map_err)
+}
+
+/// What the main terminal of a worktree or project would run
+#[tauri::command]
+fn preview_main_command(state: State<'_, Arc<AppState>>, entity_id: &str) -> Result<String> {
+    let (project_path, worktree) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                p.worktrees
+                    .iter()
+                    .find(|w| w.id == entity_id)
+                    .map(|w| (p.path.clone(), Some((w.branch.clone(), w.name.clone()))))
+            })
+            .or_else(|| {
+                persisted
+                    .projects
+                    .iter()
+                    .find(|p| p.id == entity_id)
+                    .map(|p| (p.path.clone(), None))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree or project not found: {}", entity_id)))?
+    };
+
+    let cfg = load_project_config(&state, Some(&project_path));
+    let worktree = worktree.as_ref().map(|(branch, name)| (branch.as_str(), name.as_str()));
+    Ok(main_command(&cfg, &project_path, worktree)?.unwrap_or_else(|| USER_SHELL_LABEL.to_string()))
 }
 
 #[tauri::command]
@@ -1241,7 +2661,7 @@ fn spawn_scratch_terminal(
     let shell_override = cfg.shell.as_deref();
 
     // Use scratch_id as the entity ID for PTY tracking purposes
-    pty::spawn_pty(&app, &state, scratch_id, &path, "shell", cols, rows, shell_override, None).map_err(map_err)
+    pty::spawn_pty(&app, &state, scratch_id, &path, "shell", cols, rows, shell_override, None, None, None, &cfg.terminal).map_err(map_err)
 }
 
 #[tauri::command]
@@ -1263,9 +2683,9 @@ fn spawn_shell(
         }
     };
 
-    let cfg = config::load_config_for_project(directory);
+    let cfg = load_project_config(&state, directory);
     let shell_override = cfg.shell.as_deref();
-    pty::spawn_pty(&app, &state, entity_id, &path, "shell", cols, rows, shell_override, None).map_err(map_err)
+    pty::spawn_pty(&app, &state, entity_id, &path, "shell", cols, rows, shell_override, None, None, None, &cfg.terminal).map_err(map_err)
 }
 
 /// Spawn a PTY running a specific command (for opening editors in drawer/tab)
@@ -1280,9 +2700,9 @@ fn spawn_command(
     rows: Option<u16>,
 ) -> Result<String> {
     // Run through user's shell so quoted paths and shell features work correctly
-    let cfg = config::load_config_for_project(Some(directory));
+    let cfg = load_project_config(&state, Some(directory));
     let shell = cfg.shell.clone().unwrap_or_else(pty::get_default_shell_command);
-    pty::spawn_pty(&app, &state, entity_id, directory, command, cols, rows, Some(&shell), None).map_err(map_err)
+    pty::spawn_pty(&app, &state, entity_id, directory, command, cols, rows, Some(&shell), None, None, None, &cfg.terminal).map_err(map_err)
 }
 
 #[tauri::command]
@@ -1295,6 +2715,16 @@ fn pty_resize(state: State<'_, Arc<AppState>>, pty_id: &str, cols: u16, rows: u1
     pty::resize_pty(&state, pty_id, cols, rows).map_err(map_err)
 }
 
+#[tauri::command]
+fn attach_pty(app: AppHandle, state: State<'_, Arc<AppState>>, pty_id: &str, cols: u16, rows: u16) -> Result<()> {
+    pty::attach_pty(&app, &state, pty_id, cols, rows).map_err(map_err)
+}
+
+#[tauri::command]
+fn detach_pty(pty_id: &str) {
+    pty::detach_pty(pty_id);
+}
+
 #[tauri::command]
 fn pty_interrupt(state: State<'_, Arc<AppState>>, pty_id: &str) -> Result<()> {
     pty::interrupt_pty(&state, pty_id).map_err(map_err)
@@ -1310,10 +2740,125 @@ fn pty_force_kill(state: State<'_, Arc<AppState>>, pty_id: &str) -> Result<()> {
     pty::force_kill_pty(&state, pty_id).map_err(map_err)
 }
 
+#[tauri::command]
+fn set_pty_scrollback(state: State<'_, Arc<AppState>>, pty_id: &str, lines: u32) -> Result<Option<u32>> {
+    pty::set_pty_scrollback(&state, pty_id, lines).map_err(map_err)
+}
+
+#[tauri::command]
+fn get_pty_foreground(state: State<'_, Arc<AppState>>, pty_id: &str) -> Result<Option<String>> {
+    pty::get_pty_foreground(&state, pty_id).map_err(map_err)
+}
+
+#[tauri::command]
+fn resolve_terminal_path(
+    state: State<'_, Arc<AppState>>,
+    pty_id: &str,
+    text: &str,
+) -> Result<pty::ResolvedTerminalPath> {
+    pty::resolve_terminal_path(&state, pty_id, text).map_err(map_err)
+}
+
+#[tauri::command]
+fn get_pty_cwd(state: State<'_, Arc<AppState>>, pty_id: &str) -> Result<String> {
+    pty::get_pty_cwd(&state, pty_id).map_err(map_err)
+}
+
 // Config commands
 #[tauri::command]
-fn get_config(project_path: Option<String>) -> config::ConfigResult {
-    config::load_config_with_errors(project_path.as_deref())
+fn get_config(state: State<'_, Arc<AppState>>, project_path: Option<String>) -> config::ConfigResult {
+    let options = config_load_options(&state, project_path.as_deref());
+    config::load_config_with_errors(project_path.as_deref(), &options)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigLayerStatus {
+    layer: config::ConfigLayer,
+    enabled: bool,
+    path: String,
+    exists: bool,
+}
+
+/// Enable or disable a project's repo/local config layer (for working around a broken repo config)
+#[tauri::command]
+fn set_config_layer_enabled(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_id: &str,
+    layer: config::ConfigLayer,
+    enabled: bool,
+) -> Result<()> {
+    let project_path = {
+        let mut persisted = state.persisted.write();
+        let project = persisted
+            .projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+
+        project.disabled_config_layers.retain(|l| *l != layer);
+        if !enabled {
+            project.disabled_config_layers.push(layer);
+        }
+        project.path.clone()
+    };
+
+    state.save().map_err(map_err)?;
+    info!("[set_config_layer_enabled] {:?} layer enabled={} for {}", layer, enabled, project_path);
+
+    // Let the frontend reload config the same way it does for file edits
+    let _ = app.emit(
+        events::CONFIG_CHANGED,
+        events::ConfigChanged {
+            project_path: Some(project_path),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn get_config_layer_status(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<Vec<ConfigLayerStatus>> {
+    let persisted = state.persisted.read();
+    let project = persisted
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+
+    let paths = config::get_config_paths(Some(&project.path));
+    let layers = [config::ConfigLayer::Repo, config::ConfigLayer::Local];
+
+    Ok(layers
+        .iter()
+        .zip(paths.iter().skip(1))
+        .map(|(layer, path)| ConfigLayerStatus {
+            layer: *layer,
+            enabled: !project.disabled_config_layers.contains(layer),
+            path: path.display().to_string(),
+            exists: path.exists(),
+        })
+        .collect())
+}
+
+/// Each config layer's path and whether it exists, plus which file an edit should go to
+#[tauri::command]
+fn locate_configs(project_path: Option<String>) -> Vec<config::ConfigLocation> {
+    config::locate_configs(project_path.as_deref())
+}
+
+/// Deprecated keys in the project's config layers, with suggested replacements
+#[tauri::command]
+fn get_config_warnings(state: State<'_, Arc<AppState>>, project_path: Option<String>) -> Vec<config::ConfigWarning> {
+    let options = config_load_options(&state, project_path.as_deref());
+    config::config_warnings(project_path.as_deref(), &options)
+}
+
+/// Upgrade a config file to the current schema version in place, keeping its
+/// comments and backing up the original
+#[tauri::command]
+fn migrate_config(scope: config::ConfigScope, project_path: Option<String>) -> Result<config::ConfigMigrationReport> {
+    config::migrate_config(scope, project_path.as_deref()).map_err(map_err)
 }
 
 #[tauri::command]
@@ -1359,11 +2904,12 @@ pub struct ActionPromptContext {
 
 #[tauri::command]
 fn expand_action_prompt(
+    state: State<'_, Arc<AppState>>,
     action_name: &str,
     context: ActionPromptContext,
     project_path: Option<String>,
 ) -> Result<String> {
-    let cfg = config::load_config_for_project(project_path.as_deref());
+    let cfg = load_project_config(&state, project_path.as_deref());
 
     let (template, ctx) = match action_name {
         "merge_worktree_with_conflicts" => {
@@ -1398,7 +2944,7 @@ fn expand_action_prompt(
             };
             (template.clone(), ctx)
         }
-        _ => return Err(format!("Unknown action: {}", action_name)),
+        _ => return Err(CommandError::InvalidInput(format!("Unknown action: {}", action_name))),
     };
 
     template::expand_action_template(&template, ctx).map_err(map_err)
@@ -1406,43 +2952,247 @@ fn expand_action_prompt(
 
 // Git commands
 #[tauri::command]
-fn get_changed_files(worktree_path: &str) -> Result<Vec<FileChange>> {
+fn get_changed_files(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> Result<state::ChangedFiles> {
     let path = Path::new(worktree_path);
-    git::get_changed_files(path).map_err(map_err)
+    let mut files = git::get_changed_files(path).map_err(map_err)?;
+    let cfg = load_project_config(&state, project_path.as_deref());
+    git::mark_hidden(&mut files, &path_utils::PathMatcher::new(&cfg.changes.hide_patterns));
+    Ok(state::ChangedFiles::new(files))
 }
 
+/// Commits returned per page by `get_commit_log` when no limit is given
+const DEFAULT_COMMIT_LOG_PAGE: usize = 50;
+
+/// History of a worktree or the main checkout, newest first; `skip` pages further back
 #[tauri::command]
-fn get_branch_info(
-    worktree_path: &str,
-    project_path: Option<String>,
-) -> Result<state::BranchInfo> {
-    let path = Path::new(worktree_path);
-    let cfg = config::load_config_for_project(project_path.as_deref());
-    git::get_branch_info(path, &cfg.worktree.base_branch).map_err(map_err)
+fn get_commit_log(worktree_path: &str, limit: Option<usize>, skip: Option<usize>) -> Result<Vec<git::CommitInfo>> {
+    git::get_commit_log(
+        Path::new(worktree_path),
+        limit.unwrap_or(DEFAULT_COMMIT_LOG_PAGE),
+        skip.unwrap_or(0),
+    )
+    .map_err(map_err)
 }
 
+/// Commits on a worktree's branch that aren't in the configured base branch
 #[tauri::command]
-fn get_branch_changed_files(
+fn get_branch_commit_log(
+    state: State<'_, Arc<AppState>>,
     worktree_path: &str,
     project_path: Option<String>,
-) -> Result<Vec<FileChange>> {
-    let path = Path::new(worktree_path);
-    let cfg = config::load_config_for_project(project_path.as_deref());
-    git::get_branch_changed_files(path, &cfg.worktree.base_branch).map_err(map_err)
+    limit: Option<usize>,
+) -> Result<Vec<git::CommitInfo>> {
+    let cfg = load_project_config(&state, project_path.as_deref());
+    git::get_branch_commit_log(
+        Path::new(worktree_path),
+        &cfg.worktree.base_branch,
+        limit.unwrap_or(DEFAULT_COMMIT_LOG_PAGE),
+    )
+    .map_err(map_err)
 }
 
+/// Porcelain v2 status with branch tracking, rename sources and submodule
+/// state; a superset of `get_changed_files` for integrations
 #[tauri::command]
-fn get_file_diff_content(
-    worktree_path: &str,
-    file_path: &str,
-    mode: &str,
-    project_path: Option<String>,
-) -> Result<state::DiffContent> {
-    let path = Path::new(worktree_path);
-    let cfg = config::load_config_for_project(project_path.as_deref());
-    let base_branch = &cfg.worktree.base_branch;
+fn get_status_v2(worktree_path: &str) -> Result<git::StatusV2> {
+    git::status_v2(Path::new(worktree_path)).map_err(map_err)
+}
 
-    let (original, modified, original_label, modified_label) = match mode {
+/// Restore one file to its content at `sha` (a commit on the current branch)
+/// without touching anything else, then refresh the changed-files list.
+/// Recorded in the audit log, since it overwrites local edits.
+#[tauri::command]
+fn restore_file_from_commit(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    file_path: &str,
+    sha: &str,
+    mode: git::RestoreMode,
+) -> Result<git::RestoredFile> {
+    let (_, worktree_path, event) = audited_worktree(&state, worktree_id, AuditOperation::RestoreFile)?;
+    let event = event.with_options(serde_json::json!({ "path": file_path, "sha": sha, "mode": mode }));
+    let restored = audit::record_result(
+        event,
+        git::restore_file_from_commit(Path::new(&worktree_path), file_path, sha, mode),
+    )
+    .map_err(map_err)?;
+    if let Err(e) = watcher::emit_changed_files(&app, &worktree_path) {
+        info!("[restore_file_from_commit] Failed to refresh changed files: {}", e);
+    }
+    Ok(restored)
+}
+
+/// Force a recompute of a worktree's changed files (manual refresh), bypassing
+/// the watcher debounce, and emit a fresh `files-changed` event
+#[tauri::command]
+fn refresh_changed_files(app: AppHandle, worktree_path: &str) -> Result<Vec<FileChange>> {
+    if !Path::new(worktree_path).exists() {
+        return Err(CommandError::NotFound(format!("Worktree path does not exist: {}", worktree_path)));
+    }
+    watcher::emit_changed_files(&app, worktree_path).map_err(map_err)
+}
+
+#[tauri::command]
+fn get_branch_info(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+) -> Result<state::BranchInfo> {
+    let path = Path::new(worktree_path);
+    let cfg = load_project_config(&state, project_path.as_deref());
+    git::get_branch_info(path, &cfg.worktree.base_branch).map_err(map_err)
+}
+
+/// Everything a worktree's branch adds over the base branch, committed or not,
+/// including untracked files. The worktree is given by path or by id.
+#[tauri::command]
+fn get_branch_changed_files(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: Option<String>,
+    worktree_id: Option<String>,
+    project_path: Option<String>,
+) -> Result<state::ChangedFiles> {
+    let worktree_path = match (worktree_path, worktree_id) {
+        (Some(path), _) => path,
+        (None, Some(id)) => worktree_path_by_id(&state, &id)?,
+        (None, None) => {
+            return Err(CommandError::InvalidInput(
+                "worktreePath or worktreeId is required".to_string(),
+            ))
+        }
+    };
+    let path = Path::new(&worktree_path);
+    let cfg = load_project_config(&state, project_path.as_deref());
+    let mut files = git::get_branch_changed_files(path, &cfg.worktree.base_branch).map_err(map_err)?;
+    git::mark_hidden(&mut files, &path_utils::PathMatcher::new(&cfg.changes.hide_patterns));
+    Ok(state::ChangedFiles::new(files))
+}
+
+/// Gitignored files and directories in a worktree, e.g. to see why a file
+/// doesn't show up in changes. Sizes are only computed when asked for; that
+/// walks every ignored directory (node_modules, target), so it runs off the
+/// main thread.
+#[tauri::command]
+async fn list_ignored_files(worktree_path: String, with_sizes: Option<bool>) -> Result<Vec<git::IgnoredEntry>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::list_ignored_entries(Path::new(&worktree_path), with_sizes.unwrap_or(false))
+    })
+    .await?
+    .map_err(map_err)
+}
+
+/// Why a file is (or isn't) ignored: the gitignore file, line and pattern that decide it
+#[tauri::command]
+fn check_ignore(worktree_path: &str, file_path: &str) -> Result<git::IgnoreCheck> {
+    git::check_ignore(Path::new(worktree_path), file_path).map_err(map_err)
+}
+
+/// Upstream of the worktree's branch, e.g. "origin/feature"; None if untracked
+#[tauri::command]
+fn get_upstream(worktree_path: &str) -> Result<Option<String>> {
+    git::get_upstream(Path::new(worktree_path)).map_err(map_err)
+}
+
+/// Track `<remote>/<remote_branch>`, e.g. after a rename or for a worktree
+/// whose branch was created locally
+#[tauri::command]
+fn set_upstream(worktree_path: &str, remote: &str, remote_branch: &str) -> Result<()> {
+    git::set_upstream(Path::new(worktree_path), remote, remote_branch).map_err(map_err)
+}
+
+/// Branches for the branch picker, with upstream tracking and which ones a
+/// worktree already has checked out
+#[tauri::command]
+fn list_branches(repo_path: &str, include_remote: Option<bool>) -> Result<Vec<git::BranchEntry>> {
+    git::list_branches(Path::new(repo_path), include_remote.unwrap_or(false)).map_err(map_err)
+}
+
+/// Call counts and p50/p95 durations of commands run while `perf.enabled` is on
+#[tauri::command]
+fn get_perf_stats() -> perf::PerfStats {
+    perf::stats()
+}
+
+/// Files changed and lines inserted and deleted against the base branch, for
+/// the sidebar's per-worktree summary. Counts what `get_branch_changed_files`
+/// shows, so untracked files are included and hidden ones aren't.
+#[tauri::command]
+fn get_diff_summary(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    project_path: Option<String>,
+) -> Result<git::DiffStats> {
+    let worktree_path = worktree_path_by_id(&state, worktree_id)?;
+    let cfg = load_project_config(&state, project_path.as_deref());
+    git::get_diff_stats(
+        Path::new(&worktree_path),
+        &cfg.worktree.base_branch,
+        &path_utils::PathMatcher::new(&cfg.changes.hide_patterns),
+    )
+    .map_err(map_err)
+}
+
+/// Snapshot a worktree's tracked changes without stashing them, and remember
+/// the snapshot so `list_working_snapshots` can offer it later
+#[tauri::command]
+fn create_working_snapshot(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    label: Option<String>,
+) -> Result<state::WorkingSnapshot> {
+    let hash = git::create_working_snapshot(Path::new(worktree_path)).map_err(map_err)?;
+    let snapshot = state::WorkingSnapshot {
+        hash,
+        worktree_path: path_utils::normalize_path_string(Path::new(worktree_path)),
+        label: label.filter(|l| !l.trim().is_empty()),
+        created_at: worktree::chrono_lite_now(),
+    };
+    state.record_working_snapshot(snapshot.clone());
+    state.save().map_err(map_err)?;
+    Ok(snapshot)
+}
+
+#[tauri::command]
+fn list_working_snapshots(state: State<'_, Arc<AppState>>, worktree_path: &str) -> Vec<state::WorkingSnapshot> {
+    state.working_snapshots(worktree_path)
+}
+
+/// Forget one of a worktree's snapshots and drop the ref keeping it, unless
+/// another snapshot (e.g. of a second clean worktree at the same HEAD) uses it
+#[tauri::command]
+fn delete_working_snapshot(state: State<'_, Arc<AppState>>, worktree_path: &str, snapshot_hash: &str) -> Result<()> {
+    let unused = state
+        .persisted
+        .write()
+        .forget_working_snapshots(worktree_path, Some(snapshot_hash));
+    git::delete_working_snapshots(Path::new(worktree_path), &unused).map_err(map_err)?;
+    state.save().map_err(map_err)
+}
+
+/// Unified diff from a snapshot to the worktree as it is now
+#[tauri::command]
+fn diff_against_snapshot(worktree_path: &str, snapshot_hash: &str) -> Result<String> {
+    git::diff_against_snapshot(Path::new(worktree_path), snapshot_hash).map_err(map_err)
+}
+
+#[tauri::command]
+fn get_file_diff_content(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    file_path: &str,
+    mode: &str,
+    project_path: Option<String>,
+) -> Result<state::DiffContent> {
+    let path = Path::new(worktree_path);
+    let cfg = load_project_config(&state, project_path.as_deref());
+    let base_branch = &cfg.worktree.base_branch;
+
+    let (original, modified, original_label, modified_label) = match mode {
         "uncommitted" => {
             // Original = HEAD, Modified = working tree
             let original = git::get_file_at_ref(path, file_path, "HEAD")
@@ -1480,6 +3230,38 @@ fn get_file_diff_content(
     })
 }
 
+/// Both sides of a file for the diff viewer: its content at `base_ref` and in
+/// the working tree
+#[tauri::command]
+fn get_file_diff_sides(worktree_path: &str, file_path: &str, base_ref: &str) -> Result<git::FileSides> {
+    git::get_file_sides(Path::new(worktree_path), file_path, base_ref).map_err(map_err)
+}
+
+/// Unified diff of one file in a worktree, for the changed-files panel
+#[tauri::command]
+fn get_file_diff(
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    file_path: &str,
+    mode: git::FileDiffMode,
+) -> Result<git::FileDiff> {
+    let (worktree_path, project_path) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                p.worktrees
+                    .iter()
+                    .find(|w| w.id == worktree_id)
+                    .map(|w| (w.path.clone(), p.path.clone()))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
+    };
+    let cfg = load_project_config(&state, Some(&project_path));
+    git::get_file_diff(Path::new(&worktree_path), file_path, mode, &cfg.worktree.base_branch).map_err(map_err)
+}
+
 #[tauri::command]
 fn has_uncommitted_changes(project_path: &str) -> Result<bool> {
     let path = Path::new(project_path);
@@ -1487,15 +3269,165 @@ fn has_uncommitted_changes(project_path: &str) -> Result<bool> {
 }
 
 #[tauri::command]
-fn stash_changes(project_path: &str) -> Result<String> {
+fn needs_stash(worktree_path: &str) -> Result<bool> {
+    git::needs_stash(Path::new(worktree_path)).map_err(map_err)
+}
+
+/// A shellflow auto-stash that was never popped
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingStashInfo {
+    pub stash_id: String,
+    pub stash_ref: String,
+    /// Files captured when the stash was made (empty if it predates tracking)
+    pub files: Vec<String>,
+    pub created_at: Option<String>,
+}
+
+/// Stash uncommitted changes on behalf of a workflow. Emits `merge-progress`
+/// (phase `auto-stash`) with the files about to be stashed and records the
+/// stash so it can be recovered if the pop never happens.
+fn auto_stash(app: &AppHandle, state: &AppState, repo_path: &Path) -> std::result::Result<String, git::GitError> {
+    let files: Vec<String> = git::get_changed_files(repo_path)?.into_iter().map(|f| f.path).collect();
+    let _ = app.emit(
+        events::MERGE_PROGRESS,
+        AutoStashProgress {
+            phase: "auto-stash".to_string(),
+            message: format!("Stashing {} uncommitted file(s)...", files.len()),
+            file_count: files.len(),
+            files: files.clone(),
+        },
+    );
+
+    let stash_id = git::stash_changes(repo_path)?;
+    state.record_pending_stash(state::PendingStash {
+        stash_id: stash_id.clone(),
+        repo_path: path_utils::normalize_path_string(repo_path),
+        files,
+        created_at: worktree::chrono_lite_now(),
+    });
+    if let Err(e) = state.save() {
+        info!("[auto_stash] Failed to save state: {}", e);
+    }
+    Ok(stash_id)
+}
+
+#[tauri::command]
+fn stash_changes(app: AppHandle, state: State<'_, Arc<AppState>>, project_path: &str) -> Result<String> {
     let path = Path::new(project_path);
-    git::stash_changes(path).map_err(map_err)
+    auto_stash(&app, &state, path).map_err(map_err)
 }
 
 #[tauri::command]
-fn stash_pop(project_path: &str, stash_id: &str) -> Result<()> {
+fn stash_pop(state: State<'_, Arc<AppState>>, project_path: &str, stash_id: &str) -> Result<()> {
     let path = Path::new(project_path);
-    git::stash_pop(path, stash_id).map_err(map_err)
+    // A failed pop leaves the stash (and its record) in place for get_pending_stashes
+    git::stash_pop(path, stash_id).map_err(map_err)?;
+    if state.remove_pending_stash(stash_id).is_some() {
+        state.save().map_err(map_err)?;
+    }
+    Ok(())
+}
+
+/// List shellflow auto-stashes in a project that were never popped
+#[tauri::command]
+fn get_pending_stashes(state: State<'_, Arc<AppState>>, project_id: &str) -> Result<Vec<PendingStashInfo>> {
+    let project_path = state
+        .persisted
+        .read()
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.path.clone())
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
+
+    let stashes = git::list_auto_stashes(Path::new(&project_path)).map_err(map_err)?;
+    let live_ids: Vec<String> = stashes.iter().map(|s| s.stash_id.clone()).collect();
+    let (records, pruned) = state.reconcile_pending_stashes(&project_path, &live_ids);
+    if pruned {
+        state.save().map_err(map_err)?;
+    }
+
+    Ok(stashes
+        .into_iter()
+        .map(|stash| {
+            let record = records.iter().find(|r| r.stash_id == stash.stash_id);
+            PendingStashInfo {
+                files: record.map(|r| r.files.clone()).unwrap_or_default(),
+                created_at: record.map(|r| r.created_at.clone()),
+                stash_id: stash.stash_id,
+                stash_ref: stash.stash_ref,
+            }
+        })
+        .collect())
+}
+
+/// Find the repository holding a shellflow auto-stash
+fn pending_stash_repo(state: &AppState, stash_id: &str) -> Result<String> {
+    if let Some(record) = state.pending_stash(stash_id) {
+        return Ok(record.repo_path);
+    }
+
+    let project_paths: Vec<String> = state.persisted.read().projects.iter().map(|p| p.path.clone()).collect();
+    project_paths
+        .into_iter()
+        .find(|path| {
+            git::list_auto_stashes(Path::new(path))
+                .map(|stashes| stashes.iter().any(|s| s.stash_id == stash_id))
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| CommandError::NotFound(format!("Stash not found: {}", stash_id)))
+}
+
+#[tauri::command]
+fn pop_pending_stash(state: State<'_, Arc<AppState>>, stash_id: &str) -> Result<()> {
+    let repo_path = pending_stash_repo(&state, stash_id)?;
+    if git::find_stash_ref(Path::new(&repo_path), stash_id).map_err(map_err)?.is_none() {
+        return Err(CommandError::NotFound(format!("Stash not found: {}", stash_id)));
+    }
+    git::stash_pop(Path::new(&repo_path), stash_id).map_err(map_err)?;
+    state.remove_pending_stash(stash_id);
+    state.save().map_err(map_err)
+}
+
+#[tauri::command]
+fn drop_pending_stash(state: State<'_, Arc<AppState>>, stash_id: &str) -> Result<()> {
+    let repo_path = pending_stash_repo(&state, stash_id)?;
+    git::drop_stash(Path::new(&repo_path), stash_id).map_err(map_err)?;
+    state.remove_pending_stash(stash_id);
+    state.save().map_err(map_err)
+}
+
+/// Conflicted files with per-file hunk counts and conflict kind
+#[tauri::command]
+fn get_conflict_summary(worktree_path: &str) -> Result<Vec<git::ConflictSummary>> {
+    git::get_conflict_summary(Path::new(worktree_path)).map_err(map_err)
+}
+
+/// Stage a manually resolved file; returns true once no conflicts remain
+#[tauri::command]
+fn mark_resolved(worktree_path: &str, file_path: &str) -> Result<bool> {
+    git::mark_resolved(Path::new(worktree_path), file_path).map_err(map_err)
+}
+
+#[tauri::command]
+fn continue_rebase(worktree_path: &str) -> Result<git::ContinueResult> {
+    git::continue_rebase(Path::new(worktree_path)).map_err(map_err)
+}
+
+#[tauri::command]
+fn continue_merge(worktree_path: &str) -> Result<git::ContinueResult> {
+    git::continue_merge(Path::new(worktree_path)).map_err(map_err)
+}
+
+#[tauri::command]
+fn continue_cherry_pick(worktree_path: &str) -> Result<git::ContinueResult> {
+    git::continue_cherry_pick(Path::new(worktree_path)).map_err(map_err)
+}
+
+#[tauri::command]
+fn skip_rebase_commit(worktree_path: &str) -> Result<git::ContinueResult> {
+    git::skip_rebase_commit(Path::new(worktree_path)).map_err(map_err)
 }
 
 #[tauri::command]
@@ -1528,6 +3460,14 @@ pub struct MergeWorkflowOptions {
     pub delete_worktree: bool,
     pub delete_local_branch: bool,
     pub delete_remote_branch: bool,
+    #[serde(default)]
+    pub push_after_merge: bool,
+    /// Branch to land in instead of the default branch, e.g. a release branch
+    #[serde(default)]
+    pub target_branch: Option<String>,
+    /// Only fast-forward the target; fails with NOT_FAST_FORWARD when it has diverged
+    #[serde(default)]
+    pub fast_forward: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1538,28 +3478,93 @@ pub struct MergeWorkflowResult {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MergeProgress {
-    pub phase: String,
-    pub message: String,
+/// Fetch the branch a rebase lands on from origin, emitting `merge-progress`
+/// phase `fetch`. Without an origin remote the progress message says the local
+/// branch will be used and the rebase goes ahead.
+fn fetch_merge_target(
+    app: &AppHandle,
+    repo_path: &Path,
+    target_branch: Option<&str>,
+    retry: &NetworkRetry,
+) -> std::result::Result<(), git::GitError> {
+    let target = git::merge_target_branch(&git2::Repository::open(repo_path)?, target_branch)?;
+    let emit = |message: String| {
+        let _ = app.emit(events::MERGE_PROGRESS, MergeProgress { phase: "fetch".to_string(), message });
+    };
+    emit(format!("Fetching origin/{}...", target));
+    if !git::fetch_base_branch(repo_path, &target, retry)? {
+        emit(format!("No origin remote; rebasing onto local {}", target));
+    }
+    Ok(())
+}
+
+/// Push the branch a merge landed in when `enabled`. The local merge has already
+/// happened, so a failed push is reported (returned and as a "push-failed"
+/// progress phase) instead of failing the workflow.
+fn push_after_merge(
+    project_path: &Path,
+    enabled: bool,
+    retry: &NetworkRetry,
+    mut progress: impl FnMut(MergeProgress),
+) -> Option<String> {
+    if !enabled {
+        return None;
+    }
+
+    progress(MergeProgress {
+        phase: "push".to_string(),
+        message: "Pushing merged branch...".to_string(),
+    });
+    match git::push_checked_out_branch(project_path, retry) {
+        Ok(()) => None,
+        Err(e) => {
+            let message = e.to_string();
+            info!("[push_after_merge] Push failed after merge: {}", message);
+            progress(MergeProgress {
+                phase: "push-failed".to_string(),
+                message: message.clone(),
+            });
+            Some(message)
+        }
+    }
 }
 
 #[tauri::command]
-fn check_merge_feasibility(worktree_path: &str, project_path: Option<String>) -> Result<MergeFeasibility> {
-    let path = Path::new(worktree_path);
-    let cfg = config::load_config_for_project(project_path.as_deref());
-    git::check_merge_feasibility(path, &cfg.worktree.base_branch).map_err(map_err)
+fn check_merge_feasibility(
+    state: State<'_, Arc<AppState>>,
+    worktree_path: &str,
+    project_path: Option<String>,
+    target_branch: Option<String>,
+) -> Result<MergeFeasibility> {
+    merge_feasibility(&state, worktree_path, project_path.as_deref(), target_branch.as_deref())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MergeCompleted {
-    pub worktree_id: String,
-    pub success: bool,
-    pub branch_name: String,
-    pub deleted_worktree: bool,
-    pub error: Option<String>,
+/// Whether the worktree at `worktree_path` can be merged, with the project's config
+fn merge_feasibility(
+    state: &AppState,
+    worktree_path: &str,
+    project_path: Option<&str>,
+    target_branch: Option<&str>,
+) -> Result<MergeFeasibility> {
+    let path = Path::new(worktree_path);
+    let cfg = load_project_config(state, project_path);
+    let key = path_utils::canonicalize_for_storage(path);
+    let worktree_id = state.persisted.read().projects.iter().find_map(|p| {
+        p.worktrees
+            .iter()
+            .find(|w| path_utils::canonicalize_for_storage(Path::new(&w.path)) == key)
+            .map(|w| w.id.clone())
+    });
+    let workflow = worktree_id.as_deref().and_then(status::workflow_in_progress);
+    let rebase_onto_remote = cfg.worktree.merge.strategy == MergeStrategy::Rebase && cfg.worktree.merge.rebase_onto_remote;
+    git::check_merge_feasibility(
+        path,
+        &cfg.worktree.base_branch,
+        target_branch,
+        rebase_onto_remote,
+        workflow.map(WorkflowKind::as_str),
+    )
+    .map_err(map_err)
 }
 
 // Delete worktree workflow types
@@ -1569,27 +3574,13 @@ pub struct DeleteWorktreeOptions {
     pub delete_branch: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DeleteWorktreeProgress {
-    pub phase: String,
-    pub message: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DeleteWorktreeCompleted {
-    pub worktree_id: String,
-    pub success: bool,
-    pub error: Option<String>,
-}
-
 #[tauri::command]
 fn execute_merge_workflow(
     app: AppHandle,
     state: State<'_, Arc<AppState>>,
     worktree_id: &str,
     options: MergeWorkflowOptions,
+    confirmed: bool,
 ) {
     // Find worktree and project - extract all data we need before spawning thread
     let (worktree_path, project_path) = {
@@ -1607,13 +3598,15 @@ fn execute_merge_workflow(
             Some(data) => data,
             None => {
                 let _ = app.emit(
-                    "merge-completed",
+                    events::MERGE_COMPLETED,
                     MergeCompleted {
                         worktree_id: worktree_id.to_string(),
                         success: false,
                         branch_name: String::new(),
                         deleted_worktree: false,
                         error: Some(format!("Worktree not found: {}", worktree_id)),
+                        push_failed: false,
+                        push_error: None,
                     },
                 );
                 return;
@@ -1621,136 +3614,369 @@ fn execute_merge_workflow(
         }
     };
 
+    if let Err(e) = ensure_cleanup_confirmed(
+        &state,
+        &project_path,
+        options.delete_worktree,
+        options.delete_remote_branch,
+        confirmed,
+    ) {
+        let _ = app.emit(
+            events::MERGE_COMPLETED,
+            MergeCompleted {
+                worktree_id: worktree_id.to_string(),
+                success: false,
+                branch_name: String::new(),
+                deleted_worktree: false,
+                error: Some(e.to_string()),
+                push_failed: false,
+                push_error: None,
+            },
+        );
+        return;
+    }
+
     // Clone data for the background thread
     let worktree_id = worktree_id.to_string();
     let app_state = Arc::clone(&*state);
-    let delete_worktree = options.delete_worktree;
 
     // Spawn background thread to avoid blocking UI
     std::thread::spawn(move || {
-        let worktree_path = Path::new(&worktree_path);
-        let project_path = Path::new(&project_path);
+        let completed = run_merge_workflow(
+            &app,
+            &app_state,
+            &worktree_id,
+            &worktree_path,
+            &project_path,
+            &options,
+        );
+        let _ = app.emit(events::MERGE_COMPLETED, completed);
+    });
 
-        // Emit progress: starting merge
-        let phase = if options.strategy == MergeStrategy::Rebase {
-            "rebase"
-        } else {
-            "merge"
+    info!("[execute_merge_workflow] spawned background thread");
+}
+
+/// Merge (or rebase) a worktree into its target, push and clean up as the
+/// options say, emitting `merge-progress` along the way. Runs on the caller's
+/// thread; the caller reports the returned result.
+fn run_merge_workflow(
+    app: &AppHandle,
+    app_state: &Arc<AppState>,
+    worktree_id: &str,
+    worktree_path: &str,
+    project_dir: &str,
+    options: &MergeWorkflowOptions,
+) -> MergeCompleted {
+    let workflow = status::WorkflowGuard::start(WorkflowKind::Merge, worktree_id, project_dir);
+    let policy = network_retry_policy(app_state, Some(project_dir));
+    let worktree_path = Path::new(worktree_path);
+    let project_path = Path::new(project_dir);
+
+    // Retries keep the step's phase; the message says when the next attempt runs
+    let on_retry = |notice: &RetryNotice| {
+        emit_retry_notice(app, notice);
+        let phase = match notice.operation.as_str() {
+            "push" | "fetch" => notice.operation.as_str(),
+            _ => "delete-remote-branch",
         };
         let _ = app.emit(
-            "merge-progress",
+            events::MERGE_PROGRESS,
             MergeProgress {
                 phase: phase.to_string(),
-                message: format!("{}...", if phase == "rebase" { "Rebasing" } else { "Merging" }),
+                message: notice.summary(),
             },
         );
+    };
+    let retry = NetworkRetry::new(policy, &on_retry);
+
+    // Bring the target up to date from origin before rebasing onto it
+    let rebase_onto_remote = options.strategy == MergeStrategy::Rebase
+        && load_project_config(app_state, Some(project_dir)).worktree.merge.rebase_onto_remote;
+    let fetched = if rebase_onto_remote {
+        workflow.set_phase("fetch");
+        fetch_merge_target(app, project_path, options.target_branch.as_deref(), &retry)
+    } else {
+        Ok(())
+    };
 
-        // Execute the merge/rebase
-        let branch_name = match git::execute_merge_workflow(worktree_path, project_path, options.strategy) {
-            Ok(name) => name,
-            Err(e) => {
-                let _ = app.emit(
-                    "merge-progress",
-                    MergeProgress {
-                        phase: "error".to_string(),
-                        message: e.to_string(),
-                    },
-                );
-                let _ = app.emit(
-                    "merge-completed",
-                    MergeCompleted {
-                        worktree_id,
-                        success: false,
-                        branch_name: String::new(),
-                        deleted_worktree: false,
-                        error: Some(e.to_string()),
-                    },
-                );
-                return;
-            }
-        };
-
-        // Delete worktree if requested
-        if options.delete_worktree {
+    // Emit progress: starting merge
+    let (phase, message) = match options.strategy {
+        MergeStrategy::Merge => ("merge", "Merging..."),
+        MergeStrategy::Rebase => ("rebase", "Rebasing..."),
+        MergeStrategy::Squash => ("squash", "Squashing..."),
+    };
+    workflow.set_phase(phase);
+    let _ = app.emit(
+        events::MERGE_PROGRESS,
+        MergeProgress {
+            phase: phase.to_string(),
+            message: message.to_string(),
+        },
+    );
+
+    // Execute the merge/rebase
+    let target_branch = options.target_branch.as_deref();
+    let squash_message = |branch: &str| {
+        let cfg = load_project_config(app_state, Some(project_dir));
+        quick_commit::squash_commit_message(project_path, branch, &cfg.commit.ai)
+    };
+    let merged = fetched.and_then(|()| {
+        git::execute_merge_workflow(
+            worktree_path,
+            project_path,
+            options.strategy,
+            target_branch,
+            rebase_onto_remote,
+            options.fast_forward,
+            squash_message,
+        )
+    });
+    let branch_name = match merged {
+        Ok(name) => name,
+        Err(e) => {
+            status::record_error("merge", e.to_string());
+            workflow.set_phase("error");
             let _ = app.emit(
-                "merge-progress",
+                events::MERGE_PROGRESS,
                 MergeProgress {
-                    phase: "delete-worktree".to_string(),
-                    message: "Removing worktree...".to_string(),
+                    phase: "error".to_string(),
+                    message: e.to_string(),
                 },
             );
+            return MergeCompleted {
+                worktree_id: worktree_id.to_string(),
+                success: false,
+                branch_name: String::new(),
+                deleted_worktree: false,
+                error: Some(e.to_string()),
+                push_failed: false,
+                push_error: None,
+            };
+        }
+    };
 
-            // Stop watching first
-            watcher::stop_watching(&worktree_id);
+    // Push the target branch before any cleanup; a failure only warns
+    let push_error = push_after_merge(project_path, options.push_after_merge, &retry, |progress| {
+        workflow.set_phase(&progress.phase);
+        let _ = app.emit(events::MERGE_PROGRESS, progress);
+    });
+    if let Some(e) = &push_error {
+        status::record_error("push", e.clone());
+    }
 
-            // Delete the worktree
-            let mut persisted = app_state.persisted.write();
-            for project in &mut persisted.projects {
-                if project.worktrees.iter().any(|w| w.id == worktree_id) {
-                    if let Err(e) = worktree::delete_worktree(project, &worktree_id) {
-                        info!("Failed to delete worktree: {}", e);
-                    }
-                    break;
-                }
-            }
-            drop(persisted);
-            if let Err(e) = app_state.save() {
-                info!("Failed to save state: {}", e);
-            }
+    // Delete worktree if requested
+    if options.delete_worktree {
+        workflow.set_phase("delete-worktree");
+        let _ = app.emit(
+            events::MERGE_PROGRESS,
+            MergeProgress {
+                phase: "delete-worktree".to_string(),
+                message: "Removing worktree...".to_string(),
+            },
+        );
+
+        // Stop watching first
+        watcher::stop_watching(worktree_id);
+
+        // Delete the worktree
+        let audit_options = serde_json::to_value(options).unwrap_or_default();
+        match delete_worktree_audited(&mut app_state.persisted.write(), worktree_id, audit_options) {
+            Ok(()) | Err(worktree::WorktreeError::WorktreeNotFound(_)) => {}
+            Err(e) => info!("Failed to delete worktree: {}", e),
+        }
+        if let Err(e) = app_state.save() {
+            info!("Failed to save state: {}", e);
         }
+    }
 
-        // Delete local branch if requested
-        if options.delete_local_branch {
-            let _ = app.emit(
-                "merge-progress",
-                MergeProgress {
-                    phase: "delete-local-branch".to_string(),
-                    message: "Deleting local branch...".to_string(),
-                },
-            );
+    // Delete local branch if requested
+    if options.delete_local_branch {
+        workflow.set_phase("delete-local-branch");
+        let _ = app.emit(
+            events::MERGE_PROGRESS,
+            MergeProgress {
+                phase: "delete-local-branch".to_string(),
+                message: "Deleting local branch...".to_string(),
+            },
+        );
 
-            if let Err(e) = git::delete_local_branch(project_path, &branch_name) {
-                info!("Failed to delete local branch: {}", e);
-            }
+        let result = audit::record_result(
+            AuditEvent::new(AuditOperation::DeleteLocalBranch, project_path)
+                .with_branch(&branch_name)
+                .with_options(serde_json::to_value(options).unwrap_or_default()),
+            git::delete_local_branch(project_path, &branch_name),
+        );
+        if let Err(e) = result {
+            info!("Failed to delete local branch: {}", e);
+        }
+    }
+
+    // Delete remote branch if requested
+    if options.delete_remote_branch {
+        workflow.set_phase("delete-remote-branch");
+        let _ = app.emit(
+            events::MERGE_PROGRESS,
+            MergeProgress {
+                phase: "delete-remote-branch".to_string(),
+                message: "Deleting remote branch...".to_string(),
+            },
+        );
+
+        let result = audit::record_result(
+            AuditEvent::new(AuditOperation::DeleteRemoteBranch, project_path)
+                .with_branch(&branch_name)
+                .with_options(serde_json::to_value(options).unwrap_or_default()),
+            git::delete_remote_branch(project_path, &branch_name, &retry),
+        );
+        if let Err(e) = result {
+            info!("Failed to delete remote branch: {}", e);
+        }
+    }
+
+    // Emit completion
+    workflow.set_phase("complete");
+    let _ = app.emit(
+        events::MERGE_PROGRESS,
+        MergeProgress {
+            phase: "complete".to_string(),
+            message: "Done".to_string(),
+        },
+    );
+
+    MergeCompleted {
+        worktree_id: worktree_id.to_string(),
+        success: true,
+        branch_name,
+        deleted_worktree: options.delete_worktree,
+        error: None,
+        push_failed: push_error.is_some(),
+        push_error,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeTargetStatus {
+    Merged,
+    Failed,
+    /// Not attempted because an earlier target failed
+    Skipped,
+}
+
+/// Outcome of one target of `merge_into_multiple`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTargetResult {
+    pub target_branch: String,
+    pub status: MergeTargetStatus,
+    pub error: Option<String>,
+    pub push_error: Option<String>,
+}
+
+/// Options for one target of a multi-target merge: cleanup only happens with
+/// the last target, so the branch survives until every merge has landed
+fn options_for_target(options: &MergeWorkflowOptions, target: &str, is_last: bool) -> MergeWorkflowOptions {
+    MergeWorkflowOptions {
+        target_branch: Some(target.to_string()),
+        delete_worktree: options.delete_worktree && is_last,
+        delete_local_branch: options.delete_local_branch && is_last,
+        delete_remote_branch: options.delete_remote_branch && is_last,
+        ..options.clone()
+    }
+}
+
+/// Run `run` for each target in order, stopping at the first failure; later
+/// targets are reported as skipped. Returns the per-target results and the
+/// result of the last workflow that ran.
+fn merge_targets_sequentially(
+    targets: &[String],
+    options: &MergeWorkflowOptions,
+    mut run: impl FnMut(&MergeWorkflowOptions) -> MergeCompleted,
+) -> (Vec<MergeTargetResult>, Option<MergeCompleted>) {
+    let mut results = Vec::new();
+    let mut last: Option<MergeCompleted> = None;
+
+    for (i, target) in targets.iter().enumerate() {
+        if last.as_ref().is_some_and(|completed| !completed.success) {
+            results.push(MergeTargetResult {
+                target_branch: target.clone(),
+                status: MergeTargetStatus::Skipped,
+                error: None,
+                push_error: None,
+            });
+            continue;
         }
+        let completed = run(&options_for_target(options, target, i + 1 == targets.len()));
+        results.push(MergeTargetResult {
+            target_branch: target.clone(),
+            status: if completed.success { MergeTargetStatus::Merged } else { MergeTargetStatus::Failed },
+            error: completed.error.clone(),
+            push_error: completed.push_error.clone(),
+        });
+        last = Some(completed);
+    }
+    (results, last)
+}
+
+/// Merge a worktree into several branches in turn (e.g. `main` and a release
+/// branch), stopping at the first conflict. Cleanup options apply only after
+/// the last target succeeds. Emits `merge-progress` per step, `merge-completed`
+/// for the last workflow run and `merge-multiple-completed` with every target.
+#[tauri::command]
+fn merge_into_multiple(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    worktree_id: &str,
+    targets: Vec<String>,
+    options: MergeWorkflowOptions,
+    confirmed: bool,
+) -> Result<()> {
+    if targets.is_empty() {
+        return Err(CommandError::InvalidInput("No target branches given".to_string()));
+    }
+    // Rebasing onto the second target would drag the first target's history along
+    if targets.len() > 1 && options.strategy == MergeStrategy::Rebase {
+        return Err(CommandError::InvalidInput(
+            "The rebase strategy can't land in several branches; use merge".to_string(),
+        ));
+    }
 
-        // Delete remote branch if requested
-        if options.delete_remote_branch {
-            let _ = app.emit(
-                "merge-progress",
-                MergeProgress {
-                    phase: "delete-remote-branch".to_string(),
-                    message: "Deleting remote branch...".to_string(),
-                },
-            );
+    let (worktree_path, project_path) = {
+        let persisted = state.persisted.read();
+        persisted
+            .projects
+            .iter()
+            .find_map(|p| {
+                p.worktrees
+                    .iter()
+                    .find(|w| w.id == worktree_id)
+                    .map(|w| (w.path.clone(), p.path.clone()))
+            })
+            .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?
+    };
 
-            if let Err(e) = git::delete_remote_branch(project_path, &branch_name) {
-                info!("Failed to delete remote branch: {}", e);
-            }
-        }
+    // Every target must exist before anything is merged
+    let repo = git2::Repository::open(&project_path).map_err(map_err)?;
+    for target in &targets {
+        git::merge_target_branch(&repo, Some(target)).map_err(map_err)?;
+    }
+    drop(repo);
 
-        // Emit completion
-        let _ = app.emit(
-            "merge-progress",
-            MergeProgress {
-                phase: "complete".to_string(),
-                message: "Done".to_string(),
-            },
-        );
+    ensure_cleanup_confirmed(&state, &project_path, options.delete_worktree, options.delete_remote_branch, confirmed)?;
 
-        let _ = app.emit(
-            "merge-completed",
-            MergeCompleted {
-                worktree_id,
-                success: true,
-                branch_name,
-                deleted_worktree: delete_worktree,
-                error: None,
-            },
-        );
+    let worktree_id = worktree_id.to_string();
+    let app_state = Arc::clone(&*state);
+    std::thread::spawn(move || {
+        let (results, last) = merge_targets_sequentially(&targets, &options, |target_options| {
+            run_merge_workflow(&app, &app_state, &worktree_id, &worktree_path, &project_path, target_options)
+        });
+        if let Some(completed) = last {
+            let _ = app.emit(events::MERGE_COMPLETED, completed);
+        }
+        let _ = app.emit(events::MERGE_MULTIPLE_COMPLETED, MergeMultipleCompleted { worktree_id, results });
     });
 
-    info!("[execute_merge_workflow] spawned background thread");
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1767,6 +3993,7 @@ fn cleanup_worktree(
     state: State<'_, Arc<AppState>>,
     worktree_id: &str,
     options: CleanupOptions,
+    confirmed: bool,
 ) {
     // Find worktree and project - extract all data we need before spawning thread
     let (project_path, branch_name) = {
@@ -1784,13 +4011,15 @@ fn cleanup_worktree(
             Some(data) => data,
             None => {
                 let _ = app.emit(
-                    "merge-completed",
+                    events::MERGE_COMPLETED,
                     MergeCompleted {
                         worktree_id: worktree_id.to_string(),
                         success: false,
                         branch_name: String::new(),
                         deleted_worktree: false,
                         error: Some(format!("Worktree not found: {}", worktree_id)),
+                        push_failed: false,
+                        push_error: None,
                     },
                 );
                 return;
@@ -1798,6 +4027,28 @@ fn cleanup_worktree(
         }
     };
 
+    if let Err(e) = ensure_cleanup_confirmed(
+        &state,
+        &project_path,
+        options.delete_worktree,
+        options.delete_remote_branch,
+        confirmed,
+    ) {
+        let _ = app.emit(
+            events::MERGE_COMPLETED,
+            MergeCompleted {
+                worktree_id: worktree_id.to_string(),
+                success: false,
+                branch_name: String::new(),
+                deleted_worktree: false,
+                error: Some(e.to_string()),
+                push_failed: false,
+                push_error: None,
+            },
+        );
+        return;
+    }
+
     // Clone data for the background thread
     let worktree_id = worktree_id.to_string();
     let app_state = Arc::clone(&*state);
@@ -1805,12 +4056,25 @@ fn cleanup_worktree(
 
     // Spawn background thread to avoid blocking UI
     std::thread::spawn(move || {
+        let workflow = status::WorkflowGuard::start(WorkflowKind::Cleanup, &worktree_id, &project_path);
+        let on_retry = |notice: &RetryNotice| {
+            emit_retry_notice(&app, notice);
+            let _ = app.emit(
+                events::MERGE_PROGRESS,
+                MergeProgress {
+                    phase: "delete-remote-branch".to_string(),
+                    message: notice.summary(),
+                },
+            );
+        };
+        let retry = NetworkRetry::new(network_retry_policy(&app_state, Some(&project_path)), &on_retry);
         let project_path = Path::new(&project_path);
 
         // Delete worktree if requested
         if options.delete_worktree {
+            workflow.set_phase("delete-worktree");
             let _ = app.emit(
-                "merge-progress",
+                events::MERGE_PROGRESS,
                 MergeProgress {
                     phase: "delete-worktree".to_string(),
                     message: "Removing worktree...".to_string(),
@@ -1821,16 +4085,11 @@ fn cleanup_worktree(
             watcher::stop_watching(&worktree_id);
 
             // Delete the worktree
-            let mut persisted = app_state.persisted.write();
-            for project in &mut persisted.projects {
-                if project.worktrees.iter().any(|w| w.id == worktree_id) {
-                    if let Err(e) = worktree::delete_worktree(project, &worktree_id) {
-                        info!("Failed to delete worktree: {}", e);
-                    }
-                    break;
-                }
+            let audit_options = serde_json::to_value(&options).unwrap_or_default();
+            match delete_worktree_audited(&mut app_state.persisted.write(), &worktree_id, audit_options) {
+                Ok(()) | Err(worktree::WorktreeError::WorktreeNotFound(_)) => {}
+                Err(e) => info!("Failed to delete worktree: {}", e),
             }
-            drop(persisted);
             if let Err(e) = app_state.save() {
                 info!("Failed to save state: {}", e);
             }
@@ -1838,37 +4097,52 @@ fn cleanup_worktree(
 
         // Delete local branch if requested
         if options.delete_local_branch {
+            workflow.set_phase("delete-local-branch");
             let _ = app.emit(
-                "merge-progress",
+                events::MERGE_PROGRESS,
                 MergeProgress {
                     phase: "delete-local-branch".to_string(),
                     message: "Deleting local branch...".to_string(),
                 },
             );
 
-            if let Err(e) = git::delete_local_branch(project_path, &branch_name) {
+            let result = audit::record_result(
+                AuditEvent::new(AuditOperation::DeleteLocalBranch, project_path)
+                    .with_branch(&branch_name)
+                    .with_options(serde_json::to_value(&options).unwrap_or_default()),
+                git::delete_local_branch(project_path, &branch_name),
+            );
+            if let Err(e) = result {
                 info!("Failed to delete local branch: {}", e);
             }
         }
 
         // Delete remote branch if requested
         if options.delete_remote_branch {
+            workflow.set_phase("delete-remote-branch");
             let _ = app.emit(
-                "merge-progress",
+                events::MERGE_PROGRESS,
                 MergeProgress {
                     phase: "delete-remote-branch".to_string(),
                     message: "Deleting remote branch...".to_string(),
                 },
             );
 
-            if let Err(e) = git::delete_remote_branch(project_path, &branch_name) {
+            let result = audit::record_result(
+                AuditEvent::new(AuditOperation::DeleteRemoteBranch, project_path)
+                    .with_branch(&branch_name)
+                    .with_options(serde_json::to_value(&options).unwrap_or_default()),
+                git::delete_remote_branch(project_path, &branch_name, &retry),
+            );
+            if let Err(e) = result {
                 info!("Failed to delete remote branch: {}", e);
             }
         }
 
         // Emit completion
+        workflow.set_phase("complete");
         let _ = app.emit(
-            "merge-progress",
+            events::MERGE_PROGRESS,
             MergeProgress {
                 phase: "complete".to_string(),
                 message: "Done".to_string(),
@@ -1876,13 +4150,15 @@ fn cleanup_worktree(
         );
 
         let _ = app.emit(
-            "merge-completed",
+            events::MERGE_COMPLETED,
             MergeCompleted {
                 worktree_id,
                 success: true,
                 branch_name,
                 deleted_worktree: delete_worktree,
                 error: None,
+                push_failed: false,
+                push_error: None,
             },
         );
     });
@@ -1894,7 +4170,7 @@ fn cleanup_worktree(
 #[tauri::command]
 fn get_home_dir() -> Result<String> {
     dirs::home_dir()
-        .ok_or_else(|| "Could not determine home directory".to_string())
+        .ok_or_else(|| CommandError::Other("Could not determine home directory".to_string()))
         .map(|p| p.to_string_lossy().to_string())
 }
 
@@ -1989,7 +4265,7 @@ fn parse_command_parts(command: &str) -> Result<Vec<String>> {
     }
 
     if quote.is_some() {
-        return Err(format!("Invalid command '{}': unterminated quote", command));
+        return Err(CommandError::InvalidInput(format!("Invalid command '{}': unterminated quote", command)));
     }
 
     if !current.is_empty() {
@@ -1997,7 +4273,7 @@ fn parse_command_parts(command: &str) -> Result<Vec<String>> {
     }
 
     if parts.is_empty() {
-        return Err("Command is empty".to_string());
+        return Err(CommandError::InvalidInput("Command is empty".to_string()));
     }
 
     Ok(parts)
@@ -2067,10 +4343,12 @@ fn open_with_app(path: &str, app: &str) -> Result<()> {
     let parts = build_app_command_parts(app, path)?;
     let program = parts
         .first()
-        .ok_or_else(|| format!("Invalid command '{}': empty executable", app))?;
+        .ok_or_else(|| CommandError::InvalidInput(format!("Invalid command '{}': empty executable", app)))?;
     let args: Vec<&str> = parts.iter().skip(1).map(String::as_str).collect();
+    let resolved = which::find_in_user_path(&[program])
+        .ok_or_else(|| CommandError::NotFound(format!("'{}' was not found in PATH", program)))?;
 
-    let mut command = Command::new(program);
+    let mut command = Command::new(&resolved);
     command.args(&args).env("PATH", &user_path);
     apply_command_process_options(&mut command);
     let spawn_result = command.spawn();
@@ -2153,22 +4431,195 @@ fn open_in_editor(
     open_with_app(path, &editor)
 }
 
+/// Where the command `check_apps` reports for an app comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AppCommandSource {
+    /// `apps.*` in config
+    Config,
+    /// `$VISUAL` / `$EDITOR`
+    Environment,
+    PlatformDefault,
+    /// Nothing to run (no editor configured)
+    None,
+}
+
+/// Whether one configured app can be launched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppCheck {
+    /// Command that would be run, as configured
+    pub command: Option<String>,
+    pub source: AppCommandSource,
+    pub target: config::AppTarget,
+    /// Executable the command starts with, resolved against the user's PATH
+    pub resolved_path: Option<String>,
+    pub found: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsCheck {
+    pub editor: AppCheck,
+    pub terminal: AppCheck,
+    pub file_manager: AppCheck,
+}
+
+/// Platform fallbacks used by `open_in_terminal` and `open_in_file_manager`
+#[cfg(target_os = "macos")]
+const DEFAULT_TERMINAL_COMMAND: &str = "open -a Terminal";
+#[cfg(target_os = "windows")]
+const DEFAULT_TERMINAL_COMMAND: &str = "wt";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const DEFAULT_TERMINAL_COMMAND: &str = "xdg-terminal-exec";
+
+#[cfg(target_os = "macos")]
+const DEFAULT_FILE_MANAGER_COMMAND: &str = "open";
+#[cfg(target_os = "windows")]
+const DEFAULT_FILE_MANAGER_COMMAND: &str = "explorer";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const DEFAULT_FILE_MANAGER_COMMAND: &str = "xdg-open";
+
+/// Resolve the executable a command line would start
+fn check_app_command(
+    command: Option<String>,
+    source: AppCommandSource,
+    target: config::AppTarget,
+    find: impl Fn(&str) -> Option<String>,
+) -> AppCheck {
+    let resolved_path = command
+        .as_deref()
+        .and_then(|c| build_app_command_parts(c, "").ok())
+        .and_then(|parts| parts.into_iter().next())
+        .and_then(|program| find(&program));
+    AppCheck {
+        found: resolved_path.is_some(),
+        command,
+        source,
+        target,
+        resolved_path,
+    }
+}
+
+/// Resolve one `apps.*` entry the way the matching `open_in_*` command would
+fn check_configured_app(
+    app: Option<&config::AppConfig>,
+    fallback: impl FnOnce() -> (Option<String>, AppCommandSource),
+    find: impl Fn(&str) -> Option<String>,
+) -> AppCheck {
+    let target = app.map(config::AppConfig::target).unwrap_or_default();
+    let (command, source) = match app.and_then(config::AppConfig::command) {
+        Some(command) => (Some(command.to_string()), AppCommandSource::Config),
+        None => fallback(),
+    };
+    check_app_command(command, source, target, find)
+}
+
+/// Check that the editor, terminal and file manager a project would open are
+/// installed, for settings to flag missing tools before they're used
+#[tauri::command]
+fn check_apps(state: State<'_, Arc<AppState>>, project_path: Option<String>) -> AppsCheck {
+    let config = load_project_config(&state, project_path.as_deref());
+    let apps = &config.apps;
+    let find = |program: &str| which::find_in_user_path(&[program]);
+    AppsCheck {
+        editor: check_configured_app(
+            apps.editor.as_ref(),
+            || match std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")) {
+                Ok(editor) if !editor.trim().is_empty() => (Some(editor), AppCommandSource::Environment),
+                _ => (None, AppCommandSource::None),
+            },
+            find,
+        ),
+        terminal: check_configured_app(
+            apps.terminal.as_ref(),
+            || (Some(DEFAULT_TERMINAL_COMMAND.to_string()), AppCommandSource::PlatformDefault),
+            find,
+        ),
+        file_manager: check_configured_app(
+            apps.file_manager.as_ref(),
+            || (Some(DEFAULT_FILE_MANAGER_COMMAND.to_string()), AppCommandSource::PlatformDefault),
+            find,
+        ),
+    }
+}
+
+/// Result of `check_action_command`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionCommandCheck {
+    /// `actions.command` as configured
+    pub command: String,
+    /// Executable the command runs, resolved against the user's PATH
+    pub resolved_path: Option<String>,
+    pub found: bool,
+    /// What to do about a missing command; None when found
+    pub message: Option<String>,
+}
+
+/// Resolve the program `actions.command` runs. The command goes through the
+/// user's shell, so leading `VAR=value` assignments are skipped.
+fn check_action_command_with(command: &str, find: impl Fn(&str) -> Option<String>) -> ActionCommandCheck {
+    let program = parse_command_parts(command)
+        .ok()
+        .and_then(|parts| parts.into_iter().find(|part| !is_env_assignment(part)));
+    let resolved_path = program.as_deref().and_then(&find);
+    let message = match (&program, &resolved_path) {
+        (_, Some(_)) => None,
+        (None, None) => Some("actions.command is empty. Set it to the AI CLI to run.".to_string()),
+        (Some(program), None) => Some(format!(
+            "'{}' was not found in PATH. Install {} or configure actions.command.",
+            program, program
+        )),
+    };
+    ActionCommandCheck {
+        command: command.to_string(),
+        found: resolved_path.is_some(),
+        resolved_path,
+        message,
+    }
+}
+
+fn is_env_assignment(part: &str) -> bool {
+    part.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c == '_' || c.is_ascii_alphanumeric())
+    })
+}
+
+/// Check that the AI CLI `spawn_action` runs is installed, so conflict
+/// resolution can be offered only when it will start
+#[tauri::command]
+fn check_action_command(state: State<'_, Arc<AppState>>, project_path: Option<String>) -> ActionCommandCheck {
+    let config = load_project_config(&state, project_path.as_deref());
+    check_action_command_with(&config.actions.command, |program| which::find_in_user_path(&[program]))
+}
+
 /// Ensure a shellflow config file exists and return its path.
 /// Creates the file with a schema reference if it doesn't exist.
-/// - `file_type`: "settings" or "mappings"
+/// - `file_type`: "settings", "mappings", or a project's "repo"/"local" config
+///   (which needs `project_path`)
 #[tauri::command]
-fn get_config_file_path(file_type: &str) -> Result<String> {
+fn get_config_file_path(file_type: &str, project_path: Option<String>) -> Result<String> {
     use std::fs;
     use std::path::PathBuf;
 
-    let (path, default_content): (PathBuf, &str) = match file_type {
-        "settings" => (
-            config::get_config_path(),
-            r#"{
+    const SETTINGS_CONTENT: &str = r#"{
   "$schema": "https://raw.githubusercontent.com/shkm/shellflow/main/schemas/config.schema.json"
 }
-"#,
-        ),
+"#;
+
+    let (path, default_content): (PathBuf, &str) = match file_type {
+        "settings" => (config::get_config_path(), SETTINGS_CONTENT),
+        "repo" | "local" => {
+            let project_path = project_path.ok_or_else(|| {
+                CommandError::InvalidInput(format!("The {} config needs a project path", file_type))
+            })?;
+            let paths = config::get_config_paths(Some(&project_path));
+            let index = if file_type == "repo" { 1 } else { 2 };
+            (paths[index].clone(), SETTINGS_CONTENT)
+        }
         "mappings" => (
             mappings::get_mappings_path(),
             r#"{
@@ -2177,7 +4628,7 @@ fn get_config_file_path(file_type: &str) -> Result<String> {
 }
 "#,
         ),
-        _ => return Err(format!("Unknown config file type: {}", file_type)),
+        _ => return Err(CommandError::InvalidInput(format!("Unknown config file type: {}", file_type))),
     };
 
     // Ensure parent directory exists
@@ -2293,7 +4744,7 @@ fn rename_worktree(
 ) -> Result<()> {
     // Validate the new name
     if let Some(error) = git::validate_branch_name(new_name) {
-        return Err(error);
+        return Err(CommandError::InvalidInput(error));
     }
 
     let mut persisted = state.persisted.write();
@@ -2307,18 +4758,16 @@ fn rename_worktree(
         }
     }
 
-    let (project_path, old_name) = found.ok_or_else(|| format!("Worktree not found: {}", worktree_id))?;
+    let (project_path, old_name) = found.ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?;
 
     // Check if new name is same as old name
     if old_name == new_name {
         return Ok(());
     }
 
-    // Check if the new branch name already exists
+    // Check the new name isn't taken, including by a branch differing only by case
     let project_path = std::path::Path::new(&project_path);
-    if git::branch_exists(project_path, new_name).map_err(map_err)? {
-        return Err(format!("Branch '{}' already exists", new_name));
-    }
+    git::ensure_branch_name_available(project_path, new_name, Some(&old_name)).map_err(map_err)?;
 
     // Rename the git branch
     git::rename_branch(project_path, &old_name, new_name).map_err(map_err)?;
@@ -2381,7 +4830,7 @@ fn reorder_worktrees(
         .projects
         .iter_mut()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| format!("Project not found: {}", project_id))?;
+        .ok_or_else(|| CommandError::NotFound(format!("Project not found: {}", project_id)))?;
 
     // Create order map from the provided order
     let order_map: std::collections::HashMap<String, i32> = worktree_ids
@@ -2407,12 +4856,151 @@ fn reorder_worktrees(
     Ok(())
 }
 
+// Clear the persisted task/main exit results for a worktree
+#[tauri::command]
+fn clear_worktree_results(state: State<'_, Arc<AppState>>, worktree_id: &str) -> Result<()> {
+    let mut persisted = state.persisted.write();
+    let worktree = persisted
+        .projects
+        .iter_mut()
+        .flat_map(|p| p.worktrees.iter_mut())
+        .find(|w| w.id == worktree_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Worktree not found: {}", worktree_id)))?;
+
+    worktree.last_results.clear();
+
+    drop(persisted);
+    state.save().map_err(map_err)?;
+    Ok(())
+}
+
+// Audit log commands
+#[tauri::command]
+fn get_audit_log(filter: Option<audit::AuditFilter>, limit: Option<usize>) -> audit::AuditLogResult {
+    audit::get_audit_log(&filter.unwrap_or_default(), limit.unwrap_or(200))
+}
+
+#[tauri::command]
+fn get_audit_log_path() -> String {
+    audit::get_audit_log_path().display().to_string()
+}
+
 // Update menu item enabled states based on action availability from frontend
 #[tauri::command]
 fn update_action_availability(availability: HashMap<String, bool>) {
     menu::update_action_availability(availability);
 }
 
+/// Delay before applying an active-project switch, so rapid switching updates the menu once
+const ACTIVE_PROJECT_DEBOUNCE_MS: u64 = 200;
+
+/// Effective mappings (with their source layers) for a project, or global-only
+fn effective_mappings(state: &AppState, project_id: Option<&str>) -> Vec<config::EffectiveMapping> {
+    let project_path = project_id.and_then(|id| {
+        state
+            .persisted
+            .read()
+            .projects
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.path.clone())
+    });
+    let options = config_load_options(state, project_path.as_deref());
+    config::load_effective_mappings(project_path.as_deref(), &options)
+}
+
+// Tell the backend which project is shown so menu accelerators follow its mappings
+#[tauri::command]
+fn set_active_project(state: State<'_, Arc<AppState>>, project_id: Option<String>) {
+    let generation = {
+        let mut active = state.active_project.write();
+        active.project_id = project_id;
+        active.generation += 1;
+        active.generation
+    };
+
+    let app_state = Arc::clone(&*state);
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(ACTIVE_PROJECT_DEBOUNCE_MS));
+        let project_id = {
+            let active = app_state.active_project.read();
+            if active.generation != generation {
+                // Superseded by a later switch
+                return;
+            }
+            active.project_id.clone()
+        };
+        menu::update_accelerators(&effective_mappings(&app_state, project_id.as_deref()));
+    });
+}
+
+/// Record what the frontend is showing: the project drives menu accelerators, and a
+/// focused worktree is remembered as that project's last-focused one
+#[tauri::command]
+fn set_active_entity(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    project_id: Option<String>,
+    worktree_id: Option<String>,
+) -> Result<()> {
+    if let (Some(project_id), Some(worktree_id)) = (&project_id, &worktree_id) {
+        if state.set_last_focused_worktree(project_id, worktree_id) {
+            state.save().map_err(map_err)?;
+        }
+    }
+    // Both leaving an entity and arriving at one count as activity in it
+    let previous = {
+        let active = state.active_project.read();
+        active.worktree_id.clone().or_else(|| active.project_id.clone())
+    };
+    let focused = worktree_id.clone().or_else(|| project_id.clone());
+    for entity_id in previous.iter().chain(focused.as_ref()) {
+        idle::record_focus(entity_id);
+    }
+    state.active_project.write().worktree_id = worktree_id;
+    let app_state = Arc::clone(&*state);
+    set_active_project(state, project_id);
+
+    // Opening a different entity brings up its auto-start tasks
+    if let Some(entity_id) = focused.filter(|id| previous.as_ref() != Some(id)) {
+        std::thread::spawn(move || start_auto_start_tasks(&app, &app_state, &entity_id));
+    }
+    Ok(())
+}
+
+/// Turn `autoStart` on or off for a task, in the config file that defines it
+#[tauri::command]
+fn set_task_autostart(project_path: &str, task_name: &str, enabled: bool) -> Result<()> {
+    config::set_task_autostart(Path::new(project_path), task_name, enabled).map_err(map_err)?;
+    Ok(())
+}
+
+/// Worktree to restore when switching back to a project; None if it was deleted
+#[tauri::command]
+fn get_last_focused(state: State<'_, Arc<AppState>>, project_id: &str) -> Option<String> {
+    state.last_focused_worktree(project_id)
+}
+
+/// Effective mappings for a project (defaults to the active one), with the config
+/// layer that supplied each binding
+#[tauri::command]
+fn get_effective_mappings(state: State<'_, Arc<AppState>>, project_id: Option<String>) -> Vec<config::EffectiveMapping> {
+    let project_id = project_id.or_else(|| state.active_project.read().project_id.clone());
+    effective_mappings(&state, project_id.as_deref())
+}
+
+/// Current status-bar summary, for refreshing after a reload between `status-summary` events
+#[tauri::command]
+fn get_status_summary(state: State<'_, Arc<AppState>>) -> StatusSummary {
+    status::snapshot(&state)
+}
+
+/// Every event the backend emits with its payload fields, and the schema version
+#[tauri::command]
+fn get_event_catalog() -> events::EventCatalog {
+    events::catalog()
+}
+
 // Shutdown command - gracefully terminates all PTY processes
 // Spawns a background thread and returns immediately so events can stream to frontend
 #[tauri::command]
@@ -2490,6 +5078,8 @@ pub fn run() {
         .setup(|app| {
             eprintln!("[setup] Shellflow starting...");
 
+            pty::raise_fd_limit();
+
             let window = app.get_webview_window("main").expect("main window not found");
             let app_state = app.state::<Arc<AppState>>();
 
@@ -2530,11 +5120,25 @@ pub fn run() {
 
             // Load config for menu shortcuts
             let config = config::load_config();
+            perf::set_enabled(config.perf.enabled);
 
             // Set up application menu
             menu::setup_menu(app, &config.mappings)?;
 
 
+            // Retry cleanup of worktrees a previous delete couldn't fully remove
+            let cleanup_state = Arc::clone(&*app_state);
+            std::thread::spawn(move || retry_pending_worktree_cleanups(&cleanup_state));
+
+            // Consolidated status-bar state, emitted as `status-summary` when it changes
+            status::start_aggregator(app.handle().clone(), Arc::clone(&*app_state));
+
+            // Stop daemon tasks that opted into idle shutdown
+            idle::start_idle_reaper(app.handle().clone(), Arc::clone(&*app_state));
+
+            // Restart watchers and drop ended sessions after the system sleeps
+            wake::start_wake_monitor(app.handle().clone(), Arc::clone(&*app_state), config.wake.clone());
+
             // Start file watchers for worktrees in active projects only
             // This enables detection of externally deleted worktree folders
             let persisted = app_state.persisted.read();
@@ -2547,40 +5151,86 @@ pub fn run() {
                     );
                 }
             }
+            drop(persisted);
+
+            let _ = app.emit(
+                events::APP_READY,
+                events::AppReady {
+                    schema_version: events::SCHEMA_VERSION,
+                    version: app.package_info().version.to_string(),
+                },
+            );
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(perf::timed(tauri::generate_handler![
             log_to_terminal,
             list_system_fonts,
             git_stage_all,
+            get_staged_file_diff,
+            get_git_config,
+            stage_files,
+            unstage_files,
+            discard_changes,
+            stage_hunk,
+            unstage_hunk,
             git_diff_cached,
             git_diff_cached_files,
             git_commit,
+            amend_commit,
             git_current_branch,
             git_branch_exists,
             git_create_branch,
+            fetch_remote,
             git_push_current_branch,
+            git_recent_commits,
+            get_commit_log,
+            get_branch_commit_log,
+            get_language_stats,
+            get_drift_report,
+            scan_external_worktrees,
+            adopt_worktree,
+            branch_color,
+            get_template_variables,
+            render_template_preview,
+            validate_templates,
             git_merge_to_main,
             git_push_default_branch,
+            quick_commit_push,
+            generate_commit_message,
             add_project,
+            scan_for_projects,
+            add_projects_bulk,
             list_projects,
+            update_project_path,
+            get_resource_usage,
             hide_project,
             touch_project,
+            gc_copy_store,
             create_worktree,
+            create_worktrees_batch,
+            preview_worktree_directory,
+            list_all_worktrees,
+            search_entities,
             list_worktrees,
             delete_worktree,
             check_worktree_delete_status,
             execute_delete_worktree_workflow,
             remove_stale_worktree,
+            retry_worktree_cleanup,
             rename_worktree,
             reorder_projects,
             reorder_worktrees,
+            clear_worktree_results,
+            get_audit_log,
+            get_audit_log_path,
             get_home_dir,
             open_folder,
             open_with_app,
             open_in_terminal,
             open_in_editor,
+            check_apps,
+            check_action_command,
             get_config_file_path,
             open_in_file_manager,
             open_default,
@@ -2590,31 +5240,80 @@ pub fn run() {
             spawn_shell,
             spawn_command,
             spawn_action,
+            spawn_git_interactive,
+            start_interactive_rebase,
+            save_worktree_layout,
+            get_worktree_layout,
+            reset_worktree_layout,
             watch_merge_state,
             stop_merge_watcher,
+            watch_commits,
+            stop_watch_commits,
             watch_rebase_state,
             stop_rebase_watcher,
             spawn_project_shell,
+            preview_main_command,
+            get_task_catalog,
             spawn_task,
             get_task_urls,
             pty_write,
             pty_resize,
+            attach_pty,
+            detach_pty,
             pty_interrupt,
             pty_kill,
             pty_force_kill,
+            get_pty_cwd,
+            resolve_terminal_path,
+            get_pty_foreground,
+            set_pty_scrollback,
             get_changed_files,
+            refresh_changed_files,
+            restore_file_from_commit,
+            get_status_v2,
             get_branch_info,
             get_branch_changed_files,
+            get_diff_summary,
+            get_perf_stats,
+            list_branches,
+            prune_remote_refs,
+            list_gone_branches,
+            get_upstream,
+            set_upstream,
+            list_ignored_files,
+            check_ignore,
+            create_working_snapshot,
+            list_working_snapshots,
+            delete_working_snapshot,
+            diff_against_snapshot,
             get_file_diff_content,
+            get_file_diff,
+            get_file_diff_sides,
             has_uncommitted_changes,
+            needs_stash,
             stash_changes,
             stash_pop,
+            get_pending_stashes,
+            get_conflict_summary,
+            mark_resolved,
+            continue_rebase,
+            continue_merge,
+            continue_cherry_pick,
+            skip_rebase_commit,
+            pop_pending_stash,
+            drop_pending_stash,
             abort_merge,
             abort_rebase,
             start_watching,
             stop_watching,
             get_config,
+            set_config_layer_enabled,
+            get_config_layer_status,
             update_config,
+            set_task_autostart,
+            locate_configs,
+            migrate_config,
+            get_config_warnings,
             watch_config,
             stop_config_watcher,
             get_mappings,
@@ -2623,12 +5322,19 @@ pub fn run() {
             expand_action_prompt,
             check_merge_feasibility,
             execute_merge_workflow,
+            merge_into_multiple,
             cleanup_worktree,
             shutdown,
             update_action_availability,
+            set_active_project,
+            set_active_entity,
+            get_last_focused,
+            get_effective_mappings,
+            get_status_summary,
+            get_event_catalog,
             theme::list_themes,
             theme::read_theme,
-        ])
+        ]))
         .on_window_event(|window, event| {
             match event {
                 tauri::WindowEvent::Resized(size) => {
@@ -2648,7 +5354,7 @@ pub fn run() {
                     // Prevent default close - let frontend handle it
                     api.prevent_close();
                     // Emit event to frontend to trigger shutdown flow
-                    let _ = window.emit("close-requested", ());
+                    let _ = window.emit(events::CLOSE_REQUESTED, ());
                 }
                 tauri::WindowEvent::Destroyed => {
                     // Final cleanup (in case frontend didn't trigger shutdown)
@@ -2669,3 +5375,374 @@ pub fn run() {
 pub fn run_watchdog(parent_pid: u32) {
     cleanup::run_watchdog(parent_pid);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_apps_resolves_the_program_of_the_configured_command() {
+        let find = |program: &str| (program == "code").then(|| "/usr/bin/code".to_string());
+        let editor = config::AppConfig::Full { command: Some("code --wait {{ path }}".to_string()), target: config::AppTarget::External };
+
+        let check = check_configured_app(Some(&editor), || unreachable!(), find);
+        assert_eq!(check.source, AppCommandSource::Config);
+        assert_eq!(check.resolved_path.as_deref(), Some("/usr/bin/code"));
+        assert!(check.found);
+
+        let missing = config::AppConfig::Simple("'my editor' -n".to_string());
+        let check = check_configured_app(Some(&missing), || unreachable!(), find);
+        assert_eq!(check.command.as_deref(), Some("'my editor' -n"));
+        assert!(!check.found);
+
+        // No command in config falls back, and nothing at all is reported as missing
+        let target_only = config::AppConfig::Full { command: None, target: config::AppTarget::Terminal };
+        let check = check_configured_app(Some(&target_only), || (None, AppCommandSource::None), find);
+        assert_eq!(check.target, config::AppTarget::Terminal);
+        assert_eq!(check.source, AppCommandSource::None);
+        assert!(!check.found);
+    }
+
+    #[test]
+    fn check_action_command_resolves_the_cli_past_env_assignments() {
+        let find = |program: &str| (program == "claude").then(|| "/usr/local/bin/claude".to_string());
+
+        let check = check_action_command_with("claude", find);
+        assert_eq!(check.resolved_path.as_deref(), Some("/usr/local/bin/claude"));
+        assert!(check.found && check.message.is_none());
+
+        let check = check_action_command_with("DEBUG=1 claude --verbose", find);
+        assert_eq!(check.resolved_path.as_deref(), Some("/usr/local/bin/claude"));
+
+        let check = check_action_command_with("aider --yes", find);
+        assert!(!check.found);
+        assert_eq!(
+            check.message.as_deref(),
+            Some("'aider' was not found in PATH. Install aider or configure actions.command.")
+        );
+
+        let check = check_action_command_with("  ", find);
+        assert!(!check.found);
+        assert!(check.message.unwrap().contains("empty"));
+    }
+
+    #[test]
+    fn main_command_expands_the_same_context_it_launches_with() {
+        let mut cfg = config::Config::from_raw(config::RawConfig::default());
+        assert_eq!(main_command(&cfg, "/code/repo", None).unwrap(), None);
+
+        cfg.main.command = Some("dev --name {{ worktree_name }} --branch {{ branch | sanitize }}".to_string());
+        assert_eq!(
+            main_command(&cfg, "/code/repo", Some(("feature/login", "login"))).unwrap().as_deref(),
+            Some("dev --name login --branch feature-login")
+        );
+
+        cfg.main.command = Some("open {{ repo_directory }}{% if branch %} {{ branch }}{% endif %}".to_string());
+        assert_eq!(main_command(&cfg, "/code/repo", None).unwrap().as_deref(), Some("open /code/repo"));
+    }
+
+    #[test]
+    fn auto_start_picks_idle_daemons_within_the_limit() {
+        let task = |name: &str, kind: &str, auto_start: bool| -> config::TaskConfig {
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "command": "true",
+                "kind": kind,
+                "autoStart": auto_start,
+            }))
+            .unwrap()
+        };
+        let tasks = vec![
+            task("Api", "daemon", true),
+            task("Web", "daemon", true),
+            task("Docs", "daemon", false),
+            task("Build", "command", true),
+            task("Worker", "daemon", true),
+        ];
+        let names = |picked: Vec<&config::TaskConfig>| picked.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+        let running: std::collections::HashSet<String> = ["Web".to_string()].into();
+
+        assert_eq!(names(auto_start_tasks(&tasks, &running, 1, None)), ["Api", "Worker"]);
+        assert_eq!(names(auto_start_tasks(&tasks, &running, 1, Some(0))), ["Api", "Worker"]);
+        // Three sessions allowed, two already running elsewhere
+        assert_eq!(names(auto_start_tasks(&tasks, &running, 2, Some(3))), ["Api"]);
+        assert!(auto_start_tasks(&tasks, &running, 5, Some(3)).is_empty());
+    }
+
+    const RACE_PATH: &str = "/tmp/shellflow-race/.worktrees/feature";
+
+    fn state_with_worktree() -> Arc<AppState> {
+        let state = AppState::new();
+        state.persisted.write().projects.push(Project {
+            id: "p1".to_string(),
+            name: "repo".to_string(),
+            path: "/tmp/shellflow-race".to_string(),
+            worktrees: vec![Worktree {
+                id: "wt1".to_string(),
+                name: "feature".to_string(),
+                path: RACE_PATH.to_string(),
+                branch: "feature".to_string(),
+                created_at: String::new(),
+                order: 0,
+                last_results: Default::default(),
+                pending_cleanup: false,
+                layout: None,
+            }],
+            order: 0,
+            is_active: true,
+            last_accessed_at: None,
+            disabled_config_layers: Vec::new(),
+            last_focused_worktree_id: None,
+            root_commit: None,
+        });
+        Arc::new(state)
+    }
+
+    /// Run the delete workflow's state change and the stale removal at the same
+    /// time; returns whether each of them removed the worktree
+    fn race(workflow_claims_first: bool) -> (bool, bool) {
+        let state = state_with_worktree();
+        // The workflow command claims the worktree before its thread starts
+        let early_claim = workflow_claims_first.then(|| state.begin_deletion("wt1").unwrap());
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+
+        let workflow = {
+            let (state, barrier) = (Arc::clone(&state), Arc::clone(&barrier));
+            std::thread::spawn(move || {
+                barrier.wait();
+                let Some(_deletion) = early_claim.or_else(|| state.begin_deletion("wt1")) else {
+                    return false;
+                };
+                state.remove_worktree_entry("wt1")
+            })
+        };
+        let stale = {
+            let (state, barrier) = (Arc::clone(&state), Arc::clone(&barrier));
+            std::thread::spawn(move || {
+                barrier.wait();
+                remove_stale_worktree_entry(&state, RACE_PATH)
+            })
+        };
+
+        let removed = (workflow.join().unwrap(), stale.join().unwrap());
+        assert!(state.worktree_id_for_path(RACE_PATH).is_none());
+        assert!(!state.is_being_deleted("wt1"), "the claim must be released");
+        removed
+    }
+
+    #[test]
+    fn concurrent_deletions_mutate_state_once() {
+        for _ in 0..50 {
+            assert_eq!(race(true), (true, false));
+            let (by_workflow, by_stale) = race(false);
+            assert!(by_workflow != by_stale, "exactly one path removes the worktree");
+        }
+    }
+
+    fn merge_options() -> MergeWorkflowOptions {
+        MergeWorkflowOptions {
+            strategy: MergeStrategy::Merge,
+            delete_worktree: true,
+            delete_local_branch: true,
+            delete_remote_branch: true,
+            push_after_merge: true,
+            target_branch: None,
+            fast_forward: false,
+        }
+    }
+
+    #[test]
+    fn merge_options_take_fast_forward_in_camel_case() {
+        let options: MergeWorkflowOptions = serde_json::from_value(serde_json::json!({
+            "strategy": "merge",
+            "deleteWorktree": false,
+            "deleteLocalBranch": false,
+            "deleteRemoteBranch": false,
+            "fastForward": true,
+        }))
+        .unwrap();
+        assert!(options.fast_forward);
+        assert_eq!(serde_json::to_value(&options).unwrap()["fastForward"], true);
+
+        // Older frontends leave it out
+        let options: MergeWorkflowOptions = serde_json::from_value(serde_json::json!({
+            "strategy": "rebase",
+            "deleteWorktree": true,
+            "deleteLocalBranch": true,
+            "deleteRemoteBranch": false,
+        }))
+        .unwrap();
+        assert!(!options.fast_forward);
+        assert!(!options_for_target(&merge_options(), "main", true).fast_forward);
+    }
+
+    fn completed(success: bool) -> MergeCompleted {
+        MergeCompleted {
+            worktree_id: "wt".to_string(),
+            success,
+            branch_name: "hotfix".to_string(),
+            deleted_worktree: false,
+            error: (!success).then(|| "Merge failed: conflict".to_string()),
+            push_failed: false,
+            push_error: None,
+        }
+    }
+
+    #[test]
+    fn multi_target_merge_defers_cleanup_to_the_last_target() {
+        let targets = vec!["main".to_string(), "release/2.4".to_string(), "release/2.3".to_string()];
+        let mut runs: Vec<MergeWorkflowOptions> = Vec::new();
+        let (results, last) = merge_targets_sequentially(&targets, &merge_options(), |options| {
+            runs.push(options.clone());
+            completed(true)
+        });
+
+        let targets_run: Vec<_> = runs.iter().map(|o| o.target_branch.as_deref().unwrap()).collect();
+        assert_eq!(targets_run, vec!["main", "release/2.4", "release/2.3"]);
+        let deletes: Vec<_> = runs
+            .iter()
+            .map(|o| (o.delete_worktree, o.delete_local_branch, o.delete_remote_branch))
+            .collect();
+        assert_eq!(deletes, vec![(false, false, false), (false, false, false), (true, true, true)]);
+        assert!(runs.iter().all(|o| o.push_after_merge));
+        assert!(results.iter().all(|r| r.status == MergeTargetStatus::Merged));
+        assert!(last.unwrap().success);
+    }
+
+    #[test]
+    fn multi_target_merge_stops_at_the_first_failure() {
+        let targets = vec!["main".to_string(), "release/2.4".to_string(), "release/2.3".to_string()];
+        let mut runs = 0;
+        let (results, last) = merge_targets_sequentially(&targets, &merge_options(), |options| {
+            runs += 1;
+            // Cleanup never runs for a target that isn't last
+            assert!(!options.delete_worktree);
+            completed(options.target_branch.as_deref() == Some("main"))
+        });
+
+        assert_eq!(runs, 2);
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![MergeTargetStatus::Merged, MergeTargetStatus::Failed, MergeTargetStatus::Skipped]);
+        assert_eq!(results[1].error.as_deref(), Some("Merge failed: conflict"));
+        assert!(!last.unwrap().success);
+    }
+
+    fn init_repo_without_remote() -> PathBuf {
+        let repo = std::env::temp_dir().join(format!("shellflow-lib-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo).unwrap();
+        for args in [
+            vec!["init", "-q"],
+            vec!["-c", "user.name=shellflow", "-c", "user.email=shellflow@example.com", "commit", "-q", "--allow-empty", "-m", "init"],
+        ] {
+            assert!(std::process::Command::new("git").args(&args).current_dir(&repo).status().unwrap().success());
+        }
+        repo
+    }
+
+    #[test]
+    fn map_capped_keeps_order_and_respects_cap() {
+        let running = std::sync::atomic::AtomicUsize::new(0);
+        let peak = std::sync::atomic::AtomicUsize::new(0);
+        let items: Vec<usize> = (0..20).collect();
+        let doubled = map_capped(&items, 3, |n| {
+            let now = running.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            peak.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+            n * 2
+        });
+        assert_eq!(doubled, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(peak.load(std::sync::atomic::Ordering::SeqCst) <= 3);
+        assert!(map_capped(&Vec::<usize>::new(), 3, |n| *n).is_empty());
+    }
+
+    #[test]
+    fn push_after_merge_disabled_does_nothing() {
+        let repo = init_repo_without_remote();
+        let mut phases = Vec::new();
+        let warning = push_after_merge(&repo, false, &NetworkRetry::without_notices(), |p| phases.push(p.phase));
+
+        assert!(warning.is_none());
+        assert!(phases.is_empty());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn push_after_merge_without_remote_warns() {
+        let repo = init_repo_without_remote();
+        let mut progress = Vec::new();
+        let warning = push_after_merge(&repo, true, &NetworkRetry::without_notices(), |p| progress.push(p));
+
+        let phases: Vec<&str> = progress.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(phases, ["push", "push-failed"]);
+        let warning = warning.expect("push without a remote should warn");
+        assert_eq!(progress[1].message, warning);
+        assert!(warning.contains("git push origin"), "{}", warning);
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn merge_feasibility_skips_disabled_config_layers() {
+        let repo = init_repo_without_remote();
+        std::fs::create_dir_all(repo.join(".shellflow")).unwrap();
+        std::fs::write(
+            repo.join(".shellflow").join("config.local.jsonc"),
+            r#"{ "worktree": { "baseBranch": { "name": "release" } } }"#,
+        )
+        .unwrap();
+        let state = AppState::new();
+        state.persisted.write().projects.push(worktree::create_project(&repo).unwrap());
+        let repo_path = repo.to_string_lossy().to_string();
+
+        let feasibility = merge_feasibility(&state, &repo_path, Some(&repo_path), None).unwrap();
+        assert_eq!(feasibility.target_branch, "release");
+
+        state.persisted.write().projects[0].disabled_config_layers.push(config::ConfigLayer::Local);
+        let feasibility = merge_feasibility(&state, &repo_path, Some(&repo_path), None).unwrap();
+        assert_ne!(feasibility.target_branch, "release");
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn scans_and_adopts_worktrees_added_outside_shellflow() {
+        let repo = init_repo_without_remote();
+        let git = |args: &[&str]| assert!(std::process::Command::new("git").args(args).current_dir(&repo).status().unwrap().success());
+        git(&["worktree", "add", "-q", "-b", "manual", ".worktrees/manual"]);
+        git(&["worktree", "add", "-q", "--detach", ".worktrees/detached"]);
+
+        let mut project = worktree::create_project(&repo).unwrap();
+        project.worktrees.push(Worktree {
+            id: "gone".to_string(),
+            name: "gone".to_string(),
+            path: repo.join(".worktrees").join("gone").to_string_lossy().to_string(),
+            branch: "gone".to_string(),
+            created_at: String::new(),
+            order: 0,
+            last_results: Default::default(),
+            pending_cleanup: false,
+            layout: None,
+        });
+
+        let registered = list_linked_worktrees(&repo).unwrap();
+        let scan = scan_registered_worktrees(&project, &registered);
+        let mut branches: Vec<Option<&str>> = scan.unmanaged.iter().map(|w| w.branch.as_deref()).collect();
+        branches.sort();
+        assert_eq!(branches, [None, Some("manual")]);
+        assert_eq!(scan.stale.len(), 1);
+        assert_eq!(scan.stale[0].id, "gone");
+
+        let manual = repo.join(".worktrees").join("manual");
+        let adopted = adopt_registered_worktree(&mut project, &registered, manual.to_str().unwrap()).unwrap();
+        assert_eq!(adopted.branch, "manual");
+        assert_eq!(adopted.order, 1);
+        let detached = adopt_registered_worktree(&mut project, &registered, repo.join(".worktrees").join("detached").to_str().unwrap()).unwrap();
+        assert_eq!(detached.name, "detached");
+
+        // Already tracked, and the main worktree, can't be adopted
+        assert!(matches!(adopt_registered_worktree(&mut project, &registered, manual.to_str().unwrap()), Err(CommandError::InvalidInput(_))));
+        assert!(adopt_registered_worktree(&mut project, &registered, repo.to_str().unwrap()).is_err());
+        let rescan = scan_registered_worktrees(&project, &list_linked_worktrees(&repo).unwrap());
+        assert!(rescan.unmanaged.is_empty());
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+}
@@ -3,17 +3,21 @@ use std::collections::HashMap;
 use tauri::menu::{MenuBuilder, MenuItem, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
 use tauri::{Emitter, Manager};
 
-use crate::config::MappingsConfig;
+use crate::config::{EffectiveMapping, MappingsConfig};
+use crate::events;
 
-/// Holds references to menu items that can be dynamically enabled/disabled.
+/// Holds references to menu items that can be dynamically enabled/disabled,
+/// and to the items whose accelerators follow the active project's mappings.
 pub struct DynamicMenuItems {
     items: HashMap<String, MenuItem<tauri::Wry>>,
+    accelerated: HashMap<String, MenuItem<tauri::Wry>>,
 }
 
 impl DynamicMenuItems {
     pub fn new() -> Self {
         Self {
             items: HashMap::new(),
+            accelerated: HashMap::new(),
         }
     }
 
@@ -21,6 +25,20 @@ impl DynamicMenuItems {
         self.items.insert(id.to_string(), item);
     }
 
+    /// Track an item whose ID is the action ID of its mapping
+    pub fn insert_accelerated(&mut self, item: &MenuItem<tauri::Wry>) {
+        self.accelerated.insert(item.id().as_ref().to_string(), item.clone());
+    }
+
+    /// Point each tracked item's accelerator at its effective mapping
+    pub fn update_accelerators(&self, mappings: &[EffectiveMapping]) {
+        for mapping in mappings {
+            if let Some(item) = self.accelerated.get(&mapping.action) {
+                let _ = item.set_accelerator(Some(mapping.accelerator.as_str()));
+            }
+        }
+    }
+
     /// Update menu item enabled states based on the provided availability map
     pub fn update_availability(&self, availability: &HashMap<String, bool>) {
         for (id, enabled) in availability {
@@ -386,6 +404,43 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
 
     app.set_menu(menu)?;
 
+    for item in [
+        &quit_item,
+        &add_project,
+        &switch_project,
+        &new_worktree,
+        &new_scratch_terminal,
+        &new_tab,
+        &close_tab,
+        &run_task,
+        &task_switcher,
+        &toggle_drawer,
+        &toggle_right_panel,
+        &expand_drawer,
+        &command_palette,
+        &zoom_in,
+        &zoom_out,
+        &zoom_reset,
+        &prev_session,
+        &next_session,
+        &go_back,
+        &go_forward,
+        &switch_focus,
+        &entity1,
+        &entity2,
+        &entity3,
+        &entity4,
+        &entity5,
+        &entity6,
+        &entity7,
+        &entity8,
+        &entity9,
+        &next_changed_file,
+        &prev_changed_file,
+    ] {
+        dynamic_items.insert_accelerated(item);
+    }
+
     // Store dynamic items for later updates
     *MENU_ITEMS.write() = Some(dynamic_items);
 
@@ -396,11 +451,11 @@ pub fn setup_menu(app: &tauri::App, mappings: &MappingsConfig) -> Result<(), Box
             match menu_id {
                 "app::quit" => {
                     // Trigger graceful shutdown via window close
-                    let _ = window.emit("close-requested", ());
+                    let _ = window.emit(events::CLOSE_REQUESTED, ());
                 }
                 // Emit menu action events to the frontend
                 id => {
-                    let _ = window.emit("menu-action", id);
+                    let _ = window.emit(events::MENU_ACTION, id);
                 }
             }
         }
@@ -415,3 +470,10 @@ pub fn update_action_availability(availability: HashMap<String, bool>) {
         items.update_availability(&availability);
     }
 }
+
+/// Apply a project's effective mappings to the menu accelerators
+pub fn update_accelerators(mappings: &[EffectiveMapping]) {
+    if let Some(ref items) = *MENU_ITEMS.read() {
+        items.update_accelerators(mappings);
+    }
+}
@@ -0,0 +1,346 @@
+//! Retries for git commands that talk to a remote.
+//!
+//! Pushes and remote branch deletions over a flaky connection fail now and
+//! then with errors a second try would get past. Failures are classified from
+//! git's stderr: transient ones (dropped connections, DNS hiccups, rate
+//! limits) are retried with exponential backoff and announced before each
+//! wait, permanent ones (rejected auth, non-fast-forward pushes) fail at once.
+
+use crate::config::NetworkConfig;
+use crate::git::GitError;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Upper bound on `network.retries`, however it is configured
+const MAX_RETRIES: u32 = 10;
+/// Longest single wait between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Why a remote git command failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkFailureKind {
+    ConnectionReset,
+    HostUnresolved,
+    ConnectionRefused,
+    TimedOut,
+    /// The remote hung up mid-transfer
+    EarlyEof,
+    RateLimited,
+    ServerUnavailable,
+    AuthenticationFailed,
+    NonFastForward,
+    RefRejected,
+    RepositoryNotFound,
+    /// Not recognised; treated as permanent
+    Other,
+}
+
+impl NetworkFailureKind {
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            NetworkFailureKind::ConnectionReset
+                | NetworkFailureKind::HostUnresolved
+                | NetworkFailureKind::ConnectionRefused
+                | NetworkFailureKind::TimedOut
+                | NetworkFailureKind::EarlyEof
+                | NetworkFailureKind::RateLimited
+                | NetworkFailureKind::ServerUnavailable
+        )
+    }
+}
+
+/// Stderr fragments per kind, checked in order. Permanent kinds come first:
+/// git follows an auth failure with "the remote end hung up unexpectedly",
+/// which alone would read as transient.
+const PATTERNS: &[(NetworkFailureKind, &[&str])] = &[
+    (
+        NetworkFailureKind::AuthenticationFailed,
+        &[
+            "authentication failed",
+            "permission denied",
+            "could not read username",
+            "could not read password",
+            "invalid username or password",
+            "access denied",
+            "returned error: 401",
+            "returned error: 403",
+        ],
+    ),
+    (
+        NetworkFailureKind::RepositoryNotFound,
+        &["repository not found", "does not appear to be a git repository", "returned error: 404"],
+    ),
+    (
+        NetworkFailureKind::NonFastForward,
+        &["non-fast-forward", "(fetch first)", "tip of your current branch is behind"],
+    ),
+    (
+        NetworkFailureKind::RefRejected,
+        &["[remote rejected]", "[rejected]", "hook declined", "protected branch"],
+    ),
+    (
+        NetworkFailureKind::RateLimited,
+        &["returned error: 429", "too many requests", "rate limit"],
+    ),
+    (
+        NetworkFailureKind::ServerUnavailable,
+        &[
+            "returned error: 500",
+            "returned error: 502",
+            "returned error: 503",
+            "returned error: 504",
+            "service unavailable",
+            "bad gateway",
+        ],
+    ),
+    (
+        NetworkFailureKind::HostUnresolved,
+        &[
+            "could not resolve host",
+            "could not resolve hostname",
+            "temporary failure in name resolution",
+            "name or service not known",
+        ],
+    ),
+    (
+        NetworkFailureKind::ConnectionReset,
+        &[
+            "connection reset",
+            "broken pipe",
+            "connection closed by",
+            "kex_exchange_identification",
+            "ssh_exchange_identification",
+        ],
+    ),
+    (
+        NetworkFailureKind::ConnectionRefused,
+        &["connection refused", "network is unreachable", "no route to host", "failed to connect to"],
+    ),
+    (NetworkFailureKind::TimedOut, &["timed out", "timeout"]),
+    (
+        NetworkFailureKind::EarlyEof,
+        &["early eof", "the remote end hung up unexpectedly", "unexpected disconnect", "rpc failed"],
+    ),
+];
+
+/// Classify a failed remote command by its error output
+pub fn classify_failure(stderr: &str) -> NetworkFailureKind {
+    let stderr = stderr.to_lowercase();
+    PATTERNS
+        .iter()
+        .find(|(_, fragments)| fragments.iter().any(|f| stderr.contains(f)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(NetworkFailureKind::Other)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total tries, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles for each one after
+    pub initial_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        Self {
+            max_attempts: config.retries.min(MAX_RETRIES) + 1,
+            initial_delay: Duration::from_millis(config.retry_delay_ms),
+        }
+    }
+
+    /// Wait before `attempt` (2 for the first retry)
+    pub fn delay_before(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(2).min(16);
+        self.initial_delay.saturating_mul(1 << doublings).min(MAX_RETRY_DELAY)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&NetworkConfig::default())
+    }
+}
+
+/// Payload of `git-network-retry`, sent before waiting for the next attempt
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryNotice {
+    /// e.g. "push" or "delete remote branch"
+    pub operation: String,
+    /// The attempt about to be made
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    pub reason: NetworkFailureKind,
+    /// Error of the attempt that just failed
+    pub message: String,
+}
+
+impl RetryNotice {
+    /// e.g. "push attempt 2/3 in 4s"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} attempt {}/{} in {}s",
+            self.operation,
+            self.attempt,
+            self.max_attempts,
+            self.delay_ms.div_ceil(1000)
+        )
+    }
+}
+
+/// A retry policy with somewhere to report retries
+pub struct NetworkRetry<'a> {
+    policy: RetryPolicy,
+    on_retry: &'a dyn Fn(&RetryNotice),
+}
+
+impl<'a> NetworkRetry<'a> {
+    pub fn new(policy: RetryPolicy, on_retry: &'a dyn Fn(&RetryNotice)) -> Self {
+        Self { policy, on_retry }
+    }
+
+    #[cfg(test)]
+    pub fn without_notices() -> NetworkRetry<'static> {
+        fn ignore(_: &RetryNotice) {}
+        NetworkRetry::new(RetryPolicy::default(), &ignore)
+    }
+
+    /// Run `attempt` until it succeeds, fails permanently or runs out of
+    /// attempts. `attempt` returns the error message, including git's stderr.
+    pub fn run<T>(&self, operation: &str, attempt: impl FnMut() -> Result<T, String>) -> Result<T, GitError> {
+        retry_with(operation, &self.policy, attempt, self.on_retry, std::thread::sleep)
+    }
+}
+
+/// `NetworkRetry::run` with the wait injected, for tests
+fn retry_with<T>(
+    operation: &str,
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut() -> Result<T, String>,
+    on_retry: &dyn Fn(&RetryNotice),
+    mut sleep: impl FnMut(Duration),
+) -> Result<T, GitError> {
+    let max_attempts = policy.max_attempts.max(1);
+    let mut made = 1;
+    loop {
+        let message = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(message) => message,
+        };
+        let reason = classify_failure(&message);
+        if !reason.is_transient() || made >= max_attempts {
+            return Err(GitError::Network {
+                reason,
+                attempts: made,
+                message,
+            });
+        }
+
+        made += 1;
+        let delay = policy.delay_before(made);
+        log::info!("[network_retry] {} failed ({:?}), retrying in {:?}: {}", operation, reason, delay, message);
+        on_retry(&RetryNotice {
+            operation: operation.to_string(),
+            attempt: made,
+            max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            reason,
+            message,
+        });
+        sleep(delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn classifies_real_git_errors() {
+        use NetworkFailureKind::*;
+        let cases = [
+            ("Connection reset by 140.82.121.3 port 22\r\nfatal: Could not read from remote repository.", ConnectionReset),
+            ("kex_exchange_identification: read: Connection reset by peer", ConnectionReset),
+            ("send-pack: unexpected disconnect while reading sideband packet\nfatal: the remote end hung up unexpectedly", EarlyEof),
+            ("error: RPC failed; curl 92 HTTP/2 stream 5 was not closed cleanly\nfatal: early EOF", EarlyEof),
+            ("fatal: unable to access 'https://github.com/o/r.git/': Could not resolve host: github.com", HostUnresolved),
+            ("ssh: Could not resolve hostname gitlab.internal: Temporary failure in name resolution", HostUnresolved),
+            ("ssh: connect to host github.com port 22: Connection refused", ConnectionRefused),
+            ("fatal: unable to access 'https://x/': Failed to connect to x port 443 after 2 ms: Couldn't connect to server", ConnectionRefused),
+            ("ssh: connect to host github.com port 22: Operation timed out", TimedOut),
+            ("error: RPC failed; HTTP 429 curl 22 The requested URL returned error: 429", RateLimited),
+            ("fatal: unable to access 'https://x/': The requested URL returned error: 503", ServerUnavailable),
+            ("git@github.com: Permission denied (publickey).\r\nfatal: Could not read from remote repository.\nfatal: the remote end hung up unexpectedly", AuthenticationFailed),
+            ("remote: Invalid username or password.\nfatal: Authentication failed for 'https://github.com/o/r.git/'", AuthenticationFailed),
+            ("fatal: could not read Username for 'https://github.com': terminal prompts disabled", AuthenticationFailed),
+            (" ! [rejected]        main -> main (non-fast-forward)\nerror: failed to push some refs", NonFastForward),
+            (" ! [rejected]        main -> main (fetch first)", NonFastForward),
+            (" ! [remote rejected] main -> main (pre-receive hook declined)", RefRejected),
+            ("remote: GitLab: You are not allowed to push code to protected branches on this project.\n ! [remote rejected] main -> main", RefRejected),
+            ("ERROR: Repository not found.\nfatal: Could not read from remote repository.", RepositoryNotFound),
+            ("fatal: 'origin' does not appear to be a git repository", RepositoryNotFound),
+            ("error: src refspec main does not match any", Other),
+        ];
+        for (stderr, expected) in cases {
+            assert_eq!(classify_failure(stderr), expected, "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy { max_attempts: 10, initial_delay: Duration::from_secs(2) };
+        assert_eq!(policy.delay_before(2), Duration::from_secs(2));
+        assert_eq!(policy.delay_before(3), Duration::from_secs(4));
+        assert_eq!(policy.delay_before(4), Duration::from_secs(8));
+        assert_eq!(policy.delay_before(9), MAX_RETRY_DELAY);
+
+        let config = NetworkConfig { retries: 50, retry_delay_ms: 500, ..Default::default() };
+        assert_eq!(RetryPolicy::from_config(&config).max_attempts, MAX_RETRIES + 1);
+    }
+
+    /// Runs `outcomes` in order as the attempts; returns the result, notices and waits
+    fn run(outcomes: Vec<Result<&'static str, &'static str>>) -> (Result<&'static str, GitError>, Vec<String>, Vec<Duration>) {
+        let policy = RetryPolicy { max_attempts: 3, initial_delay: Duration::from_secs(2) };
+        let mut outcomes = outcomes.into_iter();
+        let notices = RefCell::new(Vec::new());
+        let mut waits = Vec::new();
+        let result = retry_with(
+            "push",
+            &policy,
+            || outcomes.next().unwrap().map_err(str::to_string),
+            &|notice| notices.borrow_mut().push(notice.summary()),
+            |delay| waits.push(delay),
+        );
+        (result, notices.into_inner(), waits)
+    }
+
+    #[test]
+    fn retries_transient_failures_until_one_succeeds() {
+        let (result, notices, waits) = run(vec![
+            Err("fatal: the remote end hung up unexpectedly"),
+            Err("Connection reset by peer"),
+            Ok("pushed"),
+        ]);
+        assert_eq!(result.unwrap(), "pushed");
+        assert_eq!(notices, vec!["push attempt 2/3 in 2s", "push attempt 3/3 in 4s"]);
+        assert_eq!(waits, vec![Duration::from_secs(2), Duration::from_secs(4)]);
+    }
+
+    #[test]
+    fn permanent_failures_and_exhausted_retries_stop() {
+        let (result, notices, _) = run(vec![Err("Connection reset by peer"), Err("fatal: Authentication failed")]);
+        let Err(GitError::Network { reason, attempts, .. }) = result else { panic!("expected a network error") };
+        assert_eq!((reason, attempts), (NetworkFailureKind::AuthenticationFailed, 2));
+        assert_eq!(notices.len(), 1);
+
+        let (result, notices, _) = run(vec![Err("early EOF"), Err("early EOF"), Err("early EOF")]);
+        let Err(GitError::Network { reason, attempts, .. }) = result else { panic!("expected a network error") };
+        assert_eq!((reason, attempts), (NetworkFailureKind::EarlyEof, 3));
+        assert_eq!(notices.len(), 2);
+    }
+}
@@ -37,10 +37,283 @@ pub fn canonicalize_for_storage(path: &Path) -> PathBuf {
     normalize_path(&canonical)
 }
 
+/// A file reference found in terminal output, e.g. `src/lib/foo.ts:42:7`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathLocation {
+    pub path: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Parse a clicked file reference. Supports `path:line:col`, `path:line`,
+/// `path(line,col)`, `path(line)` and plain paths. Surrounding quotes/brackets
+/// and trailing punctuation are ignored. Colons that aren't followed by a
+/// number (drive letters, colons in file names) stay part of the path.
+pub fn parse_path_location(text: &str) -> Option<PathLocation> {
+    let text = trim_clicked_text(text);
+
+    // path(line,col) / path(line)
+    if let Some(inner) = text.strip_suffix(')') {
+        if let Some((path, numbers)) = inner.rsplit_once('(') {
+            let mut parts = numbers.split(',').map(|n| n.trim().parse::<u32>());
+            if let Some(Ok(line)) = parts.next() {
+                let column = match parts.next() {
+                    Some(Ok(col)) => Some(col),
+                    Some(Err(_)) => return location(text, None, None),
+                    None => None,
+                };
+                if parts.next().is_none() {
+                    return location(path, Some(line), column);
+                }
+            }
+        }
+    }
+
+    // path:line:col / path:line
+    let mut path = text;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((rest, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                let Ok(n) = n.parse::<u32>() else { break };
+                numbers.push(n);
+                path = rest;
+            }
+            _ => break,
+        }
+    }
+    numbers.reverse();
+    location(path, numbers.first().copied(), numbers.get(1).copied())
+}
+
+/// Strip wrapping quotes/brackets and sentence punctuation around a clicked path
+fn trim_clicked_text(text: &str) -> &str {
+    let mut text = text.trim();
+    loop {
+        let trimmed = text
+            .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '<' | '>' | '[' | ']'))
+            .trim_end_matches([',', ';', ':']);
+        // A single trailing period ends a sentence, but keep "." and ".." intact
+        let trimmed = match trimmed.strip_suffix('.') {
+            Some(rest) if !rest.is_empty() && !rest.ends_with(['.', '/', '\\']) => rest,
+            _ => trimmed,
+        };
+        if trimmed == text {
+            return text;
+        }
+        text = trimmed;
+    }
+}
+
+fn location(path: &str, line: Option<u32>, column: Option<u32>) -> Option<PathLocation> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(PathLocation { path: path.to_string(), line, column })
+}
+
+/// Resolve `path` against `base` and make sure the result exists and stays
+/// inside `root` (after resolving symlinks and `..`).
+pub fn resolve_within_root(path: &str, base: &Path, root: &Path) -> Result<PathBuf, String> {
+    let candidate = Path::new(path);
+    let joined = if candidate.is_absolute() {
+        candidate.to_path_buf()
+    } else {
+        base.join(candidate)
+    };
+
+    let resolved = joined
+        .canonicalize()
+        .map_err(|_| format!("Path does not exist: {}", joined.display()))?;
+    let root = root
+        .canonicalize()
+        .map_err(|_| format!("Worktree does not exist: {}", root.display()))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!("Path is outside the worktree: {}", path));
+    }
+
+    Ok(normalize_path(&resolved))
+}
+
+/// Glob patterns matched against repo-relative paths, as used by
+/// `worktree.copy.except` and `changes.hidePatterns`. A pattern matches a path
+/// or any directory above it, so `dist` (or `dist/`) covers everything inside.
+/// `*` also crosses `/`, like `**`. With `new`, case follows the platform's
+/// usual filesystem: insensitive on macOS and Windows. `copy.except` always
+/// matches case-sensitively, as it did before sharing this matcher.
+#[derive(Debug, Clone, Default)]
+pub struct PathMatcher {
+    patterns: Vec<glob::Pattern>,
+    case_sensitive: bool,
+}
+
+impl PathMatcher {
+    /// Invalid patterns are skipped; config validation reports them
+    pub fn new(patterns: &[String]) -> Self {
+        Self::with_case_sensitivity(patterns, !cfg!(any(windows, target_os = "macos")))
+    }
+
+    pub fn with_case_sensitivity(patterns: &[String], case_sensitive: bool) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .filter_map(|p| glob::Pattern::new(p.trim_end_matches('/')).ok())
+                .collect(),
+            case_sensitive,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: self.case_sensitive,
+            ..Default::default()
+        };
+        let path = path.replace('\\', "/");
+        let path = path.trim_end_matches('/');
+        // The path itself, then each parent directory
+        let candidates = path
+            .match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .chain(std::iter::once(path));
+        candidates
+            .into_iter()
+            .any(|candidate| self.patterns.iter().any(|p| p.matches_with(candidate, options)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn matcher(patterns: &[&str], case_sensitive: bool) -> PathMatcher {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PathMatcher::with_case_sensitivity(&patterns, case_sensitive)
+    }
+
+    #[test]
+    fn path_matcher_covers_directory_contents() {
+        let m = matcher(&[".claude", "dist/", "snapshots/*.snap"], true);
+        assert!(m.matches(".claude"));
+        assert!(m.matches(".claude/settings.json"));
+        assert!(m.matches("dist/app/index.js"));
+        assert!(m.matches("dist/"));
+        assert!(m.matches("snapshots/a.snap"));
+        assert!(!m.matches("src/.claudefile"));
+        assert!(!m.matches("distribution/a.js"));
+        assert!(!PathMatcher::new(&[]).matches("anything"));
+    }
+
+    #[test]
+    fn path_matcher_supports_double_star_globs() {
+        let m = matcher(&["**/package-lock.json", "**/__snapshots__", "*.lock"], true);
+        assert!(m.matches("package-lock.json"));
+        assert!(m.matches("web/app/package-lock.json"));
+        assert!(m.matches("src/__snapshots__/button.test.ts.snap"));
+        assert!(m.matches("Cargo.lock"));
+        assert!(m.matches("crates/core/Cargo.lock"));
+        assert!(!m.matches("src/lockfile.rs"));
+        // Windows separators are normalized
+        assert!(m.matches("web\\package-lock.json"));
+    }
+
+    #[test]
+    fn path_matcher_keeps_copy_except_semantics() {
+        // What the copy.except matcher did before it was shared: glob matches
+        // where `*` crosses `/`, plus everything under a literal directory
+        let m = matcher(&["*.log", ".claude", "node_modules"], true);
+        assert!(m.matches("debug.log"));
+        assert!(m.matches("logs/app/debug.log"));
+        assert!(m.matches(".claude/settings.json"));
+        assert!(m.matches("node_modules/pkg/index.js"));
+        assert!(!m.matches("src/node_modules_helper.js"));
+        assert!(!m.matches("Debug.LOG"));
+    }
+
+    #[test]
+    fn path_matcher_case_sensitivity() {
+        assert!(!matcher(&["Cargo.lock"], true).matches("cargo.lock"));
+        assert!(matcher(&["Cargo.lock"], false).matches("cargo.lock"));
+        assert!(matcher(&["DIST"], false).matches("dist/app.js"));
+        let platform_insensitive = cfg!(any(windows, target_os = "macos"));
+        let m = PathMatcher::new(&["README.md".to_string()]);
+        assert_eq!(m.matches("readme.md"), platform_insensitive);
+    }
+
+    fn loc(path: &str, line: Option<u32>, column: Option<u32>) -> Option<PathLocation> {
+        Some(PathLocation { path: path.to_string(), line, column })
+    }
+
+    #[test]
+    fn parse_path_location_plain_path() {
+        assert_eq!(parse_path_location("src/lib/foo.ts"), loc("src/lib/foo.ts", None, None));
+        assert_eq!(parse_path_location("  ./README.md  "), loc("./README.md", None, None));
+        assert_eq!(parse_path_location(""), None);
+        assert_eq!(parse_path_location(":12"), None);
+        assert_eq!(parse_path_location(".."), loc("..", None, None));
+        assert_eq!(parse_path_location("README.md."), loc("README.md", None, None));
+    }
+
+    #[test]
+    fn parse_path_location_colon_forms() {
+        assert_eq!(parse_path_location("src/lib/foo.ts:42"), loc("src/lib/foo.ts", Some(42), None));
+        assert_eq!(parse_path_location("src/lib/foo.ts:42:7"), loc("src/lib/foo.ts", Some(42), Some(7)));
+        // Trailing colon from compiler output like "foo.rs:3:9: error"
+        assert_eq!(parse_path_location("src/main.rs:3:9:"), loc("src/main.rs", Some(3), Some(9)));
+        // Only the last two numbers are a location
+        assert_eq!(parse_path_location("a:1:2:3"), loc("a:1", Some(2), Some(3)));
+    }
+
+    #[test]
+    fn parse_path_location_paren_forms() {
+        assert_eq!(parse_path_location("Foo.cs(10,5)"), loc("Foo.cs", Some(10), Some(5)));
+        assert_eq!(parse_path_location("Foo.cs(10)"), loc("Foo.cs", Some(10), None));
+        assert_eq!(parse_path_location("Foo.cs(10, 5)"), loc("Foo.cs", Some(10), Some(5)));
+        // Not a location: parenthesised text is part of the name
+        assert_eq!(parse_path_location("notes (draft).md"), loc("notes (draft).md", None, None));
+        assert_eq!(parse_path_location("fn(a,b)"), loc("fn(a,b)", None, None));
+    }
+
+    #[test]
+    fn parse_path_location_windows_drive_letters() {
+        assert_eq!(parse_path_location(r"C:\repo\src\main.rs"), loc(r"C:\repo\src\main.rs", None, None));
+        assert_eq!(parse_path_location(r"C:\repo\src\main.rs:12"), loc(r"C:\repo\src\main.rs", Some(12), None));
+        assert_eq!(parse_path_location(r"C:\repo\main.rs:12:4"), loc(r"C:\repo\main.rs", Some(12), Some(4)));
+        assert_eq!(parse_path_location(r"D:/repo/Foo.cs(3,1)"), loc(r"D:/repo/Foo.cs", Some(3), Some(1)));
+    }
+
+    #[test]
+    fn parse_path_location_paths_with_colons_and_wrapping() {
+        assert_eq!(parse_path_location("docs/a:b.md"), loc("docs/a:b.md", None, None));
+        assert_eq!(parse_path_location("docs/a:b.md:5"), loc("docs/a:b.md", Some(5), None));
+        assert_eq!(parse_path_location("`src/app.tsx:8`"), loc("src/app.tsx", Some(8), None));
+        assert_eq!(parse_path_location("\"src/app.tsx\","), loc("src/app.tsx", None, None));
+        assert_eq!(parse_path_location("[src/app.tsx:8:2]."), loc("src/app.tsx", Some(8), Some(2)));
+    }
+
+    #[test]
+    fn resolve_within_root_rejects_traversal_and_missing_files() {
+        let root = std::env::temp_dir().join(format!("shellflow-resolve-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src").join("main.rs"), "").unwrap();
+        let src = root.join("src");
+
+        let resolved = resolve_within_root("main.rs", &src, &root).unwrap();
+        assert_eq!(resolved, root.canonicalize().unwrap().join("src").join("main.rs"));
+        assert!(resolve_within_root("src/main.rs", &root, &root).is_ok());
+        assert!(resolve_within_root("../src/main.rs", &src, &root).is_ok());
+
+        let outside = resolve_within_root("../..", &src, &root).unwrap_err();
+        assert!(outside.contains("outside"), "{}", outside);
+        let missing = resolve_within_root("nope.rs", &src, &root).unwrap_err();
+        assert!(missing.contains("does not exist"), "{}", missing);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     #[cfg(windows)]
     #[test]
     fn normalize_path_strips_verbatim_prefix() {
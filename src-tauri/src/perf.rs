@@ -0,0 +1,184 @@
+//! Command timing for performance debugging.
+//!
+//! With `perf.enabled`, every command the frontend invokes has its duration
+//! recorded in a fixed-size ring buffer, so a slowness report can come with
+//! numbers ("create_worktree p95 is 8s") instead of impressions. Commands run
+//! inline in the invoke handler, so timing the handler times the command;
+//! commands that hand their work to a thread are timed up to the hand-off.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::Runtime;
+
+/// Most recent command runs kept; older ones are dropped first
+pub const SAMPLE_CAPACITY: usize = 4096;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref SAMPLES: Mutex<Samples> = Mutex::new(Samples::new(SAMPLE_CAPACITY));
+}
+
+/// Turn recording on or off. Turning it off keeps what was recorded.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record one run of `command`, if recording is on
+pub fn record(command: &str, duration: Duration) {
+    if is_enabled() {
+        SAMPLES.lock().push(command, duration);
+    }
+}
+
+/// Wrap an invoke handler (as built by `tauri::generate_handler!`) so each
+/// command it runs is recorded. Costs one atomic load per command while off.
+pub fn timed<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if !is_enabled() {
+            return handler(invoke);
+        }
+        let command = invoke.message.command().to_string();
+        let start = Instant::now();
+        let handled = handler(invoke);
+        if handled {
+            record(&command, start.elapsed());
+        }
+        handled
+    }
+}
+
+/// Durations of one command over the recorded runs
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandStats {
+    pub command: String,
+    pub calls: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Result of `get_perf_stats`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfStats {
+    pub enabled: bool,
+    /// Runs currently held; at most `SAMPLE_CAPACITY`
+    pub samples: usize,
+    /// Slowest p95 first
+    pub commands: Vec<CommandStats>,
+}
+
+/// A ring buffer of (command, duration) runs
+struct Samples {
+    capacity: usize,
+    runs: VecDeque<(String, Duration)>,
+}
+
+impl Samples {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            runs: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, command: &str, duration: Duration) {
+        if self.runs.len() == self.capacity {
+            self.runs.pop_front();
+        }
+        self.runs.push_back((command.to_string(), duration));
+    }
+
+    fn stats(&self) -> Vec<CommandStats> {
+        let mut by_command: HashMap<&str, Vec<Duration>> = HashMap::new();
+        for (command, duration) in &self.runs {
+            by_command.entry(command).or_default().push(*duration);
+        }
+        let mut stats: Vec<CommandStats> = by_command
+            .into_iter()
+            .map(|(command, mut durations)| {
+                durations.sort();
+                CommandStats {
+                    command: command.to_string(),
+                    calls: durations.len(),
+                    p50_ms: as_ms(percentile(&durations, 50)),
+                    p95_ms: as_ms(percentile(&durations, 95)),
+                    max_ms: as_ms(*durations.last().unwrap_or(&Duration::ZERO)),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| b.p95_ms.total_cmp(&a.p95_ms).then_with(|| a.command.cmp(&b.command)));
+        stats
+    }
+}
+
+/// Nearest-rank percentile of sorted `durations`
+fn percentile(durations: &[Duration], pct: usize) -> Duration {
+    if durations.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (pct * durations.len()).div_ceil(100).max(1);
+    durations[rank - 1]
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Per-command counts and durations of the recorded runs
+pub fn stats() -> PerfStats {
+    let samples = SAMPLES.lock();
+    PerfStats {
+        enabled: is_enabled(),
+        samples: samples.runs.len(),
+        commands: samples.stats(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let durations: Vec<Duration> = (1..=20).map(ms).collect();
+        assert_eq!(percentile(&durations, 50), ms(10));
+        assert_eq!(percentile(&durations, 95), ms(19));
+        assert_eq!(percentile(&[ms(7)], 95), ms(7));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_latest_runs_and_groups_them_by_command() {
+        let mut samples = Samples::new(4);
+        samples.push("list_projects", ms(100));
+        samples.push("create_worktree", ms(8000));
+        samples.push("create_worktree", ms(2000));
+        samples.push("list_projects", ms(1));
+        samples.push("list_projects", ms(3));
+
+        // The first list_projects run was dropped
+        assert_eq!(samples.runs.len(), 4);
+        let stats = samples.stats();
+        assert_eq!(stats[0].command, "create_worktree");
+        assert_eq!((stats[0].calls, stats[0].p50_ms, stats[0].max_ms), (2, 2000.0, 8000.0));
+        assert_eq!(stats[1].command, "list_projects");
+        assert_eq!((stats[1].calls, stats[1].p50_ms, stats[1].p95_ms), (2, 1.0, 3.0));
+    }
+}
@@ -0,0 +1,298 @@
+//! Bulk project import.
+//!
+//! Scans a directory such as `~/code` for git repositories so they can be
+//! added in one go instead of one dialog each. The walk is breadth-first with
+//! bounded depth and a cap on directories visited; symlinked directories are
+//! followed once by their resolved path, so loops end. Hidden directories,
+//! `node_modules` and the insides of repositories are never descended into.
+
+use crate::git;
+use crate::path_utils;
+use crate::state::Project;
+use crate::worktree;
+use git2::Repository;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Directories visited before a scan stops and reports truncation
+pub const MAX_SCAN_DIRS: usize = 10_000;
+/// Deepest `max_depth` a scan accepts
+pub const MAX_SCAN_DEPTH: usize = 8;
+
+const SKIPPED_DIRS: &[&str] = &["node_modules"];
+
+/// A repository found by a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectCandidate {
+    pub name: String,
+    pub path: String,
+    pub default_branch: Option<String>,
+    /// Unix seconds of the commit at HEAD; None for a repository without commits
+    pub last_commit_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScan {
+    /// Sorted by path
+    pub candidates: Vec<ProjectCandidate>,
+    pub visited_dirs: usize,
+    /// The directory cap was hit before the walk finished
+    pub truncated: bool,
+}
+
+/// Find repositories under `root`, up to `max_depth` levels below it.
+/// Repositories whose storage path is in `registered` are left out.
+pub fn scan_for_projects(
+    root: &Path,
+    max_depth: usize,
+    registered: &HashSet<PathBuf>,
+    dir_limit: usize,
+) -> std::io::Result<ProjectScan> {
+    // Fails early on a missing or unreadable root, rather than an empty result
+    std::fs::read_dir(root)?;
+
+    let max_depth = max_depth.min(MAX_SCAN_DEPTH);
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut queue: VecDeque<(PathBuf, usize)> = VecDeque::from([(root.to_path_buf(), 0)]);
+    let mut candidates = Vec::new();
+    let mut visited_dirs = 0;
+    let mut truncated = false;
+
+    while let Some((dir, depth)) = queue.pop_front() {
+        // Symlinks resolve to a directory already seen, which ends loops
+        let Ok(resolved) = std::fs::canonicalize(&dir) else { continue };
+        if !seen.insert(resolved) {
+            continue;
+        }
+        if visited_dirs >= dir_limit {
+            truncated = true;
+            break;
+        }
+        visited_dirs += 1;
+
+        if dir.join(".git").exists() {
+            let key = path_utils::canonicalize_for_storage(&dir);
+            if !registered.contains(&key) {
+                if let Some(candidate) = describe_repository(&dir) {
+                    candidates.push(candidate);
+                }
+            }
+            continue;
+        }
+        if depth >= max_depth {
+            continue;
+        }
+
+        // Unreadable directories are skipped; the rest of the tree still counts
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        let mut children: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref())
+            })
+            .map(|entry| entry.path())
+            // Follows symlinks, so linked directories are walked too
+            .filter(|path| path.is_dir())
+            .collect();
+        children.sort();
+        queue.extend(children.into_iter().map(|child| (child, depth + 1)));
+    }
+
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ProjectScan { candidates, visited_dirs, truncated })
+}
+
+fn describe_repository(path: &Path) -> Option<ProjectCandidate> {
+    let repo = Repository::open(path).ok()?;
+    let last_commit_at = repo
+        .head()
+        .ok()
+        .and_then(|head| head.peel_to_commit().ok())
+        .map(|commit| commit.time().seconds());
+    Some(ProjectCandidate {
+        name: git::get_repo_name(path),
+        path: path_utils::normalize_path_string(&path_utils::canonicalize_for_storage(path)),
+        default_branch: git::get_default_branch(&repo).ok(),
+        last_commit_at,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkAddStatus {
+    Added,
+    NotARepository,
+    /// Already registered, or listed twice
+    Duplicate,
+    PermissionDenied,
+}
+
+/// Outcome for one path of `add_projects_bulk`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkAddResult {
+    pub path: String,
+    pub status: BulkAddStatus,
+    pub project: Option<Project>,
+    pub message: Option<String>,
+}
+
+/// Register every path in `paths` that is a new repository, appended after
+/// the existing projects in the given order. Failures don't stop the rest.
+pub fn register_projects(projects: &mut Vec<Project>, paths: &[String]) -> Vec<BulkAddResult> {
+    let mut registered: HashSet<PathBuf> = projects
+        .iter()
+        .map(|p| path_utils::canonicalize_for_storage(Path::new(&p.path)))
+        .collect();
+    let mut next_order = projects.iter().map(|p| p.order + 1).max().unwrap_or(0);
+
+    paths
+        .iter()
+        .map(|path| {
+            let failed = |status, message: String| BulkAddResult {
+                path: path.clone(),
+                status,
+                project: None,
+                message: Some(message),
+            };
+            let canonical = path_utils::canonicalize_for_storage(Path::new(path));
+
+            if let Err(e) = std::fs::read_dir(&canonical) {
+                return match e.kind() {
+                    std::io::ErrorKind::PermissionDenied => failed(BulkAddStatus::PermissionDenied, e.to_string()),
+                    _ => failed(BulkAddStatus::NotARepository, e.to_string()),
+                };
+            }
+            if !registered.insert(canonical.clone()) {
+                return failed(BulkAddStatus::Duplicate, format!("{} is already a project", path));
+            }
+
+            match worktree::create_project(&canonical) {
+                Ok(mut project) => {
+                    project.order = next_order;
+                    next_order += 1;
+                    projects.push(project.clone());
+                    BulkAddResult {
+                        path: path.clone(),
+                        status: BulkAddStatus::Added,
+                        project: Some(project),
+                        message: None,
+                    }
+                }
+                Err(e) => {
+                    registered.remove(&canonical);
+                    failed(BulkAddStatus::NotARepository, e.to_string())
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn init_repo(path: &Path) {
+        std::fs::create_dir_all(path).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git").args(args).current_dir(path).output().unwrap().status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "-q", "-b", "main"]);
+        git(&["-c", "user.name=shellflow", "-c", "user.email=shellflow@example.com", "commit", "-q", "--allow-empty", "-m", "init"]);
+    }
+
+    /// root/{app, org/lib, org/deep/x/y/z/far, plain/docs, .hidden/secret, node_modules/dep}
+    fn tree() -> PathBuf {
+        let root = path_utils::canonicalize_for_storage(&std::env::temp_dir())
+            .join(format!("shellflow-scan-{}", uuid::Uuid::new_v4()));
+        init_repo(&root.join("app"));
+        init_repo(&root.join("org").join("lib"));
+        init_repo(&root.join("org").join("deep").join("x").join("y").join("z").join("far"));
+        std::fs::create_dir_all(root.join("plain").join("docs")).unwrap();
+        init_repo(&root.join(".hidden").join("secret"));
+        init_repo(&root.join("node_modules").join("dep"));
+        // A directory inside a repository isn't scanned for more repositories
+        init_repo(&root.join("app").join("vendor").join("inner"));
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("plain").join("loop")).unwrap();
+        root
+    }
+
+    fn names(scan: &ProjectScan) -> Vec<&str> {
+        scan.candidates.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_nested_repositories_within_depth() {
+        let root = tree();
+        let scan = scan_for_projects(&root, 3, &HashSet::new(), MAX_SCAN_DIRS).unwrap();
+        assert_eq!(names(&scan), vec!["app", "lib"]);
+        assert!(!scan.truncated);
+        assert_eq!(scan.candidates[0].default_branch.as_deref(), Some("main"));
+        assert!(scan.candidates[0].last_commit_at.is_some());
+
+        let deep = scan_for_projects(&root, 6, &HashSet::new(), MAX_SCAN_DIRS).unwrap();
+        assert_eq!(names(&deep), vec!["app", "far", "lib"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn skips_registered_projects_and_caps_the_walk() {
+        let root = tree();
+        let registered = HashSet::from([path_utils::canonicalize_for_storage(&root.join("app"))]);
+        let scan = scan_for_projects(&root, 3, &registered, MAX_SCAN_DIRS).unwrap();
+        assert_eq!(names(&scan), vec!["lib"]);
+
+        let capped = scan_for_projects(&root, 3, &HashSet::new(), 2).unwrap();
+        assert!(capped.truncated);
+        assert_eq!(capped.visited_dirs, 2);
+        assert_eq!(names(&capped), vec!["app"]);
+
+        assert!(scan_for_projects(&root.join("missing"), 3, &HashSet::new(), MAX_SCAN_DIRS).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn bulk_add_reports_each_path() {
+        let root = tree();
+        let app = root.join("app").to_string_lossy().to_string();
+        let lib = root.join("org").join("lib").to_string_lossy().to_string();
+        let mut projects = vec![worktree::create_project(Path::new(&lib)).unwrap()];
+        projects[0].order = 4;
+
+        let results = register_projects(
+            &mut projects,
+            &[
+                app.clone(),
+                app.clone(),
+                lib,
+                root.join("plain").to_string_lossy().to_string(),
+                root.join("missing").to_string_lossy().to_string(),
+            ],
+        );
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BulkAddStatus::Added,
+                BulkAddStatus::Duplicate,
+                BulkAddStatus::Duplicate,
+                BulkAddStatus::NotARepository,
+                BulkAddStatus::NotARepository,
+            ]
+        );
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[1].name, "app");
+        assert_eq!(projects[1].order, 5);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,37 +1,28 @@
-use crate::state::{AppState, PtySession};
+use crate::config::TerminalConfig;
+use crate::events::{self, PtyExit, PtyOutput, PtyReady, ShutdownProgress, TaskStructuredEvent};
+use crate::git;
+use crate::path_utils;
+use crate::state::{AppState, PtySession, TaskResult, MAIN_RESULT_KEY};
+use crate::which;
 use parking_lot::Mutex;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use thiserror::Error;
 use uuid::Uuid;
 
 #[cfg(unix)]
 use std::process::Command;
 
-#[cfg(windows)]
-fn find_in_path(candidates: &[&str]) -> Option<String> {
-    let path = std::env::var_os("PATH")?;
-    for name in candidates {
-        for dir in std::env::split_paths(&path) {
-            let full = dir.join(name);
-            if full.is_file() {
-                return Some(full.to_string_lossy().to_string());
-            }
-        }
-    }
-    None
-}
-
 #[cfg(windows)]
 fn find_windows_shell() -> String {
     let preferred = ["pwsh.exe", "pwsh", "powershell.exe", "powershell", "cmd.exe", "cmd"];
-    if let Some(found) = find_in_path(&preferred) {
+    if let Some(found) = which::find_in_user_path(&preferred) {
         return found;
     }
 
@@ -135,6 +126,16 @@ pub enum PtyError {
     Io(#[from] std::io::Error),
     #[error("Session not found: {0}")]
     SessionNotFound(String),
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+    #[error("Session limit reached: {current} of {limit} terminals are running")]
+    SessionLimitReached { limit: usize, current: usize },
+    #[error("Session limit reached for {worktree_id}: {current} of {limit} terminals are running")]
+    WorktreeSessionLimitReached {
+        worktree_id: String,
+        limit: usize,
+        current: usize,
+    },
 }
 
 impl From<Box<dyn std::error::Error + Send + Sync>> for PtyError {
@@ -149,10 +150,118 @@ impl From<anyhow::Error> for PtyError {
     }
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct PtyOutput {
-    pub pty_id: String,
-    pub data: String,
+/// Bytes of recent output kept per session for replay on attach
+pub const OUTPUT_BUFFER_BYTES: usize = 256 * 1024;
+
+/// Bounded tail of a session's output
+pub(crate) struct OutputBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    truncated: bool,
+}
+
+impl OutputBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::new(),
+            capacity,
+            truncated: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, output: &str) {
+        let bytes = output.as_bytes();
+        if bytes.len() >= self.capacity {
+            self.data.clear();
+            self.data.extend(&bytes[bytes.len() - self.capacity..]);
+            self.truncated = true;
+            return;
+        }
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        if overflow > 0 {
+            self.data.drain(..overflow);
+            self.truncated = true;
+        }
+        self.data.extend(bytes);
+    }
+
+    /// Keep up to `capacity` bytes from now on; shrinking drops the oldest output
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let overflow = self.data.len().saturating_sub(capacity);
+        if overflow > 0 {
+            self.data.drain(..overflow);
+            self.truncated = true;
+        }
+    }
+
+    /// The buffered output as text. Once older output has been dropped the
+    /// replay starts at the next line so it doesn't begin mid-character or
+    /// mid-escape-sequence.
+    pub(crate) fn snapshot(&self) -> String {
+        let bytes: Vec<u8> = self.data.iter().copied().collect();
+        let start = if self.truncated {
+            bytes
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|i| i + 1)
+                // No newline to align to: at least skip a split character
+                .unwrap_or_else(|| bytes.iter().take_while(|&&b| b & 0xC0 == 0x80).count())
+        } else {
+            0
+        };
+        String::from_utf8_lossy(&bytes[start..]).into_owned()
+    }
+}
+
+/// Longest output line `JsonLineScanner` will try to parse
+pub const MAX_STRUCTURED_LINE_BYTES: usize = 8 * 1024;
+
+/// Picks `{"shellflow": ...}` JSON objects out of a task's output, one per
+/// line. Output arrives in arbitrary chunks, so a partial line is carried over
+/// to the next `feed`.
+#[derive(Default)]
+pub(crate) struct JsonLineScanner {
+    partial: String,
+    /// The current line passed the size cap and is being skipped to its end
+    oversized: bool,
+}
+
+impl JsonLineScanner {
+    /// Structured events completed by `output`. Lines that aren't JSON objects
+    /// with a `shellflow` key are ignored.
+    pub(crate) fn feed(&mut self, output: &str) -> Vec<serde_json::Value> {
+        let mut events = Vec::new();
+        let mut rest = output;
+        while let Some(newline) = rest.find('\n') {
+            let (line, next) = rest.split_at(newline);
+            rest = &next[1..];
+            if !self.oversized && self.partial.len() + line.len() <= MAX_STRUCTURED_LINE_BYTES {
+                self.partial.push_str(line);
+                events.extend(Self::parse(&self.partial));
+            }
+            self.partial.clear();
+            self.oversized = false;
+        }
+        if !self.oversized {
+            if self.partial.len() + rest.len() > MAX_STRUCTURED_LINE_BYTES {
+                self.partial.clear();
+                self.oversized = true;
+            } else {
+                self.partial.push_str(rest);
+            }
+        }
+        events
+    }
+
+    fn parse(line: &str) -> Option<serde_json::Value> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return None;
+        }
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        value.get("shellflow").is_some().then_some(value)
+    }
 }
 
 // Thread-safe writer wrapper
@@ -171,6 +280,13 @@ impl PtyWriter {
 lazy_static::lazy_static! {
     static ref PTY_WRITERS: Mutex<HashMap<String, Arc<Mutex<PtyWriter>>>> = Mutex::new(HashMap::new());
     static ref PTY_MASTERS: Mutex<HashMap<String, Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>>> = Mutex::new(HashMap::new());
+    static ref PTY_OUTPUT: Mutex<HashMap<String, Arc<Mutex<OutputBuffer>>>> = Mutex::new(HashMap::new());
+    // Unix ms of each session's last output or input
+    static ref PTY_ACTIVITY: Mutex<HashMap<String, Arc<AtomicU64>>> = Mutex::new(HashMap::new());
+    // Terminal size of the viewer currently attached to a session
+    static ref PTY_VIEWERS: Mutex<HashMap<String, (u16, u16)>> = Mutex::new(HashMap::new());
+    // Files to delete when a session's process exits; present while it runs
+    static ref PTY_EXIT_FILES: Mutex<HashMap<String, Vec<std::path::PathBuf>>> = Mutex::new(HashMap::new());
     // Cache the user's PATH to avoid spawning shell on every PTY creation
     static ref CACHED_USER_PATH: Mutex<Option<String>> = Mutex::new(None);
     // Cache the user's shell
@@ -179,6 +295,24 @@ lazy_static::lazy_static! {
     pub(crate) static ref SHUTDOWN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
 }
 
+/// Drop the global handles (writer, master, output buffer, activity, viewer) of a session
+fn release_pty_handles(pty_id: &str) {
+    PTY_WRITERS.lock().remove(pty_id);
+    PTY_MASTERS.lock().remove(pty_id);
+    PTY_OUTPUT.lock().remove(pty_id);
+    PTY_ACTIVITY.lock().remove(pty_id);
+    PTY_VIEWERS.lock().remove(pty_id);
+}
+
+/// Unix ms of the last output or input of every running session
+pub fn session_activity() -> HashMap<String, u64> {
+    PTY_ACTIVITY
+        .lock()
+        .iter()
+        .map(|(id, at)| (id.clone(), at.load(Ordering::Relaxed)))
+        .collect()
+}
+
 /// Get the user's PATH, using cached value if available.
 /// This runs the user's login shell to get their actual PATH,
 /// which may differ from the process environment.
@@ -204,8 +338,12 @@ fn get_cached_user_shell() -> String {
         find_windows_shell()
     };
 
+    // A $SHELL that no longer exists (e.g. uninstalled) falls back to sh
     #[cfg(not(windows))]
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let shell = std::env::var("SHELL")
+        .ok()
+        .and_then(|shell| which::find_in_user_path(&[&shell]))
+        .unwrap_or_else(|| "/bin/sh".to_string());
 
     *cache = Some(shell.clone());
     shell
@@ -225,7 +363,13 @@ pub fn spawn_pty(
     rows: Option<u16>,
     shell_override: Option<&str>,
     env_vars: Option<&std::collections::HashMap<String, String>>,
+    result_key: Option<&str>,
+    structured_task: Option<&str>,
+    limits: &TerminalConfig,
 ) -> Result<String, PtyError> {
+    // Refuse before opening the pty pair so a denial leaves nothing behind
+    check_session_limits(state, worktree_id, limits)?;
+
     let pty_system = native_pty_system();
 
     let pair = pty_system.openpty(PtySize {
@@ -436,9 +580,18 @@ pub fn spawn_pty(
     let session = Arc::new(PtySession {
         worktree_id: worktree_id.to_string(),
         child_pid,
+        worktree_path: worktree_path.to_string(),
+        // Task sessions record their results under the task name
+        task_name: result_key.filter(|key| *key != MAIN_RESULT_KEY).map(str::to_string),
     });
     state.pty_sessions.write().insert(pty_id.clone(), session);
 
+    let output_buffer = Arc::new(Mutex::new(OutputBuffer::new(OUTPUT_BUFFER_BYTES)));
+    PTY_OUTPUT.lock().insert(pty_id.clone(), output_buffer.clone());
+    let activity = Arc::new(AtomicU64::new(crate::status::now_unix_ms()));
+    PTY_ACTIVITY.lock().insert(pty_id.clone(), activity.clone());
+    PTY_EXIT_FILES.lock().insert(pty_id.clone(), Vec::new());
+
     // Spawn reader thread
     let app_handle = app.clone();
     let pty_id_clone = pty_id.clone();
@@ -447,6 +600,9 @@ pub fn spawn_pty(
     let ready_emitted = Arc::new(AtomicBool::new(false));
     let ready_emitted_clone = ready_emitted.clone();
     let child_pid_for_cleanup = child_pid;
+    let result_key = result_key.map(|k| k.to_string());
+    // Tasks using the jsonlines protocol also get their output scanned for events
+    let mut structured = structured_task.map(|name| (name.to_string(), JsonLineScanner::default()));
 
     thread::spawn(move || {
         eprintln!("[PTY:{}] Reader thread started", pty_id_clone);
@@ -474,6 +630,7 @@ pub fn spawn_pty(
                     consecutive_empty = 0;
                     read_count += 1;
                     total_bytes += n;
+                    activity.store(crate::status::now_unix_ms(), Ordering::Relaxed);
                     if read_count <= 5 || read_count % 100 == 0 {
                         eprintln!("[PTY:{}] Read {} bytes (total: {})", pty_id_clone, n, total_bytes);
                         // Debug: show first read content
@@ -487,10 +644,10 @@ pub fn spawn_pty(
                     if !ready_emitted_clone.load(Ordering::SeqCst) && total_bytes > 50 {
                         ready_emitted_clone.store(true, Ordering::SeqCst);
                         eprintln!("[PTY:{}] Emitting pty-ready event for worktree {}", pty_id_clone, worktree_id_clone);
-                        let _ = app_handle.emit("pty-ready", serde_json::json!({
-                            "ptyId": pty_id_clone,
-                            "worktreeId": worktree_id_clone,
-                        }));
+                        let _ = app_handle.emit(events::PTY_READY, PtyReady {
+                            pty_id: pty_id_clone.clone(),
+                            worktree_id: worktree_id_clone.clone(),
+                        });
                     }
 
                     // Combine any leftover bytes with new data
@@ -508,13 +665,33 @@ pub fn spawn_pty(
                             std::str::from_utf8_unchecked(&utf8_buf[..valid_up_to])
                         }.to_string();
 
+                        // Structured events ride along with the raw output
+                        if let Some((task_name, scanner)) = structured.as_mut() {
+                            for payload in scanner.feed(&data) {
+                                let _ = app_handle.emit(
+                                    events::TASK_STRUCTURED_EVENT,
+                                    TaskStructuredEvent {
+                                        pty_id: pty_id_clone.clone(),
+                                        worktree_id: worktree_id_clone.clone(),
+                                        task_name: task_name.clone(),
+                                        payload,
+                                    },
+                                );
+                            }
+                        }
+
+                        // Buffer and emit under one lock so an attach replay can't interleave
+                        let mut output = output_buffer.lock();
+                        output.push(&data);
                         let _ = app_handle.emit(
-                            "pty-output",
+                            events::PTY_OUTPUT,
                             PtyOutput {
                                 pty_id: pty_id_clone.clone(),
                                 data,
+                                replay: false,
                             },
                         );
+                        drop(output);
                     }
 
                     // Keep any incomplete bytes for next read
@@ -548,19 +725,64 @@ pub fn spawn_pty(
         if child_pid_for_cleanup > 0 {
             crate::cleanup::remove_pid(child_pid_for_cleanup);
         }
+        for path in PTY_EXIT_FILES.lock().remove(&pty_id_clone).unwrap_or_default() {
+            let _ = std::fs::remove_file(path);
+        }
+
+        // Remember the result for task/main sessions so the sidebar can show it after restart
+        if let Some(key) = &result_key {
+            record_session_result(&app_handle, &worktree_id_clone, key, exit_code);
+        }
 
         eprintln!("[PTY:{}] Reader thread exiting, emitting pty-exit event", pty_id_clone);
-        let _ = app_handle.emit("pty-exit", serde_json::json!({
-            "ptyId": pty_id_clone,
-            "worktreeId": worktree_id_clone,
-            "command": command_name,
-            "exitCode": exit_code,
-        }));
+        let _ = app_handle.emit(events::PTY_EXIT, PtyExit {
+            pty_id: pty_id_clone,
+            worktree_id: worktree_id_clone,
+            command: command_name,
+            exit_code,
+        });
     });
 
     Ok(pty_id)
 }
 
+/// Delete `path` once session `pty_id`'s process exits, however it ends. A
+/// session that already exited (or never ran) has it deleted right away.
+pub fn remove_file_on_exit(pty_id: &str, path: std::path::PathBuf) {
+    let mut exit_files = PTY_EXIT_FILES.lock();
+    match exit_files.get_mut(pty_id) {
+        Some(paths) => paths.push(path),
+        None => {
+            drop(exit_files);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Persist the exit result of a task/main session on its worktree
+fn record_session_result(app: &AppHandle, worktree_id: &str, key: &str, exit_code: Option<u32>) {
+    let state = app.state::<Arc<AppState>>();
+    let Some(project) = state.worktree_project(worktree_id) else {
+        return;
+    };
+
+    let options = crate::config::ConfigLoadOptions {
+        disabled_layers: project.disabled_config_layers,
+    };
+    let cfg = crate::config::load_config_for_project_with(Some(&project.path), &options);
+    let task_names: Vec<String> = cfg.tasks.iter().map(|t| t.name.clone()).collect();
+    let result = TaskResult {
+        exit_code,
+        finished_at: crate::worktree::chrono_lite_now(),
+    };
+
+    if state.record_worktree_result(worktree_id, key, result, &task_names) {
+        if let Err(e) = state.save() {
+            eprintln!("[PTY] Failed to save session result: {}", e);
+        }
+    }
+}
+
 pub fn write_to_pty(_state: &AppState, pty_id: &str, data: &str) -> Result<(), PtyError> {
     let writers = PTY_WRITERS.lock();
     let writer = writers
@@ -570,6 +792,9 @@ pub fn write_to_pty(_state: &AppState, pty_id: &str, data: &str) -> Result<(), P
     drop(writers);
 
     writer.lock().write(data.as_bytes())?;
+    if let Some(activity) = PTY_ACTIVITY.lock().get(pty_id) {
+        activity.store(crate::status::now_unix_ms(), Ordering::Relaxed);
+    }
     Ok(())
 }
 
@@ -591,6 +816,50 @@ pub fn resize_pty(_state: &AppState, pty_id: &str, cols: u16, rows: u16) -> Resu
     Ok(())
 }
 
+/// Check that a session exists and its process is still running
+fn attachable_session(state: &AppState, pty_id: &str) -> Result<Arc<PtySession>, PtyError> {
+    state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .filter(|s| session_is_live(s))
+        .cloned()
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))
+}
+
+/// Re-attach a viewer (e.g. after a webview reload) to a running session:
+/// resize to the viewer, then replay the buffered output tail as a single
+/// `pty-output` event with `replay: true`. Live output keeps streaming as usual.
+pub fn attach_pty(app: &AppHandle, state: &AppState, pty_id: &str, cols: u16, rows: u16) -> Result<(), PtyError> {
+    attachable_session(state, pty_id)?;
+    resize_pty(state, pty_id, cols, rows)?;
+    PTY_VIEWERS.lock().insert(pty_id.to_string(), (cols, rows));
+
+    let Some(buffer) = PTY_OUTPUT.lock().get(pty_id).cloned() else {
+        return Ok(());
+    };
+    let output = buffer.lock();
+    let data = output.snapshot();
+    if !data.is_empty() {
+        let _ = app.emit(
+            events::PTY_OUTPUT,
+            PtyOutput {
+                pty_id: pty_id.to_string(),
+                data,
+                replay: true,
+            },
+        );
+    }
+    drop(output);
+
+    Ok(())
+}
+
+/// Counterpart of `attach_pty`: forgets the viewer size. The session keeps running.
+pub fn detach_pty(pty_id: &str) {
+    PTY_VIEWERS.lock().remove(pty_id);
+}
+
 /// Send SIGINT to interrupt the foreground process in a PTY session
 #[cfg(unix)]
 pub fn interrupt_pty(state: &AppState, pty_id: &str) -> Result<(), PtyError> {
@@ -655,8 +924,7 @@ pub fn kill_pty(state: &AppState, pty_id: &str) -> Result<(), PtyError> {
 pub fn kill_pty(state: &AppState, pty_id: &str) -> Result<(), PtyError> {
     // On non-Unix, just remove the session (will close the PTY)
     state.pty_sessions.write().remove(pty_id);
-    PTY_WRITERS.lock().remove(pty_id);
-    PTY_MASTERS.lock().remove(pty_id);
+    release_pty_handles(pty_id);
     Ok(())
 }
 
@@ -683,8 +951,7 @@ pub fn force_kill_pty(state: &AppState, pty_id: &str) -> Result<(), PtyError> {
 
     // Clean up state
     state.pty_sessions.write().remove(pty_id);
-    PTY_WRITERS.lock().remove(pty_id);
-    PTY_MASTERS.lock().remove(pty_id);
+    release_pty_handles(pty_id);
     Ok(())
 }
 
@@ -694,17 +961,393 @@ pub fn force_kill_pty(state: &AppState, pty_id: &str) -> Result<(), PtyError> {
     kill_pty(state, pty_id)
 }
 
-/// Shutdown progress event payload
-#[derive(Clone, serde::Serialize)]
-pub struct ShutdownProgress {
-    pub phase: String,
-    pub message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub process_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub pid: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub signal: Option<String>,
+/// SIGTERM a session, then SIGKILL it if it is still running after `grace`
+#[cfg(unix)]
+pub fn terminate_session_gracefully(state: &AppState, pty_id: &str, grace: Duration) -> Result<(), PtyError> {
+    let child_pid = state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .map(|s| s.child_pid)
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))?;
+
+    kill_pty(state, pty_id)?;
+    let deadline = std::time::Instant::now() + grace;
+    while std::time::Instant::now() < deadline && child_pid > 0 && is_process_alive(child_pid) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    if child_pid > 0 && is_process_alive(child_pid) {
+        force_kill_pty(state, pty_id)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn terminate_session_gracefully(state: &AppState, pty_id: &str, _grace: Duration) -> Result<(), PtyError> {
+    kill_pty(state, pty_id)
+}
+
+/// Upper bound for runtime scrollback adjustments
+pub const MAX_SCROLLBACK_LINES: u32 = 100_000;
+
+/// Clamp a requested scrollback size to what we allow at runtime
+pub fn clamp_scrollback(lines: u32) -> u32 {
+    lines.min(MAX_SCROLLBACK_LINES)
+}
+
+/// Replay buffer bytes allowed per scrollback line, escape sequences included
+const SCROLLBACK_LINE_BYTES: usize = 256;
+
+/// Resize a live session's replay buffer (the output tail sent on attach) to
+/// hold about `lines` lines, e.g. before running a verbose command. It never
+/// shrinks below `OUTPUT_BUFFER_BYTES`, so a small value restores the default.
+///
+/// Returns the applied (clamped) size, or `None` when the session has no
+/// replay buffer (it is exiting), in which case nothing changes.
+pub fn set_pty_scrollback(state: &AppState, pty_id: &str, lines: u32) -> Result<Option<u32>, PtyError> {
+    if !state.pty_sessions.read().contains_key(pty_id) {
+        return Err(PtyError::SessionNotFound(pty_id.to_string()));
+    }
+
+    let lines = clamp_scrollback(lines);
+    let Some(buffer) = PTY_OUTPUT.lock().get(pty_id).cloned() else {
+        return Ok(None);
+    };
+    let capacity = (lines as usize * SCROLLBACK_LINE_BYTES).max(OUTPUT_BUFFER_BYTES);
+    buffer.lock().set_capacity(capacity);
+    eprintln!("[PTY:{}] Replay buffer resized to {} bytes for {} lines", pty_id, capacity, lines);
+    Ok(Some(lines))
+}
+
+/// Get the current working directory of a session's shell, following to the
+/// most recently started child (e.g. a subshell the user `cd`'d in).
+/// Falls back to the directory the session was started in.
+#[cfg(unix)]
+pub fn get_pty_cwd(state: &AppState, pty_id: &str) -> Result<String, PtyError> {
+    let session = state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .cloned()
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))?;
+
+    if session.child_pid == 0 {
+        return Ok(session.worktree_path.clone());
+    }
+
+    let leaf_pid = get_child_pids(session.child_pid)
+        .into_iter()
+        .max()
+        .unwrap_or(session.child_pid);
+
+    let cwd = get_process_cwd(leaf_pid)
+        .or_else(|| get_process_cwd(session.child_pid))
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| session.worktree_path.clone());
+
+    Ok(cwd)
+}
+
+#[cfg(not(unix))]
+pub fn get_pty_cwd(state: &AppState, pty_id: &str) -> Result<String, PtyError> {
+    state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .map(|s| s.worktree_path.clone())
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))
+}
+
+/// Count live sessions as (total, for `worktree_id`). Sessions whose process
+/// already exited but that haven't been removed yet are not counted.
+pub fn live_session_counts(state: &AppState, worktree_id: &str) -> (usize, usize) {
+    let sessions = state.pty_sessions.read();
+    sessions
+        .values()
+        .filter(|s| session_is_live(s))
+        .fold((0, 0), |(total, in_worktree), s| {
+            (total + 1, in_worktree + usize::from(s.worktree_id == worktree_id))
+        })
+}
+
+/// Live task sessions across all entities
+pub fn live_task_session_count(state: &AppState) -> usize {
+    state
+        .pty_sessions
+        .read()
+        .values()
+        .filter(|s| s.task_name.is_some() && session_is_live(s))
+        .count()
+}
+
+/// Names of tasks with a live session in `entity_id`
+pub fn running_task_names(state: &AppState, entity_id: &str) -> std::collections::HashSet<String> {
+    state
+        .pty_sessions
+        .read()
+        .values()
+        .filter(|s| s.worktree_id == entity_id && session_is_live(s))
+        .filter_map(|s| s.task_name.clone())
+        .collect()
+}
+
+/// Remove sessions whose process has exited, returning their ids. Normally
+/// the reader thread reports an exit; this catches sessions it never did.
+pub fn close_dead_sessions(state: &AppState) -> Vec<String> {
+    let mut sessions = state.pty_sessions.write();
+    let dead: Vec<String> = sessions
+        .iter()
+        .filter(|(_, s)| !session_is_live(s))
+        .map(|(id, _)| id.clone())
+        .collect();
+    for pty_id in &dead {
+        sessions.remove(pty_id);
+        release_pty_handles(pty_id);
+    }
+    dead
+}
+
+#[cfg(unix)]
+fn session_is_live(session: &PtySession) -> bool {
+    // A pid of 0 means it couldn't be determined; assume the session is running
+    session.child_pid == 0 || is_process_alive(session.child_pid)
+}
+
+#[cfg(not(unix))]
+fn session_is_live(_session: &PtySession) -> bool {
+    true
+}
+
+/// Check the global and per-worktree session limits (0 disables a limit)
+pub fn check_session_limits(state: &AppState, worktree_id: &str, limits: &TerminalConfig) -> Result<(), PtyError> {
+    let (total, in_worktree) = live_session_counts(state, worktree_id);
+
+    if limits.max_sessions > 0 && total >= limits.max_sessions {
+        eprintln!("[PTY] Refusing to spawn: {} of {} sessions running", total, limits.max_sessions);
+        return Err(PtyError::SessionLimitReached {
+            limit: limits.max_sessions,
+            current: total,
+        });
+    }
+    if limits.max_sessions_per_worktree > 0 && in_worktree >= limits.max_sessions_per_worktree {
+        eprintln!(
+            "[PTY] Refusing to spawn in {}: {} of {} sessions running",
+            worktree_id, in_worktree, limits.max_sessions_per_worktree
+        );
+        return Err(PtyError::WorktreeSessionLimitReached {
+            worktree_id: worktree_id.to_string(),
+            limit: limits.max_sessions_per_worktree,
+            current: in_worktree,
+        });
+    }
+
+    Ok(())
+}
+
+/// Raise the open-file soft limit toward the hard limit so many terminals
+/// (each holding several descriptors) don't exhaust it
+#[cfg(unix)]
+pub fn raise_fd_limit() {
+    let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        eprintln!("[PTY] getrlimit(RLIMIT_NOFILE) failed: {}", std::io::Error::last_os_error());
+        return;
+    }
+    let before = limit.rlim_cur;
+    if before >= limit.rlim_max {
+        eprintln!("[PTY] RLIMIT_NOFILE already at hard limit ({})", before);
+        return;
+    }
+
+    // macOS rejects values above OPEN_MAX even when the hard limit is unlimited
+    for target in [limit.rlim_max, limit.rlim_max.min(10240)] {
+        if target <= before {
+            continue;
+        }
+        let raised = libc::rlimit { rlim_cur: target, rlim_max: limit.rlim_max };
+        if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &raised) } == 0 {
+            eprintln!("[PTY] Raised RLIMIT_NOFILE soft limit from {} to {}", before, target);
+            return;
+        }
+    }
+    eprintln!(
+        "[PTY] Could not raise RLIMIT_NOFILE (soft {}, hard {}): {}",
+        before,
+        limit.rlim_max,
+        std::io::Error::last_os_error()
+    );
+}
+
+#[cfg(not(unix))]
+pub fn raise_fd_limit() {}
+
+/// A clicked terminal path resolved to a file inside the session's worktree
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedTerminalPath {
+    pub path: String,
+    pub language: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Resolve a path printed in a terminal (e.g. `src/lib/foo.ts:42`) against the
+/// session's current directory, falling back to the worktree root. The result
+/// must exist and stay inside the worktree.
+pub fn resolve_terminal_path(state: &AppState, pty_id: &str, text: &str) -> Result<ResolvedTerminalPath, PtyError> {
+    let root = state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .map(|s| std::path::PathBuf::from(&s.worktree_path))
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))?;
+    let cwd = std::path::PathBuf::from(get_pty_cwd(state, pty_id)?);
+
+    let location = path_utils::parse_path_location(text)
+        .ok_or_else(|| PtyError::InvalidPath(text.to_string()))?;
+    let resolve = |path: &str| {
+        path_utils::resolve_within_root(path, &cwd, &root)
+            .or_else(|_| path_utils::resolve_within_root(path, &root, &root))
+    };
+
+    let (path, line, column) = match resolve(&location.path) {
+        Ok(path) => (path, location.line, location.column),
+        // The "location" may really be part of the file name (e.g. `notes:2`)
+        Err(e) => match resolve(text.trim()) {
+            Ok(path) => (path, None, None),
+            Err(_) => return Err(PtyError::InvalidPath(e)),
+        },
+    };
+
+    let path = path.to_string_lossy().to_string();
+    Ok(ResolvedTerminalPath {
+        language: git::detect_language(&path),
+        path,
+        line,
+        column,
+    })
+}
+
+/// Get the name of the process in the foreground of a session (e.g. "vim"),
+/// using the tty's foreground process group. Returns the shell itself when idle.
+#[cfg(unix)]
+pub fn get_pty_foreground(state: &AppState, pty_id: &str) -> Result<Option<String>, PtyError> {
+    let child_pid = state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .map(|s| s.child_pid)
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))?;
+
+    let master = PTY_MASTERS.lock().get(pty_id).cloned();
+    let foreground_pid = master
+        .and_then(|m| m.lock().process_group_leader())
+        .filter(|pid| *pid > 0)
+        .map(|pid| pid as u32)
+        // Fall back to the most recently started descendant
+        .or_else(|| get_child_pids(child_pid).into_iter().max())
+        .unwrap_or(child_pid);
+
+    if foreground_pid == 0 {
+        return Ok(None);
+    }
+
+    Ok(get_process_name(foreground_pid))
+}
+
+#[cfg(windows)]
+pub fn get_pty_foreground(state: &AppState, pty_id: &str) -> Result<Option<String>, PtyError> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let child_pid = state
+        .pty_sessions
+        .read()
+        .get(pty_id)
+        .map(|s| s.child_pid)
+        .ok_or_else(|| PtyError::SessionNotFound(pty_id.to_string()))?;
+
+    // No process groups on Windows, report the child itself
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", child_pid), "/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
+
+    // Output looks like: "pwsh.exe","1234","Console","1","90,000 K"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split(',').next())
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| !name.is_empty() && !name.starts_with("INFO:")))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn get_pty_foreground(_state: &AppState, _pty_id: &str) -> Result<Option<String>, PtyError> {
+    Ok(None)
+}
+
+/// Terminate every session running inside a worktree and wait for the processes to exit.
+/// Used before removing the worktree directory so no process keeps its cwd inside it.
+#[cfg(unix)]
+pub fn terminate_worktree_sessions(state: &AppState, worktree_id: &str) {
+    use libc::{SIGKILL, SIGTERM};
+
+    let sessions: Vec<(String, u32)> = state
+        .pty_sessions
+        .read()
+        .iter()
+        .filter(|(_, s)| s.worktree_id == worktree_id)
+        .map(|(id, s)| (id.clone(), s.child_pid))
+        .collect();
+
+    if sessions.is_empty() {
+        return;
+    }
+
+    let mut pids = Vec::new();
+    for (_, pid) in &sessions {
+        if *pid > 0 {
+            pids.extend(get_child_pids(*pid));
+            pids.push(*pid);
+        }
+    }
+
+    for pid in &pids {
+        send_signal(*pid, SIGTERM);
+    }
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while std::time::Instant::now() < deadline && pids.iter().any(|pid| is_process_alive(*pid)) {
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    for pid in pids.iter().filter(|pid| is_process_alive(**pid)) {
+        send_signal(*pid, SIGKILL);
+    }
+
+    let mut pty_sessions = state.pty_sessions.write();
+    for (pty_id, _) in &sessions {
+        pty_sessions.remove(pty_id);
+        release_pty_handles(pty_id);
+    }
+    eprintln!("[PTY] Terminated {} sessions for worktree {}", sessions.len(), worktree_id);
+}
+
+#[cfg(not(unix))]
+pub fn terminate_worktree_sessions(state: &AppState, worktree_id: &str) {
+    let pty_ids: Vec<String> = state
+        .pty_sessions
+        .read()
+        .iter()
+        .filter(|(_, s)| s.worktree_id == worktree_id)
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    for pty_id in pty_ids {
+        let _ = kill_pty(state, &pty_id);
+    }
 }
 
 /// Check if a process is still running
@@ -733,6 +1376,28 @@ pub(crate) fn get_process_name(pid: u32) -> Option<String> {
         })
 }
 
+/// Get the current working directory of a process (/proc on Linux, lsof elsewhere)
+#[cfg(unix)]
+pub(crate) fn get_process_cwd(pid: u32) -> Option<std::path::PathBuf> {
+    if let Ok(path) = std::fs::read_link(format!("/proc/{}/cwd", pid)) {
+        return Some(path);
+    }
+
+    // -Fn prints one field per line; the cwd path is on the line starting with 'n'
+    let output = Command::new("lsof")
+        .args(["-a", "-d", "cwd", "-p", &pid.to_string(), "-Fn"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(std::path::PathBuf::from)
+}
+
 /// Send a signal to a process
 #[cfg(unix)]
 pub(crate) fn send_signal(pid: u32, signal: i32) -> bool {
@@ -777,7 +1442,7 @@ pub fn shutdown_all_ptys(app: &AppHandle, state: &AppState) {
     }
 
     let emit_progress = |phase: &str, message: &str, process_name: Option<String>, pid: Option<u32>, signal: Option<&str>| {
-        let _ = app.emit("shutdown-progress", ShutdownProgress {
+        let _ = app.emit(events::SHUTDOWN_PROGRESS, ShutdownProgress {
             phase: phase.to_string(),
             message: message.to_string(),
             process_name,
@@ -863,8 +1528,7 @@ pub fn shutdown_all_ptys(app: &AppHandle, state: &AppState) {
     // Clean up internal state
     for (pty_id, _) in &sessions {
         state.pty_sessions.write().remove(pty_id);
-        PTY_WRITERS.lock().remove(pty_id);
-        PTY_MASTERS.lock().remove(pty_id);
+        release_pty_handles(pty_id);
     }
 
     // Delete PID file on clean shutdown
@@ -876,7 +1540,7 @@ pub fn shutdown_all_ptys(app: &AppHandle, state: &AppState) {
 #[cfg(not(unix))]
 pub fn shutdown_all_ptys(app: &AppHandle, state: &AppState) {
     // On non-Unix platforms, just clean up the state
-    let _ = app.emit("shutdown-progress", ShutdownProgress {
+    let _ = app.emit(events::SHUTDOWN_PROGRESS, ShutdownProgress {
         phase: "complete".to_string(),
         message: "Cleanup complete".to_string(),
         process_name: None,
@@ -887,8 +1551,7 @@ pub fn shutdown_all_ptys(app: &AppHandle, state: &AppState) {
     let pty_ids: Vec<String> = state.pty_sessions.read().keys().cloned().collect();
     for pty_id in pty_ids {
         state.pty_sessions.write().remove(&pty_id);
-        PTY_WRITERS.lock().remove(&pty_id);
-        PTY_MASTERS.lock().remove(&pty_id);
+        release_pty_handles(&pty_id);
     }
 }
 
@@ -962,6 +1625,25 @@ mod tests {
         assert!(!result, "Signal to nonexistent PID should fail");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_close_dead_sessions_removes_only_exited_processes() {
+        let state = AppState::new();
+        for (pty_id, child_pid) in [("pty-live", std::process::id()), ("pty-dead", 999999999), ("pty-unknown", 0)] {
+            state.pty_sessions.write().insert(
+                pty_id.to_string(),
+                Arc::new(PtySession {
+                    worktree_id: "w1".to_string(),
+                    child_pid,
+                    worktree_path: "/tmp".to_string(),
+                    task_name: None,
+                }),
+            );
+        }
+        assert_eq!(close_dead_sessions(&state), vec!["pty-dead".to_string()]);
+        assert_eq!(state.pty_sessions.read().len(), 2);
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_is_process_alive_returns_false_for_invalid_pid() {
@@ -986,6 +1668,323 @@ mod tests {
         assert!(name.is_none(), "Invalid PID should have no process name");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_get_process_cwd_for_current_process() {
+        let cwd = get_process_cwd(std::process::id()).expect("current process should have a cwd");
+        assert_eq!(
+            cwd.canonicalize().unwrap(),
+            std::env::current_dir().unwrap().canonicalize().unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_process_cwd_returns_none_for_invalid_pid() {
+        assert!(get_process_cwd(999999999).is_none());
+    }
+
+    #[test]
+    fn test_get_pty_cwd_returns_error_for_missing_session() {
+        let state = AppState::new();
+        let result = get_pty_cwd(&state, "nonexistent-pty-id");
+        assert!(matches!(result, Err(PtyError::SessionNotFound(_))));
+    }
+
+    #[test]
+    fn test_get_pty_cwd_falls_back_to_session_path() {
+        let state = AppState::new();
+        state.pty_sessions.write().insert(
+            "pty-1".to_string(),
+            Arc::new(PtySession {
+                worktree_id: "w1".to_string(),
+                child_pid: 0,
+                worktree_path: "/tmp/repo/.worktrees/feature".to_string(),
+                task_name: None,
+            }),
+        );
+        assert_eq!(
+            get_pty_cwd(&state, "pty-1").unwrap(),
+            "/tmp/repo/.worktrees/feature"
+        );
+    }
+
+    #[test]
+    fn test_clamp_scrollback() {
+        assert_eq!(clamp_scrollback(5000), 5000);
+        assert_eq!(clamp_scrollback(u32::MAX), MAX_SCROLLBACK_LINES);
+    }
+
+    #[test]
+    fn test_set_pty_scrollback_returns_error_for_missing_session() {
+        let state = AppState::new();
+        let result = set_pty_scrollback(&state, "nonexistent-pty-id", 5000);
+        assert!(matches!(result, Err(PtyError::SessionNotFound(_))));
+    }
+
+    #[test]
+    fn test_exit_files_of_finished_sessions_go_right_away() {
+        let path = std::env::temp_dir().join(format!("shellflow-exit-file-{}", Uuid::new_v4()));
+        std::fs::write(&path, "").unwrap();
+        remove_file_on_exit("nonexistent-pty-id", path.clone());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_get_pty_foreground_returns_error_for_missing_session() {
+        let state = AppState::new();
+        let result = get_pty_foreground(&state, "nonexistent-pty-id");
+        assert!(matches!(result, Err(PtyError::SessionNotFound(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_pty_foreground_falls_back_to_child_process() {
+        // Without a pty master, the session's own process (or its newest child) is reported
+        let state = AppState::new();
+        state.pty_sessions.write().insert(
+            "pty-fg".to_string(),
+            Arc::new(PtySession {
+                worktree_id: "w1".to_string(),
+                child_pid: std::process::id(),
+                worktree_path: "/tmp".to_string(),
+                task_name: None,
+            }),
+        );
+        let name = get_pty_foreground(&state, "pty-fg").unwrap();
+        assert!(name.is_some_and(|n| !n.is_empty()));
+    }
+
+    fn insert_idle_session(state: &AppState, pty_id: &str, worktree_path: &std::path::Path) {
+        state.pty_sessions.write().insert(
+            pty_id.to_string(),
+            Arc::new(PtySession {
+                worktree_id: "w1".to_string(),
+                child_pid: 0,
+                worktree_path: worktree_path.to_string_lossy().to_string(),
+                task_name: None,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_resolve_terminal_path_relative_to_worktree() {
+        let root = std::env::temp_dir().join(format!("shellflow-termpath-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src").join("lib")).unwrap();
+        std::fs::write(root.join("src").join("lib").join("foo.ts"), "").unwrap();
+        let state = AppState::new();
+        insert_idle_session(&state, "pty-path", &root);
+        let canonical_root = root.canonicalize().unwrap();
+
+        let resolved = resolve_terminal_path(&state, "pty-path", "src/lib/foo.ts:42:7").unwrap();
+        assert_eq!(
+            resolved,
+            ResolvedTerminalPath {
+                path: canonical_root.join("src/lib/foo.ts").to_string_lossy().to_string(),
+                language: "typescript".to_string(),
+                line: Some(42),
+                column: Some(7),
+            }
+        );
+
+        let resolved = resolve_terminal_path(&state, "pty-path", "src/lib/foo.ts(3,1)").unwrap();
+        assert_eq!((resolved.line, resolved.column), (Some(3), Some(1)));
+
+        // A file whose name looks like path:line still resolves
+        #[cfg(unix)]
+        {
+            std::fs::write(root.join("notes:2"), "").unwrap();
+            let resolved = resolve_terminal_path(&state, "pty-path", "notes:2").unwrap();
+            assert!(resolved.path.ends_with("notes:2"));
+            assert_eq!(resolved.line, None);
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_resolve_terminal_path_rejects_outside_and_missing() {
+        let root = std::env::temp_dir().join(format!("shellflow-termpath-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let state = AppState::new();
+        insert_idle_session(&state, "pty-path", &root);
+
+        assert!(matches!(
+            resolve_terminal_path(&state, "pty-path", "../../etc/passwd"),
+            Err(PtyError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            resolve_terminal_path(&state, "pty-path", "/etc/hosts:1"),
+            Err(PtyError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            resolve_terminal_path(&state, "pty-path", "missing.rs:1"),
+            Err(PtyError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            resolve_terminal_path(&state, "nope", "a.rs"),
+            Err(PtyError::SessionNotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn insert_session_with_pid(state: &AppState, pty_id: &str, worktree_id: &str, child_pid: u32) {
+        state.pty_sessions.write().insert(
+            pty_id.to_string(),
+            Arc::new(PtySession {
+                worktree_id: worktree_id.to_string(),
+                child_pid,
+                worktree_path: "/tmp".to_string(),
+                task_name: None,
+            }),
+        );
+    }
+
+    fn limits(max_sessions: usize, max_sessions_per_worktree: usize) -> TerminalConfig {
+        TerminalConfig { max_sessions, max_sessions_per_worktree }
+    }
+
+    #[test]
+    fn test_session_limits_count_per_worktree() {
+        let state = AppState::new();
+        let pid = std::process::id();
+        insert_session_with_pid(&state, "a1", "w1", pid);
+        insert_session_with_pid(&state, "a2", "w1", pid);
+        insert_session_with_pid(&state, "b1", "w2", pid);
+
+        assert_eq!(live_session_counts(&state, "w1"), (3, 2));
+        assert!(check_session_limits(&state, "w3", &limits(4, 2)).is_ok());
+        assert!(matches!(
+            check_session_limits(&state, "w1", &limits(4, 2)),
+            Err(PtyError::WorktreeSessionLimitReached { limit: 2, current: 2, .. })
+        ));
+        assert!(matches!(
+            check_session_limits(&state, "w3", &limits(3, 0)),
+            Err(PtyError::SessionLimitReached { limit: 3, current: 3 })
+        ));
+        // 0 disables both limits
+        assert!(check_session_limits(&state, "w1", &limits(0, 0)).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_session_limits_ignore_exited_sessions() {
+        // A reaped child's pid no longer refers to a live process
+        let mut child = Command::new("true").spawn().unwrap();
+        let exited_pid = child.id();
+        child.wait().unwrap();
+
+        let state = AppState::new();
+        insert_session_with_pid(&state, "live", "w1", std::process::id());
+        insert_session_with_pid(&state, "exited", "w1", exited_pid);
+
+        assert_eq!(live_session_counts(&state, "w1"), (1, 1));
+        assert!(check_session_limits(&state, "w1", &limits(2, 2)).is_ok());
+    }
+
+    #[test]
+    fn test_output_buffer_keeps_bounded_tail() {
+        let mut buffer = OutputBuffer::new(16);
+        buffer.push("hello ");
+        buffer.push("world");
+        assert_eq!(buffer.snapshot(), "hello world");
+
+        // Overflow drops old output and the replay starts on a line boundary
+        buffer.push("\nline two\nabc");
+        assert!(buffer.data.len() <= 16);
+        assert_eq!(buffer.snapshot(), "line two\nabc");
+
+        // A single chunk larger than the buffer keeps only its tail
+        let big = format!("{}\nend", "x".repeat(100));
+        buffer.push(&big);
+        assert_eq!(buffer.data.len(), 16);
+        assert_eq!(buffer.snapshot(), "end");
+    }
+
+    #[test]
+    fn test_json_line_scanner_reassembles_split_lines() {
+        let mut scanner = JsonLineScanner::default();
+        assert!(scanner.feed("building...\r\n{\"shellflow\": {\"pro").is_empty());
+        let events = scanner.feed("gress\": 0.5}}\r\n{\"shellflow\": 1}\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["shellflow"]["progress"], 0.5);
+        assert_eq!(events[1]["shellflow"], 1);
+    }
+
+    #[test]
+    fn test_json_line_scanner_requires_shellflow_key() {
+        let mut scanner = JsonLineScanner::default();
+        let events = scanner.feed("{\"level\": \"info\"}\n[1, 2]\n{\"shellflow\": \n{not json}\n  {\"shellflow\": \"done\"}  \n");
+        assert_eq!(events, vec![serde_json::json!({ "shellflow": "done" })]);
+    }
+
+    #[test]
+    fn test_json_line_scanner_skips_oversized_lines() {
+        let mut scanner = JsonLineScanner::default();
+        let padding = "x".repeat(MAX_STRUCTURED_LINE_BYTES);
+        // The oversized line spans chunks; the line after it still parses
+        assert!(scanner.feed(&format!("{{\"shellflow\": \"{}", padding)).is_empty());
+        assert!(scanner.partial.is_empty());
+        let events = scanner.feed("\"}\n{\"shellflow\": \"next\"}\n");
+        assert_eq!(events, vec![serde_json::json!({ "shellflow": "next" })]);
+
+        let fits = format!("{{\"shellflow\": \"{}\"}}", "y".repeat(MAX_STRUCTURED_LINE_BYTES - 20));
+        assert_eq!(scanner.feed(&format!("{}\n", fits)).len(), 1);
+    }
+
+    #[test]
+    fn test_output_buffer_capacity_changes_keep_the_tail() {
+        let mut buffer = OutputBuffer::new(8);
+        buffer.push("one\ntwo\n");
+        buffer.set_capacity(32);
+        buffer.push("three\nfour\n");
+        assert_eq!(buffer.snapshot(), "two\nthree\nfour\n");
+
+        buffer.set_capacity(6);
+        assert_eq!(buffer.data.len(), 6);
+        assert_eq!(buffer.snapshot(), "four\n");
+        buffer.push("five\n");
+        assert_eq!(buffer.snapshot(), "five\n");
+    }
+
+    #[test]
+    fn test_output_buffer_default_capacity_is_bounded() {
+        let mut buffer = OutputBuffer::new(OUTPUT_BUFFER_BYTES);
+        let chunk = "é".repeat(2048) + "\n";
+        for _ in 0..200 {
+            buffer.push(&chunk);
+        }
+        assert!(buffer.data.len() <= OUTPUT_BUFFER_BYTES);
+        let snapshot = buffer.snapshot();
+        assert!(snapshot.starts_with('é'));
+        assert!(!snapshot.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_attach_requires_live_session() {
+        let state = AppState::new();
+        assert!(matches!(
+            attachable_session(&state, "missing"),
+            Err(PtyError::SessionNotFound(_))
+        ));
+
+        #[cfg(unix)]
+        {
+            let mut child = Command::new("true").spawn().unwrap();
+            let exited_pid = child.id();
+            child.wait().unwrap();
+            insert_session_with_pid(&state, "dead", "w1", exited_pid);
+            assert!(matches!(
+                attachable_session(&state, "dead"),
+                Err(PtyError::SessionNotFound(_))
+            ));
+        }
+
+        insert_session_with_pid(&state, "live", "w1", std::process::id());
+        assert!(attachable_session(&state, "live").is_ok());
+    }
+
     #[test]
     fn test_get_cached_user_path_returns_non_empty() {
         // get_cached_user_path should return a non-empty PATH
@@ -0,0 +1,670 @@
+//! One-call stage, commit and push for a worktree.
+//!
+//! Meant for automation: when an agent finishes a change it can land it with a
+//! single command. Every step goes through the git CLI, so hooks, commit signing
+//! and credential helpers behave exactly as they do in a terminal. A failed step
+//! stops the chain and leaves the repository as git left it.
+
+use crate::config::{AiProvider, CommitAiConfig};
+use crate::git::{self, GitError};
+use crate::network_retry::NetworkRetry;
+use git2::Repository;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use thiserror::Error;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Sent as `anthropic-version`; the messages API requires one
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickCommitStep {
+    Stage,
+    Commit,
+    Push,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushStatus {
+    Skipped,
+    Pushed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCommitResult {
+    pub sha: String,
+    pub message: String,
+    /// Number of files in the commit
+    pub files: usize,
+    pub ai_used: bool,
+    /// Why AI generation was attempted but abandoned for the fallback message
+    pub ai_error: Option<String>,
+    pub push: PushStatus,
+}
+
+/// A quick-commit step failed. `sha` is set when the commit landed before the failure.
+#[derive(Error, Debug)]
+#[error("{source}")]
+pub struct QuickCommitError {
+    pub step: QuickCommitStep,
+    pub source: GitError,
+    pub sha: Option<String>,
+}
+
+impl QuickCommitError {
+    fn at(step: QuickCommitStep) -> impl FnOnce(GitError) -> Self {
+        move |source| Self { step, source, sha: None }
+    }
+}
+
+/// Deterministic message used when no message is given and AI isn't available
+pub fn fallback_message(file_count: usize) -> String {
+    if file_count == 1 {
+        "Update 1 file".to_string()
+    } else {
+        format!("Update {} files", file_count)
+    }
+}
+
+fn ai_configured(ai: &CommitAiConfig) -> bool {
+    let key_needed = ai.provider != AiProvider::Ollama;
+    (!key_needed || !ai.api_key.trim().is_empty()) && !ai.base_url.trim().is_empty() && !ai.model.trim().is_empty()
+}
+
+/// Fill `{{ key }}` / `{{key}}` placeholders the same way the commit dialog does
+fn apply_prompt_template(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter().fold(template.to_string(), |result, (key, value)| {
+        result
+            .replace(&format!("{{{{ {} }}}}", key), value)
+            .replace(&format!("{{{{{}}}}}", key), value)
+    })
+}
+
+/// `base_url` with `path` appended, unless it already ends with it
+fn append_endpoint(base_url: &str, path: &str) -> String {
+    let trimmed = base_url.trim_end_matches('/');
+    if trimmed.ends_with(path) {
+        trimmed.to_string()
+    } else {
+        format!("{}{}", trimmed, path)
+    }
+}
+
+fn chat_completions_endpoint(base_url: &str) -> String {
+    append_endpoint(base_url, "/chat/completions")
+}
+
+fn ai_endpoint(ai: &CommitAiConfig) -> String {
+    match ai.provider {
+        AiProvider::Openai => chat_completions_endpoint(&ai.base_url),
+        AiProvider::Anthropic if ai.base_url.trim_end_matches('/').ends_with("/v1") => {
+            append_endpoint(&ai.base_url, "/messages")
+        }
+        AiProvider::Anthropic => append_endpoint(&ai.base_url, "/v1/messages"),
+        AiProvider::Ollama => append_endpoint(&ai.base_url, "/api/chat"),
+    }
+}
+
+/// Quote a value for a curl config file
+fn curl_config_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A request to the configured provider; the prompt goes in unchanged for all of them
+#[derive(Debug)]
+struct AiRequest {
+    url: String,
+    headers: Vec<String>,
+    body: serde_json::Value,
+}
+
+fn build_ai_request(ai: &CommitAiConfig, prompt: &str, stream: bool) -> AiRequest {
+    let messages = serde_json::json!([{ "role": "user", "content": prompt }]);
+    let mut headers = vec!["Content-Type: application/json".to_string()];
+    let body = match ai.provider {
+        AiProvider::Openai => {
+            headers.push(format!("Authorization: Bearer {}", ai.api_key.trim()));
+            let mut body = serde_json::json!({
+                "model": ai.model,
+                "messages": messages,
+                "temperature": ai.temperature,
+                "max_tokens": ai.max_tokens,
+            });
+            if stream {
+                body["stream"] = serde_json::Value::Bool(true);
+            }
+            body
+        }
+        AiProvider::Anthropic => {
+            headers.push(format!("x-api-key: {}", ai.api_key.trim()));
+            headers.push(format!("anthropic-version: {}", ANTHROPIC_VERSION));
+            serde_json::json!({
+                "model": ai.model,
+                "messages": messages,
+                "temperature": ai.temperature,
+                "max_tokens": ai.max_tokens,
+                "stream": stream,
+            })
+        }
+        // Ollama streams unless told not to
+        AiProvider::Ollama => serde_json::json!({
+            "model": ai.model,
+            "messages": messages,
+            "stream": stream,
+            "options": { "temperature": ai.temperature, "num_predict": ai.max_tokens },
+        }),
+    };
+    AiRequest { url: ai_endpoint(ai), headers, body }
+}
+
+/// Start `request` as a POST. It is passed to curl as a config on stdin so
+/// the API key never shows up in argv. The response body is followed by a
+/// line with the HTTP status.
+fn spawn_ai_request(ai: &CommitAiConfig, request: &AiRequest, stream: bool) -> Result<Child, String> {
+    let mut config = vec![format!("url = {}", curl_config_quote(&request.url))];
+    config.extend(request.headers.iter().map(|h| format!("header = {}", curl_config_quote(h))));
+    config.push(format!("data-binary = {}", curl_config_quote(&request.body.to_string())));
+    config.push(format!("write-out = {}", curl_config_quote("\n%{http_code}")));
+    let config = config.join("\n");
+
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--request", "POST", "--max-time"])
+        .arg(format!("{:.3}", ai.timeout_ms as f64 / 1000.0))
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if stream {
+        cmd.arg("--no-buffer");
+    }
+    #[cfg(windows)]
+    {
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to run curl: {}", e))?;
+    // Dropping stdin closes it, so curl starts the request
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    Ok(child)
+}
+
+/// Reject a non-2xx `status` line, naming the response
+fn check_http_status(status: &str, response: &str) -> Result<(), String> {
+    if status.trim().starts_with('2') {
+        Ok(())
+    } else {
+        Err(format!("AI request failed with HTTP {}: {}", status.trim(), response.trim()))
+    }
+}
+
+/// The message in a non-streaming response
+fn completion_content(provider: AiProvider, response: &str) -> Result<String, String> {
+    let data: serde_json::Value =
+        serde_json::from_str(response).map_err(|e| format!("Invalid AI response: {}", e))?;
+    let content = match provider {
+        AiProvider::Openai => {
+            let choice = &data["choices"][0];
+            choice["message"]["content"].as_str().or_else(|| choice["text"].as_str()).unwrap_or("").to_string()
+        }
+        AiProvider::Anthropic => data["content"]
+            .as_array()
+            .map(|blocks| blocks.iter().filter_map(|b| b["text"].as_str()).collect())
+            .unwrap_or_default(),
+        AiProvider::Ollama => data["message"]["content"].as_str().unwrap_or("").to_string(),
+    };
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("AI returned an empty commit message".to_string());
+    }
+    Ok(content.to_string())
+}
+
+/// A line of a streamed response
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    /// A chunk; its text is empty for chunks that only carry a role or finish reason
+    Delta(String),
+    Done,
+    /// The server gave up partway, e.g. an overloaded error
+    Error(String),
+}
+
+/// Parse one line of a streamed response: server-sent events for OpenAI and
+/// Anthropic, one JSON object per line for Ollama. None for anything else,
+/// such as a plain JSON body from a server that ignored `stream`.
+fn parse_stream_line(provider: AiProvider, line: &str) -> Option<StreamLine> {
+    let data = match provider {
+        AiProvider::Ollama => line.trim(),
+        _ => line.strip_prefix("data:")?.trim(),
+    };
+    if data == "[DONE]" {
+        return Some(StreamLine::Done);
+    }
+    let chunk: serde_json::Value = serde_json::from_str(data).ok()?;
+    if let Some(error) = chunk.get("error") {
+        let message = error["message"].as_str().or_else(|| error.as_str());
+        return Some(StreamLine::Error(message.map(str::to_string).unwrap_or_else(|| error.to_string())));
+    }
+
+    let text = match provider {
+        AiProvider::Openai => {
+            let choice = &chunk["choices"][0];
+            choice["delta"]["content"].as_str().or_else(|| choice["text"].as_str())
+        }
+        AiProvider::Anthropic if chunk["type"] == "message_stop" => return Some(StreamLine::Done),
+        AiProvider::Anthropic => chunk["delta"]["text"].as_str(),
+        AiProvider::Ollama => Some(chunk.get("message")?["content"].as_str().unwrap_or("")),
+    };
+    Some(StreamLine::Delta(text.unwrap_or("").to_string()))
+}
+
+fn request_ai_message(ai: &CommitAiConfig, prompt: &str) -> Result<String, String> {
+    let child = spawn_ai_request(ai, &build_ai_request(ai, prompt, false), false)?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("AI request failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    check_http_status(status, response)?;
+    completion_content(ai.provider, response)
+}
+
+/// Like `request_ai_message`, but asks for a streamed response and passes each
+/// piece of text to `on_delta` as it arrives. A server that answers with a
+/// plain body gets its whole message passed once at the end.
+fn stream_ai_message(ai: &CommitAiConfig, prompt: &str, mut on_delta: impl FnMut(&str)) -> Result<String, String> {
+    let mut child = spawn_ai_request(ai, &build_ai_request(ai, prompt, true), true)?;
+    let stdout = child.stdout.take().ok_or("curl has no stdout")?;
+
+    let mut message = String::new();
+    let mut streamed = false;
+    let mut stream_error = None;
+    let mut other_lines = Vec::new();
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        match parse_stream_line(ai.provider, &line) {
+            Some(StreamLine::Delta(text)) => {
+                streamed = true;
+                if !text.is_empty() {
+                    on_delta(&text);
+                    message.push_str(&text);
+                }
+            }
+            Some(StreamLine::Done) => streamed = true,
+            Some(StreamLine::Error(e)) => stream_error = Some(e),
+            None => other_lines.push(line),
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("AI request failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    if let Some(e) = stream_error {
+        return Err(format!("AI request failed: {}", e));
+    }
+    let status = other_lines.pop().unwrap_or_default();
+    let response = other_lines.join("\n");
+    check_http_status(&status, &response)?;
+
+    if !streamed {
+        let content = completion_content(ai.provider, &response)?;
+        on_delta(&content);
+        return Ok(content);
+    }
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("AI returned an empty commit message".to_string());
+    }
+    Ok(message.to_string())
+}
+
+fn build_prompt(worktree_path: &Path, files: &[String], ai: &CommitAiConfig) -> Result<String, String> {
+    let diff = git::diff_cached(worktree_path).map_err(|e| e.to_string())?;
+    let branch = Repository::open(worktree_path)
+        .ok()
+        .and_then(|repo| git::get_current_branch(&repo).ok())
+        .unwrap_or_default();
+    let repo_name = worktree_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let files = files.join("\n");
+    Ok(apply_prompt_template(
+        &ai.prompt,
+        &[("diff", &diff), ("files", &files), ("branch", &branch), ("repo", &repo_name)],
+    ))
+}
+
+fn generate_ai_message(worktree_path: &Path, files: &[String], ai: &CommitAiConfig) -> Result<String, String> {
+    request_ai_message(ai, &build_prompt(worktree_path, files, ai)?)
+}
+
+/// Generate a message from `commit.ai` for the changes staged in
+/// `worktree_path`, passing the text to `on_delta` as it streams in so the
+/// commit box can fill in live. Returns the whole message, trimmed.
+pub fn generate_commit_message_streaming(
+    worktree_path: &Path,
+    ai: &CommitAiConfig,
+    on_delta: impl FnMut(&str),
+) -> Result<String, String> {
+    if !ai_configured(ai) {
+        return Err("Set commit.ai baseUrl, apiKey and model to generate commit messages".to_string());
+    }
+    let files = git::diff_cached_files(worktree_path).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Err(GitError::NoChanges.to_string());
+    }
+    stream_ai_message(ai, &build_prompt(worktree_path, &files, ai)?, on_delta)
+}
+
+/// Message for a squash merge of `branch`, whose changes are staged in
+/// `repo_path`. Generated from the AI config when it is configured; otherwise,
+/// or if generation fails, one naming the branch.
+pub fn squash_commit_message(repo_path: &Path, branch: &str, ai: &CommitAiConfig) -> String {
+    let fallback = format!("Squash merge branch '{}'", branch);
+    if !ai_configured(ai) {
+        return fallback;
+    }
+    let files = git::diff_cached_files(repo_path).unwrap_or_default();
+    generate_ai_message(repo_path, &files, ai).unwrap_or_else(|e| {
+        log::warn!("[quick_commit] AI squash message generation failed, using fallback: {}", e);
+        fallback
+    })
+}
+
+fn head_sha(repo_path: &Path) -> Result<String, GitError> {
+    let repo = Repository::open(repo_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.id().to_string())
+}
+
+/// Stage everything in `worktree_path`, commit it, and optionally push the
+/// branch (setting its upstream if it has none).
+///
+/// Without an explicit `message`, one is generated from the AI config when an
+/// API key is set (or an Ollama endpoint is configured); otherwise, or if
+/// generation fails, a fallback message is used.
+pub fn quick_commit_push(
+    worktree_path: &Path,
+    message: Option<&str>,
+    push: bool,
+    ai: &CommitAiConfig,
+    retry: &NetworkRetry,
+) -> Result<QuickCommitResult, QuickCommitError> {
+    git::stage_all(worktree_path).map_err(QuickCommitError::at(QuickCommitStep::Stage))?;
+    let files = git::diff_cached_files(worktree_path).map_err(QuickCommitError::at(QuickCommitStep::Stage))?;
+    if files.is_empty() {
+        return Err(QuickCommitError::at(QuickCommitStep::Stage)(GitError::NoChanges));
+    }
+
+    let mut ai_used = false;
+    let mut ai_error = None;
+    let message = match message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => message.to_string(),
+        None if ai_configured(ai) => match generate_ai_message(worktree_path, &files, ai) {
+            Ok(message) => {
+                ai_used = true;
+                message
+            }
+            Err(e) => {
+                log::warn!("[quick_commit] AI message generation failed, using fallback: {}", e);
+                ai_error = Some(e);
+                fallback_message(files.len())
+            }
+        },
+        None => fallback_message(files.len()),
+    };
+
+    git::commit_staged(worktree_path, &message).map_err(QuickCommitError::at(QuickCommitStep::Commit))?;
+    let sha = head_sha(worktree_path).map_err(QuickCommitError::at(QuickCommitStep::Commit))?;
+
+    let push_status = if push {
+        git::push_current_branch(worktree_path, retry).map_err(|source| QuickCommitError {
+            step: QuickCommitStep::Push,
+            source,
+            sha: Some(sha.clone()),
+        })?;
+        PushStatus::Pushed
+    } else {
+        PushStatus::Skipped
+    };
+
+    Ok(QuickCommitResult {
+        sha,
+        message,
+        files: files.len(),
+        ai_used,
+        ai_error,
+        push: push_status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    }
+
+    fn init_repo() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-quick-commit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        run_git(&dir, &["init", "-b", "main"]);
+        run_git(&dir, &["config", "user.email", "test@example.com"]);
+        run_git(&dir, &["config", "user.name", "Test"]);
+        run_git(&dir, &["config", "commit.gpgsign", "false"]);
+        std::fs::write(dir.join("README.md"), "hello\n").unwrap();
+        run_git(&dir, &["add", "."]);
+        run_git(&dir, &["commit", "-m", "initial"]);
+        dir
+    }
+
+    #[test]
+    fn commits_with_fallback_message_without_ai_key() {
+        let repo = init_repo();
+        std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+        std::fs::write(repo.join("README.md"), "changed\n").unwrap();
+
+        let result = quick_commit_push(&repo, None, false, &CommitAiConfig::default(), &NetworkRetry::without_notices()).unwrap();
+        assert_eq!(result.message, "Update 2 files");
+        assert_eq!(result.files, 2);
+        assert!(!result.ai_used);
+        assert_eq!(result.ai_error, None);
+        assert_eq!(result.push, PushStatus::Skipped);
+        assert_eq!(result.sha, head_sha(&repo).unwrap());
+        assert!(git::diff_cached_files(&repo).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn explicit_message_wins_and_clean_tree_reports_no_changes() {
+        let repo = init_repo();
+        std::fs::write(repo.join("b.txt"), "b\n").unwrap();
+        let result = quick_commit_push(&repo, Some("  Add b  "), false, &CommitAiConfig::default(), &NetworkRetry::without_notices()).unwrap();
+        assert_eq!(result.message, "Add b");
+
+        let err = quick_commit_push(&repo, None, false, &CommitAiConfig::default(), &NetworkRetry::without_notices()).unwrap_err();
+        assert_eq!(err.step, QuickCommitStep::Stage);
+        assert!(matches!(err.source, GitError::NoChanges));
+        assert_eq!(err.sha, None);
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn push_failure_reports_step_and_landed_commit() {
+        let repo = init_repo();
+        std::fs::write(repo.join("c.txt"), "c\n").unwrap();
+
+        // No "origin" remote, so the push step fails after the commit lands
+        let err = quick_commit_push(&repo, Some("Add c"), true, &CommitAiConfig::default(), &NetworkRetry::without_notices()).unwrap_err();
+        assert_eq!(err.step, QuickCommitStep::Push);
+        assert_eq!(err.sha, Some(head_sha(&repo).unwrap()));
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn prompt_template_and_curl_quoting() {
+        let prompt = apply_prompt_template("{{ branch }}: {{diff}}", &[("diff", "+x"), ("branch", "main")]);
+        assert_eq!(prompt, "main: +x");
+        assert_eq!(curl_config_quote("a \"b\"\\\n"), r#""a \"b\"\\\n""#);
+        assert_eq!(chat_completions_endpoint("https://api.example.com/v1/"), "https://api.example.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn parses_streamed_chunks_and_plain_bodies() {
+        let chunk = r#"data: {"choices":[{"delta":{"content":"feat: add"}}]}"#;
+        assert_eq!(parse_stream_line(AiProvider::Openai, chunk), Some(StreamLine::Delta("feat: add".to_string())));
+        let role_only = r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#;
+        assert_eq!(parse_stream_line(AiProvider::Openai, role_only), Some(StreamLine::Delta(String::new())));
+        assert_eq!(parse_stream_line(AiProvider::Openai, "data: [DONE]"), Some(StreamLine::Done));
+        // Blank separators, comments and a non-streaming body aren't SSE data
+        assert_eq!(parse_stream_line(AiProvider::Openai, ""), None);
+        assert_eq!(parse_stream_line(AiProvider::Openai, ": keep-alive"), None);
+        let body = r#"{"choices":[{"message":{"content":" fix: x \n"}}]}"#;
+        assert_eq!(parse_stream_line(AiProvider::Openai, body), None);
+
+        assert_eq!(completion_content(AiProvider::Openai, body).unwrap(), "fix: x");
+        assert!(completion_content(AiProvider::Openai, r#"{"choices":[]}"#).is_err());
+        assert!(check_http_status("200", "").is_ok());
+        assert!(check_http_status("401", "bad key").unwrap_err().contains("HTTP 401: bad key"));
+    }
+
+    fn ai_config(provider: AiProvider, base_url: &str) -> CommitAiConfig {
+        CommitAiConfig {
+            provider,
+            base_url: base_url.to_string(),
+            api_key: " secret ".to_string(),
+            model: "some-model".to_string(),
+            prompt: "{{ diff }}".to_string(),
+            temperature: 0.5,
+            max_tokens: 64,
+            timeout_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn builds_openai_request() {
+        let request = build_ai_request(&ai_config(AiProvider::Openai, "https://api.openai.com/v1/"), "+x", true);
+        assert_eq!(request.url, "https://api.openai.com/v1/chat/completions");
+        assert_eq!(request.headers, ["Content-Type: application/json", "Authorization: Bearer secret"]);
+        assert_eq!(
+            request.body,
+            serde_json::json!({
+                "model": "some-model",
+                "messages": [{ "role": "user", "content": "+x" }],
+                "temperature": 0.5,
+                "max_tokens": 64,
+                "stream": true,
+            })
+        );
+        let request = build_ai_request(&ai_config(AiProvider::Openai, "https://api.openai.com/v1"), "+x", false);
+        assert_eq!(request.body.get("stream"), None);
+    }
+
+    #[test]
+    fn builds_anthropic_request() {
+        let ai = ai_config(AiProvider::Anthropic, "https://api.anthropic.com");
+        let request = build_ai_request(&ai, "+x", false);
+        assert_eq!(request.url, "https://api.anthropic.com/v1/messages");
+        assert_eq!(
+            request.headers,
+            ["Content-Type: application/json", "x-api-key: secret", "anthropic-version: 2023-06-01"]
+        );
+        assert_eq!(
+            request.body,
+            serde_json::json!({
+                "model": "some-model",
+                "messages": [{ "role": "user", "content": "+x" }],
+                "temperature": 0.5,
+                "max_tokens": 64,
+                "stream": false,
+            })
+        );
+        assert_eq!(ai_endpoint(&ai_config(AiProvider::Anthropic, "https://proxy/v1/")), "https://proxy/v1/messages");
+
+        let body = r#"{"content":[{"type":"text","text":"feat: a"},{"type":"text","text":"dd x"}]}"#;
+        assert_eq!(completion_content(AiProvider::Anthropic, body).unwrap(), "feat: add x");
+        let delta = r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"feat"}}"#;
+        assert_eq!(parse_stream_line(AiProvider::Anthropic, delta), Some(StreamLine::Delta("feat".to_string())));
+        assert_eq!(parse_stream_line(AiProvider::Anthropic, "event: content_block_delta"), None);
+        assert_eq!(parse_stream_line(AiProvider::Anthropic, r#"data: {"type":"message_stop"}"#), Some(StreamLine::Done));
+        let error = r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(parse_stream_line(AiProvider::Anthropic, error), Some(StreamLine::Error("Overloaded".to_string())));
+    }
+
+    #[test]
+    fn builds_ollama_request_without_a_key() {
+        let mut ai = ai_config(AiProvider::Ollama, "http://localhost:11434");
+        let request = build_ai_request(&ai, "+x", true);
+        assert_eq!(request.url, "http://localhost:11434/api/chat");
+        assert_eq!(request.headers, ["Content-Type: application/json"]);
+        assert_eq!(
+            request.body,
+            serde_json::json!({
+                "model": "some-model",
+                "messages": [{ "role": "user", "content": "+x" }],
+                "stream": true,
+                "options": { "temperature": 0.5, "num_predict": 64 },
+            })
+        );
+        ai.api_key.clear();
+        assert!(ai_configured(&ai));
+        assert!(!ai_configured(&CommitAiConfig { api_key: String::new(), ..ai_config(AiProvider::Openai, "x") }));
+
+        let line = r#"{"model":"m","message":{"role":"assistant","content":"fix"},"done":false}"#;
+        assert_eq!(parse_stream_line(AiProvider::Ollama, line), Some(StreamLine::Delta("fix".to_string())));
+        assert_eq!(parse_stream_line(AiProvider::Ollama, "200"), None);
+        assert_eq!(
+            parse_stream_line(AiProvider::Ollama, r#"{"error":"model not found"}"#),
+            Some(StreamLine::Error("model not found".to_string()))
+        );
+        assert_eq!(completion_content(AiProvider::Ollama, line).unwrap(), "fix");
+    }
+
+    #[test]
+    fn streaming_generation_needs_ai_config_and_staged_changes() {
+        let repo = init_repo();
+        let err = generate_commit_message_streaming(&repo, &CommitAiConfig::default(), |_| {}).unwrap_err();
+        assert!(err.contains("apiKey"), "{}", err);
+
+        let ai = CommitAiConfig { api_key: "key".to_string(), ..CommitAiConfig::default() };
+        let err = generate_commit_message_streaming(&repo, &ai, |_| unreachable!()).unwrap_err();
+        assert_eq!(err, GitError::NoChanges.to_string());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+}
@@ -0,0 +1,257 @@
+//! Moving a project's repository on disk.
+//!
+//! When a repository directory is moved, the project and every worktree
+//! stored under the old location point at dead paths. Relocation checks the
+//! new directory is the same repository, rewrites those paths, repairs git's
+//! worktree links and reports worktrees that still don't resolve as stale,
+//! instead of failing the whole move.
+
+use crate::events::{self, ProjectPathMissing};
+use crate::git;
+use crate::path_utils;
+use crate::state::Project;
+use crate::worktree::WorktreeError;
+use log::info;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+lazy_static::lazy_static! {
+    /// Projects already reported missing this run, so the event fires once each
+    static ref REPORTED_MISSING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Result of `update_project_path`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRelocation {
+    pub project: Project,
+    /// Worktrees whose path was under the old repository path and got rewritten
+    pub rewritten_worktree_ids: Vec<String>,
+    /// Worktrees git doesn't list from the new location; kept, but likely to be dropped
+    pub stale_worktree_ids: Vec<String>,
+}
+
+/// `path` moved from under `old_root` to under `new_root`, or None if it wasn't under `old_root`
+pub fn rewrite_prefix(path: &str, old_root: &Path, new_root: &Path) -> Option<String> {
+    let relative = Path::new(path).strip_prefix(old_root).ok()?;
+    let moved = if relative.as_os_str().is_empty() {
+        new_root.to_path_buf()
+    } else {
+        new_root.join(relative)
+    };
+    Some(path_utils::normalize_path_string(&moved))
+}
+
+fn path_key(path: &Path) -> PathBuf {
+    path_utils::canonicalize_for_storage(path)
+}
+
+/// Check `new_root` holds the project's repository. A recorded root commit must
+/// match; without one, the new repository must still have a registration for
+/// one of the project's worktrees. A project with neither is accepted.
+fn verify_same_repository(project: &Project, new_root: &Path) -> Result<Option<String>, WorktreeError> {
+    if !git::is_git_repo(new_root) {
+        return Err(WorktreeError::NotARepository);
+    }
+    let root_commit = git::root_commit(new_root)?;
+    let mismatch = || WorktreeError::RepositoryMismatch(path_utils::normalize_path_string(new_root));
+
+    if let Some(expected) = &project.root_commit {
+        if root_commit.as_ref() != Some(expected) {
+            return Err(mismatch());
+        }
+    } else if !project.worktrees.is_empty() {
+        // Registrations keep the paths they were created with until repaired
+        let registered: HashSet<String> = git::list_registered_worktrees(new_root)?
+            .into_iter()
+            .map(|w| w.path)
+            .collect();
+        if !project.worktrees.iter().any(|w| registered.contains(&w.path)) {
+            return Err(mismatch());
+        }
+    }
+    Ok(root_commit)
+}
+
+/// Point `project` at `new_path`. Worktrees stored under the old project path
+/// are moved along with it; worktrees elsewhere keep their path. Nothing in
+/// `project` changes unless the new path is verified to be the same repository.
+pub fn relocate_project(project: &mut Project, new_path: &Path) -> Result<ProjectRelocation, WorktreeError> {
+    let new_root = path_utils::canonicalize_for_storage(new_path);
+    let root_commit = verify_same_repository(project, &new_root)?;
+    let old_root = PathBuf::from(&project.path);
+
+    project.path = path_utils::normalize_path_string(&new_root);
+    project.root_commit = root_commit.or(project.root_commit.take());
+
+    let mut rewritten_worktree_ids = Vec::new();
+    for wt in &mut project.worktrees {
+        if let Some(moved) = rewrite_prefix(&wt.path, &old_root, &new_root) {
+            wt.path = moved;
+            rewritten_worktree_ids.push(wt.id.clone());
+        }
+    }
+
+    // Links point both ways (worktree `.git` file and the repository's
+    // registration) and both may hold old absolute paths
+    let existing: Vec<PathBuf> = project
+        .worktrees
+        .iter()
+        .map(|w| PathBuf::from(&w.path))
+        .filter(|p| p.exists())
+        .collect();
+    if !existing.is_empty() {
+        if let Err(e) = git::repair_worktrees(&new_root, &existing) {
+            info!("[relocate_project] {}", e);
+        }
+    }
+
+    let registered: HashSet<PathBuf> = git::list_registered_worktrees(&new_root)?
+        .iter()
+        .map(|w| Path::new(&w.path))
+        .filter(|p| p.exists())
+        .map(path_key)
+        .collect();
+    let stale_worktree_ids = project
+        .worktrees
+        .iter()
+        .filter(|w| !registered.contains(&path_key(Path::new(&w.path))))
+        .map(|w| w.id.clone())
+        .collect();
+
+    REPORTED_MISSING.lock().remove(&project.id);
+
+    Ok(ProjectRelocation {
+        project: project.clone(),
+        rewritten_worktree_ids,
+        stale_worktree_ids,
+    })
+}
+
+/// Emit `project-path-missing` for projects whose directory is gone, once per
+/// project per run
+pub fn report_missing_projects(app: &AppHandle, projects: &[Project]) {
+    let mut reported = REPORTED_MISSING.lock();
+    for project in projects {
+        if Path::new(&project.path).exists() || !reported.insert(project.id.clone()) {
+            continue;
+        }
+        eprintln!("[relocate] Project '{}' not found at {}", project.name, project.path);
+        let _ = app.emit(
+            events::PROJECT_PATH_MISSING,
+            ProjectPathMissing {
+                project_id: project.id.clone(),
+                name: project.name.clone(),
+                path: project.path.clone(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Worktree;
+    use std::process::Command;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn worktree(id: &str, path: &Path) -> Worktree {
+        Worktree {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: path_utils::normalize_path_string(path),
+            branch: id.to_string(),
+            created_at: String::new(),
+            order: 0,
+            last_results: Default::default(),
+            pending_cleanup: false,
+            layout: None,
+        }
+    }
+
+    /// A repo at `<base>/before/repo` with an inner and a sibling worktree
+    fn repo_with_worktrees() -> (PathBuf, Project) {
+        let base = path_key(&std::env::temp_dir()).join(format!("shellflow-relocate-{}", uuid::Uuid::new_v4()));
+        let repo = base.join("before").join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        run_git(&repo, &["init", "-q"]);
+        run_git(&repo, &["config", "user.name", "shellflow"]);
+        run_git(&repo, &["config", "user.email", "shellflow@example.com"]);
+        // Distinct content so two fixtures never share a root commit
+        std::fs::write(repo.join("a.txt"), base.to_string_lossy().as_bytes()).unwrap();
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "init"]);
+        run_git(&repo, &["worktree", "add", "-q", "-b", "inner", ".worktrees/inner"]);
+        let sibling = base.join("sibling");
+        run_git(&repo, &["worktree", "add", "-q", "-b", "sibling", sibling.to_str().unwrap()]);
+
+        let mut project = crate::worktree::create_project(&repo).unwrap();
+        project.worktrees = vec![
+            worktree("inner", &repo.join(".worktrees").join("inner")),
+            worktree("sibling", &sibling),
+            worktree("gone", &repo.join(".worktrees").join("gone")),
+        ];
+        (base, project)
+    }
+
+    #[test]
+    fn rewrites_paths_only_under_the_old_root() {
+        let old = Path::new("/code/repo");
+        let new = Path::new("/src/repo");
+        assert_eq!(rewrite_prefix("/code/repo/.worktrees/a", old, new).as_deref(), Some("/src/repo/.worktrees/a"));
+        assert_eq!(rewrite_prefix("/code/repo", old, new).as_deref(), Some("/src/repo"));
+        // Component-wise, so a sibling sharing a name prefix stays put
+        assert_eq!(rewrite_prefix("/code/repo-other/a", old, new), None);
+        assert_eq!(rewrite_prefix("/elsewhere/a", old, new), None);
+    }
+
+    #[test]
+    fn relocates_a_moved_repository_and_its_worktrees() {
+        let (base, mut project) = repo_with_worktrees();
+        let moved = base.join("after").join("repo");
+        std::fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        std::fs::rename(base.join("before").join("repo"), &moved).unwrap();
+
+        let relocation = relocate_project(&mut project, &moved).unwrap();
+        assert_eq!(project.path, path_utils::normalize_path_string(&moved));
+        assert_eq!(relocation.rewritten_worktree_ids, vec!["inner", "gone"]);
+        assert_eq!(
+            project.worktrees[0].path,
+            path_utils::normalize_path_string(&moved.join(".worktrees").join("inner"))
+        );
+        assert_eq!(project.worktrees[1].path, path_utils::normalize_path_string(&base.join("sibling")));
+        // The repaired worktrees resolve again; the missing one is stale, not an error
+        assert_eq!(relocation.stale_worktree_ids, vec!["gone"]);
+        assert!(git::is_git_repo(&moved.join(".worktrees").join("inner")));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn rejects_a_different_repository() {
+        let (base, mut project) = repo_with_worktrees();
+        let (other_base, other) = repo_with_worktrees();
+        let original_path = project.path.clone();
+
+        let err = relocate_project(&mut project, Path::new(&other.path)).unwrap_err();
+        assert!(matches!(err, WorktreeError::RepositoryMismatch(_)));
+        assert_eq!(project.path, original_path);
+
+        let not_a_repo = base.join("plain");
+        std::fs::create_dir_all(&not_a_repo).unwrap();
+        assert!(matches!(
+            relocate_project(&mut project, &not_a_repo),
+            Err(WorktreeError::NotARepository)
+        ));
+
+        let _ = std::fs::remove_dir_all(&base);
+        let _ = std::fs::remove_dir_all(&other_base);
+    }
+}
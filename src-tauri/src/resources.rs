@@ -0,0 +1,402 @@
+//! CPU and memory use of terminal process trees.
+//!
+//! One snapshot of the system process table is taken per request and every
+//! session's tree (its shell plus all descendants) is summed from it, so the
+//! cost doesn't grow with the number of terminals. CPU is a percentage of one
+//! core, like `ps`; processes that exit mid-sample simply drop out. Linux reads
+//! /proc, macOS runs `ps`, and Windows walks a toolhelp process snapshot.
+
+use crate::state::{AppState, Project};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+#[cfg(any(target_os = "linux", windows))]
+use std::time::Duration;
+
+/// Gap between the two process table reads CPU deltas are taken over
+#[cfg(any(target_os = "linux", windows))]
+pub const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One row of the process table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessSample {
+    pub ppid: u32,
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+}
+
+/// Totals over a process and its descendants
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TreeUsage {
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub process_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub pty_id: String,
+    pub worktree_id: String,
+    pub pid: u32,
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub process_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeUsage {
+    pub worktree_id: String,
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub session_count: usize,
+}
+
+/// Result of `get_resource_usage`. Sessions whose shell already exited are left out.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    /// False where the process table can't be sampled; everything else is then empty
+    pub supported: bool,
+    pub sessions: Vec<SessionUsage>,
+    /// Heaviest CPU user first
+    pub worktrees: Vec<WorktreeUsage>,
+}
+
+/// Sum `root` and everything below it in `table`. None if `root` isn't in the
+/// table (it exited). Processes whose parent is missing are orphans and only
+/// belong to a tree they're still linked into.
+pub fn aggregate_tree(root: u32, table: &HashMap<u32, ProcessSample>) -> Option<TreeUsage> {
+    let root_sample = table.get(&root)?;
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&pid, sample) in table {
+        if pid != sample.ppid {
+            children.entry(sample.ppid).or_default().push(pid);
+        }
+    }
+
+    let mut usage = TreeUsage {
+        cpu_percent: root_sample.cpu_percent,
+        rss_bytes: root_sample.rss_bytes,
+        process_count: 1,
+    };
+    let mut seen = HashSet::from([root]);
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        for &child in children.get(&pid).into_iter().flatten() {
+            // A pid reused while sampling could link back up the tree
+            if !seen.insert(child) {
+                continue;
+            }
+            let sample = &table[&child];
+            usage.cpu_percent += sample.cpu_percent;
+            usage.rss_bytes += sample.rss_bytes;
+            usage.process_count += 1;
+            pending.push(child);
+        }
+    }
+    Some(usage)
+}
+
+/// Parse `ps -axo pid=,ppid=,pcpu=,rss=` output (rss in KiB)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))] // Sampled from /proc elsewhere
+pub fn parse_ps_output(output: &str) -> HashMap<u32, ProcessSample> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let cpu_percent = fields.next()?.parse().ok()?;
+            let rss_kib: u64 = fields.next()?.parse().ok()?;
+            Some((pid, ProcessSample { ppid, cpu_percent, rss_bytes: rss_kib * 1024 }))
+        })
+        .collect()
+}
+
+/// Parent pid and utime + stime ticks from `/proc/<pid>/stat`. The command
+/// name is parenthesized and may itself contain spaces or parentheses.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn parse_proc_stat(content: &str) -> Option<(u32, u64)> {
+    let after_comm = &content[content.rfind(')')? + 1..];
+    // Fields from the process state (field 3) onward
+    let fields: Vec<&str> = after_comm.split_whitespace().collect();
+    let ppid = fields.get(1)?.parse().ok()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some((ppid, utime + stime))
+}
+
+#[cfg(target_os = "linux")]
+fn read_proc_table() -> HashMap<u32, (u32, u64, u64)> {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            // Either read fails if the process exits in between; it's skipped
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            let statm = std::fs::read_to_string(entry.path().join("statm")).ok()?;
+            let (ppid, ticks) = parse_proc_stat(&stat)?;
+            let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+            Some((pid, (ppid, ticks, resident_pages * page_size)))
+        })
+        .collect()
+}
+
+/// Samples from two reads of (ppid, CPU time, resident bytes) per pid taken
+/// `CPU_SAMPLE_INTERVAL` apart. `cpu_per_interval` is the CPU time one busy
+/// core accrues over the interval, in the same unit as the readings.
+#[cfg(any(target_os = "linux", windows))]
+fn samples_between(
+    before: &HashMap<u32, (u32, u64, u64)>,
+    after: HashMap<u32, (u32, u64, u64)>,
+    cpu_per_interval: f64,
+) -> HashMap<u32, ProcessSample> {
+    after
+        .into_iter()
+        .map(|(pid, (ppid, cpu_time, rss_bytes))| {
+            // Processes started during the interval have no baseline
+            let used = before.get(&pid).map_or(0, |&(_, start, _)| cpu_time.saturating_sub(start));
+            let cpu_percent = used as f64 / cpu_per_interval * 100.0;
+            (pid, ProcessSample { ppid, cpu_percent, rss_bytes })
+        })
+        .collect()
+}
+
+/// Snapshot the process table, or None where that isn't supported
+#[cfg(target_os = "linux")]
+pub fn sample_processes() -> Option<HashMap<u32, ProcessSample>> {
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+    let before = read_proc_table();
+    std::thread::sleep(CPU_SAMPLE_INTERVAL);
+    let after = read_proc_table();
+
+    let elapsed_ticks = ticks_per_sec * CPU_SAMPLE_INTERVAL.as_secs_f64();
+    Some(samples_between(&before, after, elapsed_ticks))
+}
+
+#[cfg(target_os = "macos")]
+pub fn sample_processes() -> Option<HashMap<u32, ProcessSample>> {
+    let output = std::process::Command::new("ps")
+        .args(["-axo", "pid=,ppid=,pcpu=,rss="])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| parse_ps_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parent pid, kernel + user time in 100 ns units, and working set per pid.
+/// Processes that can't be opened (other users', protected ones) are kept
+/// with zero usage so their children stay linked into the tree.
+#[cfg(windows)]
+fn read_process_table() -> Option<HashMap<u32, (u32, u64, u64)>> {
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    };
+    use windows_sys::Win32::System::ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    let filetime = |t: FILETIME| (u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime);
+    let usage = |pid: u32| -> Option<(u64, u64)> {
+        // SAFETY: the handle is checked before use and closed on every path
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+            let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+            let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+            let mut memory: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+            memory.cb = size;
+            let times_ok = GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user) != 0;
+            let memory_ok = K32GetProcessMemoryInfo(process, &mut memory, size) != 0;
+            CloseHandle(process);
+            Some((
+                if times_ok { filetime(kernel) + filetime(user) } else { 0 },
+                if memory_ok { memory.WorkingSetSize as u64 } else { 0 },
+            ))
+        }
+    };
+
+    // SAFETY: the snapshot handle is checked before use and closed once walked
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        let mut table = HashMap::new();
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+        let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+        while more {
+            let (cpu_time, rss_bytes) = usage(entry.th32ProcessID).unwrap_or((0, 0));
+            table.insert(entry.th32ProcessID, (entry.th32ParentProcessID, cpu_time, rss_bytes));
+            more = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+        Some(table)
+    }
+}
+
+#[cfg(windows)]
+pub fn sample_processes() -> Option<HashMap<u32, ProcessSample>> {
+    // GetProcessTimes counts in 100 ns intervals
+    const UNITS_PER_SEC: f64 = 10_000_000.0;
+    let before = read_process_table()?;
+    std::thread::sleep(CPU_SAMPLE_INTERVAL);
+    let after = read_process_table()?;
+    Some(samples_between(&before, after, UNITS_PER_SEC * CPU_SAMPLE_INTERVAL.as_secs_f64()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn sample_processes() -> Option<HashMap<u32, ProcessSample>> {
+    None
+}
+
+/// Entity ids a scope covers: None for "all", a project plus its worktrees,
+/// or a single worktree
+pub fn scope_entities(scope: &str, projects: &[Project]) -> Option<HashSet<String>> {
+    if scope == "all" {
+        return None;
+    }
+    let entities = match projects.iter().find(|p| p.id == scope) {
+        Some(project) => std::iter::once(project.id.clone())
+            .chain(project.worktrees.iter().map(|w| w.id.clone()))
+            .collect(),
+        None => HashSet::from([scope.to_string()]),
+    };
+    Some(entities)
+}
+
+/// Per-session usage from one process table, rolled up per worktree
+pub fn usage_for_sessions(sessions: &[(String, String, u32)], table: &HashMap<u32, ProcessSample>) -> ResourceUsage {
+    let mut usage = ResourceUsage { supported: true, sessions: Vec::new(), worktrees: Vec::new() };
+    let mut by_worktree: HashMap<&str, WorktreeUsage> = HashMap::new();
+
+    for (pty_id, worktree_id, pid) in sessions {
+        let Some(tree) = aggregate_tree(*pid, table) else { continue };
+        let entry = by_worktree.entry(worktree_id).or_insert_with(|| WorktreeUsage {
+            worktree_id: worktree_id.clone(),
+            cpu_percent: 0.0,
+            rss_bytes: 0,
+            session_count: 0,
+        });
+        entry.cpu_percent += tree.cpu_percent;
+        entry.rss_bytes += tree.rss_bytes;
+        entry.session_count += 1;
+        usage.sessions.push(SessionUsage {
+            pty_id: pty_id.clone(),
+            worktree_id: worktree_id.clone(),
+            pid: *pid,
+            cpu_percent: tree.cpu_percent,
+            rss_bytes: tree.rss_bytes,
+            process_count: tree.process_count,
+        });
+    }
+
+    usage.worktrees = by_worktree.into_values().collect();
+    usage
+        .worktrees
+        .sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent).then(b.rss_bytes.cmp(&a.rss_bytes)));
+    usage.sessions.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    usage
+}
+
+/// Live sessions in `scope` ("all", a project id, or a worktree id) as
+/// (pty id, worktree id, shell pid)
+pub fn scope_sessions(state: &AppState, scope: &str) -> Vec<(String, String, u32)> {
+    let entities = scope_entities(scope, &state.persisted.read().projects);
+    state
+        .pty_sessions
+        .read()
+        .iter()
+        .filter(|(_, s)| s.child_pid > 0)
+        .filter(|(_, s)| entities.as_ref().is_none_or(|e| e.contains(&s.worktree_id)))
+        .map(|(id, s)| (id.clone(), s.worktree_id.clone(), s.child_pid))
+        .collect()
+}
+
+/// Resource usage of `sessions`. Blocks for `CPU_SAMPLE_INTERVAL` on Linux and
+/// Windows, so run it off the main thread.
+pub fn resource_usage(sessions: &[(String, String, u32)]) -> ResourceUsage {
+    match sample_processes() {
+        Some(table) => usage_for_sessions(sessions, &table),
+        None => ResourceUsage { supported: false, sessions: Vec::new(), worktrees: Vec::new() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[(u32, u32, f64, u64)]) -> HashMap<u32, ProcessSample> {
+        rows.iter()
+            .map(|&(pid, ppid, cpu_percent, rss_bytes)| (pid, ProcessSample { ppid, cpu_percent, rss_bytes }))
+            .collect()
+    }
+
+    #[test]
+    fn aggregates_a_tree_and_ignores_orphans() {
+        let table = table(&[
+            (1, 0, 0.5, 10),
+            (100, 1, 1.0, 100), // shell
+            (101, 100, 50.0, 1_000), // dev server
+            (102, 101, 25.0, 500), // its worker
+            (200, 1, 80.0, 9_999), // unrelated
+            (300, 999, 5.0, 7), // orphan whose parent is gone
+        ]);
+        let usage = aggregate_tree(100, &table).unwrap();
+        assert_eq!(usage.process_count, 3);
+        assert_eq!(usage.rss_bytes, 1_600);
+        assert!((usage.cpu_percent - 76.0).abs() < 1e-9);
+
+        // The orphan only counts as its own root
+        assert_eq!(aggregate_tree(300, &table).unwrap().process_count, 1);
+        // An exited shell has no usage
+        assert_eq!(aggregate_tree(4242, &table), None);
+    }
+
+    #[test]
+    fn pid_cycles_do_not_loop() {
+        let table = table(&[(10, 11, 1.0, 1), (11, 10, 1.0, 1), (12, 12, 1.0, 1)]);
+        assert_eq!(aggregate_tree(10, &table).unwrap().process_count, 2);
+        assert_eq!(aggregate_tree(12, &table).unwrap().process_count, 1);
+    }
+
+    #[test]
+    fn parses_ps_and_proc_stat() {
+        let ps = "  100     1  12.5  2048\n  101   100   0.0   512\ngarbage\n";
+        let parsed = parse_ps_output(ps);
+        assert_eq!(parsed[&100], ProcessSample { ppid: 1, cpu_percent: 12.5, rss_bytes: 2048 * 1024 });
+        assert_eq!(parsed.len(), 2);
+
+        let stat = "4242 (my (odd) name) S 4000 4242 4242 0 -1 4194560 100 0 0 0 31 9 0 0 20 0 1 0 1000 1 1";
+        assert_eq!(parse_proc_stat(stat), Some((4000, 40)));
+        assert_eq!(parse_proc_stat("4242 (truncated"), None);
+    }
+
+    #[test]
+    fn rolls_sessions_up_per_worktree() {
+        let table = table(&[(100, 1, 10.0, 100), (101, 100, 5.0, 50), (200, 1, 40.0, 10), (300, 1, 1.0, 1)]);
+        let sessions = vec![
+            ("pty-a".to_string(), "wt-1".to_string(), 100),
+            ("pty-b".to_string(), "wt-1".to_string(), 300),
+            ("pty-c".to_string(), "wt-2".to_string(), 200),
+            ("pty-gone".to_string(), "wt-3".to_string(), 999),
+        ];
+        let usage = usage_for_sessions(&sessions, &table);
+        assert_eq!(usage.sessions.len(), 3);
+        let ids: Vec<_> = usage.worktrees.iter().map(|w| w.worktree_id.as_str()).collect();
+        assert_eq!(ids, vec!["wt-2", "wt-1"]);
+        assert_eq!(usage.worktrees[1].session_count, 2);
+        assert_eq!(usage.worktrees[1].rss_bytes, 151);
+    }
+}
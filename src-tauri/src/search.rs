@@ -0,0 +1,282 @@
+//! Fuzzy matching for the project/worktree switcher.
+//!
+//! Scores a query as a case-insensitive subsequence of each candidate, with
+//! bonuses for consecutive characters and matches at word boundaries, so the
+//! palette ranks the same way no matter which view asks.
+
+use crate::state::Project;
+use serde::Serialize;
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const BOUNDARY_BONUS: i64 = 20;
+const FIRST_CHAR_BONUS: i64 = 8;
+const GAP_PENALTY: i64 = 2;
+const LEADING_GAP_PENALTY: i64 = 1;
+
+/// Where a query matched a candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Character (not byte) indices of the matched characters in the candidate
+    pub positions: Vec<usize>,
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '-' | '_' | '/' | '.' | ' ' | ':')
+}
+
+/// Bonus for a match at `index`: start of the string, after a separator, or a
+/// lower-to-upper camelCase step
+fn boundary_bonus(chars: &[char], index: usize) -> i64 {
+    if index == 0 {
+        return BOUNDARY_BONUS + FIRST_CHAR_BONUS;
+    }
+    let prev = chars[index - 1];
+    if is_separator(prev) || (prev.is_lowercase() && chars[index].is_uppercase()) {
+        BOUNDARY_BONUS
+    } else {
+        0
+    }
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Best-scoring subsequence match of `query` in `candidate`, or None if the
+/// query's characters don't all appear in order. Whitespace in the query is ignored.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: vec![] });
+    }
+    if query.len() > chars.len() {
+        return None;
+    }
+
+    let n = chars.len();
+    // best[i][j]: best score with query[..=i] matched and query[i] at chars[j]
+    let mut best: Vec<Vec<Option<i64>>> = vec![vec![None; n]; query.len()];
+    let mut parent: Vec<Vec<usize>> = vec![vec![0; n]; query.len()];
+
+    for j in 0..n {
+        if chars_eq(query[0], chars[j]) {
+            best[0][j] = Some(MATCH_SCORE + boundary_bonus(&chars, j) - LEADING_GAP_PENALTY * j as i64);
+        }
+    }
+    for i in 1..query.len() {
+        for j in i..n {
+            if !chars_eq(query[i], chars[j]) {
+                continue;
+            }
+            let bonus = MATCH_SCORE + boundary_bonus(&chars, j);
+            let mut chosen: Option<(i64, usize)> = None;
+            for (k, prev) in best[i - 1].iter().enumerate().take(j).skip(i - 1) {
+                let Some(prev) = *prev else { continue };
+                let step = if k + 1 == j {
+                    CONSECUTIVE_BONUS
+                } else {
+                    -GAP_PENALTY * (j - k - 1) as i64
+                };
+                let score = prev + step;
+                if chosen.is_none_or(|(s, _)| score > s) {
+                    chosen = Some((score, k));
+                }
+            }
+            if let Some((score, k)) = chosen {
+                best[i][j] = Some(score + bonus);
+                parent[i][j] = k;
+            }
+        }
+    }
+
+    let last = query.len() - 1;
+    let (mut j, score) = (0..n)
+        .filter_map(|j| best[last][j].map(|s| (j, s)))
+        .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+    let mut positions = vec![j; query.len()];
+    for i in (1..query.len()).rev() {
+        j = parent[i][j];
+        positions[i - 1] = j;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Project,
+    Worktree,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchedField {
+    Name,
+    Branch,
+}
+
+/// A project or worktree matching a switcher query
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityMatch {
+    pub kind: EntityKind,
+    pub project_id: String,
+    pub worktree_id: Option<String>,
+    pub project_name: String,
+    /// Project or worktree name
+    pub name: String,
+    pub branch: Option<String>,
+    pub project_active: bool,
+    pub matched_field: MatchedField,
+    pub score: i64,
+    /// Character indices into the matched field, for highlighting
+    pub positions: Vec<usize>,
+}
+
+/// Rank projects (by name) and worktrees (by name or branch) against `query`.
+/// Ties keep sidebar order; an empty query returns everything in that order.
+pub fn search_entities(projects: &[Project], query: &str, limit: usize) -> Vec<EntityMatch> {
+    let mut ordered: Vec<&Project> = projects.iter().collect();
+    ordered.sort_by_key(|p| p.order);
+
+    let mut results = Vec::new();
+    for project in ordered {
+        if let Some(m) = fuzzy_match(query, &project.name) {
+            results.push(EntityMatch {
+                kind: EntityKind::Project,
+                project_id: project.id.clone(),
+                worktree_id: None,
+                project_name: project.name.clone(),
+                name: project.name.clone(),
+                branch: None,
+                project_active: project.is_active,
+                matched_field: MatchedField::Name,
+                score: m.score,
+                positions: m.positions,
+            });
+        }
+
+        let mut worktrees: Vec<_> = project.worktrees.iter().collect();
+        worktrees.sort_by_key(|w| w.order);
+        for wt in worktrees {
+            let by_name = fuzzy_match(query, &wt.name).map(|m| (MatchedField::Name, m));
+            let by_branch = (wt.branch != wt.name)
+                .then(|| fuzzy_match(query, &wt.branch).map(|m| (MatchedField::Branch, m)))
+                .flatten();
+            let best = match (by_name, by_branch) {
+                (Some(a), Some(b)) => Some(if b.1.score > a.1.score { b } else { a }),
+                (a, b) => a.or(b),
+            };
+            if let Some((field, m)) = best {
+                results.push(EntityMatch {
+                    kind: EntityKind::Worktree,
+                    project_id: project.id.clone(),
+                    worktree_id: Some(wt.id.clone()),
+                    project_name: project.name.clone(),
+                    name: wt.name.clone(),
+                    branch: Some(wt.branch.clone()),
+                    project_active: project.is_active,
+                    matched_field: field,
+                    score: m.score,
+                    positions: m.positions,
+                });
+            }
+        }
+    }
+
+    // Stable sort keeps sidebar order among equal scores
+    results.sort_by_key(|r| std::cmp::Reverse(r.score));
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Worktree;
+
+    fn worktree(id: &str, name: &str, branch: &str) -> Worktree {
+        Worktree {
+            id: id.to_string(),
+            name: name.to_string(),
+            path: format!("/repo/.worktrees/{}", name),
+            branch: branch.to_string(),
+            created_at: String::new(),
+            order: 0,
+            last_results: Default::default(),
+            pending_cleanup: false,
+            layout: None,
+        }
+    }
+
+    fn project(id: &str, name: &str, worktrees: Vec<Worktree>) -> Project {
+        Project {
+            id: id.to_string(),
+            name: name.to_string(),
+            path: format!("/{}", name),
+            worktrees,
+            order: 0,
+            is_active: true,
+            last_accessed_at: None,
+            disabled_config_layers: vec![],
+            last_focused_worktree_id: None,
+            root_commit: None,
+        }
+    }
+
+    #[test]
+    fn matches_subsequences_case_insensitively() {
+        let m = fuzzy_match("fb", "Foo-Bar").unwrap();
+        assert_eq!(m.positions, vec![0, 4]);
+        assert!(fuzzy_match("bf", "Foo-Bar").is_none());
+        assert!(fuzzy_match("foox", "foo").is_none());
+        assert_eq!(fuzzy_match("", "anything").unwrap().score, 0);
+    }
+
+    #[test]
+    fn prefers_boundaries_and_consecutive_runs() {
+        // "hd" should pick the word starts in "happy-dolphin", not the inner 'h'
+        let m = fuzzy_match("hd", "happy-dolphin").unwrap();
+        assert_eq!(m.positions, vec![0, 6]);
+
+        let consecutive = fuzzy_match("olp", "xdolphin").unwrap();
+        let scattered = fuzzy_match("olp", "xoxlxp").unwrap();
+        assert!(consecutive.score > scattered.score);
+
+        let prefix = fuzzy_match("feat", "feature-login").unwrap();
+        let inner = fuzzy_match("feat", "defeat-bugs").unwrap();
+        assert!(prefix.score > inner.score);
+    }
+
+    #[test]
+    fn positions_are_character_indices() {
+        let m = fuzzy_match("é2", "café-2").unwrap();
+        assert_eq!(m.positions, vec![3, 5]);
+    }
+
+    #[test]
+    fn search_ranks_across_projects_worktrees_and_branches() {
+        let projects = vec![
+            project("p1", "shellflow", vec![worktree("w1", "happy-dolphin", "happy-dolphin")]),
+            project("p2", "website", vec![worktree("w2", "docs", "fix/login-redirect")]),
+        ];
+
+        let results = search_entities(&projects, "login", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].worktree_id.as_deref(), Some("w2"));
+        assert_eq!(results[0].matched_field, MatchedField::Branch);
+        assert_eq!(results[0].positions, vec![4, 5, 6, 7, 8]);
+
+        let results = search_entities(&projects, "sh", 10);
+        assert_eq!(results[0].kind, EntityKind::Project);
+        assert_eq!(results[0].project_id, "p1");
+
+        // Empty query lists everything in sidebar order, capped by the limit
+        let all = search_entities(&projects, "", 3);
+        let ids: Vec<_> = all.iter().map(|r| r.worktree_id.clone().unwrap_or(r.project_id.clone())).collect();
+        assert_eq!(ids, vec!["p1", "w1", "p2"]);
+    }
+}
@@ -1,7 +1,8 @@
+use crate::config::ConfigLayer;
 use crate::path_utils;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -21,6 +22,15 @@ pub struct Project {
     pub is_active: bool,
     #[serde(default, rename = "lastAccessedAt")]
     pub last_accessed_at: Option<String>,
+    /// Config layers skipped when loading this project's config
+    #[serde(default, rename = "disabledConfigLayers")]
+    pub disabled_config_layers: Vec<ConfigLayer>,
+    /// Worktree that was focused when the user last left this project
+    #[serde(default, rename = "lastFocusedWorktreeId")]
+    pub last_focused_worktree_id: Option<String>,
+    /// Root commit of the repository, to recognize it after the directory moves
+    #[serde(default, rename = "rootCommit")]
+    pub root_commit: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +43,50 @@ pub struct Worktree {
     pub created_at: String,
     #[serde(default)]
     pub order: i32,
+    /// Last exit result per task name (plus "main" for the main command)
+    #[serde(default, rename = "lastResults")]
+    pub last_results: HashMap<String, TaskResult>,
+    /// Set when deletion couldn't remove the directory; cleanup is retried later
+    #[serde(default, rename = "pendingCleanup")]
+    pub pending_cleanup: bool,
+    /// Terminal tab and split arrangement, defined and interpreted by the frontend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<serde_json::Value>,
+}
+
+/// Key used in `Worktree::last_results` for the main command
+pub const MAIN_RESULT_KEY: &str = "main";
+
+/// Exit result of a task or main command session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskResult {
+    pub exit_code: Option<u32>,
+    pub finished_at: String,
+}
+
+impl Worktree {
+    /// Record a session result, dropping entries for tasks that are no longer configured
+    pub fn record_result(&mut self, key: &str, result: TaskResult, task_names: &[String]) {
+        self.last_results.insert(key.to_string(), result);
+        self.last_results
+            .retain(|k, _| k == MAIN_RESULT_KEY || task_names.iter().any(|name| name == k));
+    }
+}
+
+/// Changed files of a worktree, with the hidden ones counted as in `files-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedFiles {
+    pub files: Vec<FileChange>,
+    /// How many of `files` match `changes.hidePatterns`
+    pub hidden_count: usize,
+}
+
+impl ChangedFiles {
+    pub fn new(files: Vec<FileChange>) -> Self {
+        let hidden_count = files.iter().filter(|f| f.hidden).count();
+        Self { files, hidden_count }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +95,9 @@ pub struct FileChange {
     pub status: FileStatus,
     pub insertions: Option<usize>,
     pub deletions: Option<usize>,
+    /// Matches `changes.hidePatterns`: still a real change, but collapsed in the UI
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +143,32 @@ pub struct PersistedState {
     pub projects: Vec<Project>,
     #[serde(default, rename = "windowSize")]
     pub window_size: Option<WindowSize>,
+    /// Stashes created automatically by workflows that haven't been popped or dropped yet
+    #[serde(default, rename = "pendingStashes")]
+    pub pending_stashes: Vec<PendingStash>,
+    /// Unix ms each worktree last got a drift warning, for once-a-day suppression
+    #[serde(default, rename = "driftWarnedAt")]
+    pub drift_warned_at: HashMap<String, u64>,
+    /// Working-tree snapshots taken with `create_working_snapshot`, oldest first
+    #[serde(default, rename = "workingSnapshots")]
+    pub working_snapshots: Vec<WorkingSnapshot>,
+}
+
+impl PersistedState {
+    /// Forget `worktree_path`'s snapshots, or only `hash` among them. Returns
+    /// the forgotten hashes no other snapshot still uses, whose refs can go.
+    pub fn forget_working_snapshots(&mut self, worktree_path: &str, hash: Option<&str>) -> Vec<String> {
+        let worktree_path = path_utils::normalize_path_string(Path::new(worktree_path));
+        let (forgotten, kept): (Vec<WorkingSnapshot>, Vec<WorkingSnapshot>) = std::mem::take(&mut self.working_snapshots)
+            .into_iter()
+            .partition(|s| s.worktree_path == worktree_path && hash.is_none_or(|h| s.hash == h));
+        self.working_snapshots = kept;
+        forgotten
+            .into_iter()
+            .map(|s| s.hash)
+            .filter(|h| !self.working_snapshots.iter().any(|s| &s.hash == h))
+            .collect()
+    }
 }
 
 impl Default for PersistedState {
@@ -93,26 +176,79 @@ impl Default for PersistedState {
         Self {
             projects: vec![],
             window_size: None,
+            pending_stashes: vec![],
+            drift_warned_at: HashMap::new(),
+            working_snapshots: vec![],
         }
     }
 }
 
+/// A workflow auto-stash along with the files it captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingStash {
+    pub stash_id: String,
+    pub repo_path: String,
+    pub files: Vec<String>,
+    pub created_at: String,
+}
+
+/// A non-destructive snapshot of a worktree's changes, kept as a commit hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingSnapshot {
+    pub hash: String,
+    pub worktree_path: String,
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
 
 #[derive(Debug, Default)]
 pub struct ProjectSyncState {
     pub last_sync_at_ms: u64,
 }
 
+/// The project the frontend is showing, used for project-aware mappings
+#[derive(Debug, Default)]
+pub struct ActiveProject {
+    pub project_id: Option<String>,
+    /// Focused worktree within the project, if any
+    pub worktree_id: Option<String>,
+    /// Bumped on every switch so a debounced update can tell it is stale
+    pub generation: u64,
+}
+
 #[allow(dead_code)]
 pub struct PtySession {
     pub worktree_id: String,
     pub child_pid: u32,
+    /// Directory the session was started in
+    pub worktree_path: String,
+    /// Configured task this session runs, if it was started as one
+    pub task_name: Option<String>,
 }
 
 pub struct AppState {
     pub persisted: RwLock<PersistedState>,
     pub pty_sessions: RwLock<HashMap<String, Arc<PtySession>>>,
     pub project_sync_state: RwLock<ProjectSyncState>,
+    pub active_project: RwLock<ActiveProject>,
+    /// Worktree ids with a deletion underway, see `begin_deletion`
+    deleting: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Marks a worktree as being deleted until dropped. Whoever holds it is the
+/// only one allowed to remove the worktree from state.
+pub struct DeletionGuard {
+    deleting: Arc<Mutex<HashSet<String>>>,
+    worktree_id: String,
+}
+
+impl Drop for DeletionGuard {
+    fn drop(&mut self) {
+        self.deleting.lock().remove(&self.worktree_id);
+    }
 }
 
 impl AppState {
@@ -121,7 +257,48 @@ impl AppState {
             persisted: RwLock::new(PersistedState::default()),
             pty_sessions: RwLock::new(HashMap::new()),
             project_sync_state: RwLock::new(ProjectSyncState::default()),
+            active_project: RwLock::new(ActiveProject::default()),
+            deleting: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Claim the deletion of a worktree. None if another caller is already deleting it.
+    pub fn begin_deletion(&self, worktree_id: &str) -> Option<DeletionGuard> {
+        if !self.deleting.lock().insert(worktree_id.to_string()) {
+            return None;
+        }
+        Some(DeletionGuard {
+            deleting: Arc::clone(&self.deleting),
+            worktree_id: worktree_id.to_string(),
+        })
+    }
+
+    pub fn is_being_deleted(&self, worktree_id: &str) -> bool {
+        self.deleting.lock().contains(worktree_id)
+    }
+
+    /// Id of the worktree stored at `path`, compared by canonical path
+    pub fn worktree_id_for_path(&self, path: &str) -> Option<String> {
+        let key = path_utils::canonicalize_for_storage(Path::new(path));
+        self.persisted
+            .read()
+            .projects
+            .iter()
+            .flat_map(|p| p.worktrees.iter())
+            .find(|w| path_utils::canonicalize_for_storage(Path::new(&w.path)) == key)
+            .map(|w| w.id.clone())
+    }
+
+    /// Drop a worktree from state. Returns false if it was already gone.
+    pub fn remove_worktree_entry(&self, worktree_id: &str) -> bool {
+        let mut persisted = self.persisted.write();
+        for project in &mut persisted.projects {
+            if let Some(idx) = project.worktrees.iter().position(|w| w.id == worktree_id) {
+                project.worktrees.remove(idx);
+                return true;
+            }
         }
+        false
     }
 
     pub fn load_or_default() -> Self {
@@ -151,6 +328,12 @@ impl AppState {
                                 }
                             }
 
+                            // A moved repository keeps its worktree records so
+                            // update_project_path can rewrite them
+                            if !Path::new(&project.path).exists() {
+                                continue;
+                            }
+
                             let before_count = project.worktrees.len();
                             project.worktrees.retain(|w| {
                                 let exists = Path::new(&w.path).exists();
@@ -183,6 +366,216 @@ impl AppState {
         state
     }
 
+    /// Find the path of the project that owns a worktree
+    pub fn worktree_project_path(&self, worktree_id: &str) -> Option<String> {
+        self.persisted
+            .read()
+            .projects
+            .iter()
+            .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
+            .map(|p| p.path.clone())
+    }
+
+    /// The project that owns a worktree
+    pub fn worktree_project(&self, worktree_id: &str) -> Option<Project> {
+        self.persisted
+            .read()
+            .projects
+            .iter()
+            .find(|p| p.worktrees.iter().any(|w| w.id == worktree_id))
+            .cloned()
+    }
+
+    /// The project that `dir` is the root of, or one of whose worktrees it is
+    pub fn project_for_directory(&self, dir: &str) -> Option<Project> {
+        self.persisted
+            .read()
+            .projects
+            .iter()
+            .find(|p| p.path == dir || p.worktrees.iter().any(|w| w.path == dir))
+            .cloned()
+    }
+
+    /// Record a task/main result on a worktree. Returns false if the worktree is unknown.
+    pub fn record_worktree_result(
+        &self,
+        worktree_id: &str,
+        key: &str,
+        result: TaskResult,
+        task_names: &[String],
+    ) -> bool {
+        let mut persisted = self.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id);
+
+        match worktree {
+            Some(worktree) => {
+                worktree.record_result(key, result, task_names);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark a worktree as pending cleanup. Returns false if the worktree is unknown.
+    pub fn set_worktree_pending_cleanup(&self, worktree_id: &str, pending: bool) -> bool {
+        let mut persisted = self.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id);
+
+        match worktree {
+            Some(worktree) => {
+                worktree.pending_cleanup = pending;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace a worktree's saved layout (None clears it). Returns false if the worktree is unknown.
+    pub fn set_worktree_layout(&self, worktree_id: &str, layout: Option<serde_json::Value>) -> bool {
+        let mut persisted = self.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id);
+
+        match worktree {
+            Some(worktree) => {
+                worktree.layout = layout;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reset a worktree's UI-only fields (its layout) to defaults, leaving the
+    /// git worktree and task results alone. Returns the updated worktree.
+    pub fn reset_worktree_ui_state(&self, worktree_id: &str) -> Option<Worktree> {
+        let mut persisted = self.persisted.write();
+        let worktree = persisted
+            .projects
+            .iter_mut()
+            .flat_map(|p| p.worktrees.iter_mut())
+            .find(|w| w.id == worktree_id)?;
+        worktree.layout = None;
+        Some(worktree.clone())
+    }
+
+    /// Remember `worktree_id` as the project's last-focused worktree. Returns true
+    /// if anything changed; a worktree outside the project is ignored.
+    pub fn set_last_focused_worktree(&self, project_id: &str, worktree_id: &str) -> bool {
+        let mut persisted = self.persisted.write();
+        let Some(project) = persisted.projects.iter_mut().find(|p| p.id == project_id) else {
+            return false;
+        };
+        if !project.worktrees.iter().any(|w| w.id == worktree_id)
+            || project.last_focused_worktree_id.as_deref() == Some(worktree_id)
+        {
+            return false;
+        }
+        project.last_focused_worktree_id = Some(worktree_id.to_string());
+        true
+    }
+
+    /// The project's last-focused worktree, if it still exists
+    pub fn last_focused_worktree(&self, project_id: &str) -> Option<String> {
+        let persisted = self.persisted.read();
+        let project = persisted.projects.iter().find(|p| p.id == project_id)?;
+        project
+            .last_focused_worktree_id
+            .clone()
+            .filter(|id| project.worktrees.iter().any(|w| &w.id == id))
+    }
+
+    /// When the worktree last got a drift warning
+    pub fn drift_warned_at(&self, worktree_id: &str) -> Option<u64> {
+        self.persisted.read().drift_warned_at.get(worktree_id).copied()
+    }
+
+    /// Record a drift warning, forgetting worktrees that no longer exist
+    pub fn record_drift_warning(&self, worktree_id: &str, at_ms: u64) {
+        let mut persisted = self.persisted.write();
+        let live: Vec<String> = persisted
+            .projects
+            .iter()
+            .flat_map(|p| p.worktrees.iter().map(|w| w.id.clone()))
+            .collect();
+        persisted.drift_warned_at.retain(|id, _| live.contains(id));
+        persisted.drift_warned_at.insert(worktree_id.to_string(), at_ms);
+    }
+
+    /// Remember an auto-stash until it is popped or dropped
+    pub fn record_pending_stash(&self, stash: PendingStash) {
+        let mut persisted = self.persisted.write();
+        persisted.pending_stashes.retain(|s| s.stash_id != stash.stash_id);
+        persisted.pending_stashes.push(stash);
+    }
+
+    /// Remember a snapshot; taking the same one again moves it to the end
+    pub fn record_working_snapshot(&self, mut snapshot: WorkingSnapshot) {
+        snapshot.worktree_path = path_utils::normalize_path_string(Path::new(&snapshot.worktree_path));
+        let mut persisted = self.persisted.write();
+        persisted
+            .working_snapshots
+            .retain(|s| s.hash != snapshot.hash || s.worktree_path != snapshot.worktree_path);
+        persisted.working_snapshots.push(snapshot);
+    }
+
+    /// Snapshots of `worktree_path`, oldest first
+    pub fn working_snapshots(&self, worktree_path: &str) -> Vec<WorkingSnapshot> {
+        let worktree_path = path_utils::normalize_path_string(Path::new(worktree_path));
+        self.persisted
+            .read()
+            .working_snapshots
+            .iter()
+            .filter(|s| s.worktree_path == worktree_path)
+            .cloned()
+            .collect()
+    }
+
+    pub fn pending_stash(&self, stash_id: &str) -> Option<PendingStash> {
+        self.persisted
+            .read()
+            .pending_stashes
+            .iter()
+            .find(|s| s.stash_id == stash_id)
+            .cloned()
+    }
+
+    pub fn remove_pending_stash(&self, stash_id: &str) -> Option<PendingStash> {
+        let mut persisted = self.persisted.write();
+        let index = persisted.pending_stashes.iter().position(|s| s.stash_id == stash_id)?;
+        Some(persisted.pending_stashes.remove(index))
+    }
+
+    /// Drop records for `repo_path` whose stash no longer exists (popped or dropped
+    /// outside shellflow) and return the remaining ones. The bool reports whether
+    /// anything was pruned.
+    pub fn reconcile_pending_stashes(&self, repo_path: &str, live_stash_ids: &[String]) -> (Vec<PendingStash>, bool) {
+        let repo_path = path_utils::normalize_path_string(Path::new(repo_path));
+        let mut persisted = self.persisted.write();
+        let before = persisted.pending_stashes.len();
+        persisted
+            .pending_stashes
+            .retain(|s| s.repo_path != repo_path || live_stash_ids.contains(&s.stash_id));
+        let pruned = persisted.pending_stashes.len() != before;
+        let remaining = persisted
+            .pending_stashes
+            .iter()
+            .filter(|s| s.repo_path == repo_path)
+            .cloned()
+            .collect();
+        (remaining, pruned)
+    }
+
     pub fn save(&self) -> Result<(), std::io::Error> {
         if let Some(home_dir) = dirs::home_dir() {
             let config_dir = home_dir.join(".shellflow");
@@ -198,3 +591,287 @@ impl AppState {
 
 unsafe impl Send for AppState {}
 unsafe impl Sync for AppState {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_worktree(id: &str) -> Worktree {
+        Worktree {
+            id: id.to_string(),
+            name: "feature".to_string(),
+            path: "/tmp/repo/.worktrees/feature".to_string(),
+            branch: "feature".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            order: 0,
+            last_results: HashMap::new(),
+            pending_cleanup: false,
+            layout: None,
+        }
+    }
+
+    fn make_result(exit_code: u32) -> TaskResult {
+        TaskResult {
+            exit_code: Some(exit_code),
+            finished_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn make_state(worktree: Worktree) -> AppState {
+        let state = AppState::new();
+        state.persisted.write().projects.push(Project {
+            id: "p1".to_string(),
+            name: "repo".to_string(),
+            path: "/tmp/repo".to_string(),
+            worktrees: vec![worktree],
+            order: 0,
+            is_active: true,
+            last_accessed_at: None,
+            disabled_config_layers: Vec::new(),
+            last_focused_worktree_id: None,
+            root_commit: None,
+        });
+        state
+    }
+
+    fn make_pending_stash(stash_id: &str) -> PendingStash {
+        PendingStash {
+            stash_id: stash_id.to_string(),
+            repo_path: "/tmp/repo".to_string(),
+            files: vec!["src/main.rs".to_string(), "notes.txt".to_string()],
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_pending_stash_persists_file_list() {
+        let state = AppState::new();
+        state.record_pending_stash(make_pending_stash("shellflow-auto-stash-1"));
+
+        let json = serde_json::to_string(&*state.persisted.read()).unwrap();
+        let restored: PersistedState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.pending_stashes, vec![make_pending_stash("shellflow-auto-stash-1")]);
+        assert!(json.contains("pendingStashes"));
+    }
+
+    #[test]
+    fn test_pending_stash_survives_failed_pop_and_clears_on_success() {
+        let state = AppState::new();
+        state.record_pending_stash(make_pending_stash("shellflow-auto-stash-1"));
+        state.record_pending_stash(make_pending_stash("shellflow-auto-stash-2"));
+
+        // Pop of stash 1 failed: it is still in the stash list, so it stays an orphan
+        let live = vec!["shellflow-auto-stash-1".to_string(), "shellflow-auto-stash-2".to_string()];
+        let (orphans, pruned) = state.reconcile_pending_stashes("/tmp/repo", &live);
+        assert!(!pruned);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[0].files, vec!["src/main.rs", "notes.txt"]);
+
+        // Successful pop removes the record
+        assert!(state.remove_pending_stash("shellflow-auto-stash-1").is_some());
+        assert!(state.pending_stash("shellflow-auto-stash-1").is_none());
+
+        // Stash 2 was popped outside shellflow: reconciling prunes it
+        let (orphans, pruned) = state.reconcile_pending_stashes("/tmp/repo", &[]);
+        assert!(pruned);
+        assert!(orphans.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_pending_stashes_ignores_other_repos() {
+        let state = AppState::new();
+        let mut other = make_pending_stash("shellflow-auto-stash-3");
+        other.repo_path = "/tmp/other".to_string();
+        state.record_pending_stash(other);
+
+        let (orphans, pruned) = state.reconcile_pending_stashes("/tmp/repo", &[]);
+        assert!(!pruned);
+        assert!(orphans.is_empty());
+        assert!(state.pending_stash("shellflow-auto-stash-3").is_some());
+    }
+
+    #[test]
+    fn test_working_snapshots_are_listed_per_worktree() {
+        let state = AppState::new();
+        let snapshot = |hash: &str, path: &str| WorkingSnapshot {
+            hash: hash.to_string(),
+            worktree_path: path.to_string(),
+            label: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        state.record_working_snapshot(snapshot("aaa", "/tmp/repo/.worktrees/a"));
+        state.record_working_snapshot(snapshot("bbb", "/tmp/repo/.worktrees/b"));
+        state.record_working_snapshot(snapshot("ccc", "/tmp/repo/.worktrees/a"));
+        state.record_working_snapshot(snapshot("aaa", "/tmp/repo/.worktrees/a"));
+
+        let hashes: Vec<String> = state
+            .working_snapshots("/tmp/repo/.worktrees/a")
+            .into_iter()
+            .map(|s| s.hash)
+            .collect();
+        assert_eq!(hashes, vec!["ccc", "aaa"]);
+
+        let json = serde_json::to_string(&*state.persisted.read()).unwrap();
+        let restored: PersistedState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.working_snapshots.len(), 3);
+    }
+
+    #[test]
+    fn test_forgotten_snapshots_keep_hashes_other_worktrees_use() {
+        let state = AppState::new();
+        let snapshot = |hash: &str, path: &str| WorkingSnapshot {
+            hash: hash.to_string(),
+            worktree_path: path.to_string(),
+            label: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        // A clean worktree snapshots HEAD, which another worktree can share
+        state.record_working_snapshot(snapshot("head", "/tmp/repo/.worktrees/a"));
+        state.record_working_snapshot(snapshot("head", "/tmp/repo/.worktrees/b"));
+        state.record_working_snapshot(snapshot("aaa", "/tmp/repo/.worktrees/a"));
+        state.record_working_snapshot(snapshot("bbb", "/tmp/repo/.worktrees/a"));
+
+        let mut persisted = state.persisted.write();
+        assert_eq!(persisted.forget_working_snapshots("/tmp/repo/.worktrees/a", Some("aaa")), vec!["aaa"]);
+        assert_eq!(persisted.forget_working_snapshots("/tmp/repo/.worktrees/a", None), vec!["bbb"]);
+        assert_eq!(persisted.working_snapshots.len(), 1);
+        assert_eq!(persisted.forget_working_snapshots("/tmp/repo/.worktrees/b", None), vec!["head"]);
+    }
+
+    #[test]
+    fn test_worktree_layout_round_trips_and_defaults_to_none() {
+        let state = make_state(make_worktree("wt1"));
+        let layout = serde_json::json!({ "tabs": [{ "kind": "terminal" }], "split": "vertical" });
+        assert!(state.set_worktree_layout("wt1", Some(layout.clone())));
+        assert!(!state.set_worktree_layout("missing", Some(layout.clone())));
+
+        let json = serde_json::to_string(&*state.persisted.read()).unwrap();
+        let restored: PersistedState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.projects[0].worktrees[0].layout, Some(layout));
+
+        // State written before layouts existed has none
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy["projects"][0]["worktrees"][0].as_object_mut().unwrap().remove("layout");
+        let restored: PersistedState = serde_json::from_value(legacy).unwrap();
+        assert_eq!(restored.projects[0].worktrees[0].layout, None);
+    }
+
+    #[test]
+    fn test_reset_worktree_ui_state_keeps_git_fields() {
+        let mut worktree = make_worktree("wt1");
+        worktree.last_results.insert(MAIN_RESULT_KEY.to_string(), make_result(0));
+        worktree.layout = Some(serde_json::json!({ "tabs": [] }));
+        let state = make_state(worktree);
+
+        let reset = state.reset_worktree_ui_state("wt1").unwrap();
+        assert_eq!(reset.layout, None);
+        assert_eq!(reset.branch, "feature");
+        assert_eq!(reset.last_results.len(), 1);
+        assert!(state.reset_worktree_ui_state("missing").is_none());
+    }
+
+    #[test]
+    fn test_record_worktree_result_updates_on_exit() {
+        let state = make_state(make_worktree("w1"));
+        let tasks = vec!["test".to_string()];
+
+        assert!(state.record_worktree_result("w1", "test", make_result(1), &tasks));
+        assert!(state.record_worktree_result("w1", MAIN_RESULT_KEY, make_result(0), &tasks));
+        assert!(state.record_worktree_result("w1", "test", make_result(0), &tasks));
+        assert!(!state.record_worktree_result("missing", "test", make_result(0), &tasks));
+
+        let persisted = state.persisted.read();
+        let results = &persisted.projects[0].worktrees[0].last_results;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["test"].exit_code, Some(0));
+        assert_eq!(results[MAIN_RESULT_KEY].exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_record_result_drops_removed_tasks() {
+        let mut worktree = make_worktree("w1");
+        let tasks = vec!["build".to_string(), "test".to_string()];
+        worktree.record_result("build", make_result(0), &tasks);
+        worktree.record_result("test", make_result(1), &tasks);
+        worktree.record_result(MAIN_RESULT_KEY, make_result(0), &tasks);
+
+        // "build" was removed from the config
+        let tasks = vec!["test".to_string()];
+        worktree.record_result("test", make_result(0), &tasks);
+
+        let mut keys: Vec<&String> = worktree.last_results.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["main", "test"]);
+    }
+
+    #[test]
+    fn test_last_results_round_trip() {
+        let mut worktree = make_worktree("w1");
+        worktree.record_result("test", make_result(2), &["test".to_string()]);
+        let state = make_state(worktree);
+
+        let content = serde_json::to_string_pretty(&*state.persisted.read()).unwrap();
+        assert!(content.contains("\"lastResults\""));
+        assert!(content.contains("\"exitCode\": 2"));
+
+        let loaded: PersistedState = serde_json::from_str(&content).unwrap();
+        assert_eq!(
+            loaded.projects[0].worktrees[0].last_results["test"],
+            make_result(2)
+        );
+    }
+
+    #[test]
+    fn test_last_results_defaults_for_old_state() {
+        let content = r#"{
+            "projects": [{
+                "id": "p1",
+                "name": "repo",
+                "path": "/tmp/repo",
+                "worktrees": [{
+                    "id": "w1",
+                    "name": "feature",
+                    "path": "/tmp/repo/.worktrees/feature",
+                    "branch": "feature",
+                    "createdAt": "2024-01-01T00:00:00Z"
+                }]
+            }]
+        }"#;
+
+        let loaded: PersistedState = serde_json::from_str(content).unwrap();
+        assert!(loaded.projects[0].worktrees[0].last_results.is_empty());
+        assert!(!loaded.projects[0].worktrees[0].pending_cleanup);
+    }
+
+    #[test]
+    fn test_pending_cleanup_keeps_worktree_in_state() {
+        let state = make_state(make_worktree("w1"));
+
+        assert!(state.set_worktree_pending_cleanup("w1", true));
+        assert!(!state.set_worktree_pending_cleanup("missing", true));
+
+        let content = serde_json::to_string(&*state.persisted.read()).unwrap();
+        let loaded: PersistedState = serde_json::from_str(&content).unwrap();
+        assert_eq!(loaded.projects[0].worktrees.len(), 1);
+        assert!(loaded.projects[0].worktrees[0].pending_cleanup);
+    }
+
+    #[test]
+    fn test_last_focused_worktree_forgets_deleted_worktrees() {
+        let state = make_state(make_worktree("w1"));
+        assert_eq!(state.last_focused_worktree("p1"), None);
+
+        assert!(state.set_last_focused_worktree("p1", "w1"));
+        assert!(!state.set_last_focused_worktree("p1", "w1"));
+        assert!(!state.set_last_focused_worktree("p1", "other-project-worktree"));
+        assert_eq!(state.last_focused_worktree("p1").as_deref(), Some("w1"));
+
+        let content = serde_json::to_string(&*state.persisted.read()).unwrap();
+        let loaded: PersistedState = serde_json::from_str(&content).unwrap();
+        assert_eq!(loaded.projects[0].last_focused_worktree_id.as_deref(), Some("w1"));
+
+        state.persisted.write().projects[0].worktrees.clear();
+        assert_eq!(state.last_focused_worktree("p1"), None);
+        assert_eq!(state.last_focused_worktree("missing"), None);
+    }
+}
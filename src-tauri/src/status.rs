@@ -0,0 +1,301 @@
+//! Consolidated status-bar summary.
+//!
+//! Snapshots live sessions, running workflows, watcher health, setup jobs and
+//! the most recent error into one `status-summary` event, emitted at most once
+//! per interval and only when something changed.
+
+use crate::events;
+use crate::pty;
+use crate::state::AppState;
+use crate::watcher;
+use crate::worktree::SETUP_LIMITER;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// How often the aggregator takes a snapshot
+pub const STATUS_SUMMARY_INTERVAL: Duration = Duration::from_secs(1);
+/// Errors older than this are dropped from the summary
+pub const RECENT_ERROR_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkflowKind {
+    Merge,
+    Cleanup,
+    Delete,
+}
+
+impl WorkflowKind {
+    /// Name as serialized, e.g. for merge blockers
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WorkflowKind::Merge => "merge",
+            WorkflowKind::Cleanup => "cleanup",
+            WorkflowKind::Delete => "delete",
+        }
+    }
+}
+
+/// A merge/cleanup/delete workflow that hasn't completed yet
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStatus {
+    pub kind: WorkflowKind,
+    pub worktree_id: String,
+    pub project_path: String,
+    /// Last progress phase emitted by the workflow
+    pub phase: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    /// Worktrees with a running file watcher
+    pub watching: usize,
+    /// Watchers that failed to start or can't see git index changes
+    pub degraded: usize,
+    /// Watchers holding back refreshes during a bulk git operation
+    pub paused: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentError {
+    pub source: String,
+    pub message: String,
+    /// Unix timestamp in milliseconds
+    pub at_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusSummary {
+    pub pty_sessions: usize,
+    pub workflows: Vec<WorkflowStatus>,
+    pub watchers: WatcherStatus,
+    pub setup_jobs: usize,
+    pub recent_error: Option<RecentError>,
+}
+
+lazy_static::lazy_static! {
+    /// Workflows in progress, keyed by worktree id
+    static ref WORKFLOWS: Mutex<HashMap<String, WorkflowStatus>> = Mutex::new(HashMap::new());
+    static ref LAST_ERROR: Mutex<Option<RecentError>> = Mutex::new(None);
+}
+
+pub(crate) fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Remember an error for the summary's `recentError`
+pub fn record_error(source: &str, message: impl Into<String>) {
+    *LAST_ERROR.lock() = Some(RecentError {
+        source: source.to_string(),
+        message: message.into(),
+        at_ms: now_unix_ms(),
+    });
+}
+
+/// Registers a workflow for the summary while alive; dropping it (on any
+/// return path) marks the workflow finished.
+pub struct WorkflowGuard {
+    worktree_id: String,
+}
+
+impl WorkflowGuard {
+    pub fn start(kind: WorkflowKind, worktree_id: &str, project_path: &str) -> Self {
+        WORKFLOWS.lock().insert(
+            worktree_id.to_string(),
+            WorkflowStatus {
+                kind,
+                worktree_id: worktree_id.to_string(),
+                project_path: project_path.to_string(),
+                phase: None,
+            },
+        );
+        Self {
+            worktree_id: worktree_id.to_string(),
+        }
+    }
+
+    pub fn set_phase(&self, phase: &str) {
+        if let Some(workflow) = WORKFLOWS.lock().get_mut(&self.worktree_id) {
+            workflow.phase = Some(phase.to_string());
+        }
+    }
+}
+
+impl Drop for WorkflowGuard {
+    fn drop(&mut self) {
+        WORKFLOWS.lock().remove(&self.worktree_id);
+    }
+}
+
+/// Kind of the workflow running for a worktree, if any
+pub fn workflow_in_progress(worktree_id: &str) -> Option<WorkflowKind> {
+    WORKFLOWS.lock().get(worktree_id).map(|w| w.kind)
+}
+
+/// Build a summary from already-collected inputs. Workflows are sorted so equal
+/// state always hashes the same, and errors outside the window are dropped.
+pub fn assemble(
+    pty_sessions: usize,
+    mut workflows: Vec<WorkflowStatus>,
+    watchers: WatcherStatus,
+    setup_jobs: usize,
+    last_error: Option<RecentError>,
+    now_ms: u64,
+) -> StatusSummary {
+    workflows.sort_by(|a, b| (a.kind, &a.worktree_id).cmp(&(b.kind, &b.worktree_id)));
+    let window_ms = RECENT_ERROR_WINDOW.as_millis() as u64;
+    let recent_error = last_error.filter(|e| now_ms.saturating_sub(e.at_ms) <= window_ms);
+    StatusSummary {
+        pty_sessions,
+        workflows,
+        watchers,
+        setup_jobs,
+        recent_error,
+    }
+}
+
+/// Snapshot the current state. Each source is copied out under its own lock,
+/// so nothing is held once this returns.
+pub fn snapshot(state: &AppState) -> StatusSummary {
+    let (pty_sessions, _) = pty::live_session_counts(state, "");
+    let workflows = WORKFLOWS.lock().values().cloned().collect();
+    let last_error = LAST_ERROR.lock().clone();
+    assemble(
+        pty_sessions,
+        workflows,
+        watcher::watcher_status(),
+        SETUP_LIMITER.running(),
+        last_error,
+        now_unix_ms(),
+    )
+}
+
+/// Tracks the hash of the last emitted summary to skip unchanged snapshots
+#[derive(Debug, Default)]
+pub struct ChangeDetector {
+    last_hash: Option<u64>,
+}
+
+impl ChangeDetector {
+    /// True if `summary` differs from the previous one passed in
+    pub fn changed(&mut self, summary: &StatusSummary) -> bool {
+        let mut hasher = DefaultHasher::new();
+        summary.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.last_hash == Some(hash) {
+            return false;
+        }
+        self.last_hash = Some(hash);
+        true
+    }
+}
+
+/// Emit `status-summary` whenever the snapshot changes, checking once per interval
+pub fn start_aggregator(app: AppHandle, state: Arc<AppState>) {
+    std::thread::spawn(move || {
+        let mut detector = ChangeDetector::default();
+        loop {
+            std::thread::sleep(STATUS_SUMMARY_INTERVAL);
+            if pty::SHUTDOWN_IN_PROGRESS.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            let summary = snapshot(&state);
+            if detector.changed(&summary) {
+                let _ = app.emit(events::STATUS_SUMMARY, &summary);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow(kind: WorkflowKind, worktree_id: &str) -> WorkflowStatus {
+        WorkflowStatus {
+            kind,
+            worktree_id: worktree_id.to_string(),
+            project_path: "/repo".to_string(),
+            phase: Some("merge".to_string()),
+        }
+    }
+
+    fn error_at(at_ms: u64) -> RecentError {
+        RecentError {
+            source: "merge".to_string(),
+            message: "conflict".to_string(),
+            at_ms,
+        }
+    }
+
+    #[test]
+    fn assemble_sorts_workflows_and_expires_old_errors() {
+        let now = 1_000_000;
+        let summary = assemble(
+            3,
+            vec![workflow(WorkflowKind::Delete, "b"), workflow(WorkflowKind::Merge, "a")],
+            WatcherStatus { watching: 2, degraded: 0, paused: 1 },
+            1,
+            Some(error_at(now - 59_000)),
+            now,
+        );
+        assert_eq!(summary.pty_sessions, 3);
+        assert_eq!(summary.workflows[0].kind, WorkflowKind::Merge);
+        assert_eq!(summary.workflows[1].worktree_id, "b");
+        assert_eq!(summary.watchers.paused, 1);
+        assert_eq!(summary.setup_jobs, 1);
+        assert!(summary.recent_error.is_some());
+
+        let stale = assemble(0, vec![], WatcherStatus::default(), 0, Some(error_at(now - 61_000)), now);
+        assert_eq!(stale.recent_error, None);
+    }
+
+    #[test]
+    fn change_detector_skips_identical_snapshots() {
+        let mut detector = ChangeDetector::default();
+        let workflows = vec![workflow(WorkflowKind::Merge, "a"), workflow(WorkflowKind::Merge, "b")];
+        let summary = assemble(1, workflows.clone(), WatcherStatus::default(), 0, None, 0);
+        assert!(detector.changed(&summary));
+        assert!(!detector.changed(&summary.clone()));
+
+        // Same state collected in a different order is still unchanged
+        let reordered = assemble(1, workflows.into_iter().rev().collect(), WatcherStatus::default(), 0, None, 5_000);
+        assert!(!detector.changed(&reordered));
+
+        let mut updated = summary;
+        updated.workflows[0].phase = Some("delete-worktree".to_string());
+        assert!(detector.changed(&updated));
+    }
+
+    #[test]
+    fn workflow_guard_tracks_phase_until_dropped() {
+        let id = format!("status-test-{}", uuid::Uuid::new_v4());
+        let guard = WorkflowGuard::start(WorkflowKind::Cleanup, &id, "/repo");
+        guard.set_phase("delete-local-branch");
+        let phase = WORKFLOWS.lock().get(&id).and_then(|w| w.phase.clone());
+        assert_eq!(phase.as_deref(), Some("delete-local-branch"));
+
+        drop(guard);
+        assert!(!WORKFLOWS.lock().contains_key(&id));
+    }
+
+    #[test]
+    fn workflow_kind_names_match_serde() {
+        for kind in [WorkflowKind::Merge, WorkflowKind::Cleanup, WorkflowKind::Delete] {
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Task switcher catalog.
+//!
+//! Arranges configured tasks into groups for the switcher: groups named in
+//! `tasksGroups` come first in that order, the rest follow in order of first
+//! appearance, and ungrouped tasks form one unnamed group. Within a group,
+//! tasks with an `order` come first (ascending), the rest keep config order.
+//! Hidden tasks are left out but can still be run by name.
+
+use crate::config::{TaskConfig, TaskKind};
+use crate::mappings::{Action, RawMappings};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCatalogEntry {
+    pub name: String,
+    pub group: Option<String>,
+    pub kind: TaskKind,
+    /// A live session runs this task in the requested entity
+    pub running: bool,
+    /// Key sequence of a mapping that names this task, e.g. `["task::run", "Build"]`
+    pub shortcut: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskGroup {
+    /// None for tasks without a group
+    pub name: Option<String>,
+    pub tasks: Vec<TaskCatalogEntry>,
+}
+
+/// Task name -> shortcut, from bindings whose action is a `task::` action with
+/// the task name as its first argument. The lexically first key wins.
+pub fn task_shortcuts(mappings: &RawMappings) -> HashMap<String, String> {
+    let mut shortcuts: HashMap<String, String> = HashMap::new();
+    for group in &mappings.bindings {
+        for (keys, action) in &group.bindings {
+            let Action::WithArgs(parts) = action else { continue };
+            let (Some(action_id), Some(task)) = (
+                parts.first().and_then(|v| v.as_str()),
+                parts.get(1).and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            if !action_id.starts_with("task::") {
+                continue;
+            }
+            shortcuts
+                .entry(task.to_string())
+                .and_modify(|existing| {
+                    if keys < existing {
+                        *existing = keys.clone();
+                    }
+                })
+                .or_insert_with(|| keys.clone());
+        }
+    }
+    shortcuts
+}
+
+/// Group and sort the switcher's tasks
+pub fn build_catalog(
+    tasks: &[TaskConfig],
+    group_order: &[String],
+    running: &HashSet<String>,
+    shortcuts: &HashMap<String, String>,
+) -> Vec<TaskGroup> {
+    let mut groups: Vec<TaskGroup> = group_order
+        .iter()
+        .map(|name| TaskGroup { name: Some(name.clone()), tasks: Vec::new() })
+        .collect();
+    let mut ordered: HashMap<Option<String>, Vec<(Option<i32>, TaskCatalogEntry)>> = HashMap::new();
+
+    for task in tasks.iter().filter(|t| !t.hidden) {
+        if !groups.iter().any(|g| g.name == task.group) {
+            groups.push(TaskGroup { name: task.group.clone(), tasks: Vec::new() });
+        }
+        ordered.entry(task.group.clone()).or_default().push((
+            task.order,
+            TaskCatalogEntry {
+                name: task.name.clone(),
+                group: task.group.clone(),
+                kind: task.kind,
+                running: running.contains(&task.name),
+                shortcut: shortcuts.get(&task.name).cloned(),
+            },
+        ));
+    }
+
+    for group in &mut groups {
+        let mut entries = ordered.remove(&group.name).unwrap_or_default();
+        // Stable, so unordered tasks keep their config order after the ordered ones
+        entries.sort_by_key(|(order, _)| (order.is_none(), *order));
+        group.tasks = entries.into_iter().map(|(_, entry)| entry).collect();
+    }
+    // Explicitly ordered groups without any visible task are dropped
+    groups.retain(|g| !g.tasks.is_empty());
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, group: Option<&str>, order: Option<i32>) -> TaskConfig {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "command": "true",
+            "group": group,
+            "order": order,
+        }))
+        .unwrap()
+    }
+
+    fn names(groups: &[TaskGroup]) -> Vec<(Option<&str>, Vec<&str>)> {
+        groups
+            .iter()
+            .map(|g| (g.name.as_deref(), g.tasks.iter().map(|t| t.name.as_str()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_first_appearance_and_sorts_by_order() {
+        let mut hidden = task("Rarely", Some("build"), None);
+        hidden.hidden = true;
+        let tasks = vec![
+            task("Lint", Some("check"), None),
+            task("Dev", None, None),
+            task("Test", Some("check"), Some(1)),
+            task("Build", Some("build"), None),
+            task("Typecheck", Some("check"), None),
+            task("Format", Some("check"), Some(0)),
+            hidden,
+        ];
+        let catalog = build_catalog(&tasks, &[], &HashSet::new(), &HashMap::new());
+        assert_eq!(
+            names(&catalog),
+            vec![
+                (Some("check"), vec!["Format", "Test", "Lint", "Typecheck"]),
+                (None, vec!["Dev"]),
+                (Some("build"), vec!["Build"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit_group_order_comes_first() {
+        let tasks = vec![
+            task("Lint", Some("check"), None),
+            task("Dev", None, None),
+            task("Build", Some("build"), None),
+        ];
+        let order = vec!["build".to_string(), "deploy".to_string()];
+        let running = HashSet::from(["Build".to_string()]);
+        let shortcuts = HashMap::from([("Lint".to_string(), "cmd-shift-l".to_string())]);
+        let catalog = build_catalog(&tasks, &order, &running, &shortcuts);
+
+        // "deploy" has no tasks and is dropped
+        assert_eq!(
+            names(&catalog),
+            vec![(Some("build"), vec!["Build"]), (Some("check"), vec!["Lint"]), (None, vec!["Dev"])]
+        );
+        assert!(catalog[0].tasks[0].running);
+        assert_eq!(catalog[1].tasks[0].shortcut.as_deref(), Some("cmd-shift-l"));
+    }
+
+    #[test]
+    fn shortcuts_come_from_task_actions_with_a_name() {
+        let mappings: RawMappings = serde_json::from_value(serde_json::json!({
+            "bindings": [
+                { "bindings": { "cmd-r": "task::run", "cmd-2": ["task::run", "Build"], "cmd-1": ["task::run", "Build"] } },
+                { "context": "drawerFocused", "bindings": { "cmd-t": ["navigate::toEntity", "Test"], "cmd-3": ["task::run", "Test"] } }
+            ]
+        }))
+        .unwrap();
+        let shortcuts = task_shortcuts(&mappings);
+        assert_eq!(shortcuts.get("Build").map(String::as_str), Some("cmd-1"));
+        assert_eq!(shortcuts.get("Test").map(String::as_str), Some("cmd-3"));
+        assert_eq!(shortcuts.len(), 2);
+    }
+}
@@ -7,10 +7,14 @@
 //! - `branch` - The branch name
 //! - `worktree_name` - The worktree name (sanitized)
 //!
+//! Action prompts have their own variables; `template_variables` lists what
+//! each context provides.
+//!
 //! # Available Filters
 //! - `sanitize` - Replace `/` and `\` with `-` for filesystem-safe paths
 //! - `hash_port` - Hash to deterministic port number (10000-19999)
 //! - `shell_escape` - Escape for safe use in shell commands
+//! - `color` - Stable hex color for a branch or worktree name (e.g. `#3fa9bf`)
 //!
 //! # Examples
 //! ```text
@@ -19,10 +23,18 @@
 //! echo "Working on {{ branch | shell_escape }}"
 //! ```
 
-use minijinja::{Environment, Value};
+use minijinja::{Environment, UndefinedBehavior, Value};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 
+lazy_static::lazy_static! {
+    /// (config path, template) pairs already warned about this session
+    static ref WARNED_TEMPLATES: Mutex<HashSet<(String, String)>> = Mutex::new(HashSet::new());
+}
+
 /// Context for template expansion (worktree directory templates).
 #[derive(Debug, Clone)]
 pub struct TemplateContext {
@@ -52,6 +64,77 @@ impl TemplateContext {
     }
 }
 
+/// Where a template is rendered, which decides the variables it can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateScope {
+    /// `worktree.directory`
+    WorktreeDirectory,
+    /// Task `command` and `env` values, and `main.command`
+    Task,
+    /// Task `urls` values
+    TaskUrl,
+    /// Action prompts such as `actions.mergeWorktreeWithConflicts`
+    Action,
+    /// `worktree.nameGenerator.prefix`
+    NamePrefix,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVariable {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+const fn variable(name: &'static str, description: &'static str) -> TemplateVariable {
+    TemplateVariable { name, description }
+}
+
+const REPO_DIRECTORY: TemplateVariable = variable("repo_directory", "The repository root path");
+const BRANCH: TemplateVariable = variable("branch", "The branch name");
+
+const WORKTREE_DIRECTORY_VARIABLES: &[TemplateVariable] = &[
+    REPO_DIRECTORY,
+    BRANCH,
+    variable("worktree_name", "The worktree name (sanitized)"),
+];
+
+const TASK_VARIABLES: &[TemplateVariable] = &[
+    REPO_DIRECTORY,
+    variable("branch", "The branch name; the current branch when run in the main repository"),
+    variable("worktree_name", "The worktree name; empty when run in the main repository"),
+];
+
+const TASK_URL_VARIABLES: &[TemplateVariable] = &[
+    REPO_DIRECTORY,
+    variable("branch", "The branch name; the current branch for the main repository"),
+];
+
+const ACTION_VARIABLES: &[TemplateVariable] = &[
+    variable("worktree_dir", "The worktree path"),
+    variable("worktree_name", "The worktree name"),
+    BRANCH,
+    variable("target_branch", "The branch being merged into or rebased onto"),
+    variable("conflicted_files", "List of files with conflicts, for use in a `for` loop"),
+];
+
+const NAME_PREFIX_VARIABLES: &[TemplateVariable] = &[
+    REPO_DIRECTORY,
+    variable("user", "The OS user name, made safe for branch names"),
+];
+
+/// Variables a template rendered in `scope` can use
+pub fn template_variables(scope: TemplateScope) -> &'static [TemplateVariable] {
+    match scope {
+        TemplateScope::WorktreeDirectory => WORKTREE_DIRECTORY_VARIABLES,
+        TemplateScope::Task => TASK_VARIABLES,
+        TemplateScope::TaskUrl => TASK_URL_VARIABLES,
+        TemplateScope::Action => ACTION_VARIABLES,
+        TemplateScope::NamePrefix => NAME_PREFIX_VARIABLES,
+    }
+}
+
 /// Hash a string to a deterministic port in range 10000-19999.
 fn hash_port(value: String) -> u16 {
     let mut h = DefaultHasher::new();
@@ -59,6 +142,61 @@ fn hash_port(value: String) -> u16 {
     10000 + (h.finish() % 10000) as u16
 }
 
+/// Saturation (%) shared by all branch colors
+const BRANCH_COLOR_SATURATION: u8 = 60;
+/// Lightness (%) shared by all branch colors: mid-range reads on light and dark themes
+const BRANCH_COLOR_LIGHTNESS: u8 = 50;
+
+/// Color derived from a branch or worktree name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchColor {
+    /// `#rrggbb`
+    pub hex: String,
+    /// Degrees, 0-359
+    pub hue: u16,
+    /// Percent
+    pub saturation: u8,
+    /// Percent
+    pub lightness: u8,
+}
+
+/// Deterministic color for a name. The hash only picks the hue, so every
+/// color has the same saturation and lightness. Uses FNV-1a rather than the
+/// std hasher so colors stay the same across releases.
+pub fn branch_color(name: &str) -> BranchColor {
+    let hue = (crate::git::fnv1a(name.as_bytes()) % 360) as u16;
+    BranchColor {
+        hex: hsl_to_hex(hue, BRANCH_COLOR_SATURATION, BRANCH_COLOR_LIGHTNESS),
+        hue,
+        saturation: BRANCH_COLOR_SATURATION,
+        lightness: BRANCH_COLOR_LIGHTNESS,
+    }
+}
+
+fn hsl_to_hex(hue: u16, saturation: u8, lightness: u8) -> String {
+    let s = f64::from(saturation) / 100.0;
+    let l = f64::from(lightness) / 100.0;
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = f64::from(hue) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+fn color_filter(value: Value) -> String {
+    branch_color(value.as_str().unwrap_or_default()).hex
+}
+
 /// Sanitize a string for use in filesystem paths.
 /// Replaces `/` and `\` with `-`.
 fn sanitize(value: Value) -> String {
@@ -106,6 +244,7 @@ fn create_environment() -> Environment<'static> {
     env.add_filter("hash_port", hash_port);
     env.add_filter("sanitize", sanitize);
     env.add_filter("shell_escape", shell_escape_filter);
+    env.add_filter("color", color_filter);
 
     env
 }
@@ -120,14 +259,16 @@ pub fn expand_template(template: &str, context: &TemplateContext) -> Result<Stri
         .template_from_str(template)
         .map_err(|e| format!("Template syntax error: {}", e))?;
 
-    let ctx = minijinja::context! {
+    tmpl.render(context_value(context))
+        .map_err(|e| format!("Template render error: {}", e))
+}
+
+fn context_value(context: &TemplateContext) -> Value {
+    minijinja::context! {
         repo_directory => &context.repo_directory,
         branch => context.branch.as_deref().unwrap_or(""),
         worktree_name => context.worktree_name.as_deref().unwrap_or(""),
-    };
-
-    tmpl.render(ctx)
-        .map_err(|e| format!("Template render error: {}", e))
+    }
 }
 
 /// Expand an action prompt template with the given context.
@@ -135,14 +276,135 @@ pub fn expand_template(template: &str, context: &TemplateContext) -> Result<Stri
 /// Accepts a minijinja Value as context, allowing each action to define its own variables.
 /// Returns the expanded string, or an error message if template parsing/rendering fails.
 pub fn expand_action_template(template: &str, context: Value) -> Result<String, String> {
-    let env = create_environment();
+    render_value(&create_environment(), template, context).map_err(|e| match e.kind() {
+        minijinja::ErrorKind::SyntaxError => format!("Template syntax error: {}", e),
+        _ => format!("Template render error: {}", e),
+    })
+}
 
-    let tmpl = env
-        .template_from_str(template)
-        .map_err(|e| format!("Template syntax error: {}", e))?;
+/// Expand a `nameGenerator.prefix` template
+pub fn expand_name_prefix(template: &str, repo_directory: &str, user: &str) -> Result<String, String> {
+    expand_action_template(template, minijinja::context! { repo_directory => repo_directory, user => user })
+}
 
-    tmpl.render(context)
-        .map_err(|e| format!("Template render error: {}", e))
+fn render_value(env: &Environment<'_>, template: &str, context: Value) -> Result<String, minijinja::Error> {
+    env.template_from_str(template)?.render(context)
+}
+
+/// A config template that doesn't render, from `TemplateValidator`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateProblem {
+    /// Where the template lives, e.g. `tasks[Dev].env.PORT`
+    pub config_path: String,
+    pub template: String,
+    pub error: String,
+}
+
+/// Renders config templates against placeholder values for their scope. Unlike
+/// runtime expansion, an unknown variable is an error, so a typo like
+/// `{{ brnch }}` is caught, even where a filter would swallow the undefined
+/// value. One validator can check any number of templates.
+pub struct TemplateValidator {
+    env: Environment<'static>,
+}
+
+impl Default for TemplateValidator {
+    fn default() -> Self {
+        let mut env = create_environment();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        Self { env }
+    }
+}
+
+impl TemplateValidator {
+    /// The problem with `template`, or None if it renders
+    pub fn check(&self, config_path: &str, template: &str, scope: TemplateScope) -> Option<TemplateProblem> {
+        let context: serde_json::Map<String, serde_json::Value> = template_variables(scope)
+            .iter()
+            .map(|variable| {
+                let placeholder = match variable.name {
+                    "conflicted_files" => serde_json::json!(["src/example.rs"]),
+                    name => serde_json::Value::String(format!("example-{}", name)),
+                };
+                (variable.name.to_string(), placeholder)
+            })
+            .collect();
+        let problem = |error: String| TemplateProblem {
+            config_path: config_path.to_string(),
+            template: template.to_string(),
+            error,
+        };
+
+        let tmpl = match self.env.template_from_str(template) {
+            Ok(tmpl) => tmpl,
+            Err(e) => return Some(problem(e.to_string())),
+        };
+        let mut unknown: Vec<String> = tmpl
+            .undeclared_variables(false)
+            .into_iter()
+            .filter(|name| !context.contains_key(name) && !self.env.globals().any(|(global, _)| global == name))
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            return Some(problem(format!("undefined variable: {}", unknown.join(", "))));
+        }
+        let error = tmpl.render(Value::from_serialize(&context)).err()?;
+        Some(problem(error.to_string()))
+    }
+}
+
+/// Log a template that failed at runtime and fell back, once per config path
+/// and template per session
+pub fn warn_template_failure(config_path: &str, template: &str, error: &str) {
+    if WARNED_TEMPLATES.lock().insert((config_path.to_string(), template.to_string())) {
+        log::warn!("[template] {} failed to expand ({}); using a fallback", config_path, error);
+    }
+}
+
+/// Why a previewed template failed, and where
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateError {
+    pub message: String,
+    /// 1-based
+    pub line: Option<usize>,
+    /// 1-based, in characters
+    pub column: Option<usize>,
+}
+
+/// Result of `preview_template`: the output, or the error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplatePreview {
+    pub output: Option<String>,
+    pub error: Option<TemplateError>,
+}
+
+/// Render `template` against an arbitrary JSON context, with the same filters
+/// as config templates, e.g. `{ "branch": "feature/x" }`
+pub fn preview_template(template: &str, context: &serde_json::Value) -> TemplatePreview {
+    let mut env = create_environment();
+    // Keeps error spans, for the column
+    env.set_debug(true);
+
+    match render_value(&env, template, Value::from_serialize(context)) {
+        Ok(output) => TemplatePreview { output: Some(output), error: None },
+        Err(e) => {
+            let column = e.range().map(|range| {
+                let before = &template[..range.start.min(template.len())];
+                before.rsplit('\n').next().unwrap_or("").chars().count() + 1
+            });
+            let message = match e.detail() {
+                Some(detail) => format!("{}: {}", e.kind(), detail),
+                None => e.kind().to_string(),
+            };
+            TemplatePreview {
+                output: None,
+                error: Some(TemplateError { message, line: e.line(), column }),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +418,71 @@ mod tests {
         assert_eq!(port1, port2, "Same input should produce same port");
     }
 
+    #[test]
+    fn test_branch_color_is_stable() {
+        let color = branch_color("feature/login");
+        assert_eq!(color, branch_color("feature/login"));
+        // Pinned so a hashing change can't silently recolor every worktree
+        assert_eq!(color.hue, 27);
+        assert_eq!(color.hex, "#cc7833");
+        assert!(color.hex.starts_with('#') && color.hex.len() == 7);
+        assert_ne!(branch_color("main").hex, branch_color("develop").hex);
+    }
+
+    #[test]
+    fn test_template_variables_match_the_rendered_context() {
+        let names = |scope| -> Vec<&str> { template_variables(scope).iter().map(|v| v.name).collect() };
+        let ctx = TemplateContext::new("/repo").with_branch("b").with_worktree_name("w");
+        let mut keys: Vec<String> = context_value(&ctx)
+            .try_iter()
+            .unwrap()
+            .map(|key| key.to_string())
+            .collect();
+        let mut listed = names(TemplateScope::WorktreeDirectory);
+        keys.sort();
+        listed.sort();
+        assert_eq!(keys, listed);
+        assert_eq!(names(TemplateScope::Task), names(TemplateScope::WorktreeDirectory));
+        assert_eq!(names(TemplateScope::TaskUrl), vec!["repo_directory", "branch"]);
+        assert!(names(TemplateScope::Action).contains(&"conflicted_files"));
+        assert_eq!(names(TemplateScope::NamePrefix), vec!["repo_directory", "user"]);
+        assert_eq!(expand_name_prefix("wt/{{ user }}/", "/repo", "alice").unwrap(), "wt/alice/");
+    }
+
+    #[test]
+    fn test_preview_template_renders_json_context_and_locates_errors() {
+        let context = serde_json::json!({ "branch": "feature/login", "conflicted_files": ["a.rs"] });
+        let preview = preview_template("{{ branch | sanitize }}:{{ conflicted_files | length }}", &context);
+        assert_eq!(preview, TemplatePreview { output: Some("feature-login:1".to_string()), error: None });
+
+        let preview = preview_template("ok\n  {{ branch | nope }}", &context);
+        let error = preview.error.unwrap();
+        assert_eq!(preview.output, None);
+        assert_eq!(error.line, Some(2));
+        assert!(error.column.is_some_and(|c| c > 1), "{:?}", error);
+        assert!(error.message.contains("nope"), "{}", error.message);
+
+        let error = preview_template("{{ branch", &context).error.unwrap();
+        assert!(error.message.starts_with("syntax error"), "{}", error.message);
+        assert_eq!(error.line, Some(1));
+    }
+
+    #[test]
+    fn test_hsl_to_hex() {
+        assert_eq!(hsl_to_hex(0, 100, 50), "#ff0000");
+        assert_eq!(hsl_to_hex(120, 100, 50), "#00ff00");
+        assert_eq!(hsl_to_hex(240, 100, 50), "#0000ff");
+        assert_eq!(hsl_to_hex(200, 60, 50), "#3399cc");
+        assert_eq!(hsl_to_hex(0, 0, 50), "#808080");
+    }
+
+    #[test]
+    fn test_color_filter() {
+        let ctx = TemplateContext::new("/repo").with_branch("feature/login");
+        let rendered = expand_template("{{ branch | color }}", &ctx).unwrap();
+        assert_eq!(rendered, branch_color("feature/login").hex);
+    }
+
     #[test]
     fn test_hash_port_range() {
         for input in ["main", "feature/foo", "", "a", "long-branch-name-123"] {
@@ -297,4 +624,28 @@ mod tests {
         let result = expand_template("echo {{ branch | shell_escape }}", &ctx).unwrap();
         assert_eq!(result, "echo 'test$(whoami)'");
     }
+
+    #[test]
+    fn validator_catches_unknown_variables_and_syntax_errors() {
+        let validator = TemplateValidator::default();
+        assert_eq!(validator.check("worktree.directory", "{{ repo_directory }}/{{ branch | sanitize }}", TemplateScope::WorktreeDirectory), None);
+        assert_eq!(validator.check("tasks[Dev].env.PORT", "{{ branch | hash_port }}", TemplateScope::Task), None);
+        assert_eq!(
+            validator.check("actions.x", "{% for f in conflicted_files %}{{ f }}{% endfor %}", TemplateScope::Action),
+            None
+        );
+
+        // Runtime expansion renders a typo as empty; validation doesn't
+        let ctx = TemplateContext::new("/repo").with_branch("main");
+        assert_eq!(expand_template("PORT={{ brnch }}", &ctx).unwrap(), "PORT=");
+        let typo = validator.check("tasks[Dev].env.PORT", "PORT={{ brnch }}", TemplateScope::Task).unwrap();
+        assert_eq!(typo.config_path, "tasks[Dev].env.PORT");
+        assert_eq!(typo.template, "PORT={{ brnch }}");
+        assert!(typo.error.contains("undefined"), "{}", typo.error);
+        // A variable from another scope is just as unknown
+        assert!(validator.check("tasks[Dev].urls.Dev", "{{ worktree_name }}", TemplateScope::TaskUrl).is_some());
+
+        let syntax = validator.check("main.command", "{{ branch", TemplateScope::Task).unwrap();
+        assert!(syntax.error.contains("syntax error"), "{}", syntax.error);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::error::CommandError;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -303,8 +304,8 @@ pub fn list_themes() -> Vec<ThemeInfo> {
 
 /// Read a theme file and return its contents
 #[tauri::command]
-pub fn read_theme(path: &str) -> Result<String, String> {
-    std::fs::read_to_string(path).map_err(|e| format!("Failed to read theme file: {}", e))
+pub fn read_theme(path: &str) -> Result<String, CommandError> {
+    Ok(std::fs::read_to_string(path)?)
 }
 
 #[cfg(test)]
@@ -0,0 +1,153 @@
+//! Recovery after system sleep.
+//!
+//! File watchers can come back from an overnight sleep (or App Nap on macOS)
+//! with a dead event stream, so activity indicators freeze while files keep
+//! changing. A background loop compares the monotonic clock, which stops
+//! while the system sleeps on macOS and Linux, with the wall clock, which
+//! doesn't. When they drift apart by more than `wake.sleepThresholdSeconds`,
+//! the system has slept: sessions whose process is gone are closed, worktree
+//! watchers are restarted a little apart, and `system-resumed` is emitted so
+//! the UI can refresh.
+
+use crate::config::WakeConfig;
+use crate::events::{self, SystemResumed};
+use crate::pty;
+use crate::state::AppState;
+use crate::watcher;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+/// How often the clocks are compared
+pub const WAKE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Longest a staggered watcher restart is spread over
+pub const MAX_RESTART_SPREAD: Duration = Duration::from_secs(10);
+
+/// Notices sleep as the wall clock getting ahead of the monotonic clock
+/// between two readings
+pub struct SleepDetector {
+    threshold: Duration,
+    last: Option<(Instant, SystemTime)>,
+}
+
+impl SleepDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold, last: None }
+    }
+
+    /// Record a reading of both clocks. Returns the time spent asleep since
+    /// the previous reading, if it reaches the threshold. A wall clock set
+    /// backwards counts as no sleep.
+    pub fn observe(&mut self, monotonic: Instant, wall: SystemTime) -> Option<Duration> {
+        let (last_monotonic, last_wall) = self.last.replace((monotonic, wall))?;
+        let awake = monotonic.saturating_duration_since(last_monotonic);
+        let elapsed = wall.duration_since(last_wall).unwrap_or_default();
+        let slept = elapsed.saturating_sub(awake);
+        (slept >= self.threshold).then_some(slept)
+    }
+}
+
+/// Start offsets for `count` restarts, `step` apart, squeezed so the last one
+/// starts within `max_spread`
+pub fn stagger_offsets(count: usize, step: Duration, max_spread: Duration) -> Vec<Duration> {
+    let Some(last) = count.checked_sub(1).filter(|&last| last > 0) else {
+        return vec![Duration::ZERO; count];
+    };
+    let step = step.min(max_spread / last as u32);
+    (0..count).map(|i| step * i as u32).collect()
+}
+
+/// Compare the clocks every `WAKE_CHECK_INTERVAL` and recover after a sleep.
+/// A threshold of 0 turns detection off.
+pub fn start_wake_monitor(app: AppHandle, state: Arc<AppState>, config: WakeConfig) {
+    if config.sleep_threshold_seconds == 0 {
+        return;
+    }
+    let stagger = Duration::from_millis(config.watcher_stagger_ms);
+    std::thread::spawn(move || {
+        let mut detector = SleepDetector::new(Duration::from_secs(config.sleep_threshold_seconds));
+        detector.observe(Instant::now(), SystemTime::now());
+        loop {
+            std::thread::sleep(WAKE_CHECK_INTERVAL);
+            if pty::SHUTDOWN_IN_PROGRESS.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            if let Some(slept) = detector.observe(Instant::now(), SystemTime::now()) {
+                recover(&app, &state, slept, stagger);
+            }
+        }
+    });
+}
+
+fn recover(app: &AppHandle, state: &AppState, slept: Duration, stagger: Duration) {
+    let closed_sessions = pty::close_dead_sessions(state);
+    let watched = watcher::watched_worktrees();
+    eprintln!(
+        "[wake] Resumed after {}s asleep; closed {} dead sessions, restarting {} watchers",
+        slept.as_secs(),
+        closed_sessions.len(),
+        watched.len()
+    );
+
+    let _ = app.emit(
+        events::SYSTEM_RESUMED,
+        SystemResumed {
+            slept_seconds: slept.as_secs(),
+            closed_sessions,
+            restarting_watchers: watched.len(),
+        },
+    );
+
+    let offsets = stagger_offsets(watched.len(), stagger, MAX_RESTART_SPREAD);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut elapsed = Duration::ZERO;
+        for (offset, (worktree_id, worktree_path)) in offsets.into_iter().zip(watched) {
+            std::thread::sleep(offset - elapsed);
+            elapsed = offset;
+            watcher::restart_watching(app.clone(), worktree_id, worktree_path);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn detects_sleep_only_when_the_wall_clock_runs_ahead() {
+        let mono = Instant::now();
+        let wall = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut detector = SleepDetector::new(60 * SECOND);
+        assert_eq!(detector.observe(mono, wall), None);
+
+        // Both clocks advanced together: awake
+        assert_eq!(detector.observe(mono + 30 * SECOND, wall + 30 * SECOND), None);
+        // A short drift stays under the threshold
+        assert_eq!(detector.observe(mono + 60 * SECOND, wall + 65 * SECOND), None);
+        // Eight hours passed on the wall clock, 30 seconds on the monotonic one
+        let slept = detector.observe(mono + 90 * SECOND, wall + (65 + 8 * 3600) * SECOND);
+        assert_eq!(slept, Some((8 * 3600 - 30) * SECOND));
+        // Measured from the latest reading, so one sleep is reported once
+        assert_eq!(detector.observe(mono + 120 * SECOND, wall + (95 + 8 * 3600) * SECOND), None);
+        // The wall clock set backwards isn't sleep
+        assert_eq!(detector.observe(mono + 150 * SECOND, wall), None);
+    }
+
+    #[test]
+    fn restarts_are_staggered_within_the_spread() {
+        let ms = Duration::from_millis;
+        assert_eq!(stagger_offsets(0, ms(200), 10 * SECOND), Vec::<Duration>::new());
+        assert_eq!(stagger_offsets(1, ms(200), 10 * SECOND), vec![Duration::ZERO]);
+        assert_eq!(stagger_offsets(3, ms(200), 10 * SECOND), vec![ms(0), ms(200), ms(400)]);
+
+        // 101 watchers 200ms apart would take 20s; squeezed into 10s
+        let offsets = stagger_offsets(101, ms(200), 10 * SECOND);
+        assert_eq!(offsets.len(), 101);
+        assert_eq!(offsets[1], ms(100));
+        assert_eq!(*offsets.last().unwrap(), 10 * SECOND);
+        assert!(offsets.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -1,14 +1,22 @@
 use crate::config;
+use crate::events::{
+    self, BulkOperation, BulkOperationFinished, CommitAdded, ConfigChanged, FilesChanged, MergeComplete,
+    RebaseComplete, WorktreeRemoved,
+};
 use crate::git;
-use crate::state::FileChange;
+use crate::path_utils::PathMatcher;
+use crate::state::{AppState, ChangedFiles, FileChange};
+use crate::status::{self, WatcherStatus};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use std::thread;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Resolve the actual git directory for a given repo/worktree path.
 /// For regular repos, this is <path>/.git
@@ -34,20 +42,132 @@ fn resolve_git_dir(repo_path: &Path) -> Option<PathBuf> {
     }
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct FilesChanged {
-    pub worktree_path: String,
-    pub files: Vec<FileChange>,
+/// The git dir shared by all worktrees, where branch refs live. A linked
+/// worktree's git dir names it in its `commondir` file; otherwise it is the
+/// git dir itself.
+fn resolve_common_dir(git_dir: &Path) -> PathBuf {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(content) => git_dir.join(content.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// How recently HEAD must have moved for the tree to count as mid-operation
+const HEAD_CHANGE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BulkAction {
+    None,
+    Started,
+    Finished,
+}
+
+/// Detects git operations (checkout, merge, ...) that rewrite many files, so
+/// the watcher can emit one start and one finish event instead of a refresh
+/// per debounce window. A bulk operation is in progress while `index.lock`
+/// exists or HEAD moved within `HEAD_CHANGE_WINDOW`, and ends once neither
+/// holds and there has been a quiet period with no events.
+struct BulkOperationTracker {
+    quiet_period: Duration,
+    active: bool,
+    last_head_change: Option<Instant>,
+    last_busy: Option<Instant>,
+}
+
+impl BulkOperationTracker {
+    fn new(quiet_period: Duration) -> Self {
+        Self {
+            quiet_period,
+            active: false,
+            last_head_change: None,
+            last_busy: None,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn head_changed(&mut self, now: Instant) {
+        self.last_head_change = Some(now);
+    }
+
+    /// Advance the state machine. `last_event` is the time of the latest
+    /// unprocessed path event, if any; only pending events start an operation
+    /// so a short-lived lock from a plain status refresh is ignored.
+    fn tick(&mut self, now: Instant, index_locked: bool, last_event: Option<Instant>) -> BulkAction {
+        let head_recent = self
+            .last_head_change
+            .is_some_and(|t| now.duration_since(t) < HEAD_CHANGE_WINDOW);
+        let busy = index_locked || head_recent;
+        if busy {
+            self.last_busy = Some(now);
+        }
+
+        if !self.active {
+            if busy && last_event.is_some() {
+                self.active = true;
+                return BulkAction::Started;
+            }
+            return BulkAction::None;
+        }
+
+        let settled_since = match (last_event, self.last_busy) {
+            (Some(event), Some(busy_at)) => event.max(busy_at),
+            (Some(t), None) | (None, Some(t)) => t,
+            (None, None) => now,
+        };
+        if !busy && now.duration_since(settled_since) >= self.quiet_period {
+            self.active = false;
+            self.last_head_change = None;
+            return BulkAction::Finished;
+        }
+        BulkAction::None
+    }
+}
+
+/// Whether a watcher event touched the worktree's HEAD file
+fn is_head_event(event_path: &Path, git_dir: &Path) -> bool {
+    event_path.file_name().is_some_and(|name| name == "HEAD")
+        && event_path.parent().is_some_and(|parent| parent == git_dir)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct WorktreeRemoved {
-    pub worktree_path: String,
+/// A running worktree watcher
+struct WatcherEntry {
+    /// Tells watcher threads apart when a worktree's watcher is restarted
+    generation: u64,
+    path: String,
+    stop: Sender<()>,
 }
 
 // Track active watchers so we can stop them
 lazy_static::lazy_static! {
-    static ref WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
+    static ref WATCHERS: Mutex<HashMap<String, WatcherEntry>> = Mutex::new(HashMap::new());
+    /// Worktrees whose watcher failed to start or can't watch the git dir
+    static ref DEGRADED_WATCHERS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    /// Worktrees whose watcher is holding back refreshes during a bulk operation
+    static ref PAUSED_WATCHERS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+static WATCHER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Drop the watcher entry of `worktree_id` if it still belongs to `generation`.
+/// A stopped watcher exits after its replacement may have registered.
+fn forget_watcher(worktree_id: &str, generation: u64) -> bool {
+    let mut watchers = WATCHERS.lock();
+    if watchers.get(worktree_id).is_some_and(|entry| entry.generation == generation) {
+        watchers.remove(worktree_id);
+        return true;
+    }
+    false
+}
+
+/// Counts for the status summary
+pub fn watcher_status() -> WatcherStatus {
+    let watching = WATCHERS.lock().len();
+    let degraded = DEGRADED_WATCHERS.lock().len();
+    let paused = PAUSED_WATCHERS.lock().len();
+    WatcherStatus { watching, degraded, paused }
 }
 
 pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String) {
@@ -58,7 +178,15 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
 
     // Create stop channel
     let (stop_tx, stop_rx) = channel::<()>();
-    WATCHERS.lock().insert(worktree_id.clone(), stop_tx);
+    let generation = WATCHER_GENERATION.fetch_add(1, Ordering::Relaxed);
+    WATCHERS.lock().insert(
+        worktree_id.clone(),
+        WatcherEntry {
+            generation,
+            path: worktree_path.clone(),
+            stop: stop_tx,
+        },
+    );
 
     let worktree_id_clone = worktree_id.clone();
 
@@ -73,7 +201,10 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
             Ok(w) => w,
             Err(e) => {
                 eprintln!("Failed to create watcher: {}", e);
-                WATCHERS.lock().remove(&worktree_id_clone);
+                status::record_error("watcher", format!("Failed to create watcher: {}", e));
+                if forget_watcher(&worktree_id_clone, generation) {
+                    DEGRADED_WATCHERS.lock().insert(worktree_id_clone);
+                }
                 return;
             }
         };
@@ -81,19 +212,25 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
         let path = Path::new(&worktree_path);
         if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
             eprintln!("Failed to watch path: {}", e);
-            WATCHERS.lock().remove(&worktree_id_clone);
+            status::record_error("watcher", format!("Failed to watch {}: {}", worktree_path, e));
+            if forget_watcher(&worktree_id_clone, generation) {
+                DEGRADED_WATCHERS.lock().insert(worktree_id_clone);
+            }
             return;
         }
+        DEGRADED_WATCHERS.lock().remove(&worktree_id_clone);
 
         // Also watch the git index file to detect staging/unstaging changes.
         // For regular repos, .git is a directory; for worktrees, .git is a file
         // pointing to the actual git directory (e.g., .git/worktrees/<name>).
         // The index file is in the git directory.
-        if let Some(git_dir) = resolve_git_dir(path) {
+        let git_dir = resolve_git_dir(path);
+        if let Some(git_dir) = &git_dir {
             // Watch the git directory (non-recursive) to catch index changes
-            if let Err(e) = watcher.watch(&git_dir, RecursiveMode::NonRecursive) {
+            if let Err(e) = watcher.watch(git_dir, RecursiveMode::NonRecursive) {
                 // Non-fatal: we can still watch file changes even if we can't watch the index
                 eprintln!("[Watcher] Failed to watch git dir {:?}: {}", git_dir, e);
+                DEGRADED_WATCHERS.lock().insert(worktree_id_clone.clone());
             }
         }
 
@@ -101,6 +238,7 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
         let debounce_duration = Duration::from_millis(500);
         let mut pending_update = false;
         let mut last_event_time = std::time::Instant::now();
+        let mut bulk = BulkOperationTracker::new(debounce_duration);
 
         // Check for external folder deletion every 2 seconds (not every loop iteration)
         let existence_check_interval = Duration::from_secs(2);
@@ -115,13 +253,19 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
 
             // Use short timeout to check for debounce expiry
             match rx.recv_timeout(Duration::from_millis(100)) {
-                Ok(Ok(_event)) => {
+                Ok(Ok(event)) => {
                     // New event: mark pending and reset timer
                     pending_update = true;
                     last_event_time = std::time::Instant::now();
+                    if let Some(git_dir) = &git_dir {
+                        if event.paths.iter().any(|p| is_head_event(p, git_dir)) {
+                            bulk.head_changed(last_event_time);
+                        }
+                    }
                 }
                 Ok(Err(e)) => {
                     eprintln!("Watch error: {}", e);
+                    status::record_error("watcher", format!("Watch error: {}", e));
                 }
                 Err(_) => {
                     // Timeout - check if we should process pending update
@@ -132,52 +276,143 @@ pub fn watch_worktree(app: AppHandle, worktree_id: String, worktree_path: String
             if last_existence_check.elapsed() >= existence_check_interval {
                 last_existence_check = std::time::Instant::now();
                 if !path.exists() {
-                    eprintln!(
-                        "[Watcher] Worktree folder deleted externally: {}",
-                        worktree_path
-                    );
+                    // Looked up now rather than trusting the id this watcher started with:
+                    // the entry may have been deleted or re-added since
+                    let state = app.state::<Arc<AppState>>();
+                    match state.worktree_id_for_path(&worktree_path) {
+                        Some(id) if state.is_being_deleted(&id) => {
+                            eprintln!("[Watcher] {} is being deleted, not reporting it", worktree_path);
+                        }
+                        Some(_) => {
+                            eprintln!(
+                                "[Watcher] Worktree folder deleted externally: {}",
+                                worktree_path
+                            );
+                            let _ = app.emit(
+                                events::WORKTREE_REMOVED,
+                                WorktreeRemoved {
+                                    worktree_path: worktree_path.clone(),
+                                },
+                            );
+                        }
+                        None => {
+                            eprintln!("[Watcher] {} is no longer a known worktree", worktree_path);
+                        }
+                    }
+                    break;
+                }
+            }
+
+            // Hold back intermediate refreshes while a checkout/merge rewrites the tree
+            let index_locked = git_dir.as_ref().is_some_and(|d| d.join("index.lock").exists());
+            let last_event = pending_update.then_some(last_event_time);
+            match bulk.tick(Instant::now(), index_locked, last_event) {
+                BulkAction::Started => {
+                    PAUSED_WATCHERS.lock().insert(worktree_id_clone.clone());
                     let _ = app.emit(
-                        "worktree-removed",
-                        WorktreeRemoved {
+                        events::BULK_OPERATION,
+                        BulkOperation {
                             worktree_path: worktree_path.clone(),
                         },
                     );
-                    break;
                 }
+                BulkAction::Finished => {
+                    PAUSED_WATCHERS.lock().remove(&worktree_id_clone);
+                    pending_update = false;
+                    if let Ok(files) = emit_changed_files(&app, &worktree_path) {
+                        let _ = app.emit(
+                            events::BULK_OPERATION_FINISHED,
+                            BulkOperationFinished {
+                                worktree_path: worktree_path.clone(),
+                                files,
+                            },
+                        );
+                    }
+                }
+                BulkAction::None => {}
             }
 
             // Process pending update after debounce period of quiet
-            if pending_update && last_event_time.elapsed() >= debounce_duration {
+            if pending_update && !bulk.is_active() && last_event_time.elapsed() >= debounce_duration {
                 pending_update = false;
 
                 // Get changed files and emit
-                if let Ok(files) = git::get_changed_files(path) {
-                    let _ = app.emit(
-                        "files-changed",
-                        FilesChanged {
-                            worktree_path: worktree_path.clone(),
-                            files,
-                        },
-                    );
-                }
+                let _ = emit_changed_files(&app, &worktree_path);
             }
         }
 
-        WATCHERS.lock().remove(&worktree_id_clone);
+        if forget_watcher(&worktree_id_clone, generation) {
+            PAUSED_WATCHERS.lock().remove(&worktree_id_clone);
+        }
     });
 }
 
+/// Recompute the changed files for a worktree and emit `files-changed`.
+/// Runs immediately (no debounce) and doesn't require an active watcher.
+pub fn emit_changed_files(app: &AppHandle, worktree_path: &str) -> Result<Vec<FileChange>, git::GitError> {
+    let mut files = git::get_changed_files(Path::new(worktree_path))?;
+    git::mark_hidden(&mut files, &hide_matcher(app, worktree_path));
+    let changed = ChangedFiles::new(files);
+    let _ = app.emit(
+        events::FILES_CHANGED,
+        FilesChanged {
+            worktree_path: worktree_path.to_string(),
+            files: changed.files.clone(),
+            hidden_count: changed.hidden_count,
+        },
+    );
+    Ok(changed.files)
+}
+
+/// `changes.hidePatterns` of the project owning `worktree_path`
+fn hide_matcher(app: &AppHandle, worktree_path: &str) -> PathMatcher {
+    let Some(project) = app.state::<Arc<AppState>>().project_for_directory(worktree_path) else {
+        return PathMatcher::default();
+    };
+    let options = config::ConfigLoadOptions {
+        disabled_layers: project.disabled_config_layers,
+    };
+    let cfg = config::load_config_for_project_with(Some(&project.path), &options);
+    PathMatcher::new(&cfg.changes.hide_patterns)
+}
+
 pub fn stop_watching(worktree_id: &str) {
-    if let Some(tx) = WATCHERS.lock().remove(worktree_id) {
-        let _ = tx.send(());
+    DEGRADED_WATCHERS.lock().remove(worktree_id);
+    PAUSED_WATCHERS.lock().remove(worktree_id);
+    if let Some(entry) = WATCHERS.lock().remove(worktree_id) {
+        let _ = entry.stop.send(());
     }
 }
 
+/// (id, path) of every worktree with a running watcher
+pub fn watched_worktrees() -> Vec<(String, String)> {
+    let mut watched: Vec<_> = WATCHERS
+        .lock()
+        .iter()
+        .map(|(id, entry)| (id.clone(), entry.path.clone()))
+        .collect();
+    watched.sort();
+    watched
+}
+
+/// Replace a worktree's watcher with a fresh one, e.g. when its event stream
+/// may have died during system sleep
+pub fn restart_watching(app: AppHandle, worktree_id: String, worktree_path: String) {
+    stop_watching(&worktree_id);
+    watch_worktree(app, worktree_id, worktree_path);
+}
+
 pub fn stop_all_watchers() {
     // Stop file watchers
     let watchers = std::mem::take(&mut *WATCHERS.lock());
-    for (_, tx) in watchers {
-        let _ = tx.send(());
+    for (_, entry) in watchers {
+        let _ = entry.stop.send(());
+    }
+
+    // Stop commit watchers
+    let commit_watchers = std::mem::take(&mut *COMMIT_WATCHERS.lock());
+    for (_, entry) in commit_watchers {
+        let _ = entry.stop.send(());
     }
 
     // Stop config watcher
@@ -192,13 +427,6 @@ lazy_static::lazy_static! {
     static ref MERGE_WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
 }
 
-#[derive(Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MergeComplete {
-    pub worktree_id: String,
-    pub worktree_path: String,
-}
-
 /// Watch for merge completion in a worktree.
 /// Detects when MERGE_HEAD is deleted (merge committed successfully).
 pub fn watch_merge_state(app: AppHandle, worktree_id: String, worktree_path: String) {
@@ -243,7 +471,7 @@ pub fn watch_merge_state(app: AppHandle, worktree_id: String, worktree_path: Str
             if !merge_head_path.exists() {
                 eprintln!("[MergeWatcher] MERGE_HEAD deleted - merge complete for {}", worktree_id_clone);
                 let _ = app.emit(
-                    "merge-complete",
+                    events::MERGE_COMPLETE,
                     MergeComplete {
                         worktree_id: worktree_id_clone.clone(),
                         worktree_path: worktree_path_clone.clone(),
@@ -265,16 +493,123 @@ pub fn stop_merge_watcher(worktree_id: &str) {
     }
 }
 
-// Track active rebase watchers
+/// A running commit watcher
+struct CommitWatcherEntry {
+    /// Tells watcher threads apart when a worktree's commit watcher is restarted
+    generation: u64,
+    stop: Sender<()>,
+}
+
+// Track active commit watchers
 lazy_static::lazy_static! {
-    static ref REBASE_WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
+    static ref COMMIT_WATCHERS: Mutex<HashMap<String, CommitWatcherEntry>> = Mutex::new(HashMap::new());
 }
 
-#[derive(Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct RebaseComplete {
-    pub worktree_id: String,
-    pub worktree_path: String,
+/// Drop the commit watcher entry of `worktree_id` if it still belongs to
+/// `generation`, like `forget_watcher`
+fn forget_commit_watcher(worktree_id: &str, generation: u64) {
+    let mut watchers = COMMIT_WATCHERS.lock();
+    if watchers.get(worktree_id).is_some_and(|entry| entry.generation == generation) {
+        watchers.remove(worktree_id);
+    }
+}
+
+/// Watch a worktree's refs and emit `commit-added` with the new commit each
+/// time HEAD advances, so a history view doesn't have to re-read the log.
+/// Watches the worktree's git dir (HEAD, its reflog) and the shared
+/// `refs/heads` and `packed-refs`, since commits update the branch ref there.
+pub fn watch_commits(app: AppHandle, worktree_id: String, worktree_path: String) {
+    let Some(git_dir) = resolve_git_dir(Path::new(&worktree_path)) else {
+        eprintln!("[CommitWatcher] Could not resolve git dir for {:?}", worktree_path);
+        return;
+    };
+    let common_dir = resolve_common_dir(&git_dir);
+
+    let (stop_tx, stop_rx) = channel::<()>();
+    let generation = WATCHER_GENERATION.fetch_add(1, Ordering::Relaxed);
+    {
+        // Checked and registered under one lock so two starts can't both spawn
+        let mut watchers = COMMIT_WATCHERS.lock();
+        if watchers.contains_key(&worktree_id) {
+            return;
+        }
+        watchers.insert(worktree_id.clone(), CommitWatcherEntry { generation, stop: stop_tx });
+    }
+
+    thread::spawn(move || {
+        let (tx, rx) = channel::<notify::Result<Event>>();
+        let mut watcher: RecommendedWatcher = match Watcher::new(tx, Config::default()) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("[CommitWatcher] Failed to create watcher: {}", e);
+                forget_commit_watcher(&worktree_id, generation);
+                return;
+            }
+        };
+        let targets = [
+            (git_dir.clone(), RecursiveMode::NonRecursive),
+            (git_dir.join("logs"), RecursiveMode::NonRecursive),
+            (common_dir.clone(), RecursiveMode::NonRecursive),
+            (common_dir.join("refs").join("heads"), RecursiveMode::Recursive),
+        ];
+        for (path, mode) in &targets {
+            if path.exists() {
+                if let Err(e) = watcher.watch(path, *mode) {
+                    eprintln!("[CommitWatcher] Failed to watch {:?}: {}", path, e);
+                }
+            }
+        }
+
+        let worktree = PathBuf::from(&worktree_path);
+        let mut last_seen = git::head_commit_id(&worktree);
+        let debounce = Duration::from_millis(200);
+        let mut pending_since: Option<Instant> = None;
+
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                eprintln!("[CommitWatcher] Stopping commit watcher for {}", worktree_id);
+                break;
+            }
+
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(Ok(_)) => pending_since = Some(Instant::now()),
+                Ok(Err(e)) => eprintln!("[CommitWatcher] Watch error: {}", e),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            if pending_since.is_some_and(|at| at.elapsed() >= debounce) {
+                pending_since = None;
+                match git::head_advanced(&worktree, &mut last_seen) {
+                    Ok(Some(commit)) => {
+                        let _ = app.emit(
+                            events::COMMIT_ADDED,
+                            CommitAdded {
+                                worktree_id: worktree_id.clone(),
+                                worktree_path: worktree_path.clone(),
+                                commit,
+                            },
+                        );
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!("[CommitWatcher] Failed to read HEAD for {}: {}", worktree_id, e),
+                }
+            }
+        }
+
+        forget_commit_watcher(&worktree_id, generation);
+    });
+}
+
+pub fn stop_commit_watcher(worktree_id: &str) {
+    if let Some(entry) = COMMIT_WATCHERS.lock().remove(worktree_id) {
+        let _ = entry.stop.send(());
+    }
+}
+
+// Track active rebase watchers
+lazy_static::lazy_static! {
+    static ref REBASE_WATCHERS: Mutex<HashMap<String, Sender<()>>> = Mutex::new(HashMap::new());
 }
 
 /// Watch for rebase completion in a worktree.
@@ -322,7 +657,7 @@ pub fn watch_rebase_state(app: AppHandle, worktree_id: String, worktree_path: St
             if !rebase_merge_path.exists() && !rebase_apply_path.exists() {
                 eprintln!("[RebaseWatcher] Rebase complete for {}", worktree_id_clone);
                 let _ = app.emit(
-                    "rebase-complete",
+                    events::REBASE_COMPLETE,
                     RebaseComplete {
                         worktree_id: worktree_id_clone.clone(),
                         worktree_path: worktree_path_clone.clone(),
@@ -349,12 +684,6 @@ lazy_static::lazy_static! {
     static ref CONFIG_WATCHER: Mutex<Option<Sender<()>>> = Mutex::new(None);
 }
 
-#[derive(Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ConfigChanged {
-    pub project_path: Option<String>,
-}
-
 /// Watch config files for changes and emit events when they change.
 /// Watches: global config, repo config, local config (if project_path provided)
 pub fn watch_config(app: AppHandle, project_path: Option<String>) {
@@ -449,8 +778,9 @@ pub fn watch_config(app: AppHandle, project_path: Option<String>) {
             if pending_update && last_event_time.elapsed() >= debounce_duration {
                 pending_update = false;
                 eprintln!("[ConfigWatcher] Config changed, emitting event");
+                crate::perf::set_enabled(config::load_config().perf.enabled);
                 let _ = app.emit(
-                    "config-changed",
+                    events::CONFIG_CHANGED,
                     ConfigChanged {
                         project_path: project_path_clone.clone(),
                     },
@@ -560,7 +890,7 @@ pub fn watch_mappings(app: AppHandle) {
             if pending_update && last_event_time.elapsed() >= debounce_duration {
                 pending_update = false;
                 eprintln!("[MappingsWatcher] Mappings changed, emitting event");
-                let _ = app.emit("mappings-changed", ());
+                let _ = app.emit(events::MAPPINGS_CHANGED, ());
             }
         }
 
@@ -573,3 +903,123 @@ pub fn stop_mappings_watcher() {
         let _ = tx.send(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET: Duration = Duration::from_millis(500);
+
+    fn at(base: Instant, ms: u64) -> Instant {
+        base + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn bulk_operation_spans_index_lock() {
+        let base = Instant::now();
+        let mut tracker = BulkOperationTracker::new(QUIET);
+
+        // Lock appears together with the first path event
+        assert_eq!(tracker.tick(at(base, 0), true, Some(at(base, 0))), BulkAction::Started);
+
+        // A stream of path events while the lock is held stays suppressed
+        for ms in (100..=2000).step_by(100) {
+            assert_eq!(tracker.tick(at(base, ms), true, Some(at(base, ms))), BulkAction::None);
+            assert!(tracker.is_active());
+        }
+
+        // Lock disappears; not finished until a quiet period has passed
+        assert_eq!(tracker.tick(at(base, 2100), false, Some(at(base, 2000))), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 2400), false, Some(at(base, 2000))), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 2600), false, Some(at(base, 2000))), BulkAction::Finished);
+        assert!(!tracker.is_active());
+
+        // Nothing more to report afterwards
+        assert_eq!(tracker.tick(at(base, 3000), false, None), BulkAction::None);
+    }
+
+    #[test]
+    fn late_events_extend_the_quiet_period() {
+        let base = Instant::now();
+        let mut tracker = BulkOperationTracker::new(QUIET);
+
+        assert_eq!(tracker.tick(at(base, 0), true, Some(at(base, 0))), BulkAction::Started);
+        assert_eq!(tracker.tick(at(base, 100), false, Some(at(base, 100))), BulkAction::None);
+        // A straggling write after the lock cleared
+        assert_eq!(tracker.tick(at(base, 550), false, Some(at(base, 500))), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 900), false, Some(at(base, 500))), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 1000), false, Some(at(base, 500))), BulkAction::Finished);
+    }
+
+    #[test]
+    fn head_change_counts_as_bulk_operation() {
+        let base = Instant::now();
+        let mut tracker = BulkOperationTracker::new(QUIET);
+
+        tracker.head_changed(at(base, 0));
+        assert_eq!(tracker.tick(at(base, 50), false, Some(at(base, 0))), BulkAction::Started);
+        // Still within the HEAD window, even after events stopped
+        assert_eq!(tracker.tick(at(base, 900), false, Some(at(base, 100))), BulkAction::None);
+        // HEAD window elapsed, but quiet period counts from the last busy tick
+        assert_eq!(tracker.tick(at(base, 1100), false, Some(at(base, 100))), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 1400), false, Some(at(base, 100))), BulkAction::Finished);
+    }
+
+    #[test]
+    fn lock_without_pending_events_is_ignored() {
+        let base = Instant::now();
+        let mut tracker = BulkOperationTracker::new(QUIET);
+
+        // e.g. a status refresh briefly taking the index lock
+        assert_eq!(tracker.tick(at(base, 0), true, None), BulkAction::None);
+        assert_eq!(tracker.tick(at(base, 100), false, Some(at(base, 100))), BulkAction::None);
+        assert!(!tracker.is_active());
+    }
+
+    #[test]
+    fn detects_head_events_in_git_dir_only() {
+        let git_dir = Path::new("/repo/.git");
+        assert!(is_head_event(Path::new("/repo/.git/HEAD"), git_dir));
+        assert!(!is_head_event(Path::new("/repo/.git/ORIG_HEAD"), git_dir));
+        assert!(!is_head_event(Path::new("/repo/src/HEAD"), git_dir));
+    }
+
+    #[test]
+    fn common_dir_of_a_linked_worktree_is_the_main_git_dir() {
+        let base = std::env::temp_dir().join(format!("shellflow-watcher-{}", uuid::Uuid::new_v4()));
+        let repo = base.join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let output = std::process::Command::new("git").args(args).current_dir(dir).output().unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", base.join("wt").to_str().unwrap()]);
+
+        let main_git_dir = resolve_git_dir(&repo).unwrap();
+        assert_eq!(resolve_common_dir(&main_git_dir), main_git_dir);
+        let linked = resolve_git_dir(&base.join("wt")).unwrap();
+        assert_ne!(linked, main_git_dir);
+        assert_eq!(
+            resolve_common_dir(&linked).canonicalize().unwrap(),
+            main_git_dir.canonicalize().unwrap()
+        );
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn exiting_commit_watcher_leaves_its_replacement_registered() {
+        let id = format!("commit-watcher-test-{}", uuid::Uuid::new_v4());
+        let (stop, _stop_rx) = channel::<()>();
+        COMMIT_WATCHERS.lock().insert(id.clone(), CommitWatcherEntry { generation: 2, stop });
+
+        // The stopped generation-1 thread exits after generation 2 started
+        forget_commit_watcher(&id, 1);
+        assert!(COMMIT_WATCHERS.lock().contains_key(&id));
+
+        forget_commit_watcher(&id, 2);
+        assert!(!COMMIT_WATCHERS.lock().contains_key(&id));
+    }
+}
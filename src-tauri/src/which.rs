@@ -0,0 +1,138 @@
+//! Resolving command names to executables, like `which`.
+//!
+//! Used before launching apps, for git itself and for shell detection, so a
+//! missing tool shows up as "not found" rather than a failed spawn. Lookups
+//! go through the user's login-shell PATH, which is wider than the PATH of an
+//! app started from the dock. A name only matches a file that can be run:
+//! one with an execute bit on Unix, and on Windows a name without an
+//! extension also matches with each `PATHEXT` extension (`code` finds `code.cmd`).
+
+use crate::pty;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// First of `candidates` that is an executable in the user's PATH, as a full path
+pub fn find_in_user_path(candidates: &[&str]) -> Option<String> {
+    let path = pty::get_cached_user_path();
+    candidates
+        .iter()
+        .find_map(|name| find_executable(name, OsStr::new(&path)))
+}
+
+/// Resolve `name` against a PATH-style list. A name with a directory part is
+/// checked as given instead of searched for.
+pub fn find_executable(name: &str, path_var: &OsStr) -> Option<String> {
+    let names: Vec<String> = std::iter::once(name.to_string()).chain(pathext_variants(name)).collect();
+
+    if Path::new(name).components().count() > 1 {
+        return names
+            .iter()
+            .map(PathBuf::from)
+            .find(|p| is_executable(p))
+            .map(|p| p.to_string_lossy().to_string());
+    }
+
+    std::env::split_paths(path_var).find_map(|dir| {
+        names
+            .iter()
+            .map(|n| dir.join(n))
+            .find(|full| is_executable(full))
+            .map(|full| full.to_string_lossy().to_string())
+    })
+}
+
+#[cfg(windows)]
+fn pathext_variants(name: &str) -> Vec<String> {
+    if Path::new(name).extension().is_some() {
+        return Vec::new();
+    }
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    pathext
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| format!("{}{}", name, ext))
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn pathext_variants(_name: &str) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp dir with `bin/` holding the given (name, executable) files
+    fn bin_dir(files: &[(&str, bool)]) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("shellflow-which-{}", uuid::Uuid::new_v4()));
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        for (name, executable) in files {
+            std::fs::write(bin.join(name), "#!/bin/sh\n").unwrap();
+            #[cfg(unix)]
+            if *executable {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(bin.join(name), std::fs::Permissions::from_mode(0o755)).unwrap();
+            }
+            #[cfg(not(unix))]
+            let _ = executable;
+        }
+        (dir, bin)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn requires_the_execute_bit() {
+        let (dir, bin) = bin_dir(&[("tool", true), ("notes", false)]);
+        let path_var = std::env::join_paths([dir.join("missing"), bin.clone()]).unwrap();
+
+        let tool = bin.join("tool").to_string_lossy().to_string();
+        assert_eq!(find_executable("tool", &path_var).as_deref(), Some(tool.as_str()));
+        assert_eq!(find_executable("notes", &path_var), None);
+        assert_eq!(find_executable("absent", &path_var), None);
+        // A path is checked as given, not searched for
+        assert_eq!(find_executable(&tool, OsStr::new("")).as_deref(), Some(tool.as_str()));
+        assert_eq!(find_executable("bin/tool", &path_var), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn matches_pathext_extensions() {
+        let (dir, bin) = bin_dir(&[("code.cmd", true), ("notes.txt", true)]);
+        let path_var = std::env::join_paths([bin.clone()]).unwrap();
+
+        let code = bin.join("code.cmd").to_string_lossy().to_string();
+        assert_eq!(find_executable("code", &path_var).as_deref(), Some(code.as_str()));
+        assert_eq!(find_executable("code.cmd", &path_var).as_deref(), Some(code.as_str()));
+        assert_eq!(find_executable("notes", &path_var), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn earlier_path_entries_win() {
+        let (first, first_bin) = bin_dir(&[("tool", true)]);
+        let (second, second_bin) = bin_dir(&[("tool", true)]);
+        let path_var = std::env::join_paths([first_bin.clone(), second_bin]).unwrap();
+
+        let found = find_executable("tool", &path_var).unwrap();
+        assert_eq!(Path::new(&found), first_bin.join("tool").as_path());
+
+        let _ = std::fs::remove_dir_all(&first);
+        let _ = std::fs::remove_dir_all(&second);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::config::BaseBranch;
+use crate::config::{BaseBranch, NameGeneratorConfig, NameStrategy};
+use crate::copy_store::CopyStore;
 use crate::git;
 use crate::path_utils;
 use crate::state::{Project, Worktree};
-use crate::template::{expand_template, TemplateContext};
+use crate::template::{expand_name_prefix, expand_template, TemplateContext};
 use log::info;
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -19,10 +24,20 @@ pub enum WorktreeError {
     NotARepository,
     #[error("Worktree not found: {0}")]
     WorktreeNotFound(String),
-    #[error("Could not generate unique branch name after {0} attempts")]
-    NameGenerationFailed(u32),
+    #[error(
+        "Could not generate unique branch name after {attempts} attempts ({blocked} blocked by nameGenerator.blocklist, {collided} already existed)"
+    )]
+    NameGenerationFailed { attempts: u32, blocked: u32, collided: u32 },
+    #[error("Name generator error: {0}")]
+    NameGenerator(String),
     #[error("Template error: {0}")]
     Template(String),
+    #[error("Worktree path is {length} characters, over the Windows limit of {limit}")]
+    PathTooLong { length: usize, limit: usize },
+    #[error("'{name}' contains a name reserved on Windows; try '{suggestion}'")]
+    ReservedName { name: String, suggestion: String },
+    #[error("'{0}' is not the same repository as the project")]
+    RepositoryMismatch(String),
 }
 
 /// Generate a random worktree name using petname (adjective-animal format)
@@ -30,20 +45,237 @@ pub fn generate_worktree_name() -> String {
     petname::petname(2, "-").unwrap_or_else(|| "quick-fox".to_string())
 }
 
+lazy_static::lazy_static! {
+    /// Word lists loaded from disk, keyed by resolved path
+    static ref WORD_LISTS: Mutex<HashMap<PathBuf, Arc<Vec<String>>>> = Mutex::new(HashMap::new());
+}
+
+/// Resolve a configured word-list path: `~/` expands to the home directory and
+/// relative paths are taken from the project directory when one is known
+pub fn resolve_word_list_path(path: &str, project_path: Option<&Path>) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    let path = PathBuf::from(path);
+    match project_path {
+        Some(project) if path.is_relative() => project.join(path),
+        _ => path,
+    }
+}
+
+/// Load a word list (one word per line, `#` starts a comment). Words are
+/// lowercased and anything that isn't letters, digits, `-` or `_` is dropped so
+/// generated names stay valid branch names regardless of the list's language.
+/// Successful loads are cached for the lifetime of the process.
+pub fn load_word_list(path: &Path) -> Result<Arc<Vec<String>>, String> {
+    if let Some(words) = WORD_LISTS.lock().get(path) {
+        return Ok(words.clone());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read word list: {}", e))?;
+    let words: Vec<String> = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim().to_lowercase())
+        .filter(|word| {
+            !word.is_empty()
+                && !word.starts_with('-')
+                && word.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        })
+        .collect();
+    if words.is_empty() {
+        return Err("Word list contains no usable words".to_string());
+    }
+
+    let words = Arc::new(words);
+    WORD_LISTS.lock().insert(path.to_path_buf(), words.clone());
+    Ok(words)
+}
+
+/// OS user name for `{{ user }}` in name prefixes, lowercased, with anything
+/// that doesn't belong in a branch name replaced by `-`
+pub fn current_user() -> String {
+    let raw = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    let user: String = raw
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    match user.trim_matches('-') {
+        "" => "user".to_string(),
+        user => user.to_string(),
+    }
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC for a Unix time
+fn utc_timestamp(secs: u64) -> String {
+    let (days, time) = (secs / 86400, secs % 86400);
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's days_from_civil, inverted)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Generates worktree names from the built-in or configured word lists,
+/// rejecting names that match the blocklist
+#[derive(Debug, Default)]
+pub struct NameGenerator {
+    blocklist: Vec<glob::Pattern>,
+    adjectives: Option<Arc<Vec<String>>>,
+    nouns: Option<Arc<Vec<String>>>,
+    /// Expanded `prefix`, ending in a separator when not empty
+    prefix: String,
+    strategy: NameStrategy,
+}
+
+impl NameGenerator {
+    pub fn from_config(config: &NameGeneratorConfig, project_path: &Path) -> Result<Self, WorktreeError> {
+        let blocklist = config
+            .blocklist
+            .iter()
+            .map(|p| {
+                glob::Pattern::new(p)
+                    .map_err(|e| WorktreeError::NameGenerator(format!("invalid blocklist pattern '{}': {}", p, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let load = |list: &Option<String>| -> Result<Option<Arc<Vec<String>>>, WorktreeError> {
+            list.as_deref()
+                .map(|p| {
+                    let path = resolve_word_list_path(p, Some(project_path));
+                    load_word_list(&path)
+                        .map_err(|e| WorktreeError::NameGenerator(format!("{}: {}", path.display(), e)))
+                })
+                .transpose()
+        };
+
+        let mut prefix = match &config.prefix {
+            Some(template) => expand_name_prefix(
+                template,
+                &path_utils::normalize_path_string(project_path),
+                &current_user(),
+            )
+            .map_err(WorktreeError::Template)?,
+            None => String::new(),
+        };
+        if !prefix.is_empty() && !prefix.ends_with(['/', '-', '_']) {
+            prefix.push('-');
+        }
+
+        Ok(Self {
+            blocklist,
+            adjectives: load(&config.adjectives)?,
+            nouns: load(&config.nouns)?,
+            prefix,
+            strategy: config.strategy,
+        })
+    }
+
+    /// Name to try on attempt `attempt` (from 0), prefix included. The
+    /// incremental strategy numbers past the highest `<prefix>N` in
+    /// `existing_branches`; without a prefix it counts `worktree-N`.
+    pub fn candidate(&self, attempt: u32, existing_branches: &[String]) -> String {
+        match self.strategy {
+            NameStrategy::Petname => format!("{}{}", self.prefix, self.generate()),
+            NameStrategy::Timestamp => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                let stamp = utc_timestamp(now.as_secs());
+                match attempt {
+                    0 => format!("{}{}", self.prefix, stamp),
+                    n => format!("{}{}-{}", self.prefix, stamp, n + 1),
+                }
+            }
+            NameStrategy::Incremental => {
+                let prefix = if self.prefix.is_empty() { "worktree-" } else { self.prefix.as_str() };
+                let highest = existing_branches
+                    .iter()
+                    .filter_map(|branch| branch.strip_prefix(prefix)?.parse::<u64>().ok())
+                    .max()
+                    .unwrap_or(0);
+                format!("{}{}", prefix, highest + 1 + u64::from(attempt))
+            }
+        }
+    }
+
+    /// Generate a candidate name (adjective-noun)
+    pub fn generate(&self) -> String {
+        if self.adjectives.is_none() && self.nouns.is_none() {
+            return generate_worktree_name();
+        }
+
+        use rand::seq::SliceRandom;
+        let mut rng = rand::thread_rng();
+        let builtin = petname::Petnames::default();
+        let pick = |custom: &Option<Arc<Vec<String>>>, fallback: &[&str], rng: &mut rand::rngs::ThreadRng| {
+            match custom {
+                Some(words) => words.choose(rng).cloned(),
+                None => fallback.choose(rng).map(|w| w.to_string()),
+            }
+        };
+        let adjective = pick(&self.adjectives, &builtin.adjectives, &mut rng).unwrap_or_else(|| "quick".to_string());
+        let noun = pick(&self.nouns, &builtin.nouns, &mut rng).unwrap_or_else(|| "fox".to_string());
+        format!("{}-{}", adjective, noun)
+    }
+
+    pub fn is_blocked(&self, name: &str) -> bool {
+        self.blocklist.iter().any(|p| p.matches(name))
+    }
+}
+
 /// Generate a unique worktree name that doesn't conflict with existing branches
-pub fn generate_unique_worktree_name(repo_path: &Path) -> Result<String, WorktreeError> {
+pub fn generate_unique_worktree_name(
+    repo_path: &Path,
+    generator: &NameGenerator,
+) -> Result<String, WorktreeError> {
+    let existing_branches = match generator.strategy {
+        NameStrategy::Incremental => git::local_branch_names(repo_path)?,
+        _ => Vec::new(),
+    };
+    generate_unique_name_with(generator, &existing_branches, |name| git::branch_exists(repo_path, name))
+}
+
+fn generate_unique_name_with(
+    generator: &NameGenerator,
+    existing_branches: &[String],
+    mut branch_exists: impl FnMut(&str) -> Result<bool, git::GitError>,
+) -> Result<String, WorktreeError> {
     const MAX_ATTEMPTS: u32 = 100;
 
-    for _ in 0..MAX_ATTEMPTS {
-        let name = generate_worktree_name();
-        match git::branch_exists(repo_path, &name) {
+    let mut blocked = 0;
+    let mut collided = 0;
+    for attempt in 0..MAX_ATTEMPTS {
+        let name = generator.candidate(attempt, existing_branches);
+        if generator.is_blocked(&name) {
+            blocked += 1;
+            continue;
+        }
+        match branch_exists(&name) {
             Ok(false) => return Ok(name),
-            Ok(true) => continue, // Branch exists, try another name
+            Ok(true) => collided += 1, // Branch exists, try another name
             Err(e) => return Err(WorktreeError::Git(e)),
         }
     }
 
-    Err(WorktreeError::NameGenerationFailed(MAX_ATTEMPTS))
+    Err(WorktreeError::NameGenerationFailed { attempts: MAX_ATTEMPTS, blocked, collided })
 }
 
 /// Resolve worktree directory with Jinja2 template support.
@@ -96,6 +328,198 @@ pub fn resolve_worktree_directory(
     Ok(path)
 }
 
+/// Final path for a worktree: `{directory}/{worktree_name}`.
+///
+/// A template that already ends in the worktree name (e.g. `.../{{ worktree_name }}`)
+/// names the worktree directory itself, so the name isn't appended a second time.
+/// Templates that don't vary per worktree always get the name appended, which
+/// keeps worktrees from colliding in one directory.
+pub fn resolve_worktree_path(
+    worktree_directory: Option<&str>,
+    project_path: &Path,
+    worktree_name: &str,
+) -> Result<PathBuf, WorktreeError> {
+    let base = resolve_worktree_directory(
+        worktree_directory,
+        project_path,
+        Some(worktree_name),
+        Some(worktree_name),
+    )?;
+    if base.file_name().is_some_and(|n| n == worktree_name)
+        && template_varies_per_worktree(worktree_directory, project_path, worktree_name, &base)?
+    {
+        return Ok(base);
+    }
+    Ok(base.join(worktree_name))
+}
+
+/// Whether the template expands differently for another worktree name (i.e. it
+/// references `branch` or `worktree_name`)
+fn template_varies_per_worktree(
+    worktree_directory: Option<&str>,
+    project_path: &Path,
+    worktree_name: &str,
+    base: &Path,
+) -> Result<bool, WorktreeError> {
+    let probe = format!("{}-shellflow-probe", worktree_name);
+    let other = resolve_worktree_directory(worktree_directory, project_path, Some(&probe), Some(&probe))?;
+    Ok(other != base)
+}
+
+/// Longest directory path Windows accepts without long-path support
+/// (MAX_PATH minus room for an 8.3 file name)
+pub const WINDOWS_MAX_DIR_PATH: usize = 248;
+/// Path limit once long paths are enabled
+pub const WINDOWS_LONG_PATH_LIMIT: usize = 32_767;
+
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Whether Windows treats a path component as a device name. Extensions and
+/// trailing spaces don't help: `con.log` and `aux ` are reserved too.
+pub fn is_windows_reserved_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or("").trim_end_matches(' ');
+    WINDOWS_RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+/// `name` with `_` appended to the stem of each reserved component,
+/// e.g. `aux` -> `aux_` and `docs/con.log` -> `docs/con_.log`
+pub fn sanitize_windows_name(name: &str) -> String {
+    name.split('/')
+        .map(|component| {
+            if !is_windows_reserved_name(component) {
+                return component.to_string();
+            }
+            match component.split_once('.') {
+                Some((stem, ext)) => format!("{}_.{}", stem.trim_end_matches(' '), ext),
+                None => format!("{}_", component.trim_end_matches(' ')),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Reject worktree paths Windows can't create: device names in the components
+/// that come from the worktree name, and paths longer than `limit` UTF-16 units
+pub fn check_windows_worktree_path(worktree_path: &Path, worktree_name: &str, limit: usize) -> Result<(), WorktreeError> {
+    if worktree_name.split('/').any(is_windows_reserved_name) {
+        return Err(WorktreeError::ReservedName {
+            name: worktree_name.to_string(),
+            suggestion: sanitize_windows_name(worktree_name),
+        });
+    }
+    let length = worktree_path.to_string_lossy().encode_utf16().count();
+    if length > limit {
+        return Err(WorktreeError::PathTooLong { length, limit });
+    }
+    Ok(())
+}
+
+/// Path limit for worktrees of this repository: long paths need both the
+/// LongPathsEnabled policy and git's `core.longpaths`, since git creates the files
+fn windows_path_limit(project_path: &Path) -> usize {
+    let git_long_paths = git2::Repository::open(project_path)
+        .and_then(|repo| repo.config())
+        .and_then(|config| config.get_bool("core.longpaths"))
+        .unwrap_or(false);
+    if git_long_paths && windows_long_paths_enabled() {
+        WINDOWS_LONG_PATH_LIMIT
+    } else {
+        WINDOWS_MAX_DIR_PATH
+    }
+}
+
+/// Reads `LongPathsEnabled` from the registry once
+#[cfg(windows)]
+fn windows_long_paths_enabled() -> bool {
+    use std::os::windows::process::CommandExt;
+    use std::sync::OnceLock;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SYSTEM\CurrentControlSet\Control\FileSystem",
+                "/v",
+                "LongPathsEnabled",
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .is_some_and(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|line| line.contains("LongPathsEnabled"))
+                    .any(|line| line.split_whitespace().last() == Some("0x1"))
+            })
+    })
+}
+
+#[cfg(not(windows))]
+fn windows_long_paths_enabled() -> bool {
+    false
+}
+
+/// Where a worktree directory template would place a worktree
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeDirectoryPreview {
+    /// Directory the worktree is created in
+    pub directory: String,
+    /// Final worktree path (see `resolve_worktree_path`)
+    pub worktree_path: String,
+    /// Likely mistakes in the template that still expand successfully
+    pub warnings: Vec<String>,
+}
+
+/// Expand a `worktree.directory` template for a sample branch, as
+/// `create_worktree` would, without touching the filesystem.
+/// Template syntax and render errors are returned as `WorktreeError::Template`.
+pub fn preview_worktree_directory(
+    worktree_directory: Option<&str>,
+    project_path: &Path,
+    sample_branch: &str,
+) -> Result<WorktreeDirectoryPreview, WorktreeError> {
+    let worktree_directory = worktree_directory.filter(|t| !t.trim().is_empty());
+    let worktree_path = resolve_worktree_path(worktree_directory, project_path, sample_branch)?;
+    let directory = worktree_path.parent().unwrap_or(&worktree_path).to_path_buf();
+
+    let mut warnings = Vec::new();
+    if worktree_path.is_relative() {
+        warnings.push(format!(
+            "Template expands to a relative path ({}); use {{{{ repo_directory }}}} or an absolute path",
+            directory.display()
+        ));
+    }
+    // A per-worktree template that doesn't end in the name still gets it
+    // appended, nesting each worktree in its own extra directory
+    let base = resolve_worktree_directory(
+        worktree_directory,
+        project_path,
+        Some(sample_branch),
+        Some(sample_branch),
+    )?;
+    if base != worktree_path
+        && template_varies_per_worktree(worktree_directory, project_path, sample_branch, &base)?
+    {
+        warnings.push(format!(
+            "Template depends on branch or worktree_name but doesn't end with it, so worktrees are nested as {}",
+            path_utils::normalize_path_string(&worktree_path)
+        ));
+    }
+
+    Ok(WorktreeDirectoryPreview {
+        directory: path_utils::normalize_path_string(&directory),
+        worktree_path: path_utils::normalize_path_string(&worktree_path),
+        warnings,
+    })
+}
+
 pub fn create_project(path: &Path) -> Result<Project, WorktreeError> {
     if !git::is_git_repo(path) {
         return Err(WorktreeError::NotARepository);
@@ -109,33 +533,46 @@ pub fn create_project(path: &Path) -> Result<Project, WorktreeError> {
         order: 0,
         is_active: true,
         last_accessed_at: Some(chrono_lite_now()),
+        disabled_config_layers: vec![],
+        last_focused_worktree_id: None,
+        root_commit: git::root_commit(path).ok().flatten(),
     })
 }
 
+/// Create a worktree named `name` (generated when None). It gets a new branch
+/// of the same name, unless `existing_branch` names a branch to check out instead.
 pub fn create_worktree(
     project: &mut Project,
     name: Option<String>,
     worktree_directory: Option<&str>,
     base_branch: &BaseBranch,
+    name_generator: &NameGeneratorConfig,
+    adopt_existing_branch: bool,
+    existing_branch: Option<&str>,
 ) -> Result<Worktree, WorktreeError> {
     let total_start = Instant::now();
     info!("[worktree::create_worktree] Starting...");
 
     let project_path = Path::new(&project.path);
-    let worktree_name = match name {
-        Some(n) => n,
-        None => generate_unique_worktree_name(project_path)?,
+    let (worktree_name, new_branch) = match name {
+        Some(n) => (n.clone(), n),
+        None => {
+            let generator = NameGenerator::from_config(name_generator, project_path)?;
+            let branch = generate_unique_worktree_name(project_path, &generator)?;
+            // A prefix like "wt/alice/" namespaces the branch only; git names a
+            // worktree after its last path component, so the directory stays flat
+            (branch.replace('/', "-"), branch)
+        }
     };
     info!("[worktree::create_worktree] worktree_name: {}", worktree_name);
 
     // Create worktree directory using template expansion
-    let worktree_base = resolve_worktree_directory(
-        worktree_directory,
-        project_path,
-        Some(&worktree_name), // branch name is the same as worktree name
-        Some(&worktree_name),
-    )?;
-    let worktree_path = worktree_base.join(&worktree_name);
+    let worktree_path = resolve_worktree_path(worktree_directory, project_path, &worktree_name)?;
+    let worktree_base = worktree_path.parent().unwrap_or(&worktree_path).to_path_buf();
+    // Fail before anything is created rather than halfway through `git worktree add`
+    if cfg!(windows) {
+        check_windows_worktree_path(&worktree_path, &worktree_name, windows_path_limit(project_path))?;
+    }
 
     let start = Instant::now();
     std::fs::create_dir_all(&worktree_base)?;
@@ -143,16 +580,28 @@ pub fn create_worktree(
 
     // Create git worktree
     let start = Instant::now();
-    git::create_worktree(project_path, &worktree_path, &worktree_name, base_branch)?;
+    match existing_branch {
+        Some(branch) => git::create_worktree_for_branch(project_path, &worktree_path, branch)?,
+        None => git::create_worktree(
+            project_path,
+            &worktree_path,
+            &new_branch,
+            base_branch,
+            adopt_existing_branch,
+        )?,
+    }
     info!("[worktree::create_worktree] git::create_worktree took {:?}", start.elapsed());
 
     let worktree = Worktree {
         id: Uuid::new_v4().to_string(),
         name: worktree_name.clone(),
         path: path_utils::normalize_path_string(&worktree_path),
-        branch: worktree_name,
+        branch: existing_branch.map_or(new_branch, str::to_string),
         created_at: chrono_lite_now(),
         order: project.worktrees.len() as i32,
+        last_results: HashMap::new(),
+        pending_cleanup: false,
+        layout: None,
     };
 
     project.worktrees.push(worktree.clone());
@@ -161,11 +610,13 @@ pub fn create_worktree(
     Ok(worktree)
 }
 
-/// Copy gitignored files from the project to the worktree, excluding patterns in `except`
+/// Copy gitignored files from the project to the worktree, excluding patterns in `except`.
+/// With a `store`, files are linked from it instead of copied.
 pub fn copy_gitignored_files(
     project_path: &Path,
     worktree_path: &Path,
     except: &[String],
+    store: Option<&CopyStore>,
 ) -> Result<(), WorktreeError> {
     let total_start = Instant::now();
     info!("[copy_gitignored_files] Starting...");
@@ -175,30 +626,24 @@ pub fn copy_gitignored_files(
     let ignored_entries = git::get_ignored_files(project_path)?;
     info!("[copy_gitignored_files] get_ignored_files took {:?}, found {} entries", start.elapsed(), ignored_entries.len());
 
-    // Compile glob patterns for exceptions
-    let patterns: Vec<glob::Pattern> = except
-        .iter()
-        .filter_map(|p| glob::Pattern::new(p).ok())
-        .collect();
+    // Case-sensitive everywhere, as copy.except always was
+    let excluded = crate::path_utils::PathMatcher::with_case_sensitivity(except, true);
 
     let mut copied_count = 0;
     let mut skipped_count = 0;
     let mut copy_time = std::time::Duration::ZERO;
+    let mut deduped = store.map(CopyStore::begin);
+    let mut copy_file = |src: &Path, dst: &Path| match deduped.as_mut() {
+        Some(deduped) => deduped.copy_file(src, dst),
+        None => std::fs::copy(src, dst).map(|_| ()),
+    };
 
     for entry in ignored_entries {
         // Remove trailing slash if present (directories come with trailing /)
         let file_path = entry.trim_end_matches('/');
 
-        // Check if this path matches any exception pattern
-        let should_skip = patterns.iter().any(|pattern| {
-            // Match against the file path and also check if it starts with the pattern
-            // (to handle directories like ".claude" matching ".claude/foo")
-            pattern.matches(file_path)
-                || file_path.starts_with(&format!("{}/", pattern.as_str()))
-                || file_path == pattern.as_str()
-        });
-
-        if should_skip {
+        // Patterns also cover directory contents, so ".claude" skips ".claude/foo"
+        if excluded.matches(file_path) {
             skipped_count += 1;
             continue;
         }
@@ -219,21 +664,29 @@ pub fn copy_gitignored_files(
         // Copy file or directory
         let start = Instant::now();
         if src.is_dir() {
-            copy_dir_recursive(&src, &dst)?;
+            copy_dir_recursive(&src, &dst, &mut copy_file)?;
         } else {
-            std::fs::copy(&src, &dst)?;
+            copy_file(&src, &dst)?;
         }
         copy_time += start.elapsed();
         copied_count += 1;
     }
 
+    if let Some(deduped) = deduped {
+        deduped.finish(worktree_path)?;
+    }
+
     info!("[copy_gitignored_files] Copied {} entries, skipped {} entries", copied_count, skipped_count);
     info!("[copy_gitignored_files] Total copy time: {:?}", copy_time);
     info!("[copy_gitignored_files] TOTAL took {:?}", total_start.elapsed());
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
+fn copy_dir_recursive(
+    src: &Path,
+    dst: &Path,
+    copy_file: &mut impl FnMut(&Path, &Path) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
     std::fs::create_dir_all(dst)?;
 
     for entry in std::fs::read_dir(src)? {
@@ -242,15 +695,80 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
         let dst_path = dst.join(entry.file_name());
 
         if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
+            copy_dir_recursive(&src_path, &dst_path, copy_file)?;
         } else {
-            std::fs::copy(&src_path, &dst_path)?;
+            copy_file(&src_path, &dst_path)?;
         }
     }
 
     Ok(())
 }
 
+lazy_static::lazy_static! {
+    /// Shared limiter for background worktree setup work (gitignored file copies)
+    pub static ref SETUP_LIMITER: SetupLimiter = SetupLimiter::default();
+}
+
+/// Counting semaphore that caps how many background setup jobs run at once.
+///
+/// The limit is passed on every acquire so config changes apply to the next
+/// job without rebuilding the limiter.
+pub struct SetupLimiter {
+    running: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Held for the duration of a setup job; releases its slot on drop.
+pub struct SetupPermit<'a> {
+    limiter: &'a SetupLimiter,
+}
+
+impl Default for SetupLimiter {
+    fn default() -> Self {
+        Self {
+            running: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+}
+
+impl SetupLimiter {
+    /// Take a slot if fewer than `limit` jobs are running.
+    pub fn try_acquire(&self, limit: usize) -> Option<SetupPermit<'_>> {
+        let mut running = self.running.lock();
+        if *running < limit.max(1) {
+            *running += 1;
+            Some(SetupPermit { limiter: self })
+        } else {
+            None
+        }
+    }
+
+    /// Block until a slot is free, then take it.
+    pub fn acquire(&self, limit: usize) -> SetupPermit<'_> {
+        let mut running = self.running.lock();
+        while *running >= limit.max(1) {
+            self.released.wait(&mut running);
+        }
+        *running += 1;
+        SetupPermit { limiter: self }
+    }
+
+    /// Number of jobs currently holding a slot
+    pub fn running(&self) -> usize {
+        *self.running.lock()
+    }
+}
+
+impl Drop for SetupPermit<'_> {
+    fn drop(&mut self) {
+        let mut running = self.limiter.running.lock();
+        *running = running.saturating_sub(1);
+        // Waiters may use different limits, so wake all of them to re-check
+        self.limiter.released.notify_all();
+    }
+}
+
 pub fn delete_worktree(project: &mut Project, worktree_id: &str) -> Result<(), WorktreeError> {
     let worktree_idx = project
         .worktrees
@@ -258,16 +776,13 @@ pub fn delete_worktree(project: &mut Project, worktree_id: &str) -> Result<(), W
         .position(|w| w.id == worktree_id)
         .ok_or_else(|| WorktreeError::WorktreeNotFound(worktree_id.to_string()))?;
 
-    let worktree = &project.worktrees[worktree_idx];
-
-    // Delete worktree
-    let project_path = Path::new(&project.path);
-    git::delete_worktree(project_path, &worktree.name)?;
+    let project_path = Path::new(&project.path).to_path_buf();
+    let worktree = &mut project.worktrees[worktree_idx];
 
-    // Remove worktree directory if it still exists
-    let worktree_path = Path::new(&worktree.path);
-    if worktree_path.exists() {
-        std::fs::remove_dir_all(worktree_path)?;
+    // Remove the directory and git registration; keep the entry if the directory survives
+    if let Err(e) = remove_worktree_files(&project_path, &worktree.name, Path::new(&worktree.path)) {
+        worktree.pending_cleanup = true;
+        return Err(e);
     }
 
     project.worktrees.remove(worktree_idx);
@@ -275,6 +790,113 @@ pub fn delete_worktree(project: &mut Project, worktree_id: &str) -> Result<(), W
     Ok(())
 }
 
+/// Number of attempts when removing a worktree directory
+const REMOVE_DIR_ATTEMPTS: u32 = 5;
+/// Delay before the first retry; doubles after each failed attempt
+const REMOVE_DIR_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Errors that may clear up on their own (open handles, NFS silly-renames)
+fn is_retryable_remove_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ResourceBusy | std::io::ErrorKind::DirectoryNotEmpty
+    )
+}
+
+/// NFS clients rename files that are still open to `.nfsXXXX` and delete them once
+/// the last handle closes. They can't be removed by us, so we leave them alone.
+fn is_nfs_silly_rename(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with(".nfs")
+}
+
+/// Recursively remove a directory, skipping `.nfs*` files.
+/// Fails with DirectoryNotEmpty if any of them are still present.
+fn remove_dir_skipping_nfs(path: &Path) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if is_nfs_silly_rename(&entry.file_name()) {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        let result = if entry.file_type()?.is_dir() {
+            remove_dir_skipping_nfs(&entry_path)
+        } else {
+            std::fs::remove_file(&entry_path)
+        };
+
+        match result {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+
+    std::fs::remove_dir(path)
+}
+
+fn remove_with_retry<F>(mut remove: F, attempts: u32, initial_backoff: Duration) -> Result<(), std::io::Error>
+where
+    F: FnMut() -> Result<(), std::io::Error>,
+{
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match remove() {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if is_retryable_remove_error(&e) && attempt < attempts => {
+                info!(
+                    "[remove_worktree_dir] attempt {}/{} failed: {}, retrying in {:?}",
+                    attempt, attempts, e, backoff
+                );
+                std::thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Remove a worktree directory, retrying with backoff on EBUSY/ENOTEMPTY.
+/// Callers should terminate sessions running inside the worktree first.
+pub fn remove_worktree_dir(path: &Path) -> Result<(), std::io::Error> {
+    remove_with_retry(
+        || remove_dir_skipping_nfs(path),
+        REMOVE_DIR_ATTEMPTS,
+        REMOVE_DIR_INITIAL_BACKOFF,
+    )
+}
+
+/// Remove a worktree's directory and its git registration.
+///
+/// `git worktree prune` runs even when the directory can't be removed, so the
+/// registration doesn't block re-creating the same name. An error means the
+/// directory is still on disk and the worktree should be kept as pending cleanup.
+pub fn remove_worktree_files(
+    project_path: &Path,
+    worktree_name: &str,
+    worktree_path: &Path,
+) -> Result<(), WorktreeError> {
+    let removal = remove_worktree_dir(worktree_path);
+
+    if removal.is_ok() {
+        if let Err(e) = git::delete_worktree(project_path, worktree_name) {
+            info!("[remove_worktree_files] git worktree removal failed for '{}': {}", worktree_name, e);
+        }
+        // Free deduplicated copies only this worktree used
+        if let Err(e) = CopyStore::open_default().release_worktree(worktree_path) {
+            info!("[remove_worktree_files] copy store cleanup failed: {}", e);
+        }
+    }
+
+    if let Err(e) = git::prune_worktrees(project_path) {
+        info!("[remove_worktree_files] git worktree prune failed: {}", e);
+    }
+
+    removal.map_err(WorktreeError::from)
+}
+
 // Simple timestamp without external chrono dependency
 pub fn chrono_lite_now() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -324,6 +946,121 @@ mod tests {
         assert!(unique_count > 1, "Names should have some variety");
     }
 
+    fn write_word_list(dir: &Path, name: &str, content: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_unique_name_normal_operation() {
+        let generator = NameGenerator::default();
+        let mut checked = 0;
+        let name = generate_unique_name_with(&generator, &[], |_| {
+            checked += 1;
+            Ok(checked < 3)
+        })
+        .unwrap();
+        assert_eq!(checked, 3);
+        assert_eq!(name.split('-').count(), 2, "unexpected name: {}", name);
+    }
+
+    #[test]
+    fn test_unique_name_blocklist_exhaustion() {
+        let config = NameGeneratorConfig { blocklist: vec!["*".to_string()], ..Default::default() };
+        let generator = NameGenerator::from_config(&config, Path::new("/tmp")).unwrap();
+        let err = generate_unique_name_with(&generator, &[], |_| panic!("blocked names must not reach git"))
+            .unwrap_err();
+        match err {
+            WorktreeError::NameGenerationFailed { attempts, blocked, collided } => {
+                assert_eq!((attempts, blocked, collided), (100, 100, 0));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_unique_name_counts_blocked_and_collided() {
+        let dir = std::env::temp_dir().join(format!("shellflow-names-{}", Uuid::new_v4()));
+        let adjectives = write_word_list(&dir, "adjectives.txt", "release\nhappy\n");
+        let config = NameGeneratorConfig {
+            blocklist: vec!["release-*".to_string()],
+            adjectives: Some(adjectives.display().to_string()),
+            nouns: None,
+            ..Default::default()
+        };
+        let generator = NameGenerator::from_config(&config, &dir).unwrap();
+        let err = generate_unique_name_with(&generator, &[], |_| Ok(true)).unwrap_err();
+        match err {
+            WorktreeError::NameGenerationFailed { blocked, collided, .. } => {
+                assert_eq!(blocked + collided, 100);
+                assert!(blocked > 0 && collided > 0, "blocked={} collided={}", blocked, collided);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_name_prefix_and_strategies() {
+        let generator = |prefix: Option<&str>, strategy| {
+            let config = NameGeneratorConfig { prefix: prefix.map(str::to_string), strategy, ..Default::default() };
+            NameGenerator::from_config(&config, Path::new("/tmp/repo")).unwrap()
+        };
+
+        let petname = generator(Some("wt/{{ user }}/"), NameStrategy::Petname).candidate(0, &[]);
+        let rest = petname.strip_prefix(&format!("wt/{}/", current_user())).unwrap();
+        assert_eq!(rest.split('-').count(), 2, "unexpected name: {}", petname);
+
+        let stamped = generator(Some("nightly"), NameStrategy::Timestamp);
+        let first = stamped.candidate(0, &[]);
+        assert!(first.starts_with("nightly-") && first.len() == "nightly-20240131-154502".len(), "{}", first);
+        assert!(stamped.candidate(1, &[]).ends_with("-2"));
+        assert_eq!(utc_timestamp(1_706_715_902), "20240131-154502");
+        assert_eq!(utc_timestamp(951_782_400), "20000229-000000");
+
+        // The next number after the highest existing one, still checked for collisions
+        let existing: Vec<String> = ["fix-1", "fix-7", "fix-x", "other-9"].iter().map(|b| b.to_string()).collect();
+        let incremental = generator(Some("fix"), NameStrategy::Incremental);
+        let name = generate_unique_name_with(&incremental, &existing, |name| Ok(name == "fix-8")).unwrap();
+        assert_eq!(name, "fix-9");
+        assert_eq!(generator(None, NameStrategy::Incremental).candidate(0, &[]), "worktree-1");
+    }
+
+    #[test]
+    fn test_custom_word_lists() {
+        let dir = std::env::temp_dir().join(format!("shellflow-names-{}", Uuid::new_v4()));
+        write_word_list(&dir, "adjectives.txt", "# adjectives\nFröhlich\nbad word\n\n");
+        write_word_list(&dir, "nouns.txt", "otter # river animal\n");
+        let config = NameGeneratorConfig {
+            blocklist: vec![],
+            adjectives: Some("adjectives.txt".to_string()),
+            nouns: Some("nouns.txt".to_string()),
+            ..Default::default()
+        };
+        let generator = NameGenerator::from_config(&config, &dir).unwrap();
+        for _ in 0..5 {
+            assert_eq!(generator.generate(), "fröhlich-otter");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_word_list_missing_or_empty() {
+        let dir = std::env::temp_dir().join(format!("shellflow-names-{}", Uuid::new_v4()));
+        assert!(load_word_list(&dir.join("missing.txt")).is_err());
+        let empty = write_word_list(&dir, "empty.txt", "# nothing here\n\n");
+        assert!(load_word_list(&empty).is_err());
+
+        let config = NameGeneratorConfig { nouns: Some("missing.txt".to_string()), ..Default::default() };
+        assert!(matches!(
+            NameGenerator::from_config(&config, &dir),
+            Err(WorktreeError::NameGenerator(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_resolve_worktree_directory_default() {
         let project_path = PathBuf::from("/home/user/myproject");
@@ -425,6 +1162,98 @@ mod tests {
         assert!((10000..20000).contains(&port));
     }
 
+    #[test]
+    fn test_preview_worktree_directory() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let preview = preview_worktree_directory(None, &project_path, "feature-x").unwrap();
+        assert_eq!(preview.directory, "/home/user/myproject/.worktrees");
+        assert_eq!(preview.worktree_path, "/home/user/myproject/.worktrees/feature-x");
+        assert!(preview.warnings.is_empty());
+
+        // An empty template falls back to the default
+        let preview = preview_worktree_directory(Some("  "), &project_path, "feature-x").unwrap();
+        assert_eq!(preview.directory, "/home/user/myproject/.worktrees");
+    }
+
+    #[test]
+    fn test_preview_worktree_directory_warnings() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let preview = preview_worktree_directory(
+            Some("/tmp/worktrees/{{ branch | hash_port }}"),
+            &project_path,
+            "feature-x",
+        )
+        .unwrap();
+        assert!(preview.worktree_path.ends_with("/feature-x"));
+        assert_eq!(preview.warnings.len(), 1);
+
+        // Ending in the name is not nesting, so there's nothing to warn about
+        let preview = preview_worktree_directory(
+            Some("{{ repo_directory }}/.worktrees/{{ worktree_name }}"),
+            &project_path,
+            "feature-x",
+        )
+        .unwrap();
+        assert_eq!(preview.directory, "/home/user/myproject/.worktrees");
+        assert!(preview.warnings.is_empty());
+
+        let preview = preview_worktree_directory(Some("worktrees"), &project_path, "feature-x").unwrap();
+        assert!(preview.warnings[0].contains("relative path"));
+    }
+
+    #[test]
+    fn test_preview_worktree_directory_template_error() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = preview_worktree_directory(Some("{{ repo_directory"), &project_path, "feature-x");
+        assert!(matches!(result, Err(WorktreeError::Template(_))));
+    }
+
+    #[test]
+    fn test_resolve_worktree_path_appends_name_to_shared_directory() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let a = resolve_worktree_path(None, &project_path, "alpha").unwrap();
+        let b = resolve_worktree_path(None, &project_path, "beta").unwrap();
+        assert_eq!(a, PathBuf::from("/home/user/myproject/.worktrees/alpha"));
+        assert_eq!(b, PathBuf::from("/home/user/myproject/.worktrees/beta"));
+
+        // A static directory that happens to share the name still gets it appended
+        let result = resolve_worktree_path(Some("/var/alpha"), &project_path, "alpha").unwrap();
+        assert_eq!(result, PathBuf::from("/var/alpha/alpha"));
+    }
+
+    #[test]
+    fn test_resolve_worktree_path_does_not_double_name() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = resolve_worktree_path(
+            Some("{{ repo_directory }}/.worktrees/{{ worktree_name }}"),
+            &project_path,
+            "happy-dolphin",
+        )
+        .unwrap();
+        assert_eq!(result, PathBuf::from("/home/user/myproject/.worktrees/happy-dolphin"));
+
+        let result = resolve_worktree_path(
+            Some("~/trees/{{ branch | sanitize }}"),
+            &project_path,
+            "happy-dolphin",
+        )
+        .unwrap();
+        assert!(result.ends_with("trees/happy-dolphin"));
+        assert_ne!(result.parent().unwrap().file_name().unwrap(), "happy-dolphin");
+    }
+
+    #[test]
+    fn test_resolve_worktree_path_nests_when_name_is_not_last() {
+        let project_path = PathBuf::from("/home/user/myproject");
+        let result = resolve_worktree_path(
+            Some("/tmp/{{ worktree_name }}/checkout"),
+            &project_path,
+            "alpha",
+        )
+        .unwrap();
+        assert_eq!(result, PathBuf::from("/tmp/alpha/checkout/alpha"));
+    }
+
     #[test]
     fn test_chrono_lite_now_format() {
         let timestamp = chrono_lite_now();
@@ -443,4 +1272,228 @@ mod tests {
         assert!(date_parts[1].parse::<u32>().is_ok(), "Month should be numeric");
         assert!(date_parts[2].parse::<u32>().is_ok(), "Day should be numeric");
     }
+
+    #[test]
+    fn test_setup_limiter_caps_concurrent_jobs() {
+        let limiter = SetupLimiter::default();
+        let first = limiter.try_acquire(2);
+        let second = limiter.try_acquire(2);
+        assert!(first.is_some());
+        assert!(second.is_some());
+        assert!(limiter.try_acquire(2).is_none());
+        assert_eq!(limiter.running(), 2);
+
+        drop(first);
+        assert_eq!(limiter.running(), 1);
+        assert!(limiter.try_acquire(2).is_some());
+    }
+
+    #[test]
+    fn test_setup_limiter_zero_limit_allows_one() {
+        let limiter = SetupLimiter::default();
+        let permit = limiter.try_acquire(0);
+        assert!(permit.is_some());
+        assert!(limiter.try_acquire(0).is_none());
+    }
+
+    #[test]
+    fn test_setup_limiter_acquire_waits_for_release() {
+        let limiter = std::sync::Arc::new(SetupLimiter::default());
+        let permit = limiter.try_acquire(1).unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || {
+                let _permit = limiter.acquire(1);
+            })
+        };
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+
+        drop(permit);
+        waiter.join().unwrap();
+        assert_eq!(limiter.running(), 0);
+    }
+
+    fn make_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shellflow-{}-{}", label, Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_remove_with_retry_recovers_from_busy() {
+        let mut calls = 0;
+        let result = remove_with_retry(
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err(std::io::Error::from(std::io::ErrorKind::ResourceBusy))
+                } else {
+                    Ok(())
+                }
+            },
+            5,
+            Duration::from_millis(1),
+        );
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_remove_with_retry_gives_up() {
+        let mut calls = 0;
+        let result = remove_with_retry(
+            || {
+                calls += 1;
+                Err(std::io::Error::from(std::io::ErrorKind::ResourceBusy))
+            },
+            3,
+            Duration::from_millis(1),
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        // Non-retryable errors fail immediately
+        let mut calls = 0;
+        let result = remove_with_retry(
+            || {
+                calls += 1;
+                Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            },
+            3,
+            Duration::from_millis(1),
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_remove_worktree_dir_removes_tree() {
+        let dir = make_temp_dir("remove");
+        std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+        std::fs::write(dir.join("src/nested/file.txt"), "x").unwrap();
+        std::fs::write(dir.join("README.md"), "x").unwrap();
+
+        remove_worktree_dir(&dir).unwrap();
+        assert!(!dir.exists());
+
+        // Already missing is fine
+        remove_worktree_dir(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_worktree_files_keeps_pending_on_busy_nfs_file() {
+        let project = make_temp_dir("project");
+        let worktree = project.join(".worktrees").join("busy");
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(worktree.join("main.rs"), "x").unwrap();
+
+        // Simulate a silly-renamed file still held open by a process
+        let nfs_file = worktree.join(".nfs000000001");
+        std::fs::write(&nfs_file, "x").unwrap();
+        let handle = std::fs::File::open(&nfs_file).unwrap();
+
+        let result = remove_worktree_files(&project, "busy", &worktree);
+        assert!(result.is_err(), "removal should report pending cleanup");
+        assert!(worktree.exists());
+        assert!(nfs_file.exists(), ".nfs files are left alone");
+        assert!(!worktree.join("main.rs").exists(), "other files are removed");
+
+        // Once the handle closes, NFS drops the file and a retry succeeds
+        drop(handle);
+        std::fs::remove_file(&nfs_file).unwrap();
+        remove_worktree_files(&project, "busy", &worktree).unwrap();
+        assert!(!worktree.exists());
+
+        let _ = std::fs::remove_dir_all(&project);
+    }
+
+    #[test]
+    fn windows_reserved_names_are_detected_and_suffixed() {
+        for reserved in ["aux", "CON", "con.log", "Com1", "lpt9.tar.gz", "nul "] {
+            assert!(is_windows_reserved_name(reserved), "{reserved}");
+        }
+        for allowed in ["auxiliary", "com10", "lpt0", "my-con", "console.log", ""] {
+            assert!(!is_windows_reserved_name(allowed), "{allowed}");
+        }
+
+        assert_eq!(sanitize_windows_name("aux"), "aux_");
+        assert_eq!(sanitize_windows_name("feature/con.log"), "feature/con_.log");
+        assert_eq!(sanitize_windows_name("prn/nul"), "prn_/nul_");
+        assert_eq!(sanitize_windows_name("happy-dolphin"), "happy-dolphin");
+    }
+
+    #[test]
+    fn windows_worktree_path_checks() {
+        let base = Path::new("C:/repo/.worktrees");
+        match check_windows_worktree_path(&base.join("fix/aux"), "fix/aux", WINDOWS_MAX_DIR_PATH) {
+            Err(WorktreeError::ReservedName { name, suggestion }) => {
+                assert_eq!(name, "fix/aux");
+                assert_eq!(suggestion, "fix/aux_");
+            }
+            other => panic!("expected ReservedName, got {:?}", other),
+        }
+
+        let long_name = "x".repeat(240);
+        let path = base.join(&long_name);
+        match check_windows_worktree_path(&path, &long_name, WINDOWS_MAX_DIR_PATH) {
+            Err(WorktreeError::PathTooLong { length, limit }) => {
+                assert_eq!(length, path.to_string_lossy().len());
+                assert_eq!(limit, WINDOWS_MAX_DIR_PATH);
+            }
+            other => panic!("expected PathTooLong, got {:?}", other),
+        }
+        assert!(check_windows_worktree_path(&path, &long_name, WINDOWS_LONG_PATH_LIMIT).is_ok());
+        assert!(check_windows_worktree_path(&base.join("ok"), "ok", WINDOWS_MAX_DIR_PATH).is_ok());
+    }
+
+    #[test]
+    fn test_slash_prefix_only_namespaces_the_branch() {
+        let repo = make_temp_dir("slash-prefix");
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=shellflow", "-c", "user.email=shellflow@example.com"])
+                .args(args)
+                .current_dir(&repo)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "-q"]);
+        std::fs::write(repo.join("a.txt"), "one\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        let mut project = create_project(&repo).unwrap();
+        let config = NameGeneratorConfig {
+            prefix: Some("wt/alice/".to_string()),
+            strategy: NameStrategy::Incremental,
+            ..Default::default()
+        };
+        let worktree = create_worktree(
+            &mut project,
+            None,
+            None,
+            &BaseBranch::Mode(crate::config::BaseBranchMode::Current),
+            &config,
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(worktree.branch, "wt/alice/1");
+        assert_eq!(worktree.name, "wt-alice-1");
+        let worktree_path = PathBuf::from(&worktree.path);
+        assert_eq!(worktree_path.parent().and_then(|p| p.file_name()).unwrap(), ".worktrees");
+        assert!(repo.join(".git/worktrees/wt-alice-1").exists());
+
+        // Removed by name, without relying on `git worktree prune`
+        git::delete_worktree(&repo, &worktree.name).unwrap();
+        assert!(!worktree_path.exists());
+        assert!(!repo.join(".git/worktrees/wt-alice-1").exists());
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
 }
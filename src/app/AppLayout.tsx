@@ -49,6 +49,7 @@ interface AppLayoutOverlaysProps {
   pendingCreateWorktreeProject: Project | null;
   pendingCreateWorktreeName: string;
   createWorktreeError: string | null;
+  createWorktreeExistingBranch: string | null;
   isCreatingWorktree: boolean;
   pendingStashProject: Project | null;
   showCommitModal: boolean;
@@ -70,6 +71,7 @@ interface AppLayoutOverlaysProps {
   onPendingCreateWorktreeNameChange: (name: string) => void;
   onCreateWorktreeWithDefault: () => void;
   onCreateWorktreeWithCustom: () => void;
+  onCreateWorktreeOnExistingBranch: () => void;
   onCancelCreateWorktree: () => void;
   onStashAndCreate: () => void;
   onCancelStash: () => void;
@@ -122,6 +124,7 @@ export function AppLayout({ theme, config, overlays, pickers, layout, toasts }:
     pendingCreateWorktreeProject,
     pendingCreateWorktreeName,
     createWorktreeError,
+    createWorktreeExistingBranch,
     isCreatingWorktree,
     pendingStashProject,
     showCommitModal,
@@ -138,6 +141,7 @@ export function AppLayout({ theme, config, overlays, pickers, layout, toasts }:
     onPendingCreateWorktreeNameChange,
     onCreateWorktreeWithDefault,
     onCreateWorktreeWithCustom,
+    onCreateWorktreeOnExistingBranch,
     onCancelCreateWorktree,
     onStashAndCreate,
     onCancelStash,
@@ -228,10 +232,12 @@ export function AppLayout({ theme, config, overlays, pickers, layout, toasts }:
             projectName={pendingCreateWorktreeProject.name}
             worktreeName={pendingCreateWorktreeName}
             error={createWorktreeError}
+            existingBranch={createWorktreeExistingBranch}
             isCreating={isCreatingWorktree}
             onWorktreeNameChange={onPendingCreateWorktreeNameChange}
             onCreateWithDefault={onCreateWorktreeWithDefault}
             onCreateWithCustomName={onCreateWorktreeWithCustom}
+            onUseExistingBranch={onCreateWorktreeOnExistingBranch}
             onCancel={onCancelCreateWorktree}
             onModalOpen={onModalOpen}
             onModalClose={onModalClose}
@@ -6,6 +6,7 @@ import type { Project } from '../types';
 import type { ActionHandlers } from '../hooks/useActions';
 import { getAppCommand, getAppTarget, type AppsConfig } from '../hooks/useConfig';
 import { substitutePathTemplate } from '../lib/pathTemplate';
+import { errorMessage } from '../lib/errors';
 
 type EntityOrderEntry = { type: 'scratch' | 'project' | 'worktree'; id: string };
 
@@ -208,7 +209,7 @@ export function buildActionHandlers(deps: BuildActionHandlersDeps): ActionHandle
           terminalApp: terminalCommand ?? null,
         }).catch((err) => {
           console.error('Failed to open editor:', err);
-          const message = err instanceof Error ? err.message : String(err);
+          const message = errorMessage(err);
           showWarning(`打开编辑器失败：${message}`);
         });
       }
@@ -211,6 +211,7 @@ interface BuildAppLayoutDeps {
   pendingCreateWorktreeProject: Project | null;
   pendingCreateWorktreeName: string;
   createWorktreeError: string | null;
+  createWorktreeExistingBranch: string | null;
   isCreatingWorktree: boolean;
   pendingStashProject: Project | null;
   stashError: string | null;
@@ -225,6 +226,7 @@ interface BuildAppLayoutDeps {
   onPendingCreateWorktreeNameChange: (name: string) => void;
   onCreateWorktreeWithDefault: () => void;
   onCreateWorktreeWithCustom: () => void;
+  onCreateWorktreeOnExistingBranch: () => void;
   onCancelCreateWorktree: () => void;
   onStashAndCreate: () => void;
   onCancelStash: () => void;
@@ -458,6 +460,7 @@ export function buildAppLayoutProps(deps: BuildAppLayoutDeps): AppLayoutParts {
     pendingCreateWorktreeProject: deps.pendingCreateWorktreeProject,
     pendingCreateWorktreeName: deps.pendingCreateWorktreeName,
     createWorktreeError: deps.createWorktreeError,
+    createWorktreeExistingBranch: deps.createWorktreeExistingBranch,
     isCreatingWorktree: deps.isCreatingWorktree,
     pendingStashProject: deps.pendingStashProject,
     showCommitModal: deps.isCommitModalOpen,
@@ -498,6 +501,7 @@ export function buildAppLayoutProps(deps: BuildAppLayoutDeps): AppLayoutParts {
     onPendingCreateWorktreeNameChange: deps.onPendingCreateWorktreeNameChange,
     onCreateWorktreeWithDefault: deps.onCreateWorktreeWithDefault,
     onCreateWorktreeWithCustom: deps.onCreateWorktreeWithCustom,
+    onCreateWorktreeOnExistingBranch: deps.onCreateWorktreeOnExistingBranch,
     onCancelCreateWorktree: deps.onCancelCreateWorktree,
     onStashAndCreate: deps.onStashAndCreate,
     onCancelStash: deps.onCancelStash,
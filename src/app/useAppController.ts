@@ -12,7 +12,7 @@ import { useDrawerTabs } from '../hooks/useDrawerTabs';
 import { useSessionTabs, SessionTab } from '../hooks/useSessionTabs';
 import { useSplitActions } from '../contexts/SplitContext';
 import { log } from '../lib/log';
-import { selectFolder, shutdown, ptyKill, ptyForceKill, stashChanges, stashPop, reorderProjects, reorderWorktrees, expandActionPrompt, ActionPromptContext, touchProject, updateConfig } from '../lib/tauri';
+import { selectFolder, shutdown, ptyKill, ptyForceKill, stashChanges, stashPop, reorderProjects, reorderWorktrees, expandActionPrompt, ActionPromptContext, touchProject, getLastFocused, updateConfig, getDriftReport, updateProjectPath } from '../lib/tauri';
 import { ActionContext, ActionId } from '../lib/actions';
 import { useActions, ActionHandlers } from '../hooks/useActions';
 import { arrayMove } from '@dnd-kit/sortable';
@@ -25,9 +25,10 @@ import { useExpandedProjects } from './useExpandedProjects';
 import { buildActionHandlers } from './buildActionHandlers';
 import { buildAppLayoutProps } from './buildAppLayoutProps';
 import { useCommitModal } from './useCommitModal';
-import { Project, Worktree, RunningTask, MergeCompleted, Session, SessionKind, ChangedFilesViewMode } from '../types';
+import { Project, Worktree, RunningTask, TaskIdleShutdown, TaskAutoStarted, DriftWarning, ProjectPathMissing, MergeCompleted, Session, SessionKind, ChangedFilesViewMode } from '../types';
 import { useToast } from '../hooks/useToast';
 import type { ThemeBorderStyle } from '../theme';
+import { errorMessage, isCommandError } from '../lib/errors';
 
 const EXPANDED_PROJECTS_KEY = 'shellflow:expandedProjects';
 const SELECTED_TASKS_KEY = 'shellflow:selectedTasks';
@@ -123,7 +124,7 @@ export function useAppController() {
   const { resolveKeyEvent, getShortcut } = useMappings();
 
   // Toast notifications
-  const { toasts, dismissToast, showError, showWarning } = useToast();
+  const { toasts, dismissToast, showError, showWarning, showInfo } = useToast();
 
   // Open worktrees (main terminals are kept alive for these)
   const [openWorktreeIds, setOpenWorktreeIds] = useState<Set<string>>(new Set());
@@ -339,6 +340,8 @@ export function useAppController() {
   const [pendingCreateWorktreeProjectId, setPendingCreateWorktreeProjectId] = useState<string | null>(null);
   const [pendingCreateWorktreeName, setPendingCreateWorktreeName] = useState('');
   const [createWorktreeError, setCreateWorktreeError] = useState<string | null>(null);
+  // Branch named by the last failed create, offered for creating the worktree on it instead
+  const [adoptableBranch, setAdoptableBranch] = useState<string | null>(null);
   const [isCreatingWorktree, setIsCreatingWorktree] = useState(false);
   const [pendingStashWorktreeName, setPendingStashWorktreeName] = useState<string | null>(null);
   const [pendingStashProject, setPendingStashProject] = useState<Project | null>(null);
@@ -1810,12 +1813,24 @@ export function useAppController() {
     // Optimistically update local state to mirror backend (sets isActive = true)
     activateProject(projectId);
 
+    // Return to the worktree that was focused when the project was last left
+    const lastWorktreeId = await getLastFocused(projectId).catch(() => null);
+    const restoredWorktree = lastWorktreeId
+      ? project.worktrees.find((w) => w.id === lastWorktreeId)
+      : undefined;
+
     // Navigate to the project
     setOpenProjectIds((prev) => {
       if (prev.has(projectId)) return prev;
       return new Set([...prev, projectId]);
     });
-    setActiveWorktreeId(null);
+    if (restoredWorktree) {
+      setOpenWorktreeIds((prev) => {
+        if (prev.has(restoredWorktree.id)) return prev;
+        return new Set([...prev, restoredWorktree.id]);
+      });
+    }
+    setActiveWorktreeId(restoredWorktree?.id ?? null);
     setActiveScratchId(null);
     setActiveProjectId(projectId);
     setIsProjectSwitcherOpen(false);
@@ -1923,8 +1938,8 @@ export function useAppController() {
     }
   }, [config.worktree.focusNewBranchNames]);
 
-  const createWorktreeForProject = useCallback(async (project: Project, name?: string) => {
-    const worktree = await createWorktree(project.path, name);
+  const createWorktreeForProject = useCallback(async (project: Project, name?: string, adoptExistingBranch = false) => {
+    const worktree = await createWorktree(project.path, name, adoptExistingBranch);
     activateCreatedWorktree(worktree);
     return worktree;
   }, [createWorktree, activateCreatedWorktree]);
@@ -1939,6 +1954,7 @@ export function useAppController() {
       setPendingCreateWorktreeProjectId(projectId);
       setPendingCreateWorktreeName('');
       setCreateWorktreeError(null);
+      setAdoptableBranch(null);
       setPendingStashWorktreeName(null);
     },
     [projects]
@@ -1949,40 +1965,53 @@ export function useAppController() {
     setPendingCreateWorktreeProjectId(null);
     setPendingCreateWorktreeName('');
     setCreateWorktreeError(null);
+    setAdoptableBranch(null);
   }, [isCreatingWorktree]);
 
-  const createPendingWorktree = useCallback(async (name?: string) => {
+  const createPendingWorktree = useCallback(async (name?: string, adoptExistingBranch = false) => {
     if (!pendingCreateWorktreeProjectId) return;
     const project = projects.find((p) => p.id === pendingCreateWorktreeProjectId);
     if (!project) {
       setPendingCreateWorktreeProjectId(null);
       setPendingCreateWorktreeName('');
       setCreateWorktreeError(null);
+      setAdoptableBranch(null);
       return;
     }
 
     setIsCreatingWorktree(true);
     setCreateWorktreeError(null);
+    setAdoptableBranch(null);
 
     try {
-      await createWorktreeForProject(project, name);
+      await createWorktreeForProject(project, name, adoptExistingBranch);
       setPendingCreateWorktreeProjectId(null);
       setPendingCreateWorktreeName('');
       setCreateWorktreeError(null);
+      setAdoptableBranch(null);
       setPendingStashWorktreeName(null);
     } catch (err) {
-      const errorMessage = String(err);
-      if (errorMessage.includes('uncommitted changes')) {
+      const message = errorMessage(err);
+      if (isCommandError(err) ? err.code === 'UNCOMMITTED_CHANGES' : message.includes('uncommitted changes')) {
         setPendingCreateWorktreeProjectId(null);
         setPendingCreateWorktreeName('');
         setCreateWorktreeError(null);
+        setAdoptableBranch(null);
         setStashError(null);
         setPendingStashProject(project);
         setPendingStashWorktreeName(name ?? null);
+      } else if (isCommandError(err) && err.code === 'RESERVED_NAME' && typeof err.details?.suggestion === 'string') {
+        // Offer the Windows-safe name by filling it into the name field
+        setCreateWorktreeError(message);
+        setPendingCreateWorktreeName(err.details.suggestion);
+      } else if (isCommandError(err) && err.code === 'BRANCH_ALREADY_EXISTS' && name) {
+        // Let the user create the worktree on the existing branch instead
+        setCreateWorktreeError(message);
+        setAdoptableBranch(name);
       } else {
         console.error('Failed to create worktree:', err);
-        setCreateWorktreeError(errorMessage);
-        showError(`Failed to create worktree: ${errorMessage}`);
+        setCreateWorktreeError(message);
+        showError(`Failed to create worktree: ${message}`);
       }
     } finally {
       setIsCreatingWorktree(false);
@@ -2002,6 +2031,11 @@ export function useAppController() {
     void createPendingWorktree(trimmed);
   }, [pendingCreateWorktreeName, createPendingWorktree]);
 
+  const handleCreateWorktreeOnExistingBranch = useCallback(() => {
+    if (!adoptableBranch) return;
+    void createPendingWorktree(adoptableBranch, true);
+  }, [adoptableBranch, createPendingWorktree]);
+
   const handleStashAndCreate = useCallback(async () => {
     if (!pendingStashProject) return;
 
@@ -2036,7 +2070,7 @@ export function useAppController() {
       }
     } catch (err) {
       console.error('[handleStashAndCreate] Failed:', err);
-      setStashError(String(err));
+      setStashError(errorMessage(err));
       // Try to restore the stash if worktree creation failed
       if (stashId) {
         try {
@@ -2097,6 +2131,129 @@ export function useAppController() {
     setExpandedProjects((prev) => new Set([...prev, project.id]));
   }, []);
 
+  // Task to start once its entity is active, e.g. a restart after idle shutdown
+  const [pendingTaskRestart, setPendingTaskRestart] = useState<{ entityId: string; taskName: string } | null>(null);
+
+  useEffect(() => {
+    if (!pendingTaskRestart) return;
+    const { entityId, taskName } = pendingTaskRestart;
+    if (activeEntityId === entityId) {
+      setPendingTaskRestart(null);
+      void handleStartTask(taskName);
+      return;
+    }
+    const worktree = projects.flatMap((p) => p.worktrees).find((w) => w.id === entityId);
+    const project = projects.find((p) => p.id === entityId);
+    if (worktree) {
+      handleSelectWorktree(worktree);
+    } else if (project) {
+      handleSelectProject(project);
+    } else {
+      setPendingTaskRestart(null);
+    }
+  }, [pendingTaskRestart, activeEntityId, projects, handleStartTask, handleSelectWorktree, handleSelectProject]);
+
+  // Daemon tasks stopped for inactivity offer a one-click restart
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    listen<TaskIdleShutdown>('task-idle-shutdown', (event) => {
+      const { worktreeId, taskName, idleMinutes } = event.payload;
+      showInfo(`${taskName} stopped after ${idleMinutes} minutes of inactivity`, 0, {
+        label: 'Restart',
+        onClick: () => setPendingTaskRestart({ entityId: worktreeId, taskName }),
+      });
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [showInfo]);
+
+  // Auto-started tasks run in the background, tracked like silent tasks
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    listen<TaskAutoStarted>('task-auto-started', (event) => {
+      const { worktreeId, taskName, ptyId } = event.payload;
+      setRunningTasks((prev) => {
+        const next = new Map(prev);
+        const existing = prev.get(worktreeId) ?? [];
+        const filtered = existing.filter(t => t.taskName !== taskName || t.status === 'running');
+        next.set(worktreeId, [...filtered, { taskName, ptyId, kind: 'daemon', status: 'running', worktreeId }]);
+        return next;
+      });
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, []);
+
+  // Check base-branch drift whenever a project (or one of its worktrees) comes
+  // into focus; the backend only warns once a day per worktree
+  const driftProjectId = activeProjectId
+    ?? projects.find((p) => p.worktrees.some((w) => w.id === activeWorktreeId))?.id
+    ?? null;
+  useEffect(() => {
+    if (!driftProjectId) return;
+    getDriftReport(driftProjectId).catch(() => {});
+  }, [driftProjectId]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    listen<DriftWarning>('drift-warning', (event) => {
+      const { name, baseBranch, commitsBehind, daysSinceActivity } = event.payload;
+      const idle = daysSinceActivity > 0 ? ` and idle for ${daysSinceActivity} days` : '';
+      showInfo(`${name} is ${commitsBehind} commits behind ${baseBranch}${idle}. Consider updating it.`);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [showInfo]);
+
+  // A project whose directory moved can be pointed at its new location
+  // without losing its worktrees
+  const handleLocateProject = useCallback(async (projectId: string) => {
+    const path = await selectFolder();
+    if (!path) return;
+    try {
+      const { staleWorktreeIds } = await updateProjectPath(projectId, path);
+      await refreshProjects({ syncFromGit: true });
+      if (staleWorktreeIds.length > 0) {
+        showWarning(`${staleWorktreeIds.length} worktree(s) could not be found at the new location`);
+      }
+    } catch (err) {
+      showError(`Failed to update project location: ${errorMessage(err)}`);
+    }
+  }, [refreshProjects, showError, showWarning]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    listen<ProjectPathMissing>('project-path-missing', (event) => {
+      const { projectId, name, path } = event.payload;
+      showInfo(`${name} was not found at ${path}`, 0, {
+        label: 'Locate…',
+        onClick: () => void handleLocateProject(projectId),
+      });
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, [showInfo, handleLocateProject]);
+
   // Scratch terminal handlers
   const handleAddScratchTerminal = useCallback(() => {
     // Get the cwd of the currently active scratch tab (if any) to start the new terminal there
@@ -2917,6 +3074,7 @@ export function useAppController() {
         setPendingCreateWorktreeProjectId(null);
         setPendingCreateWorktreeName('');
         setCreateWorktreeError(null);
+        setAdoptableBranch(null);
       }
     },
 
@@ -3240,6 +3398,8 @@ export function useAppController() {
     pendingCreateWorktreeProject,
     pendingCreateWorktreeName,
     createWorktreeError,
+    createWorktreeExistingBranch:
+      adoptableBranch && adoptableBranch === pendingCreateWorktreeName.trim() ? adoptableBranch : null,
     isCreatingWorktree,
     pendingStashProject,
     stashError,
@@ -3254,6 +3414,7 @@ export function useAppController() {
     onPendingCreateWorktreeNameChange: setPendingCreateWorktreeName,
     onCreateWorktreeWithDefault: handleCreateWorktreeWithDefault,
     onCreateWorktreeWithCustom: handleCreateWorktreeWithCustom,
+    onCreateWorktreeOnExistingBranch: handleCreateWorktreeOnExistingBranch,
     onCancelCreateWorktree: handleCancelCreateWorktree,
     onStashAndCreate: handleStashAndCreate,
     onCancelStash: handleCancelStash,
@@ -3,7 +3,7 @@ import { listen } from '@tauri-apps/api/event';
 import { getActiveContexts, type ContextState, type ActiveContexts } from '../lib/contexts';
 import { executeAction, type ActionHandlerMap } from '../lib/actionHandlers';
 import { getMenuAvailability, type ActionContext } from '../lib/actions';
-import { updateActionAvailability } from '../lib/tauri';
+import { setActiveEntity, updateActionAvailability } from '../lib/tauri';
 import type { ResolvedBinding } from '../lib/mappings';
 import type { SessionKind } from '../types';
 
@@ -202,4 +202,10 @@ export function useAppGlobalBindings(options: GlobalBindingsOptions) {
     const menuAvailability = getMenuAvailability(actionContext);
     updateActionAvailability(menuAvailability);
   }, [actionContext]);
+
+  // Menu accelerators follow the active project's mappings; the focused
+  // worktree is remembered so switching back to the project can restore it
+  useEffect(() => {
+    setActiveEntity(activeProjectId, activeWorktreeId).catch(() => {});
+  }, [activeProjectId, activeWorktreeId]);
 }
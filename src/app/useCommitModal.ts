@@ -1,9 +1,9 @@
 import { useCallback, useMemo, useRef, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import type { CommitConfig } from '../hooks/useConfig';
 import {
   gitStageAll,
   gitDiffCached,
-  gitDiffCachedFiles,
   gitCommit,
   gitCurrentBranch,
   gitBranchExists,
@@ -12,7 +12,10 @@ import {
   gitPushCurrentBranch,
   gitMergeToMain,
   gitPushDefaultBranch,
+  generateCommitMessage,
 } from '../lib/tauri';
+import type { CommitMessageChunk, CommitMessageCompleted } from '../types';
+import { errorMessage } from '../lib/errors';
 
 type CommitContext = {
   repoPath: string;
@@ -26,26 +29,6 @@ interface UseCommitModalArgs {
   commitConfig?: CommitConfig;
 }
 
-function resolveCommitEndpoint(baseUrl: string): string {
-  const trimmed = baseUrl.replace(/\/+$/, '');
-  if (trimmed.endsWith('/chat/completions')) return trimmed;
-  return `${trimmed}/chat/completions`;
-}
-
-function applyPromptTemplate(template: string, vars: Record<string, string>): string {
-  let result = template;
-  for (const [key, value] of Object.entries(vars)) {
-    result = result.split(`{{ ${key} }}`).join(value).split(`{{${key}}}`).join(value);
-  }
-  return result;
-}
-
-function getRepoName(repoPath: string): string {
-  const normalized = repoPath.replace(/\\/g, '/');
-  const parts = normalized.split('/').filter(Boolean);
-  return parts[parts.length - 1] ?? repoPath;
-}
-
 function slugifySegment(value: string): string {
   let slug = value.toLowerCase();
   slug = slug.replace(/['"`]/g, '');
@@ -79,40 +62,27 @@ function deriveBranchName(message: string): string {
   return slug;
 }
 
-async function requestCommitMessage(config: CommitConfig, prompt: string): Promise<string> {
-  const endpoint = resolveCommitEndpoint(config.ai.baseUrl);
-  const controller = new AbortController();
-  const timeout = setTimeout(() => controller.abort(), config.ai.timeoutMs ?? 15000);
+// Generate a message in the backend, passing each streamed piece to `onDelta`
+async function streamCommitMessage(repoPath: string, onDelta: (delta: string) => void): Promise<string> {
+  let resolveCompleted: (payload: CommitMessageCompleted) => void = () => {};
+  const completed = new Promise<CommitMessageCompleted>((resolve) => {
+    resolveCompleted = resolve;
+  });
+  const unlistenChunk = await listen<CommitMessageChunk>('commit-message-chunk', (event) => {
+    if (event.payload.repoPath === repoPath) onDelta(event.payload.delta);
+  });
+  const unlistenCompleted = await listen<CommitMessageCompleted>('commit-message-completed', (event) => {
+    if (event.payload.repoPath === repoPath) resolveCompleted(event.payload);
+  });
 
   try {
-    const response = await fetch(endpoint, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: `Bearer ${config.ai.apiKey}`,
-      },
-      body: JSON.stringify({
-        model: config.ai.model,
-        messages: [{ role: 'user', content: prompt }],
-        temperature: config.ai.temperature,
-        max_tokens: config.ai.maxTokens,
-      }),
-      signal: controller.signal,
-    });
-
-    if (!response.ok) {
-      const text = await response.text();
-      throw new Error(`生成失败: ${text || response.statusText}`);
-    }
-
-    const data = await response.json();
-    const content =
-      data?.choices?.[0]?.message?.content ??
-      data?.choices?.[0]?.text ??
-      '';
-    return String(content).trim();
+    await generateCommitMessage(repoPath);
+    const result = await completed;
+    if (result.error) throw new Error(`生成失败: ${result.error}`);
+    return (result.message ?? '').trim();
   } finally {
-    clearTimeout(timeout);
+    unlistenChunk();
+    unlistenCompleted();
   }
 }
 
@@ -175,7 +145,8 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
   const generate = useCallback(async () => {
     const context = ensureContext();
     if (!context) return;
-    if (!commitConfig?.ai?.baseUrl || !commitConfig?.ai?.apiKey || !commitConfig?.ai?.model) {
+    const keyNeeded = commitConfig?.ai?.provider !== 'ollama';
+    if (!commitConfig?.ai?.baseUrl || (keyNeeded && !commitConfig?.ai?.apiKey) || !commitConfig?.ai?.model) {
       setError('请先在设置里配置 commit.ai 的 baseUrl / apiKey / model。');
       return;
     }
@@ -191,23 +162,17 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
         return;
       }
 
-      const files = await gitDiffCachedFiles(context.repoPath);
-      const branch = await gitCurrentBranch(context.repoPath).catch(() => '');
-      const prompt = applyPromptTemplate(commitConfig.ai.prompt, {
-        diff,
-        files: files.join('\n'),
-        branch,
-        repo: getRepoName(context.repoPath),
+      setMessage('');
+      const result = await streamCommitMessage(context.repoPath, (delta) => {
+        setMessage((current) => current + delta);
       });
-
-      const result = await requestCommitMessage(commitConfig, prompt);
       if (!result) {
         setError('生成的提交信息为空。');
         return;
       }
       setMessage(result);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
@@ -229,7 +194,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
       await gitCommit(context.repoPath, message.trim());
       setHasCommitted(true);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
@@ -264,7 +229,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
         setBranchName(candidate);
         setCurrentBranch(candidate);
       } catch (err) {
-        setError(String(err));
+        setError(errorMessage(err));
       } finally {
         setIsBusy(false);
         setBusyLabel(null);
@@ -285,7 +250,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
       setBranchName(candidate);
       setCurrentBranch(candidate);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
@@ -305,7 +270,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
     try {
       await gitPushCurrentBranch(context.repoPath);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
@@ -329,7 +294,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
     try {
       await gitMergeToMain(context.worktreePath, context.projectPath);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
@@ -353,7 +318,7 @@ export function useCommitModal({ getContext, commitConfig }: UseCommitModalArgs)
     try {
       await gitPushDefaultBranch(context.projectPath);
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
     } finally {
       setIsBusy(false);
       setBusyLabel(null);
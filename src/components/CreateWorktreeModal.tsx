@@ -7,9 +7,12 @@ interface CreateWorktreeModalProps {
   worktreeName: string;
   isCreating?: boolean;
   error?: string | null;
+  /** Branch that already exists under the requested name, offered for adoption */
+  existingBranch?: string | null;
   onWorktreeNameChange: (name: string) => void;
   onCreateWithDefault: () => void;
   onCreateWithCustomName: () => void;
+  onUseExistingBranch?: () => void;
   onCancel: () => void;
   onModalOpen?: () => void;
   onModalClose?: () => void;
@@ -20,9 +23,11 @@ export function CreateWorktreeModal({
   worktreeName,
   isCreating = false,
   error = null,
+  existingBranch = null,
   onWorktreeNameChange,
   onCreateWithDefault,
   onCreateWithCustomName,
+  onUseExistingBranch,
   onCancel,
   onModalOpen,
   onModalClose,
@@ -74,6 +79,16 @@ export function CreateWorktreeModal({
           <div className="mt-3 p-2.5 bg-red-900/20 border border-red-700/50 rounded text-[13px] text-red-300">
             <p className="font-medium mb-1">Failed to create worktree:</p>
             <p className="text-red-400 font-mono text-[11px] break-all">{error}</p>
+            {existingBranch && onUseExistingBranch && (
+              <button
+                type="button"
+                onClick={onUseExistingBranch}
+                disabled={isCreating}
+                className="mt-2 text-[12px] text-blue-400 hover:text-blue-300 underline disabled:opacity-50"
+              >
+                Create worktree on existing branch "{existingBranch}"
+              </button>
+            )}
           </div>
         )}
       </ModalBody>
@@ -5,6 +5,7 @@ import { Worktree, DeleteWorktreeProgress, DeleteWorktreeCompleted, WorktreeDele
 import { executeDeleteWorktreeWorkflow, checkWorktreeDeleteStatus } from '../lib/tauri';
 import { DeleteConfig } from '../hooks/useConfig';
 import { Modal, ModalHeader, ModalBody, ModalActions, ModalButton } from './Modal';
+import { errorMessage } from '../lib/errors';
 
 interface DeleteWorktreeModalProps {
   worktree: Worktree;
@@ -124,9 +125,10 @@ export function DeleteWorktreeModal({
     setCurrentPhase(null);
 
     try {
-      await executeDeleteWorktreeWorkflow(worktree.id, { deleteBranch });
+      // Confirmed by this modal
+      await executeDeleteWorktreeWorkflow(worktree.id, { deleteBranch }, true);
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
   }, [worktree.id, deleteBranch]);
@@ -5,6 +5,7 @@ import { getFileDiffContent } from '../../lib/tauri';
 import type { DiffContent, ChangedFilesViewMode } from '../../types';
 import { TerminalConfig } from '../../hooks/useConfig';
 import { useTheme } from '../../theme';
+import { errorMessage } from '../../lib/errors';
 
 const SHELLFLOW_THEME_NAME = 'shellflow-theme';
 
@@ -56,7 +57,7 @@ export function DiffViewer({
       .catch((err) => {
         if (cancelled) return;
         console.error('Failed to initialize Monaco:', err);
-        setMonacoError(err instanceof Error ? err.message : String(err));
+        setMonacoError(errorMessage(err));
       });
 
     return () => {
@@ -89,7 +90,7 @@ export function DiffViewer({
       } catch (err) {
         if (!cancelled) {
           console.error('Failed to fetch diff content:', err);
-          setError(err instanceof Error ? err.message : String(err));
+          setError(errorMessage(err));
         }
       } finally {
         if (!cancelled) {
@@ -13,6 +13,7 @@ import { registerActiveTerminal, unregisterActiveTerminal, registerTerminalInsta
 import { spawnAction, ptyWrite, ptyResize, ptyKill, watchMergeState, stopMergeWatcher, watchRebaseState, stopRebaseWatcher, cleanupWorktree, MergeOptions, MergeStrategy } from '../../lib/tauri';
 import { TerminalSearchControl } from '../TerminalSearchControl';
 import '@xterm/xterm/css/xterm.css';
+import { errorMessage } from '../../lib/errors';
 
 
 // Debounce helper with cancel support
@@ -356,7 +357,7 @@ export function ActionTerminal({
 
     initAction().catch((err) => {
       console.error('[ActionTerminal] initAction error:', err);
-      outputBuffer.write(`\x1b[31mError: ${err}\x1b[0m\r\n`);
+      outputBuffer.write(`\x1b[31mError: ${errorMessage(err)}\x1b[0m\r\n`);
     });
 
     return () => {
@@ -549,11 +550,16 @@ export function ActionTerminal({
 
   // Handle completion with cleanup
   const handleComplete = useCallback(() => {
-    cleanupWorktree(worktreeId, {
-      deleteWorktree,
-      deleteLocalBranch,
-      deleteRemoteBranch,
-    });
+    // The options were chosen and confirmed in the merge modal
+    cleanupWorktree(
+      worktreeId,
+      {
+        deleteWorktree,
+        deleteLocalBranch,
+        deleteRemoteBranch,
+      },
+      true
+    );
     // onMergeComplete is called by the merge-completed event listener in App.tsx
   }, [worktreeId, deleteWorktree, deleteLocalBranch, deleteRemoteBranch]);
 
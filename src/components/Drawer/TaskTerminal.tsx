@@ -13,6 +13,7 @@ import { registerActiveTerminal, unregisterActiveTerminal, registerTerminalInsta
 import { spawnTask, ptyWrite, ptyResize, ptyKill } from '../../lib/tauri';
 import { TerminalSearchControl } from '../TerminalSearchControl';
 import '@xterm/xterm/css/xterm.css';
+import { errorMessage } from '../../lib/errors';
 
 
 // Debounce helper with cancel support
@@ -67,6 +68,7 @@ function debounce<T extends (...args: unknown[]) => void>(fn: T, ms: number): T
 interface PtyOutput {
   pty_id: string;
   data: string;
+  replay?: boolean;
 }
 
 interface TaskTerminalProps {
@@ -344,7 +346,7 @@ export function TaskTerminal({
     initTask().catch((err) => {
       console.error('[TaskTerminal] initTask error:', err);
       // Show error in terminal
-      outputBuffer.write(`\x1b[31mError: ${err}\x1b[0m\r\n`);
+      outputBuffer.write(`\x1b[31mError: ${errorMessage(err)}\x1b[0m\r\n`);
     });
 
     return () => {
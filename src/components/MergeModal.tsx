@@ -1,10 +1,11 @@
 import { useState, useEffect, useCallback, useMemo } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { GitMerge, AlertCircle, CheckCircle, Loader2, AlertTriangle, Circle, Sparkles } from 'lucide-react';
-import { Worktree, MergeFeasibility, MergeStrategy, MergeProgress, MergeCompleted } from '../types';
+import { Worktree, MergeFeasibility, MergeBlocker, MergeStrategy, MergeProgress, MergeCompleted, RetryNotice, ActionCommandCheck } from '../types';
 import { MergeConfig } from '../hooks/useConfig';
-import { checkMergeFeasibility, executeMergeWorkflow, cleanupWorktree, abortMerge, abortRebase, MergeOptions } from '../lib/tauri';
+import { checkMergeFeasibility, checkActionCommand, executeMergeWorkflow, cleanupWorktree, abortMerge, abortRebase, MergeOptions } from '../lib/tauri';
 import { Modal, ModalHeader, ModalBody, ModalActions, ModalButton } from './Modal';
+import { errorMessage } from '../lib/errors';
 
 // Re-export for consumers
 export type { MergeOptions };
@@ -34,6 +35,40 @@ interface Step {
   label: string;
 }
 
+const STRATEGY_LABELS: Record<MergeStrategy, string> = { merge: 'Merge', rebase: 'Rebase', squash: 'Squash' };
+
+const plural = (count: number, noun: string) => `${count} ${noun}${count !== 1 ? 's' : ''}`;
+
+// Why the merge can't proceed, phrased as what to do about it
+function describeMergeBlocker(blocker: MergeBlocker): string {
+  switch (blocker.kind) {
+    case 'detachedHead':
+      return 'The worktree is not on a branch. Check out a branch to merge.';
+    case 'onTargetBranch':
+      return `Already on ${blocker.branch}; there is nothing to merge into itself.`;
+    case 'targetBranchMissing':
+      return `Branch ${blocker.branch} does not exist.`;
+    case 'uncommittedChanges':
+      return `${plural(blocker.fileCount, 'uncommitted file')}. Commit or stash before merging.`;
+    case 'nothingToMerge':
+      return `Nothing to merge — branch is up to date with ${blocker.targetBranch}`;
+    case 'behindRemote':
+      return `${blocker.branch} is ${plural(blocker.count, 'commit')} behind ${blocker.upstream}. Pull it before pushing.`;
+    case 'operationInProgress':
+      return `A ${blocker.operation} is in progress. Finish or abort it first.`;
+    case 'workflowInProgress':
+      return `A ${blocker.workflow} is already running for this worktree.`;
+  }
+}
+
+// Step a retried remote command belongs to, and e.g. "push attempt 2/3 in 4s"
+function describeRetry(notice: RetryNotice): { phase: string; summary: string } {
+  return {
+    phase: notice.operation === 'push' || notice.operation === 'fetch' ? notice.operation : 'delete-remote-branch',
+    summary: `${notice.operation} attempt ${notice.attempt}/${notice.maxAttempts} in ${Math.ceil(notice.delayMs / 1000)}s`,
+  };
+}
+
 export function MergeModal({
   worktree,
   projectPath,
@@ -49,6 +84,7 @@ export function MergeModal({
   const [error, setError] = useState<string | null>(null);
   const [executing, setExecuting] = useState(false);
   const [currentPhase, setCurrentPhase] = useState<string | null>(null);
+  const [retry, setRetry] = useState<{ phase: string; summary: string } | null>(null);
   const [completedPhases, setCompletedPhases] = useState<Set<string>>(new Set());
 
   // Form state
@@ -56,6 +92,7 @@ export function MergeModal({
   const [deleteWorktree, setDeleteWorktree] = useState(defaultConfig.deleteWorktree);
   const [deleteLocalBranch, setDeleteLocalBranch] = useState(defaultConfig.deleteLocalBranch);
   const [deleteRemoteBranch, setDeleteRemoteBranch] = useState(defaultConfig.deleteRemoteBranch);
+  const [pushAfterMerge, setPushAfterMerge] = useState(defaultConfig.pushAfterMerge ?? false);
 
   const [executionSteps, setExecutionSteps] = useState<Step[]>([]);
 
@@ -67,16 +104,18 @@ export function MergeModal({
       .finally(() => setLoading(false));
   }, [worktree.path, projectPath]);
 
-  const buildSteps = useCallback((isMerge: boolean, strat: MergeStrategy, delWorktree: boolean, delLocal: boolean, delRemote: boolean): Step[] => {
+  const buildSteps = useCallback((isMerge: boolean, strat: MergeStrategy, delWorktree: boolean, delLocal: boolean, delRemote: boolean, push = false): Step[] => {
     const steps: Step[] = [];
     if (isMerge) {
-      steps.push({ phase: strat, label: strat === 'rebase' ? 'Rebase' : 'Merge' });
+      if (strat === 'rebase' && defaultConfig.rebaseOntoRemote) steps.push({ phase: 'fetch', label: 'Fetch' });
+      steps.push({ phase: strat, label: STRATEGY_LABELS[strat] });
+      if (push) steps.push({ phase: 'push', label: 'Push' });
     }
     if (delWorktree) steps.push({ phase: 'delete-worktree', label: 'Delete worktree' });
     if (delLocal) steps.push({ phase: 'delete-local-branch', label: 'Delete local branch' });
     if (delRemote) steps.push({ phase: 'delete-remote-branch', label: 'Delete remote branch' });
     return steps;
-  }, []);
+  }, [defaultConfig.rebaseOntoRemote]);
 
   // Listen for progress events
   useEffect(() => {
@@ -108,14 +147,27 @@ export function MergeModal({
     };
   }, [executionSteps]);
 
+  // Listen for retries of push / remote branch deletion
+  useEffect(() => {
+    const unlisten = listen<RetryNotice>('git-network-retry', (event) => {
+      setRetry(describeRetry(event.payload));
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Listen for completion events
   useEffect(() => {
     const unlisten = listen<MergeCompleted>('merge-completed', (event) => {
-      const { worktreeId, success, deletedWorktree, error } = event.payload;
+      const { worktreeId, success, deletedWorktree, error, pushFailed, pushError } = event.payload;
 
       if (worktreeId !== worktree.id) return;
 
       if (success) {
+        // The local merge already happened, so a failed push is only a warning
+        if (pushFailed) console.warn('Push after merge failed:', pushError);
         onMergeComplete(worktreeId, deletedWorktree);
       } else {
         setError(error || 'Unknown error');
@@ -129,25 +181,27 @@ export function MergeModal({
   }, [worktree.id, onMergeComplete]);
 
   const handleMerge = useCallback(async () => {
-    const steps = buildSteps(true, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch);
+    const steps = buildSteps(true, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, pushAfterMerge);
     setExecutionSteps(steps);
     setCompletedPhases(new Set());
     setCurrentPhase(null);
     setExecuting(true);
     setError(null);
 
+    // Submitting the modal is the user's confirmation of the selected deletions
     try {
       await executeMergeWorkflow(worktree.id, {
         strategy,
         deleteWorktree,
         deleteLocalBranch,
         deleteRemoteBranch,
-      });
+        pushAfterMerge,
+      }, true);
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
-  }, [worktree.id, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, buildSteps]);
+  }, [worktree.id, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, pushAfterMerge, buildSteps]);
 
   const handleCleanup = useCallback(async () => {
     if (!deleteWorktree && !deleteLocalBranch && !deleteRemoteBranch) {
@@ -167,18 +221,29 @@ export function MergeModal({
         deleteWorktree,
         deleteLocalBranch,
         deleteRemoteBranch,
-      });
+      }, true);
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setExecuting(false);
     }
   }, [worktree.id, strategy, deleteWorktree, deleteLocalBranch, deleteRemoteBranch, buildSteps]);
 
   const canExecute = feasibility?.canMerge && !executing && !error;
-  const canCleanup = feasibility && !feasibility.canMerge && !feasibility.isUpToDate && !feasibility.hasUncommittedChanges && !executing && !error;
+  const busy = feasibility?.blockers.some((b) => b.kind === 'workflowInProgress' || b.kind === 'operationInProgress');
+  const canCleanup = feasibility && !feasibility.canMerge && !feasibility.isUpToDate && !feasibility.hasUncommittedChanges && !busy && !executing && !error;
   const showCleanupButton = canCleanup && (deleteWorktree || deleteLocalBranch || deleteRemoteBranch);
   const hasConflict = error && error.toLowerCase().includes('conflict');
-  const canResolveWithAI = hasConflict && !executing && onTriggerAction && feasibility;
+  const [actionCheck, setActionCheck] = useState<ActionCommandCheck | null>(null);
+
+  // Only offer AI resolution when actions.command is installed
+  useEffect(() => {
+    if (!hasConflict || !onTriggerAction) return;
+    checkActionCommand(projectPath)
+      .then(setActionCheck)
+      .catch(() => setActionCheck(null));
+  }, [hasConflict, onTriggerAction, projectPath]);
+
+  const canResolveWithAI = hasConflict && !executing && onTriggerAction && feasibility && actionCheck?.found;
 
   // Close with abort when there's a conflict
   const handleClose = useCallback(async () => {
@@ -257,11 +322,16 @@ export function MergeModal({
       );
     }
 
-    if (feasibility.hasUncommittedChanges) {
+    const blockers = feasibility.blockers.filter((blocker) => blocker.kind !== 'nothingToMerge');
+    if (blockers.length > 0) {
       return (
-        <div className="flex items-center gap-2 text-[13px] text-yellow-400">
-          <AlertTriangle size={14} />
-          Uncommitted changes detected. Commit or stash before merging.
+        <div className="space-y-1">
+          {blockers.map((blocker) => (
+            <div key={blocker.kind} className="flex items-center gap-2 text-[13px] text-yellow-400">
+              <AlertTriangle size={14} />
+              {describeMergeBlocker(blocker)}
+            </div>
+          ))}
         </div>
       );
     }
@@ -291,6 +361,13 @@ export function MergeModal({
             {feasibility.commitsBehind > 0 && <>, {feasibility.commitsBehind} behind</>}
             {feasibility.canFastForward && ' • Fast-forward possible'}
           </div>
+          {pushAfterMerge &&
+            feasibility.warnings.map((warning) => (
+              <div key={warning.kind} className="flex items-center gap-2 text-[13px] text-yellow-400">
+                <AlertTriangle size={14} />
+                {describeMergeBlocker(warning)}
+              </div>
+            ))}
         </div>
       );
     }
@@ -307,6 +384,11 @@ export function MergeModal({
           <div key={step.phase} className="flex items-center gap-2.5">
             {getStepIcon(step.phase)}
             <span className={`text-[13px] ${getStepTextClass(step.phase)}`}>{step.label}</span>
+            {retry?.phase === step.phase && currentPhase === step.phase && (
+              <span className="text-[12px]" style={{ color: 'var(--modal-item-text-muted)' }}>
+                {retry.summary}
+              </span>
+            )}
           </div>
         ))}
       </div>
@@ -320,7 +402,7 @@ export function MergeModal({
           Strategy
         </label>
         <div className="flex gap-1.5">
-          {(['merge', 'rebase'] as const).map((s) => (
+          {(['merge', 'rebase', 'squash'] as const).map((s) => (
             <button
               key={s}
               onClick={() => setStrategy(s)}
@@ -331,7 +413,7 @@ export function MergeModal({
                 color: strategy === s ? 'var(--modal-item-text)' : 'var(--modal-item-text-muted)',
               }}
             >
-              {s.charAt(0).toUpperCase() + s.slice(1)}
+              {STRATEGY_LABELS[s]}
             </button>
           ))}
         </div>
@@ -346,6 +428,9 @@ export function MergeModal({
             { checked: deleteWorktree, onChange: setDeleteWorktree, label: 'Delete worktree' },
             { checked: deleteLocalBranch, onChange: setDeleteLocalBranch, label: 'Delete local branch' },
             { checked: deleteRemoteBranch, onChange: setDeleteRemoteBranch, label: 'Delete remote branch' },
+            ...(feasibility?.canMerge
+              ? [{ checked: pushAfterMerge, onChange: setPushAfterMerge, label: 'Push after merge' }]
+              : []),
           ].map(({ checked, onChange, label }) => (
             <label key={label} className="flex items-center gap-2 text-[13px] cursor-pointer" style={{ color: 'var(--modal-item-text)' }}>
               <input
@@ -379,6 +464,11 @@ export function MergeModal({
         {renderStatus()}
         {executing && renderProgress()}
         {(feasibility?.canMerge || canCleanup) && !executing && renderOptions()}
+        {hasConflict && actionCheck && !actionCheck.found && (
+          <div className="mt-3 text-[12px]" style={{ color: 'var(--modal-item-text-muted)' }}>
+            {actionCheck.message}
+          </div>
+        )}
       </ModalBody>
 
       <ModalActions>
@@ -392,7 +482,7 @@ export function MergeModal({
         )}
         {canExecute && (
           <ModalButton onClick={handleMerge} variant="primary" icon={<GitMerge size={13} />}>
-            {strategy === 'rebase' ? 'Rebase' : 'Merge'}
+            {STRATEGY_LABELS[strategy]}
           </ModalButton>
         )}
         {showCleanupButton && (
@@ -412,4 +412,30 @@ describe('ChangedFiles', () => {
       expect(button.title).toContain('Ctrl+Shift+D');
     });
   });
+
+  describe('hidden files', () => {
+    const files: FileChange[] = [
+      { path: 'src/app.ts', status: 'modified' },
+      { path: 'package-lock.json', status: 'modified', hidden: true },
+      { path: 'src/__snapshots__/app.snap', status: 'modified', hidden: true },
+    ];
+
+    it('collapses hidden files under a summary row but still counts them', () => {
+      render(<ChangedFiles files={files} />);
+
+      expect(screen.getByText('3 files')).toBeInTheDocument();
+      expect(screen.getByText('src/app.ts')).toBeInTheDocument();
+      expect(screen.getByTestId('hidden-files-toggle')).toHaveTextContent('2 hidden files');
+      expect(screen.queryByText('package-lock.json')).not.toBeInTheDocument();
+    });
+
+    it('expands hidden files when the summary row is clicked', async () => {
+      const user = userEvent.setup();
+      render(<ChangedFiles files={files} />);
+
+      await user.click(screen.getByTestId('hidden-files-toggle'));
+      expect(screen.getByText('package-lock.json')).toBeInTheDocument();
+      expect(screen.getByText('src/__snapshots__/app.snap')).toBeInTheDocument();
+    });
+  });
 });
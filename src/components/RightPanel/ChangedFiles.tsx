@@ -1,4 +1,5 @@
-import { FileDiff } from 'lucide-react';
+import { useState } from 'react';
+import { ChevronDown, ChevronRight, FileDiff } from 'lucide-react';
 import { FileChange, ChangedFilesViewMode } from '../../types';
 
 interface ChangedFilesProps {
@@ -38,6 +39,10 @@ export function ChangedFiles({
   openDiffShortcut,
 }: ChangedFilesProps) {
   const diffShortcut = openDiffShortcut ?? 'Ctrl+Shift+D';
+  const [showHidden, setShowHidden] = useState(false);
+  // Files matching changes.hidePatterns are still counted, just collapsed
+  const visibleFiles = files.filter((file) => !file.hidden);
+  const hiddenFiles = files.filter((file) => file.hidden);
   // Calculate total insertions and deletions
   const totals = files.reduce(
     (acc, file) => ({
@@ -49,6 +54,40 @@ export function ChangedFiles({
 
   const hasChanges = totals.insertions > 0 || totals.deletions > 0;
 
+  const renderFile = (file: FileChange) => {
+    const config = statusConfig[file.status];
+    const isSelected = selectedFile === file.path;
+    return (
+      <li
+        key={file.path}
+        className={`flex items-center gap-2 px-3 py-1.5 group ${
+          isSelected
+            ? 'bg-theme-3'
+            : 'hover:bg-theme-2'
+        } ${onFileClick ? 'cursor-pointer' : ''}`}
+        onClick={() => onFileClick?.(file.path)}
+      >
+        <span className={`flex-shrink-0 w-4 text-xs font-mono ${config.color}`}>
+          {config.label}
+        </span>
+        <span className="text-sm text-theme-1 truncate flex-1" title={file.path}>
+          {file.path}
+        </span>
+        {(file.insertions !== undefined || file.deletions !== undefined) && (
+          <span className="text-xs font-mono flex-shrink-0">
+            {file.insertions !== undefined && file.insertions > 0 && (
+              <span className="text-green-400">+{file.insertions}</span>
+            )}
+            {file.insertions !== undefined && file.insertions > 0 && file.deletions !== undefined && file.deletions > 0 && ' '}
+            {file.deletions !== undefined && file.deletions > 0 && (
+              <span className="text-red-400">-{file.deletions}</span>
+            )}
+          </span>
+        )}
+      </li>
+    );
+  };
+
   const getEmptyMessage = () => {
     if (!isGitRepo) {
       return 'Not a git repository';
@@ -122,39 +161,18 @@ export function ChangedFiles({
           </div>
         ) : (
           <ul className="py-1">
-            {files.map((file) => {
-              const config = statusConfig[file.status];
-              const isSelected = selectedFile === file.path;
-              return (
-                <li
-                  key={file.path}
-                  className={`flex items-center gap-2 px-3 py-1.5 group ${
-                    isSelected
-                      ? 'bg-theme-3'
-                      : 'hover:bg-theme-2'
-                  } ${onFileClick ? 'cursor-pointer' : ''}`}
-                  onClick={() => onFileClick?.(file.path)}
-                >
-                  <span className={`flex-shrink-0 w-4 text-xs font-mono ${config.color}`}>
-                    {config.label}
-                  </span>
-                  <span className="text-sm text-theme-1 truncate flex-1" title={file.path}>
-                    {file.path}
-                  </span>
-                  {(file.insertions !== undefined || file.deletions !== undefined) && (
-                    <span className="text-xs font-mono flex-shrink-0">
-                      {file.insertions !== undefined && file.insertions > 0 && (
-                        <span className="text-green-400">+{file.insertions}</span>
-                      )}
-                      {file.insertions !== undefined && file.insertions > 0 && file.deletions !== undefined && file.deletions > 0 && ' '}
-                      {file.deletions !== undefined && file.deletions > 0 && (
-                        <span className="text-red-400">-{file.deletions}</span>
-                      )}
-                    </span>
-                  )}
-                </li>
-              );
-            })}
+            {visibleFiles.map(renderFile)}
+            {hiddenFiles.length > 0 && (
+              <li
+                className="flex items-center gap-1 px-3 py-1.5 cursor-pointer text-xs text-theme-3 hover:bg-theme-2"
+                onClick={() => setShowHidden((shown) => !shown)}
+                data-testid="hidden-files-toggle"
+              >
+                {showHidden ? <ChevronDown className="w-3 h-3" /> : <ChevronRight className="w-3 h-3" />}
+                {hiddenFiles.length} hidden {hiddenFiles.length === 1 ? 'file' : 'files'}
+              </li>
+            )}
+            {showHidden && hiddenFiles.map(renderFile)}
           </ul>
         )}
       </div>
@@ -30,6 +30,7 @@ import { SortableProject } from './SortableProject';
 import { SortableWorktree } from './SortableWorktree';
 import { SortableScratch } from './SortableScratch';
 import { substitutePathTemplate } from '../../lib/pathTemplate';
+import { errorMessage } from '../../lib/errors';
 
 interface SidebarProps {
   projects: Project[];
@@ -433,7 +434,7 @@ export function Sidebar({
       terminalApp: terminalCommand ?? null,
     }).catch((err) => {
       console.error('Failed to open editor:', err);
-      const message = err instanceof Error ? err.message : String(err);
+      const message = errorMessage(err);
       onShowWarning(`打开编辑器失败：${message}`);
     });
   };
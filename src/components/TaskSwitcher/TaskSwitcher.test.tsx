@@ -51,6 +51,18 @@ describe('TaskSwitcher', () => {
       expect(screen.getByText('test')).toBeInTheDocument();
     });
 
+    it('leaves hidden tasks out', () => {
+      const tasks = [
+        createTask({ name: 'build', command: 'npm run build' }),
+        createTask({ name: 'release', command: 'npm run release', hidden: true }),
+      ];
+
+      render(<TaskSwitcher {...defaultProps} tasks={tasks} />);
+
+      expect(screen.getByText('build')).toBeInTheDocument();
+      expect(screen.queryByText('release')).not.toBeInTheDocument();
+    });
+
     it('shows task commands', () => {
       const tasks = [createTask({ name: 'lint', command: 'eslint src/' })];
 
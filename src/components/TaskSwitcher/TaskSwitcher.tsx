@@ -35,11 +35,12 @@ export function TaskSwitcher({
 
   const isMac = navigator.platform.toUpperCase().indexOf('MAC') >= 0;
 
-  // Filter tasks based on query (match name only)
+  // Filter tasks based on query (match name only); hidden tasks are only run by name
   const filteredTasks = useMemo(() => {
-    if (!query.trim()) return tasks;
+    const visible = tasks.filter((task) => !task.hidden);
+    if (!query.trim()) return visible;
     const lowerQuery = query.toLowerCase();
-    return tasks.filter((task) => task.name.toLowerCase().includes(lowerQuery));
+    return visible.filter((task) => task.name.toLowerCase().includes(lowerQuery));
   }, [tasks, query]);
 
   // Check if a task is running
@@ -4,11 +4,18 @@ import { writeText } from '@tauri-apps/plugin-clipboard-manager';
 
 export type ToastVariant = 'error' | 'warning' | 'info' | 'success';
 
+export interface ToastAction {
+  label: string;
+  onClick: () => void;
+}
+
 export interface ToastData {
   id: string;
   variant: ToastVariant;
   message: string;
   duration?: number;
+  /** Optional button shown next to the message; clicking it also dismisses the toast */
+  action?: ToastAction;
 }
 
 interface ToastProps {
@@ -111,6 +118,17 @@ function Toast({ toast, onDismiss }: ToastProps) {
       <div className={`flex items-start gap-2 ${styles.text} text-sm`}>
         <Icon className={`w-4 h-4 mt-0.5 flex-shrink-0 ${styles.icon}`} />
         <div className="flex-1 min-w-0 pr-1">{toast.message}</div>
+        {toast.action && (
+          <button
+            onClick={() => {
+              toast.action?.onClick();
+              handleDismiss();
+            }}
+            className={`${styles.icon} hover:${styles.text} transition-colors flex-shrink-0 font-medium`}
+          >
+            {toast.action.label}
+          </button>
+        )}
         <button
           onClick={handleCopy}
           className={`${styles.icon} hover:${styles.text} transition-colors flex-shrink-0`}
@@ -35,12 +35,26 @@ export interface MergeConfig {
   deleteWorktree: boolean;
   deleteLocalBranch: boolean;
   deleteRemoteBranch: boolean;
+  pushAfterMerge: boolean;
+  rebaseOntoRemote: boolean;
 }
 
 export interface DeleteConfig {
   deleteBranchWithWorktree: boolean;
 }
 
+export interface DriftWarningConfig {
+  /** Warn at this many commits behind the base branch (0 = never) */
+  warnBehind: number;
+  /** Warn when behind and untouched for this many days (0 = never) */
+  staleDays: number;
+}
+
+export interface ChangesConfig {
+  /** Globs for changed files to collapse in the changed-files panel */
+  hidePatterns: string[];
+}
+
 export interface NavigationConfig {
   // Reserved for future navigation settings
 }
@@ -55,6 +69,25 @@ export interface TaskConfig {
   shell?: string;
   /** Named URL templates. Key is display label, value is URL template (supports minijinja). */
   urls?: Record<string, string>;
+  /** Daemon tasks only: stop after this many idle minutes (0 = never) */
+  idleShutdownMinutes?: number;
+  /** "jsonlines": emit `{"shellflow": ...}` output lines as structured events */
+  protocol?: 'jsonlines';
+  /** Task switcher group */
+  group?: string;
+  /** Position within the group */
+  order?: number;
+  /** Left out of the task switcher, still runnable by name */
+  hidden?: boolean;
+  /** Daemon tasks only: start when the worktree is opened */
+  autoStart?: boolean;
+}
+
+export interface TaskDefaultsConfig {
+  /** Stop idle daemon tasks after this many minutes (null or 0 = never) */
+  idleShutdownMinutes: number | null;
+  /** Auto-start stops once this many task sessions run (null or 0 = no limit) */
+  autoStartLimit: number | null;
 }
 
 
@@ -87,7 +120,10 @@ export interface AppsConfig {
   fileManager?: AppConfig;
 }
 
+export type AiProvider = 'openai' | 'anthropic' | 'ollama';
+
 export interface CommitAiConfig {
+  provider: AiProvider;
   baseUrl: string;
   apiKey: string;
   model: string;
@@ -123,6 +159,7 @@ export interface WorktreeConfig {
   focusNewBranchNames: boolean;
   merge: MergeConfig;
   delete: DeleteConfig;
+  driftWarning: DriftWarningConfig;
 }
 
 export interface PanesConfig {
@@ -139,6 +176,8 @@ export type { ThemeBorderStyle };
 
 
 export interface Config {
+  /** Config format version; absent in files that predate versioning */
+  schemaVersion?: number;
   main: MainConfig;
   drawer: DrawerConfig;
   apps: AppsConfig;
@@ -146,9 +185,13 @@ export interface Config {
   navigation: NavigationConfig;
   indicators: IndicatorsConfig;
   tasks: TaskConfig[];
+  /** Order of task groups in the switcher */
+  tasksGroups: string[];
+  taskDefaults: TaskDefaultsConfig;
   actions: ActionsConfig;
   scratch: ScratchConfig;
   worktree: WorktreeConfig;
+  changes: ChangesConfig;
   panes: PanesConfig;
   /** Theme configuration - can be a single theme name or light/dark object */
   theme?: ThemeConfig;
@@ -160,6 +203,17 @@ export interface Config {
 export interface ConfigError {
   file: string;
   message: string;
+  /** 'info' entries are notes (e.g. a disabled config layer), not parse errors;
+   * 'warning' flags settings that load but look wrong, like a broken template */
+  severity?: 'error' | 'warning' | 'info';
+  /** Position of a parse error in the original file, with a caret-marked excerpt */
+  location?: ConfigErrorLocation | null;
+}
+
+export interface ConfigErrorLocation {
+  line: number;
+  column: number;
+  excerpt: string;
 }
 
 /** Result from get_config, includes config and any parse errors */
@@ -195,6 +249,7 @@ const defaultConfig: Config = {
   },
   commit: {
     ai: {
+      provider: 'openai',
       baseUrl: 'https://api.openai.com/v1',
       apiKey: '',
       model: 'gpt-4o-mini',
@@ -210,6 +265,8 @@ const defaultConfig: Config = {
     showIdleCheck: true,
   },
   tasks: [],
+  tasksGroups: [],
+  taskDefaults: { idleShutdownMinutes: null, autoStartLimit: null },
   actions: {
     mergeWorktreeWithConflicts: '',
   },
@@ -223,10 +280,19 @@ const defaultConfig: Config = {
       deleteWorktree: true,
       deleteLocalBranch: false,
       deleteRemoteBranch: false,
+      pushAfterMerge: false,
+      rebaseOntoRemote: false,
     },
     delete: {
       deleteBranchWithWorktree: true,
     },
+    driftWarning: {
+      warnBehind: 20,
+      staleDays: 7,
+    },
+  },
+  changes: {
+    hidePatterns: [],
   },
   panes: {
     unfocusedOpacity: 0.95,
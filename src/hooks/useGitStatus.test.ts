@@ -4,6 +4,9 @@ import { useGitStatus } from './useGitStatus';
 import { resetMocks, mockInvokeResponses, invokeHistory, emitEvent } from '../test/setup';
 import type { FileChange } from '../types';
 
+// What get_changed_files and get_branch_changed_files return
+const changed = (files: FileChange[]) => ({ files, hidden_count: files.filter((f) => f.hidden).length });
+
 describe('useGitStatus', () => {
   beforeEach(() => {
     resetMocks();
@@ -23,7 +26,7 @@ describe('useGitStatus', () => {
       const mockFiles: FileChange[] = [
         { path: 'src/app.ts', status: 'modified', insertions: 10, deletions: 5 },
       ];
-      mockInvokeResponses.set('get_changed_files', mockFiles);
+      mockInvokeResponses.set('get_changed_files', changed(mockFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -39,7 +42,7 @@ describe('useGitStatus', () => {
 
   describe('watcher lifecycle', () => {
     it('starts watching on mount', async () => {
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -56,7 +59,7 @@ describe('useGitStatus', () => {
     });
 
     it('stops watching on unmount', async () => {
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('stop_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
@@ -84,7 +87,7 @@ describe('useGitStatus', () => {
       const initialFiles: FileChange[] = [
         { path: 'initial.ts', status: 'modified' },
       ];
-      mockInvokeResponses.set('get_changed_files', initialFiles);
+      mockInvokeResponses.set('get_changed_files', changed(initialFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -112,7 +115,7 @@ describe('useGitStatus', () => {
 
     it('ignores events for other paths', async () => {
       const myFiles: FileChange[] = [{ path: 'mine.ts', status: 'modified' }];
-      mockInvokeResponses.set('get_changed_files', myFiles);
+      mockInvokeResponses.set('get_changed_files', changed(myFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -135,12 +138,36 @@ describe('useGitStatus', () => {
       // Should still have original files
       expect(result.current.files).toEqual(myFiles);
     });
+
+    it('refetches files on system-resumed', async () => {
+      mockInvokeResponses.set('get_changed_files', changed([{ path: 'before.ts', status: 'modified' }]));
+      mockInvokeResponses.set('start_watching', null);
+      mockInvokeResponses.set('get_branch_info', null);
+
+      const { result } = renderHook(() =>
+        useGitStatus({ id: 'worktree-1', path: '/path/to/worktree' })
+      );
+
+      await waitFor(() => {
+        expect(result.current.files).toEqual([{ path: 'before.ts', status: 'modified' }]);
+      });
+
+      const afterSleep: FileChange[] = [{ path: 'after.ts', status: 'added' }];
+      mockInvokeResponses.set('get_changed_files', changed(afterSleep));
+      act(() => {
+        emitEvent('system-resumed', { sleptSeconds: 3600, closedSessions: [], restartingWatchers: 1 });
+      });
+
+      await waitFor(() => {
+        expect(result.current.files).toEqual(afterSleep);
+      });
+    });
   });
 
   describe('refresh', () => {
     it('provides working refresh function', async () => {
       const newFiles: FileChange[] = [{ path: 'new.ts', status: 'added' }];
-      mockInvokeResponses.set('get_changed_files', newFiles);
+      mockInvokeResponses.set('get_changed_files', changed(newFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -200,7 +227,7 @@ describe('useGitStatus', () => {
 
     it('handles start_watching errors gracefully', async () => {
       const consoleSpy = vi.spyOn(console, 'error').mockImplementation(() => {});
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', () => {
         throw new Error('Watcher error');
       });
@@ -225,7 +252,7 @@ describe('useGitStatus', () => {
       const mockFiles: FileChange[] = [
         { path: 'src/feature.ts', status: 'modified', insertions: 20, deletions: 5 },
       ];
-      mockInvokeResponses.set('get_branch_changed_files', mockFiles);
+      mockInvokeResponses.set('get_branch_changed_files', changed(mockFiles));
       mockInvokeResponses.set('get_branch_info', {
         currentBranch: 'feature-branch',
         baseBranch: 'main',
@@ -249,7 +276,7 @@ describe('useGitStatus', () => {
       const mockFiles: FileChange[] = [
         { path: 'src/app.ts', status: 'modified' },
       ];
-      mockInvokeResponses.set('get_changed_files', mockFiles);
+      mockInvokeResponses.set('get_changed_files', changed(mockFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', {
         currentBranch: 'main',
@@ -273,8 +300,8 @@ describe('useGitStatus', () => {
       const uncommittedFiles: FileChange[] = [{ path: 'uncommitted.ts', status: 'modified' }];
       const branchFiles: FileChange[] = [{ path: 'branch.ts', status: 'added' }];
 
-      mockInvokeResponses.set('get_changed_files', uncommittedFiles);
-      mockInvokeResponses.set('get_branch_changed_files', branchFiles);
+      mockInvokeResponses.set('get_changed_files', changed(uncommittedFiles));
+      mockInvokeResponses.set('get_branch_changed_files', changed(branchFiles));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('stop_watching', null);
       mockInvokeResponses.set('get_branch_info', {
@@ -302,7 +329,7 @@ describe('useGitStatus', () => {
     });
 
     it('does not start watcher in branch mode', async () => {
-      mockInvokeResponses.set('get_branch_changed_files', []);
+      mockInvokeResponses.set('get_branch_changed_files', changed([]));
       mockInvokeResponses.set('get_branch_info', {
         currentBranch: 'feature',
         baseBranch: 'main',
@@ -321,7 +348,7 @@ describe('useGitStatus', () => {
     });
 
     it('passes projectPath to get_branch_changed_files', async () => {
-      mockInvokeResponses.set('get_branch_changed_files', []);
+      mockInvokeResponses.set('get_branch_changed_files', changed([]));
       mockInvokeResponses.set('get_branch_info', {
         currentBranch: 'feature',
         baseBranch: 'main',
@@ -352,7 +379,7 @@ describe('useGitStatus', () => {
         baseBranch: 'main',
         isOnBaseBranch: false,
       };
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', mockBranchInfo);
 
@@ -372,7 +399,7 @@ describe('useGitStatus', () => {
 
     it('handles branchInfo fetch error gracefully', async () => {
       const consoleSpy = vi.spyOn(console, 'error').mockImplementation(() => {});
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', null);
       // Use a promise rejection instead of throwing synchronously
       mockInvokeResponses.set('get_branch_info', Promise.reject(new Error('Branch info error')));
@@ -394,7 +421,7 @@ describe('useGitStatus', () => {
 
   describe('isGitRepo handling', () => {
     it('sets isGitRepo to true for valid git repository', async () => {
-      mockInvokeResponses.set('get_changed_files', []);
+      mockInvokeResponses.set('get_changed_files', changed([]));
       mockInvokeResponses.set('start_watching', null);
       mockInvokeResponses.set('get_branch_info', null);
 
@@ -1,7 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
-import { FileChange, FilesChanged, Worktree, ChangedFilesViewMode, BranchInfo } from '../types';
+import { ChangedFiles, FileChange, FilesChanged, Worktree, ChangedFilesViewMode, BranchInfo } from '../types';
 
 // Can be a worktree or a project (both have id and path)
 type GitStatusTarget = { id: string; path: string } | null;
@@ -56,17 +56,18 @@ export function useGitStatus(
 
       if (mode === 'uncommitted') {
         // Fetch uncommitted changes (working tree vs HEAD)
-        const result = await invoke<FileChange[]>('get_changed_files', {
+        const result = await invoke<ChangedFiles>('get_changed_files', {
           worktreePath,
+          projectPath,
         });
-        setFiles(result);
+        setFiles(result.files);
       } else {
         // Fetch branch changes (current branch vs base branch)
-        const result = await invoke<FileChange[]>('get_branch_changed_files', {
+        const result = await invoke<ChangedFiles>('get_branch_changed_files', {
           worktreePath,
           projectPath,
         });
-        setFiles(result);
+        setFiles(result.files);
       }
       setIsGitRepo(true);
     } catch (err) {
@@ -171,6 +172,27 @@ export function useGitStatus(
     };
   }, [worktreeId, worktreePath, refresh, mode]);
 
+  // Changes made while the system slept may never have been reported
+  useEffect(() => {
+    let unlistenFn: UnlistenFn | null = null;
+    let cancelled = false;
+
+    listen('system-resumed', () => {
+      refresh();
+    }).then((fn) => {
+      if (cancelled) {
+        fn();
+      } else {
+        unlistenFn = fn;
+      }
+    });
+
+    return () => {
+      cancelled = true;
+      unlistenFn?.();
+    };
+  }, [refresh]);
+
   return {
     files,
     loading,
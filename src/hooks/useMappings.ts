@@ -20,6 +20,7 @@ import {
   type ActionId,
 } from '../lib/mappings';
 import { getActiveContexts, type ActiveContexts, type ContextState } from '../lib/contexts';
+import { errorMessage } from '../lib/errors';
 
 /**
  * Error from loading mappings
@@ -104,7 +105,7 @@ export function useMappings(): UseMappingsReturn {
       setErrors(result.errors);
     } catch (e) {
       console.error('Failed to load mappings:', e);
-      setErrors([{ file: 'unknown', message: String(e) }]);
+      setErrors([{ file: 'unknown', message: errorMessage(e) }]);
     } finally {
       setLoading(false);
     }
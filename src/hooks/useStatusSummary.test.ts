@@ -0,0 +1,50 @@
+import { describe, it, expect, beforeEach, vi } from 'vitest';
+import { renderHook, act, waitFor } from '@testing-library/react';
+import { useStatusSummary } from './useStatusSummary';
+import { resetMocks, mockInvokeResponses, emitEvent } from '../test/setup';
+import type { StatusSummary } from '../types';
+
+const idle: StatusSummary = {
+  ptySessions: 0,
+  workflows: [],
+  watchers: { watching: 1, degraded: 0, paused: 0 },
+  setupJobs: 0,
+  recentError: null,
+};
+
+describe('useStatusSummary', () => {
+  beforeEach(() => {
+    resetMocks();
+    vi.clearAllMocks();
+  });
+
+  it('loads the current summary on mount', async () => {
+    mockInvokeResponses.set('get_status_summary', idle);
+
+    const { result } = renderHook(() => useStatusSummary());
+
+    await waitFor(() => {
+      expect(result.current).toEqual(idle);
+    });
+  });
+
+  it('updates from status-summary events', async () => {
+    mockInvokeResponses.set('get_status_summary', idle);
+
+    const { result } = renderHook(() => useStatusSummary());
+    await waitFor(() => {
+      expect(result.current).toEqual(idle);
+    });
+
+    const merging: StatusSummary = {
+      ...idle,
+      ptySessions: 2,
+      workflows: [{ kind: 'merge', worktreeId: 'wt-1', projectPath: '/repo', phase: 'merge' }],
+    };
+    act(() => {
+      emitEvent('status-summary', merging);
+    });
+
+    expect(result.current).toEqual(merging);
+  });
+});
@@ -0,0 +1,40 @@
+/**
+ * useStatusSummary Hook
+ *
+ * Tracks the backend's consolidated status-bar summary: fetched once on mount
+ * (so a reload doesn't wait for the next change) and then kept current from
+ * `status-summary` events.
+ */
+
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { getStatusSummary } from '../lib/tauri';
+import type { StatusSummary } from '../types';
+
+export function useStatusSummary(): StatusSummary | null {
+  const [summary, setSummary] = useState<StatusSummary | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    // Events may arrive before the initial fetch resolves; don't overwrite them
+    let received = false;
+
+    const unlisten = listen<StatusSummary>('status-summary', (event) => {
+      received = true;
+      setSummary(event.payload);
+    });
+
+    getStatusSummary()
+      .then((initial) => {
+        if (!cancelled && !received) setSummary(initial);
+      })
+      .catch((err) => console.error('[useStatusSummary] Failed to load status summary:', err));
+
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return summary;
+}
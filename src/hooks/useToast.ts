@@ -1,14 +1,14 @@
 import { useState, useCallback } from 'react';
-import { ToastData, ToastVariant } from '../components/Toast';
+import { ToastAction, ToastData, ToastVariant } from '../components/Toast';
 
 let toastIdCounter = 0;
 
 export function useToast() {
   const [toasts, setToasts] = useState<ToastData[]>([]);
 
-  const addToast = useCallback((variant: ToastVariant, message: string, duration?: number) => {
+  const addToast = useCallback((variant: ToastVariant, message: string, duration?: number, action?: ToastAction) => {
     const id = `toast-${++toastIdCounter}`;
-    setToasts((prev) => [...prev, { id, variant, message, duration }]);
+    setToasts((prev) => [...prev, { id, variant, message, duration, action }]);
     return id;
   }, []);
 
@@ -24,8 +24,8 @@ export function useToast() {
     return addToast('warning', message, duration);
   }, [addToast]);
 
-  const showInfo = useCallback((message: string, duration?: number) => {
-    return addToast('info', message, duration);
+  const showInfo = useCallback((message: string, duration?: number, action?: ToastAction) => {
+    return addToast('info', message, duration, action);
   }, [addToast]);
 
   const showSuccess = useCallback((message: string, duration?: number) => {
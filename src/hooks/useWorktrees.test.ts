@@ -197,6 +197,25 @@ describe('useWorktrees', () => {
       expect(createCall?.args).toEqual({ projectPath: project.path, name: 'custom-name' });
     });
 
+    it('passes an existing branch to check out', async () => {
+      const project = createTestProject({ id: 'proj-1', name: 'my-project' });
+      mockInvokeResponses.set('list_projects', [project]);
+      mockInvokeResponses.set('create_worktree', createTestWorktree({ id: 'wt-existing', branch: 'feature/login' }));
+
+      const { result } = renderHook(() => useWorktrees());
+
+      await waitFor(() => {
+        expect(result.current.loading).toBe(false);
+      });
+
+      await act(async () => {
+        await result.current.createWorktree(project.path, undefined, false, 'feature/login');
+      });
+
+      const createCall = invokeHistory.find((h) => h.command === 'create_worktree');
+      expect(createCall?.args).toMatchObject({ projectPath: project.path, existingBranch: 'feature/login' });
+    });
+
     it('throws when worktree creation fails', async () => {
       const project = createTestProject({ id: 'proj-1' });
       mockInvokeResponses.set('list_projects', [project]);
@@ -110,12 +110,14 @@ export function useWorktrees() {
   }, []);
 
   const createWorktree = useCallback(
-    async (projectPath: string, name?: string) => {
+    async (projectPath: string, name?: string, adoptExistingBranch = false, existingBranch?: string) => {
       console.log('[useWorktrees.createWorktree] Called with path:', projectPath);
       try {
         const worktree = await invoke<Worktree>('create_worktree', {
           projectPath,
           name,
+          adoptExistingBranch,
+          existingBranch,
         });
         console.log('[useWorktrees.createWorktree] Success:', worktree.name);
         // Reload projects to get updated worktree list
@@ -130,9 +132,9 @@ export function useWorktrees() {
   );
 
   const deleteWorktree = useCallback(
-    async (worktreeId: string) => {
+    async (worktreeId: string, confirmed = false) => {
       try {
-        await invoke('delete_worktree', { worktreeId });
+        await invoke('delete_worktree', { worktreeId, confirmed });
         await loadProjects({ syncFromGit: false });
       } catch (err) {
         console.error('Failed to delete worktree:', err);
@@ -0,0 +1,22 @@
+// Errors returned by backend commands: { code, message, details }
+export interface CommandError {
+  code: string;
+  message: string;
+  details?: Record<string, unknown> | null;
+}
+
+export function isCommandError(err: unknown): err is CommandError {
+  return (
+    typeof err === 'object' &&
+    err !== null &&
+    typeof (err as CommandError).code === 'string' &&
+    typeof (err as CommandError).message === 'string'
+  );
+}
+
+// Human-readable message for anything thrown by invoke() or other code
+export function errorMessage(err: unknown): string {
+  if (isCommandError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+}
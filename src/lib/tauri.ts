@@ -3,7 +3,46 @@ import { open } from '@tauri-apps/plugin-dialog';
 import {
   Project,
   Worktree,
-  FileChange,
+  EntityMatch,
+  GlobalWorktree,
+  WorktreeDirectoryPreview,
+  QuickCommitResult,
+  CommitSummary,
+  CommitInfo,
+  WorkingSnapshot,
+  CopyStoreGc,
+  LanguageStats,
+  DriftReport,
+  ExternalWorktreeScan,
+  StatusV2,
+  GitConfigScope,
+  RebaseAction,
+  RestoredFile,
+  FileDiff,
+  FileDiffMode,
+  FileSides,
+  IgnoredEntry,
+  BranchEntry,
+  GoneBranch,
+  DiffStats,
+  PerfStats,
+  IgnoreCheck,
+  DiscardResult,
+  ProjectRelocation,
+  ProjectScan,
+  BulkAddResult,
+  ResourceUsage,
+  TaskGroup,
+  BranchColor,
+  TemplatePreview,
+  TemplateProblem,
+  TemplateScope,
+  TemplateVariable,
+  ConfigLocation,
+  ConfigScope,
+  ConfigMigrationReport,
+  ConfigWarning,
+  ChangedFiles,
   MergeFeasibility,
   MergeWorkflowOptions,
   CleanupOptions,
@@ -12,6 +51,10 @@ import {
   BranchInfo,
   DiffContent,
   ChangedFilesViewMode,
+  StatusSummary,
+  EventCatalog,
+  AppsCheck,
+  ActionCommandCheck,
 } from '../types';
 
 // Project commands
@@ -19,6 +62,29 @@ export async function addProject(path: string): Promise<Project> {
   return invoke<Project>('add_project', { path });
 }
 
+export async function scanForProjects(rootPath: string, maxDepth?: number): Promise<ProjectScan> {
+  return invoke<ProjectScan>('scan_for_projects', { rootPath, maxDepth });
+}
+
+export async function addProjectsBulk(paths: string[]): Promise<BulkAddResult[]> {
+  return invoke<BulkAddResult[]>('add_projects_bulk', { paths });
+}
+
+// Switcher tasks grouped and sorted per config; hidden tasks are left out
+export async function getTaskCatalog(projectPath?: string, entityId?: string): Promise<TaskGroup[]> {
+  return invoke<TaskGroup[]>('get_task_catalog', { projectPath, entityId });
+}
+
+// CPU/RSS of live terminals' process trees; scope is 'all', a project id, or a worktree id
+export async function getResourceUsage(scope: string = 'all'): Promise<ResourceUsage> {
+  return invoke<ResourceUsage>('get_resource_usage', { scope });
+}
+
+// Point a project at its repository's new location after the directory moved
+export async function updateProjectPath(projectId: string, newPath: string): Promise<ProjectRelocation> {
+  return invoke<ProjectRelocation>('update_project_path', { projectId, newPath });
+}
+
 export async function listProjects(options: { syncFromGit?: boolean } = {}): Promise<Project[]> {
   const { syncFromGit = false } = options;
   return invoke<Project[]>('list_projects', { syncFromGit });
@@ -33,11 +99,33 @@ export async function touchProject(projectId: string): Promise<void> {
 }
 
 // Worktree commands
+// With `existingBranch`, the worktree checks out that branch (local, or a
+// remote one a teammate pushed) instead of creating a new one
 export async function createWorktree(
   projectPath: string,
-  name?: string
+  name?: string,
+  adoptExistingBranch = false,
+  existingBranch?: string
 ): Promise<Worktree> {
-  return invoke<Worktree>('create_worktree', { projectPath, name });
+  return invoke<Worktree>('create_worktree', { projectPath, name, adoptExistingBranch, existingBranch });
+}
+
+// Every worktree across all projects, with change summaries, for global switching
+export async function listAllWorktrees(): Promise<GlobalWorktree[]> {
+  return invoke<GlobalWorktree[]>('list_all_worktrees');
+}
+
+// Fuzzy-ranked projects and worktrees for the switcher palette
+export async function searchEntities(query: string, limit?: number): Promise<EntityMatch[]> {
+  return invoke<EntityMatch[]>('search_entities', { query, limit });
+}
+
+export async function previewWorktreeDirectory(
+  projectPath: string,
+  template: string | null,
+  sampleBranch?: string
+): Promise<WorktreeDirectoryPreview> {
+  return invoke<WorktreeDirectoryPreview>('preview_worktree_directory', { projectPath, template, sampleBranch });
 }
 
 export async function listWorktrees(
@@ -48,8 +136,14 @@ export async function listWorktrees(
   return invoke<Worktree[]>('list_worktrees', { projectPath, syncFromGit });
 }
 
-export async function deleteWorktree(worktreeId: string): Promise<void> {
-  return invoke('delete_worktree', { worktreeId });
+// Rejects with WORKFLOW_IN_PROGRESS while another deletion of the worktree is running
+export async function deleteWorktree(worktreeId: string, confirmed: boolean): Promise<void> {
+  return invoke('delete_worktree', { worktreeId, confirmed });
+}
+
+// Remove deduplicated copies (`worktree.copy.dedupe`) no worktree links to any more
+export async function gcCopyStore(): Promise<CopyStoreGc> {
+  return invoke<CopyStoreGc>('gc_copy_store');
 }
 
 export async function checkWorktreeDeleteStatus(
@@ -61,11 +155,12 @@ export async function checkWorktreeDeleteStatus(
 
 export async function executeDeleteWorktreeWorkflow(
   worktreeId: string,
-  options: DeleteWorktreeOptions
+  options: DeleteWorktreeOptions,
+  confirmed: boolean
 ): Promise<void> {
   // Fire and forget - the command runs in a background thread
   // and emits 'delete-worktree-completed' event when done
-  await invoke<void>('execute_delete_worktree_workflow', { worktreeId, options });
+  await invoke<void>('execute_delete_worktree_workflow', { worktreeId, options, confirmed });
 }
 
 // Reorder commands
@@ -85,6 +180,58 @@ export async function gitStageAll(repoPath: string): Promise<void> {
   return invoke('git_stage_all', { repoPath });
 }
 
+export async function restoreFileFromCommit(
+  worktreeId: string,
+  filePath: string,
+  sha: string,
+  mode: 'working' | 'staged'
+): Promise<RestoredFile> {
+  return invoke<RestoredFile>('restore_file_from_commit', { worktreeId, filePath, sha, mode });
+}
+
+// Unified diff of what is staged for one file; '' when nothing is staged
+export async function getStagedFileDiff(worktreePath: string, filePath: string): Promise<string> {
+  return invoke<string>('get_staged_file_diff', { worktreePath, filePath });
+}
+
+export async function getGitConfig(
+  worktreePath: string,
+  key: string,
+  scope?: GitConfigScope
+): Promise<string | null> {
+  return invoke<string | null>('get_git_config', { worktreePath, key, scope });
+}
+
+// Stage one hunk of `git diff`; rejects with code HUNK_OUTDATED if the file changed since
+export async function stageHunk(worktreePath: string, filePath: string, hunkHeader: string, hunkLines: string[]): Promise<void> {
+  return invoke('stage_hunk', { worktreePath, filePath, hunkHeader, hunkLines });
+}
+
+// Unstage one hunk of `git diff --cached`
+export async function unstageHunk(worktreePath: string, filePath: string, hunkHeader: string, hunkLines: string[]): Promise<void> {
+  return invoke('unstage_hunk', { worktreePath, filePath, hunkHeader, hunkLines });
+}
+
+// Whole files; a deleted file stages its removal. Emits files-changed afterwards.
+export async function stageFiles(worktreeId: string, paths: string[]): Promise<void> {
+  return invoke('stage_files', { worktreeId, paths });
+}
+
+export async function unstageFiles(worktreeId: string, paths: string[]): Promise<void> {
+  return invoke('unstage_files', { worktreeId, paths });
+}
+
+// Staged changes are kept; untracked files are deleted only with includeUntracked.
+// Rejects with CONFIRMATION_REQUIRED unless confirmed, per confirmations.forceReset/cleanUntracked
+export async function discardChanges(
+  worktreeId: string,
+  paths: string[],
+  includeUntracked: boolean,
+  confirmed: boolean
+): Promise<DiscardResult[]> {
+  return invoke<DiscardResult[]>('discard_changes', { worktreeId, paths, includeUntracked, confirmed });
+}
+
 export async function gitDiffCached(repoPath: string): Promise<string> {
   return invoke<string>('git_diff_cached', { repoPath });
 }
@@ -97,6 +244,11 @@ export async function gitCommit(repoPath: string, message: string): Promise<void
   return invoke('git_commit', { repoPath, message });
 }
 
+// Without a message the last commit keeps its own
+export async function amendCommit(repoPath: string, message?: string): Promise<void> {
+  return invoke('amend_commit', { repoPath, message });
+}
+
 export async function gitCurrentBranch(repoPath: string): Promise<string> {
   return invoke<string>('git_current_branch', { repoPath });
 }
@@ -113,10 +265,94 @@ export async function renameWorktreeBranch(worktreeId: string, newName: string):
   return invoke('rename_worktree', { worktreeId, newName });
 }
 
+export async function gitRecentCommits(
+  repoPath: string,
+  options: { limit?: number; includeEmail?: boolean } = {}
+): Promise<CommitSummary[]> {
+  return invoke<CommitSummary[]>('git_recent_commits', { repoPath, ...options });
+}
+
+// Newest first; pass `skip` to load older pages
+export async function getCommitLog(
+  worktreePath: string,
+  options: { limit?: number; skip?: number } = {}
+): Promise<CommitInfo[]> {
+  return invoke<CommitInfo[]>('get_commit_log', { worktreePath, ...options });
+}
+
+// Only commits the configured base branch lacks; empty on the base branch itself
+export async function getBranchCommitLog(
+  worktreePath: string,
+  projectPath?: string,
+  limit?: number
+): Promise<CommitInfo[]> {
+  return invoke<CommitInfo[]>('get_branch_commit_log', { worktreePath, projectPath, limit });
+}
+
+// Cached per HEAD commit, so repeated calls are cheap until the next commit
+export async function getLanguageStats(projectPath: string, includeBytes = false): Promise<LanguageStats> {
+  return invoke<LanguageStats>('get_language_stats', { projectPath, includeBytes });
+}
+
+// Same name, same color, across sessions
+export async function branchColor(name: string): Promise<BranchColor> {
+  return invoke<BranchColor>('branch_color', { name });
+}
+
+// Variables a template can use where it is rendered, for config autocomplete
+export async function getTemplateVariables(scope: TemplateScope): Promise<TemplateVariable[]> {
+  return invoke<TemplateVariable[]>('get_template_variables', { scope });
+}
+
+export async function renderTemplatePreview(
+  template: string,
+  context: Record<string, unknown>
+): Promise<TemplatePreview> {
+  return invoke<TemplatePreview>('render_template_preview', { template, context });
+}
+
+// Stricter than runtime expansion: unknown variables are reported too
+export async function validateTemplates(projectPath?: string): Promise<TemplateProblem[]> {
+  return invoke<TemplateProblem[]>('validate_templates', { projectPath });
+}
+
+// Worktrees by commits behind the base branch; may emit `drift-warning` events
+export async function getDriftReport(projectId: string): Promise<DriftReport> {
+  return invoke<DriftReport>('get_drift_report', { projectId });
+}
+
+// Worktrees added with `git worktree add` outside shellflow, and tracked ones whose directory is gone
+export async function scanExternalWorktrees(projectId: string): Promise<ExternalWorktreeScan> {
+  return invoke<ExternalWorktreeScan>('scan_external_worktrees', { projectId });
+}
+
+export async function adoptWorktree(projectId: string, worktreePath: string): Promise<Worktree> {
+  return invoke<Worktree>('adopt_worktree', { projectId, worktreePath });
+}
+
+// Returns right away; the outcome arrives as `fetch-completed`
+export async function fetchRemote(repoPath: string, remote?: string): Promise<void> {
+  return invoke('fetch_remote', { repoPath, remote });
+}
+
 export async function gitPushCurrentBranch(repoPath: string): Promise<void> {
   return invoke('git_push_current_branch', { repoPath });
 }
 
+// Returns right away; the text streams in as `commit-message-chunk`, then `commit-message-completed`
+export async function generateCommitMessage(repoPath: string): Promise<void> {
+  return invoke('generate_commit_message', { repoPath });
+}
+
+// Stage, commit and optionally push a worktree; errors carry the failed step in `details.step`
+export async function quickCommitPush(
+  worktreeId: string,
+  message: string | null,
+  push = false
+): Promise<QuickCommitResult> {
+  return invoke<QuickCommitResult>('quick_commit_push', { worktreeId, message, push });
+}
+
 export async function gitMergeToMain(worktreePath: string, repoPath: string): Promise<void> {
   return invoke('git_merge_to_main', { worktreePath, repoPath });
 }
@@ -130,6 +366,11 @@ export async function spawnMain(worktreeId: string): Promise<string> {
   return invoke<string>('spawn_main', { worktreeId });
 }
 
+// The expanded `main.command` of a worktree or project, or "<user shell>" when unset
+export async function previewMainCommand(entityId: string): Promise<string> {
+  return invoke<string>('preview_main_command', { entityId });
+}
+
 export async function spawnTerminal(worktreeId: string): Promise<string> {
   return invoke<string>('spawn_terminal', { worktreeId });
 }
@@ -143,6 +384,37 @@ export async function spawnAction(
   return invoke<string>('spawn_action', { worktreeId, prompt, cols, rows });
 }
 
+export async function spawnGitInteractive(
+  worktreeId: string,
+  args: string[],
+  cols?: number,
+  rows?: number
+): Promise<string> {
+  return invoke<string>('spawn_git_interactive', { worktreeId, args, cols, rows });
+}
+
+export async function startInteractiveRebase(
+  worktreePath: string,
+  base: string,
+  plan: RebaseAction[],
+  cols?: number,
+  rows?: number
+): Promise<string> {
+  return invoke<string>('start_interactive_rebase', { worktreePath, base, plan, cols, rows });
+}
+
+export async function saveWorktreeLayout(worktreeId: string, layout: unknown | null): Promise<void> {
+  return invoke('save_worktree_layout', { worktreeId, layout });
+}
+
+export async function resetWorktreeLayout(worktreeId: string): Promise<Worktree> {
+  return invoke<Worktree>('reset_worktree_layout', { worktreeId });
+}
+
+export async function getWorktreeLayout<T = unknown>(worktreeId: string): Promise<T | null> {
+  return invoke<T | null>('get_worktree_layout', { worktreeId });
+}
+
 export async function watchMergeState(worktreeId: string): Promise<void> {
   return invoke('watch_merge_state', { worktreeId });
 }
@@ -151,6 +423,15 @@ export async function stopMergeWatcher(worktreeId: string): Promise<void> {
   return invoke('stop_merge_watcher', { worktreeId });
 }
 
+// Emits commit-added with the new commit whenever HEAD advances
+export async function watchCommits(worktreeId: string): Promise<void> {
+  return invoke('watch_commits', { worktreeId });
+}
+
+export async function stopWatchCommits(worktreeId: string): Promise<void> {
+  return invoke('stop_watch_commits', { worktreeId });
+}
+
 export async function watchRebaseState(worktreeId: string): Promise<void> {
   return invoke('watch_rebase_state', { worktreeId });
 }
@@ -168,6 +449,11 @@ export async function spawnTask(
   return invoke<string>('spawn_task', { entityId, taskName, cols, rows });
 }
 
+/** Turn `autoStart` on or off in the config file that defines the task */
+export async function setTaskAutostart(projectPath: string, taskName: string, enabled: boolean): Promise<void> {
+  return invoke('set_task_autostart', { projectPath, taskName, enabled });
+}
+
 export interface NamedUrl {
   name: string;
   url: string;
@@ -205,8 +491,13 @@ export async function ptyForceKill(ptyId: string): Promise<void> {
 }
 
 // Git commands
-export async function getChangedFiles(worktreePath: string): Promise<FileChange[]> {
-  return invoke<FileChange[]>('get_changed_files', { worktreePath });
+export async function getChangedFiles(worktreePath: string, projectPath?: string): Promise<ChangedFiles> {
+  return invoke<ChangedFiles>('get_changed_files', { worktreePath, projectPath });
+}
+
+// Full porcelain v2 status; getChangedFiles covers the common case
+export async function getStatusV2(worktreePath: string): Promise<StatusV2> {
+  return invoke<StatusV2>('get_status_v2', { worktreePath });
 }
 
 export async function hasUncommittedChanges(projectPath: string): Promise<boolean> {
@@ -239,8 +530,71 @@ export async function getBranchInfo(
 export async function getBranchChangedFiles(
   worktreePath: string,
   projectPath?: string
-): Promise<FileChange[]> {
-  return invoke<FileChange[]>('get_branch_changed_files', { worktreePath, projectPath });
+): Promise<ChangedFiles> {
+  return invoke<ChangedFiles>('get_branch_changed_files', { worktreePath, projectPath });
+}
+
+// What the branch adds over its base branch, committed or not, including untracked files
+export async function getBranchChangedFilesById(worktreeId: string, projectPath?: string): Promise<ChangedFiles> {
+  return invoke<ChangedFiles>('get_branch_changed_files', { worktreeId, projectPath });
+}
+
+export async function listIgnoredFiles(worktreePath: string, withSizes?: boolean): Promise<IgnoredEntry[]> {
+  return invoke<IgnoredEntry[]>('list_ignored_files', { worktreePath, withSizes });
+}
+
+export async function checkIgnore(worktreePath: string, filePath: string): Promise<IgnoreCheck> {
+  return invoke<IgnoreCheck>('check_ignore', { worktreePath, filePath });
+}
+
+// Most recently committed first; remote-tracking branches only with includeRemote
+export async function listBranches(repoPath: string, includeRemote?: boolean): Promise<BranchEntry[]> {
+  return invoke<BranchEntry[]>('list_branches', { repoPath, includeRemote });
+}
+
+export async function getUpstream(worktreePath: string): Promise<string | null> {
+  return invoke<string | null>('get_upstream', { worktreePath });
+}
+
+// The remote must exist and remoteBranch must have been fetched
+export async function setUpstream(worktreePath: string, remote: string, remoteBranch: string): Promise<void> {
+  return invoke('set_upstream', { worktreePath, remote, remoteBranch });
+}
+
+export async function getDiffSummary(worktreeId: string, projectPath?: string): Promise<DiffStats> {
+  return invoke<DiffStats>('get_diff_summary', { worktreeId, projectPath });
+}
+
+// Empty unless perf.enabled is set in the user config
+export async function getPerfStats(): Promise<PerfStats> {
+  return invoke<PerfStats>('get_perf_stats');
+}
+
+// Returns the pruned remote-tracking refs; remote defaults to origin
+export async function pruneRemoteRefs(projectPath: string, remote?: string): Promise<string[]> {
+  return invoke<string[]>('prune_remote_refs', { projectPath, remote });
+}
+
+// Upstreams only go missing once pruned, so call after pruneRemoteRefs or a fetch
+export async function listGoneBranches(projectPath: string): Promise<GoneBranch[]> {
+  return invoke<GoneBranch[]>('list_gone_branches', { projectPath });
+}
+
+// Leaves the working tree and stash list untouched; untracked files aren't captured
+export async function createWorkingSnapshot(worktreePath: string, label?: string): Promise<WorkingSnapshot> {
+  return invoke<WorkingSnapshot>('create_working_snapshot', { worktreePath, label });
+}
+
+export async function listWorkingSnapshots(worktreePath: string): Promise<WorkingSnapshot[]> {
+  return invoke<WorkingSnapshot[]>('list_working_snapshots', { worktreePath });
+}
+
+export async function deleteWorkingSnapshot(worktreePath: string, snapshotHash: string): Promise<void> {
+  return invoke('delete_working_snapshot', { worktreePath, snapshotHash });
+}
+
+export async function diffAgainstSnapshot(worktreePath: string, snapshotHash: string): Promise<string> {
+  return invoke<string>('diff_against_snapshot', { worktreePath, snapshotHash });
 }
 
 export async function getFileDiffContent(
@@ -252,6 +606,14 @@ export async function getFileDiffContent(
   return invoke<DiffContent>('get_file_diff_content', { worktreePath, filePath, mode, projectPath });
 }
 
+export async function getFileDiff(worktreeId: string, filePath: string, mode: FileDiffMode): Promise<FileDiff> {
+  return invoke<FileDiff>('get_file_diff', { worktreeId, filePath, mode });
+}
+
+export async function getFileDiffSides(worktreePath: string, filePath: string, baseRef: string): Promise<FileSides> {
+  return invoke<FileSides>('get_file_diff_sides', { worktreePath, filePath, baseRef });
+}
+
 // Dialog helpers
 export async function selectFolder(): Promise<string | null> {
   const selected = await open({
@@ -267,6 +629,37 @@ export async function updateConfig(patch: Record<string, unknown>): Promise<void
   await invoke('update_config', { patch });
 }
 
+// Config layers, in load order, for choosing which file to edit
+export async function locateConfigs(projectPath: string | null): Promise<ConfigLocation[]> {
+  return invoke<ConfigLocation[]>('locate_configs', { projectPath });
+}
+
+// Deprecated keys in the config layers, with their replacements
+export async function getConfigWarnings(projectPath: string | null): Promise<ConfigWarning[]> {
+  return invoke<ConfigWarning[]>('get_config_warnings', { projectPath });
+}
+
+// Upgrade a config file's deprecated forms in place (comments are kept), backing up the original
+export async function migrateConfig(scope: ConfigScope, projectPath: string | null): Promise<ConfigMigrationReport> {
+  return invoke<ConfigMigrationReport>('migrate_config', { scope, projectPath });
+}
+
+// Path of a config layer's file, creating it with a schema reference if missing
+export async function openConfig(scope: ConfigScope, projectPath: string | null): Promise<string> {
+  const fileType = scope === 'global' ? 'settings' : scope;
+  return invoke<string>('get_config_file_path', { fileType, projectPath });
+}
+
+// Whether the configured editor, terminal and file manager resolve to an installed program
+export async function checkApps(projectPath?: string): Promise<AppsCheck> {
+  return invoke<AppsCheck>('check_apps', { projectPath });
+}
+
+// Whether the AI CLI in actions.command resolves to an installed program
+export async function checkActionCommand(projectPath?: string): Promise<ActionCommandCheck> {
+  return invoke<ActionCommandCheck>('check_action_command', { projectPath });
+}
+
 // Action commands
 export interface MergeOptions {
   deleteWorktree: boolean;
@@ -296,30 +689,45 @@ export async function expandActionPrompt(
 // Merge workflow commands
 export async function checkMergeFeasibility(
   worktreePath: string,
-  projectPath?: string
+  projectPath?: string,
+  targetBranch?: string
 ): Promise<MergeFeasibility> {
-  return invoke<MergeFeasibility>('check_merge_feasibility', { worktreePath, projectPath });
+  return invoke<MergeFeasibility>('check_merge_feasibility', { worktreePath, projectPath, targetBranch });
 }
 
 export async function executeMergeWorkflow(
   worktreeId: string,
-  options: MergeWorkflowOptions
+  options: MergeWorkflowOptions,
+  confirmed: boolean
 ): Promise<void> {
   // Fire and forget - the command runs in a background thread
   // and emits 'merge-completed' event when done
   await invoke<void>('execute_merge_workflow', {
     worktreeId,
     options,
+    confirmed,
   });
 }
 
+export async function mergeIntoMultiple(
+  worktreeId: string,
+  targets: string[],
+  options: MergeWorkflowOptions,
+  confirmed: boolean
+): Promise<void> {
+  // Runs in a background thread; emits 'merge-multiple-completed' with per-target results
+  await invoke<void>('merge_into_multiple', { worktreeId, targets, options, confirmed });
+}
+
 export async function cleanupWorktree(
   worktreeId: string,
-  options: CleanupOptions
+  options: CleanupOptions,
+  confirmed: boolean
 ): Promise<void> {
   return invoke<void>('cleanup_worktree', {
     worktreeId,
     options,
+    confirmed,
   });
 }
 
@@ -333,3 +741,28 @@ export async function shutdown(): Promise<boolean> {
 export async function updateActionAvailability(availability: Record<string, boolean>): Promise<void> {
   return invoke<void>('update_action_availability', { availability });
 }
+
+// Tell the backend which project is active so menu accelerators use its mappings
+export async function setActiveProject(projectId: string | null): Promise<void> {
+  return invoke<void>('set_active_project', { projectId });
+}
+
+// Like setActiveProject, and also remembers the focused worktree for the project
+export async function setActiveEntity(projectId: string | null, worktreeId: string | null): Promise<void> {
+  return invoke<void>('set_active_entity', { projectId, worktreeId });
+}
+
+// Worktree to restore when switching back to a project (null if it was deleted)
+export async function getLastFocused(projectId: string): Promise<string | null> {
+  return invoke<string | null>('get_last_focused', { projectId });
+}
+
+// Status bar summary; later changes arrive as `status-summary` events
+export async function getStatusSummary(): Promise<StatusSummary> {
+  return invoke<StatusSummary>('get_status_summary');
+}
+
+// Names and payload fields of every backend event, for checking compatibility
+export async function getEventCatalog(): Promise<EventCatalog> {
+  return invoke<EventCatalog>('get_event_catalog');
+}
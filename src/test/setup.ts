@@ -36,6 +36,7 @@ export const defaultTestConfig = {
   apps: { terminal: 'Terminal', editor: 'VS Code' },
   commit: {
     ai: {
+      provider: 'openai',
       baseUrl: 'https://api.openai.com/v1',
       apiKey: '',
       model: 'gpt-4o-mini',
@@ -138,6 +139,9 @@ vi.mock('@tauri-apps/api/core', () => ({
         return Promise.resolve([]);
       case 'read_theme':
         return Promise.resolve('{}');
+      case 'get_changed_files':
+      case 'get_branch_changed_files':
+        return Promise.resolve({ files: [], hidden_count: 0 });
       default:
         return Promise.resolve(null);
     }
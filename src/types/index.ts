@@ -6,6 +6,10 @@ export interface Project {
   order?: number;
   isActive: boolean;
   lastAccessedAt?: string;
+  /** Worktree focused when the project was last left */
+  lastFocusedWorktreeId?: string | null;
+  /** Identifies the repository if its directory moves */
+  rootCommit?: string | null;
 }
 
 // Scratch terminal - a general-purpose terminal not tied to any project/worktree
@@ -82,6 +86,147 @@ export interface Worktree {
   branch: string;
   createdAt: string;
   order?: number;
+  /** Saved tab/split layout; shape owned by the frontend */
+  layout?: unknown;
+}
+
+/** A project or worktree ranked by `search_entities` */
+export interface EntityMatch {
+  kind: 'project' | 'worktree';
+  projectId: string;
+  worktreeId: string | null;
+  projectName: string;
+  name: string;
+  branch: string | null;
+  projectActive: boolean;
+  matchedField: 'name' | 'branch';
+  score: number;
+  /** Character indices into the matched field, for highlighting */
+  positions: number[];
+}
+
+export interface ChangeSummary {
+  files: number;
+  insertions: number;
+  deletions: number;
+}
+
+/** A worktree from any project, as returned by `list_all_worktrees` */
+export interface GlobalWorktree extends Worktree {
+  projectId: string;
+  projectName: string;
+  projectPath: string;
+  projectActive: boolean;
+  /** Null when the worktree's status couldn't be read */
+  changes: ChangeSummary | null;
+}
+
+/** Commit author as shown in the UI; the email is left out */
+export interface AuthorIdentity {
+  authorId: string;
+  displayName: string;
+  initials: string;
+  /** Index into the avatar palette */
+  colorIndex: number;
+}
+
+export interface CommitSummary {
+  sha: string;
+  summary: string;
+  /** Unix timestamp in seconds */
+  time: number;
+  author: AuthorIdentity;
+  /** Present only when requested with `includeEmail` */
+  authorEmail?: string;
+}
+
+export interface CommitInfo {
+  hash: string;
+  shortHash: string;
+  author: string;
+  email: string;
+  /** Unix timestamp in seconds */
+  timestamp: number;
+  subject: string;
+  /** Message after the subject line */
+  body: string | null;
+}
+
+/** A non-destructive snapshot of a worktree's tracked changes */
+export interface WorkingSnapshot {
+  hash: string;
+  worktreePath: string;
+  label: string | null;
+  createdAt: string;
+}
+
+export interface LanguageStat {
+  language: string;
+  files: number;
+  bytes: number | null;
+}
+
+/** Tracked-file breakdown from `get_language_stats` */
+export interface LanguageStats {
+  head: string | null;
+  totalFiles: number;
+  totalBytes: number | null;
+  languages: LanguageStat[];
+}
+
+export type ConfigScope = 'global' | 'repo' | 'local';
+
+/** A config layer's file, from `locate_configs` */
+export interface ConfigLocation {
+  scope: ConfigScope;
+  path: string;
+  exists: boolean;
+  /** The file an edit should go to; created by the edit if it doesn't exist */
+  editTarget: boolean;
+}
+
+export interface ConfigMigration {
+  /** Dotted key path */
+  key: string;
+  description: string;
+}
+
+/** A deprecated config key found by `get_config_warnings` */
+export interface ConfigWarning {
+  source: 'global' | 'repo' | 'local';
+  file: string;
+  /** Dotted key path */
+  key: string;
+  /** Key path or value to use instead */
+  replacement: string;
+  message: string;
+}
+
+/** Result of `migrate_config` */
+export interface ConfigMigrationReport {
+  path: string;
+  /** Copy of the original file; null if nothing needed migrating */
+  backupPath: string | null;
+  fromVersion: number | null;
+  toVersion: number;
+  migrations: ConfigMigration[];
+}
+
+/** Result of `quick_commit_push` */
+export interface QuickCommitResult {
+  sha: string;
+  message: string;
+  files: number;
+  aiUsed: boolean;
+  /** Why AI generation fell back to the default message, if it did */
+  aiError: string | null;
+  push: 'skipped' | 'pushed';
+}
+
+export interface WorktreeDirectoryPreview {
+  directory: string;
+  worktreePath: string;
+  warnings: string[];
 }
 
 export interface FileChange {
@@ -89,8 +234,162 @@ export interface FileChange {
   status: 'added' | 'modified' | 'deleted' | 'renamed' | 'untracked';
   insertions?: number;
   deletions?: number;
+  /** Matches `changes.hidePatterns`; shown collapsed, never dropped */
+  hidden?: boolean;
+}
+
+/** Result of `get_changed_files` and `get_branch_changed_files` */
+export interface ChangedFiles {
+  files: FileChange[];
+  /** How many of `files` match changes.hidePatterns, as in `files-changed` */
+  hidden_count: number;
+}
+
+/** Branch headers of porcelain v2 status */
+export interface StatusBranch {
+  /** null before the first commit */
+  oid: string | null;
+  /** null when detached */
+  head: string | null;
+  upstream: string | null;
+  ahead: number | null;
+  behind: number | null;
+}
+
+export interface StatusEntry {
+  kind: 'ordinary' | 'renamed' | 'copied' | 'unmerged' | 'untracked' | 'ignored';
+  /** Two-letter git XY code, `.` meaning unchanged */
+  xy: string;
+  path: string;
+  origPath: string | null;
+  score: number | null;
+  submodule: { commitChanged: boolean; trackedChanges: boolean; untrackedChanges: boolean } | null;
+  modes: string[];
+  oids: string[];
+}
+
+/** Parsed `git status --porcelain=v2 --branch --show-stash` */
+export interface StatusV2 {
+  branch: StatusBranch;
+  stashCount: number;
+  entries: StatusEntry[];
+}
+
+/** One step of a scripted interactive rebase; steps run in array order */
+export interface RebaseAction {
+  commit: string;
+  action: 'pick' | 'reword' | 'squash' | 'drop';
+}
+
+/** Result of `restore_file_from_commit` */
+export interface RestoredFile {
+  path: string;
+  /** Path read at that commit; differs from `path` when the file was renamed since */
+  sourcePath: string;
+  sha: string;
 }
 
+/** A file at a base ref and in the working tree, for the diff viewer */
+export interface FileSides {
+  /** Empty when the file was added since the base ref */
+  oldContent: string;
+  /** Empty when the file was deleted */
+  newContent: string;
+  language: string;
+  /** Both contents are left empty for binary files */
+  binary: boolean;
+}
+
+/** The gitignore rule deciding whether a path is ignored */
+export type IgnoreCheck =
+  | { kind: 'ignored'; source: string; line: number; pattern: string }
+  /** The deciding rule is a `!` negation */
+  | { kind: 'unignored'; source: string; line: number; pattern: string }
+  | { kind: 'notIgnored' };
+
+/** Outcome of discarding one file's changes */
+export interface DiscardResult {
+  path: string;
+  discarded: boolean;
+  error: string | null;
+}
+
+/** A branch for the branch picker */
+export interface BranchEntry {
+  /** e.g. `feature/x`, or `origin/feature/x` for a remote-tracking branch */
+  name: string;
+  isRemote: boolean;
+  isCurrent: boolean;
+  /** Checked out in a worktree (or the main checkout), so it can't be used for a new one */
+  isCheckedOutInWorktree: boolean;
+  worktreePath: string | null;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  /** Unix timestamp in seconds */
+  lastCommitTime: number;
+}
+
+/** A local branch whose upstream was deleted on the remote */
+export interface GoneBranch {
+  name: string;
+  /** The upstream it still names, e.g. `origin/feature` */
+  upstream: string;
+  /** Set when checked out in a worktree, which keeps it from being deleted */
+  worktreePath: string | null;
+}
+
+/** Totals of the files the changes panel lists against the base branch; hidden files aren't counted */
+export interface DiffStats {
+  filesChanged: number;
+  totalInsertions: number;
+  totalDeletions: number;
+}
+
+/** Durations of one command over the runs recorded while `perf.enabled` is on */
+export interface CommandStats {
+  command: string;
+  calls: number;
+  p50Ms: number;
+  p95Ms: number;
+  maxMs: number;
+}
+
+/** Result of `get_perf_stats` */
+export interface PerfStats {
+  enabled: boolean;
+  /** Runs currently held; the oldest are dropped past a fixed limit */
+  samples: number;
+  /** Slowest p95 first */
+  commands: CommandStats[];
+}
+
+/** A gitignored file or directory */
+export interface IgnoredEntry {
+  /** Relative to the worktree; directories end with a slash */
+  path: string;
+  isDir: boolean;
+  /** Bytes on disk, only when requested */
+  size: number | null;
+}
+
+export type FileDiffMode = 'working-vs-head' | 'working-vs-base' | 'staged';
+
+/** Unified diff of one file */
+export interface FileDiff {
+  path: string;
+  /** Old-side path when the file was renamed */
+  oldPath: string | null;
+  /** Empty for binary files */
+  diff: string;
+  binary: boolean;
+  /** Not tracked yet; diffed against an empty file */
+  untracked: boolean;
+}
+
+/** Git config file a setting is read from or written to */
+export type GitConfigScope = 'worktree' | 'local' | 'global';
+
 export type ChangedFilesViewMode = 'uncommitted' | 'branch';
 
 export interface BranchInfo {
@@ -111,15 +410,18 @@ export interface DiffContent {
 export interface PtyOutput {
   pty_id: string;
   data: string;
+  replay?: boolean;
 }
 
 export interface FilesChanged {
   worktree_path: string;
   files: FileChange[];
+  /** How many of `files` match changes.hidePatterns */
+  hidden_count: number;
 }
 
 // Merge workflow types
-export type MergeStrategy = 'merge' | 'rebase';
+export type MergeStrategy = 'merge' | 'rebase' | 'squash';
 
 export interface MergeFeasibility {
   canMerge: boolean;
@@ -131,13 +433,32 @@ export interface MergeFeasibility {
   currentBranch: string;
   targetBranch: string;
   error: string | null;
+  /** Every reason the merge can't proceed; empty exactly when canMerge */
+  blockers: MergeBlocker[];
+  /** Problems that only matter when pushing after the merge (behindRemote) */
+  warnings: MergeBlocker[];
 }
 
+export type MergeBlocker =
+  | { kind: 'detachedHead' }
+  | { kind: 'onTargetBranch'; branch: string }
+  | { kind: 'targetBranchMissing'; branch: string }
+  | { kind: 'uncommittedChanges'; fileCount: number }
+  | { kind: 'nothingToMerge'; targetBranch: string }
+  | { kind: 'behindRemote'; branch: string; upstream: string; count: number }
+  | { kind: 'operationInProgress'; operation: string }
+  | { kind: 'workflowInProgress'; workflow: 'merge' | 'cleanup' | 'delete' };
+
 export interface MergeWorkflowOptions {
   strategy: MergeStrategy;
   deleteWorktree: boolean;
   deleteLocalBranch: boolean;
   deleteRemoteBranch: boolean;
+  pushAfterMerge?: boolean;
+  /** Branch to land in instead of the default branch */
+  targetBranch?: string | null;
+  /** Only fast-forward; fails with NOT_FAST_FORWARD when the target has diverged */
+  fastForward?: boolean;
 }
 
 export interface MergeWorkflowResult {
@@ -152,6 +473,22 @@ export interface MergeCompleted {
   branchName: string;
   deletedWorktree: boolean;
   error: string | null;
+  pushFailed: boolean;
+  pushError: string | null;
+}
+
+/** One target of `merge_into_multiple` */
+export interface MergeTargetResult {
+  targetBranch: string;
+  status: 'merged' | 'failed' | 'skipped';
+  error: string | null;
+  pushError: string | null;
+}
+
+/** Payload of `merge-multiple-completed` */
+export interface MergeMultipleCompleted {
+  worktreeId: string;
+  results: MergeTargetResult[];
 }
 
 export interface MergeProgress {
@@ -199,6 +536,207 @@ export interface RunningTask {
   exitCode?: number;
 }
 
+/** Payload of `task-idle-shutdown`: a daemon task stopped for inactivity */
+export interface TaskIdleShutdown {
+  ptyId: string;
+  worktreeId: string;
+  taskName: string;
+  idleMinutes: number;
+}
+
+/** An `autoStart` task started because its worktree was opened */
+export interface TaskAutoStarted {
+  worktreeId: string;
+  taskName: string;
+  ptyId: string;
+}
+
+/** Stable color for a branch or worktree name, from `branch_color` */
+export interface BranchColor {
+  /** `#rrggbb` */
+  hex: string;
+  hue: number;
+  saturation: number;
+  lightness: number;
+}
+
+/** Where a config template is rendered */
+export type TemplateScope = 'worktreeDirectory' | 'task' | 'taskUrl' | 'action' | 'namePrefix';
+
+export interface TemplateVariable {
+  name: string;
+  description: string;
+}
+
+/** Where a previewed template failed; line and column are 1-based */
+export interface TemplateError {
+  message: string;
+  line: number | null;
+  column: number | null;
+}
+
+export interface TemplatePreview {
+  output: string | null;
+  error: TemplateError | null;
+}
+
+/** A config template that doesn't render, e.g. `tasks[Dev].env.PORT` */
+export interface TemplateProblem {
+  configPath: string;
+  template: string;
+  error: string;
+}
+
+/** A worktree's position relative to its base branch */
+export interface WorktreeDrift {
+  worktreeId: string;
+  name: string;
+  branch: string;
+  baseBranch: string;
+  commitsBehind: number;
+  commitsAhead: number;
+  /** Whole days since the last commit or focus */
+  daysSinceActivity: number;
+  exceedsThreshold: boolean;
+}
+
+/** Result of `get_drift_report`, most behind first */
+export interface DriftReport {
+  warnBehind: number;
+  staleDays: number;
+  worktrees: WorktreeDrift[];
+}
+
+/** Payload of the `drift-warning` event */
+export interface DriftWarning extends WorktreeDrift {
+  projectId: string;
+}
+
+/** A worktree git has registered that the project doesn't track */
+export interface UnmanagedWorktree {
+  path: string;
+  /** null for a detached HEAD */
+  branch: string | null;
+}
+
+/** A tracked worktree git no longer has registered, or whose directory is gone */
+export interface StaleWorktree {
+  id: string;
+  name: string;
+  path: string;
+}
+
+/** Result of `scan_external_worktrees` */
+export interface ExternalWorktreeScan {
+  unmanaged: UnmanagedWorktree[];
+  stale: StaleWorktree[];
+}
+
+/** A task in the switcher catalog */
+export interface TaskCatalogEntry {
+  name: string;
+  group: string | null;
+  kind: 'command' | 'daemon';
+  /** Running in the entity the catalog was requested for */
+  running: boolean;
+  /** Key sequence of a mapping naming this task */
+  shortcut: string | null;
+}
+
+/** Switcher tasks sharing a group; `name` is null for ungrouped tasks */
+export interface TaskGroup {
+  name: string | null;
+  tasks: TaskCatalogEntry[];
+}
+
+/** CPU (percent of one core) and memory of one terminal's process tree */
+export interface SessionUsage {
+  ptyId: string;
+  worktreeId: string;
+  pid: number;
+  cpuPercent: number;
+  rssBytes: number;
+  processCount: number;
+}
+
+export interface WorktreeUsage {
+  worktreeId: string;
+  cpuPercent: number;
+  rssBytes: number;
+  sessionCount: number;
+}
+
+/** Result of `get_resource_usage`, heaviest first */
+export interface ResourceUsage {
+  /** False where process sampling isn't available (platforms other than Linux, macOS and Windows) */
+  supported: boolean;
+  sessions: SessionUsage[];
+  worktrees: WorktreeUsage[];
+}
+
+/** A repository found by `scan_for_projects` */
+export interface ProjectCandidate {
+  name: string;
+  path: string;
+  defaultBranch: string | null;
+  /** Unix seconds of the HEAD commit */
+  lastCommitAt: number | null;
+}
+
+export interface ProjectScan {
+  candidates: ProjectCandidate[];
+  visitedDirs: number;
+  /** The scan hit its directory cap before finishing */
+  truncated: boolean;
+}
+
+export type BulkAddStatus = 'added' | 'notARepository' | 'duplicate' | 'permissionDenied';
+
+export interface BulkAddResult {
+  path: string;
+  status: BulkAddStatus;
+  project: Project | null;
+  message: string | null;
+}
+
+/** Result of `update_project_path` */
+export interface ProjectRelocation {
+  project: Project;
+  /** Worktrees that lived under the old repository path */
+  rewrittenWorktreeIds: string[];
+  /** Worktrees git no longer lists from the new location */
+  staleWorktreeIds: string[];
+}
+
+/** Payload of the `project-path-missing` event */
+export interface ProjectPathMissing {
+  projectId: string;
+  name: string;
+  path: string;
+}
+
+/** Result of `gc_copy_store` */
+export interface CopyStoreGc {
+  removedObjects: number;
+  freedBytes: number;
+}
+
+/** Payload of `system-resumed`, sent after the system wakes from sleep */
+export interface SystemResumed {
+  sleptSeconds: number;
+  /** Sessions whose process ended while asleep, already removed */
+  closedSessions: string[];
+  restartingWatchers: number;
+}
+
+/** A `{"shellflow": ...}` line from a task using the jsonlines protocol */
+export interface TaskStructuredEvent {
+  ptyId: string;
+  worktreeId: string;
+  taskName: string;
+  payload: { shellflow: unknown } & Record<string, unknown>;
+}
+
 // Re-export split types
 export type {
   SplitPaneConfig,
@@ -211,3 +749,139 @@ export {
   generatePaneId,
   createDefaultSplitState,
 } from '../lib/splitTypes';
+
+export interface WorkflowStatus {
+  kind: 'merge' | 'cleanup' | 'delete';
+  worktreeId: string;
+  projectPath: string;
+  phase: string | null;
+}
+
+export interface WatcherStatus {
+  watching: number;
+  degraded: number;
+  paused: number;
+}
+
+export interface RecentError {
+  source: string;
+  message: string;
+  atMs: number;
+}
+
+export interface EventField {
+  name: string;
+  /** TypeScript-style type; `?` marks a field that may be left out */
+  type: string;
+}
+
+export interface EventSpec {
+  name: string;
+  description: string;
+  payload: 'object' | 'string' | 'null';
+  fields: EventField[];
+  /** Set for shapes kept for compatibility that a later schema version will change */
+  deprecated: string | null;
+}
+
+/** Result of `get_event_catalog`; `schemaVersion` is also sent with `app-ready` */
+export interface EventCatalog {
+  schemaVersion: number;
+  events: EventSpec[];
+}
+
+/** Payload of `status-summary` and `get_status_summary` */
+export interface StatusSummary {
+  ptySessions: number;
+  workflows: WorkflowStatus[];
+  watchers: WatcherStatus;
+  setupJobs: number;
+  recentError: RecentError | null;
+}
+
+/** Where the command checked by `check_apps` came from */
+export type AppCommandSource = 'config' | 'environment' | 'platformDefault' | 'none';
+
+export interface AppCheck {
+  command: string | null;
+  source: AppCommandSource;
+  target: 'external' | 'drawer' | 'tab' | 'terminal';
+  resolvedPath: string | null;
+  found: boolean;
+}
+
+/** Whether the AI CLI in `actions.command` is installed */
+export interface ActionCommandCheck {
+  command: string;
+  resolvedPath: string | null;
+  found: boolean;
+  /** What to do about a missing command; null when found */
+  message: string | null;
+}
+
+export interface AppsCheck {
+  editor: AppCheck;
+  terminal: AppCheck;
+  fileManager: AppCheck;
+}
+
+/** Why a push or remote branch deletion failed */
+export type NetworkFailureKind =
+  | 'connectionReset'
+  | 'hostUnresolved'
+  | 'connectionRefused'
+  | 'timedOut'
+  | 'earlyEof'
+  | 'rateLimited'
+  | 'serverUnavailable'
+  | 'authenticationFailed'
+  | 'nonFastForward'
+  | 'refRejected'
+  | 'repositoryNotFound'
+  | 'other';
+
+/** Payload of `git-network-retry`, sent before waiting for the next attempt */
+export interface RetryNotice {
+  operation: string;
+  /** The attempt about to be made */
+  attempt: number;
+  maxAttempts: number;
+  delayMs: number;
+  reason: NetworkFailureKind;
+  message: string;
+}
+
+/** Payload of `fetch-progress` */
+export interface FetchProgress {
+  repoPath: string;
+  remote: string;
+  message: string;
+}
+
+/** Payload of `fetch-completed`; `error` also covers a missing remote or a timeout */
+export interface FetchCompleted {
+  repoPath: string;
+  remote: string;
+  success: boolean;
+  error: string | null;
+}
+
+/** Payload of `commit-added`, sent while a worktree is watched with `watchCommits` */
+export interface CommitAdded {
+  worktreeId: string;
+  worktreePath: string;
+  commit: CommitInfo;
+}
+
+/** Payload of `commit-message-chunk`: more text of a message from `generateCommitMessage` */
+export interface CommitMessageChunk {
+  repoPath: string;
+  delta: string;
+}
+
+/** Payload of `commit-message-completed` */
+export interface CommitMessageCompleted {
+  repoPath: string;
+  message: string | null;
+  error: string | null;
+}